@@ -0,0 +1,1273 @@
+use agent_client_protocol as acp;
+use agent_settings::AgentSettings;
+use anyhow::Result;
+use collections::HashSet;
+use dap::{SteppingGranularity, client::SessionId};
+use futures::{FutureExt as _, StreamExt as _, channel::mpsc};
+use gpui::{App, AsyncApp, Entity, SharedString, Task, WindowHandle};
+use language::Point;
+use parking_lot::Mutex;
+use project::{
+    Project,
+    debugger::{
+        breakpoint_store::{
+            Breakpoint, BreakpointEditAction, BreakpointStore, BreakpointWithPosition,
+        },
+        session::{Session, SessionEvent, ThreadId},
+    },
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use settings::Settings;
+use std::{fmt::Write, pin::pin, sync::Arc, time::Duration};
+use util::markdown::MarkdownInlineCode;
+use workspace::{MultiWorkspace, Workspace};
+
+use crate::{
+    AgentTool, ToolCallEventStream, ToolInput, ToolPermissionDecision,
+    decide_permission_from_settings,
+};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Drives the debugger, to investigate failing tests or unexpected behavior by stepping through the code instead of adding print statements.
+///
+/// Start a debug scenario defined in the project's debug configuration, set breakpoints where you want the program to stop, then read the stack, variables and expressions once it has stopped.
+///
+/// All operations other than `start` and the breakpoint ones act on the most recently started debug session.
+///
+/// <example>
+/// To stop at line 42 of `project/src/parser.rs` and start debugging the "Run tests" scenario:
+/// { "operation": "set_breakpoint", "path": "project/src/parser.rs", "line": 42 }
+/// { "operation": "start", "scenario": "Run tests" }
+/// </example>
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct DebuggerToolInput {
+    /// The operation to perform.
+    pub operation: DebuggerOperation,
+    /// The label of the debug scenario to start. Required by `start`.
+    pub scenario: Option<String>,
+    /// The file to set or clear a breakpoint in. Required by `set_breakpoint` and `clear_breakpoint`.
+    ///
+    /// This path should never be absolute, and the first component
+    /// of the path should always be a root directory in a project.
+    pub path: Option<String>,
+    /// The one-based line to set or clear a breakpoint at. Required by `set_breakpoint` and `clear_breakpoint`.
+    pub line: Option<u32>,
+    /// An expression that must be true for the breakpoint to stop the program. Only used by `set_breakpoint`.
+    pub condition: Option<String>,
+    /// The expression to evaluate. Required by `evaluate`.
+    pub expression: Option<String>,
+    /// The ID of the stack frame to read variables from or evaluate in, as listed by `stack_trace`. Defaults to the top frame.
+    pub frame_id: Option<u64>,
+    /// The reference of a variable to list the children of, as listed by `variables`. Only used by `variables`.
+    pub variables_reference: Option<u64>,
+    /// How long to wait for the program to stop after `start`, `continue` or a step, in milliseconds. Defaults to 30 seconds.
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DebuggerOperation {
+    /// Starts the debug scenario labeled `scenario`, and waits until the program stops or exits.
+    Start,
+    /// Sets a breakpoint at `line` of `path`, with an optional `condition`.
+    SetBreakpoint,
+    /// Clears the breakpoint at `line` of `path`.
+    ClearBreakpoint,
+    /// Resumes the stopped thread, and waits until the program stops again or exits.
+    Continue,
+    /// Steps over the current line, and waits until the program stops again or exits.
+    StepOver,
+    /// Steps into the function called on the current line, and waits until the program stops again or exits.
+    StepInto,
+    /// Steps out of the current function, and waits until the program stops again or exits.
+    StepOut,
+    /// Lists the stack frames of the stopped thread.
+    StackTrace,
+    /// Lists the variables in the scopes of a stack frame, or the children of `variables_reference`.
+    Variables,
+    /// Evaluates `expression` in a stack frame.
+    Evaluate,
+    /// Stops the debug session.
+    Stop,
+}
+
+impl DebuggerOperation {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::SetBreakpoint => "set_breakpoint",
+            Self::ClearBreakpoint => "clear_breakpoint",
+            Self::Continue => "continue",
+            Self::StepOver => "step_over",
+            Self::StepInto => "step_into",
+            Self::StepOut => "step_out",
+            Self::StackTrace => "stack_trace",
+            Self::Variables => "variables",
+            Self::Evaluate => "evaluate",
+            Self::Stop => "stop",
+        }
+    }
+}
+
+impl DebuggerToolInput {
+    /// The input that tool permission rules are matched against: the
+    /// operation, followed by what it applies to.
+    fn permission_input(&self) -> String {
+        let argument = match self.operation {
+            DebuggerOperation::Start => self.scenario.clone(),
+            DebuggerOperation::SetBreakpoint | DebuggerOperation::ClearBreakpoint => {
+                self.breakpoint_location()
+            }
+            DebuggerOperation::Evaluate => self.expression.clone(),
+            _ => None,
+        };
+        match argument {
+            Some(argument) => format!("{} {argument}", self.operation.name()),
+            None => self.operation.name().to_string(),
+        }
+    }
+
+    fn breakpoint_location(&self) -> Option<String> {
+        Some(format!("{}:{}", self.path.as_ref()?, self.line?))
+    }
+}
+
+pub struct DebuggerTool {
+    project: Entity<Project>,
+    /// The thread that the tool last resumed or reported as stopped, which
+    /// adapters that stop all threads at once don't name again.
+    current_thread: Mutex<Option<(SessionId, ThreadId)>>,
+}
+
+impl DebuggerTool {
+    pub fn new(project: Entity<Project>) -> Self {
+        Self {
+            project,
+            current_thread: Mutex::new(None),
+        }
+    }
+}
+
+impl AgentTool for DebuggerTool {
+    type Input = DebuggerToolInput;
+    type Output = String;
+
+    const NAME: &'static str = "debugger";
+
+    fn kind() -> acp::ToolKind {
+        acp::ToolKind::Execute
+    }
+
+    fn initial_title(
+        &self,
+        input: Result<Self::Input, serde_json::Value>,
+        _cx: &mut App,
+    ) -> SharedString {
+        let Ok(input) = input else {
+            return "Debugger".into();
+        };
+        let code = |text: Option<String>| MarkdownInlineCode(&text.unwrap_or_default()).to_string();
+        match input.operation {
+            DebuggerOperation::Start => format!("Start debugging {}", code(input.scenario)).into(),
+            DebuggerOperation::SetBreakpoint => {
+                format!("Set breakpoint at {}", code(input.breakpoint_location())).into()
+            }
+            DebuggerOperation::ClearBreakpoint => {
+                format!("Clear breakpoint at {}", code(input.breakpoint_location())).into()
+            }
+            DebuggerOperation::Continue => "Continue debugging".into(),
+            DebuggerOperation::StepOver => "Step over".into(),
+            DebuggerOperation::StepInto => "Step into".into(),
+            DebuggerOperation::StepOut => "Step out".into(),
+            DebuggerOperation::StackTrace => "Read stack trace".into(),
+            DebuggerOperation::Variables => "Read variables".into(),
+            DebuggerOperation::Evaluate => format!("Evaluate {}", code(input.expression)).into(),
+            DebuggerOperation::Stop => "Stop debugging".into(),
+        }
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: ToolInput<Self::Input>,
+        event_stream: ToolCallEventStream,
+        cx: &mut App,
+    ) -> Task<Result<Self::Output, Self::Output>> {
+        cx.spawn(async move |cx| {
+            let input = input
+                .recv()
+                .await
+                .map_err(|e| format!("Failed to receive tool input: {e}"))?;
+
+            let authorize = cx.update(|cx| {
+                let permission_input = input.permission_input();
+                let decision = decide_permission_from_settings(
+                    Self::NAME,
+                    std::slice::from_ref(&permission_input),
+                    AgentSettings::get_global(cx),
+                );
+
+                match decision {
+                    ToolPermissionDecision::Allow => Ok(None),
+                    ToolPermissionDecision::Deny(reason) => Err(reason),
+                    ToolPermissionDecision::Confirm => {
+                        let context =
+                            crate::ToolPermissionContext::new(Self::NAME, vec![permission_input]);
+                        Ok(Some(event_stream.authorize(
+                            self.initial_title(Ok(input.clone()), cx),
+                            context,
+                            cx,
+                        )))
+                    }
+                }
+            })?;
+            if let Some(authorize) = authorize {
+                authorize.await.map_err(|e| e.to_string())?;
+            }
+
+            let timeout = input
+                .timeout_ms
+                .map_or(DEFAULT_TIMEOUT, Duration::from_millis);
+            match input.operation {
+                DebuggerOperation::Start => {
+                    let label = input
+                        .scenario
+                        .ok_or("`scenario` is required to start debugging")?;
+                    self.start(label, timeout, &event_stream, cx).await
+                }
+                DebuggerOperation::SetBreakpoint | DebuggerOperation::ClearBreakpoint => {
+                    self.edit_breakpoint(&input, cx).await
+                }
+                DebuggerOperation::Continue
+                | DebuggerOperation::StepOver
+                | DebuggerOperation::StepInto
+                | DebuggerOperation::StepOut => {
+                    self.resume(input.operation, timeout, &event_stream, cx)
+                        .await
+                }
+                DebuggerOperation::StackTrace => self.stack_trace(cx).await,
+                DebuggerOperation::Variables => self.variables(&input, cx).await,
+                DebuggerOperation::Evaluate => {
+                    let expression = input
+                        .expression
+                        .ok_or("`expression` is required to evaluate")?;
+                    self.evaluate(expression, input.frame_id, cx).await
+                }
+                DebuggerOperation::Stop => self.stop(cx).await,
+            }
+        })
+    }
+}
+
+impl DebuggerTool {
+    fn session_ids(&self, cx: &App) -> HashSet<SessionId> {
+        self.project
+            .read(cx)
+            .dap_store()
+            .read(cx)
+            .sessions()
+            .map(|session| session.read(cx).session_id())
+            .collect()
+    }
+
+    fn active_session(&self, cx: &App) -> Result<Entity<Session>, String> {
+        self.project
+            .read(cx)
+            .dap_store()
+            .read(cx)
+            .sessions()
+            .filter(|session| !session.read(cx).is_terminated())
+            .last()
+            .cloned()
+            .ok_or_else(|| "No debug session is running. Start one first.".to_string())
+    }
+
+    /// Finds the workspace that the project is open in, which debug sessions
+    /// are started in.
+    fn workspace(&self, cx: &App) -> Option<(WindowHandle<MultiWorkspace>, Entity<Workspace>)> {
+        cx.windows()
+            .into_iter()
+            .filter_map(|window| window.downcast::<MultiWorkspace>())
+            .find_map(|window| {
+                let workspace = window
+                    .read(cx)
+                    .ok()?
+                    .workspaces()
+                    .iter()
+                    .find(|workspace| workspace.read(cx).project() == &self.project)?
+                    .clone();
+                Some((window, workspace))
+            })
+    }
+
+    async fn stopped_session(
+        &self,
+        cx: &mut AsyncApp,
+    ) -> Result<(Entity<Session>, ThreadId), String> {
+        let session = cx.update(|cx| self.active_session(cx))?;
+        let thread_id = self
+            .stopped_thread(&session, None, cx)
+            .await?
+            .ok_or("The program isn't stopped. Set a breakpoint where it should stop first.")?;
+        Ok((session, thread_id))
+    }
+
+    /// Returns the thread that `session` is stopped in, preferring `hint` and
+    /// then the thread that the tool last resumed or reported as stopped.
+    async fn stopped_thread(
+        &self,
+        session: &Entity<Session>,
+        hint: Option<ThreadId>,
+        cx: &mut AsyncApp,
+    ) -> Result<Option<ThreadId>, String> {
+        let (session_id, thread_id, list_threads) = cx.update(|cx| {
+            let session = session.read(cx);
+            let session_id = session.session_id();
+            let preferred = hint.or_else(|| {
+                self.current_thread
+                    .lock()
+                    .filter(|(current_session_id, _)| *current_session_id == session_id)
+                    .map(|(_, thread_id)| thread_id)
+            });
+            let thread_id = session.stopped_thread_id(preferred);
+            // Adapters that stop all threads at once may not say which one
+            // caused it, in which case any of them will do.
+            let list_threads =
+                thread_id.is_none() && preferred.is_none() && session.any_stopped_thread();
+            (session_id, thread_id, list_threads)
+        });
+        let thread_id = match thread_id {
+            Some(thread_id) => thread_id,
+            None if list_threads => {
+                let threads = session
+                    .read_with(cx, |session, _| session.request_threads())
+                    .await
+                    .map_err(|e| e.to_string())?;
+                let thread = threads
+                    .first()
+                    .ok_or("The stopped program has no threads")?;
+                ThreadId(thread.id)
+            }
+            None => return Ok(None),
+        };
+        *self.current_thread.lock() = Some((session_id, thread_id));
+        Ok(Some(thread_id))
+    }
+
+    async fn start(
+        &self,
+        label: String,
+        timeout: Duration,
+        event_stream: &ToolCallEventStream,
+        cx: &mut AsyncApp,
+    ) -> Result<String, String> {
+        let (window, workspace) = cx
+            .update(|cx| self.workspace(cx))
+            .ok_or("The project isn't open in a window, so it can't be debugged")?;
+        let task_contexts = window
+            .update(cx, |_, window, cx| {
+                workspace.update(cx, |workspace, cx| {
+                    tasks_ui::task_contexts(workspace, window, cx)
+                })
+            })
+            .map_err(|e| e.to_string())?
+            .await;
+
+        let task_store = self
+            .project
+            .read_with(cx, |project, _| project.task_store().clone());
+        let scenarios = task_store.update(cx, |task_store, cx| {
+            task_store.task_inventory().map(|inventory| {
+                inventory.update(cx, |inventory, cx| {
+                    inventory.list_debug_scenarios(
+                        &task_contexts,
+                        Vec::new(),
+                        Vec::new(),
+                        false,
+                        cx,
+                    )
+                })
+            })
+        });
+        let (recent, scenarios) = match scenarios {
+            Some(scenarios) => scenarios.await,
+            None => (Vec::new(), Vec::new()),
+        };
+        let scenario = recent
+            .into_iter()
+            .map(|(scenario, _)| scenario)
+            .chain(scenarios.into_iter().map(|(_, scenario)| scenario))
+            .find(|scenario| scenario.label.as_ref() == label)
+            .ok_or_else(|| {
+                format!(
+                    "There is no debug scenario labeled {}",
+                    MarkdownInlineCode(&label)
+                )
+            })?;
+        let task_context = task_contexts
+            .active_context()
+            .cloned()
+            .map(Into::into)
+            .unwrap_or_default();
+
+        let previous_sessions = cx.update(|cx| self.session_ids(cx));
+        window
+            .update(cx, |_, window, cx| {
+                workspace.update(cx, |workspace, cx| {
+                    workspace.start_debug_session(
+                        scenario,
+                        task_context,
+                        None,
+                        task_contexts.worktree(),
+                        window,
+                        cx,
+                    )
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        let status = self
+            .wait_for_stop(
+                |session| !previous_sessions.contains(&session.session_id()),
+                &previous_sessions,
+                timeout,
+                event_stream,
+                cx,
+            )
+            .await?;
+        match status {
+            StopStatus::NotStarted => Err(format!(
+                "The debug scenario {} didn't start. Check the debug panel for errors.",
+                MarkdownInlineCode(&label)
+            )),
+            status => Ok(self.describe_stop(status, cx).await),
+        }
+    }
+
+    async fn resume(
+        &self,
+        operation: DebuggerOperation,
+        timeout: Duration,
+        event_stream: &ToolCallEventStream,
+        cx: &mut AsyncApp,
+    ) -> Result<String, String> {
+        let (session, thread_id) = self.stopped_session(cx).await?;
+        let (session_id, previous_sessions) = cx.update(|cx| {
+            session.update(cx, |session, cx| match operation {
+                DebuggerOperation::StepOver => {
+                    session.step_over(thread_id, SteppingGranularity::Line, cx)
+                }
+                DebuggerOperation::StepInto => {
+                    session.step_in(thread_id, SteppingGranularity::Line, cx)
+                }
+                DebuggerOperation::StepOut => {
+                    session.step_out(thread_id, SteppingGranularity::Line, cx)
+                }
+                _ => session.continue_thread(thread_id, cx),
+            });
+            (session.read(cx).session_id(), self.session_ids(cx))
+        });
+        let status = self
+            .wait_for_stop(
+                |session| session.session_id() == session_id,
+                &previous_sessions,
+                timeout,
+                event_stream,
+                cx,
+            )
+            .await?;
+        Ok(self.describe_stop(status, cx).await)
+    }
+
+    /// Waits until one of the sessions accepted by `is_watched` stops, or
+    /// until all of them have ended.
+    ///
+    /// Sessions that aren't in `previous_sessions` count as stopped as soon as
+    /// any of their threads is. The others only count once the adapter reports
+    /// a new stop, as their threads can still be marked as stopped from before
+    /// they were resumed.
+    async fn wait_for_stop(
+        &self,
+        is_watched: impl Fn(&Session) -> bool,
+        previous_sessions: &HashSet<SessionId>,
+        timeout: Duration,
+        event_stream: &ToolCallEventStream,
+        cx: &mut AsyncApp,
+    ) -> Result<StopStatus, String> {
+        let (changes_tx, mut changes_rx) = mpsc::unbounded();
+        let dap_store = self.project.read_with(cx, |project, _| project.dap_store());
+        let mut subscriptions = vec![cx.update(|cx| {
+            let changes_tx = changes_tx.clone();
+            cx.observe(&dap_store, move |_, _| {
+                changes_tx.unbounded_send(None).ok();
+            })
+        })];
+        let mut subscribed_sessions = HashSet::default();
+        let mut started = false;
+        let mut stop = None;
+        let mut timeout = pin!(cx.background_executor().timer(timeout).fuse());
+        let mut cancelled = pin!(event_stream.cancelled_by_user().fuse());
+        loop {
+            let status = cx.update(|cx| {
+                let sessions = dap_store
+                    .read(cx)
+                    .sessions()
+                    .filter(|session| is_watched(session.read(cx)))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                for session in &sessions {
+                    let session_id = session.read(cx).session_id();
+                    if subscribed_sessions.insert(session_id) {
+                        let changes_tx = changes_tx.clone();
+                        subscriptions.push(cx.subscribe(
+                            session,
+                            move |_, event: &SessionEvent, _| {
+                                if let SessionEvent::Stopped(thread_id) = event {
+                                    changes_tx
+                                        .unbounded_send(Some((session_id, *thread_id)))
+                                        .ok();
+                                }
+                            },
+                        ));
+                        let changes_tx = changes_tx.clone();
+                        subscriptions.push(cx.observe(session, move |_, _| {
+                            changes_tx.unbounded_send(None).ok();
+                        }));
+                    }
+                }
+                started |= !sessions.is_empty();
+
+                let stopped = sessions.iter().find_map(|session| {
+                    let session_id = session.read(cx).session_id();
+                    match stop {
+                        Some((stopped_session_id, thread_id))
+                            if stopped_session_id == session_id =>
+                        {
+                            Some((session.clone(), thread_id))
+                        }
+                        _ if !previous_sessions.contains(&session_id)
+                            && session.read(cx).any_stopped_thread() =>
+                        {
+                            Some((session.clone(), None))
+                        }
+                        _ => None,
+                    }
+                });
+                if let Some((session, thread_id)) = stopped {
+                    Some(StopStatus::Stopped(session, thread_id))
+                } else if started
+                    && sessions
+                        .iter()
+                        .all(|session| session.read(cx).is_terminated())
+                {
+                    Some(StopStatus::Ended)
+                } else {
+                    None
+                }
+            });
+            if let Some(status) = status {
+                return Ok(status);
+            }
+            futures::select_biased! {
+                change = changes_rx.next() => stop = change.flatten(),
+                _ = cancelled => {
+                    return Err("Stopped waiting for the program, as the user cancelled".into());
+                }
+                _ = timeout => break,
+            }
+        }
+        Ok(if started {
+            StopStatus::Running
+        } else {
+            StopStatus::NotStarted
+        })
+    }
+
+    async fn describe_stop(&self, status: StopStatus, cx: &mut AsyncApp) -> String {
+        match status {
+            StopStatus::Stopped(session, hint) => {
+                let Ok(Some(thread_id)) = self.stopped_thread(&session, hint, cx).await else {
+                    return "The program stopped.".into();
+                };
+                let frames = session
+                    .read_with(cx, |session, _| session.request_stack_frames(thread_id))
+                    .await;
+                match frames.as_deref().map(<[_]>::first) {
+                    Ok(Some(frame)) => {
+                        format!("Stopped in thread {} at {}", thread_id.0, format_frame(frame))
+                    }
+                    _ => format!("Stopped in thread {}", thread_id.0),
+                }
+            }
+            StopStatus::Ended => "The debug session has ended.".into(),
+            StopStatus::Running | StopStatus::NotStarted => {
+                "The program is still running. Set a breakpoint where it should stop, or stop the debug session.".into()
+            }
+        }
+    }
+
+    async fn edit_breakpoint(
+        &self,
+        input: &DebuggerToolInput,
+        cx: &mut AsyncApp,
+    ) -> Result<String, String> {
+        let (Some(path), Some(line)) = (&input.path, input.line) else {
+            return Err("`path` and `line` are required to set or clear a breakpoint".into());
+        };
+        let row = line.checked_sub(1).ok_or("`line` is one-based")?;
+        let project_path = cx
+            .update(|cx| self.project.read(cx).find_project_path(path, cx))
+            .ok_or_else(|| format!("Could not find path {path} in project"))?;
+        let buffer = self
+            .project
+            .update(cx, |project, cx| project.open_buffer(project_path, cx))
+            .await
+            .map_err(|e| e.to_string())?;
+        let location = MarkdownInlineCode(&format!("{path}:{line}")).to_string();
+
+        cx.update(|cx| {
+            if row > buffer.read(cx).max_point().row {
+                return Err(format!("{path} has no line {line}"));
+            }
+            let abs_path = BreakpointStore::abs_path_from_buffer(&buffer, cx)
+                .ok_or_else(|| format!("{path} is not a local file"))?;
+            let breakpoint_store = self.project.read(cx).breakpoint_store();
+            let existing = breakpoint_store
+                .read(cx)
+                .breakpoint_at_row(&abs_path, row, cx);
+
+            if input.operation == DebuggerOperation::ClearBreakpoint {
+                let (buffer, breakpoint) =
+                    existing.ok_or_else(|| format!("There is no breakpoint at {location}"))?;
+                breakpoint_store.update(cx, |store, cx| {
+                    store.toggle_breakpoint(buffer, breakpoint, BreakpointEditAction::Toggle, cx)
+                });
+                return Ok(format!("Cleared the breakpoint at {location}"));
+            }
+
+            let edit_action = match &input.condition {
+                Some(condition) => BreakpointEditAction::EditCondition(condition.as_str().into()),
+                None if existing.is_some() => {
+                    return Ok(format!("There already is a breakpoint at {location}"));
+                }
+                None => BreakpointEditAction::Toggle,
+            };
+            let breakpoint = existing.map_or_else(
+                || BreakpointWithPosition {
+                    position: buffer.read(cx).anchor_before(Point::new(row, 0)),
+                    bp: Breakpoint::new_standard(),
+                },
+                |(_, breakpoint)| breakpoint,
+            );
+            breakpoint_store.update(cx, |store, cx| {
+                store.toggle_breakpoint(buffer, breakpoint, edit_action, cx)
+            });
+            Ok(format!("Set a breakpoint at {location}"))
+        })
+    }
+
+    async fn stack_trace(&self, cx: &mut AsyncApp) -> Result<String, String> {
+        let (session, thread_id) = self.stopped_session(cx).await?;
+        let frames = session
+            .read_with(cx, |session, _| session.request_stack_frames(thread_id))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut output = format!("Stack of thread {}:\n", thread_id.0);
+        for frame in &frames {
+            writeln!(output, "- [frame_id {}] {}", frame.id, format_frame(frame)).ok();
+        }
+        Ok(output)
+    }
+
+    async fn variables(
+        &self,
+        input: &DebuggerToolInput,
+        cx: &mut AsyncApp,
+    ) -> Result<String, String> {
+        let (session, thread_id) = self.stopped_session(cx).await?;
+        let mut output = String::new();
+
+        if let Some(variables_reference) = input.variables_reference {
+            let variables = session
+                .read_with(cx, |session, _| {
+                    session.request_variables(variables_reference)
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+            write_variables(&mut output, &variables);
+            return Ok(output);
+        }
+
+        let frame_id = match input.frame_id {
+            Some(frame_id) => frame_id,
+            None => top_frame_id(&session, thread_id, cx).await?,
+        };
+        let scopes = session
+            .read_with(cx, |session, _| session.request_scopes(frame_id))
+            .await
+            .map_err(|e| e.to_string())?;
+        for scope in scopes {
+            writeln!(output, "## {}", scope.name).ok();
+            if scope.expensive {
+                writeln!(
+                    output,
+                    "Not listed, as it is expensive to read. Pass variables_reference {} to list it.",
+                    scope.variables_reference
+                )
+                .ok();
+                continue;
+            }
+            let variables = session
+                .read_with(cx, |session, _| {
+                    session.request_variables(scope.variables_reference)
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+            write_variables(&mut output, &variables);
+        }
+        Ok(output)
+    }
+
+    async fn evaluate(
+        &self,
+        expression: String,
+        frame_id: Option<u64>,
+        cx: &mut AsyncApp,
+    ) -> Result<String, String> {
+        let session = cx.update(|cx| self.active_session(cx))?;
+        let frame_id = match frame_id {
+            Some(frame_id) => Some(frame_id),
+            None => match self.stopped_thread(&session, None, cx).await? {
+                Some(thread_id) => Some(top_frame_id(&session, thread_id, cx).await?),
+                None => None,
+            },
+        };
+        let response = session
+            .read_with(cx, |session, _| {
+                session.request_evaluate(expression, frame_id)
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut output = response.result;
+        if let Some(type_) = response.type_ {
+            write!(output, " ({type_})").ok();
+        }
+        if response.variables_reference > 0 {
+            write!(
+                output,
+                " [variables_reference {}]",
+                response.variables_reference
+            )
+            .ok();
+        }
+        Ok(output)
+    }
+
+    async fn stop(&self, cx: &mut AsyncApp) -> Result<String, String> {
+        let shutdown = cx.update(|cx| {
+            let mut session = self.active_session(cx)?;
+            while let Some(parent) = session.read(cx).parent_session().cloned() {
+                session = parent;
+            }
+            let session_id = session.read(cx).session_id();
+            Ok::<_, String>(
+                self.project
+                    .read(cx)
+                    .dap_store()
+                    .update(cx, |store, cx| store.shutdown_session(session_id, cx)),
+            )
+        })?;
+        shutdown.await.map_err(|e| e.to_string())?;
+        Ok("Stopped the debug session.".into())
+    }
+}
+
+enum StopStatus {
+    /// The session stopped, in the given thread if the adapter said which.
+    Stopped(Entity<Session>, Option<ThreadId>),
+    Ended,
+    Running,
+    NotStarted,
+}
+
+async fn top_frame_id(
+    session: &Entity<Session>,
+    thread_id: ThreadId,
+    cx: &mut AsyncApp,
+) -> Result<u64, String> {
+    let frames = session
+        .read_with(cx, |session, _| session.request_stack_frames(thread_id))
+        .await
+        .map_err(|e| e.to_string())?;
+    frames
+        .first()
+        .map(|frame| frame.id)
+        .ok_or_else(|| "The stopped thread has no stack frames".into())
+}
+
+fn format_frame(frame: &dap::StackFrame) -> String {
+    let path = frame
+        .source
+        .as_ref()
+        .and_then(|source| source.path.as_ref().or(source.name.as_ref()));
+    match path {
+        Some(path) => format!("{} ({path}:{})", frame.name, frame.line),
+        None => frame.name.clone(),
+    }
+}
+
+fn write_variables(output: &mut String, variables: &[dap::Variable]) {
+    if variables.is_empty() {
+        writeln!(output, "No variables").ok();
+    }
+    for variable in variables {
+        write!(output, "- {}", variable.name).ok();
+        if let Some(type_) = &variable.type_ {
+            write!(output, ": {type_}").ok();
+        }
+        write!(output, " = {}", variable.value).ok();
+        if variable.variables_reference > 0 {
+            write!(
+                output,
+                " [variables_reference {}]",
+                variable.variables_reference
+            )
+            .ok();
+        }
+        writeln!(output).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dap::requests::{Evaluate, Next, Scopes, StackTrace, Threads, Variables};
+    use gpui::TestAppContext;
+    use project::FakeFs;
+    use serde_json::json;
+    use settings::SettingsStore;
+    use util::path;
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+        });
+        allow_all_tools(cx);
+    }
+
+    fn allow_all_tools(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let mut settings = AgentSettings::get_global(cx).clone();
+            settings.tool_permissions.default = settings::ToolPermissionMode::Allow;
+            AgentSettings::override_global(settings, cx);
+        });
+    }
+
+    fn input(operation: DebuggerOperation) -> DebuggerToolInput {
+        DebuggerToolInput {
+            operation,
+            scenario: None,
+            path: None,
+            line: None,
+            condition: None,
+            expression: None,
+            frame_id: None,
+            variables_reference: None,
+            timeout_ms: None,
+        }
+    }
+
+    fn breakpoint_input(
+        operation: DebuggerOperation,
+        line: u32,
+        condition: Option<&str>,
+    ) -> DebuggerToolInput {
+        DebuggerToolInput {
+            path: Some("root/main.rs".into()),
+            line: Some(line),
+            condition: condition.map(Into::into),
+            ..input(operation)
+        }
+    }
+
+    async fn run(
+        tool: &Arc<DebuggerTool>,
+        input: DebuggerToolInput,
+        cx: &mut TestAppContext,
+    ) -> Result<String, String> {
+        cx.update(|cx| {
+            tool.clone().run(
+                ToolInput::resolved(input),
+                ToolCallEventStream::test().0,
+                cx,
+            )
+        })
+        .await
+    }
+
+    #[gpui::test]
+    async fn test_debugger_tool_breakpoints(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                "main.rs": "fn main() {\n    let x = 1;\n}\n",
+            }),
+        )
+        .await;
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        let tool = Arc::new(DebuggerTool::new(project.clone()));
+        let breakpoints = |cx: &mut TestAppContext| {
+            project.read_with(cx, |project, cx| {
+                project
+                    .breakpoint_store()
+                    .read(cx)
+                    .all_source_breakpoints(cx)
+                    .into_values()
+                    .flatten()
+                    .map(|breakpoint| (breakpoint.row, breakpoint.condition))
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        let output = run(
+            &tool,
+            breakpoint_input(DebuggerOperation::SetBreakpoint, 2, None),
+            cx,
+        )
+        .await;
+        assert_eq!(output, Ok("Set a breakpoint at `root/main.rs:2`".into()));
+        assert_eq!(breakpoints(cx), vec![(1, None)]);
+
+        // Setting a breakpoint with a condition where there already is one adds the condition to it.
+        run(
+            &tool,
+            breakpoint_input(DebuggerOperation::SetBreakpoint, 2, Some("x > 0")),
+            cx,
+        )
+        .await
+        .unwrap();
+        assert_eq!(breakpoints(cx), vec![(1, Some("x > 0".into()))]);
+
+        let output = run(
+            &tool,
+            breakpoint_input(DebuggerOperation::ClearBreakpoint, 2, None),
+            cx,
+        )
+        .await;
+        assert_eq!(
+            output,
+            Ok("Cleared the breakpoint at `root/main.rs:2`".into())
+        );
+        assert_eq!(breakpoints(cx), vec![]);
+
+        let output = run(
+            &tool,
+            breakpoint_input(DebuggerOperation::ClearBreakpoint, 2, None),
+            cx,
+        )
+        .await;
+        assert_eq!(
+            output,
+            Err("There is no breakpoint at `root/main.rs:2`".into())
+        );
+        let output = run(
+            &tool,
+            breakpoint_input(DebuggerOperation::SetBreakpoint, 10, None),
+            cx,
+        )
+        .await;
+        assert_eq!(output, Err("root/main.rs has no line 10".into()));
+
+        // Inspecting the program requires a debug session.
+        let output = run(&tool, input(DebuggerOperation::StackTrace), cx).await;
+        assert_eq!(
+            output,
+            Err("No debug session is running. Start one first.".into())
+        );
+    }
+
+    #[test]
+    fn test_debugger_tool_permission_input() {
+        assert_eq!(
+            breakpoint_input(DebuggerOperation::SetBreakpoint, 12, Some("x > 0"))
+                .permission_input(),
+            "set_breakpoint root/main.rs:12"
+        );
+        let evaluate = DebuggerToolInput {
+            expression: Some("x + 1".into()),
+            ..input(DebuggerOperation::Evaluate)
+        };
+        assert_eq!(evaluate.permission_input(), "evaluate x + 1");
+        assert_eq!(
+            input(DebuggerOperation::StepOver).permission_input(),
+            "step_over"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_debugger_tool_session(cx: &mut TestAppContext) {
+        debugger_ui::tests::init_test(cx);
+        allow_all_tools(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                ".zed": {
+                    "debug.json": r#"[
+                        {
+                            "adapter": "fake-adapter",
+                            "label": "Run tests",
+                            "request": "launch"
+                        }
+                    ]"#
+                },
+                "main.rs": "fn main() {\n    let x = 1;\n}\n",
+            }),
+        )
+        .await;
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        let _workspace = debugger_ui::tests::init_test_workspace(&project, cx).await;
+        let tool = Arc::new(DebuggerTool::new(project.clone()));
+
+        let client = Arc::new(Mutex::new(None));
+        let stepped_threads = Arc::new(Mutex::new(Vec::new()));
+        let _subscription = project::debugger::test::intercept_debug_sessions(cx, {
+            let client = client.clone();
+            let stepped_threads = stepped_threads.clone();
+            move |adapter_client| {
+                *client.lock() = Some(adapter_client.clone());
+                adapter_client.on_request::<Threads, _>(|_, _| {
+                    Ok(dap::ThreadsResponse {
+                        threads: vec![dap::Thread {
+                            id: 1,
+                            name: "main".into(),
+                        }],
+                    })
+                });
+                adapter_client.on_request::<StackTrace, _>(|_, _| {
+                    Ok(dap::StackTraceResponse {
+                        stack_frames: vec![dap::StackFrame {
+                            id: 7,
+                            name: "main".into(),
+                            source: Some(dap::Source {
+                                name: Some("main.rs".into()),
+                                path: Some(path!("/root/main.rs").into()),
+                                source_reference: None,
+                                presentation_hint: None,
+                                origin: None,
+                                sources: None,
+                                adapter_data: None,
+                                checksums: None,
+                            }),
+                            line: 2,
+                            column: 5,
+                            end_line: None,
+                            end_column: None,
+                            can_restart: None,
+                            instruction_pointer_reference: None,
+                            module_id: None,
+                            presentation_hint: None,
+                        }],
+                        total_frames: None,
+                    })
+                });
+                adapter_client.on_request::<Scopes, _>(|_, _| {
+                    Ok(dap::ScopesResponse {
+                        scopes: vec![dap::Scope {
+                            name: "Locals".into(),
+                            presentation_hint: None,
+                            variables_reference: 2,
+                            named_variables: None,
+                            indexed_variables: None,
+                            expensive: false,
+                            source: None,
+                            line: None,
+                            column: None,
+                            end_line: None,
+                            end_column: None,
+                        }],
+                    })
+                });
+                adapter_client.on_request::<Variables, _>(|_, _| {
+                    Ok(dap::VariablesResponse {
+                        variables: vec![dap::Variable {
+                            name: "x".into(),
+                            value: "1".into(),
+                            type_: Some("i32".into()),
+                            presentation_hint: None,
+                            evaluate_name: None,
+                            variables_reference: 0,
+                            named_variables: None,
+                            indexed_variables: None,
+                            memory_reference: None,
+                            declaration_location_reference: None,
+                            value_location_reference: None,
+                        }],
+                    })
+                });
+                adapter_client.on_request::<Evaluate, _>(|_, args| {
+                    Ok(dap::EvaluateResponse {
+                        result: match (args.expression.as_str(), args.frame_id) {
+                            ("x + 1", Some(7)) => "2".into(),
+                            _ => "unknown".into(),
+                        },
+                        type_: Some("i32".into()),
+                        presentation_hint: None,
+                        variables_reference: 0,
+                        named_variables: None,
+                        indexed_variables: None,
+                        memory_reference: None,
+                        value_location_reference: None,
+                    })
+                });
+                adapter_client.on_request::<Next, _>({
+                    let stepped_threads = stepped_threads.clone();
+                    move |_, args| {
+                        stepped_threads.lock().push(args.thread_id);
+                        Ok(())
+                    }
+                });
+            }
+        });
+        let stopped_event = |thread_id| {
+            dap::messages::Events::Stopped(dap::StoppedEvent {
+                reason: dap::StoppedEventReason::Breakpoint,
+                description: None,
+                thread_id,
+                preserve_focus_hint: None,
+                text: None,
+                all_threads_stopped: Some(true),
+                hit_breakpoint_ids: None,
+            })
+        };
+        let stopped_at = format!("Stopped in thread 1 at main ({}:2)", path!("/root/main.rs"));
+
+        let start = DebuggerToolInput {
+            scenario: Some("Run tests".into()),
+            ..input(DebuggerOperation::Start)
+        };
+        let task = cx.update(|cx| {
+            tool.clone().run(
+                ToolInput::resolved(start),
+                ToolCallEventStream::test().0,
+                cx,
+            )
+        });
+        cx.run_until_parked();
+        let client = client
+            .lock()
+            .clone()
+            .expect("debug session should have started");
+
+        // The adapter stops all threads without saying which one caused it.
+        client.fake_event(stopped_event(None)).await;
+        assert_eq!(task.await, Ok(stopped_at.clone()));
+
+        let output = run(&tool, input(DebuggerOperation::StackTrace), cx).await;
+        assert_eq!(
+            output,
+            Ok(format!(
+                "Stack of thread 1:\n- [frame_id 7] main ({}:2)\n",
+                path!("/root/main.rs")
+            ))
+        );
+        let output = run(&tool, input(DebuggerOperation::Variables), cx).await;
+        assert_eq!(output, Ok("## Locals\n- x: i32 = 1\n".into()));
+        let evaluate = DebuggerToolInput {
+            expression: Some("x + 1".into()),
+            ..input(DebuggerOperation::Evaluate)
+        };
+        let output = run(&tool, evaluate, cx).await;
+        assert_eq!(output, Ok("2 (i32)".into()));
+
+        // Stepping waits for the adapter to report a new stop, even though the
+        // threads were all stopped before.
+        let task = cx.update(|cx| {
+            tool.clone().run(
+                ToolInput::resolved(input(DebuggerOperation::StepOver)),
+                ToolCallEventStream::test().0,
+                cx,
+            )
+        });
+        cx.run_until_parked();
+        assert_eq!(*stepped_threads.lock(), vec![1]);
+        assert!(!task.is_ready());
+        client.fake_event(stopped_event(Some(1))).await;
+        assert_eq!(task.await, Ok(stopped_at));
+    }
+
+    #[gpui::test]
+    async fn test_debugger_tool_denied(cx: &mut TestAppContext) {
+        init_test(cx);
+        cx.update(|cx| {
+            let mut settings = AgentSettings::get_global(cx).clone();
+            settings.tool_permissions.tools.insert(
+                DebuggerTool::NAME.into(),
+                agent_settings::ToolRules {
+                    always_deny: vec![
+                        agent_settings::CompiledRegex::new("^set_breakpoint", false).unwrap(),
+                    ],
+                    ..Default::default()
+                },
+            );
+            AgentSettings::override_global(settings, cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                "main.rs": "fn main() {\n    let x = 1;\n}\n",
+            }),
+        )
+        .await;
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        let tool = Arc::new(DebuggerTool::new(project.clone()));
+
+        let (event_stream, mut event_rx) = ToolCallEventStream::test();
+        let output = cx
+            .update(|cx| {
+                tool.clone().run(
+                    ToolInput::resolved(breakpoint_input(
+                        DebuggerOperation::SetBreakpoint,
+                        2,
+                        None,
+                    )),
+                    event_stream,
+                    cx,
+                )
+            })
+            .await;
+        assert!(output.is_err(), "Denied operations should fail: {output:?}");
+        assert!(
+            !matches!(
+                event_rx.try_next(),
+                Ok(Some(Ok(crate::ThreadEvent::ToolCallAuthorization(_))))
+            ),
+            "Denied operations should not ask for authorization",
+        );
+        project.read_with(cx, |project, cx| {
+            assert!(
+                project
+                    .breakpoint_store()
+                    .read(cx)
+                    .all_source_breakpoints(cx)
+                    .is_empty()
+            );
+        });
+
+        // Operations that the rules don't deny still run.
+        let output = run(&tool, input(DebuggerOperation::StackTrace), cx).await;
+        assert_eq!(
+            output,
+            Err("No debug session is running. Start one first.".into())
+        );
+    }
+}
@@ -0,0 +1,458 @@
+use std::sync::Arc;
+
+use editor::{ClipboardHistory, ClipboardHistoryEntry, Editor};
+use fuzzy::{StringMatch, StringMatchCandidate};
+use gpui::{
+    App, Context, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, ParentElement,
+    Render, Styled, Task, WeakEntity, Window, rems,
+};
+use picker::{Picker, PickerDelegate};
+use time::{OffsetDateTime, UtcOffset};
+use ui::{HighlightedLabel, ListItem, ListItemSpacing, prelude::*};
+use util::ResultExt;
+use workspace::ModalView;
+use zed_actions::clipboard_history::{ClearHistory, Toggle};
+
+/// The maximum number of characters of an entry's first line shown in the list.
+const MAX_LABEL_LEN: usize = 80;
+
+pub fn init(cx: &mut App) {
+    cx.observe_new(ClipboardHistoryView::register).detach();
+    cx.on_action(|_: &ClearHistory, cx| ClipboardHistory::clear(cx));
+}
+
+pub fn toggle(editor: Entity<Editor>, _: &Toggle, window: &mut Window, cx: &mut App) {
+    let Some(workspace) = editor.read(cx).workspace() else {
+        return;
+    };
+    workspace.update(cx, |workspace, cx| {
+        workspace.toggle_modal(window, cx, |window, cx| {
+            ClipboardHistoryView::new(editor, window, cx)
+        });
+    });
+}
+
+pub struct ClipboardHistoryView {
+    picker: Entity<Picker<ClipboardHistoryDelegate>>,
+}
+
+impl Focusable for ClipboardHistoryView {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for ClipboardHistoryView {}
+impl ModalView for ClipboardHistoryView {}
+
+impl Render for ClipboardHistoryView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("ClipboardHistory")
+            .w(rems(40.))
+            .on_action(cx.listener(|_, _: &Toggle, _window, cx| cx.emit(DismissEvent)))
+            .child(self.picker.clone())
+    }
+}
+
+impl ClipboardHistoryView {
+    fn register(editor: &mut Editor, _: Option<&mut Window>, cx: &mut Context<Editor>) {
+        if editor.mode().is_full() {
+            let handle = cx.entity().downgrade();
+            editor
+                .register_action(move |action, window, cx| {
+                    if let Some(editor) = handle.upgrade() {
+                        toggle(editor, action, window, cx);
+                    }
+                })
+                .detach();
+        }
+    }
+
+    fn new(editor: Entity<Editor>, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let delegate = ClipboardHistoryDelegate::new(cx.entity().downgrade(), editor, cx);
+        let picker = cx.new(|cx| Picker::uniform_list(delegate, window, cx));
+        Self { picker }
+    }
+}
+
+struct ClipboardHistoryDelegate {
+    clipboard_history_view: WeakEntity<ClipboardHistoryView>,
+    editor: Entity<Editor>,
+    entries: Vec<ClipboardHistoryEntry>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+    timezone: UtcOffset,
+}
+
+impl ClipboardHistoryDelegate {
+    fn new(
+        clipboard_history_view: WeakEntity<ClipboardHistoryView>,
+        editor: Entity<Editor>,
+        cx: &mut App,
+    ) -> Self {
+        let entries = ClipboardHistory::entries(cx);
+        let candidates = entries
+            .iter()
+            .enumerate()
+            .map(|(ix, entry)| StringMatchCandidate::new(ix, &entry.text))
+            .collect();
+        Self {
+            clipboard_history_view,
+            editor,
+            entries,
+            candidates,
+            matches: Vec::new(),
+            selected_index: 0,
+            timezone: UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
+        }
+    }
+}
+
+impl PickerDelegate for ClipboardHistoryDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _window: &mut Window, _cx: &mut App) -> Arc<str> {
+        "Search clipboard history…".into()
+    }
+
+    fn no_matches_text(&self, _window: &mut Window, _cx: &mut App) -> Option<SharedString> {
+        Some(if self.entries.is_empty() {
+            "Nothing has been copied yet".into()
+        } else {
+            "No matching entries".into()
+        })
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(
+        &mut self,
+        ix: usize,
+        _window: &mut Window,
+        _cx: &mut Context<Picker<Self>>,
+    ) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn_in(window, async move |this, cx| {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                fuzzy::match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    true,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(cx, |this, cx| {
+                this.delegate.matches = matches;
+                this.delegate.selected_index = 0;
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _secondary: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        if let Some(entry) = self
+            .matches
+            .get(self.selected_index)
+            .and_then(|mat| self.entries.get(mat.candidate_id))
+        {
+            self.editor.update(cx, |editor, cx| {
+                editor.paste_clipboard_history_entry(entry, window, cx);
+                window.focus(&editor.focus_handle(cx), cx);
+            });
+        }
+        self.dismissed(window, cx);
+    }
+
+    fn dismissed(&mut self, _window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        self.clipboard_history_view
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _window: &mut Window,
+        _cx: &mut Context<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = self.matches.get(ix)?;
+        let entry = self.entries.get(mat.candidate_id)?;
+
+        // Show the first non-blank line, keeping the match positions within it.
+        let line_start = entry
+            .text
+            .find(|c: char| !c.is_whitespace())
+            .unwrap_or_default();
+        let line_end = entry.text[line_start..]
+            .find('\n')
+            .map_or(entry.text.len(), |end| line_start + end);
+        let line = entry.text[line_start..line_end].trim_end();
+        let label = util::truncate_and_trailoff(line, MAX_LABEL_LEN);
+        let positions = mat
+            .positions
+            .iter()
+            .filter_map(|position| position.checked_sub(line_start))
+            .filter(|position| {
+                *position < line.len().min(label.len()) && label.is_char_boundary(*position)
+            })
+            .collect::<Vec<_>>();
+
+        let mut details = Vec::new();
+        if let Some(selections) = &entry.selections
+            && selections.len() > 1
+        {
+            details.push(format!("{} cursors", selections.len()));
+        }
+        let line_count = entry.text.trim_end_matches('\n').lines().count();
+        if line_count > 1 {
+            details.push(format!("{line_count} lines"));
+        }
+        if let Some(language) = &entry.language {
+            details.push(language.clone());
+        }
+        details.push(time_format::format_localized_timestamp(
+            OffsetDateTime::from(entry.copied_at),
+            OffsetDateTime::now_utc(),
+            self.timezone,
+            time_format::TimestampFormat::Relative,
+        ));
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .toggle_state(selected)
+                .child(
+                    h_flex()
+                        .w_full()
+                        .gap_2()
+                        .child(HighlightedLabel::new(label, positions))
+                        .child(div().flex_1())
+                        .child(
+                            Label::new(details.join(" · "))
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        ),
+                ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use db::kvp::KeyValueStore;
+    use editor::actions::{Copy, SelectAll};
+    use gpui::{TestAppContext, UpdateGlobal, VisualTestContext};
+    use project::{FakeFs, Project};
+    use serde_json::json;
+    use settings::SettingsStore;
+    use util::{path, rel_path::rel_path};
+    use workspace::{AppState, MultiWorkspace, Workspace};
+
+    #[gpui::test]
+    async fn test_paste_from_clipboard_history(cx: &mut TestAppContext) {
+        init_test(cx);
+        let (workspace, editor, cx) = open_editor(cx).await;
+        copy(&editor, "one", cx);
+        copy(&editor, "two three", cx);
+        editor.update_in(cx, |editor, window, cx| editor.set_text("", window, cx));
+
+        let picker = open_clipboard_history(&workspace, cx);
+        assert_eq!(match_texts(&picker, cx), ["two three", "one"]);
+
+        cx.simulate_input("one");
+        cx.run_until_parked();
+        assert_eq!(match_texts(&picker, cx), ["one"]);
+
+        cx.dispatch_action(menu::Confirm);
+        cx.run_until_parked();
+        assert_eq!(editor.update(cx, |editor, cx| editor.text(cx)), "one");
+        assert_eq!(
+            cx.read_from_clipboard().and_then(|item| item.text()),
+            Some("one".to_string())
+        );
+        workspace.update(cx, |workspace, cx| {
+            assert!(workspace.active_modal::<ClipboardHistoryView>(cx).is_none())
+        });
+        assert_eq!(entry_texts(cx), ["one", "two three"]);
+    }
+
+    #[gpui::test]
+    async fn test_clipboard_history_skips_large_entries(cx: &mut TestAppContext) {
+        init_test(cx);
+        let (_, editor, cx) = open_editor(cx).await;
+        copy(&editor, "small", cx);
+        copy(
+            &editor,
+            &"x".repeat(ClipboardHistory::MAX_ENTRY_LEN + 1),
+            cx,
+        );
+        assert_eq!(entry_texts(cx), ["small"]);
+    }
+
+    #[gpui::test]
+    async fn test_clipboard_history_persistence(cx: &mut TestAppContext) {
+        init_test(cx);
+        let (_, editor, cx) = open_editor(cx).await;
+        set_persist(true, cx);
+
+        // Copying repeatedly persists the history once it settles.
+        copy(&editor, "one", cx);
+        copy(&editor, "two", cx);
+        assert_eq!(persisted_history(cx), None);
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.run_until_parked();
+        let persisted = persisted_history(cx).unwrap();
+        assert!(persisted.contains("one") && persisted.contains("two"));
+
+        // Disabling persistence deletes the stored history, but keeps the one in
+        // memory for the rest of the session.
+        set_persist(false, cx);
+        cx.run_until_parked();
+        assert_eq!(persisted_history(cx), None);
+        assert_eq!(entry_texts(cx), ["two", "one"]);
+
+        copy(&editor, "three", cx);
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.run_until_parked();
+        assert_eq!(persisted_history(cx), None);
+    }
+
+    fn init_test(cx: &mut TestAppContext) -> Arc<AppState> {
+        cx.update(|cx| {
+            let state = AppState::test(cx);
+            cx.set_global(db::AppDatabase::test_new());
+            editor::init(cx);
+            crate::init(cx);
+            state
+        })
+    }
+
+    async fn open_editor(
+        cx: &mut TestAppContext,
+    ) -> (Entity<Workspace>, Entity<Editor>, &mut VisualTestContext) {
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(path!("/dir"), json!({ "a.txt": "" })).await;
+        let project = Project::test(fs, [path!("/dir").as_ref()], cx).await;
+        let (multi_workspace, cx) =
+            cx.add_window_view(|window, cx| MultiWorkspace::test_new(project.clone(), window, cx));
+        let workspace = cx.read(|cx| multi_workspace.read(cx).workspace().clone());
+        let worktree_id = project.read_with(cx, |project, cx| {
+            project.worktrees(cx).next().unwrap().read(cx).id()
+        });
+        let editor = workspace
+            .update_in(cx, |workspace, window, cx| {
+                workspace.open_path((worktree_id, rel_path("a.txt")), None, true, window, cx)
+            })
+            .await
+            .unwrap()
+            .downcast::<Editor>()
+            .unwrap();
+        (workspace, editor, cx)
+    }
+
+    fn copy(editor: &Entity<Editor>, text: &str, cx: &mut VisualTestContext) {
+        editor.update_in(cx, |editor, window, cx| {
+            editor.set_text(text, window, cx);
+            editor.select_all(&SelectAll, window, cx);
+            editor.copy(&Copy, window, cx);
+        });
+    }
+
+    fn open_clipboard_history(
+        workspace: &Entity<Workspace>,
+        cx: &mut VisualTestContext,
+    ) -> Entity<Picker<ClipboardHistoryDelegate>> {
+        cx.dispatch_action(Toggle);
+        cx.run_until_parked();
+        workspace.update(cx, |workspace, cx| {
+            workspace
+                .active_modal::<ClipboardHistoryView>(cx)
+                .unwrap()
+                .read(cx)
+                .picker
+                .clone()
+        })
+    }
+
+    fn match_texts(
+        picker: &Entity<Picker<ClipboardHistoryDelegate>>,
+        cx: &mut VisualTestContext,
+    ) -> Vec<String> {
+        picker.read_with(cx, |picker, _| {
+            picker
+                .delegate
+                .matches
+                .iter()
+                .map(|mat| picker.delegate.entries[mat.candidate_id].text.clone())
+                .collect()
+        })
+    }
+
+    fn entry_texts(cx: &mut VisualTestContext) -> Vec<String> {
+        cx.update(|_, cx| ClipboardHistory::entries(cx))
+            .into_iter()
+            .map(|entry| entry.text)
+            .collect()
+    }
+
+    fn set_persist(persist: bool, cx: &mut VisualTestContext) {
+        cx.update(|_, cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings
+                        .editor
+                        .clipboard_history
+                        .get_or_insert_default()
+                        .persist = Some(persist);
+                });
+            });
+        });
+    }
+
+    fn persisted_history(cx: &mut VisualTestContext) -> Option<String> {
+        cx.update(|_, cx| {
+            KeyValueStore::global(cx)
+                .read_kvp("clipboard_history")
+                .unwrap()
+        })
+    }
+}
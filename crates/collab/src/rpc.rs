@@ -311,6 +311,7 @@ impl Server {
             .add_request_handler(forward_read_only_project_request::<proto::OpenBufferByPath>)
             .add_request_handler(forward_read_only_project_request::<proto::OpenImageByPath>)
             .add_request_handler(forward_read_only_project_request::<proto::DownloadFileByPath>)
+            .add_request_handler(forward_read_only_project_request::<proto::ReadFileRange>)
             .add_request_handler(forward_read_only_project_request::<proto::GitGetBranches>)
             .add_request_handler(forward_read_only_project_request::<proto::GetDefaultBranch>)
             .add_request_handler(forward_read_only_project_request::<proto::OpenUnstagedDiff>)
@@ -340,6 +341,7 @@ impl Server {
             .add_request_handler(forward_mutating_project_request::<proto::ApplyCodeAction>)
             .add_request_handler(forward_mutating_project_request::<proto::PrepareRename>)
             .add_request_handler(forward_mutating_project_request::<proto::PerformRename>)
+            .add_request_handler(forward_mutating_project_request::<proto::GetRenameEdits>)
             .add_request_handler(forward_mutating_project_request::<proto::ReloadBuffers>)
             .add_request_handler(forward_mutating_project_request::<proto::ApplyCodeActionKind>)
             .add_request_handler(forward_mutating_project_request::<proto::FormatBuffers>)
@@ -432,6 +434,12 @@ impl Server {
             .add_request_handler(forward_mutating_project_request::<proto::GetBlobContent>)
             .add_request_handler(forward_mutating_project_request::<proto::GitCreateBranch>)
             .add_request_handler(forward_mutating_project_request::<proto::GitChangeBranch>)
+            .add_request_handler(forward_mutating_project_request::<proto::GitSetBranchTarget>)
+            .add_request_handler(forward_read_only_project_request::<proto::GitReflog>)
+            .add_request_handler(forward_read_only_project_request::<proto::GitGetSubmodules>)
+            .add_request_handler(forward_mutating_project_request::<proto::GitSubmoduleOperation>)
+            .add_request_handler(forward_read_only_project_request::<proto::GitLineHistory>)
+            .add_request_handler(forward_read_only_project_request::<proto::GitCommitSignature>)
             .add_request_handler(forward_mutating_project_request::<proto::GitCreateRemote>)
             .add_request_handler(forward_mutating_project_request::<proto::GitRemoveRemote>)
             .add_request_handler(forward_read_only_project_request::<proto::GitGetWorktrees>)
@@ -4,7 +4,11 @@ use debugger_panel::DebugPanel;
 use editor::{Editor, MultiBufferOffsetUtf16};
 use gpui::{Action, App, DispatchPhase, EntityInputHandler, actions};
 use new_process_modal::{NewProcessModal, NewProcessMode};
-use project::debugger::{self, breakpoint_store::SourceBreakpoint, session::ThreadStatus};
+use project::{
+    DirectoryLister,
+    debugger::{self, breakpoint_store::SourceBreakpoint, session::ThreadStatus},
+};
+use recording_view::SessionRecordingView;
 use schemars::JsonSchema;
 use serde::Deserialize;
 use session::DebugSession;
@@ -20,6 +24,7 @@ pub mod debugger_panel;
 mod dropdown_menus;
 mod new_process_modal;
 mod persistence;
+mod recording_view;
 pub(crate) mod session;
 
 #[cfg(any(test, feature = "test-support"))]
@@ -82,6 +87,11 @@ actions!(
         /// When toggled on, only frames from the user's code are shown
         /// When toggled off, all frames are shown
         ToggleUserFrames,
+        /// Starts or stops recording the stops of the active debugging session
+        /// to a file.
+        ToggleSessionRecording,
+        /// Opens a recorded debugging session to step through its stops.
+        OpenSessionRecording,
     ]
 );
 
@@ -112,6 +122,8 @@ pub fn init(cx: &mut App) {
     cx.observe_new(|workspace: &mut Workspace, _, _| {
         workspace
             .register_action(spawn_task_or_modal)
+            .register_action(toggle_session_recording)
+            .register_action(open_session_recording)
             .register_action(|workspace, _: &ToggleFocus, window, cx| {
                 workspace.toggle_panel_focus::<DebugPanel>(window, cx);
             })
@@ -444,3 +456,83 @@ fn spawn_task_or_modal(
         }
     }
 }
+
+fn toggle_session_recording(
+    workspace: &mut Workspace,
+    _: &ToggleSessionRecording,
+    window: &mut ui::Window,
+    cx: &mut ui::Context<Workspace>,
+) {
+    let Some(session) = workspace
+        .panel::<DebugPanel>(cx)
+        .and_then(|panel| panel.read(cx).active_session())
+        .map(|session| session.read(cx).session(cx))
+    else {
+        return;
+    };
+
+    let recording_path = session.read(cx).recording_path().map(ToOwned::to_owned);
+    if let Some(recording_path) = recording_path {
+        let finished = session.update(cx, |session, _| session.stop_recording());
+        cx.spawn_in(window, async move |workspace, cx| {
+            finished.await;
+            workspace
+                .update_in(cx, |_, window, cx| {
+                    SessionRecordingView::open(workspace.clone(), recording_path, window, cx)
+                })?
+                .await
+        })
+        .detach_and_log_err(cx);
+    } else {
+        let fs = workspace.app_state().fs.clone();
+        session.update(cx, |session, cx| {
+            let name = session
+                .label()
+                .map(|label| label.to_string())
+                .unwrap_or_else(|| session.adapter().to_string())
+                .replace(|c: char| !c.is_alphanumeric(), "-");
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            session.start_recording(
+                paths::debug_recordings_dir().join(format!("{name}-{timestamp}.json")),
+                fs,
+                cx,
+            );
+        });
+    }
+}
+
+fn open_session_recording(
+    workspace: &mut Workspace,
+    _: &OpenSessionRecording,
+    window: &mut ui::Window,
+    cx: &mut ui::Context<Workspace>,
+) {
+    let prompt = workspace.prompt_for_open_path(
+        gpui::PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: None,
+        },
+        DirectoryLister::Local(
+            workspace.project().clone(),
+            workspace.app_state().fs.clone(),
+        ),
+        window,
+        cx,
+    );
+    cx.spawn_in(window, async move |workspace, cx| {
+        let Some(path) = prompt.await?.and_then(|mut paths| paths.pop()) else {
+            return Ok(());
+        };
+        workspace
+            .update_in(cx, |_, window, cx| {
+                SessionRecordingView::open(workspace.clone(), path, window, cx)
+            })?
+            .await
+    })
+    .detach_and_log_err(cx);
+}
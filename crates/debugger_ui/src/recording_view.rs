@@ -0,0 +1,433 @@
+//! A read-only view of a recorded debug session, for stepping through its stops
+//! after the session has ended.
+
+use std::{ops::Range, path::PathBuf};
+
+use anyhow::{Context as _, Result};
+use collections::HashSet;
+use dap::VariableReference;
+use gpui::{
+    AnyElement, EventEmitter, FocusHandle, Focusable, ScrollStrategy, Task,
+    UniformListScrollHandle, WeakEntity, uniform_list,
+};
+use project::debugger::recording::{RecordedStop, SessionRecording};
+use ui::{Disclosure, ListItem, Tooltip, prelude::*};
+use workspace::{Workspace, item::Item};
+
+pub struct SessionRecordingView {
+    path: PathBuf,
+    recording: SessionRecording,
+    stop_ix: usize,
+    /// The thread and stack frame whose scopes are shown.
+    selected_frame: Option<(usize, usize)>,
+    expanded_variables: HashSet<VariableReference>,
+    /// The lines of console output of all stops.
+    console_lines: Vec<SharedString>,
+    /// The number of console lines printed up to each stop.
+    console_line_counts: Vec<usize>,
+    focus_handle: FocusHandle,
+    console_scroll_handle: UniformListScrollHandle,
+}
+
+impl SessionRecordingView {
+    pub fn new(path: PathBuf, recording: SessionRecording, cx: &mut Context<Self>) -> Self {
+        let mut console_lines = Vec::new();
+        let mut console_line_counts = Vec::with_capacity(recording.stops.len());
+        for stop in &recording.stops {
+            console_lines.extend(
+                stop.output
+                    .iter()
+                    .flat_map(|event| event.output.lines())
+                    .map(|line| SharedString::from(line.to_string())),
+            );
+            console_line_counts.push(console_lines.len());
+        }
+        let mut this = Self {
+            path,
+            recording,
+            stop_ix: 0,
+            selected_frame: None,
+            expanded_variables: HashSet::default(),
+            console_lines,
+            console_line_counts,
+            focus_handle: cx.focus_handle(),
+            console_scroll_handle: UniformListScrollHandle::new(),
+        };
+        this.select_stop(0, cx);
+        this
+    }
+
+    /// Reads the recording at `path` and opens it in the active pane.
+    pub(crate) fn open(
+        workspace: WeakEntity<Workspace>,
+        path: PathBuf,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<()>> {
+        window.spawn(cx, async move |cx| {
+            let fs = workspace.read_with(cx, |workspace, _| workspace.app_state().fs.clone())?;
+            let contents = fs
+                .load(&path)
+                .await
+                .with_context(|| format!("reading debug recording {}", path.display()))?;
+            let recording = serde_json::from_str::<SessionRecording>(&contents)
+                .with_context(|| format!("parsing debug recording {}", path.display()))?;
+            workspace.update_in(cx, |workspace, window, cx| {
+                let view = cx.new(|cx| Self::new(path, recording, cx));
+                workspace.add_item_to_active_pane(Box::new(view), None, true, window, cx);
+            })
+        })
+    }
+
+    #[cfg(test)]
+    pub(crate) fn stop_ix(&self) -> usize {
+        self.stop_ix
+    }
+
+    #[cfg(test)]
+    pub(crate) fn selected_frame(&self) -> Option<(usize, usize)> {
+        self.selected_frame
+    }
+
+    pub(crate) fn select_stop(&mut self, ix: usize, cx: &mut Context<Self>) {
+        self.stop_ix = ix.min(self.recording.stops.len().saturating_sub(1));
+        self.expanded_variables.clear();
+        // Show the top frame of the thread that stopped, as the debugger panel does.
+        self.selected_frame = self.stop().and_then(|stop| {
+            let stopped_thread_id = stop.event.as_ref().and_then(|event| event.thread_id);
+            let thread_ix = stop
+                .threads
+                .iter()
+                .position(|thread| Some(thread.thread.id) == stopped_thread_id)
+                .or_else(|| {
+                    stop.threads
+                        .iter()
+                        .position(|thread| !thread.stack_frames.is_empty())
+                })?;
+            (!stop.threads[thread_ix].stack_frames.is_empty()).then_some((thread_ix, 0))
+        });
+        if let Some(line_count) = self.console_line_count().checked_sub(1) {
+            self.console_scroll_handle
+                .scroll_to_item(line_count, ScrollStrategy::Bottom);
+        }
+        cx.notify();
+    }
+
+    pub(crate) fn select_frame(
+        &mut self,
+        thread_ix: usize,
+        frame_ix: usize,
+        cx: &mut Context<Self>,
+    ) {
+        self.selected_frame = Some((thread_ix, frame_ix));
+        cx.notify();
+    }
+
+    fn toggle_variable(&mut self, variables_reference: VariableReference, cx: &mut Context<Self>) {
+        if !self.expanded_variables.remove(&variables_reference) {
+            self.expanded_variables.insert(variables_reference);
+        }
+        cx.notify();
+    }
+
+    fn stop(&self) -> Option<&RecordedStop> {
+        self.recording.stops.get(self.stop_ix)
+    }
+
+    /// The number of console lines shown at the selected stop, which are all
+    /// those printed up to it.
+    fn console_line_count(&self) -> usize {
+        self.console_line_counts
+            .get(self.stop_ix)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn render_header(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let stop_count = self.recording.stops.len();
+        let title = if stop_count == 0 {
+            "No stops were recorded".to_string()
+        } else {
+            format!("Stop {} of {stop_count}", self.stop_ix + 1)
+        };
+        let reason = self
+            .stop()
+            .and_then(|stop| stop.event.as_ref())
+            .map(|event| {
+                event
+                    .description
+                    .clone()
+                    .unwrap_or_else(|| format!("{:?}", event.reason))
+            });
+
+        h_flex()
+            .p_1()
+            .gap_1()
+            .border_b_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(
+                IconButton::new("previous-recorded-stop", IconName::ArrowLeft)
+                    .icon_size(IconSize::Small)
+                    .tooltip(Tooltip::text("Previous Stop"))
+                    .disabled(self.stop_ix == 0)
+                    .on_click(cx.listener(|this, _, _, cx| {
+                        this.select_stop(this.stop_ix.saturating_sub(1), cx)
+                    })),
+            )
+            .child(
+                IconButton::new("next-recorded-stop", IconName::ArrowRight)
+                    .icon_size(IconSize::Small)
+                    .tooltip(Tooltip::text("Next Stop"))
+                    .disabled(self.stop_ix + 1 >= stop_count)
+                    .on_click(cx.listener(|this, _, _, cx| this.select_stop(this.stop_ix + 1, cx))),
+            )
+            .child(Label::new(title).size(LabelSize::Small))
+            .when_some(reason, |this, reason| {
+                this.child(
+                    Label::new(reason)
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+            })
+    }
+
+    fn render_frames(&self, stop: &RecordedStop, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex().children(stop.threads.iter().enumerate().map(|(thread_ix, thread)| {
+            v_flex()
+                .child(
+                    Label::new(thread.thread.name.clone())
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+                .children(
+                    thread
+                        .stack_frames
+                        .iter()
+                        .enumerate()
+                        .map(|(frame_ix, stack_frame)| {
+                            let frame = &stack_frame.frame;
+                            let location = frame
+                                .source
+                                .as_ref()
+                                .and_then(|source| {
+                                    source.name.clone().or_else(|| source.path.clone())
+                                })
+                                .map(|source| format!("{source}:{}", frame.line));
+                            ListItem::new(("recorded-stack-frame", thread_ix * 10_000 + frame_ix))
+                                .inset(true)
+                                .toggle_state(self.selected_frame == Some((thread_ix, frame_ix)))
+                                .on_click(cx.listener(move |this, _, _, cx| {
+                                    this.select_frame(thread_ix, frame_ix, cx)
+                                }))
+                                .child(
+                                    h_flex()
+                                        .gap_1()
+                                        .child(
+                                            Label::new(frame.name.clone()).size(LabelSize::Small),
+                                        )
+                                        .when_some(location, |this, location| {
+                                            this.child(
+                                                Label::new(location)
+                                                    .size(LabelSize::Small)
+                                                    .color(Color::Muted),
+                                            )
+                                        }),
+                                )
+                        }),
+                )
+        }))
+    }
+
+    fn render_variables(&self, stop: &RecordedStop, cx: &mut Context<Self>) -> impl IntoElement {
+        let mut rows = Vec::new();
+        if let Some(scopes) = self.selected_frame.and_then(|(thread_ix, frame_ix)| {
+            Some(
+                &stop
+                    .threads
+                    .get(thread_ix)?
+                    .stack_frames
+                    .get(frame_ix)?
+                    .scopes,
+            )
+        }) {
+            for scope in scopes {
+                rows.push(
+                    Label::new(scope.name.clone())
+                        .size(LabelSize::Small)
+                        .color(Color::Muted)
+                        .into_any_element(),
+                );
+                self.render_variable_rows(
+                    stop,
+                    scope.variables_reference,
+                    &mut HashSet::default(),
+                    &mut rows,
+                    cx,
+                );
+            }
+        }
+        if rows.is_empty() {
+            rows.push(
+                Label::new("No variables were inspected in this frame")
+                    .size(LabelSize::Small)
+                    .color(Color::Muted)
+                    .into_any_element(),
+            );
+        }
+        v_flex().children(rows)
+    }
+
+    fn render_variable_rows(
+        &self,
+        stop: &RecordedStop,
+        variables_reference: VariableReference,
+        ancestors: &mut HashSet<VariableReference>,
+        rows: &mut Vec<AnyElement>,
+        cx: &mut Context<Self>,
+    ) {
+        // Adapters can reuse references, so a variable may contain one of its
+        // ancestors, which isn't expanded again.
+        if !ancestors.insert(variables_reference) {
+            return;
+        }
+        let depth = ancestors.len() - 1;
+        for variable in stop.variables(variables_reference) {
+            let reference = variable.variables_reference;
+            let is_expanded = self.expanded_variables.contains(&reference);
+            // Only variables that were expanded during the session have recorded children.
+            let has_children = reference != 0
+                && !ancestors.contains(&reference)
+                && !stop.variables(reference).is_empty();
+            rows.push(
+                h_flex()
+                    .pl(px(depth as f32 * 12.))
+                    .gap_1()
+                    .child(div().w_4().when(has_children, |this| {
+                        this.child(
+                            Disclosure::new(("recorded-variable", reference), is_expanded)
+                                .on_toggle_expanded(cx.listener(move |this, _, _, cx| {
+                                    this.toggle_variable(reference, cx)
+                                })),
+                        )
+                    }))
+                    .child(Label::new(variable.name.clone()).size(LabelSize::Small))
+                    .child(
+                        Label::new(variable.value.clone())
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .into_any_element(),
+            );
+            if has_children && is_expanded {
+                self.render_variable_rows(stop, reference, ancestors, rows, cx);
+            }
+        }
+        ancestors.remove(&variables_reference);
+    }
+
+    fn render_console(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        uniform_list(
+            "recorded-console",
+            self.console_line_count(),
+            cx.processor(|this, range: Range<usize>, _, cx| {
+                this.console_lines[range]
+                    .iter()
+                    .map(|line| {
+                        Label::new(line.clone())
+                            .size(LabelSize::Small)
+                            .buffer_font(cx)
+                            .into_any_element()
+                    })
+                    .collect()
+            }),
+        )
+        .track_scroll(&self.console_scroll_handle)
+        .flex_1()
+    }
+}
+
+impl EventEmitter<()> for SessionRecordingView {}
+
+impl Focusable for SessionRecordingView {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Item for SessionRecordingView {
+    type Event = ();
+
+    fn tab_content_text(&self, _detail: usize, _cx: &App) -> SharedString {
+        let label = self
+            .recording
+            .label
+            .clone()
+            .unwrap_or_else(|| self.recording.adapter.clone());
+        format!("Recording: {label}").into()
+    }
+
+    fn tab_icon(&self, _window: &Window, _cx: &App) -> Option<Icon> {
+        Some(Icon::new(IconName::Debug))
+    }
+
+    fn tab_tooltip_text(&self, _: &App) -> Option<SharedString> {
+        Some(self.path.to_string_lossy().into_owned().into())
+    }
+}
+
+impl Render for SessionRecordingView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let section = |title: &'static str| {
+            Label::new(title)
+                .size(LabelSize::Small)
+                .color(Color::Muted)
+                .into_any_element()
+        };
+        let body = self.stop().cloned().map(|stop| {
+            h_flex()
+                .size_full()
+                .items_start()
+                .gap_2()
+                .p_1()
+                .child(
+                    v_flex()
+                        .id("recorded-frames")
+                        .w_1_3()
+                        .h_full()
+                        .overflow_y_scroll()
+                        .child(section("Frames"))
+                        .child(self.render_frames(&stop, cx)),
+                )
+                .child(
+                    v_flex()
+                        .id("recorded-variables")
+                        .w_1_3()
+                        .h_full()
+                        .overflow_y_scroll()
+                        .child(section("Variables"))
+                        .child(self.render_variables(&stop, cx)),
+                )
+                .child(
+                    v_flex()
+                        .w_1_3()
+                        .h_full()
+                        .child(section("Console"))
+                        .child(self.render_console(cx)),
+                )
+        });
+
+        v_flex()
+            .key_context("SessionRecordingView")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(self.render_header(cx))
+            .child(
+                div()
+                    .id("session-recording")
+                    .flex_1()
+                    .min_h_0()
+                    .children(body),
+            )
+    }
+}
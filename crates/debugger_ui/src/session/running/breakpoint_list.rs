@@ -5,26 +5,28 @@ use std::{
     time::Duration,
 };
 
+use anyhow::Context as _;
 use dap::{Capabilities, ExceptionBreakpointsFilter, adapters::DebugAdapterName};
 use db::kvp::KeyValueStore;
 use editor::Editor;
 use gpui::{
-    Action, AppContext, ClickEvent, Entity, FocusHandle, Focusable, MouseButton, ScrollStrategy,
-    Task, UniformListScrollHandle, WeakEntity, actions, uniform_list,
+    Action, AppContext, ClickEvent, Entity, FocusHandle, Focusable, MouseButton, PathPromptOptions,
+    ScrollStrategy, Subscription, Task, UniformListScrollHandle, WeakEntity, actions, uniform_list,
 };
 use itertools::Itertools;
 use language::Point;
 use project::{
-    Project,
+    DirectoryLister, Project, ProjectPath, WorktreeId,
     debugger::{
         breakpoint_store::{BreakpointEditAction, BreakpointStore, SourceBreakpoint},
         dap_store::{DapStore, PersistedAdapterOptions},
         session::Session,
     },
-    worktree_store::WorktreeStore,
+    worktree_store::{WorktreeStore, WorktreeStoreEvent},
 };
+use task::{BreakpointSet, BreakpointSetFile, VsCodeBreakpointFile};
 use ui::{
-    Divider, DividerColor, FluentBuilder as _, Indicator, IntoElement, ListItem, Render,
+    Checkbox, Divider, DividerColor, FluentBuilder as _, Indicator, IntoElement, ListItem, Render,
     ScrollAxes, StatefulInteractiveElement, Tooltip, WithScrollbar, prelude::*,
 };
 use util::rel_path::RelPath;
@@ -37,7 +39,13 @@ actions!(
         /// Navigates to the previous breakpoint property in the list.
         PreviousBreakpointProperty,
         /// Navigates to the next breakpoint property in the list.
-        NextBreakpointProperty
+        NextBreakpointProperty,
+        /// Saves the breakpoints of each worktree as a new set in its `.zed/breakpoints.json`.
+        SaveBreakpointSet,
+        /// Imports a breakpoint set from a JSON file of VS Code breakpoints: an
+        /// array of objects with a `uri` and a 1-based `lineNumber`, and optionally
+        /// `enabled`, `condition`, `hitCondition` and `logMessage`.
+        ImportBreakpointSet
     ]
 );
 #[derive(Clone, Copy, PartialEq)]
@@ -60,6 +68,30 @@ pub(crate) struct BreakpointList {
     input: Entity<Editor>,
     strip_mode: Option<ActiveBreakpointStripMode>,
     serialize_exception_breakpoints_task: Option<Task<anyhow::Result<()>>>,
+    breakpoint_sets: Vec<BreakpointSetEntry>,
+    load_breakpoint_sets_task: Option<Task<()>>,
+    set_name_input: Entity<Editor>,
+    naming_breakpoint_set: Option<BreakpointSetNaming>,
+    _worktree_store_subscription: Subscription,
+}
+
+/// A breakpoint set defined in a worktree, that can be loaded and unloaded as a group.
+struct BreakpointSetEntry {
+    worktree_id: WorktreeId,
+    set: BreakpointSet,
+}
+
+/// What the name entered in the set name input is for.
+enum BreakpointSetNaming {
+    /// Saving the current breakpoints as a new set.
+    Save,
+    /// Saving the sets imported from a VS Code file.
+    Import(Vec<BreakpointSetEntry>),
+    /// Renaming the set named `name` in the worktree.
+    Rename {
+        worktree_id: WorktreeId,
+        name: String,
+    },
 }
 
 impl Focusable for BreakpointList {
@@ -92,7 +124,20 @@ impl BreakpointList {
 
         let adapter_name = session.as_ref().map(|session| session.read(cx).adapter());
         cx.new(|cx| {
-            let this = Self {
+            let worktree_store_subscription =
+                cx.subscribe(&worktree_store, |this, _, event, cx| match event {
+                    WorktreeStoreEvent::WorktreeUpdatedEntries(_, changes)
+                        if changes.iter().any(|(path, _, _)| {
+                            **path == *paths::local_breakpoints_file_relative_path()
+                        }) =>
+                    {
+                        this.load_breakpoint_sets(cx)
+                    }
+                    WorktreeStoreEvent::WorktreeAdded(_)
+                    | WorktreeStoreEvent::WorktreeRemoved(..) => this.load_breakpoint_sets(cx),
+                    _ => {}
+                });
+            let mut this = Self {
                 breakpoint_store,
                 dap_store,
                 worktree_store,
@@ -106,10 +151,20 @@ impl BreakpointList {
                 input: cx.new(|cx| Editor::single_line(window, cx)),
                 strip_mode: None,
                 serialize_exception_breakpoints_task: None,
+                breakpoint_sets: Vec::new(),
+                load_breakpoint_sets_task: None,
+                set_name_input: cx.new(|cx| {
+                    let mut editor = Editor::single_line(window, cx);
+                    editor.set_placeholder_text("Breakpoint Set Name", window, cx);
+                    editor
+                }),
+                naming_breakpoint_set: None,
+                _worktree_store_subscription: worktree_store_subscription,
             };
             if let Some(name) = adapter_name {
                 _ = this.deserialize_exception_breakpoints(name, cx);
             }
+            this.load_breakpoint_sets(cx);
             this
         })
     }
@@ -309,7 +364,11 @@ impl BreakpointList {
     }
 
     fn dismiss(&mut self, _: &menu::Cancel, window: &mut Window, cx: &mut Context<Self>) {
-        if self.input.focus_handle(cx).contains_focused(window, cx) {
+        if self.naming_breakpoint_set.is_some() {
+            self.naming_breakpoint_set.take();
+            self.focus_handle.focus(window, cx);
+            cx.notify();
+        } else if self.input.focus_handle(cx).contains_focused(window, cx) {
             self.focus_handle.focus(window, cx);
         } else if self.strip_mode.is_some() {
             self.strip_mode.take();
@@ -319,6 +378,10 @@ impl BreakpointList {
         }
     }
     fn confirm(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        if self.naming_breakpoint_set.is_some() {
+            self.confirm_breakpoint_set_name(window, cx);
+            return;
+        }
         let Some(entry) = self.selected_ix.and_then(|ix| self.breakpoints.get_mut(ix)) else {
             return;
         };
@@ -388,6 +451,10 @@ impl BreakpointList {
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        if self.naming_breakpoint_set.is_some() {
+            cx.propagate();
+            return;
+        }
         let Some(entry) = self.selected_ix.and_then(|ix| self.breakpoints.get_mut(ix)) else {
             return;
         };
@@ -543,6 +610,417 @@ impl BreakpointList {
         Ok(())
     }
 
+    /// Reads the breakpoint sets from the `.zed/breakpoints.json` of each worktree.
+    fn load_breakpoint_sets(&mut self, cx: &mut Context<Self>) {
+        let Ok(project) = self
+            .workspace
+            .read_with(cx, |workspace, _| workspace.project().clone())
+        else {
+            return;
+        };
+        let path = paths::local_breakpoints_file_relative_path();
+        let worktree_ids = self
+            .worktree_store
+            .read(cx)
+            .visible_worktrees(cx)
+            .filter(|worktree| worktree.read(cx).entry_for_path(path).is_some())
+            .map(|worktree| worktree.read(cx).id())
+            .collect::<Vec<_>>();
+        let buffers = worktree_ids
+            .into_iter()
+            .map(|worktree_id| {
+                let buffer = project.update(cx, |project, cx| {
+                    project.open_buffer(
+                        ProjectPath {
+                            worktree_id,
+                            path: path.into_arc(),
+                        },
+                        cx,
+                    )
+                });
+                (worktree_id, buffer)
+            })
+            .collect::<Vec<_>>();
+        self.load_breakpoint_sets_task = Some(cx.spawn(async move |this, cx| {
+            let mut breakpoint_sets = Vec::new();
+            for (worktree_id, buffer) in buffers {
+                let contents = match buffer.await {
+                    Ok(buffer) => buffer.read_with(cx, |buffer, _| buffer.text()),
+                    Err(error) => {
+                        log::error!("Failed to open {}: {error:#}", path.as_unix_str());
+                        continue;
+                    }
+                };
+                match serde_json_lenient::from_str::<BreakpointSetFile>(&contents) {
+                    Ok(file) => {
+                        breakpoint_sets.extend(
+                            file.0
+                                .into_iter()
+                                .map(|set| BreakpointSetEntry { worktree_id, set }),
+                        );
+                    }
+                    Err(error) => log::error!("Failed to parse {}: {error}", path.as_unix_str()),
+                }
+            }
+            this.update(cx, |this, cx| {
+                this.breakpoint_sets = breakpoint_sets;
+                cx.notify();
+            })
+            .ok();
+        }));
+    }
+
+    /// Applies `update` to the breakpoint sets in the `.zed/breakpoints.json`
+    /// of the worktree and saves the file, creating it if needed.
+    fn update_breakpoint_set_file(
+        &self,
+        worktree_id: WorktreeId,
+        update: impl FnOnce(&mut BreakpointSetFile) + 'static,
+        cx: &mut Context<Self>,
+    ) -> Task<anyhow::Result<()>> {
+        let Ok(project) = self
+            .workspace
+            .read_with(cx, |workspace, _| workspace.project().clone())
+        else {
+            return Task::ready(Ok(()));
+        };
+        let buffer = project.update(cx, |project, cx| {
+            project.open_buffer(
+                ProjectPath {
+                    worktree_id,
+                    path: paths::local_breakpoints_file_relative_path().into_arc(),
+                },
+                cx,
+            )
+        });
+        cx.spawn(async move |_, cx| {
+            let buffer = buffer.await?;
+            buffer.update(cx, |buffer, cx| {
+                let contents = buffer.text();
+                let mut file = if contents.trim().is_empty() {
+                    BreakpointSetFile::default()
+                } else {
+                    serde_json_lenient::from_str::<BreakpointSetFile>(&contents)?
+                };
+                update(&mut file);
+                buffer.edit(
+                    [(0..buffer.len(), serde_json::to_string_pretty(&file)?)],
+                    None,
+                    cx,
+                );
+                anyhow::Ok(())
+            })?;
+            project
+                .update(cx, |project, cx| project.save_buffer(buffer, cx))
+                .await
+        })
+    }
+
+    fn start_naming_breakpoint_set(
+        &mut self,
+        naming: BreakpointSetNaming,
+        name: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.naming_breakpoint_set = Some(naming);
+        self.set_name_input.update(cx, |input, cx| {
+            input.set_text(name, window, cx);
+            input.select_all(&editor::actions::SelectAll, window, cx);
+        });
+        self.set_name_input.focus_handle(cx).focus(window, cx);
+        cx.notify();
+    }
+
+    fn confirm_breakpoint_set_name(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(naming) = self.naming_breakpoint_set.take() else {
+            return;
+        };
+        let name = self.set_name_input.read(cx).text(cx).trim().to_string();
+        self.focus_handle.focus(window, cx);
+        cx.notify();
+        if name.is_empty() {
+            return;
+        }
+
+        let sets: Vec<BreakpointSetEntry> = match naming {
+            BreakpointSetNaming::Save => {
+                let breakpoint_store = self.breakpoint_store.read(cx);
+                self.worktree_store
+                    .read(cx)
+                    .visible_worktrees(cx)
+                    .map(|worktree| {
+                        let worktree_id = worktree.read(cx).id();
+                        BreakpointSetEntry {
+                            worktree_id,
+                            set: breakpoint_store.breakpoint_set(name.clone(), worktree_id, cx),
+                        }
+                    })
+                    .filter(|entry| !entry.set.breakpoints.is_empty())
+                    .collect()
+            }
+            BreakpointSetNaming::Import(sets) => sets,
+            BreakpointSetNaming::Rename {
+                worktree_id,
+                name: old_name,
+            } => {
+                self.update_breakpoint_set_file(
+                    worktree_id,
+                    move |file| file.rename(&old_name, name),
+                    cx,
+                )
+                .detach_and_log_err(cx);
+                return;
+            }
+        };
+        for BreakpointSetEntry {
+            worktree_id,
+            mut set,
+        } in sets
+        {
+            set.name = name.clone();
+            self.update_breakpoint_set_file(worktree_id, move |file| file.insert(set), cx)
+                .detach_and_log_err(cx);
+        }
+    }
+
+    pub(crate) fn save_breakpoint_set(
+        &mut self,
+        _: &SaveBreakpointSet,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let name = (1..)
+            .map(|n| format!("Breakpoint Set {n}"))
+            .find(|name| {
+                !self
+                    .breakpoint_sets
+                    .iter()
+                    .any(|entry| &entry.set.name == name)
+            })
+            .unwrap_or_default();
+        self.start_naming_breakpoint_set(BreakpointSetNaming::Save, &name, window, cx);
+    }
+
+    pub(crate) fn import_breakpoint_set(
+        &mut self,
+        _: &ImportBreakpointSet,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Ok((prompt, fs)) = self.workspace.update(cx, |workspace, cx| {
+            let fs = workspace.app_state().fs.clone();
+            let prompt = workspace.prompt_for_open_path(
+                PathPromptOptions {
+                    files: true,
+                    directories: false,
+                    multiple: false,
+                    prompt: None,
+                },
+                DirectoryLister::Local(workspace.project().clone(), fs.clone()),
+                window,
+                cx,
+            );
+            (prompt, fs)
+        }) else {
+            return;
+        };
+        let worktrees = self
+            .worktree_store
+            .read(cx)
+            .visible_worktrees(cx)
+            .map(|worktree| (worktree.read(cx).id(), worktree.read(cx).abs_path()))
+            .collect::<Vec<_>>();
+        cx.spawn_in(window, async move |this, cx| {
+            let Some(path) = prompt.await?.and_then(|mut paths| paths.pop()) else {
+                return Ok(());
+            };
+            let contents = fs.load(&path).await?;
+            let file = serde_json_lenient::from_str::<VsCodeBreakpointFile>(&contents)
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let sets = worktrees
+                .into_iter()
+                .map(|(worktree_id, worktree_abs_path)| BreakpointSetEntry {
+                    worktree_id,
+                    set: file.to_breakpoint_set(name.clone(), &worktree_abs_path),
+                })
+                .filter(|entry| !entry.set.breakpoints.is_empty())
+                .collect::<Vec<_>>();
+            anyhow::ensure!(
+                !sets.is_empty(),
+                "{} has no breakpoints in the project",
+                path.display()
+            );
+            this.update_in(cx, |this, window, cx| {
+                this.start_naming_breakpoint_set(
+                    BreakpointSetNaming::Import(sets),
+                    &name,
+                    window,
+                    cx,
+                )
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    pub(crate) fn rename_breakpoint_set(
+        &mut self,
+        ix: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(entry) = self.breakpoint_sets.get(ix) else {
+            return;
+        };
+        let name = entry.set.name.clone();
+        self.start_naming_breakpoint_set(
+            BreakpointSetNaming::Rename {
+                worktree_id: entry.worktree_id,
+                name: name.clone(),
+            },
+            &name,
+            window,
+            cx,
+        );
+    }
+
+    pub(crate) fn remove_breakpoint_set(&mut self, ix: usize, cx: &mut Context<Self>) {
+        let Some(entry) = self.breakpoint_sets.get(ix) else {
+            return;
+        };
+        let name = entry.set.name.clone();
+        self.update_breakpoint_set_file(entry.worktree_id, move |file| file.remove(&name), cx)
+            .detach_and_log_err(cx);
+    }
+
+    #[cfg(test)]
+    pub(crate) fn breakpoint_sets(&self) -> Vec<&BreakpointSet> {
+        self.breakpoint_sets
+            .iter()
+            .map(|entry| &entry.set)
+            .collect()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn name_breakpoint_set(
+        &mut self,
+        name: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_name_input
+            .update(cx, |input, cx| input.set_text(name, window, cx));
+        self.confirm_breakpoint_set_name(window, cx);
+    }
+
+    pub(crate) fn toggle_breakpoint_set(&mut self, ix: usize, cx: &mut Context<Self>) {
+        let Some(entry) = self.breakpoint_sets.get(ix) else {
+            return;
+        };
+        self.breakpoint_store.update(cx, |breakpoint_store, cx| {
+            if breakpoint_store.is_breakpoint_set_loaded(entry.worktree_id, &entry.set, cx) {
+                breakpoint_store.unload_breakpoint_set(entry.worktree_id, &entry.set, cx);
+            } else {
+                breakpoint_store
+                    .load_breakpoint_set(entry.worktree_id, &entry.set, cx)
+                    .detach_and_log_err(cx);
+            }
+        });
+        cx.notify();
+    }
+
+    fn render_breakpoint_sets(&self, window: &Window, cx: &Context<Self>) -> impl IntoElement {
+        let breakpoint_store = self.breakpoint_store.read(cx);
+        let is_loaded = self
+            .breakpoint_sets
+            .iter()
+            .map(|entry| {
+                breakpoint_store.is_breakpoint_set_loaded(entry.worktree_id, &entry.set, cx)
+            })
+            .collect::<Vec<_>>();
+
+        v_flex()
+            .when(self.naming_breakpoint_set.is_some(), |this| {
+                this.child(
+                    h_flex()
+                        .m_1()
+                        .p_1()
+                        .rounded_sm()
+                        .bg(cx.theme().colors().editor_background)
+                        .border_1()
+                        .when(
+                            self.set_name_input
+                                .focus_handle(cx)
+                                .contains_focused(window, cx),
+                            |this| this.border_color(cx.theme().colors().border_focused),
+                        )
+                        .child(self.set_name_input.clone()),
+                )
+            })
+            .children(self.breakpoint_sets.iter().zip(is_loaded).enumerate().map(
+                |(ix, (entry, is_loaded))| {
+                    let count = entry.set.breakpoints.len();
+                    let count_label = if count == 1 {
+                        "1 breakpoint".to_string()
+                    } else {
+                        format!("{count} breakpoints")
+                    };
+                    ListItem::new(("breakpoint-set", ix))
+                        .inset(true)
+                        .start_slot(
+                            Checkbox::new(("breakpoint-set-checkbox", ix), is_loaded.into())
+                                .on_click(cx.listener(move |this, _, _, cx| {
+                                    this.toggle_breakpoint_set(ix, cx)
+                                })),
+                        )
+                        .child(
+                            h_flex()
+                                .gap_1()
+                                .child(
+                                    Label::new(entry.set.name.clone())
+                                        .size(LabelSize::Small)
+                                        .line_height_style(ui::LineHeightStyle::UiLabel),
+                                )
+                                .child(
+                                    Label::new(count_label)
+                                        .size(LabelSize::Small)
+                                        .color(Color::Muted)
+                                        .line_height_style(ui::LineHeightStyle::UiLabel),
+                                ),
+                        )
+                        .end_slot(
+                            h_flex()
+                                .child(
+                                    IconButton::new(
+                                        ("rename-breakpoint-set", ix),
+                                        IconName::Pencil,
+                                    )
+                                    .icon_size(IconSize::XSmall)
+                                    .tooltip(Tooltip::text("Rename Breakpoint Set"))
+                                    .on_click(cx.listener(
+                                        move |this, _, window, cx| {
+                                            this.rename_breakpoint_set(ix, window, cx)
+                                        },
+                                    )),
+                                )
+                                .child(
+                                    IconButton::new(("remove-breakpoint-set", ix), IconName::Trash)
+                                        .icon_size(IconSize::XSmall)
+                                        .tooltip(Tooltip::text("Remove Breakpoint Set"))
+                                        .on_click(cx.listener(move |this, _, _, cx| {
+                                            this.remove_breakpoint_set(ix, cx)
+                                        })),
+                                ),
+                        )
+                        .show_end_slot_on_hover()
+                },
+            ))
+    }
+
     fn render_list(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
         let selected_ix = self.selected_ix;
         let focus_handle = self.focus_handle.clone();
@@ -604,6 +1082,55 @@ impl BreakpointList {
         });
 
         h_flex()
+            .child(
+                IconButton::new(
+                    "import-breakpoint-set-breakpoint-list",
+                    IconName::EditorVsCode,
+                )
+                .icon_size(IconSize::Small)
+                .tooltip({
+                    let focus_handle = focus_handle.clone();
+                    move |_window, cx| {
+                        Tooltip::with_meta_in(
+                            "Import Breakpoint Set",
+                            Some(&ImportBreakpointSet),
+                            "Import breakpoints from a VS Code breakpoints file",
+                            &focus_handle,
+                            cx,
+                        )
+                    }
+                })
+                .on_click({
+                    let focus_handle = focus_handle.clone();
+                    move |_, window, cx| {
+                        focus_handle.focus(window, cx);
+                        window.dispatch_action(ImportBreakpointSet.boxed_clone(), cx)
+                    }
+                }),
+            )
+            .child(
+                IconButton::new("save-breakpoint-set-breakpoint-list", IconName::Download)
+                    .icon_size(IconSize::Small)
+                    .tooltip({
+                        let focus_handle = focus_handle.clone();
+                        move |_window, cx| {
+                            Tooltip::with_meta_in(
+                                "Save Breakpoint Set",
+                                Some(&SaveBreakpointSet),
+                                "Save the breakpoints to .zed/breakpoints.json under a name",
+                                &focus_handle,
+                                cx,
+                            )
+                        }
+                    })
+                    .on_click({
+                        let focus_handle = focus_handle.clone();
+                        move |_, window, cx| {
+                            focus_handle.focus(window, cx);
+                            window.dispatch_action(SaveBreakpointSet.boxed_clone(), cx)
+                        }
+                    }),
+            )
             .child(
                 IconButton::new(
                     "disable-breakpoint-breakpoint-list",
@@ -772,8 +1299,17 @@ impl Render for BreakpointList {
             .on_action(cx.listener(Self::unset_breakpoint))
             .on_action(cx.listener(Self::next_breakpoint_property))
             .on_action(cx.listener(Self::previous_breakpoint_property))
+            .on_action(cx.listener(Self::save_breakpoint_set))
+            .on_action(cx.listener(Self::import_breakpoint_set))
             .size_full()
             .pt_1()
+            .when(
+                !self.breakpoint_sets.is_empty() || self.naming_breakpoint_set.is_some(),
+                |this| {
+                    this.child(self.render_breakpoint_sets(window, cx))
+                        .child(Divider::horizontal().color(DividerColor::Border))
+                },
+            )
             .child(self.render_list(cx))
             .custom_scrollbars(
                 ui::Scrollbars::new(ScrollAxes::Both)
@@ -0,0 +1,447 @@
+use std::{ops::Range, path::Path};
+
+use anyhow::Result;
+use collections::{HashMap, HashSet};
+use dap::{DisassembledInstruction, SteppingGranularity};
+use gpui::{
+    AnyElement, Entity, FocusHandle, Focusable, ScrollStrategy, Subscription, Task,
+    UniformListScrollHandle, WeakEntity, uniform_list,
+};
+use project::{
+    Project,
+    debugger::session::{Session, ThreadId, ThreadStatus},
+};
+use ui::{Tooltip, WithScrollbar, prelude::*};
+
+use crate::{
+    StepInto, StepOut, StepOver,
+    session::running::{
+        RunningState,
+        stack_frame_list::{StackFrameList, StackFrameListEvent},
+    },
+};
+
+/// How many instructions are disassembled around the instruction pointer.
+const INSTRUCTION_COUNT: u64 = 200;
+/// How many of those come before the instruction pointer, so that the
+/// instructions leading up to it are shown too.
+const INSTRUCTIONS_BEFORE_INSTRUCTION_POINTER: i64 = 100;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum DisassemblyRow {
+    /// A line of the source that the instructions below it were compiled from.
+    Source {
+        line: u64,
+        text: SharedString,
+    },
+    Instruction(DisassembledInstruction),
+}
+
+/// Shows the instructions around the instruction pointer of the selected stack
+/// frame. Stepping while it's focused steps by instruction.
+pub(crate) struct DisassemblyView {
+    session: Entity<Session>,
+    project: WeakEntity<Project>,
+    stack_frame_list: Entity<StackFrameList>,
+    running_state: WeakEntity<RunningState>,
+    focus_handle: FocusHandle,
+    scroll_handle: UniformListScrollHandle,
+    /// The instruction pointer that the rows were disassembled around.
+    instruction_pointer: Option<String>,
+    rows: Vec<DisassemblyRow>,
+    _disassemble_task: Task<Result<()>>,
+    _subscription: Subscription,
+}
+
+impl DisassemblyView {
+    pub(crate) fn new(
+        session: Entity<Session>,
+        project: WeakEntity<Project>,
+        stack_frame_list: Entity<StackFrameList>,
+        running_state: WeakEntity<RunningState>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let _subscription =
+            cx.subscribe(&stack_frame_list, |this, _, _: &StackFrameListEvent, cx| {
+                this.update_instruction_pointer(cx);
+            });
+
+        let mut this = Self {
+            session,
+            project,
+            stack_frame_list,
+            running_state,
+            focus_handle: cx.focus_handle(),
+            scroll_handle: UniformListScrollHandle::new(),
+            instruction_pointer: None,
+            rows: Vec::new(),
+            _disassemble_task: Task::ready(Ok(())),
+            _subscription,
+        };
+        this.update_instruction_pointer(cx);
+        this
+    }
+
+    #[cfg(test)]
+    pub(crate) fn rows(&self) -> &[DisassemblyRow] {
+        &self.rows
+    }
+
+    /// Disassembles around the instruction pointer of the opened stack frame
+    /// when it changes.
+    fn update_instruction_pointer(&mut self, cx: &mut Context<Self>) {
+        let instruction_pointer = self
+            .stack_frame_list
+            .read(cx)
+            .opened_stack_frame()
+            .and_then(|stack_frame| stack_frame.instruction_pointer_reference.clone());
+        if instruction_pointer != self.instruction_pointer {
+            self.instruction_pointer = instruction_pointer;
+            self.disassemble(cx);
+            cx.notify();
+        }
+    }
+
+    fn disassemble(&mut self, cx: &mut Context<Self>) {
+        self.rows.clear();
+        let Some(instruction_pointer) = self.instruction_pointer.clone() else {
+            self._disassemble_task = Task::ready(Ok(()));
+            return;
+        };
+        let fs = self
+            .project
+            .read_with(cx, |project, _| project.fs().clone())
+            .ok();
+
+        self._disassemble_task = cx.spawn(async move |this, cx| {
+            // Only the adapter knows where the instructions before the
+            // instruction pointer start, as they vary in length on some
+            // architectures, so they're requested as an instruction offset.
+            let instructions = this
+                .update(cx, |this, cx| {
+                    this.session.update(cx, |session, cx| {
+                        session.disassemble(
+                            instruction_pointer.clone(),
+                            -INSTRUCTIONS_BEFORE_INSTRUCTION_POINTER,
+                            INSTRUCTION_COUNT,
+                            cx,
+                        )
+                    })
+                })?
+                .await
+                .unwrap_or_default();
+
+            let mut sources = HashMap::default();
+            if let Some(fs) = fs {
+                let paths = instructions
+                    .iter()
+                    .filter_map(|instruction| instruction.location.as_ref()?.path.clone())
+                    .filter(|path| Path::new(path).is_absolute())
+                    .collect::<HashSet<_>>();
+                for path in paths {
+                    if let Ok(text) = fs.load(Path::new(&path)).await {
+                        sources.insert(path, text);
+                    }
+                }
+            }
+
+            this.update(cx, |this, cx| {
+                this.rows = disassembly_rows(instructions, &sources);
+                if let Some(ix) = this.rows.iter().position(|row| {
+                    matches!(row, DisassemblyRow::Instruction(instruction)
+                        if is_same_address(&instruction.address, &instruction_pointer))
+                }) {
+                    this.scroll_handle
+                        .scroll_to_item(ix, ScrollStrategy::Center);
+                }
+                cx.notify();
+            })
+        });
+    }
+
+    fn toggle_instruction_breakpoint(&mut self, address: String, cx: &mut Context<Self>) {
+        self.session.update(cx, |session, cx| {
+            session.toggle_instruction_breakpoint(address, cx);
+        });
+        cx.notify();
+    }
+
+    fn step(
+        &mut self,
+        step: impl FnOnce(&mut Session, ThreadId, SteppingGranularity, &mut Context<Session>),
+        cx: &mut Context<Self>,
+    ) {
+        let Some(thread_id) = self
+            .running_state
+            .read_with(cx, |state, _| state.thread_id())
+            .ok()
+            .flatten()
+        else {
+            return;
+        };
+        self.session.update(cx, |session, cx| {
+            if session.thread_status(thread_id) == ThreadStatus::Stopped {
+                step(session, thread_id, SteppingGranularity::Instruction, cx);
+            }
+        });
+    }
+
+    fn step_over(&mut self, _: &StepOver, _: &mut Window, cx: &mut Context<Self>) {
+        self.step(Session::step_over, cx);
+    }
+
+    fn step_in(&mut self, _: &StepInto, _: &mut Window, cx: &mut Context<Self>) {
+        self.step(Session::step_in, cx);
+    }
+
+    fn step_out(&mut self, _: &StepOut, _: &mut Window, cx: &mut Context<Self>) {
+        self.step(Session::step_out, cx);
+    }
+
+    fn render_row(&self, ix: usize, cx: &mut Context<Self>) -> AnyElement {
+        let instruction = match &self.rows[ix] {
+            DisassemblyRow::Source { line, text } => {
+                return h_flex()
+                    .id(("disassembly-source", ix))
+                    .w_full()
+                    .gap_2()
+                    .pl_10()
+                    .child(
+                        Label::new(line.to_string())
+                            .buffer_font(cx)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .child(
+                        Label::new(text.clone())
+                            .buffer_font(cx)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                            .truncate(),
+                    )
+                    .into_any_element();
+            }
+            DisassemblyRow::Instruction(instruction) => instruction,
+        };
+
+        let session = self.session.read(cx);
+        let supports_instruction_breakpoints = session
+            .capabilities()
+            .supports_instruction_breakpoints
+            .unwrap_or_default();
+        let has_breakpoint = session
+            .instruction_breakpoints()
+            .contains(&instruction.address);
+        let is_instruction_pointer =
+            self.instruction_pointer
+                .as_deref()
+                .is_some_and(|instruction_pointer| {
+                    is_same_address(&instruction.address, instruction_pointer)
+                });
+        let address = instruction.address.clone();
+
+        h_flex()
+            .id(("disassembly-instruction", ix))
+            .w_full()
+            .gap_2()
+            .when(is_instruction_pointer, |this| {
+                this.bg(cx.theme().colors().editor_debugger_active_line_background)
+            })
+            .child(
+                div()
+                    .id(("disassembly-breakpoint", ix))
+                    .flex_none()
+                    .w_4()
+                    .when(has_breakpoint, |this| {
+                        this.child(
+                            Icon::new(IconName::DebugBreakpoint)
+                                .size(IconSize::XSmall)
+                                .color(Color::Debugger),
+                        )
+                    })
+                    .when(supports_instruction_breakpoints, |this| {
+                        this.cursor_pointer()
+                            .tooltip(Tooltip::text("Toggle Instruction Breakpoint"))
+                            .on_click(cx.listener(move |this, _, _, cx| {
+                                this.toggle_instruction_breakpoint(address.clone(), cx);
+                            }))
+                    }),
+            )
+            .child(
+                div()
+                    .flex_none()
+                    .w_4()
+                    .when(is_instruction_pointer, |this| {
+                        this.child(
+                            Icon::new(IconName::ArrowRight)
+                                .size(IconSize::XSmall)
+                                .color(Color::Debugger),
+                        )
+                    }),
+            )
+            .child(
+                Label::new(instruction.address.clone())
+                    .buffer_font(cx)
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            )
+            .when_some(instruction.instruction_bytes.clone(), |this, bytes| {
+                this.child(
+                    Label::new(bytes)
+                        .buffer_font(cx)
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+            })
+            .child(
+                Label::new(instruction.instruction.clone())
+                    .buffer_font(cx)
+                    .size(LabelSize::Small),
+            )
+            .when_some(instruction.symbol.clone(), |this, symbol| {
+                this.child(
+                    Label::new(format!("<{symbol}>"))
+                        .buffer_font(cx)
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+            })
+            .into_any_element()
+    }
+
+    fn render_controls(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        h_flex()
+            .w_full()
+            .gap_1()
+            .pb_1()
+            .child(
+                IconButton::new("disassembly-step-over", IconName::DebugStepOver)
+                    .icon_size(IconSize::XSmall)
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.step_over(&StepOver, window, cx);
+                    }))
+                    .tooltip(Tooltip::text("Step Over Instruction")),
+            )
+            .child(
+                IconButton::new("disassembly-step-into", IconName::DebugStepInto)
+                    .icon_size(IconSize::XSmall)
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.step_in(&StepInto, window, cx);
+                    }))
+                    .tooltip(Tooltip::text("Step Into Instruction")),
+            )
+            .child(
+                IconButton::new("disassembly-step-out", IconName::DebugStepOut)
+                    .icon_size(IconSize::XSmall)
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.step_out(&StepOut, window, cx);
+                    }))
+                    .tooltip(Tooltip::text("Step Out")),
+            )
+    }
+}
+
+/// Interleaves the instructions with the source lines they were compiled from,
+/// wherever the line changes from one instruction to the next.
+fn disassembly_rows(
+    instructions: Vec<DisassembledInstruction>,
+    sources: &HashMap<String, String>,
+) -> Vec<DisassemblyRow> {
+    let source_lines = sources
+        .iter()
+        .map(|(path, text)| (path.as_str(), text.lines().collect::<Vec<_>>()))
+        .collect::<HashMap<_, _>>();
+
+    let mut rows = Vec::with_capacity(instructions.len());
+    let mut path = None;
+    let mut last_source = None;
+    for instruction in instructions {
+        // Instructions without a location are from the same source as the one before them.
+        if let Some(location) = &instruction.location {
+            path = location.path.clone().or_else(|| location.name.clone());
+        }
+        if let Some(line) = instruction.line
+            && last_source.as_ref() != Some(&(path.clone(), line))
+        {
+            let text = path
+                .as_deref()
+                .and_then(|path| {
+                    let line_ix = usize::try_from(line).ok()?.checked_sub(1)?;
+                    let text = source_lines.get(path)?.get(line_ix)?;
+                    Some(text.trim_end().to_string())
+                })
+                .unwrap_or_else(|| match &path {
+                    Some(path) => format!("{path}:{line}"),
+                    None => format!("Line {line}"),
+                });
+            rows.push(DisassemblyRow::Source {
+                line,
+                text: text.into(),
+            });
+            last_source = Some((path.clone(), line));
+        }
+        rows.push(DisassemblyRow::Instruction(instruction));
+    }
+    rows
+}
+
+fn parse_address(address: &str) -> Option<u64> {
+    let address = address.trim();
+    match address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => address.parse().ok(),
+    }
+}
+
+fn is_same_address(a: &str, b: &str) -> bool {
+    match (parse_address(a), parse_address(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+impl Focusable for DisassemblyView {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for DisassemblyView {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let content = if self.instruction_pointer.is_none() {
+            div()
+                .p_2()
+                .child(
+                    Label::new("The selected stack frame has no instruction pointer")
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+                .into_any_element()
+        } else {
+            uniform_list(
+                "disassembly-view",
+                self.rows.len(),
+                cx.processor(|this, range: Range<usize>, _window, cx| {
+                    range.map(|ix| this.render_row(ix, cx)).collect()
+                }),
+            )
+            .track_scroll(&self.scroll_handle)
+            .size_full()
+            .into_any_element()
+        };
+
+        v_flex()
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::step_over))
+            .on_action(cx.listener(Self::step_in))
+            .on_action(cx.listener(Self::step_out))
+            .size_full()
+            .p_1()
+            .child(self.render_controls(cx))
+            .child(content)
+            .vertical_scrollbar_for(&self.scroll_handle, window, cx)
+    }
+}
@@ -219,6 +219,20 @@ impl StackFrameList {
         self.opened_stack_frame_id
     }
 
+    pub(crate) fn opened_stack_frame(&self) -> Option<&dap::StackFrame> {
+        let stack_frame_id = self.opened_stack_frame_id?;
+        self.all_stack_frames()
+            .find(|stack_frame| stack_frame.id == stack_frame_id)
+    }
+
+    fn all_stack_frames(&self) -> impl Iterator<Item = &dap::StackFrame> {
+        self.entries.iter().flat_map(|entry| match entry {
+            StackFrameEntry::Label(stack_frame) => std::slice::from_ref(stack_frame),
+            StackFrameEntry::Normal(stack_frame) => std::slice::from_ref(stack_frame),
+            StackFrameEntry::Collapsed(stack_frames) => stack_frames.as_slice(),
+        })
+    }
+
     pub(super) fn schedule_refresh(
         &mut self,
         select_first: bool,
@@ -371,13 +385,7 @@ impl StackFrameList {
         cx: &mut Context<Self>,
     ) -> Task<Result<()>> {
         let Some(stack_frame) = self
-            .entries
-            .iter()
-            .flat_map(|entry| match entry {
-                StackFrameEntry::Label(stack_frame) => std::slice::from_ref(stack_frame),
-                StackFrameEntry::Normal(stack_frame) => std::slice::from_ref(stack_frame),
-                StackFrameEntry::Collapsed(stack_frames) => stack_frames.as_slice(),
-            })
+            .all_stack_frames()
             .find(|stack_frame| stack_frame.id == stack_frame_id)
             .cloned()
         else {
@@ -15,12 +15,16 @@ use crate::{debugger_panel::DebugPanel, session::DebugSession};
 #[cfg(test)]
 mod attach_modal;
 #[cfg(test)]
+mod breakpoint_list;
+#[cfg(test)]
 mod console;
 #[cfg(test)]
 mod dap_logger;
 #[cfg(test)]
 mod debugger_panel;
 #[cfg(test)]
+mod disassembly_view;
+#[cfg(test)]
 mod inline_values;
 #[cfg(test)]
 mod module_list;
@@ -29,6 +33,8 @@ mod new_process_modal;
 #[cfg(test)]
 mod persistence;
 #[cfg(test)]
+mod recording_view;
+#[cfg(test)]
 mod stack_frame_list;
 #[cfg(test)]
 mod variable_list;
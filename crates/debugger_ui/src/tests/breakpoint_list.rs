@@ -0,0 +1,181 @@
+use std::path::Path;
+
+use crate::{
+    session::running::breakpoint_list::{BreakpointList, SaveBreakpointSet},
+    tests::{init_test, init_test_workspace},
+};
+use gpui::{BackgroundExecutor, TestAppContext, VisualTestContext};
+use project::{FakeFs, Project, debugger::breakpoint_store::BreakpointEditAction};
+use serde_json::json;
+use task::{BreakpointSet, BreakpointSetFile, SavedBreakpoint};
+use util::path;
+
+#[gpui::test]
+async fn test_breakpoint_sets(executor: BackgroundExecutor, cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+    fs.insert_tree(
+        path!("/project"),
+        json!({
+            "main.rs": "fn main() {\n    let x = 1;\n    println!(\"{x}\");\n}\n",
+            ".zed": {
+                "breakpoints.json": r#"[{ "name": "first line", "breakpoints": [{ "path": "main.rs", "line": 1 }] }]"#,
+            },
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), [path!("/project").as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+    let worktree_id = project.read_with(cx, |project, cx| {
+        project.worktrees(cx).next().unwrap().read(cx).id()
+    });
+    let breakpoint_store = project.read_with(cx, |project, _| project.breakpoint_store());
+    let read_file = || -> BreakpointSetFile {
+        serde_json::from_slice(
+            &fs.read_file_sync(path!("/project/.zed/breakpoints.json"))
+                .unwrap(),
+        )
+        .unwrap()
+    };
+
+    let list = workspace
+        .update(cx, |multi, window, cx| {
+            BreakpointList::new(None, multi.workspace().downgrade(), &project, window, cx)
+        })
+        .unwrap();
+    cx.run_until_parked();
+    let set_names = |list: &BreakpointList| {
+        list.breakpoint_sets()
+            .into_iter()
+            .map(|set| set.name.clone())
+            .collect::<Vec<_>>()
+    };
+    list.update(cx, |list, _| assert_eq!(set_names(list), ["first line"]));
+
+    let saved_breakpoints = vec![
+        SavedBreakpoint {
+            path: "main.rs".into(),
+            line: 2,
+            enabled: true,
+            condition: Some("x > 0".into()),
+            hit_condition: None,
+            log_message: None,
+        },
+        SavedBreakpoint {
+            path: "main.rs".into(),
+            line: 3,
+            enabled: false,
+            condition: None,
+            hit_condition: None,
+            log_message: Some("x is {x}".into()),
+        },
+    ];
+    breakpoint_store
+        .update(cx, |store, cx| {
+            store.load_breakpoint_set(
+                worktree_id,
+                &BreakpointSet {
+                    name: "initial".into(),
+                    breakpoints: saved_breakpoints.clone(),
+                },
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+
+    // Saving asks for a name before writing the set.
+    list.update_in(cx, |list, window, cx| {
+        list.save_breakpoint_set(&SaveBreakpointSet, window, cx);
+        list.name_breakpoint_set("parser", window, cx);
+    });
+    cx.run_until_parked();
+    assert_eq!(
+        read_file().0,
+        vec![
+            BreakpointSet {
+                name: "first line".into(),
+                breakpoints: vec![SavedBreakpoint {
+                    path: "main.rs".into(),
+                    line: 1,
+                    enabled: true,
+                    condition: None,
+                    hit_condition: None,
+                    log_message: None,
+                }],
+            },
+            BreakpointSet {
+                name: "parser".into(),
+                breakpoints: saved_breakpoints.clone(),
+            },
+        ]
+    );
+    list.update(cx, |list, _| {
+        assert_eq!(set_names(list), ["first line", "parser"])
+    });
+
+    list.update_in(cx, |list, window, cx| {
+        list.rename_breakpoint_set(0, window, cx);
+        list.name_breakpoint_set("startup", window, cx);
+    });
+    cx.run_until_parked();
+    list.update(cx, |list, _| {
+        assert_eq!(set_names(list), ["startup", "parser"])
+    });
+
+    // Sets toggle their breakpoints as a group.
+    breakpoint_store.update(cx, |store, cx| store.clear_breakpoints(cx));
+    cx.run_until_parked();
+    list.update(cx, |list, cx| list.toggle_breakpoint_set(1, cx));
+    cx.run_until_parked();
+    breakpoint_store.read_with(cx, |store, cx| {
+        assert_eq!(
+            store
+                .breakpoint_set("current".into(), worktree_id, cx)
+                .breakpoints,
+            saved_breakpoints
+        );
+    });
+
+    list.update(cx, |list, cx| list.toggle_breakpoint_set(0, cx));
+    cx.run_until_parked();
+    breakpoint_store.read_with(cx, |store, cx| {
+        assert_eq!(
+            store
+                .breakpoint_set("current".into(), worktree_id, cx)
+                .breakpoints
+                .len(),
+            3
+        );
+    });
+
+    // Breakpoints edited after loading still belong to their set.
+    breakpoint_store.update(cx, |store, cx| {
+        let (buffer, breakpoint) = store
+            .breakpoint_at_row(Path::new(path!("/project/main.rs")), 1, cx)
+            .unwrap();
+        store.toggle_breakpoint(
+            buffer,
+            breakpoint,
+            BreakpointEditAction::EditCondition("x > 1".into()),
+            cx,
+        );
+    });
+    list.update(cx, |list, cx| list.toggle_breakpoint_set(1, cx));
+    cx.run_until_parked();
+    breakpoint_store.read_with(cx, |store, cx| {
+        let breakpoints = store
+            .breakpoint_set("current".into(), worktree_id, cx)
+            .breakpoints;
+        assert_eq!(breakpoints.len(), 1);
+        assert_eq!(breakpoints[0].line, 1);
+    });
+
+    list.update(cx, |list, cx| list.remove_breakpoint_set(1, cx));
+    cx.run_until_parked();
+    list.update(cx, |list, _| assert_eq!(set_names(list), ["startup"]));
+    assert_eq!(read_file().0.len(), 1);
+}
@@ -0,0 +1,230 @@
+#![expect(clippy::result_large_err)]
+use crate::{
+    StepOver,
+    persistence::DebuggerPaneItem,
+    session::running::disassembly_view::DisassemblyRow,
+    tests::{active_debug_session_panel, init_test, init_test_workspace, start_debug_session},
+};
+use dap::{
+    DisassembledInstruction, SteppingGranularity,
+    requests::{
+        Disassemble, Initialize, Next, Scopes, SetInstructionBreakpoints, StackTrace, Threads,
+    },
+};
+use gpui::{BackgroundExecutor, Focusable as _, TestAppContext, VisualTestContext};
+use parking_lot::Mutex;
+use project::{FakeFs, Project};
+use serde_json::json;
+use std::sync::Arc;
+use util::path;
+
+fn instruction(address: &str, line: u64, source: Option<dap::Source>) -> DisassembledInstruction {
+    DisassembledInstruction {
+        address: address.into(),
+        instruction_bytes: None,
+        instruction: format!("nop ; {address}"),
+        symbol: None,
+        location: source,
+        line: Some(line),
+        column: None,
+        end_line: None,
+        end_column: None,
+        presentation_hint: None,
+    }
+}
+
+#[gpui::test]
+async fn test_disassembly_view(executor: BackgroundExecutor, cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+    fs.insert_tree(
+        path!("/project"),
+        json!({
+            "main.c": "int main() {\n    return 0;\n}\n",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, [path!("/project").as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let session = start_debug_session(&workspace, cx, |client| {
+        client.on_request::<Initialize, _>(move |_, _| {
+            Ok(dap::Capabilities {
+                supports_disassemble_request: Some(true),
+                supports_instruction_breakpoints: Some(true),
+                supports_stepping_granularity: Some(true),
+                ..Default::default()
+            })
+        });
+    })
+    .unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client.on_request::<Scopes, _>(move |_, _| Ok(dap::ScopesResponse { scopes: vec![] }));
+    client.on_request::<Threads, _>(move |_, _| {
+        Ok(dap::ThreadsResponse {
+            threads: vec![dap::Thread {
+                id: 1,
+                name: "Thread 1".into(),
+            }],
+        })
+    });
+
+    let source = dap::Source {
+        name: Some("main.c".into()),
+        path: Some(path!("/project/main.c").into()),
+        source_reference: None,
+        presentation_hint: None,
+        origin: None,
+        sources: None,
+        adapter_data: None,
+        checksums: None,
+    };
+    client.on_request::<StackTrace, _>({
+        let source = source.clone();
+        move |_, _| {
+            Ok(dap::StackTraceResponse {
+                stack_frames: vec![dap::StackFrame {
+                    id: 1,
+                    name: "main".into(),
+                    source: Some(source.clone()),
+                    line: 2,
+                    column: 1,
+                    end_line: None,
+                    end_column: None,
+                    can_restart: None,
+                    instruction_pointer_reference: Some("0x1008".into()),
+                    module_id: None,
+                    presentation_hint: None,
+                }],
+                total_frames: None,
+            })
+        }
+    });
+
+    let instructions = vec![
+        instruction("0x1000", 1, Some(source)),
+        instruction("0x1004", 1, None),
+        instruction("0x1008", 2, None),
+        instruction("0x100C", 2, None),
+    ];
+    let disassembled_references = Arc::new(Mutex::new(Vec::new()));
+    client.on_request::<Disassemble, _>({
+        let instructions = instructions.clone();
+        let disassembled_references = disassembled_references.clone();
+        move |_, args| {
+            disassembled_references
+                .lock()
+                .push((args.memory_reference.clone(), args.instruction_offset));
+            Ok(dap::DisassembleResponse {
+                instructions: instructions.clone(),
+            })
+        }
+    });
+
+    let instruction_breakpoints = Arc::new(Mutex::new(Vec::new()));
+    client.on_request::<SetInstructionBreakpoints, _>({
+        let instruction_breakpoints = instruction_breakpoints.clone();
+        move |_, args| {
+            *instruction_breakpoints.lock() = args
+                .breakpoints
+                .into_iter()
+                .map(|breakpoint| breakpoint.instruction_reference)
+                .collect();
+            Ok(dap::SetInstructionBreakpointsResponse {
+                breakpoints: Vec::new(),
+            })
+        }
+    });
+
+    let step_granularity = Arc::new(Mutex::new(None));
+    client.on_request::<Next, _>({
+        let step_granularity = step_granularity.clone();
+        move |_, args| {
+            *step_granularity.lock() = args.granularity;
+            Ok(())
+        }
+    });
+
+    client
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Pause,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+    cx.run_until_parked();
+
+    let disassembly_view =
+        active_debug_session_panel(workspace, cx).update_in(cx, |item, window, cx| {
+            item.running_state().update(cx, |state, cx| {
+                state.activate_item(DebuggerPaneItem::Disassembly, window, cx);
+                state.disassembly_view().clone()
+            })
+        });
+    cx.run_until_parked();
+
+    // Disassembly starts before the instruction pointer, so that the
+    // instructions leading up to it are shown too.
+    assert_eq!(
+        *disassembled_references.lock(),
+        vec![("0x1008".to_string(), Some(-100))]
+    );
+    disassembly_view.update(cx, |view, _| {
+        assert_eq!(
+            view.rows(),
+            &[
+                DisassemblyRow::Source {
+                    line: 1,
+                    text: "int main() {".into(),
+                },
+                DisassemblyRow::Instruction(instructions[0].clone()),
+                DisassemblyRow::Instruction(instructions[1].clone()),
+                DisassemblyRow::Source {
+                    line: 2,
+                    text: "    return 0;".into(),
+                },
+                DisassemblyRow::Instruction(instructions[2].clone()),
+                DisassemblyRow::Instruction(instructions[3].clone()),
+            ]
+        );
+    });
+
+    session.update(cx, |session, cx| {
+        session.toggle_instruction_breakpoint("0x100C".into(), cx);
+    });
+    cx.run_until_parked();
+    assert_eq!(*instruction_breakpoints.lock(), vec!["0x100C".to_string()]);
+
+    // Adapters that re-initialize forget the instruction breakpoints, so they're sent again.
+    instruction_breakpoints.lock().clear();
+    client
+        .fake_event(dap::messages::Events::Initialized(None))
+        .await;
+    cx.run_until_parked();
+    assert_eq!(*instruction_breakpoints.lock(), vec!["0x100C".to_string()]);
+
+    session.update(cx, |session, cx| {
+        session.toggle_instruction_breakpoint("0x100C".into(), cx);
+    });
+    cx.run_until_parked();
+    assert!(instruction_breakpoints.lock().is_empty());
+
+    // Stepping while the disassembly is focused steps by instruction.
+    disassembly_view.update_in(cx, |view, window, cx| {
+        window.focus(&view.focus_handle(cx), cx);
+    });
+    cx.dispatch_action(StepOver);
+    cx.run_until_parked();
+    assert_eq!(
+        *step_granularity.lock(),
+        Some(SteppingGranularity::Instruction)
+    );
+}
@@ -0,0 +1,194 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    DebugPanel,
+    recording_view::SessionRecordingView,
+    tests::{init_test, init_test_workspace, start_debug_session},
+};
+use dap::{
+    Scope, StackFrame, Variable,
+    requests::{Scopes, StackTrace, Threads, Variables},
+};
+use gpui::{BackgroundExecutor, TestAppContext, VisualTestContext};
+use project::{FakeFs, Fs, Project, debugger::recording::SessionRecording};
+use serde_json::json;
+use util::path;
+
+fn stopped_event(thread_id: u64) -> dap::messages::Events {
+    dap::messages::Events::Stopped(dap::StoppedEvent {
+        reason: dap::StoppedEventReason::Breakpoint,
+        description: None,
+        thread_id: Some(thread_id),
+        preserve_focus_hint: None,
+        text: None,
+        all_threads_stopped: None,
+        hit_breakpoint_ids: None,
+    })
+}
+
+fn output_event(output: &str) -> dap::messages::Events {
+    dap::messages::Events::Output(dap::OutputEvent {
+        category: Some(dap::OutputEventCategory::Stdout),
+        output: output.to_string(),
+        data: None,
+        variables_reference: None,
+        source: None,
+        line: None,
+        column: None,
+        group: None,
+        location_reference: None,
+    })
+}
+
+#[gpui::test]
+async fn test_record_and_replay_session(executor: BackgroundExecutor, cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+    fs.insert_tree(
+        path!("/project"),
+        json!({
+            "main.rs": "fn main() {\n    let x = 1;\n}\n",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), [path!("/project").as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    workspace
+        .update(cx, |workspace, window, cx| {
+            workspace.focus_panel::<DebugPanel>(window, cx);
+        })
+        .unwrap();
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+    let session = start_debug_session(&workspace, cx, |_| {}).unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client.on_request::<Threads, _>(move |_, _| {
+        Ok(dap::ThreadsResponse {
+            threads: vec![dap::Thread {
+                id: 1,
+                name: "main".into(),
+            }],
+        })
+    });
+    client.on_request::<StackTrace, _>(move |_, _| {
+        Ok(dap::StackTraceResponse {
+            stack_frames: vec![StackFrame {
+                id: 1,
+                name: "main".into(),
+                source: Some(dap::Source {
+                    name: Some("main.rs".into()),
+                    path: Some(path!("/project/main.rs").into()),
+                    source_reference: None,
+                    presentation_hint: None,
+                    origin: None,
+                    sources: None,
+                    adapter_data: None,
+                    checksums: None,
+                }),
+                line: 2,
+                column: 1,
+                end_line: None,
+                end_column: None,
+                can_restart: None,
+                instruction_pointer_reference: None,
+                module_id: None,
+                presentation_hint: None,
+            }],
+            total_frames: None,
+        })
+    });
+    client.on_request::<Scopes, _>(move |_, _| {
+        Ok(dap::ScopesResponse {
+            scopes: vec![Scope {
+                name: "Locals".into(),
+                presentation_hint: None,
+                variables_reference: 2,
+                named_variables: None,
+                indexed_variables: None,
+                expensive: false,
+                source: None,
+                line: None,
+                column: None,
+                end_line: None,
+                end_column: None,
+            }],
+        })
+    });
+    let stop_count = Arc::new(AtomicUsize::new(0));
+    client.on_request::<Variables, _>({
+        let stop_count = stop_count.clone();
+        move |_, _| {
+            let value = stop_count.load(Ordering::SeqCst);
+            Ok(dap::VariablesResponse {
+                variables: vec![Variable {
+                    name: "x".into(),
+                    value: value.to_string(),
+                    type_: None,
+                    presentation_hint: None,
+                    evaluate_name: None,
+                    variables_reference: 0,
+                    named_variables: None,
+                    indexed_variables: None,
+                    memory_reference: None,
+                    declaration_location_reference: None,
+                    value_location_reference: None,
+                }],
+            })
+        }
+    });
+
+    let recording_path = path!("/recordings/session.json");
+    session.update(cx, |session, cx| {
+        session.start_recording(recording_path.into(), fs.clone() as Arc<dyn Fs>, cx)
+    });
+
+    client.fake_event(output_event("starting\n")).await;
+    stop_count.fetch_add(1, Ordering::SeqCst);
+    client.fake_event(stopped_event(1)).await;
+    cx.run_until_parked();
+
+    client.fake_event(output_event("between stops\n")).await;
+    stop_count.fetch_add(1, Ordering::SeqCst);
+    client.fake_event(stopped_event(1)).await;
+    cx.run_until_parked();
+
+    session
+        .update(cx, |session, _| session.stop_recording())
+        .await;
+    cx.run_until_parked();
+    assert!(session.read_with(cx, |session, _| session.recording_path().is_none()));
+
+    let recording: SessionRecording =
+        serde_json::from_slice(&fs.read_file_sync(recording_path).unwrap()).unwrap();
+    assert_eq!(recording.stops.len(), 2);
+    for (ix, stop) in recording.stops.iter().enumerate() {
+        assert_eq!(stop.threads.len(), 1);
+        let stack_frames = &stop.threads[0].stack_frames;
+        assert_eq!(stack_frames.len(), 1);
+        assert_eq!(stack_frames[0].scopes[0].name, "Locals");
+        let values = stop
+            .variables(2)
+            .iter()
+            .map(|variable| variable.value.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(values, [(ix + 1).to_string()]);
+    }
+    assert_eq!(recording.stops[0].output[0].output, "starting\n");
+    assert_eq!(recording.stops[1].output[0].output, "between stops\n");
+
+    let view = cx.new(|cx| SessionRecordingView::new(recording_path.into(), recording, cx));
+    view.update(cx, |view, cx| {
+        assert_eq!(view.stop_ix(), 0);
+        assert_eq!(view.selected_frame(), Some((0, 0)));
+
+        view.select_stop(1, cx);
+        assert_eq!(view.stop_ix(), 1);
+        view.select_stop(5, cx);
+        assert_eq!(view.stop_ix(), 1);
+    });
+}
@@ -298,6 +298,9 @@ pub struct SpawnNearestTask {
 pub struct DiffClipboardWithSelectionData {
     pub clipboard_text: String,
     pub editor: Entity<Editor>,
+    /// Where the compared text comes from, shown in the diff's title instead
+    /// of "Clipboard".
+    pub text_label: Option<SharedString>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Default)]
@@ -370,6 +373,109 @@ pub struct InsertSnippet {
     pub snippet: Option<String>,
 }
 
+/// Plays a keyboard macro, either the last recorded one or one saved in the
+/// `keyboard_macros` setting.
+#[derive(PartialEq, Clone, Default, Debug, Deserialize, JsonSchema, Action)]
+#[action(namespace = editor)]
+#[serde(deny_unknown_fields)]
+pub struct PlayMacro {
+    /// The name of a macro saved in the `keyboard_macros` setting, or `None` to
+    /// play the last recorded macro.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// How many times to play the macro in a row.
+    #[serde(default)]
+    pub times: Option<usize>,
+    /// Where to play the macro.
+    #[serde(default)]
+    pub target: MacroPlaybackTarget,
+}
+
+/// Where a keyboard macro is played.
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MacroPlaybackTarget {
+    /// Plays the macro once with all the current selections.
+    #[default]
+    Selections,
+    /// Plays the macro separately at each cursor, starting from that cursor alone.
+    EachCursor,
+    /// Plays the macro separately on each match of the buffer search, starting
+    /// with the match selected.
+    EachSearchMatch,
+}
+
+/// Pipes the selections through a shell command, or the whole buffer when
+/// nothing is selected.
+#[derive(PartialEq, Clone, Default, Debug, Deserialize, JsonSchema, Action)]
+#[action(namespace = editor)]
+#[serde(deny_unknown_fields)]
+pub struct FilterSelectionsThroughCommand {
+    /// The shell command to run, or `None` to prompt for one.
+    #[serde(default)]
+    pub command: Option<String>,
+    /// What to do with the command's output.
+    #[serde(default)]
+    pub output: FilterCommandOutput,
+}
+
+/// What is done with the output of a command that selections are piped through.
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterCommandOutput {
+    /// Replaces each input with the command's output for it.
+    #[default]
+    Replace,
+    /// Inserts the command's output on the lines below each input.
+    InsertBelow,
+    /// Compares the newest selection's lines with the command's output for
+    /// them in a diff view.
+    Diff,
+}
+
+/// Evaluates each selection as an arithmetic or date expression, like
+/// `0xff & 0b1010`, `7 / 2` or `2024-01-31 + 2w`. Without a selection, the
+/// text before the cursor on its line is evaluated.
+#[derive(PartialEq, Clone, Default, Debug, Deserialize, JsonSchema, Action)]
+#[action(namespace = editor)]
+#[serde(deny_unknown_fields)]
+pub struct EvaluateSelections {
+    /// What to do with the result.
+    #[serde(default)]
+    pub output: EvaluationOutput,
+}
+
+/// What is done with the result of an evaluated expression.
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EvaluationOutput {
+    /// Replaces the expression with its result.
+    #[default]
+    Replace,
+    /// Appends ` = ` and the result after the expression.
+    Append,
+}
+
+/// Replaces each selection with the next number of a sequence, in the order
+/// the selections appear in the buffer.
+#[derive(PartialEq, Clone, Default, Debug, Deserialize, JsonSchema, Action)]
+#[action(namespace = editor)]
+#[serde(deny_unknown_fields)]
+pub struct InsertSequence {
+    /// The first number, like `1`, `001` or `0x0A`, whose radix, letter case
+    /// and zero padding are kept for the whole sequence. Defaults to the
+    /// number in the first selection, or to 1.
+    #[serde(default)]
+    pub start: Option<String>,
+    /// How much each number differs from the previous one. Defaults to 1.
+    #[serde(default)]
+    pub step: Option<i64>,
+    /// A template for the inserted text, where `{}` is replaced by each
+    /// number, like `item-{}`.
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
 actions!(
     debugger,
     [
@@ -402,8 +508,16 @@ actions!(
         ApplyAllDiffHunks,
         /// Applies the diff hunk at the current position.
         ApplyDiffHunk,
+        /// Applies the accepted edits of the rename preview.
+        ApplyRenamePreview,
         /// Deletes the character before the cursor.
         Backspace,
+        /// Moves the opening bracket of the enclosing bracketed syntax node after
+        /// its first child.
+        BarfBackward,
+        /// Moves the closing bracket of the enclosing bracketed syntax node before
+        /// its last child.
+        BarfForward,
         /// Shows git blame information for the current line.
         BlameHover,
         /// Cancels the current operation.
@@ -416,6 +530,8 @@ actions!(
         ClearFlycheck,
         /// Confirms the rename operation.
         ConfirmRename,
+        /// Confirms the rename operation, previewing its edits before applying them.
+        ConfirmRenameWithPreview,
         /// Confirms completion by inserting at cursor.
         ConfirmCompletionInsert,
         /// Confirms completion by replacing existing text.
@@ -486,6 +602,8 @@ actions!(
         DuplicateLineUp,
         /// Duplicates the current selection.
         DuplicateSelection,
+        /// Inserts a copy of the syntax node under the cursor after it.
+        DuplicateSyntaxNode,
         /// Expands all diff hunks in the editor.
         #[action(deprecated_aliases = ["editor::ExpandAllHunkDiffs"])]
         ExpandAllDiffHunks,
@@ -568,8 +686,14 @@ actions!(
         GoToImplementation,
         /// Goes to implementation in a split pane.
         GoToImplementationSplit,
+        /// Goes to the state of the buffer that was created right after the current
+        /// one, possibly on another branch of the undo tree.
+        GoToNewerUndoState,
         /// Goes to the next change in the file.
         GoToNextChange,
+        /// Goes to the state of the buffer that was created right before the current
+        /// one, possibly on another branch of the undo tree.
+        GoToOlderUndoState,
         /// Goes to the parent module of the current file.
         GoToParentModule,
         /// Goes to the previous change in the file.
@@ -620,6 +744,10 @@ actions!(
         MoveLineUp,
         /// Moves cursor right.
         MoveRight,
+        /// Swaps the syntax node under the cursor with its next sibling.
+        MoveSyntaxNodeDown,
+        /// Swaps the syntax node under the cursor with its previous sibling.
+        MoveSyntaxNodeUp,
         /// Moves cursor to the beginning of the document.
         MoveToBeginning,
         /// Moves cursor to the enclosing bracket.
@@ -717,6 +845,8 @@ actions!(
         RotateSelectionsForward,
         /// Runs flycheck diagnostics.
         RunFlycheck,
+        /// Saves the last recorded keyboard macro under a name in the settings.
+        SaveLastMacro,
         /// Scrolls the cursor to the bottom of the viewport.
         ScrollCursorBottom,
         /// Scrolls the cursor to the center of the viewport.
@@ -729,6 +859,14 @@ actions!(
         SelectAll,
         /// Selects all matches of the current selection.
         SelectAllMatches,
+        /// Extends the block selection down by one row.
+        SelectBlockDown,
+        /// Extends the block selection left by one column.
+        SelectBlockLeft,
+        /// Extends the block selection right by one column, past the end of the line if needed.
+        SelectBlockRight,
+        /// Extends the block selection up by one row.
+        SelectBlockUp,
         /// Selects to the start of the current excerpt.
         SelectToStartOfExcerpt,
         /// Selects to the start of the next excerpt.
@@ -797,6 +935,12 @@ actions!(
         SignatureHelpNext,
         /// Navigates to the previous signature in the signature help popup.
         SignatureHelpPrevious,
+        /// Moves the opening bracket of the enclosing bracketed syntax node before
+        /// the preceding syntax node.
+        SlurpBackward,
+        /// Moves the closing bracket of the enclosing bracketed syntax node after
+        /// the following syntax node.
+        SlurpForward,
         /// Sorts selected lines by length.
         SortLinesByLength,
         /// Sorts selected lines case-insensitively.
@@ -843,6 +987,8 @@ actions!(
         ToggleEditPrediction,
         /// Toggles line numbers display.
         ToggleLineNumbers,
+        /// Starts recording a keyboard macro, or stops the current recording.
+        ToggleMacroRecording,
         /// Toggles the minimap display.
         ToggleMinimap,
         /// Swaps the start and end of the current selection.
@@ -851,6 +997,8 @@ actions!(
         SetMark,
         /// Toggles relative line numbers display.
         ToggleRelativeLineNumbers,
+        /// Toggles whether the rename preview edits at the selections are accepted.
+        ToggleRenamePreviewEdits,
         /// Toggles diff display for selected hunks.
         #[action(deprecated_aliases = ["editor::ToggleHunkDiff"])]
         ToggleSelectedDiffHunks,
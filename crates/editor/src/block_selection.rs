@@ -0,0 +1,136 @@
+//! Rectangular selections whose columns may extend past the end of shorter
+//! lines, which are padded with spaces when text is inserted into them.
+use gpui::{Context, Window};
+use multi_buffer::MultiBufferRow;
+
+use crate::{
+    DisplayPoint, DisplayRow, Editor, HideMouseCursorOrigin, RowRangeExt, SelectionEffects,
+    actions::{SelectBlockDown, SelectBlockLeft, SelectBlockRight, SelectBlockUp},
+};
+
+impl Editor {
+    pub fn select_block_up(
+        &mut self,
+        _: &SelectBlockUp,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.extend_block_selection(-1, 0, window, cx);
+    }
+
+    pub fn select_block_down(
+        &mut self,
+        _: &SelectBlockDown,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.extend_block_selection(1, 0, window, cx);
+    }
+
+    pub fn select_block_left(
+        &mut self,
+        _: &SelectBlockLeft,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.extend_block_selection(0, -1, window, cx);
+    }
+
+    pub fn select_block_right(
+        &mut self,
+        _: &SelectBlockRight,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.extend_block_selection(0, 1, window, cx);
+    }
+
+    /// Moves the head of the block selection by the given number of rows and
+    /// columns, starting a new block from the newest selection if there is none.
+    fn extend_block_selection(
+        &mut self,
+        row_delta: i32,
+        column_delta: i32,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.mode.is_single_line() {
+            cx.propagate();
+            return;
+        }
+
+        self.hide_mouse_cursor(HideMouseCursorOrigin::MovementAction, cx);
+        let display_map = self.display_snapshot(cx);
+        let (tail, head) = match self.selections.block_selection() {
+            Some(block) => (block.tail(&display_map), block.head(&display_map)),
+            None => {
+                let newest = self.selections.newest_display(&display_map);
+                (newest.tail(), newest.head())
+            }
+        };
+
+        let max_row = display_map.max_point().row();
+        let mut row = DisplayRow(head.row().0.saturating_add_signed(row_delta)).min(max_row);
+        while row != head.row() && display_map.is_block_line(row) {
+            row = DisplayRow(row.0.saturating_add_signed(row_delta)).min(max_row);
+        }
+        let column = head.column().saturating_add_signed(column_delta);
+
+        self.change_selections(SelectionEffects::default(), window, cx, |s| {
+            s.select_block(tail, DisplayPoint::new(row, column), false);
+        });
+    }
+
+    /// Pads the lines of the block selection that end before its columns with
+    /// spaces, and selects the block again so that every line gets a selection
+    /// within it. Lines are padded up to the block's first column, or up to its
+    /// last one when `to_end` is set.
+    ///
+    /// Returns whether there was a block selection to pad.
+    pub fn pad_block_selection(
+        &mut self,
+        to_end: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let Some(block) = self.selections.block_selection().cloned() else {
+            return false;
+        };
+        let display_map = self.display_snapshot(cx);
+        let columns = block.columns();
+        let target_column = if to_end { columns.end } else { columns.start };
+
+        let buffer = display_map.buffer_snapshot();
+        let edits = block
+            .rows(&display_map)
+            .iter_rows()
+            .filter(|row| !display_map.is_block_line(*row))
+            .filter_map(|row| {
+                let line_len = display_map.line_len(row);
+                if line_len >= target_column {
+                    return None;
+                }
+                // Only pad at the end of buffer lines, not within soft-wrapped ones.
+                let line_end = DisplayPoint::new(row, line_len).to_point(&display_map);
+                if line_end.column != buffer.line_len(MultiBufferRow(line_end.row)) {
+                    return None;
+                }
+                let padding = " ".repeat((target_column - line_len) as usize);
+                Some((line_end..line_end, padding))
+            })
+            .collect::<Vec<_>>();
+
+        if !edits.is_empty() {
+            let tail = block.tail(&display_map);
+            let head = block.head(&display_map);
+            self.transact(window, cx, |this, window, cx| {
+                this.buffer
+                    .update(cx, |buffer, cx| buffer.edit(edits, None, cx));
+                this.change_selections(SelectionEffects::no_scroll(), window, cx, |s| {
+                    s.select_block(tail, head, false);
+                });
+            });
+        }
+        true
+    }
+}
@@ -0,0 +1,261 @@
+//! A bounded history of the texts copied or cut in editors, which can be
+//! browsed and pasted again later.
+use std::{
+    collections::VecDeque,
+    sync::LazyLock,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Result;
+use db::kvp::KeyValueStore;
+use gpui::{App, ClipboardEntry, ClipboardItem, Context, Global, Subscription, Task, Window};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsStore};
+use util::ResultExt as _;
+
+use crate::{ClipboardSelection, Editor, EditorSettings};
+
+const CLIPBOARD_HISTORY_KEY: &str = "clipboard_history";
+
+/// How long the history must stay unchanged before it is persisted, so that
+/// copying repeatedly writes it once.
+const PERSIST_DEBOUNCE: Duration = Duration::from_secs(1);
+
+/// Matches texts that look like private keys, access tokens or password
+/// assignments, which are not kept in the history unless configured otherwise.
+static SECRET_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"-----BEGIN [A-Z ]*PRIVATE KEY-----",
+        r"|\bAKIA[0-9A-Z]{16}\b",
+        r"|\bgh[pousr]_[A-Za-z0-9]{36,}",
+        r"|\bgithub_pat_[A-Za-z0-9_]{22,}",
+        r"|\bsk-[A-Za-z0-9_-]{20,}",
+        r"|\bxox[abprs]-[A-Za-z0-9-]{10,}",
+        r"|\beyJ[A-Za-z0-9_-]{10,}\.eyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}",
+        r#"|(?i)\b(password|passwd|secret|api[_-]?key|access[_-]?token|auth[_-]?token)\b["']?\s*[:=]\s*["']?[^\s"']{8,}"#,
+    ))
+    .unwrap()
+});
+
+/// A text copied or cut in an editor.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ClipboardHistoryEntry {
+    pub text: String,
+    /// How the text is split between the selections it was copied from, so
+    /// that it is pasted back one selection per cursor.
+    pub selections: Option<Vec<ClipboardSelection>>,
+    /// The name of the language of the buffer the text was copied from.
+    pub language: Option<String>,
+    pub copied_at: SystemTime,
+}
+
+impl ClipboardHistoryEntry {
+    fn to_clipboard_item(&self) -> ClipboardItem {
+        match &self.selections {
+            Some(selections) => {
+                ClipboardItem::new_string_with_json_metadata(self.text.clone(), selections.clone())
+            }
+            None => ClipboardItem::new_string(self.text.clone()),
+        }
+    }
+}
+
+/// The texts copied or cut in all editors, most recent first.
+#[derive(Default)]
+pub struct ClipboardHistory {
+    entries: VecDeque<ClipboardHistoryEntry>,
+    loaded_persisted_entries: bool,
+    /// The value of the `persist` setting the history was last updated for.
+    persist: Option<bool>,
+    /// Whether the history changed since it was last persisted.
+    dirty: bool,
+    persist_task: Option<Task<()>>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl Global for ClipboardHistory {}
+
+pub(crate) fn init(cx: &mut App) {
+    let subscriptions = vec![
+        cx.observe_global::<SettingsStore>(ClipboardHistory::settings_changed),
+        cx.on_app_quit(ClipboardHistory::flush),
+    ];
+    cx.set_global(ClipboardHistory {
+        _subscriptions: subscriptions,
+        ..ClipboardHistory::default()
+    });
+    ClipboardHistory::settings_changed(cx);
+}
+
+impl ClipboardHistory {
+    /// Texts longer than this, in bytes, are not kept in the history.
+    pub const MAX_ENTRY_LEN: usize = 64 * 1024;
+
+    /// Returns the entries of the history, most recent first.
+    pub fn entries(cx: &mut App) -> Vec<ClipboardHistoryEntry> {
+        Self::load_persisted_entries(cx);
+        cx.global::<Self>().entries.iter().cloned().collect()
+    }
+
+    pub fn clear(cx: &mut App) {
+        Self::load_persisted_entries(cx);
+        cx.global_mut::<Self>().entries.clear();
+        Self::persist(cx);
+    }
+
+    fn push(entry: ClipboardHistoryEntry, cx: &mut App) {
+        let settings = EditorSettings::get_global(cx).clipboard_history;
+        if entry.text.trim().is_empty()
+            || entry.text.len() > Self::MAX_ENTRY_LEN
+            || settings.exclude_secrets && SECRET_REGEX.is_match(&entry.text)
+        {
+            return;
+        }
+
+        Self::load_persisted_entries(cx);
+        let history = cx.global_mut::<Self>();
+        history
+            .entries
+            .retain(|existing| existing.text != entry.text);
+        history.entries.push_front(entry);
+        history.entries.truncate(settings.max_entries);
+        Self::persist(cx);
+    }
+
+    fn load_persisted_entries(cx: &mut App) {
+        if cx.default_global::<Self>().loaded_persisted_entries
+            || !EditorSettings::get_global(cx).clipboard_history.persist
+        {
+            return;
+        }
+        let persisted_entries = KeyValueStore::global(cx)
+            .read_kvp(CLIPBOARD_HISTORY_KEY)
+            .log_err()
+            .flatten()
+            .and_then(|json| serde_json::from_str::<Vec<ClipboardHistoryEntry>>(&json).log_err())
+            .unwrap_or_default();
+        let max_entries = EditorSettings::get_global(cx).clipboard_history.max_entries;
+        let history = cx.global_mut::<Self>();
+        history.loaded_persisted_entries = true;
+        for entry in persisted_entries {
+            if !history
+                .entries
+                .iter()
+                .any(|existing| existing.text == entry.text)
+            {
+                history.entries.push_back(entry);
+            }
+        }
+        history.entries.truncate(max_entries);
+    }
+
+    /// Persists the history once it stopped changing for a moment.
+    fn persist(cx: &mut App) {
+        if !EditorSettings::get_global(cx).clipboard_history.persist {
+            return;
+        }
+        let task = cx.spawn(async move |cx| {
+            cx.background_executor().timer(PERSIST_DEBOUNCE).await;
+            cx.update(Self::write).await.log_err();
+        });
+        let history = cx.global_mut::<Self>();
+        history.dirty = true;
+        history.persist_task = Some(task);
+    }
+
+    /// Writes the history to the database, serializing it in the background.
+    fn write(cx: &mut App) -> Task<Result<()>> {
+        let history = cx.global_mut::<Self>();
+        history.dirty = false;
+        let entries = history.entries.clone();
+        let kvp = KeyValueStore::global(cx);
+        cx.background_spawn(async move {
+            let json = serde_json::to_string(&entries)?;
+            kvp.write_kvp(CLIPBOARD_HISTORY_KEY.into(), json).await
+        })
+    }
+
+    /// Writes the changes that are waiting to be persisted before quitting.
+    fn flush(cx: &mut App) -> impl Future<Output = ()> + use<> {
+        let history = cx.global_mut::<Self>();
+        history.persist_task = None;
+        let write = history.dirty.then(|| Self::write(cx));
+        async move {
+            if let Some(write) = write {
+                write.await.log_err();
+            }
+        }
+    }
+
+    /// Deletes the persisted history when persistence gets disabled, and
+    /// persists the current one when it gets enabled.
+    fn settings_changed(cx: &mut App) {
+        let persist = EditorSettings::get_global(cx).clipboard_history.persist;
+        let history = cx.global_mut::<Self>();
+        let previous = history.persist.replace(persist);
+        if previous == Some(persist) {
+            return;
+        }
+
+        if persist {
+            if previous.is_some() {
+                Self::load_persisted_entries(cx);
+                Self::persist(cx);
+            }
+        } else {
+            history.persist_task = None;
+            history.dirty = false;
+            history.loaded_persisted_entries = false;
+            let kvp = KeyValueStore::global(cx);
+            db::write_and_log(cx, move || async move {
+                kvp.delete_kvp(CLIPBOARD_HISTORY_KEY.into()).await
+            });
+        }
+    }
+}
+
+impl Editor {
+    /// Adds a text copied or cut in this editor to the clipboard history.
+    pub(crate) fn record_clipboard_history(&self, item: &ClipboardItem, cx: &mut Context<Self>) {
+        let Some(ClipboardEntry::String(string)) = item.entries().first() else {
+            return;
+        };
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let head = self.selections.newest_anchor().head();
+        let language = snapshot
+            .language_at(head)
+            .map(|language| language.name().to_string());
+        ClipboardHistory::push(
+            ClipboardHistoryEntry {
+                text: string.text().to_string(),
+                selections: string.metadata_json::<Vec<ClipboardSelection>>(),
+                language,
+                copied_at: SystemTime::now(),
+            },
+            cx,
+        );
+    }
+
+    /// Pastes an entry of the clipboard history, which then becomes the most
+    /// recent entry and the content of the clipboard.
+    pub fn paste_clipboard_history_entry(
+        &mut self,
+        entry: &ClipboardHistoryEntry,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.read_only(cx) {
+            return;
+        }
+        cx.write_to_clipboard(entry.to_clipboard_item());
+        ClipboardHistory::push(
+            ClipboardHistoryEntry {
+                copied_at: SystemTime::now(),
+                ..entry.clone()
+            },
+            cx,
+        );
+        self.do_paste(&entry.text, entry.selections.clone(), true, window, cx);
+    }
+}
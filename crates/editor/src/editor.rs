@@ -13,14 +13,18 @@
 //! If you're looking to improve Vim mode, you should check out Vim crate that wraps Editor and overrides its behavior.
 pub mod actions;
 pub mod blink_manager;
+mod block_selection;
 mod bracket_colorization;
 mod clangd_ext;
+mod clipboard_history;
 pub mod code_context_menus;
 pub mod display_map;
 mod document_colors;
 mod document_symbols;
 mod editor_settings;
 mod element;
+mod expression_evaluation;
+mod filter_command;
 mod folding_ranges;
 mod git;
 mod highlight_matching_bracket;
@@ -30,11 +34,13 @@ mod indent_guides;
 mod inlays;
 pub mod items;
 mod jsx_tag_auto_close;
+mod keyboard_macros;
 mod linked_editing_ranges;
 mod lsp_ext;
 mod mouse_context_menu;
 pub mod movement;
 mod persistence;
+mod rename_preview;
 mod runnables;
 mod rust_analyzer_ext;
 pub mod scroll;
@@ -42,6 +48,7 @@ mod selections_collection;
 pub mod semantic_tokens;
 mod split;
 pub mod split_editor_view;
+mod structural_editing;
 
 #[cfg(test)]
 mod code_completion_tests;
@@ -54,6 +61,7 @@ mod signature_help;
 pub mod test;
 
 pub(crate) use actions::*;
+pub use clipboard_history::{ClipboardHistory, ClipboardHistoryEntry};
 pub use display_map::{
     ChunkRenderer, ChunkRendererContext, DisplayPoint, FoldPlaceholder, HighlightKey,
     SemanticTokenHighlight,
@@ -68,6 +76,7 @@ pub use element::{
     CursorLayout, EditorElement, HighlightedRange, HighlightedRangeLine, PointForPosition,
     render_breadcrumb_text,
 };
+pub use expression_evaluation::format_number_like;
 pub use git::blame::BlameRenderer;
 pub use hover_popover::hover_markdown_style;
 pub use inlays::Inlay;
@@ -80,6 +89,7 @@ pub use multi_buffer::{
     MultiBufferOffset, MultiBufferOffsetUtf16, MultiBufferSnapshot, PathKey, RowInfo, ToOffset,
     ToPoint,
 };
+pub use rename_preview::RenamePreview;
 pub use split::{SplittableEditor, ToggleSplitDiff};
 pub use split_editor_view::SplitEditorView;
 pub use text::Bias;
@@ -106,6 +116,7 @@ use edit_prediction_types::{
 };
 use editor_settings::{GoToDefinitionFallback, Minimap as MinimapSettings};
 use element::{LineWithInvisibles, PositionMap, layout_line};
+use fs::Fs;
 use futures::{
     FutureExt,
     future::{self, Shared, join},
@@ -159,7 +170,7 @@ use project::{
     BreakpointWithPosition, CodeAction, Completion, CompletionDisplayOptions, CompletionIntent,
     CompletionResponse, CompletionSource, DisableAiSettings, DocumentHighlight, InlayHint, InlayId,
     InvalidationStrategy, Location, LocationLink, LspAction, PrepareRenameResponse, Project,
-    ProjectItem, ProjectPath, ProjectTransaction,
+    ProjectItem, ProjectPath, ProjectTransaction, RenameEdits,
     debugger::{
         breakpoint_store::{
             Breakpoint, BreakpointEditAction, BreakpointSessionState, BreakpointState,
@@ -202,7 +213,7 @@ use std::{
     time::{Duration, Instant},
 };
 use task::TaskVariables;
-use text::{BufferId, FromAnchor, OffsetUtf16, Rope, ToOffset as _, ToPoint as _};
+use text::{BufferId, FromAnchor, OffsetUtf16, Rope, ToOffset as _, ToPoint as _, UndoStateId};
 use theme::{
     AccentColors, ActiveTheme, GlobalTheme, PlayerColor, StatusColors, SyntaxTheme, Theme,
 };
@@ -233,6 +244,7 @@ use crate::{
         InlineValueCache,
         inlay_hints::{LspInlayHintData, inlay_hint_settings},
     },
+    rename_preview::should_preview_rename,
     runnables::{ResolvedTasks, RunnableData, RunnableTasks},
     scroll::{ScrollOffset, ScrollPixelOffset},
     selections_collection::resolve_selections_wrapping_blocks,
@@ -337,12 +349,19 @@ pub fn init(cx: &mut App) {
     cx.set_global(GlobalBlameRenderer(Arc::new(())));
     cx.set_global(breadcrumbs::RenderBreadcrumbText(render_breadcrumb_text));
 
+    clipboard_history::init(cx);
+    filter_command::init(cx);
+    keyboard_macros::init(cx);
+
     workspace::register_project_item::<Editor>(cx);
     workspace::FollowableViewRegistry::register::<Editor>(cx);
     workspace::register_serializable_item::<Editor>(cx);
 
     cx.observe_new(
-        |workspace: &mut Workspace, _: Option<&mut Window>, _cx: &mut Context<Workspace>| {
+        |workspace: &mut Workspace, window: Option<&mut Window>, cx: &mut Context<Workspace>| {
+            if let Some(window) = window {
+                rename_preview::register(workspace, window, cx);
+            }
             workspace.register_action(Editor::new_file);
             workspace.register_action(Editor::new_file_split);
             workspace.register_action(Editor::new_file_vertical);
@@ -1321,6 +1340,7 @@ pub struct Editor {
     _scroll_cursor_center_top_bottom_task: Task<()>,
     serialize_selections: Task<()>,
     serialize_folds: Task<()>,
+    serialize_undo_history: Task<()>,
     mouse_cursor_hidden: bool,
     minimap: Option<Entity<Self>>,
     hide_mouse_mode: HideMouseMode,
@@ -2577,6 +2597,7 @@ impl Editor {
             toggle_fold_multiple_buffers: Task::ready(()),
             serialize_selections: Task::ready(()),
             serialize_folds: Task::ready(()),
+            serialize_undo_history: Task::ready(()),
             text_style_refinement: None,
             load_diff_task: load_uncommitted_diff,
             temporary_diff_override: false,
@@ -3936,6 +3957,130 @@ impl Editor {
         });
     }
 
+    /// Persists the undo history of a saved buffer, so that it can be restored when
+    /// the file is reopened with the same contents.
+    fn save_undo_history(&mut self, cx: &mut Context<Self>) {
+        let settings = EditorSettings::get_global(cx).persistent_undo;
+        if !settings.enabled || !self.mode.is_full() {
+            return;
+        }
+        let Some(buffer) = self.buffer().read(cx).as_singleton() else {
+            return;
+        };
+        let buffer = buffer.read(cx);
+        let Some(abs_path) =
+            project::File::from_dyn(buffer.file()).map(|file| Arc::<Path>::from(file.abs_path(cx)))
+        else {
+            return;
+        };
+        if buffer.is_dirty() {
+            return;
+        }
+        let Some(replay) = buffer.undo_history_replay() else {
+            return;
+        };
+
+        let snapshot = buffer.text_snapshot();
+        let max_size = (settings.max_size_kb as usize).saturating_mul(1024);
+        let db = EditorDb::global(cx);
+        self.serialize_undo_history = cx.background_spawn(async move {
+            let content_hash = persistence::undo_history_content_hash(&snapshot);
+            let result = match persistence::serialize_undo_history(replay.build(), max_size) {
+                Some(history) => db.save_undo_history(&abs_path, content_hash, history).await,
+                None => db.delete_undo_history(&abs_path).await,
+            };
+            result
+                .with_context(|| format!("persisting undo history for {abs_path:?}"))
+                .log_err();
+        });
+    }
+
+    /// Restores the undo history persisted for the buffer's file, provided that the
+    /// buffer has no history of its own and the file hasn't changed since.
+    ///
+    /// Only buffers of local projects that aren't shared are restored, as the
+    /// replayed history isn't broadcast to collaborators.
+    pub(crate) fn restore_undo_history(&mut self, cx: &mut Context<Self>) {
+        if !EditorSettings::get_global(cx).persistent_undo.enabled || !self.mode.is_full() {
+            return;
+        }
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        if !Self::is_private_local_project(&project, cx) {
+            return;
+        }
+        let Some(buffer) = self.buffer().read(cx).as_singleton() else {
+            return;
+        };
+        let buffer_ref = buffer.read(cx);
+        let Some(abs_path) = project::File::from_dyn(buffer_ref.file())
+            .map(|file| Arc::<Path>::from(file.abs_path(cx)))
+        else {
+            return;
+        };
+        if buffer_ref.is_dirty()
+            || buffer_ref.peek_undo_stack().is_some()
+            || buffer_ref.peek_redo_stack().is_some()
+        {
+            return;
+        }
+
+        let snapshot = buffer_ref.text_snapshot();
+        let version = snapshot.version().clone();
+        let buffer = buffer.downgrade();
+        let db = EditorDb::global(cx);
+        cx.spawn(async move |_, cx| {
+            let history = cx
+                .background_spawn(async move {
+                    let Some((content_hash, history)) = db.get_undo_history(&abs_path)? else {
+                        return anyhow::Ok(None);
+                    };
+                    if content_hash != persistence::undo_history_content_hash(&snapshot) {
+                        // The file was changed outside of the editor since the history was saved.
+                        db.delete_undo_history(&abs_path).await?;
+                        return Ok(None);
+                    }
+                    Ok(Some(persistence::deserialize_undo_history(&history)?))
+                })
+                .await?;
+            if let Some(history) = history {
+                buffer.update(cx, |buffer, cx| {
+                    // The project may have been shared in the meantime.
+                    if buffer.version() == version && Self::is_private_local_project(&project, cx) {
+                        buffer.restore_undo_history(&history, cx);
+                    }
+                })?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn is_private_local_project(project: &Entity<Project>, cx: &App) -> bool {
+        let project = project.read(cx);
+        project.is_local() && !project.is_shared()
+    }
+
+    /// Forgets the persisted undo histories of files that were deleted, or that
+    /// haven't been saved in a while.
+    pub(crate) fn prune_undo_histories(cx: &App) -> Task<Result<()>> {
+        let db = EditorDb::global(cx);
+        let fs = <dyn Fs>::try_global(cx);
+        cx.background_spawn(async move {
+            db.delete_stale_undo_histories("-30 days").await?;
+            let Some(fs) = fs else {
+                return Ok(());
+            };
+            for path in db.get_undo_history_paths()? {
+                if !fs.is_file(&path).await {
+                    db.delete_undo_history(&path).await?;
+                }
+            }
+            Ok(())
+        })
+    }
+
     pub fn sync_selections(
         &mut self,
         other: Entity<Editor>,
@@ -4534,6 +4679,18 @@ impl Editor {
             }
         };
 
+        // Selecting with the mouse keeps the rectangle under the pointer, even
+        // where it extends past the end of shorter lines. Those lines only get
+        // a selection when the rectangle doesn't select text on any line.
+        if matches!(columnar_state, ColumnarSelectionState::FromMouse { .. }) {
+            let head = DisplayPoint::new(head.row(), goal_column);
+            self.change_selections(SelectionEffects::no_scroll(), window, cx, |s| {
+                s.select_block(tail, head, true);
+            });
+            cx.notify();
+            return;
+        }
+
         let start_row = cmp::min(tail.row(), head.row());
         let end_row = cmp::max(tail.row(), head.row());
         let start_column = cmp::min(tail.column(), goal_column);
@@ -4543,10 +4700,7 @@ impl Editor {
         let selection_ranges = (start_row.0..=end_row.0)
             .map(DisplayRow)
             .filter_map(|row| {
-                if (matches!(columnar_state, ColumnarSelectionState::FromMouse { .. })
-                    || start_column <= display_map.line_len(row))
-                    && !display_map.is_block_line(row)
-                {
+                if start_column <= display_map.line_len(row) && !display_map.is_block_line(row) {
                     let start = display_map
                         .clip_point(DisplayPoint::new(row, start_column), Bias::Left)
                         .to_point(display_map);
@@ -4567,23 +4721,8 @@ impl Editor {
             return;
         }
 
-        let ranges = match columnar_state {
-            ColumnarSelectionState::FromMouse { .. } => {
-                let mut non_empty_ranges = selection_ranges
-                    .iter()
-                    .filter(|selection_range| selection_range.start != selection_range.end)
-                    .peekable();
-                if non_empty_ranges.peek().is_some() {
-                    non_empty_ranges.cloned().collect()
-                } else {
-                    selection_ranges
-                }
-            }
-            _ => selection_ranges,
-        };
-
         self.change_selections(SelectionEffects::no_scroll(), window, cx, |s| {
-            s.select_ranges(ranges);
+            s.select_ranges(selection_ranges);
         });
         cx.notify();
     }
@@ -4750,6 +4889,7 @@ impl Editor {
         self.hide_mouse_cursor(HideMouseCursorOrigin::TypingAction, cx);
 
         self.unfold_buffers_with_selections(cx);
+        self.pad_block_selection(false, window, cx);
 
         let selections = self.selections.all_adjusted(&self.display_snapshot(cx));
         let mut bracket_inserted = false;
@@ -7213,15 +7353,19 @@ impl Editor {
         // avoid opening a new editor to display them.
 
         if let [(buffer, transaction)] = &*entries {
-            let cursor_excerpt = editor.update(cx, |editor, cx| {
-                let snapshot = editor.buffer().read(cx).snapshot(cx);
-                let head = editor.selections.newest_anchor().head();
-                let (buffer_snapshot, excerpt_range) = snapshot.excerpt_containing(head..head)?;
-                if buffer_snapshot.remote_id() != buffer.read(cx).remote_id() {
-                    return None;
-                }
-                Some(excerpt_range)
-            })?;
+            let cursor_excerpt = editor
+                .update(cx, |editor, cx| {
+                    let snapshot = editor.buffer().read(cx).snapshot(cx);
+                    let head = editor.selections.newest_anchor().head();
+                    let (buffer_snapshot, excerpt_range) =
+                        snapshot.excerpt_containing(head..head)?;
+                    if buffer_snapshot.remote_id() != buffer.read(cx).remote_id() {
+                        return None;
+                    }
+                    Some(excerpt_range)
+                })
+                .ok()
+                .flatten();
 
             if let Some(excerpt_range) = cursor_excerpt {
                 let all_edits_within_excerpt = buffer.read_with(cx, |buffer, _| {
@@ -13918,6 +14062,7 @@ impl Editor {
     pub fn cut(&mut self, _: &Cut, window: &mut Window, cx: &mut Context<Self>) {
         self.hide_mouse_cursor(HideMouseCursorOrigin::TypingAction, cx);
         let item = self.cut_common(true, window, cx);
+        self.record_clipboard_history(&item, cx);
         cx.write_to_clipboard(item);
     }
 
@@ -13934,6 +14079,7 @@ impl Editor {
             });
         });
         let item = self.cut_common(false, window, cx);
+        self.record_clipboard_history(&item, cx);
         cx.set_global(KillRing(item))
     }
 
@@ -14058,10 +14204,11 @@ impl Editor {
             ));
         }
 
-        cx.write_to_clipboard(ClipboardItem::new_string_with_json_metadata(
-            text,
-            clipboard_selections,
-        ));
+        drop(buffer);
+
+        let item = ClipboardItem::new_string_with_json_metadata(text, clipboard_selections);
+        self.record_clipboard_history(&item, cx);
+        cx.write_to_clipboard(item);
     }
 
     pub fn do_paste(
@@ -14081,6 +14228,7 @@ impl Editor {
         let clipboard_text = Cow::Borrowed(text.as_str());
 
         self.transact(window, cx, |this, window, cx| {
+            this.pad_block_selection(false, window, cx);
             let had_active_edit_prediction = this.has_active_edit_prediction();
             let display_map = this.display_snapshot(cx);
             let old_selections = this.selections.all::<MultiBufferOffset>(&display_map);
@@ -14282,6 +14430,7 @@ impl Editor {
             Box::new(DiffClipboardWithSelectionData {
                 clipboard_text,
                 editor: cx.entity(),
+                text_label: None,
             }),
             cx,
         );
@@ -14337,6 +14486,72 @@ impl Editor {
         }
     }
 
+    /// Moves a singleton buffer to the given state of its undo tree, selecting the
+    /// first change between the two states.
+    pub fn go_to_undo_state(
+        &mut self,
+        id: UndoStateId,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.read_only(cx) {
+            return;
+        }
+        let Some(buffer) = self.buffer.read(cx).as_singleton() else {
+            return;
+        };
+
+        let old_version = buffer.read(cx).version();
+        if !buffer.update(cx, |buffer, cx| buffer.go_to_undo_state(id, cx)) {
+            return;
+        }
+        let first_change = buffer
+            .read(cx)
+            .edits_since::<usize>(&old_version)
+            .next()
+            .map(|edit| MultiBufferOffset(edit.new.start));
+        if let Some(offset) = first_change {
+            self.change_selections(SelectionEffects::no_scroll(), window, cx, |s| {
+                s.select_ranges([offset..offset]);
+            });
+        }
+        self.request_autoscroll(Autoscroll::fit(), cx);
+        self.unmark_text(window, cx);
+        self.refresh_edit_prediction(true, false, window, cx);
+    }
+
+    pub fn go_to_older_undo_state(
+        &mut self,
+        _: &GoToOlderUndoState,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let older = self
+            .buffer
+            .read(cx)
+            .as_singleton()
+            .and_then(|buffer| buffer.read(cx).undo_tree().older());
+        if let Some(id) = older {
+            self.go_to_undo_state(id, window, cx);
+        }
+    }
+
+    pub fn go_to_newer_undo_state(
+        &mut self,
+        _: &GoToNewerUndoState,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let newer = self
+            .buffer
+            .read(cx)
+            .as_singleton()
+            .and_then(|buffer| buffer.read(cx).undo_tree().newer());
+        if let Some(id) = newer {
+            self.go_to_undo_state(id, window, cx);
+        }
+    }
+
     pub fn redo(&mut self, _: &Redo, window: &mut Window, cx: &mut Context<Self>) {
         if self.read_only(cx) {
             return;
@@ -19288,6 +19503,24 @@ impl Editor {
         _: &ConfirmRename,
         window: &mut Window,
         cx: &mut Context<Self>,
+    ) -> Option<Task<Result<()>>> {
+        self.do_confirm_rename(false, window, cx)
+    }
+
+    pub fn confirm_rename_with_preview(
+        &mut self,
+        _: &ConfirmRenameWithPreview,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<Task<Result<()>>> {
+        self.do_confirm_rename(true, window, cx)
+    }
+
+    fn do_confirm_rename(
+        &mut self,
+        force_preview: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
     ) -> Option<Task<Result<()>>> {
         let rename = self.take_rename(false, window, cx)?;
         let workspace = self.workspace()?.downgrade();
@@ -19306,23 +19539,36 @@ impl Editor {
         let old_name = rename.old_name;
         let new_name = rename.editor.read(cx).text(cx);
 
-        let rename = self.semantics_provider.as_ref()?.perform_rename(
-            &buffer,
-            start,
-            new_name.clone(),
-            cx,
-        )?;
+        let semantics_provider = self.semantics_provider.clone()?;
+        let rename_edits = semantics_provider.rename_edits(&buffer, start, new_name.clone(), cx)?;
 
         Some(cx.spawn_in(window, async move |editor, cx| {
-            let project_transaction = rename.await?;
-            Self::open_project_transaction(
-                &editor,
-                workspace,
-                project_transaction,
-                format!("Rename: {} → {}", old_name, new_name),
-                cx,
-            )
-            .await?;
+            let title = format!("Rename: {} → {}", old_name, new_name);
+            let project_transaction = match rename_edits.await? {
+                RenameEdits::Edits(edits) => {
+                    if cx.update(|_, cx| should_preview_rename(&edits, force_preview, cx))? {
+                        workspace.update_in(cx, |workspace, window, cx| {
+                            RenamePreview::deploy(
+                                editor.clone(),
+                                edits,
+                                title.clone(),
+                                workspace,
+                                window,
+                                cx,
+                            );
+                        })?;
+                        None
+                    } else {
+                        Some(cx.update(|_, cx| edits.apply(cx))?)
+                    }
+                }
+                // The rename creates, renames or deletes files, which can't be previewed.
+                RenameEdits::Applied(project_transaction) => Some(project_transaction),
+            };
+            if let Some(project_transaction) = project_transaction {
+                Self::open_project_transaction(&editor, workspace, project_transaction, title, cx)
+                    .await?;
+            }
 
             editor.update(cx, |editor, cx| {
                 editor.refresh_document_highlights(cx);
@@ -24362,7 +24608,10 @@ impl Editor {
                 cx.notify();
             }
             multi_buffer::Event::DirtyChanged => cx.emit(EditorEvent::DirtyChanged),
-            multi_buffer::Event::Saved => cx.emit(EditorEvent::Saved),
+            multi_buffer::Event::Saved => {
+                self.save_undo_history(cx);
+                cx.emit(EditorEvent::Saved);
+            }
             multi_buffer::Event::FileHandleChanged
             | multi_buffer::Event::Reloaded
             | multi_buffer::Event::BufferDiffChanged => cx.emit(EditorEvent::TitleChanged),
@@ -27031,6 +27280,16 @@ pub trait SemanticsProvider {
         cx: &mut App,
     ) -> Task<Result<Option<Range<text::Anchor>>>>;
 
+    /// Returns the edits of a rename without applying them, unless the rename
+    /// also creates, renames or deletes files.
+    fn rename_edits(
+        &self,
+        buffer: &Entity<Buffer>,
+        position: text::Anchor,
+        new_name: String,
+        cx: &mut App,
+    ) -> Option<Task<Result<RenameEdits>>>;
+
     fn perform_rename(
         &self,
         buffer: &Entity<Buffer>,
@@ -27652,6 +27911,19 @@ impl SemanticsProvider for WeakEntity<Project> {
         })
     }
 
+    fn rename_edits(
+        &self,
+        buffer: &Entity<Buffer>,
+        position: text::Anchor,
+        new_name: String,
+        cx: &mut App,
+    ) -> Option<Task<Result<RenameEdits>>> {
+        self.update(cx, |project, cx| {
+            project.rename_edits(buffer.clone(), position, new_name, cx)
+        })
+        .ok()
+    }
+
     fn perform_rename(
         &self,
         buffer: &Entity<Buffer>,
@@ -1,13 +1,14 @@
 use core::num;
 
+use collections::HashMap;
 use gpui::App;
 use language::CursorShape;
 use project::project_settings::DiagnosticSeverity;
 pub use settings::{
     CompletionDetailAlignment, CurrentLineHighlight, DelayMs, DiffViewStyle, DisplayIn,
     DocumentColorsRenderMode, DoubleClickInMultibuffer, GoToDefinitionFallback, HideMouseMode,
-    MinimapThumb, MinimapThumbBorder, MultiCursorModifier, ScrollBeyondLastLine,
-    ScrollbarDiagnostics, SeedQuerySetting, ShowMinimap, SnippetSortOrder,
+    KeyboardMacroStep, MinimapThumb, MinimapThumbBorder, MultiCursorModifier, RenamePreviewMode,
+    ScrollBeyondLastLine, ScrollbarDiagnostics, SeedQuerySetting, ShowMinimap, SnippetSortOrder,
 };
 use settings::{RegisterSetting, RelativeLineNumbers, Settings};
 use ui::scrollbars::ShowScrollbar;
@@ -55,6 +56,10 @@ pub struct EditorSettings {
     pub diagnostics_max_severity: Option<DiagnosticSeverity>,
     pub inline_code_actions: bool,
     pub drag_and_drop_selection: DragAndDropSelection,
+    pub persistent_undo: PersistentUndo,
+    pub keyboard_macros: HashMap<String, Vec<KeyboardMacroStep>>,
+    pub clipboard_history: ClipboardHistory,
+    pub rename_preview: RenamePreviewMode,
     pub lsp_document_colors: DocumentColorsRenderMode,
     pub minimum_contrast_for_highlights: f32,
     pub completion_menu_scrollbar: ShowScrollbar,
@@ -161,6 +166,40 @@ pub struct DragAndDropSelection {
     pub delay: DelayMs,
 }
 
+/// Whether to keep the undo history of files across restarts.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct PersistentUndo {
+    /// When true, the undo and redo history of a file is saved along with it, and
+    /// restored when the file is reopened unless it was changed in the meantime.
+    ///
+    /// Default: false
+    pub enabled: bool,
+
+    /// The maximum size in kilobytes of the history kept for each file.
+    ///
+    /// Default: 1024
+    pub max_size_kb: u64,
+}
+
+/// The history of texts copied or cut in editors.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct ClipboardHistory {
+    /// The maximum number of entries kept in the history.
+    ///
+    /// Default: 50
+    pub max_entries: usize,
+
+    /// Whether to keep the history across restarts.
+    ///
+    /// Default: false
+    pub persist: bool,
+
+    /// Whether to leave out of the history copied texts that look like secrets.
+    ///
+    /// Default: true
+    pub exclude_secrets: bool,
+}
+
 /// Default options for buffer and project search items.
 #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
 pub struct SearchSettings {
@@ -194,6 +233,8 @@ impl Settings for EditorSettings {
         let toolbar = editor.toolbar.unwrap();
         let search = editor.search.unwrap();
         let drag_and_drop_selection = editor.drag_and_drop_selection.unwrap();
+        let persistent_undo = editor.persistent_undo.unwrap();
+        let clipboard_history = editor.clipboard_history.unwrap();
         let sticky_scroll = editor.sticky_scroll.unwrap();
         Self {
             cursor_blink: editor.cursor_blink.unwrap(),
@@ -287,6 +328,17 @@ impl Settings for EditorSettings {
                 enabled: drag_and_drop_selection.enabled.unwrap(),
                 delay: drag_and_drop_selection.delay.unwrap(),
             },
+            persistent_undo: PersistentUndo {
+                enabled: persistent_undo.enabled.unwrap(),
+                max_size_kb: persistent_undo.max_size_kb.unwrap(),
+            },
+            keyboard_macros: editor.keyboard_macros.unwrap(),
+            clipboard_history: ClipboardHistory {
+                max_entries: clipboard_history.max_entries.unwrap(),
+                persist: clipboard_history.persist.unwrap(),
+                exclude_secrets: clipboard_history.exclude_secrets.unwrap(),
+            },
+            rename_preview: editor.rename_preview.unwrap(),
             lsp_document_colors: editor.lsp_document_colors.unwrap(),
             minimum_contrast_for_highlights: editor.minimum_contrast_for_highlights.unwrap().0,
             completion_menu_scrollbar: editor
@@ -40,7 +40,7 @@ use multi_buffer::{IndentGuide, MultiBuffer, MultiBufferOffset, MultiBufferOffse
 use parking_lot::Mutex;
 use pretty_assertions::{assert_eq, assert_ne};
 use project::{
-    FakeFs, Project,
+    FakeFs, Fs as _, Project,
     debugger::breakpoint_store::{BreakpointState, SourceBreakpoint},
     project_settings::LspSettings,
     trusted_worktrees::{PathTrust, TrustedWorktrees},
@@ -7349,6 +7349,89 @@ fn test_transpose(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_evaluate_selections(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(indoc! {"
+        «1 + 2 * 3ˇ»
+        width = «0x10 << 2ˇ»
+            12 * 4ˇ
+        unchanged «1 +ˇ»
+    "});
+    cx.update_editor(|editor, window, cx| {
+        editor.evaluate_selections(&EvaluateSelections::default(), window, cx);
+    });
+    cx.assert_editor_state(indoc! {"
+        «7ˇ»
+        width = «0x40ˇ»
+            «48ˇ»
+        unchanged «1 +ˇ»
+    "});
+
+    cx.set_state(indoc! {"
+        price: «19.99 * 3ˇ»
+        2 + 2 =ˇ
+    "});
+    cx.update_editor(|editor, window, cx| {
+        editor.evaluate_selections(
+            &EvaluateSelections {
+                output: EvaluationOutput::Append,
+            },
+            window,
+            cx,
+        );
+    });
+    cx.assert_editor_state(indoc! {"
+        price: 19.99 * 3 = 59.97ˇ
+        2 + 2 = 4ˇ
+    "});
+}
+
+#[gpui::test]
+async fn test_insert_sequence(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(indoc! {"
+        item ˇ
+        item ˇ
+        item ˇ
+    "});
+    cx.update_editor(|editor, window, cx| {
+        editor.insert_sequence(
+            &InsertSequence {
+                start: Some("08".to_string()),
+                step: Some(2),
+                format: Some("#{}".to_string()),
+            },
+            window,
+            cx,
+        );
+    });
+    cx.assert_editor_state(indoc! {"
+        item #08ˇ
+        item #10ˇ
+        item #12ˇ
+    "});
+
+    // Without a start, the sequence continues from the first selection.
+    cx.set_state(indoc! {"
+        «0x0Aˇ»
+        «xˇ»
+        «xˇ»
+    "});
+    cx.update_editor(|editor, window, cx| {
+        editor.insert_sequence(&InsertSequence::default(), window, cx);
+    });
+    cx.assert_editor_state(indoc! {"
+        0x0Aˇ
+        0x0Bˇ
+        0x0Cˇ
+    "});
+}
+
 #[gpui::test]
 async fn test_rewrap(cx: &mut TestAppContext) {
     init_test(cx, |settings| {
@@ -8348,6 +8431,72 @@ async fn test_cut_line_ends(cx: &mut TestAppContext) {
         fox jumps overˇthe lazy dog"});
 }
 
+#[gpui::test]
+async fn test_keyboard_macros(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+    cx.update(|cx| {
+        cx.bind_keys([
+            gpui::KeyBinding::new("ctrl-q", ToggleMacroRecording, None),
+            gpui::KeyBinding::new("ctrl-e", MoveToEndOfLine::default(), None),
+            gpui::KeyBinding::new("down", MoveDown, None),
+        ]);
+    });
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state(indoc! {"
+        ˇone
+        two
+        three
+        four"});
+    cx.simulate_keystrokes("ctrl-q ctrl-e ; down ctrl-q");
+    cx.assert_editor_state(indoc! {"
+        one;
+        twoˇ
+        three
+        four"});
+
+    cx.update_editor(|editor, window, cx| {
+        editor.play_macro(
+            &PlayMacro {
+                times: Some(2),
+                ..Default::default()
+            },
+            window,
+            cx,
+        );
+    });
+    cx.run_until_parked();
+    cx.assert_editor_state(indoc! {"
+        one;
+        two;
+        three;
+        fourˇ"});
+
+    cx.set_state("ˇone");
+    cx.simulate_keystrokes("ctrl-q ctrl-e ! ctrl-q");
+    cx.assert_editor_state("one!ˇ");
+
+    cx.set_state(indoc! {"
+        oˇne
+        two
+        tˇhree"});
+    cx.update_editor(|editor, window, cx| {
+        editor.play_macro(
+            &PlayMacro {
+                target: MacroPlaybackTarget::EachCursor,
+                ..Default::default()
+            },
+            window,
+            cx,
+        );
+    });
+    cx.run_until_parked();
+    cx.assert_editor_state(indoc! {"
+        one!ˇ
+        two
+        three!ˇ"});
+}
+
 #[gpui::test]
 async fn test_clipboard(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -8434,6 +8583,52 @@ async fn test_clipboard(cx: &mut TestAppContext) {
         tˇhe lazy dog"});
 }
 
+#[gpui::test]
+async fn test_clipboard_history(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state("«oneˇ» two «threeˇ» four");
+    cx.update_editor(|e, window, cx| e.copy(&Copy, window, cx));
+    cx.set_state("one «twoˇ» three four");
+    cx.update_editor(|e, window, cx| e.cut(&Cut, window, cx));
+    // Texts that look like secrets are left out of the history.
+    cx.set_state("«password = hunter2hunter2ˇ»");
+    cx.update_editor(|e, window, cx| e.copy(&Copy, window, cx));
+
+    let entries = cx.update(|_, cx| ClipboardHistory::entries(cx));
+    assert_eq!(
+        entries
+            .iter()
+            .map(|entry| entry.text.as_str())
+            .collect::<Vec<_>>(),
+        ["two", "one\nthree"]
+    );
+    assert_eq!(entries[1].selections.as_ref().map(Vec::len), Some(2));
+
+    // Pasting an older entry restores its text per cursor and makes it the most
+    // recent entry.
+    cx.set_state("a ˇ b ˇ");
+    cx.update_editor(|e, window, cx| e.paste_clipboard_history_entry(&entries[1], window, cx));
+    cx.assert_editor_state("a oneˇ b threeˇ");
+    let entries = cx.update(|_, cx| ClipboardHistory::entries(cx));
+    assert_eq!(
+        entries
+            .iter()
+            .map(|entry| entry.text.as_str())
+            .collect::<Vec<_>>(),
+        ["one\nthree", "two"]
+    );
+
+    // Copying the same text again doesn't duplicate the entry.
+    cx.set_state("«twoˇ»");
+    cx.update_editor(|e, window, cx| e.copy(&Copy, window, cx));
+    let entries = cx.update(|_, cx| ClipboardHistory::entries(cx));
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].text, "two");
+}
+
 #[gpui::test]
 async fn test_copy_trim(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -9237,6 +9432,98 @@ async fn test_split_selection_into_lines_interacting_with_creases(cx: &mut TestA
         );
 }
 
+#[gpui::test]
+async fn test_block_selection_in_virtual_space(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(indoc! {"
+        oneˇ
+        a
+        three
+    "});
+
+    // The block extends past the end of shorter lines, whose selections are
+    // clipped to the line end.
+    cx.update_editor(|editor, window, cx| {
+        editor.select_block_right(&SelectBlockRight, window, cx);
+        editor.select_block_down(&SelectBlockDown, window, cx);
+        editor.select_block_down(&SelectBlockDown, window, cx);
+    });
+    cx.assert_editor_state(indoc! {"
+        oneˇ
+        aˇ
+        thr«eˇ»e
+    "});
+    cx.update_editor(|editor, _, _| {
+        let block = editor.selections.block_selection().unwrap();
+        assert_eq!(block.columns(), 3..4);
+    });
+
+    // Typing pads the shorter lines up to the block.
+    cx.update_editor(|editor, window, cx| editor.handle_input("|", window, cx));
+    cx.assert_editor_state(indoc! {"
+        one|ˇ
+        a  |ˇ
+        thr|ˇe
+    "});
+    cx.update_editor(|editor, _, _| {
+        assert!(editor.selections.block_selection().is_none());
+    });
+}
+
+#[gpui::test]
+async fn test_mouse_block_selection_skips_short_lines(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(indoc! {"
+        ˇone
+        a
+        three
+    "});
+
+    // Lines ending before the block get no selection while others select text.
+    cx.update_editor(|editor, window, cx| {
+        editor.begin_columnar_selection(
+            DisplayPoint::new(DisplayRow(0), 1),
+            1,
+            true,
+            ColumnarMode::FromMouse,
+            window,
+            cx,
+        );
+        editor.update_selection(
+            DisplayPoint::new(DisplayRow(2), 3),
+            3,
+            gpui::Point::<f32>::default(),
+            window,
+            cx,
+        );
+    });
+    cx.assert_editor_state(indoc! {"
+        o«neˇ»
+        a
+        t«hrˇ»ee
+    "});
+
+    // When no line has text within the block, every line gets a selection.
+    cx.update_editor(|editor, window, cx| {
+        editor.update_selection(
+            DisplayPoint::new(DisplayRow(1), 1),
+            1,
+            gpui::Point::<f32>::default(),
+            window,
+            cx,
+        );
+    });
+    cx.assert_editor_state(indoc! {"
+        oˇne
+        aˇ
+        three
+    "});
+}
+
 #[gpui::test]
 async fn test_add_selection_above_below(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -11235,6 +11522,75 @@ async fn test_unwrap_syntax_nodes(cx: &mut gpui::TestAppContext) {
               // method"});
 }
 
+#[gpui::test]
+async fn test_structural_editing(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let language = Arc::new(Language::new(
+        LanguageConfig::default(),
+        Some(tree_sitter_rust::LANGUAGE.into()),
+    ));
+
+    cx.update_buffer(|buffer, cx| {
+        buffer.set_language(Some(language), cx);
+    });
+
+    cx.set_state(indoc! { r#"fn f(ˇa: u8, b: u16) {}"# });
+    cx.update_editor(|editor, window, cx| {
+        editor.move_syntax_node_down(&MoveSyntaxNodeDown, window, cx);
+    });
+    cx.assert_editor_state(indoc! { r#"fn f(b: u16, ˇa: u8) {}"# });
+
+    cx.update_editor(|editor, window, cx| {
+        editor.move_syntax_node_up(&MoveSyntaxNodeUp, window, cx);
+    });
+    cx.assert_editor_state(indoc! { r#"fn f(ˇa: u8, b: u16) {}"# });
+
+    cx.set_state(indoc! { r#"fn f() { let v = [ˇ1, 2, ˇ3, 4]; }"# });
+    cx.update_editor(|editor, window, cx| {
+        editor.move_syntax_node_down(&MoveSyntaxNodeDown, window, cx);
+    });
+    cx.assert_editor_state(indoc! { r#"fn f() { let v = [2, ˇ1, 4, ˇ3]; }"# });
+
+    // Selections without a sibling in the requested direction are left alone.
+    cx.update_editor(|editor, window, cx| {
+        editor.move_syntax_node_down(&MoveSyntaxNodeDown, window, cx);
+    });
+    cx.assert_editor_state(indoc! { r#"fn f() { let v = [2, 4, ˇ1, ˇ3]; }"# });
+
+    cx.set_state(indoc! { r#"fn f() { let v = [1, ˇ2, 3]; }"# });
+    cx.update_editor(|editor, window, cx| {
+        editor.duplicate_syntax_node(&DuplicateSyntaxNode, window, cx);
+    });
+    cx.assert_editor_state(indoc! { r#"fn f() { let v = [1, 2, ˇ2, 3]; }"# });
+
+    cx.set_state(indoc! { r#"fn f() { let v = [(aˇ), b]; }"# });
+    cx.update_editor(|editor, window, cx| {
+        editor.slurp_forward(&SlurpForward, window, cx);
+    });
+    cx.assert_editor_state(indoc! { r#"fn f() { let v = [(aˇ, b)]; }"# });
+
+    cx.set_state(indoc! { r#"fn f() { let v = [(ˇa, b)]; }"# });
+    cx.update_editor(|editor, window, cx| {
+        editor.barf_forward(&BarfForward, window, cx);
+    });
+    cx.assert_editor_state(indoc! { r#"fn f() { let v = [(ˇa), b]; }"# });
+
+    cx.set_state(indoc! { r#"fn f() { let v = [a, (ˇb)]; }"# });
+    cx.update_editor(|editor, window, cx| {
+        editor.slurp_backward(&SlurpBackward, window, cx);
+    });
+    cx.assert_editor_state(indoc! { r#"fn f() { let v = [(a, ˇb)]; }"# });
+
+    cx.set_state(indoc! { r#"fn f() { let v = [(a, bˇ)]; }"# });
+    cx.update_editor(|editor, window, cx| {
+        editor.barf_backward(&BarfBackward, window, cx);
+    });
+    cx.assert_editor_state(indoc! { r#"fn f() { let v = [a, (bˇ)]; }"# });
+}
+
 #[gpui::test]
 async fn test_fold_function_bodies(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -27679,6 +28035,159 @@ async fn test_rename_without_prepare(cx: &mut TestAppContext) {
     "});
 }
 
+#[gpui::test]
+async fn test_rename_with_preview(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+    let capabilities = lsp::ServerCapabilities {
+        rename_provider: Some(lsp::OneOf::Left(true)),
+        ..Default::default()
+    };
+    let mut cx = EditorLspTestContext::new_rust(capabilities, cx).await;
+
+    cx.set_state(indoc! {"
+        struct Fˇoo {}
+        fn a(_: Foo) {}
+        fn b(_: Foo) {}
+    "});
+
+    cx.update_editor(|e, window, cx| e.rename(&Rename, window, cx))
+        .expect("Prepare rename was not started")
+        .await
+        .expect("Prepare rename failed");
+
+    let mut rename_handler =
+        cx.set_request_handler::<lsp::request::Rename, _, _>(move |url, _, _| async move {
+            let edits = [(0, 7), (1, 8), (2, 8)]
+                .into_iter()
+                .map(|(line, character)| lsp::TextEdit {
+                    range: lsp::Range::new(
+                        lsp::Position::new(line, character),
+                        lsp::Position::new(line, character + 3),
+                    ),
+                    new_text: "Bar".to_string(),
+                })
+                .collect();
+            Ok(Some(lsp::WorkspaceEdit::new(
+                std::collections::HashMap::from_iter(Some((url, edits))),
+            )))
+        });
+    let rename_task = cx
+        .update_editor(|e, window, cx| {
+            e.confirm_rename_with_preview(&ConfirmRenameWithPreview, window, cx)
+        })
+        .expect("Confirm rename was not started");
+    rename_handler.next().await.unwrap();
+    rename_task.await.expect("Confirm rename failed");
+    cx.run_until_parked();
+
+    // The edits are only previewed.
+    assert_eq!(
+        cx.update_editor(|editor, _, cx| editor.text(cx)),
+        "struct Foo {}\nfn a(_: Foo) {}\nfn b(_: Foo) {}\n"
+    );
+    let preview = cx
+        .update_workspace(|workspace, _, cx| workspace.active_item_as::<RenamePreview>(cx))
+        .expect("Rename preview was not opened");
+
+    // Reject the second edit and apply the others.
+    cx.update(|window, cx| {
+        preview.update(cx, |preview, cx| {
+            preview.toggle_hunk(1, cx);
+            preview.apply(&ApplyRenamePreview, window, cx);
+        })
+    });
+    cx.run_until_parked();
+    assert_eq!(
+        cx.update_editor(|editor, _, cx| editor.text(cx)),
+        "struct Bar {}\nfn a(_: Foo) {}\nfn b(_: Bar) {}\n"
+    );
+
+    // The applied edits are undone together.
+    cx.update_editor(|editor, window, cx| editor.undo(&Undo, window, cx));
+    assert_eq!(
+        cx.update_editor(|editor, _, cx| editor.text(cx)),
+        "struct Foo {}\nfn a(_: Foo) {}\nfn b(_: Foo) {}\n"
+    );
+}
+
+#[gpui::test]
+async fn test_rename_with_file_operations(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+    let capabilities = lsp::ServerCapabilities {
+        rename_provider: Some(lsp::OneOf::Left(true)),
+        ..Default::default()
+    };
+    let mut cx = EditorLspTestContext::new_rust(capabilities, cx).await;
+
+    cx.set_state(indoc! {"
+        struct Fˇoo {}
+    "});
+
+    cx.update_editor(|e, window, cx| e.rename(&Rename, window, cx))
+        .expect("Prepare rename was not started")
+        .await
+        .expect("Prepare rename failed");
+
+    let new_file_path = EditorLspTestContext::root_path().join("dir").join("bar.rs");
+    let new_file_uri = lsp::Uri::from_file_path(&new_file_path).unwrap();
+    let rename_requests = Arc::new(AtomicUsize::new(0));
+    let mut rename_handler = cx.set_request_handler::<lsp::request::Rename, _, _>({
+        let rename_requests = rename_requests.clone();
+        move |url, _, _| {
+            rename_requests.fetch_add(1, atomic::Ordering::SeqCst);
+            let new_file_uri = new_file_uri.clone();
+            async move {
+                Ok(Some(lsp::WorkspaceEdit {
+                    document_changes: Some(lsp::DocumentChanges::Operations(vec![
+                        lsp::DocumentChangeOperation::Edit(lsp::TextDocumentEdit {
+                            text_document: lsp::OptionalVersionedTextDocumentIdentifier {
+                                uri: url,
+                                version: None,
+                            },
+                            edits: vec![lsp::OneOf::Left(lsp::TextEdit {
+                                range: lsp::Range::new(
+                                    lsp::Position::new(0, 7),
+                                    lsp::Position::new(0, 10),
+                                ),
+                                new_text: "Bar".to_string(),
+                            })],
+                        }),
+                        lsp::DocumentChangeOperation::Op(lsp::ResourceOp::Create(
+                            lsp::CreateFile {
+                                uri: new_file_uri,
+                                options: None,
+                                annotation_id: None,
+                            },
+                        )),
+                    ])),
+                    ..Default::default()
+                }))
+            }
+        }
+    });
+    let rename_task = cx
+        .update_editor(|e, window, cx| {
+            e.confirm_rename_with_preview(&ConfirmRenameWithPreview, window, cx)
+        })
+        .expect("Confirm rename was not started");
+    rename_handler.next().await.unwrap();
+    rename_task.await.expect("Confirm rename failed");
+    cx.run_until_parked();
+
+    // Renames that create files are applied from the server's only response,
+    // without a preview.
+    assert_eq!(rename_requests.load(atomic::Ordering::SeqCst), 1);
+    assert!(
+        cx.update_workspace(|workspace, _, cx| workspace.active_item_as::<RenamePreview>(cx))
+            .is_none()
+    );
+    cx.assert_editor_state(indoc! {"
+        struct Barˇ {}
+    "});
+    let fs = cx.update_editor(|editor, _, cx| editor.project().unwrap().read(cx).fs().clone());
+    assert!(fs.is_file(&new_file_path).await);
+}
+
 #[gpui::test]
 async fn test_tree_sitter_brackets_newline_insertion(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -302,9 +302,17 @@ impl EditorElement {
         register_action(editor, window, Editor::copy);
         register_action(editor, window, Editor::copy_and_trim);
         register_action(editor, window, Editor::diff_clipboard_with_selection);
+        register_action(editor, window, Editor::filter_selections_through_command);
+        register_action(editor, window, Editor::evaluate_selections);
+        register_action(editor, window, Editor::insert_sequence);
         register_action(editor, window, Editor::paste);
         register_action(editor, window, Editor::undo);
         register_action(editor, window, Editor::redo);
+        register_action(editor, window, Editor::go_to_older_undo_state);
+        register_action(editor, window, Editor::go_to_newer_undo_state);
+        register_action(editor, window, Editor::toggle_macro_recording);
+        register_action(editor, window, Editor::play_macro);
+        register_action(editor, window, Editor::save_last_macro);
         register_action(editor, window, Editor::move_page_up);
         register_action(editor, window, Editor::move_page_down);
         register_action(editor, window, Editor::next_screen);
@@ -358,6 +366,10 @@ impl EditorElement {
         register_action(editor, window, Editor::select_down);
         register_action(editor, window, Editor::select_left);
         register_action(editor, window, Editor::select_right);
+        register_action(editor, window, Editor::select_block_up);
+        register_action(editor, window, Editor::select_block_down);
+        register_action(editor, window, Editor::select_block_left);
+        register_action(editor, window, Editor::select_block_right);
         register_action(editor, window, Editor::select_to_previous_word_start);
         register_action(editor, window, Editor::select_to_previous_subword_start);
         register_action(editor, window, Editor::select_to_next_word_end);
@@ -405,6 +417,13 @@ impl EditorElement {
         );
         register_action(editor, window, Editor::select_to_end_of_larger_syntax_node);
         register_action(editor, window, Editor::unwrap_syntax_node);
+        register_action(editor, window, Editor::move_syntax_node_up);
+        register_action(editor, window, Editor::move_syntax_node_down);
+        register_action(editor, window, Editor::duplicate_syntax_node);
+        register_action(editor, window, Editor::slurp_forward);
+        register_action(editor, window, Editor::slurp_backward);
+        register_action(editor, window, Editor::barf_forward);
+        register_action(editor, window, Editor::barf_backward);
         register_action(editor, window, Editor::move_to_start_of_larger_syntax_node);
         register_action(editor, window, Editor::move_to_end_of_larger_syntax_node);
         register_action(editor, window, Editor::select_enclosing_symbol);
@@ -614,6 +633,13 @@ impl EditorElement {
                 cx.propagate();
             }
         });
+        register_action(editor, window, |editor, action, window, cx| {
+            if let Some(task) = editor.confirm_rename_with_preview(action, window, cx) {
+                editor.detach_and_notify_err(task, window, cx);
+            } else {
+                cx.propagate();
+            }
+        });
         register_action(editor, window, |editor, action, window, cx| {
             if let Some(task) = editor.find_all_references(action, window, cx) {
                 task.detach_and_log_err(cx);
@@ -6624,10 +6650,76 @@ impl EditorElement {
                     }
                 }
             }
+            self.paint_block_selection_virtual_space(layout, window, cx);
             invisible_display_ranges
         })
     }
 
+    /// Paints the part of a local block selection that extends past the end
+    /// of shorter lines, which the selections themselves can't cover.
+    fn paint_block_selection_virtual_space(
+        &self,
+        layout: &EditorLayout,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        let editor = self.editor.read(cx);
+        if !editor.show_local_selections {
+            return;
+        }
+        let Some(block) = editor.selections.block_selection() else {
+            return;
+        };
+        let Some((player_color, _)) = layout.selections.first() else {
+            return;
+        };
+
+        let position_map = &layout.position_map;
+        let snapshot = &position_map.snapshot;
+        let columns = block.columns();
+        let block_rows = block.rows(snapshot);
+        let visible_rows = &layout.visible_display_row_range;
+        let rows = cmp::max(block_rows.start, visible_rows.start)
+            ..cmp::min(block_rows.end, visible_rows.end);
+        for row in rows.iter_rows() {
+            let line_len = snapshot.line_len(row);
+            if columns.end <= line_len || snapshot.is_block_line(row) {
+                continue;
+            }
+            let Some(line_layout) = position_map
+                .line_layouts
+                .get(row.minus(visible_rows.start) as usize)
+            else {
+                continue;
+            };
+            let alignment_offset =
+                line_layout.alignment_offset(layout.text_align, layout.content_width);
+            let x_for_column = |column: u32| {
+                layout.content_origin.x
+                    + line_layout.width
+                    + alignment_offset
+                    + position_map.em_advance * (column - line_len) as f32
+                    - Pixels::from(position_map.scroll_pixel_position.x)
+            };
+            let start_y = layout.content_origin.y
+                + Pixels::from(
+                    (row.as_f64() - position_map.scroll_position.y)
+                        * ScrollOffset::from(position_map.line_height),
+                );
+            HighlightedRange {
+                color: player_color.selection,
+                line_height: position_map.line_height,
+                corner_radius: Pixels::ZERO,
+                start_y,
+                lines: vec![HighlightedRangeLine {
+                    start_x: x_for_column(cmp::max(columns.start, line_len)),
+                    end_x: x_for_column(columns.end),
+                }],
+            }
+            .paint(true, position_map.text_hitbox.bounds, window);
+        }
+    }
+
     fn paint_lines(
         &mut self,
         invisible_display_ranges: &[Range<DisplayPoint>],
@@ -0,0 +1,911 @@
+//! Evaluating selected arithmetic and date expressions in place, and inserting
+//! numbered sequences across selections.
+use std::{fmt, ops::Range, sync::LazyLock};
+
+use anyhow::{Context as _, Result, anyhow, bail};
+use gpui::{Context, Window};
+use language::Point;
+use multi_buffer::MultiBufferOffset;
+use regex::Regex;
+use time::{Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time};
+
+use crate::{
+    Editor, SelectionEffects, ToOffset as _, ToPoint as _,
+    actions::{EvaluateSelections, EvaluationOutput, InsertSequence},
+};
+
+static DATE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d{4})-(\d{2})-(\d{2})(?:[T ](\d{2}):(\d{2})(?::(\d{2}))?)?").unwrap()
+});
+static DURATION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:\d+(?:\.\d+)?[wdhms])+").unwrap());
+static DURATION_PART_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\d+(?:\.\d+)?)([wdhms])").unwrap());
+static NUMBER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\d[\d_]*(\.\d+)?([eE][+-]?\d+)?").unwrap());
+
+impl Editor {
+    pub fn evaluate_selections(
+        &mut self,
+        action: &EvaluateSelections,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.read_only(cx) {
+            return;
+        }
+
+        let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+        let now = PrimitiveDateTime::new(
+            now.date(),
+            now.time().replace_nanosecond(0).unwrap_or(Time::MIDNIGHT),
+        );
+        let display_map = self.display_snapshot(cx);
+        let buffer = display_map.buffer_snapshot();
+        let selections = self.selections.all::<MultiBufferOffset>(&display_map);
+
+        let mut selection_edits = Vec::with_capacity(selections.len());
+        let mut first_error = None;
+        let mut previous_end = MultiBufferOffset(0);
+        for selection in selections {
+            // Without a selection, the expression is the text before the cursor
+            // on its line.
+            let range = if selection.is_empty() {
+                let row = selection.head().to_point(buffer).row;
+                let line_start = Point::new(row, 0).to_offset(buffer);
+                line_start.max(previous_end)..selection.head()
+            } else {
+                selection.range()
+            };
+            previous_end = selection.end;
+
+            let text = buffer.text_for_range(range.clone()).collect::<String>();
+            let trimmed = text.trim();
+            let (expression, ends_with_equals) = match trimmed.strip_suffix('=') {
+                Some(expression) => (expression.trim_end(), true),
+                None => (trimmed, false),
+            };
+            if expression.is_empty() {
+                selection_edits.push((selection.range(), None));
+                continue;
+            }
+
+            let result = match evaluate_expression(expression, now) {
+                Ok(result) => result,
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                    selection_edits.push((selection.range(), None));
+                    continue;
+                }
+            };
+            let expression_start = range.start + (text.len() - text.trim_start().len());
+            let edit = match action.output {
+                EvaluationOutput::Replace => {
+                    (expression_start..expression_start + trimmed.len(), result)
+                }
+                EvaluationOutput::Append if ends_with_equals => {
+                    let separator = if text.ends_with(char::is_whitespace) {
+                        ""
+                    } else {
+                        " "
+                    };
+                    (range.end..range.end, format!("{separator}{result}"))
+                }
+                EvaluationOutput::Append => {
+                    let end = expression_start + trimmed.len();
+                    (end..end, format!(" = {result}"))
+                }
+            };
+            selection_edits.push((selection.range(), Some(edit)));
+        }
+
+        if selection_edits.iter().all(|(_, edit)| edit.is_none()) {
+            if let Some(error) = first_error
+                && let Some(workspace) = self.workspace()
+            {
+                workspace.update(cx, |workspace, cx| workspace.show_error(&error, cx));
+            }
+            return;
+        }
+        let select_results = action.output == EvaluationOutput::Replace;
+        self.apply_selection_edits(selection_edits, select_results, window, cx);
+    }
+
+    pub fn insert_sequence(
+        &mut self,
+        action: &InsertSequence,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.read_only(cx) {
+            return;
+        }
+
+        let display_map = self.display_snapshot(cx);
+        let buffer = display_map.buffer_snapshot();
+        let selections = self.selections.all::<MultiBufferOffset>(&display_map);
+        let start = match &action.start {
+            Some(start) => match parse_integer_literal(start.trim()) {
+                Some(start) => start,
+                None => {
+                    if let Some(workspace) = self.workspace() {
+                        let error = anyhow!("{start:?} is not an integer");
+                        workspace.update(cx, |workspace, cx| workspace.show_error(&error, cx));
+                    }
+                    return;
+                }
+            },
+            // Continue from the number in the first selection, if there is one.
+            None => selections
+                .first()
+                .and_then(|selection| {
+                    let text = buffer.text_for_range(selection.range()).collect::<String>();
+                    parse_integer_literal(text.trim())
+                })
+                .unwrap_or((1, IntegerFormat::default())),
+        };
+
+        let (mut value, format) = start;
+        let step = i128::from(action.step.unwrap_or(1));
+        let selection_edits = selections
+            .into_iter()
+            .map(|selection| {
+                let mut text = format.format(value);
+                if let Some(template) = &action.format {
+                    text = template.replace("{}", &text);
+                }
+                value = value.saturating_add(step);
+                (selection.range(), Some((selection.range(), text)))
+            })
+            .collect();
+        self.apply_selection_edits(selection_edits, false, window, cx);
+    }
+
+    /// Applies the edits made for each selection, in the order of the
+    /// selections, then either selects the text inserted for each of them or
+    /// places a cursor after it. Selections without an edit are kept.
+    fn apply_selection_edits(
+        &mut self,
+        selection_edits: Vec<(
+            Range<MultiBufferOffset>,
+            Option<(Range<MultiBufferOffset>, String)>,
+        )>,
+        select_inserted_text: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let mut delta = 0_isize;
+        let mut new_ranges = Vec::with_capacity(selection_edits.len());
+        let mut edits = Vec::new();
+        for (selection, edit) in selection_edits {
+            match edit {
+                Some((range, text)) => {
+                    let start = range.start + delta;
+                    let end = start + text.len();
+                    new_ranges.push(if select_inserted_text {
+                        start..end
+                    } else {
+                        end..end
+                    });
+                    delta += text.len() as isize - (range.end - range.start) as isize;
+                    edits.push((range, text));
+                }
+                None => new_ranges.push(selection.start + delta..selection.end + delta),
+            }
+        }
+
+        self.transact(window, cx, |this, window, cx| {
+            this.edit(edits, cx);
+            this.change_selections(SelectionEffects::default(), window, cx, |s| {
+                s.select_ranges(new_ranges);
+            });
+        });
+    }
+}
+
+/// Formats `value` in the given radix, with the letter case and zero padding
+/// of the digits of an existing number, so that numbers derived from another
+/// one are written the same way.
+pub fn format_number_like(value: u64, radix: u32, digits: &str) -> String {
+    let width = if radix == 10 && !digits.starts_with('0') {
+        0
+    } else {
+        digits.len()
+    };
+    match radix {
+        16 if uses_lowercase_digits(digits) => format!("{value:0width$x}"),
+        16 => format!("{value:0width$X}"),
+        8 => format!("{value:0width$o}"),
+        2 => format!("{value:0width$b}"),
+        _ => format!("{value:0width$}"),
+    }
+}
+
+fn uses_lowercase_digits(digits: &str) -> bool {
+    let mut uses_uppercase = false;
+    for ch in digits.chars() {
+        if ch.is_ascii_lowercase() {
+            return true;
+        }
+        if ch.is_ascii_uppercase() {
+            uses_uppercase = true;
+        }
+    }
+    !uses_uppercase
+}
+
+/// How an integer literal was written: its radix, its prefix (like `0x`) and
+/// its digits, which determine the letter case and zero padding.
+#[derive(Clone, Debug, PartialEq)]
+struct IntegerFormat {
+    radix: u32,
+    prefix: String,
+    digits: String,
+}
+
+impl Default for IntegerFormat {
+    fn default() -> Self {
+        Self {
+            radix: 10,
+            prefix: String::new(),
+            digits: String::new(),
+        }
+    }
+}
+
+impl IntegerFormat {
+    fn format(&self, value: i128) -> String {
+        let sign = if value < 0 { "-" } else { "" };
+        match u64::try_from(value.unsigned_abs()) {
+            // Negative numbers are written in decimal.
+            Ok(magnitude) if self.radix == 10 || value >= 0 => format!(
+                "{sign}{}{}",
+                self.prefix,
+                format_number_like(magnitude, self.radix, &self.digits)
+            ),
+            _ => value.to_string(),
+        }
+    }
+}
+
+/// Parses an integer written in decimal, or in hexadecimal, octal or binary
+/// with a `0x`, `0o` or `0b` prefix.
+fn parse_integer_literal(text: &str) -> Option<(i128, IntegerFormat)> {
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, text),
+    };
+    let radix = match unsigned.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0o" | "0O") => 8,
+        Some("0b" | "0B") => 2,
+        _ => 10,
+    };
+    let (prefix, digits) = unsigned.split_at(if radix == 10 { 0 } else { 2 });
+    if digits.is_empty() || !digits.chars().all(|ch| ch.is_digit(radix) || ch == '_') {
+        return None;
+    }
+
+    let value = i128::from_str_radix(&digits.replace('_', ""), radix).ok()?;
+    let format = IntegerFormat {
+        radix,
+        prefix: prefix.to_string(),
+        digits: digits.to_string(),
+    };
+    Some((if negative { -value } else { value }, format))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Value {
+    Integer(i128),
+    Float(f64),
+    DateTime(PrimitiveDateTime),
+    Duration(Duration),
+}
+
+impl Value {
+    fn as_float(self) -> Option<f64> {
+        match self {
+            Value::Integer(value) => Some(value as f64),
+            Value::Float(value) => Some(value),
+            Value::DateTime(_) | Value::Duration(_) => None,
+        }
+    }
+
+    fn kind(self) -> &'static str {
+        match self {
+            Value::Integer(_) | Value::Float(_) => "a number",
+            Value::DateTime(_) => "a date",
+            Value::Duration(_) => "a duration",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Power,
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    ShiftLeft,
+    ShiftRight,
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Operator::Add => "+",
+            Operator::Subtract => "-",
+            Operator::Multiply => "*",
+            Operator::Divide => "/",
+            Operator::Remainder => "%",
+            Operator::Power => "**",
+            Operator::BitAnd => "&",
+            Operator::BitOr => "|",
+            Operator::BitXor => "^",
+            Operator::BitNot => "~",
+            Operator::ShiftLeft => "<<",
+            Operator::ShiftRight => ">>",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Integer(i128, IntegerFormat),
+    Value(Value),
+    Operator(Operator),
+    OpenParen,
+    CloseParen,
+}
+
+/// Evaluates an arithmetic expression over integers, floats, dates and
+/// durations, returning its result written the way it could be typed back.
+///
+/// Integers written in hexadecimal, octal or binary make the result written
+/// the same way as the first of them. Dates are written like `2024-01-31` or
+/// `2024-01-31T09:30`, `today` and `now` refer to the given time, and
+/// durations combine weeks, days, hours, minutes and seconds like `1d12h`.
+fn evaluate_expression(expression: &str, now: PrimitiveDateTime) -> Result<String> {
+    let tokens = tokenize(expression, now)?;
+    let integer_format = tokens.iter().find_map(|token| match token {
+        Token::Integer(_, format) if format.radix != 10 => Some(format.clone()),
+        _ => None,
+    });
+    let mut parser = Parser {
+        tokens,
+        ix: 0,
+        depth: 0,
+    };
+    let value = parser.parse_bit_or()?;
+    if parser.ix < parser.tokens.len() {
+        bail!("unexpected input in {expression:?}");
+    }
+
+    match value {
+        Value::Integer(value) => Ok(integer_format.unwrap_or_default().format(value)),
+        Value::Float(value) => format_float(value),
+        Value::DateTime(date_time) => Ok(format_date_time(date_time)),
+        Value::Duration(duration) => Ok(format_duration(duration)),
+    }
+}
+
+fn tokenize(expression: &str, now: PrimitiveDateTime) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = expression;
+    while let Some(ch) = rest.chars().next() {
+        if ch.is_whitespace() {
+            rest = &rest[ch.len_utf8()..];
+            continue;
+        }
+
+        let (token, len) = if ch.is_ascii_digit() {
+            tokenize_number(rest)?
+        } else if ch.is_alphabetic() {
+            let len = rest
+                .find(|ch: char| !ch.is_alphanumeric() && ch != '_')
+                .unwrap_or(rest.len());
+            let value = match &rest[..len] {
+                "now" => Value::DateTime(now),
+                "today" => Value::DateTime(now.replace_time(Time::MIDNIGHT)),
+                name => bail!("unknown name {name:?}"),
+            };
+            (Token::Value(value), len)
+        } else {
+            let operator = |operator| Token::Operator(operator);
+            match rest.get(..2) {
+                Some("**") => (operator(Operator::Power), 2),
+                Some("<<") => (operator(Operator::ShiftLeft), 2),
+                Some(">>") => (operator(Operator::ShiftRight), 2),
+                _ => {
+                    let token = match ch {
+                        '+' => operator(Operator::Add),
+                        '-' => operator(Operator::Subtract),
+                        '*' | '×' => operator(Operator::Multiply),
+                        '/' | '÷' => operator(Operator::Divide),
+                        '%' => operator(Operator::Remainder),
+                        '&' => operator(Operator::BitAnd),
+                        '|' => operator(Operator::BitOr),
+                        '^' => operator(Operator::BitXor),
+                        '~' => operator(Operator::BitNot),
+                        '(' => Token::OpenParen,
+                        ')' => Token::CloseParen,
+                        _ => bail!("unexpected character {ch:?}"),
+                    };
+                    (token, ch.len_utf8())
+                }
+            }
+        };
+        tokens.push(token);
+        rest = &rest[len..];
+    }
+    Ok(tokens)
+}
+
+/// Reads the number, date or duration at the start of `text`, returning it
+/// along with its length.
+fn tokenize_number(text: &str) -> Result<(Token, usize)> {
+    let (token, len) = if let Some(captures) = DATE_REGEX.captures(text) {
+        let part = |ix: usize| {
+            captures
+                .get(ix)
+                .map_or(Ok(0), |part| part.as_str().parse::<u8>())
+        };
+        let month = Month::try_from(part(2)?)?;
+        let date = Date::from_calendar_date(captures[1].parse()?, month, part(3)?)?;
+        let time = Time::from_hms(part(4)?, part(5)?, part(6)?)?;
+        let date_time = PrimitiveDateTime::new(date, time);
+        (Token::Value(Value::DateTime(date_time)), captures[0].len())
+    } else if let Some((value, format)) = text
+        .get(..2)
+        .filter(|prefix| matches!(*prefix, "0x" | "0X" | "0o" | "0O" | "0b" | "0B"))
+        .and_then(|_| {
+            let len = text[2..]
+                .find(|ch: char| !ch.is_ascii_alphanumeric() && ch != '_')
+                .map_or(text.len(), |len| len + 2);
+            parse_integer_literal(&text[..len])
+        })
+    {
+        let len = format.prefix.len() + format.digits.len();
+        (Token::Integer(value, format), len)
+    } else if let Some(duration) = DURATION_REGEX.find(text) {
+        let mut seconds = 0.0;
+        for captures in DURATION_PART_REGEX.captures_iter(duration.as_str()) {
+            let amount = captures[1].parse::<f64>()?;
+            let unit_seconds = match &captures[2] {
+                "w" => 7. * 86400.,
+                "d" => 86400.,
+                "h" => 3600.,
+                "m" => 60.,
+                _ => 1.,
+            };
+            seconds += amount * unit_seconds;
+        }
+        let duration = Duration::checked_seconds_f64(seconds)
+            .with_context(|| format!("{:?} is too long", duration.as_str()))?;
+        (
+            Token::Value(Value::Duration(duration)),
+            duration.as_str().len(),
+        )
+    } else {
+        let number = NUMBER_REGEX
+            .captures(text)
+            .with_context(|| format!("invalid number in {text:?}"))?;
+        let literal = &number[0];
+        let token = if number.get(1).is_some() || number.get(2).is_some() {
+            Token::Value(Value::Float(literal.replace('_', "").parse()?))
+        } else {
+            let (value, format) = parse_integer_literal(literal)
+                .with_context(|| format!("{literal:?} is too large"))?;
+            Token::Integer(value, format)
+        };
+        (token, literal.len())
+    };
+
+    if text[len..].starts_with(|ch: char| ch.is_alphanumeric()) {
+        bail!("invalid number in {text:?}");
+    }
+    Ok((token, len))
+}
+
+/// How deeply parentheses, unary operators and exponents can be nested, so
+/// that evaluating a pathological selection can't overflow the stack.
+const MAX_NESTING_DEPTH: usize = 64;
+
+/// A recursive descent parser evaluating the expression as it goes, with
+/// operators binding like they do in Python.
+struct Parser {
+    tokens: Vec<Token>,
+    ix: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn parse_nested(&mut self, parse: impl FnOnce(&mut Self) -> Result<Value>) -> Result<Value> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            bail!("expression is nested too deeply");
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn next_operator(&mut self, operators: &[Operator]) -> Option<Operator> {
+        match self.tokens.get(self.ix) {
+            Some(Token::Operator(operator)) if operators.contains(operator) => {
+                self.ix += 1;
+                Some(*operator)
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_binary(
+        &mut self,
+        operators: &[Operator],
+        mut parse_operand: impl FnMut(&mut Self) -> Result<Value>,
+    ) -> Result<Value> {
+        let mut value = parse_operand(self)?;
+        while let Some(operator) = self.next_operator(operators) {
+            let rhs = parse_operand(self)?;
+            value = apply_binary(operator, value, rhs)?;
+        }
+        Ok(value)
+    }
+
+    fn parse_bit_or(&mut self) -> Result<Value> {
+        self.parse_binary(&[Operator::BitOr], Self::parse_bit_xor)
+    }
+
+    fn parse_bit_xor(&mut self) -> Result<Value> {
+        self.parse_binary(&[Operator::BitXor], Self::parse_bit_and)
+    }
+
+    fn parse_bit_and(&mut self) -> Result<Value> {
+        self.parse_binary(&[Operator::BitAnd], Self::parse_shift)
+    }
+
+    fn parse_shift(&mut self) -> Result<Value> {
+        self.parse_binary(
+            &[Operator::ShiftLeft, Operator::ShiftRight],
+            Self::parse_sum,
+        )
+    }
+
+    fn parse_sum(&mut self) -> Result<Value> {
+        self.parse_binary(&[Operator::Add, Operator::Subtract], Self::parse_product)
+    }
+
+    fn parse_product(&mut self) -> Result<Value> {
+        self.parse_binary(
+            &[Operator::Multiply, Operator::Divide, Operator::Remainder],
+            Self::parse_unary,
+        )
+    }
+
+    fn parse_unary(&mut self) -> Result<Value> {
+        match self.next_operator(&[Operator::Add, Operator::Subtract, Operator::BitNot]) {
+            Some(operator) => {
+                let value = self.parse_nested(Self::parse_unary)?;
+                apply_unary(operator, value)
+            }
+            None => self.parse_power(),
+        }
+    }
+
+    fn parse_power(&mut self) -> Result<Value> {
+        let base = self.parse_primary()?;
+        if self.next_operator(&[Operator::Power]).is_some() {
+            let exponent = self.parse_nested(Self::parse_unary)?;
+            apply_binary(Operator::Power, base, exponent)
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Value> {
+        let token = self
+            .tokens
+            .get(self.ix)
+            .cloned()
+            .context("incomplete expression")?;
+        self.ix += 1;
+        match token {
+            Token::Integer(value, _) => Ok(Value::Integer(value)),
+            Token::Value(value) => Ok(value),
+            Token::OpenParen => {
+                let value = self.parse_nested(Self::parse_bit_or)?;
+                match self.tokens.get(self.ix) {
+                    Some(Token::CloseParen) => {
+                        self.ix += 1;
+                        Ok(value)
+                    }
+                    _ => bail!("unclosed parenthesis"),
+                }
+            }
+            Token::CloseParen => bail!("unexpected closing parenthesis"),
+            Token::Operator(operator) => bail!("unexpected operator {operator}"),
+        }
+    }
+}
+
+fn apply_unary(operator: Operator, value: Value) -> Result<Value> {
+    Ok(match (operator, value) {
+        (Operator::Add, Value::Integer(_) | Value::Float(_) | Value::Duration(_)) => value,
+        (Operator::Subtract, Value::Integer(value)) => {
+            Value::Integer(value.checked_neg().context("integer overflow")?)
+        }
+        (Operator::Subtract, Value::Float(value)) => Value::Float(-value),
+        (Operator::Subtract, Value::Duration(value)) => {
+            Value::Duration(value.checked_neg().context("duration overflow")?)
+        }
+        (Operator::BitNot, Value::Integer(value)) => Value::Integer(!value),
+        (operator, value) => bail!("cannot apply {operator} to {}", value.kind()),
+    })
+}
+
+fn apply_binary(operator: Operator, lhs: Value, rhs: Value) -> Result<Value> {
+    use Operator::*;
+    use Value::{DateTime, Float, Integer};
+
+    let overflow = || anyhow!("integer overflow");
+    Ok(match (operator, lhs, rhs) {
+        (Add, Integer(a), Integer(b)) => Integer(a.checked_add(b).ok_or_else(overflow)?),
+        (Subtract, Integer(a), Integer(b)) => Integer(a.checked_sub(b).ok_or_else(overflow)?),
+        (Multiply, Integer(a), Integer(b)) => Integer(a.checked_mul(b).ok_or_else(overflow)?),
+        (Divide | Remainder, Integer(_), Integer(0)) => bail!("division by zero"),
+        (Divide, Integer(a), Integer(b)) if a.checked_rem(b) == Some(0) => {
+            Integer(a.checked_div(b).ok_or_else(overflow)?)
+        }
+        (Remainder, Integer(a), Integer(b)) => Integer(a.checked_rem(b).ok_or_else(overflow)?),
+        (Power, Integer(a), Integer(b)) if b >= 0 => Integer(
+            u32::try_from(b)
+                .ok()
+                .and_then(|b| a.checked_pow(b))
+                .ok_or_else(overflow)?,
+        ),
+        (BitAnd, Integer(a), Integer(b)) => Integer(a & b),
+        (BitOr, Integer(a), Integer(b)) => Integer(a | b),
+        (BitXor, Integer(a), Integer(b)) => Integer(a ^ b),
+        (ShiftLeft, Integer(a), Integer(b)) => Integer(
+            u32::try_from(b)
+                .ok()
+                .and_then(|b| a.checked_shl(b))
+                .ok_or_else(overflow)?,
+        ),
+        (ShiftRight, Integer(a), Integer(b)) => Integer(
+            u32::try_from(b)
+                .ok()
+                .and_then(|b| a.checked_shr(b))
+                .ok_or_else(overflow)?,
+        ),
+        (BitAnd | BitOr | BitXor | ShiftLeft | ShiftRight, lhs, rhs) => {
+            bail!(
+                "cannot apply {operator} to {} and {}, only to integers",
+                lhs.kind(),
+                rhs.kind()
+            )
+        }
+
+        (Add, DateTime(date_time), Value::Duration(duration))
+        | (Add, Value::Duration(duration), DateTime(date_time)) => DateTime(
+            date_time
+                .checked_add(duration)
+                .context("date out of range")?,
+        ),
+        (Subtract, DateTime(date_time), Value::Duration(duration)) => DateTime(
+            date_time
+                .checked_sub(duration)
+                .context("date out of range")?,
+        ),
+        (Subtract, DateTime(a), DateTime(b)) => Value::Duration(a - b),
+        (Add, Value::Duration(a), Value::Duration(b)) => {
+            Value::Duration(a.checked_add(b).context("duration overflow")?)
+        }
+        (Subtract, Value::Duration(a), Value::Duration(b)) => {
+            Value::Duration(a.checked_sub(b).context("duration overflow")?)
+        }
+        (Divide, Value::Duration(a), Value::Duration(b)) => {
+            if b.is_zero() {
+                bail!("division by zero");
+            }
+            Float(a.as_seconds_f64() / b.as_seconds_f64())
+        }
+        (Multiply, Value::Duration(duration), factor)
+        | (Multiply, factor, Value::Duration(duration))
+            if factor.as_float().is_some() =>
+        {
+            let factor = factor.as_float().unwrap_or_default();
+            scale_duration(duration, factor)?
+        }
+        (Divide, Value::Duration(duration), divisor) if divisor.as_float().is_some() => {
+            let divisor = divisor.as_float().unwrap_or_default();
+            if divisor == 0. {
+                bail!("division by zero");
+            }
+            scale_duration(duration, 1. / divisor)?
+        }
+
+        (operator, lhs, rhs) => match (lhs.as_float(), rhs.as_float()) {
+            (Some(a), Some(b)) => {
+                if b == 0. && matches!(operator, Divide | Remainder) {
+                    bail!("division by zero");
+                }
+                Float(match operator {
+                    Add => a + b,
+                    Subtract => a - b,
+                    Multiply => a * b,
+                    Divide => a / b,
+                    Remainder => a % b,
+                    _ => a.powf(b),
+                })
+            }
+            _ => bail!(
+                "cannot apply {operator} to {} and {}",
+                lhs.kind(),
+                rhs.kind()
+            ),
+        },
+    })
+}
+
+fn scale_duration(duration: Duration, factor: f64) -> Result<Value> {
+    Duration::checked_seconds_f64(duration.as_seconds_f64() * factor)
+        .map(Value::Duration)
+        .context("duration overflow")
+}
+
+fn format_float(value: f64) -> Result<String> {
+    if !value.is_finite() {
+        bail!("the result is not a finite number");
+    }
+    let magnitude = value.abs();
+    if magnitude != 0. && !(1e-9..1e15).contains(&magnitude) {
+        return Ok(format!("{value:e}"));
+    }
+    // Round away the noise of floating point arithmetic, like in `0.1 + 0.2`.
+    let formatted = format!("{value:.10}");
+    let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+    Ok(if formatted == "-0" { "0" } else { formatted }.to_string())
+}
+
+fn format_date_time(date_time: PrimitiveDateTime) -> String {
+    let mut formatted = format!(
+        "{:04}-{:02}-{:02}",
+        date_time.year(),
+        u8::from(date_time.month()),
+        date_time.day()
+    );
+    if date_time.time() != Time::MIDNIGHT {
+        formatted.push_str(&format!(
+            "T{:02}:{:02}",
+            date_time.hour(),
+            date_time.minute()
+        ));
+        if date_time.second() != 0 {
+            formatted.push_str(&format!(":{:02}", date_time.second()));
+        }
+    }
+    formatted
+}
+
+fn format_duration(duration: Duration) -> String {
+    if duration.is_zero() {
+        return "0s".to_string();
+    }
+    let sign = if duration.is_negative() { "-" } else { "" };
+    let duration = duration.abs();
+    let total_seconds = duration.whole_seconds();
+    let mut formatted = sign.to_string();
+    for (amount, unit) in [
+        (total_seconds / 86400, "d"),
+        (total_seconds % 86400 / 3600, "h"),
+        (total_seconds % 3600 / 60, "m"),
+    ] {
+        if amount != 0 {
+            formatted.push_str(&format!("{amount}{unit}"));
+        }
+    }
+    let seconds = total_seconds % 60;
+    let milliseconds = duration.subsec_milliseconds();
+    if milliseconds != 0 {
+        let fraction = format!("{milliseconds:03}");
+        formatted.push_str(&format!("{seconds}.{}s", fraction.trim_end_matches('0')));
+    } else if seconds != 0 {
+        formatted.push_str(&format!("{seconds}s"));
+    }
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn evaluate(expression: &str) -> String {
+        evaluate_expression(expression, datetime!(2024-02-28 09:30))
+            .unwrap_or_else(|error| format!("error: {error}"))
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(evaluate("1 + 2 * 3"), "7");
+        assert_eq!(evaluate("(1 + 2) * 3"), "9");
+        assert_eq!(evaluate("-2 ** 2"), "-4");
+        assert_eq!(evaluate("2 ** 3 ** 2"), "512");
+        assert_eq!(evaluate("7 / 2"), "3.5");
+        assert_eq!(evaluate("8 / 2"), "4");
+        assert_eq!(evaluate("7 % 4"), "3");
+        assert_eq!(evaluate("0.1 + 0.2"), "0.3");
+        assert_eq!(evaluate("1_000 * 1.5e3"), "1500000");
+        assert_eq!(evaluate("2 ** -1"), "0.5");
+        assert_eq!(evaluate("1 / 0"), "error: division by zero");
+        assert_eq!(evaluate("1 +"), "error: incomplete expression");
+        assert_eq!(evaluate("(1 + 2"), "error: unclosed parenthesis");
+        assert_eq!(evaluate("2 ** 200"), "error: integer overflow");
+        assert_eq!(
+            evaluate(&format!("{}1{}", "(".repeat(1000), ")".repeat(1000))),
+            "error: expression is nested too deeply"
+        );
+        assert_eq!(
+            evaluate(&format!("{}1", "-".repeat(1000))),
+            "error: expression is nested too deeply"
+        );
+        assert_eq!(
+            evaluate(&format!("{}1{}", "(".repeat(10), ")".repeat(10))),
+            "1"
+        );
+    }
+
+    #[test]
+    fn test_bitwise_and_radix() {
+        assert_eq!(evaluate("0xff & 0x0f"), "0x0f");
+        assert_eq!(evaluate("0xFF + 1"), "0x100");
+        assert_eq!(evaluate("0b1010 | 0b0101"), "0b1111");
+        assert_eq!(evaluate("0o17 + 1"), "0o20");
+        assert_eq!(evaluate("1 << 4 | 1"), "17");
+        assert_eq!(evaluate("~0 & 0xff"), "0xff");
+        assert_eq!(evaluate("6 ^ 3"), "5");
+        assert_eq!(evaluate("0x10 - 0x20"), "-16");
+        assert_eq!(
+            evaluate("1.5 & 1"),
+            "error: cannot apply & to a number and a number, only to integers"
+        );
+    }
+
+    #[test]
+    fn test_dates_and_durations() {
+        assert_eq!(evaluate("2024-01-31 + 1d"), "2024-02-01");
+        assert_eq!(evaluate("2024-03-01 - 2024-02-01"), "29d");
+        assert_eq!(evaluate("2024-01-01T08:00 + 1h30m"), "2024-01-01T09:30");
+        assert_eq!(evaluate("today + 2w"), "2024-03-13");
+        assert_eq!(evaluate("now - 30m"), "2024-02-28T09:00");
+        assert_eq!(evaluate("1d12h / 2"), "18h");
+        assert_eq!(evaluate("90s * 3"), "4m30s");
+        assert_eq!(evaluate("1h / 15m"), "4");
+        assert_eq!(evaluate("2024-01-01 - 2024-01-02"), "-1d");
+        assert_eq!(evaluate("1.5s + 1m"), "1m1.5s");
+        assert_eq!(
+            evaluate("2024-01-01 + 2024-01-02"),
+            "error: cannot apply + to a date and a date"
+        );
+        assert_eq!(evaluate("yesterday"), "error: unknown name \"yesterday\"");
+    }
+
+    #[test]
+    fn test_format_number_like() {
+        assert_eq!(format_number_like(255, 16, "00"), "ff");
+        assert_eq!(format_number_like(255, 16, "0A"), "FF");
+        assert_eq!(format_number_like(5, 2, "0000"), "0101");
+        assert_eq!(format_number_like(7, 10, "001"), "007");
+        assert_eq!(format_number_like(7, 10, "100"), "7");
+    }
+}
@@ -0,0 +1,591 @@
+//! Piping selections through external commands, like `sort`, `jq` or a
+//! formatter, and replacing them with the commands' output.
+use std::{cmp, ops::Range, process::Stdio};
+
+use anyhow::{Context as _, Result, anyhow};
+use futures::{AsyncWriteExt as _, future};
+use gpui::{
+    App, AppContext as _, Context, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable,
+    Global, Render, Task, WeakEntity, Window,
+};
+use language::{OffsetRangeExt as _, Point};
+use multi_buffer::MultiBufferRow;
+use project::search_history::{QueryInsertionBehavior, SearchHistory, SearchHistoryCursor};
+use ui::prelude::*;
+use workspace::ModalView;
+
+use crate::{
+    Anchor, Editor, SelectionEffects, ToPoint as _,
+    actions::{
+        DiffClipboardWithSelectionData, FilterCommandOutput, FilterSelectionsThroughCommand,
+        MoveToEnd,
+    },
+};
+
+/// The number of commands remembered by the prompt.
+const MAX_HISTORY_LEN: usize = 100;
+
+/// The commands previously run from the prompt, shared by all editors.
+struct FilterCommandHistory(SearchHistory);
+
+impl Global for FilterCommandHistory {}
+
+pub(crate) fn init(cx: &mut App) {
+    cx.set_global(FilterCommandHistory(SearchHistory::new(
+        Some(MAX_HISTORY_LEN),
+        QueryInsertionBehavior::AlwaysInsert,
+    )));
+}
+
+impl Editor {
+    pub fn filter_selections_through_command(
+        &mut self,
+        action: &FilterSelectionsThroughCommand,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.read_only(cx) && action.output != FilterCommandOutput::Diff {
+            return;
+        }
+        if self.project.is_none() {
+            return;
+        }
+        if let Some(command) = action
+            .command
+            .as_ref()
+            .filter(|command| !command.trim().is_empty())
+        {
+            let task = self.run_filter_command(command.clone(), action.output, window, cx);
+            self.detach_and_notify_err(task, window, cx);
+            return;
+        }
+
+        let Some(workspace) = self.workspace() else {
+            return;
+        };
+        let editor = cx.entity().downgrade();
+        let output = action.output;
+        workspace.update(cx, |workspace, cx| {
+            workspace.toggle_modal(window, cx, |window, cx| {
+                FilterCommandModal::new(editor, output, window, cx)
+            });
+        });
+    }
+
+    /// Runs the command once per input, concurrently, and applies the outputs
+    /// once all of them succeeded.
+    fn run_filter_command(
+        &mut self,
+        command: String,
+        output: FilterCommandOutput,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let Some(project) = self.project.clone() else {
+            return Task::ready(Ok(()));
+        };
+
+        let inputs = if output == FilterCommandOutput::Diff {
+            self.diff_filter_input(cx).into_iter().collect::<Vec<_>>()
+        } else {
+            self.filter_inputs(cx)
+        };
+        if inputs.is_empty() {
+            return Task::ready(Ok(()));
+        }
+        let processes = inputs
+            .iter()
+            .map(|_| project.update(cx, |project, cx| project.exec_in_shell(command.clone(), cx)))
+            .collect::<Vec<_>>();
+
+        let executor = cx.background_executor().clone();
+        cx.spawn_in(window, async move |editor, cx| {
+            let outputs =
+                future::join_all(processes.into_iter().zip(&inputs).map(|(process, input)| {
+                    let executor = executor.clone();
+                    let command = command.clone();
+                    let input = input.text.clone();
+                    async move {
+                        let process = process.await?;
+                        executor
+                            .spawn(run_filter_process(process, command, input))
+                            .await
+                    }
+                }))
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>>>()?;
+
+            editor.update_in(cx, |editor, window, cx| {
+                editor.apply_filter_outputs(&command, output, inputs, outputs, window, cx);
+            })
+        })
+    }
+
+    fn apply_filter_outputs(
+        &mut self,
+        command: &str,
+        output: FilterCommandOutput,
+        inputs: Vec<FilterInput>,
+        outputs: Vec<String>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match output {
+            FilterCommandOutput::Replace => {
+                let selects_outputs = inputs.iter().all(|input| input.is_selection);
+                self.transact(window, cx, |editor, window, cx| {
+                    editor.edit(
+                        inputs
+                            .iter()
+                            .zip(outputs)
+                            .map(|(input, output)| {
+                                let output = if input.text.ends_with('\n') {
+                                    output
+                                } else {
+                                    trim_trailing_newline(&output).to_string()
+                                };
+                                (input.range.clone(), output)
+                            })
+                            .collect::<Vec<_>>(),
+                        cx,
+                    );
+                    if selects_outputs {
+                        let ranges = inputs
+                            .iter()
+                            .map(|input| input.range.clone())
+                            .collect::<Vec<_>>();
+                        editor.change_selections(SelectionEffects::default(), window, cx, |s| {
+                            s.select_anchor_ranges(ranges)
+                        });
+                    }
+                });
+            }
+            FilterCommandOutput::InsertBelow => {
+                let snapshot = self.buffer.read(cx).snapshot(cx);
+                let edits = inputs
+                    .iter()
+                    .zip(outputs)
+                    .map(|(input, output)| {
+                        let end = input.range.end.to_point(&snapshot);
+                        let end = if end.column == 0
+                            && end.row > input.range.start.to_point(&snapshot).row
+                        {
+                            end.row - 1
+                        } else {
+                            end.row
+                        };
+                        let line_end = Point::new(end, snapshot.line_len(MultiBufferRow(end)));
+                        (
+                            line_end..line_end,
+                            format!("\n{}", trim_trailing_newline(&output)),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                self.transact(window, cx, |editor, _, cx| {
+                    editor.edit(edits, cx);
+                });
+            }
+            FilterCommandOutput::Diff => {
+                let Some(output) = outputs.into_iter().next() else {
+                    return;
+                };
+                window.dispatch_action(
+                    Box::new(DiffClipboardWithSelectionData {
+                        clipboard_text: output,
+                        editor: cx.entity(),
+                        text_label: Some(format!("`{command}`").into()),
+                    }),
+                    cx,
+                );
+            }
+        }
+    }
+
+    /// The texts piped through a command: each selection, with empty selections
+    /// expanded to their line, or the whole buffer if nothing is selected.
+    fn filter_inputs(&self, cx: &mut Context<Self>) -> Vec<FilterInput> {
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let selections = self.selections.all::<Point>(&self.display_snapshot(cx));
+
+        if selections.iter().all(|selection| selection.is_empty())
+            && self.buffer.read(cx).is_singleton()
+        {
+            let range = Point::zero()..snapshot.max_point();
+            return vec![FilterInput {
+                text: snapshot.text_for_range(range.clone()).collect(),
+                range: snapshot.anchor_before(range.start)..snapshot.anchor_after(range.end),
+                is_selection: false,
+            }];
+        }
+
+        let mut previous_end = None;
+        selections
+            .into_iter()
+            .map(|selection| {
+                if selection.is_empty() {
+                    let row = selection.head().row;
+                    Point::new(row, 0)..Point::new(row, snapshot.line_len(MultiBufferRow(row)))
+                } else {
+                    selection.range()
+                }
+            })
+            .filter(|range| {
+                let overlaps = previous_end.is_some_and(|end| range.start < end);
+                if !overlaps {
+                    previous_end = Some(range.end);
+                }
+                !overlaps
+            })
+            .map(|range| FilterInput {
+                text: snapshot.text_for_range(range.clone()).collect(),
+                range: snapshot.anchor_before(range.start)..snapshot.anchor_after(range.end),
+                is_selection: true,
+            })
+            .collect()
+    }
+
+    /// The text compared with a command's output: the lines of the newest
+    /// selection, or its whole buffer if it's empty, as shown by the diff view.
+    fn diff_filter_input(&self, cx: &mut Context<Self>) -> Option<FilterInput> {
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let selection = self.selections.newest_anchor();
+        let (buffer, range) = snapshot.anchor_range_to_buffer_anchor_range(selection.range())?;
+        let range = range.to_point(buffer);
+        let max_point = buffer.max_point();
+        let range = if range.is_empty() {
+            Point::zero()..max_point
+        } else if range.end.column > 0 {
+            Point::new(range.start.row, 0)..cmp::min(max_point, Point::new(range.end.row + 1, 0))
+        } else {
+            Point::new(range.start.row, 0)..range.end
+        };
+        Some(FilterInput {
+            text: buffer.text_for_range(range).collect(),
+            range: selection.range(),
+            is_selection: false,
+        })
+    }
+}
+
+struct FilterInput {
+    range: Range<Anchor>,
+    text: String,
+    is_selection: bool,
+}
+
+async fn run_filter_process(
+    mut process: smol::process::Command,
+    command: String,
+    input: String,
+) -> Result<String> {
+    process
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = process
+        .spawn()
+        .with_context(|| format!("failed to run `{command}`"))?;
+    let mut stdin = child.stdin.take().context("failed to open stdin")?;
+    let write_input = async move {
+        // Commands may exit without reading all their input, which is fine.
+        stdin.write_all(input.as_bytes()).await.ok();
+        stdin.close().await.ok();
+    };
+    let (_, output) = future::join(write_input, child.output()).await;
+    let output = output.with_context(|| format!("failed to run `{command}`"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!(
+            "`{command}` failed with {}: {}",
+            output.status,
+            stderr.trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn trim_trailing_newline(text: &str) -> &str {
+    text.strip_suffix('\n')
+        .map(|text| text.strip_suffix('\r').unwrap_or(text))
+        .unwrap_or(text)
+}
+
+/// Prompts for the command that selections are piped through.
+struct FilterCommandModal {
+    editor: WeakEntity<Editor>,
+    command_editor: Entity<Editor>,
+    output: FilterCommandOutput,
+    history_cursor: SearchHistoryCursor,
+}
+
+impl FilterCommandModal {
+    fn new(
+        editor: WeakEntity<Editor>,
+        output: FilterCommandOutput,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let command_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Shell command, e.g. sort -u", window, cx);
+            editor
+        });
+        Self {
+            editor,
+            command_editor,
+            output,
+            history_cursor: SearchHistoryCursor::default(),
+        }
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let command = self.command_editor.read(cx).text(cx).trim().to_string();
+        if command.is_empty() {
+            return;
+        }
+        cx.global_mut::<FilterCommandHistory>()
+            .0
+            .add(&mut self.history_cursor, command.clone());
+        let output = self.output;
+        self.editor
+            .update(cx, |editor, cx| {
+                let task = editor.run_filter_command(command, output, window, cx);
+                editor.detach_and_notify_err(task, window, cx);
+            })
+            .ok();
+        cx.emit(DismissEvent);
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, _: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn previous_command(
+        &mut self,
+        _: &menu::SelectPrevious,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let current = self.command_editor.read(cx).text(cx);
+        let Some(command) = cx
+            .global_mut::<FilterCommandHistory>()
+            .0
+            .previous(&mut self.history_cursor, &current)
+            .map(str::to_string)
+        else {
+            return;
+        };
+        self.set_command(&command, window, cx);
+    }
+
+    fn next_command(&mut self, _: &menu::SelectNext, window: &mut Window, cx: &mut Context<Self>) {
+        let command = cx
+            .global_mut::<FilterCommandHistory>()
+            .0
+            .next(&mut self.history_cursor)
+            .map(str::to_string)
+            .or_else(|| self.history_cursor.take_draft());
+        if let Some(command) = command {
+            self.set_command(&command, window, cx);
+        }
+    }
+
+    fn set_command(&mut self, command: &str, window: &mut Window, cx: &mut Context<Self>) {
+        self.command_editor.update(cx, |editor, cx| {
+            editor.set_text(command, window, cx);
+            editor.move_to_end(&MoveToEnd, window, cx);
+        });
+    }
+}
+
+impl EventEmitter<DismissEvent> for FilterCommandModal {}
+impl ModalView for FilterCommandModal {}
+
+impl Focusable for FilterCommandModal {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.command_editor.focus_handle(cx)
+    }
+}
+
+impl Render for FilterCommandModal {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let description = match self.output {
+            FilterCommandOutput::Replace => "Replace the selections with the command's output",
+            FilterCommandOutput::InsertBelow => "Insert the command's output below the selections",
+            FilterCommandOutput::Diff => "Compare the selected lines with the command's output",
+        };
+        v_flex()
+            .w(rems(34.))
+            .elevation_2(cx)
+            .key_context("FilterCommandModal")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .on_action(cx.listener(Self::previous_command))
+            .on_action(cx.listener(Self::next_command))
+            .child(
+                div()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .px_2()
+                    .py_1()
+                    .child(self.command_editor.clone()),
+            )
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .child(Label::new(description).color(Color::Muted)),
+            )
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::{
+        editor_tests::init_test, test::build_editor_with_project,
+        test::editor_test_context::EditorTestContext,
+    };
+    use gpui::{TestAppContext, VisualTestContext};
+    use multi_buffer::MultiBuffer;
+    use project::{FakeFs, Project};
+    use serde_json::json;
+    use settings::SettingsStore;
+    use std::ops::Deref as _;
+
+    #[gpui::test]
+    async fn test_filter_selection_through_command(cx: &mut TestAppContext) {
+        let mut cx = filter_test_context(cx).await;
+
+        cx.set_state("«c\nb\na\nˇ»d\n");
+        run_filter(&mut cx, "sort", FilterCommandOutput::Replace)
+            .await
+            .unwrap();
+        cx.assert_editor_state("«a\nb\nc\nˇ»d\n");
+
+        // A selection without a trailing newline doesn't get the one appended
+        // by the command.
+        cx.set_state("«helloˇ» world\n");
+        run_filter(&mut cx, "tr a-z A-Z", FilterCommandOutput::Replace)
+            .await
+            .unwrap();
+        cx.assert_editor_state("«HELLOˇ» world\n");
+
+        // Without a selection, the whole buffer is piped through the command.
+        cx.set_state("b\nˇa\n");
+        run_filter(&mut cx, "sort", FilterCommandOutput::Replace)
+            .await
+            .unwrap();
+        assert_eq!(cx.buffer_text(), "a\nb\n");
+
+        cx.set_state("«one twoˇ»\nthree\n");
+        run_filter(
+            &mut cx,
+            "wc -w | tr -d ' '",
+            FilterCommandOutput::InsertBelow,
+        )
+        .await
+        .unwrap();
+        assert_eq!(cx.buffer_text(), "one two\n2\nthree\n");
+    }
+
+    #[gpui::test]
+    async fn test_filter_multiple_selections_through_command(cx: &mut TestAppContext) {
+        let mut cx = filter_test_context(cx).await;
+
+        // The command runs once per selection, and empty selections are
+        // expanded to their line.
+        cx.set_state("«fooˇ» bar\nbaz ˇqux\n«quuxˇ»\n");
+        run_filter(&mut cx, "tr a-z A-Z", FilterCommandOutput::Replace)
+            .await
+            .unwrap();
+        cx.assert_editor_state("«FOOˇ» bar\n«BAZ QUXˇ»\n«QUUXˇ»\n");
+
+        cx.set_state("«aˇ»\n«bˇ»\n");
+        run_filter(&mut cx, "cat; echo !", FilterCommandOutput::InsertBelow)
+            .await
+            .unwrap();
+        assert_eq!(cx.buffer_text(), "a\na!\nb\nb!\n");
+    }
+
+    #[gpui::test]
+    async fn test_filter_command_failure(cx: &mut TestAppContext) {
+        let mut cx = filter_test_context(cx).await;
+
+        // When the command fails for any selection, no selection is replaced.
+        cx.set_state("«oneˇ»\n«twoˇ»\n");
+        let error = run_filter(
+            &mut cx,
+            "if grep -q two; then echo oops >&2; exit 3; fi; echo replaced",
+            FilterCommandOutput::Replace,
+        )
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("oops"), "{error:#}");
+        cx.assert_editor_state("«oneˇ»\n«twoˇ»\n");
+    }
+
+    #[gpui::test]
+    async fn test_filter_command_spawn_failure(cx: &mut TestAppContext) {
+        let mut cx = filter_test_context(cx).await;
+        cx.update(|_, cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.terminal.get_or_insert_default().project.shell = Some(
+                        settings::Shell::Program("/nonexistent/filter-command-shell".into()),
+                    );
+                });
+            });
+        });
+
+        cx.set_state("«oneˇ»\n");
+        let error = run_filter(&mut cx, "sort", FilterCommandOutput::Replace)
+            .await
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("failed to run `sort`"),
+            "{error:#}"
+        );
+        cx.assert_editor_state("«oneˇ»\n");
+    }
+
+    /// An editor in a project rooted in an existing directory, which the
+    /// commands run in.
+    async fn filter_test_context(cx: &mut TestAppContext) -> EditorTestContext {
+        init_test(cx, |_| {});
+        cx.executor().allow_parking();
+
+        let root = std::env::temp_dir();
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(&root, json!({ "file": "" })).await;
+        let project = Project::test(fs, [root.as_path()], cx).await;
+        let buffer = project
+            .update(cx, |project, cx| {
+                project.open_local_buffer(root.join("file"), cx)
+            })
+            .await
+            .unwrap();
+        let window = cx.add_window(|window, cx| {
+            build_editor_with_project(
+                project,
+                MultiBuffer::build_from_buffer(buffer, cx),
+                window,
+                cx,
+            )
+        });
+        let editor = window.root(cx).unwrap();
+        let mut cx = VisualTestContext::from_window(*window.deref(), cx);
+        EditorTestContext::for_editor_in(editor, &mut cx).await
+    }
+
+    fn run_filter(
+        cx: &mut EditorTestContext,
+        command: &str,
+        output: FilterCommandOutput,
+    ) -> Task<Result<()>> {
+        cx.update_editor(|editor, window, cx| {
+            editor.run_filter_command(command.to_string(), output, window, cx)
+        })
+    }
+}
@@ -1016,6 +1016,8 @@ impl Item for Editor {
             .detach();
         }
 
+        self.restore_undo_history(cx);
+
         // Load persisted folds if this editor doesn't already have folds.
         // This handles manually-opened files (not workspace restoration).
         let display_snapshot = self
@@ -1144,13 +1146,18 @@ impl SerializableItem for Editor {
         _window: &mut Window,
         cx: &mut App,
     ) -> Task<Result<()>> {
-        workspace::delete_unloaded_items(
+        let prune_undo_histories = Editor::prune_undo_histories(cx);
+        let delete_unloaded_items = workspace::delete_unloaded_items(
             alive_items,
             workspace_id,
             "editors",
             &EditorDb::global(cx),
             cx,
-        )
+        );
+        cx.background_spawn(async move {
+            prune_undo_histories.await?;
+            delete_unloaded_items.await
+        })
     }
 
     fn deserialize(
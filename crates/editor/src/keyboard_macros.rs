@@ -0,0 +1,474 @@
+//! Mode-agnostic keyboard macros, recorded from the actions dispatched by
+//! keystrokes and the text typed in editors.
+use std::{collections::VecDeque, ops::Range, sync::Arc};
+
+use gpui::{
+    Action, App, Context, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, Global,
+    Render, WeakEntity, Window,
+};
+use settings::{KeyboardMacroStep, Settings, update_settings_file};
+use ui::prelude::*;
+use workspace::{ModalView, Toast, notifications::NotificationId};
+
+use crate::{
+    Anchor, Editor, EditorEvent, EditorSettings, HighlightKey, SelectionEffects,
+    actions::{
+        MacroPlaybackTarget, PlayMacro, SaveLastMacro, ShowCharacterPalette, ToggleMacroRecording,
+    },
+};
+
+/// The maximum number of steps played by a single playback, which stops macros
+/// that end up playing themselves.
+const MAX_PLAYED_STEPS: usize = 10_000;
+
+pub(crate) fn init(cx: &mut App) {
+    cx.set_global(KeyboardMacros::default());
+
+    cx.observe_keystrokes(|event, _, cx| {
+        if let Some(action) = event.action.as_ref() {
+            KeyboardMacros::observe_action(action.as_ref(), cx);
+        }
+    })
+    .detach();
+
+    cx.observe_new(|editor: &mut Editor, _, cx| {
+        if !editor.mode().is_full() {
+            return;
+        }
+        cx.subscribe_self(|_, event: &EditorEvent, cx| {
+            if let EditorEvent::InputHandled {
+                utf16_range_to_replace,
+                text,
+            } = event
+            {
+                KeyboardMacros::observe_insertion(text, utf16_range_to_replace.clone(), cx);
+            }
+        })
+        .detach();
+    })
+    .detach();
+}
+
+#[derive(Debug)]
+pub enum MacroStep {
+    Action(Box<dyn Action>),
+    Insertion {
+        text: Arc<str>,
+        utf16_range_to_replace: Option<Range<isize>>,
+    },
+}
+
+impl Clone for MacroStep {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Action(action) => Self::Action(action.boxed_clone()),
+            Self::Insertion {
+                text,
+                utf16_range_to_replace,
+            } => Self::Insertion {
+                text: text.clone(),
+                utf16_range_to_replace: utf16_range_to_replace.clone(),
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct KeyboardMacros {
+    recording: Option<Vec<MacroStep>>,
+    last_macro: Option<Arc<[MacroStep]>>,
+    playing: bool,
+}
+
+impl Global for KeyboardMacros {}
+
+impl KeyboardMacros {
+    fn observe_action(action: &dyn Action, cx: &mut App) {
+        let Some(macros) = cx.try_global::<Self>() else {
+            return;
+        };
+        if macros.playing || macros.recording.is_none() || !should_record(action) {
+            return;
+        }
+        let macros = cx.global_mut::<Self>();
+        if let Some(recording) = macros.recording.as_mut() {
+            recording.push(MacroStep::Action(action.boxed_clone()));
+        }
+    }
+
+    fn observe_insertion(
+        text: &Arc<str>,
+        utf16_range_to_replace: Option<Range<isize>>,
+        cx: &mut App,
+    ) {
+        let Some(macros) = cx.try_global::<Self>() else {
+            return;
+        };
+        if macros.playing || macros.recording.is_none() {
+            return;
+        }
+        let macros = cx.global_mut::<Self>();
+        if let Some(recording) = macros.recording.as_mut() {
+            recording.push(MacroStep::Insertion {
+                text: text.clone(),
+                utf16_range_to_replace,
+            });
+        }
+    }
+}
+
+fn should_record(action: &dyn Action) -> bool {
+    if ToggleMacroRecording.partial_eq(action)
+        || SaveLastMacro.partial_eq(action)
+        // skip so that we don't leave the character palette open
+        || ShowCharacterPalette.partial_eq(action)
+    {
+        return false;
+    }
+    // Playing the last macro from within a recording would make it play itself.
+    action
+        .as_any()
+        .downcast_ref::<PlayMacro>()
+        .is_none_or(|play| play.name.is_some())
+}
+
+/// Converts a step saved in the settings into one that can be played.
+fn step_from_settings(step: &KeyboardMacroStep, cx: &App) -> anyhow::Result<MacroStep> {
+    match step {
+        KeyboardMacroStep::Action { action, args } => {
+            Ok(MacroStep::Action(cx.build_action(action, args.clone())?))
+        }
+        KeyboardMacroStep::Text { text } => Ok(MacroStep::Insertion {
+            text: text.as_str().into(),
+            utf16_range_to_replace: None,
+        }),
+    }
+}
+
+/// The steps of a macro saved in the `keyboard_macros` setting.
+fn saved_macro_steps(name: &str, cx: &App) -> Option<Vec<MacroStep>> {
+    let Some(steps) = EditorSettings::get_global(cx).keyboard_macros.get(name) else {
+        log::error!("No keyboard macro named {name:?} in the settings");
+        return None;
+    };
+    steps
+        .iter()
+        .map(|step| step_from_settings(step, cx))
+        .collect::<anyhow::Result<_>>()
+        .inspect_err(|error| log::error!("Invalid keyboard macro {name:?}: {error}"))
+        .ok()
+}
+
+/// Converts a recorded step into one that can be saved in the settings. The
+/// arguments of actions are taken from the key bindings they were dispatched by.
+fn step_to_settings(step: &MacroStep, window: &Window) -> KeyboardMacroStep {
+    match step {
+        MacroStep::Action(action) => KeyboardMacroStep::Action {
+            action: action.name().to_string(),
+            args: window
+                .highest_precedence_binding_for_action(action.as_ref())
+                .and_then(|binding| binding.action_input())
+                .and_then(|input| serde_json::from_str(&input).ok()),
+        },
+        MacroStep::Insertion { text, .. } => KeyboardMacroStep::Text {
+            text: text.to_string(),
+        },
+    }
+}
+
+enum PlaybackStep {
+    Play(MacroStep),
+    /// Replaces the selections with the given range.
+    Select(Range<Anchor>),
+    /// Remembers the selections left by the preceding steps.
+    CollectSelections,
+    /// Selects all the remembered selections.
+    RestoreSelections,
+}
+
+struct Playback {
+    editor: WeakEntity<Editor>,
+    steps: VecDeque<PlaybackStep>,
+    collected_selections: Vec<Range<Anchor>>,
+    played_steps: usize,
+}
+
+impl Playback {
+    fn play_next(mut self, window: &mut Window, cx: &mut App) {
+        let step = if self.played_steps < MAX_PLAYED_STEPS {
+            self.steps.pop_front()
+        } else {
+            log::error!("Aborting keyboard macro after {MAX_PLAYED_STEPS} steps");
+            None
+        };
+        self.played_steps += 1;
+        let (Some(step), Some(editor)) = (step, self.editor.upgrade()) else {
+            self.finish(cx);
+            return;
+        };
+
+        match step {
+            PlaybackStep::Play(MacroStep::Action(action)) => {
+                // Saved macros played by this one are expanded in place, since
+                // `PlayMacro` is ignored while a macro is playing.
+                match action.as_any().downcast_ref::<PlayMacro>() {
+                    Some(PlayMacro {
+                        name: Some(name),
+                        times,
+                        target: MacroPlaybackTarget::Selections,
+                    }) => {
+                        let steps = saved_macro_steps(name, cx).unwrap_or_default();
+                        for _ in 0..times.unwrap_or(1).max(1) {
+                            for step in steps.iter().rev() {
+                                self.steps.push_front(PlaybackStep::Play(step.clone()));
+                            }
+                        }
+                    }
+                    _ => window.dispatch_action(action, cx),
+                }
+            }
+            PlaybackStep::Play(MacroStep::Insertion {
+                text,
+                utf16_range_to_replace,
+            }) => editor.update(cx, |editor, cx| {
+                editor.replay_insert_event(&text, utf16_range_to_replace, window, cx)
+            }),
+            PlaybackStep::Select(range) => editor.update(cx, |editor, cx| {
+                editor.change_selections(SelectionEffects::no_scroll(), window, cx, |s| {
+                    s.select_anchor_ranges([range])
+                })
+            }),
+            PlaybackStep::CollectSelections => {
+                self.collected_selections
+                    .extend(editor.read(cx).selections.disjoint_anchor_ranges());
+            }
+            PlaybackStep::RestoreSelections => {
+                let selections = std::mem::take(&mut self.collected_selections);
+                editor.update(cx, |editor, cx| {
+                    editor.change_selections(Default::default(), window, cx, |s| {
+                        s.select_anchor_ranges(selections)
+                    })
+                });
+            }
+        }
+        window.defer(cx, move |window, cx| self.play_next(window, cx));
+    }
+
+    fn finish(self, cx: &mut App) {
+        cx.global_mut::<KeyboardMacros>().playing = false;
+        if let Some(editor) = self.editor.upgrade() {
+            editor.update(cx, |editor, cx| {
+                editor
+                    .buffer()
+                    .update(cx, |buffer, cx| buffer.finalize_last_transaction(cx))
+            });
+        }
+    }
+}
+
+impl Editor {
+    pub fn toggle_macro_recording(
+        &mut self,
+        _: &ToggleMacroRecording,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let macros = cx.global_mut::<KeyboardMacros>();
+        let message = if let Some(recording) = macros.recording.take() {
+            let step_count = recording.len();
+            if !recording.is_empty() {
+                macros.last_macro = Some(recording.into());
+            }
+            match step_count {
+                1 => "Recorded a macro with 1 step".to_string(),
+                step_count => format!("Recorded a macro with {step_count} steps"),
+            }
+        } else {
+            macros.recording = Some(Vec::new());
+            "Recording macro…".to_string()
+        };
+
+        if let Some(workspace) = self.workspace() {
+            workspace.update(cx, |workspace, cx| {
+                struct KeyboardMacroRecording;
+
+                workspace.show_toast(
+                    Toast::new(NotificationId::unique::<KeyboardMacroRecording>(), message)
+                        .autohide(),
+                    cx,
+                )
+            });
+        }
+    }
+
+    pub fn play_macro(&mut self, action: &PlayMacro, window: &mut Window, cx: &mut Context<Self>) {
+        if cx.global::<KeyboardMacros>().playing {
+            return;
+        }
+        let steps = match &action.name {
+            Some(name) => {
+                let Some(steps) = saved_macro_steps(name, cx) else {
+                    return;
+                };
+                steps
+            }
+            None => {
+                let Some(last_macro) = cx.global::<KeyboardMacros>().last_macro.as_ref() else {
+                    return;
+                };
+                last_macro.to_vec()
+            }
+        };
+        let times = action.times.unwrap_or(1).max(1);
+        let steps = &steps;
+        let repeated_steps =
+            || (0..times).flat_map(move |_| steps.iter().cloned().map(PlaybackStep::Play));
+
+        let mut playback_steps = VecDeque::new();
+        match action.target {
+            MacroPlaybackTarget::Selections => playback_steps.extend(repeated_steps()),
+            MacroPlaybackTarget::EachCursor | MacroPlaybackTarget::EachSearchMatch => {
+                let ranges: Vec<Range<Anchor>> = if action.target == MacroPlaybackTarget::EachCursor
+                {
+                    self.selections.disjoint_anchor_ranges().collect()
+                } else {
+                    self.background_highlights
+                        .get(&HighlightKey::BufferSearchHighlights)
+                        .map(|(_, ranges)| ranges.to_vec())
+                        .unwrap_or_default()
+                };
+                if ranges.is_empty() {
+                    return;
+                }
+                for range in ranges {
+                    playback_steps.push_back(PlaybackStep::Select(range));
+                    playback_steps.extend(repeated_steps());
+                    playback_steps.push_back(PlaybackStep::CollectSelections);
+                }
+                playback_steps.push_back(PlaybackStep::RestoreSelections);
+            }
+        }
+
+        self.buffer
+            .update(cx, |buffer, cx| buffer.finalize_last_transaction(cx));
+        cx.global_mut::<KeyboardMacros>().playing = true;
+        let playback = Playback {
+            editor: cx.entity().downgrade(),
+            steps: playback_steps,
+            collected_selections: Vec::new(),
+            played_steps: 0,
+        };
+        window.defer(cx, move |window, cx| playback.play_next(window, cx));
+    }
+
+    pub fn save_last_macro(
+        &mut self,
+        _: &SaveLastMacro,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(last_macro) = cx.global::<KeyboardMacros>().last_macro.clone() else {
+            return;
+        };
+        let Some(workspace) = self.workspace() else {
+            return;
+        };
+        let steps = last_macro
+            .iter()
+            .map(|step| step_to_settings(step, window))
+            .collect();
+        workspace.update(cx, |workspace, cx| {
+            let fs = workspace.app_state().fs.clone();
+            workspace.toggle_modal(window, cx, |window, cx| {
+                SaveMacroModal::new(steps, fs, window, cx)
+            });
+        });
+    }
+}
+
+/// Asks for the name under which the last recorded macro is saved.
+struct SaveMacroModal {
+    name_editor: Entity<Editor>,
+    steps: Vec<KeyboardMacroStep>,
+    fs: Arc<dyn fs::Fs>,
+}
+
+impl SaveMacroModal {
+    fn new(
+        steps: Vec<KeyboardMacroStep>,
+        fs: Arc<dyn fs::Fs>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let name_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Macro name", window, cx);
+            editor
+        });
+        Self {
+            name_editor,
+            steps,
+            fs,
+        }
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, _: &mut Window, cx: &mut Context<Self>) {
+        let name = self.name_editor.read(cx).text(cx).trim().to_string();
+        if name.is_empty() {
+            return;
+        }
+        let steps = std::mem::take(&mut self.steps);
+        update_settings_file(self.fs.clone(), cx, move |settings, _| {
+            settings
+                .editor
+                .keyboard_macros
+                .get_or_insert_default()
+                .insert(name, steps);
+        });
+        cx.emit(DismissEvent);
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, _: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+}
+
+impl EventEmitter<DismissEvent> for SaveMacroModal {}
+impl ModalView for SaveMacroModal {}
+
+impl Focusable for SaveMacroModal {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.name_editor.focus_handle(cx)
+    }
+}
+
+impl Render for SaveMacroModal {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let step_count = self.steps.len();
+        v_flex()
+            .w(rems(24.))
+            .elevation_2(cx)
+            .key_context("SaveMacroModal")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .child(
+                div()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .px_2()
+                    .py_1()
+                    .child(self.name_editor.clone()),
+            )
+            .child(
+                h_flex().px_2().py_1().child(
+                    Label::new(match step_count {
+                        1 => "Save the last macro (1 step) in the settings".to_string(),
+                        step_count => {
+                            format!("Save the last macro ({step_count} steps) in the settings")
+                        }
+                    })
+                    .color(Color::Muted),
+                ),
+            )
+    }
+}
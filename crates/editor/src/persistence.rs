@@ -10,10 +10,13 @@ use db::{
 };
 use fs::MTime;
 use itertools::Itertools as _;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{
     path::{Path, PathBuf},
     sync::Arc,
 };
+use text::{HistoryEdit, UndoHistory};
 
 use workspace::{ItemId, WorkspaceDb, WorkspaceId};
 
@@ -126,6 +129,13 @@ impl Domain for EditorDb {
     //   start_fingerprint: Option<String>,
     //   end_fingerprint: Option<String>,
     // )
+    //
+    // undo_histories(
+    //   path: PathBuf,
+    //   content_hash: String,
+    //   history: String,
+    //   timestamp: String,
+    // )
 
     const MIGRATIONS: &[&str] = &[
         sql! (
@@ -223,6 +233,16 @@ impl Domain for EditorDb {
                 PRIMARY KEY(workspace_id, path, start)
             );
         ),
+        // Undo histories are keyed by file path alone, so that they're restored
+        // regardless of the workspace the file is opened in.
+        sql! (
+            CREATE TABLE undo_histories (
+                path TEXT NOT NULL PRIMARY KEY,
+                content_hash TEXT NOT NULL,
+                history TEXT NOT NULL,
+                timestamp TEXT DEFAULT CURRENT_TIMESTAMP NOT NULL
+            ) STRICT;
+        ),
     ];
 }
 
@@ -396,6 +416,42 @@ VALUES {placeholders};
         .await
     }
 
+    query! {
+        pub fn get_undo_history(path: &Path) -> Result<Option<(String, String)>> {
+            SELECT content_hash, history
+            FROM undo_histories
+            WHERE path = ?
+        }
+    }
+
+    query! {
+        pub async fn save_undo_history(path: &Path, content_hash: String, history: String) -> Result<()> {
+            INSERT OR REPLACE INTO undo_histories (path, content_hash, history, timestamp)
+            VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)
+        }
+    }
+
+    query! {
+        pub fn get_undo_history_paths() -> Result<Vec<PathBuf>> {
+            SELECT path
+            FROM undo_histories
+        }
+    }
+
+    query! {
+        pub async fn delete_stale_undo_histories(age_modifier: &str) -> Result<()> {
+            DELETE FROM undo_histories
+            WHERE timestamp < datetime(CURRENT_TIMESTAMP, ?)
+        }
+    }
+
+    query! {
+        pub async fn delete_undo_history(path: &Path) -> Result<()> {
+            DELETE FROM undo_histories
+            WHERE path = ?
+        }
+    }
+
     pub async fn delete_file_folds(
         &self,
         workspace_id: WorkspaceId,
@@ -410,6 +466,109 @@ VALUES {placeholders};
     }
 }
 
+/// Identifies the text an undo history applies to.
+pub(crate) fn undo_history_content_hash(text: &text::BufferSnapshot) -> String {
+    let mut hasher = Sha256::new();
+    for chunk in text.as_rope().chunks() {
+        hasher.update(chunk.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedUndoHistory {
+    undo_stack: Vec<Vec<SerializedHistoryEdit>>,
+    redo_stack: Vec<Vec<SerializedHistoryEdit>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedHistoryEdit {
+    start: usize,
+    end: usize,
+    old_text: String,
+    new_text: String,
+}
+
+/// Serializes the given history, dropping its oldest undo transactions and then its
+/// furthest redo transactions until it approximately fits in `max_size` bytes.
+///
+/// Returns `None` if no transaction is left.
+pub(crate) fn serialize_undo_history(mut history: UndoHistory, max_size: usize) -> Option<String> {
+    fn transaction_size(edits: &[HistoryEdit]) -> usize {
+        edits
+            .iter()
+            .map(|edit| edit.old_text.len() + edit.new_text.len() + 64)
+            .sum()
+    }
+
+    let mut size = history
+        .undo_stack
+        .iter()
+        .chain(&history.redo_stack)
+        .map(|edits| transaction_size(edits))
+        .sum::<usize>();
+    let mut dropped_undos = 0;
+    while size > max_size && dropped_undos < history.undo_stack.len() {
+        size -= transaction_size(&history.undo_stack[dropped_undos]);
+        dropped_undos += 1;
+    }
+    history.undo_stack.drain(..dropped_undos);
+    let mut dropped_redos = 0;
+    while size > max_size && dropped_redos < history.redo_stack.len() {
+        size -= transaction_size(&history.redo_stack[dropped_redos]);
+        dropped_redos += 1;
+    }
+    history.redo_stack.drain(..dropped_redos);
+    if history.is_empty() {
+        return None;
+    }
+
+    let serialize_stack = |stack: Vec<Vec<HistoryEdit>>| {
+        stack
+            .into_iter()
+            .map(|edits| {
+                edits
+                    .into_iter()
+                    .map(|edit| SerializedHistoryEdit {
+                        start: edit.range.start,
+                        end: edit.range.end,
+                        old_text: edit.old_text,
+                        new_text: edit.new_text,
+                    })
+                    .collect()
+            })
+            .collect()
+    };
+    serde_json::to_string(&SerializedUndoHistory {
+        undo_stack: serialize_stack(history.undo_stack),
+        redo_stack: serialize_stack(history.redo_stack),
+    })
+    .ok()
+}
+
+pub(crate) fn deserialize_undo_history(history: &str) -> Result<UndoHistory> {
+    let history: SerializedUndoHistory = serde_json::from_str(history)?;
+    let deserialize_stack = |stack: Vec<Vec<SerializedHistoryEdit>>| {
+        stack
+            .into_iter()
+            .map(|edits| {
+                edits
+                    .into_iter()
+                    .map(|edit| HistoryEdit {
+                        range: edit.start..edit.end,
+                        old_text: edit.old_text,
+                        new_text: edit.new_text,
+                    })
+                    .collect()
+            })
+            .collect()
+    };
+    Ok(UndoHistory {
+        undo_stack: deserialize_stack(history.undo_stack),
+        redo_stack: deserialize_stack(history.redo_stack),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -504,6 +663,57 @@ mod tests {
     // 3. Reopen Zed - folds should be restored at their NEW correct positions
     // The search uses contains_str_at() to find fingerprints in the buffer.
 
+    #[gpui::test]
+    async fn test_save_and_get_undo_history(cx: &mut gpui::TestAppContext) {
+        let editor_db = cx.update(|cx| EditorDb::global(cx));
+        let path = Path::new("/tmp/test_undo_history.rs");
+        let edit = |range: std::ops::Range<usize>, old_text: &str, new_text: &str| HistoryEdit {
+            range,
+            old_text: old_text.into(),
+            new_text: new_text.into(),
+        };
+        let history = UndoHistory {
+            undo_stack: vec![
+                vec![edit(0..0, "", &"a".repeat(100))],
+                vec![edit(0..3, "aaa", "b"), edit(10..10, "", "c")],
+            ],
+            redo_stack: vec![vec![edit(1..2, "a", "d")]],
+        };
+
+        let serialized = serialize_undo_history(history.clone(), usize::MAX).unwrap();
+        editor_db
+            .save_undo_history(path, "hash".into(), serialized)
+            .await
+            .unwrap();
+        let (content_hash, serialized) = editor_db.get_undo_history(path).unwrap().unwrap();
+        assert_eq!(content_hash, "hash");
+        assert_eq!(deserialize_undo_history(&serialized).unwrap(), history);
+
+        // Recently saved histories aren't stale.
+        editor_db
+            .delete_stale_undo_histories("-1 days")
+            .await
+            .unwrap();
+        assert_eq!(
+            editor_db.get_undo_history_paths().unwrap(),
+            vec![path.to_path_buf()]
+        );
+
+        // The oldest transactions are dropped first to fit the size limit.
+        let serialized = serialize_undo_history(history.clone(), 300).unwrap();
+        assert_eq!(
+            deserialize_undo_history(&serialized).unwrap(),
+            UndoHistory {
+                undo_stack: history.undo_stack[1..].to_vec(),
+                redo_stack: history.redo_stack.clone(),
+            }
+        );
+        assert_eq!(serialize_undo_history(history, 0), None);
+
+        editor_db.delete_undo_history(path).await.unwrap();
+        assert_eq!(editor_db.get_undo_history(path).unwrap(), None);
+    }
+
     #[gpui::test]
     async fn test_save_and_get_file_folds(cx: &mut gpui::TestAppContext) {
         let db = cx.update(|cx| workspace::WorkspaceDb::global(cx));
@@ -0,0 +1,596 @@
+//! A preview of the edits of a rename across the project, where each of them
+//! can be accepted or rejected before the accepted ones are applied.
+use std::{
+    any::{Any, TypeId},
+    ops::Range,
+    sync::Arc,
+};
+
+use gpui::{
+    AnyElement, AnyEntity, App, Context, Empty, Entity, EventEmitter, FocusHandle, Focusable,
+    WeakEntity, Window,
+};
+use language::{Buffer, BufferSnapshot, Capability, Point};
+use multi_buffer::{Anchor, MultiBuffer, PathKey, ToOffset as _};
+use project::{Project, ProjectEdits};
+use settings::Settings;
+use text::ToOffset as _;
+use ui::{Checkbox, KeyBinding, Tooltip, prelude::*};
+use util::ResultExt as _;
+use workspace::{
+    Item, ItemNavHistory, Workspace,
+    item::{ItemEvent, TabContentParams},
+};
+
+use crate::{
+    Editor, EditorSettings, HighlightKey,
+    actions::{ApplyRenamePreview, ToggleRenamePreviewEdits},
+    display_map::{BlockContext, BlockPlacement, BlockProperties, BlockStyle},
+    editor_settings::RenamePreviewMode,
+    multibuffer_context_lines,
+};
+
+/// Whether the edits of a rename should be previewed before being applied.
+pub(crate) fn should_preview_rename(edits: &ProjectEdits, force: bool, cx: &App) -> bool {
+    if edits.is_empty() {
+        return false;
+    }
+    force
+        || match EditorSettings::get_global(cx).rename_preview {
+            RenamePreviewMode::Never => false,
+            RenamePreviewMode::MultipleFiles => edits.0.len() > 1,
+            RenamePreviewMode::Always => true,
+        }
+}
+
+/// Makes the workspace's project propose the edits that language servers
+/// request when files are renamed, when they're to be previewed. Other edits
+/// are still applied before the files are renamed.
+pub(crate) fn register(
+    workspace: &mut Workspace,
+    window: &mut Window,
+    cx: &mut Context<Workspace>,
+) {
+    let project = workspace.project().clone();
+    project.update(cx, |project, _| {
+        project.set_propose_will_rename_edits(|edits, cx| should_preview_rename(edits, false, cx))
+    });
+    cx.subscribe_in(
+        &project,
+        window,
+        |workspace, _: &Entity<Project>, event, window, cx| {
+            let project::Event::WillRenameEditsProposed(edits) = event else {
+                return;
+            };
+            RenamePreview::deploy(
+                workspace
+                    .active_item_as::<Editor>(cx)
+                    .map(|editor| editor.downgrade())
+                    .unwrap_or_else(WeakEntity::new_invalid),
+                edits.clone(),
+                "Rename".to_string(),
+                workspace,
+                window,
+                cx,
+            );
+        },
+    )
+    .detach();
+}
+
+/// The edits of a rename touching the same lines of a buffer.
+struct RenamePreviewHunk {
+    buffer: Entity<Buffer>,
+    /// The edited lines, in the preview's multibuffer.
+    range: Range<Anchor>,
+    edits: Vec<(Range<text::Anchor>, Arc<str>)>,
+    /// The highlighted ranges of the edits, in the preview's multibuffer.
+    edit_ranges: Vec<Range<Anchor>>,
+    /// The edited lines as they'll be once the edits are applied.
+    new_lines: Vec<SharedString>,
+    accepted: bool,
+}
+
+pub struct RenamePreview {
+    editor: Entity<Editor>,
+    /// The editor the rename was performed in.
+    source_editor: WeakEntity<Editor>,
+    workspace: WeakEntity<Workspace>,
+    hunks: Vec<RenamePreviewHunk>,
+    title: SharedString,
+}
+
+impl RenamePreview {
+    /// Opens a preview of the given edits, which haven't been applied yet and
+    /// from which the accepted ones can be applied.
+    pub(crate) fn deploy(
+        source_editor: WeakEntity<Editor>,
+        edits: ProjectEdits,
+        title: String,
+        workspace: &mut Workspace,
+        window: &mut Window,
+        cx: &mut Context<Workspace>,
+    ) {
+        let mut entries = edits.0.clone();
+        entries
+            .sort_unstable_by_key(|(buffer, _)| buffer.read(cx).file().map(|f| f.path().clone()));
+        let project = workspace.project().clone();
+        let workspace_handle = cx.entity().downgrade();
+        let preview = cx.new(|cx| {
+            let multibuffer = cx.new(|_| MultiBuffer::new(Capability::ReadOnly));
+            let editor = cx.new(|cx| {
+                let mut editor =
+                    Editor::for_multibuffer(multibuffer.clone(), Some(project), window, cx);
+                editor.set_read_only(true);
+                editor
+            });
+
+            let mut hunks = Vec::new();
+            for (buffer, edits) in entries {
+                let snapshot = buffer.read(cx).snapshot();
+                let mut edits = edits
+                    .into_iter()
+                    .map(|(range, text)| {
+                        (
+                            range.start.to_offset(&snapshot)..range.end.to_offset(&snapshot),
+                            text,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                edits.sort_by_key(|(range, _)| range.start);
+                let buffer_hunks = group_edits_by_lines(&snapshot, edits);
+                multibuffer.update(cx, |multibuffer, cx| {
+                    multibuffer.set_excerpts_for_path(
+                        PathKey::for_buffer(&buffer, cx),
+                        buffer.clone(),
+                        buffer_hunks.iter().map(|(lines, _, _)| lines.clone()),
+                        multibuffer_context_lines(cx),
+                        cx,
+                    );
+                    let snapshot = multibuffer.snapshot(cx);
+                    let buffer_snapshot = buffer.read(cx).snapshot();
+                    for (lines, edits, new_lines) in buffer_hunks {
+                        let lines = buffer_snapshot.anchor_range_inside(lines);
+                        let Some(start) = snapshot.anchor_in_buffer(lines.start) else {
+                            continue;
+                        };
+                        let Some(end) = snapshot.anchor_in_buffer(lines.end) else {
+                            continue;
+                        };
+                        let edit_ranges = edits
+                            .iter()
+                            .filter_map(|(range, _)| {
+                                Some(
+                                    snapshot.anchor_in_buffer(range.start)?
+                                        ..snapshot.anchor_in_buffer(range.end)?,
+                                )
+                            })
+                            .collect();
+                        hunks.push(RenamePreviewHunk {
+                            buffer: buffer.clone(),
+                            range: start..end,
+                            edits,
+                            edit_ranges,
+                            new_lines,
+                            accepted: true,
+                        });
+                    }
+                });
+            }
+
+            Self {
+                editor,
+                source_editor,
+                workspace: workspace_handle,
+                hunks,
+                title: title.into(),
+            }
+        });
+
+        // Don't lose the rename when none of its edits could be shown.
+        if preview.read(cx).hunks.is_empty() {
+            let transaction = edits.apply(cx);
+            let source_editor = preview.read(cx).source_editor.clone();
+            let title = preview.read(cx).title.to_string();
+            cx.spawn_in(window, async move |workspace, cx| {
+                Editor::open_project_transaction(&source_editor, workspace, transaction, title, cx)
+                    .await
+            })
+            .detach_and_log_err(cx);
+            return;
+        }
+        preview.update(cx, |preview, cx| preview.insert_hunk_blocks(cx));
+        workspace.add_item_to_active_pane(Box::new(preview), None, true, window, cx);
+    }
+
+    fn insert_hunk_blocks(&mut self, cx: &mut Context<Self>) {
+        let preview = cx.entity().downgrade();
+        let blocks = self
+            .hunks
+            .iter()
+            .enumerate()
+            .map(|(ix, hunk)| {
+                let preview = preview.clone();
+                BlockProperties {
+                    placement: BlockPlacement::Above(hunk.range.start),
+                    height: Some(hunk.new_lines.len().max(1) as u32),
+                    style: BlockStyle::Flex,
+                    render: Arc::new(move |cx| render_hunk_block(&preview, ix, cx)),
+                    priority: 0,
+                }
+            })
+            .collect::<Vec<_>>();
+        self.editor.update(cx, |editor, cx| {
+            editor.insert_blocks(blocks, None, cx);
+        });
+        self.update_highlights(cx);
+    }
+
+    fn update_highlights(&mut self, cx: &mut Context<Self>) {
+        let ranges = self
+            .hunks
+            .iter()
+            .filter(|hunk| hunk.accepted)
+            .flat_map(|hunk| hunk.edit_ranges.iter().cloned())
+            .collect::<Vec<_>>();
+        self.editor.update(cx, |editor, cx| {
+            editor.highlight_background(
+                HighlightKey::Rename,
+                &ranges,
+                |_, theme| theme.status().deleted_background,
+                cx,
+            );
+        });
+        cx.notify();
+    }
+
+    pub(crate) fn toggle_hunk(&mut self, ix: usize, cx: &mut Context<Self>) {
+        if let Some(hunk) = self.hunks.get_mut(ix) {
+            hunk.accepted = !hunk.accepted;
+            self.update_highlights(cx);
+        }
+    }
+
+    fn set_all_accepted(&mut self, accepted: bool, cx: &mut Context<Self>) {
+        for hunk in &mut self.hunks {
+            hunk.accepted = accepted;
+        }
+        self.update_highlights(cx);
+    }
+
+    fn toggle_edits(
+        &mut self,
+        _: &ToggleRenamePreviewEdits,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let snapshot = self.editor.read(cx).buffer().read(cx).snapshot(cx);
+        let selections = self
+            .editor
+            .read(cx)
+            .selections
+            .disjoint_anchors_arc()
+            .iter()
+            .map(|selection| {
+                selection.start.to_offset(&snapshot)..selection.end.to_offset(&snapshot)
+            })
+            .collect::<Vec<_>>();
+        let intersecting = self
+            .hunks
+            .iter()
+            .enumerate()
+            .filter(|(_, hunk)| {
+                let range =
+                    hunk.range.start.to_offset(&snapshot)..hunk.range.end.to_offset(&snapshot);
+                selections
+                    .iter()
+                    .any(|selection| selection.start <= range.end && range.start <= selection.end)
+            })
+            .map(|(ix, _)| ix)
+            .collect::<Vec<_>>();
+        // Accept all of the hunks unless they're all accepted already, like toggling a checkbox.
+        let accepted = intersecting.iter().any(|ix| !self.hunks[*ix].accepted);
+        for ix in intersecting {
+            self.hunks[ix].accepted = accepted;
+        }
+        self.update_highlights(cx);
+    }
+
+    pub(crate) fn apply(
+        &mut self,
+        _: &ApplyRenamePreview,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let mut edits = ProjectEdits::default();
+        for hunk in self.hunks.iter().filter(|hunk| hunk.accepted) {
+            edits.push(hunk.buffer.clone(), hunk.edits.clone());
+        }
+        let project_transaction = edits.apply(cx);
+        cx.emit(ItemEvent::CloseItem);
+
+        let source_editor = self.source_editor.clone();
+        let workspace = self.workspace.clone();
+        let title = self.title.to_string();
+        cx.spawn_in(window, async move |_, cx| {
+            Editor::open_project_transaction(
+                &source_editor,
+                workspace,
+                project_transaction,
+                title,
+                cx,
+            )
+            .await
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn render_header(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let accepted_count = self.hunks.iter().filter(|hunk| hunk.accepted).count();
+        let mut buffers = self
+            .hunks
+            .iter()
+            .map(|hunk| hunk.buffer.entity_id())
+            .collect::<Vec<_>>();
+        buffers.dedup();
+        let focus_handle = self.editor.focus_handle(cx);
+
+        h_flex()
+            .w_full()
+            .px_2()
+            .py_1()
+            .gap_2()
+            .border_b_1()
+            .border_color(cx.theme().colors().border_variant)
+            .bg(cx.theme().colors().toolbar_background)
+            .child(
+                Label::new(format!(
+                    "{accepted_count} of {} edits in {} files accepted",
+                    self.hunks.len(),
+                    buffers.len()
+                ))
+                .color(Color::Muted),
+            )
+            .child(div().flex_1())
+            .child(
+                Button::new("accept-all", "Accept All")
+                    .label_size(LabelSize::Small)
+                    .disabled(accepted_count == self.hunks.len())
+                    .on_click(cx.listener(|this, _, _, cx| this.set_all_accepted(true, cx))),
+            )
+            .child(
+                Button::new("reject-all", "Reject All")
+                    .label_size(LabelSize::Small)
+                    .disabled(accepted_count == 0)
+                    .on_click(cx.listener(|this, _, _, cx| this.set_all_accepted(false, cx))),
+            )
+            .child(
+                Button::new("discard", "Discard")
+                    .label_size(LabelSize::Small)
+                    .tooltip(Tooltip::text("Close the preview without applying any edit"))
+                    .on_click(cx.listener(|_, _, _, cx| cx.emit(ItemEvent::CloseItem))),
+            )
+            .child(
+                Button::new("apply", "Apply")
+                    .label_size(LabelSize::Small)
+                    .style(ButtonStyle::Filled)
+                    .disabled(accepted_count == 0)
+                    .key_binding(
+                        KeyBinding::for_action_in(&ApplyRenamePreview, &focus_handle, cx)
+                            .map(|kb| kb.size(rems_from_px(12.))),
+                    )
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.apply(&ApplyRenamePreview, window, cx)
+                    })),
+            )
+    }
+}
+
+/// Groups edits touching the same lines, returning the lines of every group
+/// along with their anchored edits and their text once the edits are applied.
+fn group_edits_by_lines(
+    snapshot: &BufferSnapshot,
+    edits: Vec<(Range<usize>, Arc<str>)>,
+) -> Vec<(
+    Range<Point>,
+    Vec<(Range<text::Anchor>, Arc<str>)>,
+    Vec<SharedString>,
+)> {
+    let mut groups: Vec<(Range<u32>, Vec<(Range<usize>, Arc<str>)>)> = Vec::new();
+    for (range, new_text) in edits {
+        let start_row = snapshot.offset_to_point(range.start).row;
+        let end_row = snapshot.offset_to_point(range.end).row;
+        match groups.last_mut() {
+            Some((rows, edits)) if rows.end >= start_row => {
+                rows.end = rows.end.max(end_row);
+                edits.push((range, new_text));
+            }
+            _ => groups.push((start_row..end_row, vec![(range, new_text)])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(rows, edits)| {
+            let lines =
+                Point::new(rows.start, 0)..Point::new(rows.end, snapshot.line_len(rows.end));
+            let lines_start = snapshot.point_to_offset(lines.start);
+            let mut new_text = snapshot.text_for_range(lines.clone()).collect::<String>();
+            for (range, text) in edits.iter().rev() {
+                new_text.replace_range(range.start - lines_start..range.end - lines_start, text);
+            }
+            let new_lines = new_text
+                .lines()
+                .map(|line| SharedString::from(line.to_string()))
+                .collect();
+            let edits = edits
+                .into_iter()
+                .map(|(range, text)| {
+                    (
+                        snapshot.anchor_before(range.start)..snapshot.anchor_after(range.end),
+                        text,
+                    )
+                })
+                .collect();
+            (lines, edits, new_lines)
+        })
+        .collect()
+}
+
+fn render_hunk_block(
+    preview: &WeakEntity<RenamePreview>,
+    ix: usize,
+    cx: &mut BlockContext,
+) -> AnyElement {
+    let Some(preview_handle) = preview.upgrade() else {
+        return Empty.into_any_element();
+    };
+    let Some(hunk) = preview_handle.read(cx).hunks.get(ix) else {
+        return Empty.into_any_element();
+    };
+    let accepted = hunk.accepted;
+    let new_lines = hunk.new_lines.clone();
+    let line_height = cx.line_height;
+    let background = if accepted {
+        cx.theme().status().created_background
+    } else {
+        cx.theme().colors().editor_background
+    };
+
+    h_flex()
+        .id(cx.block_id)
+        .w_full()
+        .items_start()
+        .gap_2()
+        .pl(cx.margins.gutter.width)
+        .child(
+            div().h(line_height).flex().items_center().child(
+                Checkbox::new(("rename-preview-hunk", ix), accepted.into())
+                    .fill()
+                    .on_click({
+                        let preview = preview.clone();
+                        move |_, _, cx| {
+                            preview
+                                .update(cx, |preview, cx| preview.toggle_hunk(ix, cx))
+                                .log_err();
+                        }
+                    }),
+            ),
+        )
+        .child(
+            v_flex()
+                .flex_1()
+                .bg(background)
+                .children(new_lines.into_iter().map(|line| {
+                    div().h(line_height).child(
+                        Label::new(line)
+                            .buffer_font(cx)
+                            .color(if accepted {
+                                Color::Default
+                            } else {
+                                Color::Muted
+                            })
+                            .when(!accepted, |label| label.strikethrough()),
+                    )
+                })),
+        )
+        .into_any_element()
+}
+
+impl EventEmitter<ItemEvent> for RenamePreview {}
+
+impl Focusable for RenamePreview {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.editor.focus_handle(cx)
+    }
+}
+
+impl Item for RenamePreview {
+    type Event = ItemEvent;
+
+    fn to_item_events(event: &Self::Event, f: &mut dyn FnMut(ItemEvent)) {
+        f(*event)
+    }
+
+    fn tab_icon(&self, _window: &Window, _cx: &App) -> Option<Icon> {
+        Some(Icon::new(IconName::Replace).color(Color::Muted))
+    }
+
+    fn tab_content(&self, params: TabContentParams, _window: &Window, cx: &App) -> AnyElement {
+        Label::new(self.tab_content_text(params.detail.unwrap_or_default(), cx))
+            .color(params.text_color())
+            .into_any_element()
+    }
+
+    fn tab_content_text(&self, _detail: usize, _: &App) -> SharedString {
+        self.title.clone()
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("Rename Preview Opened")
+    }
+
+    fn deactivated(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.editor
+            .update(cx, |editor, cx| editor.deactivated(window, cx));
+    }
+
+    fn act_as_type<'a>(
+        &'a self,
+        type_id: TypeId,
+        self_handle: &'a Entity<Self>,
+        _: &'a App,
+    ) -> Option<AnyEntity> {
+        if type_id == TypeId::of::<Self>() {
+            Some(self_handle.clone().into())
+        } else if type_id == TypeId::of::<Editor>() {
+            Some(self.editor.clone().into())
+        } else {
+            None
+        }
+    }
+
+    fn set_nav_history(
+        &mut self,
+        nav_history: ItemNavHistory,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.editor.update(cx, |editor, _| {
+            editor.set_nav_history(Some(nav_history));
+        });
+    }
+
+    fn navigate(
+        &mut self,
+        data: Arc<dyn Any + Send>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        self.editor
+            .update(cx, |editor, cx| editor.navigate(data, window, cx))
+    }
+
+    fn added_to_workspace(
+        &mut self,
+        workspace: &mut Workspace,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.editor.update(cx, |editor, cx| {
+            editor.added_to_workspace(workspace, window, cx)
+        });
+    }
+}
+
+impl Render for RenamePreview {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("RenamePreview")
+            .size_full()
+            .on_action(cx.listener(Self::apply))
+            .on_action(cx.listener(Self::toggle_edits))
+            .child(self.render_header(cx))
+            .child(self.editor.clone())
+    }
+}
@@ -11,7 +11,8 @@ use multi_buffer::{MultiBufferDimension, MultiBufferOffset};
 use util::post_inc;
 
 use crate::{
-    Anchor, DisplayPoint, DisplayRow, MultiBufferSnapshot, SelectMode, ToOffset,
+    Anchor, DisplayPoint, DisplayRow, MultiBufferSnapshot, RowExt, RowRangeExt, SelectMode,
+    ToOffset,
     display_map::{DisplaySnapshot, ToDisplayPoint},
     movement::TextLayoutDetails,
 };
@@ -33,6 +34,66 @@ pub struct SelectionsCollection {
     pending: Option<PendingSelection>,
     select_mode: SelectMode,
     is_extending: bool,
+    /// The rectangle the selections were made from, when they form a block
+    /// selection. It is cleared as soon as the selections change otherwise.
+    block_selection: Option<BlockSelection>,
+}
+
+/// A rectangular selection over a range of display rows and columns. Unlike
+/// the selections it produces, its columns may extend past the end of shorter
+/// lines, into virtual space.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockSelection {
+    tail_row: Anchor,
+    head_row: Anchor,
+    tail_column: u32,
+    head_column: u32,
+}
+
+impl BlockSelection {
+    fn new(tail: DisplayPoint, head: DisplayPoint, snapshot: &DisplaySnapshot) -> Self {
+        let row_start = |row| {
+            snapshot
+                .buffer_snapshot()
+                .anchor_before(DisplayPoint::new(row, 0).to_point(snapshot))
+        };
+        Self {
+            tail_row: row_start(tail.row()),
+            head_row: row_start(head.row()),
+            tail_column: tail.column(),
+            head_column: head.column(),
+        }
+    }
+
+    /// The corner the selection was started from, possibly past the end of its line.
+    pub fn tail(&self, snapshot: &DisplaySnapshot) -> DisplayPoint {
+        DisplayPoint::new(
+            self.tail_row.to_display_point(snapshot).row(),
+            self.tail_column,
+        )
+    }
+
+    /// The corner the selection was extended to, possibly past the end of its line.
+    pub fn head(&self, snapshot: &DisplaySnapshot) -> DisplayPoint {
+        DisplayPoint::new(
+            self.head_row.to_display_point(snapshot).row(),
+            self.head_column,
+        )
+    }
+
+    pub fn rows(&self, snapshot: &DisplaySnapshot) -> Range<DisplayRow> {
+        let tail = self.tail(snapshot).row();
+        let head = self.head(snapshot).row();
+        cmp::min(tail, head)..cmp::max(tail, head).next_row()
+    }
+
+    pub fn columns(&self) -> Range<u32> {
+        cmp::min(self.tail_column, self.head_column)..cmp::max(self.tail_column, self.head_column)
+    }
+
+    pub fn reversed(&self) -> bool {
+        self.head_column < self.tail_column
+    }
 }
 
 impl SelectionsCollection {
@@ -53,6 +114,7 @@ impl SelectionsCollection {
             }),
             select_mode: SelectMode::Character,
             is_extending: false,
+            block_selection: None,
         }
     }
 
@@ -61,6 +123,7 @@ impl SelectionsCollection {
         self.line_mode = other.line_mode;
         self.disjoint = other.disjoint.clone();
         self.pending.clone_from(&other.pending);
+        self.block_selection.clone_from(&other.block_selection);
     }
 
     pub fn count(&self) -> usize {
@@ -530,9 +593,13 @@ impl SelectionsCollection {
             snapshot,
             collection: self,
             selections_changed: false,
+            block_selection_changed: false,
         };
 
         let result = change(&mut mutable_collection);
+        if mutable_collection.selections_changed && !mutable_collection.block_selection_changed {
+            mutable_collection.block_selection = None;
+        }
         assert!(
             !mutable_collection.disjoint.is_empty() || mutable_collection.pending.is_some(),
             "There must be at least one selection"
@@ -604,6 +671,11 @@ impl SelectionsCollection {
         self.is_extending
     }
 
+    /// The rectangle the current selections were made from, if any.
+    pub fn block_selection(&self) -> Option<&BlockSelection> {
+        self.block_selection.as_ref()
+    }
+
     pub fn set_is_extending(&mut self, is_extending: bool) {
         self.is_extending = is_extending;
     }
@@ -613,6 +685,7 @@ pub struct MutableSelectionsCollection<'snap, 'a> {
     collection: &'a mut SelectionsCollection,
     snapshot: &'snap DisplaySnapshot,
     selections_changed: bool,
+    block_selection_changed: bool,
 }
 
 impl<'snap, 'a> fmt::Debug for MutableSelectionsCollection<'snap, 'a> {
@@ -620,6 +693,7 @@ impl<'snap, 'a> fmt::Debug for MutableSelectionsCollection<'snap, 'a> {
         f.debug_struct("MutableSelectionsCollection")
             .field("collection", &self.collection)
             .field("selections_changed", &self.selections_changed)
+            .field("block_selection_changed", &self.block_selection_changed)
             .finish()
     }
 }
@@ -1062,6 +1136,49 @@ impl<'snap, 'a> MutableSelectionsCollection<'snap, 'a> {
         self.select(new_selections);
     }
 
+    /// Selects the rectangle between `tail` and `head`, with one selection per
+    /// display row. Rows ending before the rectangle's columns get a selection
+    /// clipped to their end, while the rectangle itself keeps the columns.
+    ///
+    /// With `skip_empty_rows`, rows whose selection would be empty get none,
+    /// unless every row's selection is empty.
+    pub fn select_block(&mut self, tail: DisplayPoint, head: DisplayPoint, skip_empty_rows: bool) {
+        let snapshot = self.snapshot;
+        let block = BlockSelection::new(tail, head, snapshot);
+        let columns = block.columns();
+        let reversed = block.reversed();
+        let mut ranges = block
+            .rows(snapshot)
+            .iter_rows()
+            .filter(|row| !snapshot.is_block_line(*row))
+            .map(|row| {
+                let start = snapshot
+                    .clip_point(DisplayPoint::new(row, columns.start), Bias::Left)
+                    .to_point(snapshot);
+                let end = snapshot
+                    .clip_point(DisplayPoint::new(row, columns.end), Bias::Right)
+                    .to_point(snapshot);
+                if reversed { end..start } else { start..end }
+            })
+            .collect::<Vec<_>>();
+        if ranges.is_empty() {
+            return;
+        }
+        if skip_empty_rows && ranges.iter().any(|range| range.start != range.end) {
+            ranges.retain(|range| range.start != range.end);
+        }
+
+        self.select_ranges(ranges);
+        self.set_block_selection(tail, head);
+    }
+
+    /// Records the rectangle between `tail` and `head` as the one the current
+    /// selections were made from, without changing the selections.
+    pub fn set_block_selection(&mut self, tail: DisplayPoint, head: DisplayPoint) {
+        self.collection.block_selection = Some(BlockSelection::new(tail, head, self.snapshot));
+        self.block_selection_changed = true;
+    }
+
     pub fn pending_anchor_mut(&mut self) -> Option<&mut Selection<Anchor>> {
         self.selections_changed = true;
         self.pending.as_mut().map(|pending| &mut pending.selection)
@@ -0,0 +1,400 @@
+//! Structural edits that operate on the syntax tree: moving and duplicating
+//! syntax nodes, and paredit-style slurping and barfing of bracketed nodes.
+use std::ops::Range;
+
+use gpui::{Context, Window};
+use itertools::Itertools;
+use language::{BufferSnapshot, Node, Point, Selection, SelectionGoal, bracket_children};
+
+use crate::{
+    Autoscroll, BufferOffset, Editor, HideMouseCursorOrigin, MultiBufferOffset, SelectionEffects,
+    ToOffset,
+    actions::{
+        BarfBackward, BarfForward, DuplicateSyntaxNode, MoveSyntaxNodeDown, MoveSyntaxNodeUp,
+        SlurpBackward, SlurpForward,
+    },
+};
+
+/// The text replacing a range of the buffer, along with where the selection
+/// that produced the edit should end up relative to the start of the new text.
+type StructuralEdit = (String, Option<Range<usize>>);
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Backward,
+    Forward,
+}
+
+impl Editor {
+    pub fn move_syntax_node_up(
+        &mut self,
+        _: &MoveSyntaxNodeUp,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.edit_syntax_nodes(window, cx, |buffer, range| {
+            swap_with_sibling(buffer, range, Direction::Backward)
+        });
+    }
+
+    pub fn move_syntax_node_down(
+        &mut self,
+        _: &MoveSyntaxNodeDown,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.edit_syntax_nodes(window, cx, |buffer, range| {
+            swap_with_sibling(buffer, range, Direction::Forward)
+        });
+    }
+
+    pub fn duplicate_syntax_node(
+        &mut self,
+        _: &DuplicateSyntaxNode,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.edit_syntax_nodes(window, cx, duplicate_node);
+    }
+
+    pub fn slurp_forward(&mut self, _: &SlurpForward, window: &mut Window, cx: &mut Context<Self>) {
+        self.edit_syntax_nodes(window, cx, |buffer, range| {
+            slurp(buffer, range, Direction::Forward)
+        });
+    }
+
+    pub fn slurp_backward(
+        &mut self,
+        _: &SlurpBackward,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.edit_syntax_nodes(window, cx, |buffer, range| {
+            slurp(buffer, range, Direction::Backward)
+        });
+    }
+
+    pub fn barf_forward(&mut self, _: &BarfForward, window: &mut Window, cx: &mut Context<Self>) {
+        self.edit_syntax_nodes(window, cx, |buffer, range| {
+            barf(buffer, range, Direction::Forward)
+        });
+    }
+
+    pub fn barf_backward(&mut self, _: &BarfBackward, window: &mut Window, cx: &mut Context<Self>) {
+        self.edit_syntax_nodes(window, cx, |buffer, range| {
+            barf(buffer, range, Direction::Backward)
+        });
+    }
+
+    /// Computes the edits for every selection, drops those that overlap the
+    /// edits of a previous selection, and applies the rest in one transaction.
+    fn edit_syntax_nodes(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+        edits_for_range: impl Fn(&BufferSnapshot, Range<usize>) -> Vec<(Range<usize>, StructuralEdit)>,
+    ) {
+        if self.read_only(cx) {
+            return;
+        }
+        self.hide_mouse_cursor(HideMouseCursorOrigin::TypingAction, cx);
+
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let old_selections = self.selections.all_anchors(&self.display_snapshot(cx));
+
+        let mut groups = Vec::new();
+        for selection in old_selections.iter() {
+            let range = selection.start.to_offset(&snapshot)..selection.end.to_offset(&snapshot);
+            let Some(edits) =
+                snapshot.map_excerpt_ranges(range, |buffer, excerpt_range, input_buffer_range| {
+                    let edits = edits_for_range(
+                        buffer,
+                        input_buffer_range.start.0..input_buffer_range.end.0,
+                    );
+                    let context = excerpt_range.context.start.0..excerpt_range.context.end.0;
+                    if edits
+                        .iter()
+                        .any(|(range, _)| range.start < context.start || range.end > context.end)
+                    {
+                        return Vec::new();
+                    }
+                    edits
+                        .into_iter()
+                        .map(|(range, edit)| {
+                            (BufferOffset(range.start)..BufferOffset(range.end), edit)
+                        })
+                        .collect()
+                })
+            else {
+                continue;
+            };
+            if let Some(span_start) = edits.iter().map(|(range, _)| range.start).min()
+                && let Some(span_end) = edits.iter().map(|(range, _)| range.end).max()
+            {
+                groups.push((selection.id, span_start..span_end, edits));
+            }
+        }
+        groups.sort_by_key(|(_, span, _)| span.start);
+
+        let mut accepted_end = None;
+        let mut edits = Vec::new();
+        let mut moved_selections = Vec::new();
+        for (selection_id, span, group_edits) in groups {
+            if accepted_end.is_some_and(|end| span.start <= end) {
+                continue;
+            }
+            accepted_end = Some(span.end);
+            for (range, (text, relative_selection)) in group_edits {
+                if let Some(relative_selection) = relative_selection {
+                    moved_selections.push((selection_id, range.start, relative_selection));
+                }
+                edits.push((range, text));
+            }
+        }
+        if edits.is_empty() {
+            return;
+        }
+        edits.sort_by_key(|(range, _)| range.start);
+
+        let moved_selections = moved_selections
+            .into_iter()
+            .map(|(selection_id, edit_start, relative_selection)| {
+                let (inserted, deleted) = edits
+                    .iter()
+                    .take_while(|(range, _)| range.start < edit_start)
+                    .fold((0, 0), |(inserted, deleted), (range, text)| {
+                        (inserted + text.len(), deleted + (range.end - range.start))
+                    });
+                let start = edit_start + inserted - deleted;
+                (
+                    selection_id,
+                    start + relative_selection.start..start + relative_selection.end,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        self.transact(window, cx, |this, window, cx| {
+            this.buffer.update(cx, |buffer, cx| {
+                buffer.edit(edits, None, cx);
+            });
+            let snapshot = this.buffer.read(cx).snapshot(cx);
+            let new_selections = old_selections
+                .iter()
+                .map(|selection| {
+                    if let Some((_, range)) = moved_selections
+                        .iter()
+                        .find(|(selection_id, _)| *selection_id == selection.id)
+                    {
+                        Selection {
+                            id: selection.id,
+                            start: range.start,
+                            end: range.end,
+                            reversed: selection.reversed,
+                            goal: SelectionGoal::None,
+                        }
+                    } else {
+                        Selection {
+                            id: selection.id,
+                            start: selection.start.to_offset(&snapshot),
+                            end: selection.end.to_offset(&snapshot),
+                            reversed: selection.reversed,
+                            goal: SelectionGoal::None,
+                        }
+                    }
+                })
+                .sorted_by_key(|selection: &Selection<MultiBufferOffset>| selection.start)
+                .collect::<Vec<_>>();
+            this.change_selections(
+                SelectionEffects::scroll(Autoscroll::fit()),
+                window,
+                cx,
+                |s| s.select(new_selections),
+            );
+        });
+    }
+}
+
+fn next_named_sibling(node: Node<'_>, direction: Direction) -> Option<Node<'_>> {
+    let mut sibling = node;
+    loop {
+        sibling = match direction {
+            Direction::Backward => sibling.prev_named_sibling()?,
+            Direction::Forward => sibling.next_named_sibling()?,
+        };
+        if !sibling.is_extra() {
+            return Some(sibling);
+        }
+    }
+}
+
+fn named_children(node: Node<'_>) -> Vec<Node<'_>> {
+    let mut cursor = node.walk();
+    node.named_children(&mut cursor)
+        .filter(|child| !child.is_extra())
+        .collect()
+}
+
+fn relative_range(range: &Range<usize>, node: &Range<usize>) -> Range<usize> {
+    range.start.saturating_sub(node.start)..range.end.min(node.end).saturating_sub(node.start)
+}
+
+/// Swaps the node at the range with its previous or next named sibling, keeping
+/// the selection at the same position within the moved node.
+fn swap_with_sibling(
+    buffer: &BufferSnapshot,
+    range: Range<usize>,
+    direction: Direction,
+) -> Vec<(Range<usize>, StructuralEdit)> {
+    let Some(node) = buffer.syntax_node_for_structural_edit(range.clone()) else {
+        return Vec::new();
+    };
+    let Some(sibling) = next_named_sibling(node, direction) else {
+        return Vec::new();
+    };
+    let node_range = node.byte_range();
+    let sibling_range = sibling.byte_range();
+    let node_text = buffer
+        .text_for_range(node_range.clone())
+        .collect::<String>();
+    let sibling_text = buffer
+        .text_for_range(sibling_range.clone())
+        .collect::<String>();
+    let relative_selection = relative_range(&range, &node_range);
+    vec![
+        (node_range, (sibling_text, None)),
+        (sibling_range, (node_text, Some(relative_selection))),
+    ]
+}
+
+/// Inserts a copy of the node at the range after it, separated from the original
+/// the same way the node is separated from its siblings, and selects the copy.
+fn duplicate_node(
+    buffer: &BufferSnapshot,
+    range: Range<usize>,
+) -> Vec<(Range<usize>, StructuralEdit)> {
+    let Some(node) = buffer.syntax_node_for_structural_edit(range.clone()) else {
+        return Vec::new();
+    };
+    let node_range = node.byte_range();
+    let separator = if let Some(next) = next_named_sibling(node, Direction::Forward) {
+        buffer
+            .text_for_range(node_range.end..next.start_byte())
+            .collect::<String>()
+    } else if let Some(previous) = next_named_sibling(node, Direction::Backward) {
+        buffer
+            .text_for_range(previous.end_byte()..node_range.start)
+            .collect::<String>()
+    } else {
+        let start = buffer.offset_to_point(node_range.start);
+        let line_start = buffer.point_to_offset(Point::new(start.row, 0));
+        if buffer
+            .chars_for_range(line_start..node_range.start)
+            .all(|c| c.is_whitespace())
+        {
+            let indent = buffer.indent_size_for_line(start.row);
+            format!("\n{}", indent.chars().collect::<String>())
+        } else {
+            " ".to_string()
+        }
+    };
+    let node_text = buffer
+        .text_for_range(node_range.clone())
+        .collect::<String>();
+    let relative_selection = relative_range(&range, &node_range);
+    let relative_selection =
+        separator.len() + relative_selection.start..separator.len() + relative_selection.end;
+    vec![(
+        node_range.end..node_range.end,
+        (separator + &node_text, Some(relative_selection)),
+    )]
+}
+
+/// Extends the bracketed node enclosing the range to include the node following
+/// (or preceding) it, by moving its closing (or opening) bracket past that node.
+fn slurp(
+    buffer: &BufferSnapshot,
+    range: Range<usize>,
+    direction: Direction,
+) -> Vec<(Range<usize>, StructuralEdit)> {
+    let Some(node) = buffer.syntax_enclosing_bracketed_node(range) else {
+        return Vec::new();
+    };
+    let Some((open, close)) = bracket_children(node) else {
+        return Vec::new();
+    };
+
+    // The bracketed node may be the last part of a larger node, like the
+    // arguments of a call, in which case the larger node's sibling is slurped.
+    let mut outer = node;
+    let sibling = loop {
+        if let Some(sibling) = next_named_sibling(outer, direction) {
+            break sibling;
+        }
+        let parent = outer.parent().filter(|parent| parent.parent().is_some());
+        match (direction, parent) {
+            (Direction::Forward, Some(parent)) if parent.end_byte() == outer.end_byte() => {
+                outer = parent
+            }
+            (Direction::Backward, Some(parent)) if parent.start_byte() == outer.start_byte() => {
+                outer = parent
+            }
+            _ => return Vec::new(),
+        }
+    };
+
+    let (bracket, insertion) = match direction {
+        Direction::Forward => (close, sibling.end_byte()),
+        Direction::Backward => (open, sibling.start_byte()),
+    };
+    move_bracket(buffer, bracket.byte_range(), insertion)
+}
+
+/// Shrinks the bracketed node enclosing the range to exclude its last (or first)
+/// named child, by moving its closing (or opening) bracket before that child.
+fn barf(
+    buffer: &BufferSnapshot,
+    range: Range<usize>,
+    direction: Direction,
+) -> Vec<(Range<usize>, StructuralEdit)> {
+    let Some(node) = buffer.syntax_enclosing_bracketed_node(range) else {
+        return Vec::new();
+    };
+    let Some((open, close)) = bracket_children(node) else {
+        return Vec::new();
+    };
+    let children = named_children(node);
+    if children.is_empty() {
+        return Vec::new();
+    }
+
+    let (bracket, insertion) = match direction {
+        Direction::Forward => {
+            let insertion = children
+                .len()
+                .checked_sub(2)
+                .map_or(open.end_byte(), |ix| children[ix].end_byte());
+            (close, insertion)
+        }
+        Direction::Backward => {
+            let insertion = children
+                .get(1)
+                .map_or(close.start_byte(), |child| child.start_byte());
+            (open, insertion)
+        }
+    };
+    move_bracket(buffer, bracket.byte_range(), insertion)
+}
+
+fn move_bracket(
+    buffer: &BufferSnapshot,
+    bracket: Range<usize>,
+    insertion: usize,
+) -> Vec<(Range<usize>, StructuralEdit)> {
+    let bracket_text = buffer.text_for_range(bracket.clone()).collect::<String>();
+    let mut edits = vec![
+        (bracket, (String::new(), None)),
+        (insertion..insertion, (bracket_text, None)),
+    ];
+    edits.sort_by_key(|(range, _)| range.start);
+    edits
+}
@@ -8,19 +8,21 @@ use git::{
     repository::{
         AskPassDelegate, Branch, CommitDataReader, CommitDetails, CommitOptions, FetchOptions,
         GRAPH_CHUNK_SIZE, GitRepository, GitRepositoryCheckpoint, InitialGraphCommitData, LogOrder,
-        LogSource, PushOptions, Remote, RepoPath, ResetMode, SearchCommitArgs, Worktree,
+        LogSource, PushOptions, ReflogEntry, Remote, RepoPath, ResetMode, SearchCommitArgs,
+        Worktree,
     },
     status::{
         DiffTreeType, FileStatus, GitStatus, StatusCode, TrackedStatus, TreeDiff, TreeDiffStatus,
         UnmergedStatus,
     },
+    submodule::{Submodule, SubmoduleOperation},
 };
 use gpui::{AsyncApp, BackgroundExecutor, SharedString, Task};
 use ignore::gitignore::GitignoreBuilder;
 use parking_lot::Mutex;
 use rope::Rope;
 use smol::{channel::Sender, future::FutureExt as _};
-use std::{path::PathBuf, sync::Arc, sync::atomic::AtomicBool};
+use std::{ops::Range, path::PathBuf, sync::Arc, sync::atomic::AtomicBool};
 use text::LineEnding;
 use util::{paths::PathStyle, rel_path::RelPath};
 
@@ -52,6 +54,9 @@ pub struct FakeGitRepositoryState {
     pub simulated_index_write_error_message: Option<String>,
     pub simulated_create_worktree_error: Option<String>,
     pub refs: HashMap<String, String>,
+    /// Reflog entries keyed by ref name (e.g. `HEAD`), newest first.
+    pub reflogs: HashMap<String, Vec<ReflogEntry>>,
+    pub submodules: Vec<Submodule>,
     pub graph_commits: Vec<Arc<InitialGraphCommitData>>,
 }
 
@@ -68,6 +73,8 @@ impl FakeGitRepositoryState {
             simulated_index_write_error_message: Default::default(),
             simulated_create_worktree_error: Default::default(),
             refs: HashMap::from_iter([("HEAD".into(), "abc".into())]),
+            reflogs: HashMap::default(),
+            submodules: Vec::new(),
             merge_base_contents: Default::default(),
             oids: Default::default(),
             remotes: HashMap::default(),
@@ -133,6 +140,15 @@ impl GitRepository for FakeGitRepository {
         unimplemented!()
     }
 
+    fn load_branch_diff(
+        &self,
+        _base_ref: String,
+        _head_ref: String,
+        _cx: AsyncApp,
+    ) -> BoxFuture<'_, Result<git::repository::CommitDiff>> {
+        unimplemented!()
+    }
+
     fn set_index_text(
         &self,
         path: RepoPath,
@@ -212,6 +228,13 @@ impl GitRepository for FakeGitRepository {
         .boxed()
     }
 
+    fn commit_signature(
+        &self,
+        _commit: String,
+    ) -> BoxFuture<'_, Result<Option<git::signing::CommitSignature>>> {
+        async { Ok(None) }.boxed()
+    }
+
     fn reset(
         &self,
         _commit: String,
@@ -260,14 +283,32 @@ impl GitRepository for FakeGitRepository {
             })
             .collect::<Vec<_>>();
 
+        // Files inside submodules belong to the submodule's repository.
+        let submodule_paths = self
+            .fs
+            .with_git_state(&self.dot_git_path, false, |state| {
+                state
+                    .submodules
+                    .iter()
+                    .map(|submodule| submodule.path.as_std_path().to_path_buf())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
         // Load working copy files.
         let git_files: HashMap<RepoPath, (String, bool)> = self
             .fs
             .files()
             .iter()
             .filter_map(|path| {
-                // TODO better simulate git status output in the case of submodules and worktrees
+                // TODO better simulate git status output in the case of worktrees
                 let repo_path = path.strip_prefix(workdir_path).ok()?;
+                if submodule_paths
+                    .iter()
+                    .any(|submodule_path| repo_path.starts_with(submodule_path))
+                {
+                    return None;
+                }
                 let mut is_ignored = repo_path.starts_with(".git");
                 for ignore in &ignores {
                     match ignore.matched_path_or_any_parents(path, false) {
@@ -697,6 +738,25 @@ impl GitRepository for FakeGitRepository {
         })
     }
 
+    fn set_branch_target(&self, name: String, commit: String) -> BoxFuture<'_, Result<()>> {
+        self.with_state_async(true, move |state| {
+            if !state.branches.contains(&name) {
+                bail!("no such branch: {name}");
+            }
+            if state.current_branch_name.as_ref() == Some(&name) {
+                bail!("cannot force update the current branch: {name}");
+            }
+            state.refs.insert(format!("refs/heads/{name}"), commit);
+            Ok(())
+        })
+    }
+
+    fn reflog(&self, ref_name: String) -> BoxFuture<'_, Result<Vec<ReflogEntry>>> {
+        self.with_state_async(false, move |state| {
+            Ok(state.reflogs.get(&ref_name).cloned().unwrap_or_default())
+        })
+    }
+
     fn blame(
         &self,
         path: RepoPath,
@@ -731,6 +791,21 @@ impl GitRepository for FakeGitRepository {
         .boxed()
     }
 
+    fn line_history(
+        &self,
+        path: RepoPath,
+        rows: Range<u32>,
+    ) -> BoxFuture<'_, Result<git::repository::LineHistory>> {
+        async move {
+            Ok(git::repository::LineHistory {
+                entries: Vec::new(),
+                path,
+                rows,
+            })
+        }
+        .boxed()
+    }
+
     fn stage_paths(
         &self,
         paths: Vec<RepoPath>,
@@ -865,6 +940,39 @@ impl GitRepository for FakeGitRepository {
         unimplemented!()
     }
 
+    fn submodules(&self) -> BoxFuture<'_, Result<Vec<Submodule>>> {
+        self.with_state_async(false, |state| Ok(state.submodules.clone()))
+    }
+
+    fn submodule_operation(
+        &self,
+        operation: SubmoduleOperation,
+        paths: Vec<RepoPath>,
+        _askpass: AskPassDelegate,
+        _env: Arc<HashMap<String, String>>,
+        _cx: AsyncApp,
+    ) -> BoxFuture<'_, Result<git::repository::RemoteCommandOutput>> {
+        self.with_state_async(true, move |state| {
+            for submodule in &mut state.submodules {
+                if !paths.is_empty() && !paths.contains(&submodule.path) {
+                    continue;
+                }
+                match operation {
+                    SubmoduleOperation::Init | SubmoduleOperation::Sync => {}
+                    SubmoduleOperation::Update => {
+                        submodule.checked_out_sha = submodule.recorded_sha.clone();
+                        submodule.status.initialized = true;
+                        submodule.status.commit_changed = false;
+                    }
+                }
+            }
+            Ok(git::repository::RemoteCommandOutput {
+                stdout: String::new(),
+                stderr: String::new(),
+            })
+        })
+    }
+
     fn get_all_remotes(&self) -> BoxFuture<'_, Result<Vec<Remote>>> {
         self.with_state_async(false, move |state| {
             let remotes = state
@@ -40,7 +40,8 @@ use smol::io::AsyncWriteExt;
 #[cfg(feature = "test-support")]
 use std::path::Component;
 use std::{
-    io::{self, Write},
+    io::{self, Read as _, Seek as _, Write},
+    ops::Range,
     path::{Path, PathBuf},
     pin::Pin,
     sync::Arc,
@@ -124,6 +125,8 @@ pub trait Fs: Send + Sync {
         Ok(String::from_utf8(self.load_bytes(path).await?)?)
     }
     async fn load_bytes(&self, path: &Path) -> Result<Vec<u8>>;
+    /// Loads the bytes of the file within the range, or fewer if the file ends before it.
+    async fn load_range(&self, path: &Path, range: Range<u64>) -> Result<Vec<u8>>;
     async fn atomic_write(&self, path: PathBuf, text: String) -> Result<()>;
     async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()>;
     async fn write(&self, path: &Path, content: &[u8]) -> Result<()>;
@@ -174,6 +177,11 @@ impl dyn Fs {
         GlobalFs::global(cx).0.clone()
     }
 
+    /// Returns the global [`Fs`], if it has been set.
+    pub fn try_global(cx: &App) -> Option<Arc<Self>> {
+        cx.try_global::<GlobalFs>().map(|fs| fs.0.clone())
+    }
+
     /// Sets the global [`Fs`].
     pub fn set_global(fs: Arc<Self>, cx: &mut App) {
         cx.set_global(GlobalFs(fs));
@@ -841,6 +849,23 @@ impl Fs for RealFs {
         Ok(bytes)
     }
 
+    async fn load_range(&self, path: &Path, range: Range<u64>) -> Result<Vec<u8>> {
+        let path = path.to_path_buf();
+        self.executor
+            .spawn(async move {
+                maybe!({
+                    let mut file = std::fs::File::open(&path)?;
+                    file.seek(io::SeekFrom::Start(range.start))?;
+                    let mut bytes = Vec::new();
+                    file.take(range.end.saturating_sub(range.start))
+                        .read_to_end(&mut bytes)?;
+                    anyhow::Ok(bytes)
+                })
+                .with_context(|| format!("Failed to read file {}", path.display()))
+            })
+            .await
+    }
+
     #[cfg(not(target_os = "windows"))]
     async fn atomic_write(&self, path: PathBuf, data: String) -> Result<()> {
         smol::unblock(move || {
@@ -2161,6 +2186,29 @@ impl FakeFs {
         .unwrap();
     }
 
+    pub fn set_reflog_for_repo(
+        &self,
+        dot_git: &Path,
+        ref_name: &str,
+        entries: Vec<git::repository::ReflogEntry>,
+    ) {
+        self.with_git_state(dot_git, true, |state| {
+            state.reflogs.insert(ref_name.to_string(), entries);
+        })
+        .unwrap();
+    }
+
+    pub fn set_submodules_for_repo(
+        &self,
+        dot_git: &Path,
+        submodules: Vec<git::submodule::Submodule>,
+    ) {
+        self.with_git_state(dot_git, true, |state| {
+            state.submodules = submodules;
+        })
+        .unwrap();
+    }
+
     pub fn set_graph_commits(&self, dot_git: &Path, commits: Vec<Arc<InitialGraphCommitData>>) {
         self.with_git_state(dot_git, true, |state| {
             state.graph_commits = commits;
@@ -2772,6 +2820,14 @@ impl Fs for FakeFs {
         self.load_internal(path).await
     }
 
+    async fn load_range(&self, path: &Path, range: Range<u64>) -> Result<Vec<u8>> {
+        let content = self.load_internal(path).await?;
+        let len = content.len() as u64;
+        let start = range.start.min(len);
+        let end = range.end.clamp(start, len);
+        Ok(content[start as usize..end as usize].to_vec())
+    }
+
     async fn atomic_write(&self, path: PathBuf, data: String) -> Result<()> {
         self.simulate_random_delay().await;
         let path = normalize_path(path.as_path());
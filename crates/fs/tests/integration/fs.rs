@@ -453,6 +453,28 @@ async fn test_realfs_atomic_write_non_existing_file(executor: BackgroundExecutor
     assert_eq!(content, "Hello");
 }
 
+#[gpui::test]
+async fn test_load_range(executor: BackgroundExecutor) {
+    let temp_dir = TempDir::new().unwrap();
+    let real_path = temp_dir.path().join("file.txt");
+    std::fs::write(&real_path, "0123456789").unwrap();
+    let real_fs = RealFs::new(None, executor.clone());
+    let fake_fs = FakeFs::new(executor);
+    fake_fs
+        .insert_tree(path!("/root"), json!({ "file.txt": "0123456789" }))
+        .await;
+    let fake_path = Path::new(path!("/root/file.txt"));
+
+    for (fs, path) in [
+        (&real_fs as &dyn Fs, real_path.as_path()),
+        (&*fake_fs, fake_path),
+    ] {
+        assert_eq!(fs.load_range(path, 2..5).await.unwrap(), b"234");
+        assert_eq!(fs.load_range(path, 8..20).await.unwrap(), b"89");
+        assert_eq!(fs.load_range(path, 20..30).await.unwrap(), b"");
+    }
+}
+
 #[gpui::test]
 #[cfg(target_os = "windows")]
 async fn test_realfs_canonicalize(executor: BackgroundExecutor) {
@@ -3,8 +3,11 @@ pub mod commit;
 mod hosting_provider;
 mod remote;
 pub mod repository;
+pub mod review;
+pub mod signing;
 pub mod stash;
 pub mod status;
+pub mod submodule;
 
 pub use crate::hosting_provider::*;
 pub use crate::remote::*;
@@ -49,6 +52,8 @@ actions!(
         Blame,
         /// Shows the git history for the current file.
         FileHistory,
+        /// Shows the git history for the selected lines of the current file.
+        LineHistory,
         /// Stages the current file.
         StageFile,
         /// Unstages the current file.
@@ -70,6 +75,12 @@ actions!(
         TrashUntrackedFiles,
         /// Undoes the last commit, keeping changes in the working directory.
         Uncommit,
+        /// Registers the submodules listed in `.gitmodules`.
+        InitSubmodules,
+        /// Checks out the recorded commit of each submodule, cloning it if needed.
+        UpdateSubmodules,
+        /// Updates submodule remote URLs from `.gitmodules`.
+        SyncSubmodules,
         /// Pushes commits to the remote repository.
         Push,
         /// Pushes commits to a specific remote branch.
@@ -1,6 +1,11 @@
 use crate::commit::parse_git_diff_name_status;
+use crate::signing::{CommitSignature, GPG_ASKPASS_SCRIPT, GPG_PROGRAM_ENV_VAR, SigningFormat};
 use crate::stash::GitStash;
 use crate::status::{DiffTreeType, GitStatus, StatusCode, TreeDiff};
+use crate::submodule::{
+    Submodule, SubmoduleOperation, merge_submodule_info, parse_gitlinks,
+    parse_porcelain_submodule_states, parse_submodule_status,
+};
 use crate::{Oid, RunHook, SHORT_SHA_LENGTH};
 use anyhow::{Context as _, Result, anyhow, bail};
 use collections::HashMap;
@@ -28,6 +33,7 @@ use std::str::FromStr;
 use std::{
     cmp::Ordering,
     future,
+    ops::Range,
     path::{Path, PathBuf},
     sync::Arc,
 };
@@ -54,6 +60,14 @@ static GRAPH_COMMIT_FORMAT: &str = "--format=%H%x00%P%x00%D";
 /// %H - Full commit hash
 static SEARCH_COMMIT_FORMAT: &str = "--format=%H";
 
+/// Format string used to read reflog entries
+/// %H - Full commit hash
+/// %gd - Reflog selector, shortened; contains the unix timestamp when used with `--date=unix`
+/// %gs - Reflog subject
+/// %s - Commit subject
+/// %x00 - Null byte separator
+pub static REFLOG_FORMAT: &str = "--format=%H%x00%gd%x00%gs%x00%s";
+
 /// Number of commits to load per chunk for the git graph.
 pub const GRAPH_CHUNK_SIZE: usize = 1000;
 
@@ -297,6 +311,37 @@ pub fn parse_worktrees_from_str<T: AsRef<str>>(raw_worktrees: T) -> Vec<Worktree
     worktrees
 }
 
+/// Parses the output of `git log --walk-reflogs --date=unix` run with [`REFLOG_FORMAT`].
+pub fn parse_reflog(ref_name: &str, input: &str) -> Vec<ReflogEntry> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .filter_map(|(ix, line)| {
+            let mut fields = line.split('\0');
+            let sha = fields.next()?.trim();
+            let timestamp = fields
+                .next()?
+                .rsplit_once("@{")
+                .and_then(|(_, rest)| rest.strip_suffix('}'))
+                .and_then(|timestamp| timestamp.parse().ok())
+                .unwrap_or(0);
+            let message = fields.next().unwrap_or_default();
+            let subject = fields.next().unwrap_or_default();
+            if sha.is_empty() {
+                return None;
+            }
+            Some(ReflogEntry {
+                selector: format!("{ref_name}@{{{ix}}}").into(),
+                sha: sha.to_string().into(),
+                message: message.to_string().into(),
+                subject: subject.to_string().into(),
+                timestamp,
+            })
+        })
+        .collect()
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct Upstream {
     pub ref_name: SharedString,
@@ -325,10 +370,15 @@ impl Upstream {
     }
 }
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Default)]
 pub struct CommitOptions {
     pub amend: bool,
     pub signoff: bool,
+    /// Whether to sign the commit. `None` defers to the `commit.gpgsign` config.
+    pub sign: Option<bool>,
+    /// The key to sign with instead of `user.signingkey`. Setting this signs the
+    /// commit unless `sign` is `Some(false)`.
+    pub signing_key: Option<SharedString>,
 }
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
@@ -411,6 +461,46 @@ pub struct FileHistory {
     pub path: RepoPath,
 }
 
+/// A commit that touched a traced range of lines, newest commits come first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineHistoryEntry {
+    pub commit: FileHistoryEntry,
+    /// The path of the file in this commit, which differs from the traced path
+    /// when the file has been renamed since.
+    pub path: RepoPath,
+    /// The hunks of this commit that changed the traced lines, as a unified diff.
+    pub diff: SharedString,
+}
+
+#[derive(Debug, Clone)]
+pub struct LineHistory {
+    pub entries: Vec<LineHistoryEntry>,
+    pub path: RepoPath,
+    /// The traced rows, zero-based and end-exclusive.
+    pub rows: Range<u32>,
+}
+
+/// A single entry of a ref's reflog, newest entries come first.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct ReflogEntry {
+    /// The reflog selector for this entry, e.g. `HEAD@{2}`.
+    pub selector: SharedString,
+    /// The commit the ref pointed to after this update.
+    pub sha: SharedString,
+    /// The reflog message, e.g. `reset: moving to HEAD~1`.
+    pub message: SharedString,
+    /// The subject of the commit the ref pointed to.
+    pub subject: SharedString,
+    /// Unix timestamp of the ref update.
+    pub timestamp: i64,
+}
+
+impl ReflogEntry {
+    pub fn short_sha(&self) -> &str {
+        &self.sha[..self.sha.len().min(SHORT_SHA_LENGTH)]
+    }
+}
+
 #[derive(Debug)]
 pub struct CommitDiff {
     pub files: Vec<CommitFile>,
@@ -711,6 +801,12 @@ pub trait GitRepository: Send + Sync {
 
     fn delete_branch(&self, is_remote: bool, name: String) -> BoxFuture<'_, Result<()>>;
 
+    /// Forcibly points the (not checked out) branch `name` at `commit`.
+    fn set_branch_target(&self, name: String, commit: String) -> BoxFuture<'_, Result<()>>;
+
+    /// Lists the reflog of `ref_name` (e.g. `HEAD` or a branch name), newest first.
+    fn reflog(&self, ref_name: String) -> BoxFuture<'_, Result<Vec<ReflogEntry>>>;
+
     fn worktrees(&self) -> BoxFuture<'_, Result<Vec<Worktree>>>;
 
     fn create_worktree(
@@ -740,7 +836,19 @@ pub trait GitRepository: Send + Sync {
 
     fn show(&self, commit: String) -> BoxFuture<'_, Result<CommitDetails>>;
 
+    /// Verifies the signature of `commit`. This runs the signing program, so it is
+    /// only worth doing for commits whose signature is displayed.
+    fn commit_signature(&self, commit: String) -> BoxFuture<'_, Result<Option<CommitSignature>>>;
+
     fn load_commit(&self, commit: String, cx: AsyncApp) -> BoxFuture<'_, Result<CommitDiff>>;
+    /// Loads the full contents of every file changed on `head_ref` since it
+    /// diverged from `base_ref`.
+    fn load_branch_diff(
+        &self,
+        base_ref: String,
+        head_ref: String,
+        cx: AsyncApp,
+    ) -> BoxFuture<'_, Result<CommitDiff>>;
     fn blame(
         &self,
         path: RepoPath,
@@ -754,6 +862,9 @@ pub trait GitRepository: Send + Sync {
         skip: usize,
         limit: Option<usize>,
     ) -> BoxFuture<'_, Result<FileHistory>>;
+    /// Returns the commits that changed the given rows of a file, following
+    /// the lines back across renames.
+    fn line_history(&self, path: RepoPath, rows: Range<u32>) -> BoxFuture<'_, Result<LineHistory>>;
 
     /// Returns the absolute path to the repository. For worktrees, this will be the path to the
     /// worktree's gitdir within the main repository (typically `.git/worktrees/<name>`).
@@ -852,6 +963,21 @@ pub trait GitRepository: Send + Sync {
         cx: AsyncApp,
     ) -> BoxFuture<'_, Result<RemoteCommandOutput>>;
 
+    /// Lists the submodules registered in this repository and their state.
+    fn submodules(&self) -> BoxFuture<'_, Result<Vec<Submodule>>>;
+
+    /// Runs `git submodule <operation>` for `paths`, or for every submodule
+    /// when `paths` is empty.
+    fn submodule_operation(
+        &self,
+        operation: SubmoduleOperation,
+        paths: Vec<RepoPath>,
+        askpass: AskPassDelegate,
+        env: Arc<HashMap<String, String>>,
+        // Updating may clone submodules, so like `fetch` this must run on the main thread.
+        cx: AsyncApp,
+    ) -> BoxFuture<'_, Result<RemoteCommandOutput>>;
+
     fn get_push_remote(&self, branch: String) -> BoxFuture<'_, Result<Option<Remote>>>;
 
     fn get_branch_remote(&self, branch: String) -> BoxFuture<'_, Result<Option<Remote>>>;
@@ -1113,6 +1239,30 @@ impl GitRepository for RealGitRepository {
             .boxed()
     }
 
+    fn commit_signature(&self, commit: String) -> BoxFuture<'_, Result<Option<CommitSignature>>> {
+        let git_binary = self.git_binary();
+        self.executor
+            .spawn(async move {
+                let git = git_binary?;
+                let output = git
+                    .run(&[
+                        "show",
+                        "--no-patch",
+                        "--format=%G?%x00%GS%x00%GK",
+                        "--end-of-options",
+                        &commit,
+                    ])
+                    .await?;
+                let mut fields = output.split('\0');
+                Ok(CommitSignature::from_format_fields(
+                    fields.next().unwrap_or_default(),
+                    fields.next().unwrap_or_default(),
+                    fields.next().unwrap_or_default(),
+                ))
+            })
+            .boxed()
+    }
+
     fn load_commit(&self, commit: String, cx: AsyncApp) -> BoxFuture<'_, Result<CommitDiff>> {
         if self.repository.lock().workdir().is_none() {
             return future::ready(Err(anyhow!("no working directory"))).boxed();
@@ -1138,105 +1288,51 @@ impl GitRepository for RealGitRepository {
                 .context("starting git show process")?;
 
             let show_stdout = String::from_utf8_lossy(&show_output.stdout);
-            let changes = parse_git_diff_name_status(&show_stdout);
             let parent_sha = format!("{}^", commit);
+            let files = load_changed_files(&git, &parent_sha, &commit, &show_stdout).await?;
 
-            let mut cat_file_process = git
-                .build_command(&["cat-file", "--batch=%(objectsize)"])
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()
-                .context("starting git cat-file process")?;
-
-            let mut files = Vec::<CommitFile>::new();
-            let mut stdin = BufWriter::with_capacity(512, cat_file_process.stdin.take().unwrap());
-            let mut stdout = BufReader::new(cat_file_process.stdout.take().unwrap());
-            let mut info_line = String::new();
-            let mut newline = [b'\0'];
-            for (path, status_code) in changes {
-                // git-show outputs `/`-delimited paths even on Windows.
-                let Some(rel_path) = RelPath::unix(path).log_err() else {
-                    continue;
-                };
-
-                match status_code {
-                    StatusCode::Modified => {
-                        stdin.write_all(commit.as_bytes()).await?;
-                        stdin.write_all(b":").await?;
-                        stdin.write_all(path.as_bytes()).await?;
-                        stdin.write_all(b"\n").await?;
-                        stdin.write_all(parent_sha.as_bytes()).await?;
-                        stdin.write_all(b":").await?;
-                        stdin.write_all(path.as_bytes()).await?;
-                        stdin.write_all(b"\n").await?;
-                    }
-                    StatusCode::Added => {
-                        stdin.write_all(commit.as_bytes()).await?;
-                        stdin.write_all(b":").await?;
-                        stdin.write_all(path.as_bytes()).await?;
-                        stdin.write_all(b"\n").await?;
-                    }
-                    StatusCode::Deleted => {
-                        stdin.write_all(parent_sha.as_bytes()).await?;
-                        stdin.write_all(b":").await?;
-                        stdin.write_all(path.as_bytes()).await?;
-                        stdin.write_all(b"\n").await?;
-                    }
-                    _ => continue,
-                }
-                stdin.flush().await?;
-
-                info_line.clear();
-                stdout.read_line(&mut info_line).await?;
-
-                let len = info_line.trim_end().parse().with_context(|| {
-                    format!("invalid object size output from cat-file {info_line}")
-                })?;
-                let mut text_bytes = vec![0; len];
-                stdout.read_exact(&mut text_bytes).await?;
-                stdout.read_exact(&mut newline).await?;
-
-                let mut old_text = None;
-                let mut new_text = None;
-                let mut is_binary = is_binary_content(&text_bytes);
-                let text = if is_binary {
-                    String::new()
-                } else {
-                    String::from_utf8_lossy(&text_bytes).to_string()
-                };
-
-                match status_code {
-                    StatusCode::Modified => {
-                        info_line.clear();
-                        stdout.read_line(&mut info_line).await?;
-                        let len = info_line.trim_end().parse().with_context(|| {
-                            format!("invalid object size output from cat-file {}", info_line)
-                        })?;
-                        let mut parent_bytes = vec![0; len];
-                        stdout.read_exact(&mut parent_bytes).await?;
-                        stdout.read_exact(&mut newline).await?;
-                        is_binary = is_binary || is_binary_content(&parent_bytes);
-                        if is_binary {
-                            old_text = Some(String::new());
-                            new_text = Some(String::new());
-                        } else {
-                            old_text = Some(String::from_utf8_lossy(&parent_bytes).to_string());
-                            new_text = Some(text);
-                        }
-                    }
-                    StatusCode::Added => new_text = Some(text),
-                    StatusCode::Deleted => old_text = Some(text),
-                    _ => continue,
-                }
+            Ok(CommitDiff { files })
+        })
+        .boxed()
+    }
 
-                files.push(CommitFile {
-                    path: RepoPath(Arc::from(rel_path)),
-                    old_text,
-                    new_text,
-                    is_binary,
-                })
-            }
+    fn load_branch_diff(
+        &self,
+        base_ref: String,
+        head_ref: String,
+        cx: AsyncApp,
+    ) -> BoxFuture<'_, Result<CommitDiff>> {
+        if self.repository.lock().workdir().is_none() {
+            return future::ready(Err(anyhow!("no working directory"))).boxed();
+        }
+        let git_binary = self.git_binary();
+        cx.background_spawn(async move {
+            let git = git_binary?;
+            let head_sha = git
+                .run(&[
+                    "rev-parse",
+                    "--verify",
+                    "--end-of-options",
+                    &format!("{head_ref}^{{commit}}"),
+                ])
+                .await
+                .with_context(|| format!("resolving {head_ref}"))?;
+            let merge_base = git
+                .run(&["merge-base", "--end-of-options", &base_ref, &head_sha])
+                .await
+                .with_context(|| format!("finding the merge base of {base_ref} and {head_ref}"))?;
+            let name_status = git
+                .run_raw(&[
+                    "diff",
+                    "--name-status",
+                    "-z",
+                    "--no-renames",
+                    &merge_base,
+                    &head_sha,
+                    "--",
+                ])
+                .await?;
+            let files = load_changed_files(&git, &merge_base, &head_sha, &name_status).await?;
 
             Ok(CommitDiff { files })
         })
@@ -1820,6 +1916,47 @@ impl GitRepository for RealGitRepository {
             .boxed()
     }
 
+    fn set_branch_target(&self, name: String, commit: String) -> BoxFuture<'_, Result<()>> {
+        let git_binary = self.git_binary();
+
+        self.executor
+            .spawn(async move {
+                git_binary?
+                    .run(&["branch", "--force", "--end-of-options", &name, &commit])
+                    .await?;
+                anyhow::Ok(())
+            })
+            .boxed()
+    }
+
+    fn reflog(&self, ref_name: String) -> BoxFuture<'_, Result<Vec<ReflogEntry>>> {
+        let git_binary = self.git_binary();
+
+        self.executor
+            .spawn(async move {
+                let output = git_binary?
+                    .build_command(&[
+                        "log",
+                        "--walk-reflogs",
+                        "--date=unix",
+                        REFLOG_FORMAT,
+                        "--end-of-options",
+                    ])
+                    .arg(&ref_name)
+                    .arg("--")
+                    .output()
+                    .await?;
+                anyhow::ensure!(
+                    output.status.success(),
+                    "Failed to read reflog for {ref_name}:\n{}",
+                    String::from_utf8_lossy(&output.stderr)
+                );
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                Ok(parse_reflog(&ref_name, &stdout))
+            })
+            .boxed()
+    }
+
     fn blame(
         &self,
         path: RepoPath,
@@ -1921,6 +2058,43 @@ impl GitRepository for RealGitRepository {
             .boxed()
     }
 
+    fn line_history(&self, path: RepoPath, rows: Range<u32>) -> BoxFuture<'_, Result<LineHistory>> {
+        let git_binary = self.git_binary();
+        self.executor
+            .spawn(async move {
+                let git = git_binary?;
+                // `-L` takes one-based, inclusive line numbers and follows the
+                // range across renames on its own.
+                let start = rows.start + 1;
+                let end = rows.end.max(start);
+                let range_arg = format!("-L{start},{end}:{}", path.as_unix_str());
+                let output = git
+                    .build_command(&[
+                        "-c",
+                        "core.quotepath=false",
+                        "log",
+                        "--no-color",
+                        "--format=%x1e%H%x00%s%x00%B%x00%at%x00%an%x00%ae%x00",
+                    ])
+                    .arg(range_arg)
+                    .output()
+                    .await?;
+
+                if !output.status.success() {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    bail!("git log failed: {stderr}");
+                }
+
+                let stdout = std::str::from_utf8(&output.stdout)?;
+                Ok(LineHistory {
+                    entries: parse_line_history(stdout, &path),
+                    path,
+                    rows,
+                })
+            })
+            .boxed()
+    }
+
     fn diff(&self, diff: DiffType) -> BoxFuture<'_, Result<String>> {
         let git_binary = self.git_binary();
         self.executor
@@ -2149,7 +2323,43 @@ impl GitRepository for RealGitRepository {
         // which we want to block on.
         async move {
             let git = git_binary?;
-            let mut cmd = git.build_command(&["commit", "--quiet", "-m"]);
+
+            let will_sign = match (options.sign, &options.signing_key) {
+                (Some(sign), _) => sign,
+                (None, Some(_)) => true,
+                (None, None) => git
+                    .run(&["config", "--type=bool", "commit.gpgsign"])
+                    .await
+                    .is_ok_and(|value| value == "true"),
+            };
+            // Route GPG passphrase prompts through askpass, since there's no
+            // terminal for gpg-agent's pinentry to prompt on.
+            let mut gpg_wrapper = None;
+            if will_sign && cfg!(unix) {
+                let format = git.run(&["config", "gpg.format"]).await.ok();
+                if SigningFormat::from_config(format.as_deref()) == SigningFormat::OpenPgp {
+                    let gpg_program = git
+                        .run(&["config", "gpg.program"])
+                        .await
+                        .unwrap_or_else(|_| "gpg".to_string());
+                    let dir = tempfile::Builder::new().prefix("zed-gpg").tempdir()?;
+                    let script_path = dir.path().join("gpg.sh");
+                    smol::fs::write(&script_path, GPG_ASKPASS_SCRIPT).await?;
+                    util::fs::make_file_executable(&script_path).await?;
+                    gpg_wrapper = Some((dir, script_path, gpg_program));
+                }
+            }
+
+            let mut args = Vec::new();
+            if let Some((_, script_path, _)) = &gpg_wrapper {
+                args.push("-c".to_string());
+                args.push(format!("gpg.program={}", script_path.display()));
+            }
+            args.extend(["commit", "--quiet", "-m"].map(String::from));
+            let mut cmd = git.build_command(&args);
+            if let Some((_, _, gpg_program)) = &gpg_wrapper {
+                cmd.env(GPG_PROGRAM_ENV_VAR, gpg_program);
+            }
             cmd.envs(env.iter())
                 .arg(&message.to_string())
                 .arg("--cleanup=strip")
@@ -2165,6 +2375,19 @@ impl GitRepository for RealGitRepository {
                 cmd.arg("--signoff");
             }
 
+            match (options.sign, &options.signing_key) {
+                (Some(false), _) => {
+                    cmd.arg("--no-gpg-sign");
+                }
+                (_, Some(key)) => {
+                    cmd.arg(format!("--gpg-sign={key}"));
+                }
+                (Some(true), None) => {
+                    cmd.arg("--gpg-sign");
+                }
+                (None, None) => {}
+            }
+
             if let Some((name, email)) = name_and_email {
                 cmd.arg("--author").arg(&format!("{name} <{email}>"));
             }
@@ -2301,6 +2524,82 @@ impl GitRepository for RealGitRepository {
         .boxed()
     }
 
+    fn submodules(&self) -> BoxFuture<'_, Result<Vec<Submodule>>> {
+        let git_binary = self.git_binary();
+        self.executor
+            .spawn(async move {
+                let git = git_binary?;
+                let status_output = git.run(&["submodule", "status"]).await?;
+                let status_lines = parse_submodule_status(&status_output);
+                if status_lines.is_empty() {
+                    return Ok(Vec::new());
+                }
+
+                let paths = status_lines
+                    .iter()
+                    .map(|line| line.path.as_unix_str().to_string())
+                    .collect::<Vec<_>>();
+                let mut ls_tree_args = vec!["ls-tree", "-z", "HEAD", "--"];
+                ls_tree_args.extend(paths.iter().map(String::as_str));
+                // An unborn HEAD has no recorded submodule commits yet.
+                let gitlinks = git
+                    .run(&ls_tree_args)
+                    .await
+                    .map(|output| parse_gitlinks(&output))
+                    .unwrap_or_default();
+
+                let mut status_args = vec![
+                    "status",
+                    "--porcelain=v2",
+                    "-z",
+                    "--ignore-submodules=none",
+                    "--",
+                ];
+                status_args.extend(paths.iter().map(String::as_str));
+                let states = parse_porcelain_submodule_states(&git.run(&status_args).await?);
+
+                Ok(merge_submodule_info(status_lines, gitlinks, states))
+            })
+            .boxed()
+    }
+
+    fn submodule_operation(
+        &self,
+        operation: SubmoduleOperation,
+        paths: Vec<RepoPath>,
+        ask_pass: AskPassDelegate,
+        env: Arc<HashMap<String, String>>,
+        cx: AsyncApp,
+    ) -> BoxFuture<'_, Result<RemoteCommandOutput>> {
+        let working_directory = self.working_directory();
+        let git_directory = self.path();
+        let git_binary_path = self.system_git_binary_path.clone();
+        let executor = cx.background_executor().clone();
+        let is_trusted = self.is_trusted();
+        async move {
+            let git_binary_path =
+                git_binary_path.context("git not found on $PATH, can't run git submodule")?;
+            let working_directory = working_directory?;
+            let git = GitBinary::new(
+                git_binary_path,
+                working_directory,
+                git_directory,
+                executor.clone(),
+                is_trusted,
+            );
+            let mut command = git.build_command(operation.args());
+            command
+                .arg("--")
+                .args(paths.iter().map(|path| path.as_unix_str()))
+                .envs(env.iter())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped());
+
+            run_git_command(env, ask_pass, command, executor).await
+        }
+        .boxed()
+    }
+
     fn get_push_remote(&self, branch: String) -> BoxFuture<'_, Result<Option<Remote>>> {
         let git_binary = self.git_binary();
         self.executor
@@ -3146,6 +3445,117 @@ impl GitBinary {
     }
 }
 
+/// Reads both sides of each file listed in `--name-status -z` output from the
+/// blobs at `old_rev` and `new_rev`.
+async fn load_changed_files(
+    git: &GitBinary,
+    old_rev: &str,
+    new_rev: &str,
+    name_status: &str,
+) -> Result<Vec<CommitFile>> {
+    let changes = parse_git_diff_name_status(name_status);
+
+    let mut cat_file_process = git
+        .build_command(&["cat-file", "--batch=%(objectsize)"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("starting git cat-file process")?;
+
+    let mut files = Vec::<CommitFile>::new();
+    let mut stdin = BufWriter::with_capacity(512, cat_file_process.stdin.take().unwrap());
+    let mut stdout = BufReader::new(cat_file_process.stdout.take().unwrap());
+    let mut info_line = String::new();
+    let mut newline = [b'\0'];
+    for (path, status_code) in changes {
+        // git-show outputs `/`-delimited paths even on Windows.
+        let Some(rel_path) = RelPath::unix(path).log_err() else {
+            continue;
+        };
+
+        match status_code {
+            StatusCode::Modified => {
+                stdin.write_all(new_rev.as_bytes()).await?;
+                stdin.write_all(b":").await?;
+                stdin.write_all(path.as_bytes()).await?;
+                stdin.write_all(b"\n").await?;
+                stdin.write_all(old_rev.as_bytes()).await?;
+                stdin.write_all(b":").await?;
+                stdin.write_all(path.as_bytes()).await?;
+                stdin.write_all(b"\n").await?;
+            }
+            StatusCode::Added => {
+                stdin.write_all(new_rev.as_bytes()).await?;
+                stdin.write_all(b":").await?;
+                stdin.write_all(path.as_bytes()).await?;
+                stdin.write_all(b"\n").await?;
+            }
+            StatusCode::Deleted => {
+                stdin.write_all(old_rev.as_bytes()).await?;
+                stdin.write_all(b":").await?;
+                stdin.write_all(path.as_bytes()).await?;
+                stdin.write_all(b"\n").await?;
+            }
+            _ => continue,
+        }
+        stdin.flush().await?;
+
+        info_line.clear();
+        stdout.read_line(&mut info_line).await?;
+
+        let len = info_line
+            .trim_end()
+            .parse()
+            .with_context(|| format!("invalid object size output from cat-file {info_line}"))?;
+        let mut text_bytes = vec![0; len];
+        stdout.read_exact(&mut text_bytes).await?;
+        stdout.read_exact(&mut newline).await?;
+
+        let mut old_text = None;
+        let mut new_text = None;
+        let mut is_binary = is_binary_content(&text_bytes);
+        let text = if is_binary {
+            String::new()
+        } else {
+            String::from_utf8_lossy(&text_bytes).to_string()
+        };
+
+        match status_code {
+            StatusCode::Modified => {
+                info_line.clear();
+                stdout.read_line(&mut info_line).await?;
+                let len = info_line.trim_end().parse().with_context(|| {
+                    format!("invalid object size output from cat-file {}", info_line)
+                })?;
+                let mut parent_bytes = vec![0; len];
+                stdout.read_exact(&mut parent_bytes).await?;
+                stdout.read_exact(&mut newline).await?;
+                is_binary = is_binary || is_binary_content(&parent_bytes);
+                if is_binary {
+                    old_text = Some(String::new());
+                    new_text = Some(String::new());
+                } else {
+                    old_text = Some(String::from_utf8_lossy(&parent_bytes).to_string());
+                    new_text = Some(text);
+                }
+            }
+            StatusCode::Added => new_text = Some(text),
+            StatusCode::Deleted => old_text = Some(text),
+            _ => continue,
+        }
+
+        files.push(CommitFile {
+            path: RepoPath(Arc::from(rel_path)),
+            old_text,
+            new_text,
+            is_binary,
+        })
+    }
+
+    Ok(files)
+}
+
 #[derive(Error, Debug)]
 #[error("Git command failed:\n{stdout}{stderr}\n")]
 struct GitBinaryCommandError {
@@ -3384,6 +3794,48 @@ fn checkpoint_author_envs() -> HashMap<String, String> {
     ])
 }
 
+/// Parses the output of `git log -L` run with a format whose records start
+/// with `\x1e` and consist of the NUL-terminated sha, subject, body,
+/// timestamp, author name and email, followed by the record's diff.
+fn parse_line_history(output: &str, path: &RepoPath) -> Vec<LineHistoryEntry> {
+    let mut entries = Vec::new();
+    for record in output.split('\x1e') {
+        let fields: Vec<&str> = record.splitn(7, '\0').collect();
+        let [
+            sha,
+            subject,
+            message,
+            timestamp,
+            author_name,
+            author_email,
+            diff,
+        ] = fields[..]
+        else {
+            continue;
+        };
+        let diff = diff.trim_start_matches('\n').trim_end();
+        let diff_path = diff
+            .lines()
+            .find_map(|line| line.strip_prefix("+++ b/"))
+            .or_else(|| diff.lines().find_map(|line| line.strip_prefix("--- a/")))
+            .and_then(|diff_path| RepoPath::new(diff_path).ok());
+
+        entries.push(LineHistoryEntry {
+            commit: FileHistoryEntry {
+                sha: sha.trim().to_string().into(),
+                subject: subject.trim().to_string().into(),
+                message: message.trim().to_string().into(),
+                commit_timestamp: timestamp.trim().parse().unwrap_or(0),
+                author_name: author_name.trim().to_string().into(),
+                author_email: author_email.trim().to_string().into(),
+            },
+            path: diff_path.unwrap_or_else(|| path.clone()),
+            diff: diff.to_string().into(),
+        });
+    }
+    entries
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
@@ -3958,6 +4410,403 @@ mod tests {
         assert!(result[0].is_main);
     }
 
+    #[test]
+    fn test_parse_reflog() {
+        assert!(parse_reflog("HEAD", "").is_empty());
+
+        let input = "060964da10574cd9bf06463a53bf6e0769c5c45e\0HEAD@{1733187470}\0reset: moving to HEAD~1\0Initial commit\n\
+                     eb0cae33272689bd11030822939dd2701c52f81e\0HEAD@{1733187400}\0commit: Add feature\0Add feature\n";
+        let entries = parse_reflog("HEAD", input);
+        assert_eq!(
+            entries,
+            vec![
+                ReflogEntry {
+                    selector: "HEAD@{0}".into(),
+                    sha: "060964da10574cd9bf06463a53bf6e0769c5c45e".into(),
+                    message: "reset: moving to HEAD~1".into(),
+                    subject: "Initial commit".into(),
+                    timestamp: 1733187470,
+                },
+                ReflogEntry {
+                    selector: "HEAD@{1}".into(),
+                    sha: "eb0cae33272689bd11030822939dd2701c52f81e".into(),
+                    message: "commit: Add feature".into(),
+                    subject: "Add feature".into(),
+                    timestamp: 1733187400,
+                },
+            ]
+        );
+        assert_eq!(entries[0].short_sha(), "060964d");
+    }
+
+    #[gpui::test]
+    async fn test_reflog_after_reset(cx: &mut TestAppContext) {
+        disable_git_global_config();
+        cx.executor().allow_parking();
+
+        let repo_dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(repo_dir.path()).unwrap();
+        let repo = RealGitRepository::new(
+            &repo_dir.path().join(".git"),
+            None,
+            Some("git".into()),
+            cx.executor(),
+        )
+        .unwrap();
+
+        for (contents, message) in [("one", "First commit"), ("two", "Second commit")] {
+            smol::fs::write(repo_dir.path().join("file.txt"), contents)
+                .await
+                .unwrap();
+            repo.stage_paths(vec![repo_path("file.txt")], Arc::new(HashMap::default()))
+                .await
+                .unwrap();
+            repo.commit(
+                message.into(),
+                None,
+                CommitOptions::default(),
+                AskPassDelegate::new(&mut cx.to_async(), |_, _, _| {}),
+                Arc::new(checkpoint_author_envs()),
+            )
+            .await
+            .unwrap();
+        }
+        let lost_sha = repo.head_sha().await.unwrap();
+
+        repo.reset(
+            "HEAD~1".into(),
+            ResetMode::Mixed,
+            Arc::new(HashMap::default()),
+        )
+        .await
+        .unwrap();
+
+        let entries = repo.reflog("HEAD".into()).await.unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].selector.as_ref(), "HEAD@{0}");
+        assert!(entries[0].message.starts_with("reset: moving to"));
+        assert_eq!(entries[0].subject.as_ref(), "First commit");
+        assert_eq!(entries[1].sha.as_ref(), lost_sha);
+        assert_eq!(entries[1].subject.as_ref(), "Second commit");
+
+        let branch = repo.branches().await.unwrap().into_iter().next().unwrap();
+        repo.create_branch("recovered".into(), Some(lost_sha.clone()))
+            .await
+            .unwrap();
+        repo.change_branch(branch.name().to_string()).await.unwrap();
+        repo.set_branch_target("recovered".into(), "HEAD".into())
+            .await
+            .unwrap();
+        let recovered = repo.reflog("recovered".into()).await.unwrap();
+        assert_eq!(recovered.len(), 2);
+        assert_eq!(recovered[1].sha.as_ref(), lost_sha);
+
+        // Ref names are never parsed as options.
+        assert!(repo.reflog("--all".into()).await.is_err());
+        assert!(
+            repo.set_branch_target("--delete".into(), "HEAD".into())
+                .await
+                .is_err()
+        );
+    }
+
+    #[gpui::test]
+    async fn test_load_branch_diff(cx: &mut TestAppContext) {
+        disable_git_global_config();
+        cx.executor().allow_parking();
+
+        let repo_dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(repo_dir.path()).unwrap();
+        let repo = RealGitRepository::new(
+            &repo_dir.path().join(".git"),
+            None,
+            Some("git".into()),
+            cx.executor(),
+        )
+        .unwrap();
+        let commit = async |files: &[(&str, Option<&str>)], message: &str| {
+            for (path, contents) in files {
+                let abs_path = repo_dir.path().join(path);
+                match contents {
+                    Some(contents) => smol::fs::write(abs_path, contents).await.unwrap(),
+                    None => smol::fs::remove_file(abs_path).await.unwrap(),
+                }
+            }
+            repo.stage_paths(
+                files.iter().map(|(path, _)| repo_path(path)).collect(),
+                Arc::new(HashMap::default()),
+            )
+            .await
+            .unwrap();
+            repo.commit(
+                message.into(),
+                None,
+                CommitOptions::default(),
+                AskPassDelegate::new(&mut cx.to_async(), |_, _, _| {}),
+                Arc::new(checkpoint_author_envs()),
+            )
+            .await
+            .unwrap();
+        };
+
+        commit(
+            &[
+                ("kept.txt", Some("one\ntwo\n")),
+                ("removed.txt", Some("gone\n")),
+            ],
+            "Initial commit",
+        )
+        .await;
+        let base_branch = repo.branches().await.unwrap().into_iter().next().unwrap();
+        repo.create_branch("feature".into(), None).await.unwrap();
+        repo.change_branch("feature".into()).await.unwrap();
+        commit(
+            &[
+                ("kept.txt", Some("one\n2\n")),
+                ("removed.txt", None),
+                ("added.txt", Some("new\n")),
+            ],
+            "Feature commit",
+        )
+        .await;
+        // Changes made to the base branch after the feature branched off are not
+        // part of the review.
+        repo.change_branch(base_branch.name().to_string())
+            .await
+            .unwrap();
+        commit(&[("other.txt", Some("other\n"))], "Base commit").await;
+
+        let mut files = repo
+            .load_branch_diff(
+                base_branch.name().to_string(),
+                "feature".into(),
+                cx.to_async(),
+            )
+            .await
+            .unwrap()
+            .files;
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(
+            files
+                .iter()
+                .map(|file| (
+                    file.path.as_unix_str(),
+                    file.old_text.as_deref(),
+                    file.new_text.as_deref()
+                ))
+                .collect::<Vec<_>>(),
+            [
+                ("added.txt", None, Some("new\n")),
+                ("kept.txt", Some("one\ntwo\n"), Some("one\n2\n")),
+                ("removed.txt", Some("gone\n"), None),
+            ]
+        );
+
+        assert!(
+            repo.load_branch_diff("--all".into(), "feature".into(), cx.to_async())
+                .await
+                .is_err()
+        );
+    }
+
+    #[gpui::test]
+    async fn test_line_history_follows_renames(cx: &mut TestAppContext) {
+        disable_git_global_config();
+        cx.executor().allow_parking();
+
+        let repo_dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(repo_dir.path()).unwrap();
+        let repo = RealGitRepository::new(
+            &repo_dir.path().join(".git"),
+            None,
+            Some("git".into()),
+            cx.executor(),
+        )
+        .unwrap();
+
+        let commit = async |message: &str| {
+            repo.commit(
+                message.to_string().into(),
+                None,
+                CommitOptions::default(),
+                AskPassDelegate::new(&mut cx.to_async(), |_, _, _| {}),
+                Arc::new(checkpoint_author_envs()),
+            )
+            .await
+            .unwrap();
+        };
+
+        smol::fs::write(repo_dir.path().join("old.txt"), "one\ntwo\nthree\nfour\n")
+            .await
+            .unwrap();
+        repo.stage_paths(vec![repo_path("old.txt")], Arc::new(HashMap::default()))
+            .await
+            .unwrap();
+        commit("Add file").await;
+
+        smol::fs::write(repo_dir.path().join("old.txt"), "one\nTWO\nthree\nfour\n")
+            .await
+            .unwrap();
+        repo.stage_paths(vec![repo_path("old.txt")], Arc::new(HashMap::default()))
+            .await
+            .unwrap();
+        commit("Change second line").await;
+
+        smol::fs::rename(
+            repo_dir.path().join("old.txt"),
+            repo_dir.path().join("new.txt"),
+        )
+        .await
+        .unwrap();
+        repo.stage_paths(
+            vec![repo_path("old.txt"), repo_path("new.txt")],
+            Arc::new(HashMap::default()),
+        )
+        .await
+        .unwrap();
+        commit("Rename file").await;
+
+        smol::fs::write(repo_dir.path().join("new.txt"), "one\nTWO\nthree\nFOUR\n")
+            .await
+            .unwrap();
+        repo.stage_paths(vec![repo_path("new.txt")], Arc::new(HashMap::default()))
+            .await
+            .unwrap();
+        commit("Change last line").await;
+
+        let history = repo.line_history(repo_path("new.txt"), 1..2).await.unwrap();
+        let subjects = history
+            .entries
+            .iter()
+            .map(|entry| entry.commit.subject.as_ref())
+            .collect::<Vec<_>>();
+        assert_eq!(subjects, ["Change second line", "Add file"]);
+        assert_eq!(history.entries[0].path, repo_path("old.txt"));
+        assert!(history.entries[0].diff.contains("-two\n+TWO"));
+        assert_eq!(history.entries[0].commit.author_name.as_ref(), "Zed");
+
+        let history = repo.line_history(repo_path("new.txt"), 3..4).await.unwrap();
+        assert_eq!(history.entries.len(), 2);
+        assert_eq!(history.entries[0].path, repo_path("new.txt"));
+        assert!(history.entries[0].diff.contains("+FOUR"));
+    }
+
+    #[gpui::test]
+    async fn test_commit_signing_options(cx: &mut TestAppContext) {
+        disable_git_global_config();
+        cx.executor().allow_parking();
+
+        let repo_dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(repo_dir.path()).unwrap();
+        let repo = RealGitRepository::new(
+            &repo_dir.path().join(".git"),
+            None,
+            Some("git".into()),
+            cx.executor(),
+        )
+        .unwrap();
+        let git = repo.git_binary().unwrap();
+        // Any attempt to sign would fail, so this only succeeds if signing is skipped.
+        git.run(&["config", "commit.gpgsign", "true"])
+            .await
+            .unwrap();
+        git.run(&["config", "gpg.program", "false"]).await.unwrap();
+
+        smol::fs::write(repo_dir.path().join("file.txt"), "one")
+            .await
+            .unwrap();
+        repo.stage_paths(vec![repo_path("file.txt")], Arc::new(HashMap::default()))
+            .await
+            .unwrap();
+        repo.commit(
+            "Unsigned commit".into(),
+            None,
+            CommitOptions {
+                sign: Some(false),
+                ..CommitOptions::default()
+            },
+            AskPassDelegate::new(&mut cx.to_async(), |_, _, _| {}),
+            Arc::new(checkpoint_author_envs()),
+        )
+        .await
+        .unwrap();
+
+        let details = repo.show("HEAD".into()).await.unwrap();
+        assert_eq!(details.message.trim(), "Unsigned commit");
+        assert_eq!(repo.commit_signature("HEAD".into()).await.unwrap(), None);
+    }
+
+    #[gpui::test]
+    async fn test_submodules(cx: &mut TestAppContext) {
+        disable_git_global_config();
+        cx.executor().allow_parking();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let sub_dir = temp_dir.path().join("sub");
+        let super_dir = temp_dir.path().join("super");
+        fs::create_dir_all(&sub_dir).unwrap();
+        fs::create_dir_all(&super_dir).unwrap();
+
+        let mut repos = Vec::new();
+        for dir in [&sub_dir, &super_dir] {
+            git2::Repository::init(dir).unwrap();
+            let repo =
+                RealGitRepository::new(&dir.join(".git"), None, Some("git".into()), cx.executor())
+                    .unwrap();
+            smol::fs::write(dir.join("file.txt"), "one").await.unwrap();
+            repo.stage_paths(vec![repo_path("file.txt")], Arc::new(HashMap::default()))
+                .await
+                .unwrap();
+            repo.commit(
+                "Initial commit".into(),
+                None,
+                CommitOptions::default(),
+                AskPassDelegate::new(&mut cx.to_async(), |_, _, _| {}),
+                Arc::new(checkpoint_author_envs()),
+            )
+            .await
+            .unwrap();
+            repos.push(repo);
+        }
+        let super_repo = repos.pop().unwrap();
+        assert_eq!(super_repo.submodules().await.unwrap(), Vec::new());
+
+        let git = super_repo.git_binary().unwrap();
+        git.run(&[
+            "-c",
+            "protocol.file.allow=always",
+            "submodule",
+            "add",
+            sub_dir.to_str().unwrap(),
+            "libs/sub",
+        ])
+        .await
+        .unwrap();
+        super_repo
+            .commit(
+                "Add submodule".into(),
+                None,
+                CommitOptions::default(),
+                AskPassDelegate::new(&mut cx.to_async(), |_, _, _| {}),
+                Arc::new(checkpoint_author_envs()),
+            )
+            .await
+            .unwrap();
+
+        let submodules = super_repo.submodules().await.unwrap();
+        assert_eq!(submodules.len(), 1);
+        assert_eq!(submodules[0].path, repo_path("libs/sub"));
+        assert_eq!(submodules[0].status.label(), "up to date");
+        assert_eq!(submodules[0].recorded_sha, submodules[0].checked_out_sha);
+
+        smol::fs::write(super_dir.join("libs/sub/file.txt"), "two")
+            .await
+            .unwrap();
+        let submodules = super_repo.submodules().await.unwrap();
+        assert!(submodules[0].status.has_modified_content);
+        assert!(!submodules[0].status.commit_changed);
+    }
+
     #[gpui::test]
     async fn test_create_and_list_worktrees(cx: &mut TestAppContext) {
         disable_git_global_config();
@@ -0,0 +1,103 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+use gpui::{App, Global, SharedString};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// A branch under review, and the branch it is going to be merged into.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ReviewTarget {
+    pub base_ref: SharedString,
+    pub head_ref: SharedString,
+}
+
+impl ReviewTarget {
+    pub fn display_name(&self) -> String {
+        format!("{}...{}", self.base_ref, self.head_ref)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewComment {
+    pub author: SharedString,
+    pub body: String,
+    pub timestamp: i64,
+}
+
+/// A conversation anchored to a line of a file on the reviewed branch.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewThread {
+    pub id: u64,
+    pub path: String,
+    /// Zero-based row of the line in the file, as of when the thread was last saved.
+    pub row: u32,
+    /// The text of the line, used to find it again if the file changed since.
+    #[serde(default)]
+    pub line_text: Option<String>,
+    pub comments: Vec<ReviewComment>,
+    pub resolved: bool,
+    /// How many of the leading comments have been sent through a [`ReviewProvider`].
+    #[serde(default)]
+    pub posted_count: usize,
+}
+
+impl ReviewThread {
+    pub fn unposted_comments(&self) -> &[ReviewComment] {
+        &self.comments[self.posted_count.min(self.comments.len())..]
+    }
+}
+
+/// Publishes locally written review comments somewhere other reviewers can see them,
+/// such as a pull request on a hosting provider.
+pub trait ReviewProvider: Send + Sync {
+    fn name(&self) -> SharedString;
+
+    fn post_comments(
+        &self,
+        target: &ReviewTarget,
+        thread: &ReviewThread,
+        comments: Vec<ReviewComment>,
+    ) -> BoxFuture<'static, Result<()>>;
+}
+
+#[derive(Default)]
+struct GlobalReviewProvider(Option<Arc<dyn ReviewProvider>>);
+
+impl Global for GlobalReviewProvider {}
+
+/// Sets the provider used to post review comments.
+pub fn set_review_provider(provider: Arc<dyn ReviewProvider>, cx: &mut App) {
+    cx.set_global(GlobalReviewProvider(Some(provider)));
+}
+
+/// Returns the provider used to post review comments, if one has been set.
+pub fn review_provider(cx: &App) -> Option<Arc<dyn ReviewProvider>> {
+    cx.try_global::<GlobalReviewProvider>()
+        .and_then(|global| global.0.clone())
+}
+
+#[cfg(any(test, feature = "test-support"))]
+#[derive(Default)]
+pub struct FakeReviewProvider {
+    pub posted: parking_lot::Mutex<Vec<(ReviewTarget, u64, ReviewComment)>>,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl ReviewProvider for FakeReviewProvider {
+    fn name(&self) -> SharedString {
+        "Fake".into()
+    }
+
+    fn post_comments(
+        &self,
+        target: &ReviewTarget,
+        thread: &ReviewThread,
+        comments: Vec<ReviewComment>,
+    ) -> BoxFuture<'static, Result<()>> {
+        let mut posted = self.posted.lock();
+        for comment in comments {
+            posted.push((target.clone(), thread.id, comment));
+        }
+        Box::pin(futures::future::ready(Ok(())))
+    }
+}
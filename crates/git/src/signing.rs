@@ -0,0 +1,154 @@
+use gpui::SharedString;
+
+/// The result of verifying a commit signature, as reported by `git log --format=%G?`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SignatureStatus {
+    /// A good signature from a trusted key.
+    Good,
+    /// A good signature from a key of unknown validity.
+    UnknownValidity,
+    /// A good signature that has expired.
+    Expired,
+    /// A good signature made by a key that has since expired.
+    ExpiredKey,
+    /// A good signature made by a key that has since been revoked.
+    RevokedKey,
+    /// The signature could not be checked, usually because the key is missing.
+    Unverifiable,
+    Bad,
+}
+
+impl SignatureStatus {
+    /// Parses a `%G?` status letter. Returns `None` for unsigned commits.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "G" => Some(Self::Good),
+            "U" => Some(Self::UnknownValidity),
+            "X" => Some(Self::Expired),
+            "Y" => Some(Self::ExpiredKey),
+            "R" => Some(Self::RevokedKey),
+            "E" => Some(Self::Unverifiable),
+            "B" => Some(Self::Bad),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Good => "G",
+            Self::UnknownValidity => "U",
+            Self::Expired => "X",
+            Self::ExpiredKey => "Y",
+            Self::RevokedKey => "R",
+            Self::Unverifiable => "E",
+            Self::Bad => "B",
+        }
+    }
+
+    pub fn is_verified(&self) -> bool {
+        matches!(self, Self::Good | Self::UnknownValidity)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Good => "Verified",
+            Self::UnknownValidity => "Verified (untrusted key)",
+            Self::Expired => "Expired signature",
+            Self::ExpiredKey => "Signed with expired key",
+            Self::RevokedKey => "Signed with revoked key",
+            Self::Unverifiable => "Unverified signature",
+            Self::Bad => "Bad signature",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CommitSignature {
+    pub status: SignatureStatus,
+    /// The signer's identity, such as the user ID of a GPG key.
+    pub signer: SharedString,
+    /// The key the commit was signed with.
+    pub key: SharedString,
+}
+
+impl CommitSignature {
+    /// Builds a signature from the `%G?`, `%GS` and `%GK` placeholders of `git log`.
+    pub fn from_format_fields(status: &str, signer: &str, key: &str) -> Option<Self> {
+        Some(Self {
+            status: SignatureStatus::from_code(status.trim())?,
+            signer: signer.trim().to_string().into(),
+            key: key.trim().to_string().into(),
+        })
+    }
+}
+
+/// The `gpg.format` configured for signing commits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SigningFormat {
+    OpenPgp,
+    Ssh,
+    X509,
+}
+
+impl SigningFormat {
+    pub fn from_config(value: Option<&str>) -> Self {
+        match value.map(str::trim) {
+            Some("ssh") => Self::Ssh,
+            Some("x509") => Self::X509,
+            _ => Self::OpenPgp,
+        }
+    }
+}
+
+/// Name of the environment variable holding the GPG program wrapped by
+/// [`GPG_ASKPASS_SCRIPT`].
+pub const GPG_PROGRAM_ENV_VAR: &str = "ZED_GPG_PROGRAM";
+
+/// A `gpg.program` wrapper that signs with the passphrase cached by gpg-agent when
+/// possible, and otherwise asks for it through `$GIT_ASKPASS` and passes it to gpg
+/// in loopback mode. SSH keys don't need this, as `ssh-keygen` honors `SSH_ASKPASS`.
+pub const GPG_ASKPASS_SCRIPT: &str = r#"#!/bin/sh
+data="$(mktemp)" || exit 1
+err="$(mktemp)" || exit 1
+trap 'rm -f "$data" "$err"' EXIT
+cat > "$data"
+
+if "$ZED_GPG_PROGRAM" --pinentry-mode error "$@" < "$data" 2> "$err"; then
+    cat "$err" >&2
+    exit 0
+fi
+
+passphrase="$("$GIT_ASKPASS" "Passphrase for GPG signing key:")" || exit 1
+"$ZED_GPG_PROGRAM" --pinentry-mode loopback --passphrase-fd 3 "$@" < "$data" 3<<ZED_GPG_PASSPHRASE
+$passphrase
+ZED_GPG_PASSPHRASE
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commit_signature() {
+        let signature = CommitSignature::from_format_fields(
+            "G",
+            "Jane Doe <jane@example.com>",
+            "ABCDEF0123456789",
+        )
+        .unwrap();
+        assert_eq!(signature.status, SignatureStatus::Good);
+        assert!(signature.status.is_verified());
+        assert_eq!(signature.signer.as_ref(), "Jane Doe <jane@example.com>");
+
+        let signature = CommitSignature::from_format_fields("B", "", "ABCDEF").unwrap();
+        assert!(!signature.status.is_verified());
+        assert_eq!(signature.status.label(), "Bad signature");
+
+        assert_eq!(CommitSignature::from_format_fields("N", "", ""), None);
+        assert_eq!(
+            SigningFormat::from_config(Some("ssh\n")),
+            SigningFormat::Ssh
+        );
+        assert_eq!(SigningFormat::from_config(None), SigningFormat::OpenPgp);
+    }
+}
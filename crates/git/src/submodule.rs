@@ -0,0 +1,281 @@
+use crate::repository::RepoPath;
+use collections::HashMap;
+use gpui::SharedString;
+
+/// A submodule registered in a repository, along with its state relative to
+/// the superproject.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Submodule {
+    pub path: RepoPath,
+    /// The commit the superproject's `HEAD` records for this submodule.
+    pub recorded_sha: Option<SharedString>,
+    /// The commit checked out inside the submodule, if it is initialized.
+    pub checked_out_sha: Option<SharedString>,
+    pub status: SubmoduleStatus,
+}
+
+impl Submodule {
+    /// Whether the submodule has anything worth expanding in a diff.
+    pub fn has_changes(&self) -> bool {
+        self.status.initialized && (self.status.commit_changed || self.status.is_dirty())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SubmoduleStatus {
+    pub initialized: bool,
+    /// The checked out commit differs from the one recorded in the superproject.
+    pub commit_changed: bool,
+    pub has_modified_content: bool,
+    pub has_untracked_content: bool,
+    pub conflicted: bool,
+}
+
+impl SubmoduleStatus {
+    pub fn is_dirty(&self) -> bool {
+        self.has_modified_content || self.has_untracked_content
+    }
+
+    pub fn label(&self) -> &'static str {
+        if !self.initialized {
+            "not initialized"
+        } else if self.conflicted {
+            "conflict"
+        } else {
+            match (self.commit_changed, self.is_dirty()) {
+                (true, true) => "new commits, modified",
+                (true, false) => "new commits",
+                (false, true) => "modified content",
+                (false, false) => "up to date",
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubmoduleOperation {
+    /// Registers submodules from `.gitmodules` in the repository config.
+    Init,
+    /// Checks out the recorded commit, cloning the submodule if needed.
+    Update,
+    /// Copies submodule URLs from `.gitmodules` into the repository config.
+    Sync,
+}
+
+impl SubmoduleOperation {
+    pub fn args(&self) -> &'static [&'static str] {
+        match self {
+            SubmoduleOperation::Init => &["submodule", "init"],
+            SubmoduleOperation::Update => &["submodule", "update", "--init"],
+            SubmoduleOperation::Sync => &["submodule", "sync"],
+        }
+    }
+
+    /// Whether the operation may talk to a remote and thus need credentials.
+    pub fn is_remote(&self) -> bool {
+        matches!(self, SubmoduleOperation::Update)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SubmoduleOperation::Init => "init",
+            SubmoduleOperation::Update => "update",
+            SubmoduleOperation::Sync => "sync",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "init" => Some(SubmoduleOperation::Init),
+            "update" => Some(SubmoduleOperation::Update),
+            "sync" => Some(SubmoduleOperation::Sync),
+            _ => None,
+        }
+    }
+}
+
+/// A single line of `git submodule status` output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubmoduleStatusLine {
+    pub path: RepoPath,
+    pub sha: SharedString,
+    pub initialized: bool,
+    pub commit_changed: bool,
+    pub conflicted: bool,
+}
+
+/// Parses the output of `git submodule status`, whose lines look like
+/// `[ -+U]<sha> <path>[ (<describe>)]`.
+pub fn parse_submodule_status(input: &str) -> Vec<SubmoduleStatusLine> {
+    input
+        .lines()
+        .filter_map(|line| {
+            let mut chars = line.chars();
+            let marker = chars.next()?;
+            let rest = chars.as_str();
+            let (sha, path) = rest.split_once(' ')?;
+            let path = match path.rfind(" (") {
+                Some(ix) if path.ends_with(')') => &path[..ix],
+                _ => path,
+            };
+            Some(SubmoduleStatusLine {
+                path: RepoPath::new(path).ok()?,
+                sha: SharedString::from(sha.to_string()),
+                initialized: marker != '-',
+                commit_changed: marker == '+',
+                conflicted: marker == 'U',
+            })
+        })
+        .collect()
+}
+
+/// Parses NUL-separated `git ls-tree -z` output, keeping only gitlink
+/// (submodule) entries and returning the commit each one records.
+pub fn parse_gitlinks(input: &str) -> HashMap<RepoPath, SharedString> {
+    input
+        .split('\0')
+        .filter_map(|entry| {
+            let (meta, path) = entry.split_once('\t')?;
+            let mut fields = meta.split(' ');
+            let mode = fields.next()?;
+            let kind = fields.next()?;
+            let sha = fields.next()?;
+            if mode != "160000" || kind != "commit" {
+                return None;
+            }
+            Some((
+                RepoPath::new(path).ok()?,
+                SharedString::from(sha.to_string()),
+            ))
+        })
+        .collect()
+}
+
+/// Parses `git status --porcelain=v2 -z` output, returning the submodule state
+/// field (`S<c><m><u>`) for every entry that is a submodule.
+pub fn parse_porcelain_submodule_states(input: &str) -> HashMap<RepoPath, SubmoduleStatus> {
+    let mut states = HashMap::default();
+    let mut entries = input.split('\0');
+    while let Some(entry) = entries.next() {
+        let (field_count, is_rename) = match entry.as_bytes().first() {
+            Some(b'1') => (8, false),
+            Some(b'2') => (9, true),
+            Some(b'u') => (10, false),
+            _ => continue,
+        };
+        if is_rename {
+            // Renamed entries are followed by their original path.
+            entries.next();
+        }
+        let mut fields = entry.splitn(field_count + 1, ' ');
+        let xy = fields.nth(1).unwrap_or_default();
+        let Some(sub) = fields.next() else {
+            continue;
+        };
+        let Some(path) = fields.nth(field_count - 3) else {
+            continue;
+        };
+        let sub = sub.as_bytes();
+        if sub.len() != 4 || sub[0] != b'S' {
+            continue;
+        }
+        let Ok(path) = RepoPath::new(path) else {
+            continue;
+        };
+        states.insert(
+            path,
+            SubmoduleStatus {
+                initialized: true,
+                commit_changed: sub[1] == b'C',
+                has_modified_content: sub[2] == b'M',
+                has_untracked_content: sub[3] == b'U',
+                conflicted: entry.starts_with('u') || xy.contains('U'),
+            },
+        );
+    }
+    states
+}
+
+/// Combines the outputs of `git submodule status`, `git ls-tree` and
+/// `git status --porcelain=v2` into a list of submodules.
+pub fn merge_submodule_info(
+    status_lines: Vec<SubmoduleStatusLine>,
+    mut gitlinks: HashMap<RepoPath, SharedString>,
+    mut states: HashMap<RepoPath, SubmoduleStatus>,
+) -> Vec<Submodule> {
+    status_lines
+        .into_iter()
+        .map(|line| {
+            let recorded_sha = gitlinks.remove(&line.path);
+            let state = states.remove(&line.path).unwrap_or_default();
+            let checked_out_sha = line.initialized.then(|| line.sha.clone());
+            let commit_changed = line.initialized
+                && (line.commit_changed
+                    || state.commit_changed
+                    || recorded_sha.as_ref().is_some_and(|sha| *sha != line.sha));
+            Submodule {
+                path: line.path,
+                recorded_sha,
+                checked_out_sha,
+                status: SubmoduleStatus {
+                    initialized: line.initialized,
+                    commit_changed,
+                    has_modified_content: line.initialized && state.has_modified_content,
+                    has_untracked_content: line.initialized && state.has_untracked_content,
+                    conflicted: line.conflicted || state.conflicted,
+                },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::repo_path;
+
+    #[test]
+    fn test_parse_submodule_status() {
+        let input = " 1111111111111111111111111111111111111111 libs/a (v1.0)\n\
+                     -2222222222222222222222222222222222222222 libs/b\n\
+                     +3333333333333333333333333333333333333333 libs/with space (heads/main)\n\
+                     U0000000000000000000000000000000000000000 libs/d\n";
+        let lines = parse_submodule_status(input);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0].path, repo_path("libs/a"));
+        assert!(lines[0].initialized && !lines[0].commit_changed);
+        assert_eq!(lines[1].path, repo_path("libs/b"));
+        assert!(!lines[1].initialized);
+        assert_eq!(lines[2].path, repo_path("libs/with space"));
+        assert!(lines[2].commit_changed);
+        assert!(lines[3].conflicted);
+    }
+
+    #[test]
+    fn test_parse_gitlinks() {
+        let input = "160000 commit 1111111111111111111111111111111111111111\tlibs/a\0\
+                     100644 blob 2222222222222222222222222222222222222222\t.gitmodules\0";
+        let gitlinks = parse_gitlinks(input);
+        assert_eq!(gitlinks.len(), 1);
+        assert_eq!(
+            gitlinks[&repo_path("libs/a")].as_ref(),
+            "1111111111111111111111111111111111111111"
+        );
+    }
+
+    #[test]
+    fn test_parse_porcelain_submodule_states() {
+        let input = "1 .M SCM. 160000 160000 160000 aaaa bbbb libs/a\0\
+                     1 .M N... 100644 100644 100644 cccc dddd src/main.rs\0\
+                     2 R. N... 100644 100644 100644 eeee ffff R100 new.rs\0old.rs\0\
+                     1 .M S..U 160000 160000 160000 aaaa aaaa libs/b\0\
+                     ? untracked.txt\0";
+        let states = parse_porcelain_submodule_states(input);
+        assert_eq!(states.len(), 2);
+        let a = states[&repo_path("libs/a")];
+        assert!(a.commit_changed && a.has_modified_content && !a.has_untracked_content);
+        let b = states[&repo_path("libs/b")];
+        assert!(!b.commit_changed && !b.has_modified_content && b.has_untracked_content);
+        assert_eq!(b.label(), "modified content");
+    }
+}
@@ -0,0 +1,782 @@
+use anyhow::{Context as _, Result};
+use collections::HashMap;
+use db::kvp::KeyValueStore;
+use editor::Editor;
+use editor::display_map::{
+    BlockContext, BlockPlacement, BlockProperties, BlockStyle, CustomBlockId, RenderBlock,
+};
+use git::repository::get_git_committer;
+use git::review::{ReviewComment, ReviewTarget, ReviewThread, review_provider};
+use gpui::{
+    App, AsyncWindowContext, Context, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable,
+    InteractiveElement, IntoElement, ParentElement, Render, SharedString, Styled, Task, WeakEntity,
+    Window, actions, rems,
+};
+use language::{Anchor, BufferSnapshot, Point, ToPoint as _};
+use menu::{Cancel, Confirm};
+use project::git_store::Repository;
+use std::{path::Path, sync::Arc};
+use time::OffsetDateTime;
+use ui::{Headline, HeadlineSize, prelude::*};
+use util::ResultExt;
+use workspace::notifications::DetachAndPromptErr;
+use workspace::{ModalView, Toast, Workspace, notifications::NotificationId};
+
+use crate::multi_diff_view::MultiDiffView;
+
+actions!(
+    branch_review,
+    [
+        /// Adds a comment to the thread on the line under the cursor, starting one if needed.
+        AddComment,
+        /// Resolves the thread on the line under the cursor, or reopens it if it was resolved.
+        ToggleThreadResolved,
+        /// Posts comments that haven't been posted yet through the configured review provider.
+        PostComments,
+    ]
+);
+
+const BRANCH_REVIEW_KEY: &str = "GitBranchReview";
+
+pub fn register(workspace: &mut Workspace) {
+    workspace.register_action(open);
+}
+
+fn open(
+    workspace: &mut Workspace,
+    action: &zed_actions::git::ReviewBranch,
+    window: &mut Window,
+    cx: &mut Context<Workspace>,
+) {
+    let Some(repository) = crate::resolve_active_repository(workspace, cx) else {
+        return;
+    };
+    let head_ref = action
+        .head
+        .clone()
+        .filter(|head| !head.is_empty())
+        .unwrap_or_else(|| "HEAD".to_string());
+    let base_ref = action.base.clone().filter(|base| !base.is_empty());
+    let workspace = workspace.weak_handle();
+
+    window
+        .spawn(cx, async move |cx| {
+            let base_ref = match base_ref {
+                Some(base_ref) => base_ref,
+                None => repository
+                    .update(cx, |repository, _| repository.default_branch(false))
+                    .await??
+                    .context("could not determine which branch to review against")?
+                    .to_string(),
+            };
+            let target = ReviewTarget {
+                base_ref: base_ref.into(),
+                head_ref: head_ref.into(),
+            };
+            let files = repository
+                .update(cx, |repository, _| {
+                    repository
+                        .load_branch_diff(target.base_ref.to_string(), target.head_ref.to_string())
+                })
+                .await??
+                .files;
+
+            let view = workspace
+                .update_in(cx, |workspace, window, cx| {
+                    MultiDiffView::open_file_diffs(
+                        format!("Review {}", target.display_name()).into(),
+                        files,
+                        repository.clone(),
+                        workspace,
+                        window,
+                        cx,
+                    )
+                })?
+                .await?;
+            BranchReview::attach(target, &repository, &view, workspace, cx).await?;
+            anyhow::Ok(())
+        })
+        .detach_and_prompt_err("Failed to open branch review", window, cx, |_, _, _| None);
+}
+
+/// Comment threads for a branch under review, rendered into the diff of that branch.
+///
+/// Threads are stored locally per repository and review target, so that they survive
+/// reopening the review, and can optionally be posted through a [`git::review::ReviewProvider`].
+pub struct BranchReview {
+    target: ReviewTarget,
+    serialization_key: String,
+    threads: Vec<ReviewThread>,
+    /// The start of the line each thread is attached to, keyed by thread id.
+    anchors: HashMap<u64, Anchor>,
+    author: SharedString,
+    editor: WeakEntity<Editor>,
+    workspace: WeakEntity<Workspace>,
+    blocks: Vec<CustomBlockId>,
+    pending_serialization: Task<Option<()>>,
+}
+
+impl BranchReview {
+    /// Loads the stored threads for `target` and shows them in `view`.
+    pub async fn attach(
+        target: ReviewTarget,
+        repository: &Entity<Repository>,
+        view: &Entity<MultiDiffView>,
+        workspace: WeakEntity<Workspace>,
+        cx: &mut AsyncWindowContext,
+    ) -> Result<Entity<Self>> {
+        let work_directory = repository.read_with(cx, |repository, _| {
+            repository.work_directory_abs_path.clone()
+        });
+        let serialization_key = Self::serialization_key(&work_directory, &target);
+        let kvp = cx.update(|_, cx| KeyValueStore::global(cx))?;
+        let threads = cx
+            .background_spawn({
+                let serialization_key = serialization_key.clone();
+                async move { kvp.read_kvp(&serialization_key) }
+            })
+            .await
+            .context("loading review threads")
+            .log_err()
+            .flatten()
+            .and_then(|threads| serde_json::from_str::<Vec<ReviewThread>>(&threads).log_err())
+            .unwrap_or_default();
+        let author = get_git_committer(cx)
+            .await
+            .name
+            .map(SharedString::from)
+            .unwrap_or_else(|| "You".into());
+        let editor = view.read_with(cx, |view, _| view.editor().downgrade());
+
+        let review = cx.new(|cx| {
+            let mut review = Self {
+                target,
+                serialization_key,
+                threads,
+                anchors: HashMap::default(),
+                author,
+                editor,
+                workspace,
+                blocks: Vec::new(),
+                pending_serialization: Task::ready(None),
+            };
+            review.anchor_threads(cx);
+            review.refresh_blocks(cx);
+            review
+        });
+        view.update(cx, |view, cx| view.set_review(review.clone(), cx));
+        Ok(review)
+    }
+
+    fn serialization_key(work_directory: &Path, target: &ReviewTarget) -> String {
+        format!(
+            "{}-{}-{}",
+            BRANCH_REVIEW_KEY,
+            work_directory.display(),
+            target.display_name()
+        )
+    }
+
+    pub fn target(&self) -> &ReviewTarget {
+        &self.target
+    }
+
+    pub fn threads(&self) -> &[ReviewThread] {
+        &self.threads
+    }
+
+    /// The row of the reviewed file that the thread is currently attached to.
+    pub fn thread_row(&self, thread_id: u64, cx: &App) -> Option<u32> {
+        let anchor = self.anchors.get(&thread_id)?;
+        let (_, buffer) = self.buffer_for_anchor(*anchor, cx)?;
+        Some(anchor.to_point(&buffer).row)
+    }
+
+    /// Adds a comment to the unresolved thread on the line of `anchor`, starting a
+    /// new thread if there is none.
+    pub fn add_comment(&mut self, anchor: Anchor, body: String, cx: &mut Context<Self>) {
+        let Some((path, buffer)) = self.buffer_for_anchor(anchor, cx) else {
+            return;
+        };
+        let row = anchor.to_point(&buffer).row;
+        let comment = ReviewComment {
+            author: self.author.clone(),
+            body,
+            timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+        };
+        let existing_thread = self.threads.iter().position(|thread| {
+            !thread.resolved && thread.path == path && self.thread_row(thread.id, cx) == Some(row)
+        });
+        if let Some(ix) = existing_thread {
+            self.threads[ix].comments.push(comment);
+        } else {
+            let id = self
+                .threads
+                .iter()
+                .map(|thread| thread.id + 1)
+                .max()
+                .unwrap_or_default();
+            self.threads.push(ReviewThread {
+                id,
+                path,
+                row,
+                line_text: Some(line_text(&buffer, row)),
+                comments: vec![comment],
+                resolved: false,
+                posted_count: 0,
+            });
+            self.anchors
+                .insert(id, buffer.anchor_before(Point::new(row, 0)));
+        }
+        self.threads_changed(cx);
+    }
+
+    pub fn toggle_resolved(&mut self, thread_id: u64, cx: &mut Context<Self>) {
+        if let Some(thread) = self
+            .threads
+            .iter_mut()
+            .find(|thread| thread.id == thread_id)
+        {
+            thread.resolved = !thread.resolved;
+            self.threads_changed(cx);
+        }
+    }
+
+    pub(crate) fn toggle_resolved_at_cursor(&mut self, cx: &mut Context<Self>) {
+        let Some((path, _, row)) = self.cursor_position(cx) else {
+            return;
+        };
+        // Prefer the open thread on the line, so that a resolved one can't shadow it.
+        let thread_id = self
+            .threads
+            .iter()
+            .filter(|thread| thread.path == path && self.thread_row(thread.id, cx) == Some(row))
+            .min_by_key(|thread| thread.resolved)
+            .map(|thread| thread.id);
+        if let Some(thread_id) = thread_id {
+            self.toggle_resolved(thread_id, cx);
+        }
+    }
+
+    /// Posts every comment that hasn't been posted yet, returning how many were posted.
+    pub fn post_comments(&mut self, cx: &mut Context<Self>) -> Task<Result<usize>> {
+        let Some(provider) = review_provider(cx) else {
+            return Task::ready(Err(anyhow::anyhow!(
+                "No review provider is configured to post comments to"
+            )));
+        };
+        let target = self.target.clone();
+        let pending = self
+            .threads
+            .iter()
+            .filter(|thread| !thread.unposted_comments().is_empty())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        cx.spawn(async move |this, cx| {
+            let mut posted = 0;
+            for thread in pending {
+                let comments = thread.unposted_comments().to_vec();
+                let count = comments.len();
+                provider
+                    .post_comments(&target, &thread, comments)
+                    .await
+                    .with_context(|| format!("posting comments on {}", thread.path))?;
+                posted += count;
+                this.update(cx, |this, cx| {
+                    if let Some(stored) = this.threads.iter_mut().find(|t| t.id == thread.id) {
+                        stored.posted_count = thread.posted_count + count;
+                    }
+                    this.threads_changed(cx);
+                })?;
+            }
+            Ok(posted)
+        })
+    }
+
+    pub(crate) fn post_comments_and_notify(
+        review: &Entity<Self>,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        let workspace = review.read(cx).workspace.clone();
+        let task = review.update(cx, |review, cx| review.post_comments(cx));
+        window
+            .spawn(cx, async move |cx| {
+                let posted = task.await?;
+                let message = match posted {
+                    0 => "No new review comments to post".to_string(),
+                    1 => "Posted 1 review comment".to_string(),
+                    count => format!("Posted {count} review comments"),
+                };
+                workspace.update(cx, |workspace, cx| {
+                    struct PostedReviewComments;
+                    workspace.show_toast(
+                        Toast::new(NotificationId::unique::<PostedReviewComments>(), message)
+                            .autohide(),
+                        cx,
+                    );
+                })?;
+                anyhow::Ok(())
+            })
+            .detach_and_prompt_err("Failed to post review comments", window, cx, |_, _, _| None);
+    }
+
+    pub(crate) fn deploy_comment_modal(review: &Entity<Self>, window: &mut Window, cx: &mut App) {
+        let (workspace, position) = {
+            let review = review.read(cx);
+            (review.workspace.clone(), review.cursor_position(cx))
+        };
+        let Some((path, anchor, row)) = position else {
+            return;
+        };
+        let review = review.clone();
+        workspace
+            .update(cx, |workspace, cx| {
+                workspace.toggle_modal(window, cx, |window, cx| {
+                    ReviewCommentModal::new(review, path, anchor, row, window, cx)
+                });
+            })
+            .ok();
+    }
+
+    /// The file, buffer position and zero-based row under the newest cursor.
+    fn cursor_position(&self, cx: &App) -> Option<(String, Anchor, u32)> {
+        let editor = self.editor.upgrade()?;
+        let editor = editor.read(cx);
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let (anchor, buffer) =
+            snapshot.anchor_to_buffer_anchor(editor.selections.newest_anchor().head())?;
+        let path = buffer.file()?.path().as_unix_str().to_string();
+        Some((path, anchor, anchor.to_point(buffer).row))
+    }
+
+    fn buffer_for_anchor(&self, anchor: Anchor, cx: &App) -> Option<(String, BufferSnapshot)> {
+        let editor = self.editor.upgrade()?;
+        let buffer = editor.read(cx).buffer().read(cx).buffer(anchor.buffer_id)?;
+        let buffer = buffer.read(cx);
+        let path = buffer.file()?.path().as_unix_str().to_string();
+        Some((path, buffer.snapshot()))
+    }
+
+    /// Attaches each stored thread to its line in the reviewed files. A thread whose
+    /// line has moved since it was saved is matched back up by the line's text.
+    fn anchor_threads(&mut self, cx: &App) {
+        let Some(editor) = self.editor.upgrade() else {
+            return;
+        };
+        let buffers = editor.read(cx).buffer().read(cx).all_buffers();
+        self.anchors.clear();
+        for thread in &self.threads {
+            let Some(buffer) = buffers.iter().find(|buffer| {
+                buffer
+                    .read(cx)
+                    .file()
+                    .is_some_and(|file| file.path().as_unix_str() == thread.path)
+            }) else {
+                continue;
+            };
+            let buffer = buffer.read(cx).snapshot();
+            let row = thread
+                .line_text
+                .as_deref()
+                .and_then(|text| find_line(&buffer, thread.row, text))
+                .unwrap_or_else(|| thread.row.min(buffer.max_point().row));
+            self.anchors
+                .insert(thread.id, buffer.anchor_before(Point::new(row, 0)));
+        }
+    }
+
+    /// Records where each thread's line is now, for the next time the review is opened.
+    fn sync_thread_rows(&mut self, cx: &App) {
+        let Some(editor) = self.editor.upgrade() else {
+            return;
+        };
+        let multibuffer = editor.read(cx).buffer().read(cx);
+        for thread in &mut self.threads {
+            let Some(anchor) = self.anchors.get(&thread.id) else {
+                continue;
+            };
+            let Some(buffer) = multibuffer.buffer(anchor.buffer_id) else {
+                continue;
+            };
+            let buffer = buffer.read(cx).snapshot();
+            thread.row = anchor.to_point(&buffer).row;
+            thread.line_text = Some(line_text(&buffer, thread.row));
+        }
+    }
+
+    fn threads_changed(&mut self, cx: &mut Context<Self>) {
+        self.refresh_blocks(cx);
+        self.serialize(cx);
+        cx.notify();
+    }
+
+    fn serialize(&mut self, cx: &mut Context<Self>) {
+        self.sync_thread_rows(cx);
+        let kvp = KeyValueStore::global(cx);
+        let serialization_key = self.serialization_key.clone();
+        let threads = serde_json::to_string(&self.threads);
+        self.pending_serialization = cx.background_spawn(
+            async move {
+                kvp.write_kvp(serialization_key, threads?).await?;
+                anyhow::Ok(())
+            }
+            .log_err(),
+        );
+    }
+
+    fn refresh_blocks(&mut self, cx: &mut Context<Self>) {
+        let Some(editor) = self.editor.upgrade() else {
+            return;
+        };
+        let old_blocks = std::mem::take(&mut self.blocks);
+        let threads = &self.threads;
+        let anchors = &self.anchors;
+        self.blocks = editor.update(cx, |editor, cx| {
+            editor.remove_blocks(old_blocks.into_iter().collect(), None, cx);
+
+            let multibuffer = editor.buffer().read(cx);
+            let snapshot = multibuffer.snapshot(cx);
+            let blocks = threads
+                .iter()
+                .filter_map(|thread| {
+                    let line_anchor = anchors.get(&thread.id)?;
+                    let buffer = multibuffer
+                        .buffer(line_anchor.buffer_id)?
+                        .read(cx)
+                        .snapshot();
+                    let row = line_anchor.to_point(&buffer).row;
+                    let text_anchor = buffer.anchor_after(Point::new(row, buffer.line_len(row)));
+                    let anchor = snapshot.anchor_in_buffer(text_anchor)?;
+                    let height = if thread.resolved {
+                        1
+                    } else {
+                        1 + thread.comments.len() as u32
+                    };
+                    Some(BlockProperties {
+                        placement: BlockPlacement::Below(anchor),
+                        height: Some(height),
+                        style: BlockStyle::Flex,
+                        render: render_thread(thread.clone()),
+                        priority: 0,
+                    })
+                })
+                .collect::<Vec<_>>();
+            editor.insert_blocks(blocks, None, cx)
+        });
+    }
+}
+
+fn line_text(buffer: &BufferSnapshot, row: u32) -> String {
+    buffer
+        .text_for_range(Point::new(row, 0)..Point::new(row, buffer.line_len(row)))
+        .collect()
+}
+
+/// The row of the line reading `text` that is closest to `row`.
+fn find_line(buffer: &BufferSnapshot, row: u32, text: &str) -> Option<u32> {
+    (0..=buffer.max_point().row)
+        .filter(|candidate| line_text(buffer, *candidate) == text)
+        .min_by_key(|candidate| candidate.abs_diff(row))
+}
+
+fn render_thread(thread: ReviewThread) -> RenderBlock {
+    Arc::new(move |cx: &mut BlockContext| {
+        let header = if thread.resolved {
+            match thread.comments.len() {
+                1 => "Resolved · 1 comment".to_string(),
+                count => format!("Resolved · {count} comments"),
+            }
+        } else {
+            "Thread".to_string()
+        };
+        v_flex()
+            .pl(cx.margins.gutter.full_width())
+            .pr_2()
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        Icon::new(if thread.resolved {
+                            IconName::Check
+                        } else {
+                            IconName::Chat
+                        })
+                        .size(IconSize::XSmall)
+                        .color(Color::Muted),
+                    )
+                    .child(
+                        Label::new(header)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+            )
+            .when(!thread.resolved, |this| {
+                this.children(thread.comments.iter().enumerate().map(|(ix, comment)| {
+                    h_flex()
+                        .gap_1p5()
+                        .pl_4()
+                        .child(
+                            Label::new(comment.author.clone())
+                                .size(LabelSize::Small)
+                                .color(Color::Accent),
+                        )
+                        .child(Label::new(comment.body.clone()).size(LabelSize::Small))
+                        .when(ix >= thread.posted_count, |this| {
+                            this.child(
+                                Label::new("not posted")
+                                    .size(LabelSize::XSmall)
+                                    .color(Color::Muted),
+                            )
+                        })
+                }))
+            })
+            .into_any_element()
+    })
+}
+
+struct ReviewCommentModal {
+    review: Entity<BranchReview>,
+    path: String,
+    anchor: Anchor,
+    row: u32,
+    editor: Entity<Editor>,
+}
+
+impl ReviewCommentModal {
+    fn new(
+        review: Entity<BranchReview>,
+        path: String,
+        anchor: Anchor,
+        row: u32,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Leave a comment", window, cx);
+            editor
+        });
+        Self {
+            review,
+            path,
+            anchor,
+            row,
+            editor,
+        }
+    }
+
+    fn cancel(&mut self, _: &Cancel, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &Confirm, _window: &mut Window, cx: &mut Context<Self>) {
+        let body = self.editor.read(cx).text(cx);
+        if !body.trim().is_empty() {
+            let anchor = self.anchor;
+            self.review.update(cx, |review, cx| {
+                review.add_comment(anchor, body.trim().to_string(), cx)
+            });
+        }
+        cx.emit(DismissEvent);
+    }
+}
+
+impl EventEmitter<DismissEvent> for ReviewCommentModal {}
+impl ModalView for ReviewCommentModal {}
+impl Focusable for ReviewCommentModal {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.editor.focus_handle(cx)
+    }
+}
+
+impl Render for ReviewCommentModal {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("ReviewCommentModal")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .elevation_2(cx)
+            .w(rems(34.))
+            .child(
+                h_flex()
+                    .px_3()
+                    .pt_2()
+                    .pb_1()
+                    .w_full()
+                    .gap_1p5()
+                    .child(Icon::new(IconName::Chat).size(IconSize::XSmall))
+                    .child(
+                        Headline::new(format!("Comment on {}:{}", self.path, self.row + 1))
+                            .size(HeadlineSize::XSmall),
+                    ),
+            )
+            .child(div().px_3().pb_3().w_full().child(self.editor.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git::repository::{CommitFile, repo_path};
+    use git::review::{FakeReviewProvider, set_review_provider};
+    use gpui::{TestAppContext, VisualTestContext};
+    use project::{FakeFs, Project};
+    use serde_json::json;
+    use settings::SettingsStore;
+    use workspace::MultiWorkspace;
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            cx.set_global(db::AppDatabase::test_new());
+
+            theme_settings::init(theme::LoadThemes::JustBase, cx);
+            editor::init(cx);
+        })
+    }
+
+    #[gpui::test]
+    async fn test_branch_review_threads(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            Path::new("/project"),
+            json!({
+                ".git": {},
+                "src": { "lib.rs": "one\n2\nthree\n" },
+            }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), [Path::new("/project")], cx).await;
+        cx.run_until_parked();
+        let repository = project
+            .read_with(cx, |project, cx| project.active_repository(cx))
+            .unwrap();
+        let multi_workspace =
+            cx.add_window(|window, cx| MultiWorkspace::test_new(project, window, cx));
+        let cx = &mut VisualTestContext::from_window(*multi_workspace, cx);
+        let workspace = multi_workspace
+            .update(cx, |workspace, _, _| workspace.workspace().clone())
+            .unwrap();
+
+        let target = ReviewTarget {
+            base_ref: "main".into(),
+            head_ref: "feature".into(),
+        };
+        let open_view = |new_text: &str, cx: &mut VisualTestContext| {
+            let files = vec![CommitFile {
+                path: repo_path("src/lib.rs"),
+                old_text: Some("one\ntwo\nthree\n".into()),
+                new_text: Some(new_text.into()),
+                is_binary: false,
+            }];
+            workspace.update_in(cx, |workspace, window, cx| {
+                MultiDiffView::open_file_diffs(
+                    "Review".into(),
+                    files,
+                    repository.clone(),
+                    workspace,
+                    window,
+                    cx,
+                )
+            })
+        };
+        let attach = |view: &Entity<MultiDiffView>, cx: &mut VisualTestContext| {
+            let (target, repository, view) = (target.clone(), repository.clone(), view.clone());
+            workspace.update_in(cx, |workspace, window, cx| {
+                let workspace = workspace.weak_handle();
+                window.spawn(cx, async move |cx| {
+                    BranchReview::attach(target, &repository, &view, workspace, cx).await
+                })
+            })
+        };
+        let view = open_view("one\n2\nthree\n", cx).await.unwrap();
+        let buffer = view.read_with(cx, |view, cx| {
+            view.editor()
+                .read(cx)
+                .buffer()
+                .read(cx)
+                .all_buffers_iter()
+                .next()
+                .unwrap()
+        });
+        let line_start = |row: u32, cx: &mut VisualTestContext| {
+            buffer.read_with(cx, |buffer, _| buffer.anchor_before(Point::new(row, 0)))
+        };
+        let review = attach(&view, cx).await.unwrap();
+
+        let (second_line, third_line) = (line_start(1, cx), line_start(2, cx));
+        review.update(cx, |review, cx| {
+            review.add_comment(second_line, "Why a digit?".into(), cx);
+            review.add_comment(second_line, "Consistency.".into(), cx);
+            review.add_comment(third_line, "Looks good".into(), cx);
+        });
+        review.read_with(cx, |review, _| {
+            assert_eq!(review.threads().len(), 2);
+            assert_eq!(review.threads()[0].comments.len(), 2);
+            assert_eq!(review.blocks.len(), 2);
+        });
+
+        // Resolved threads no longer collect new comments.
+        review.update(cx, |review, cx| {
+            review.toggle_resolved(0, cx);
+            review.add_comment(second_line, "One more thing".into(), cx);
+        });
+        review.read_with(cx, |review, _| {
+            assert_eq!(review.threads().len(), 3);
+            assert!(review.threads()[0].resolved);
+        });
+
+        let provider = Arc::new(FakeReviewProvider::default());
+        cx.update(|_, cx| set_review_provider(provider.clone(), cx));
+        let posted = review
+            .update(cx, |review, cx| review.post_comments(cx))
+            .await
+            .unwrap();
+        assert_eq!(posted, 4);
+        assert_eq!(provider.posted.lock().len(), 4);
+        let posted = review
+            .update(cx, |review, cx| review.post_comments(cx))
+            .await
+            .unwrap();
+        assert_eq!(posted, 0);
+        cx.run_until_parked();
+
+        let threads = review.read_with(cx, |review, _| review.threads().to_vec());
+        assert!(
+            threads
+                .iter()
+                .all(|thread| thread.posted_count == thread.comments.len())
+        );
+        let reopened = attach(&view, cx).await.unwrap();
+        reopened.read_with(cx, |reopened, _| {
+            assert_eq!(reopened.threads(), threads.as_slice());
+        });
+
+        // Threads stay on their line when lines are inserted above it.
+        buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "zero\n")], None, cx));
+        let moved_line = line_start(3, cx);
+        review.update(cx, |review, cx| {
+            assert_eq!(review.thread_row(1, cx), Some(3));
+            review.add_comment(moved_line, "Still here".into(), cx);
+            assert_eq!(review.threads()[1].comments.len(), 2);
+        });
+
+        // Reopening the review after the branch changed finds the lines by their text.
+        cx.run_until_parked();
+        let view = open_view("zero\nzero\none\n2\nthree\n", cx).await.unwrap();
+        let reopened = attach(&view, cx).await.unwrap();
+        reopened.read_with(cx, |reopened, cx| {
+            let rows = reopened
+                .threads()
+                .iter()
+                .map(|thread| reopened.thread_row(thread.id, cx))
+                .collect::<Vec<_>>();
+            assert_eq!(rows, [Some(3), Some(4), Some(3)]);
+        });
+    }
+}
@@ -453,6 +453,7 @@ impl CommitModal {
                                     CommitOptions {
                                         amend: is_amend_pending,
                                         signoff: is_signoff_enabled,
+                                        ..Default::default()
                                     },
                                     window,
                                     cx,
@@ -3,10 +3,11 @@ use editor::hover_markdown_style;
 use futures::Future;
 use git::blame::BlameEntry;
 use git::repository::CommitSummary;
+use git::signing::CommitSignature;
 use git::{GitRemote, commit::ParsedCommitMessage};
 use gpui::{
-    AbsoluteLength, App, Asset, Element, Entity, MouseButton, ParentElement, Render, ScrollHandle,
-    StatefulInteractiveElement, WeakEntity, prelude::*,
+    AbsoluteLength, App, Asset, Element, ElementId, Entity, MouseButton, ParentElement, Render,
+    ScrollHandle, StatefulInteractiveElement, Task, WeakEntity, prelude::*,
 };
 use markdown::{Markdown, MarkdownElement};
 use project::git_store::Repository;
@@ -14,7 +15,7 @@ use settings::Settings;
 use std::hash::Hash;
 use theme_settings::ThemeSettings;
 use time::{OffsetDateTime, UtcOffset};
-use ui::{Avatar, CopyButton, Divider, prelude::*, tooltip_container};
+use ui::{Avatar, CopyButton, Divider, Tooltip, prelude::*, tooltip_container};
 use workspace::Workspace;
 
 #[derive(Clone, Debug)]
@@ -24,6 +25,34 @@ pub struct CommitDetails {
     pub author_email: SharedString,
     pub commit_time: OffsetDateTime,
     pub message: Option<ParsedCommitMessage>,
+    pub signature: Option<CommitSignature>,
+}
+
+/// Renders whether a commit's signature could be verified, with the signer in a tooltip.
+pub fn render_signature_badge(
+    id: impl Into<ElementId>,
+    signature: &CommitSignature,
+) -> impl IntoElement {
+    let (icon, color) = if signature.status.is_verified() {
+        (IconName::Check, Color::Success)
+    } else {
+        (IconName::Warning, Color::Warning)
+    };
+    let signer = if signature.signer.is_empty() {
+        signature.key.clone()
+    } else {
+        format!("{} ({})", signature.signer, signature.key).into()
+    };
+    h_flex()
+        .id(id)
+        .gap_0p5()
+        .child(Icon::new(icon).size(IconSize::XSmall).color(color))
+        .child(
+            Label::new(signature.status.label())
+                .size(LabelSize::Small)
+                .color(color),
+        )
+        .tooltip(move |_, cx| Tooltip::with_meta("Signed by", None, signer.clone(), cx))
 }
 
 pub struct CommitAvatar<'a> {
@@ -157,6 +186,7 @@ pub struct CommitTooltip {
     markdown: Entity<Markdown>,
     repository: Entity<Repository>,
     workspace: WeakEntity<Workspace>,
+    _load_signature: Option<Task<()>>,
 }
 
 impl CommitTooltip {
@@ -183,11 +213,32 @@ impl CommitTooltip {
                     .into(),
                 author_email: blame.author_mail.clone().unwrap_or("".to_string()).into(),
                 message: details,
+                signature: None,
             },
             repository,
             workspace,
             cx,
         )
+        .load_signature(cx)
+    }
+
+    /// Signatures are verified separately, since that is only worth doing once
+    /// the tooltip is shown.
+    pub(crate) fn load_signature(mut self, cx: &mut Context<Self>) -> Self {
+        let signature = self.repository.update(cx, |repository, _| {
+            repository.commit_signature(self.commit.sha.to_string())
+        });
+        self._load_signature = Some(cx.spawn(async move |this, cx| {
+            let Ok(Ok(signature)) = signature.await else {
+                return;
+            };
+            this.update(cx, |this, cx| {
+                this.commit.signature = signature;
+                cx.notify();
+            })
+            .ok();
+        }));
+        self
     }
 
     pub fn new(
@@ -214,6 +265,7 @@ impl CommitTooltip {
             workspace,
             scroll_handle: ScrollHandle::new(),
             markdown,
+            _load_signature: None,
         }
     }
 }
@@ -225,6 +277,7 @@ impl Render for CommitTooltip {
         let author = self.commit.author_name.clone();
 
         let author_email = self.commit.author_email.clone();
+        let signature = self.commit.signature.clone();
 
         let short_commit_id = self
             .commit
@@ -325,7 +378,15 @@ impl Render for CommitTooltip {
                                 .pt_1p5()
                                 .border_t_1()
                                 .border_color(cx.theme().colors().border_variant)
-                                .child(absolute_timestamp)
+                                .child(h_flex().gap_1p5().child(absolute_timestamp).when_some(
+                                    signature,
+                                    |this, signature| {
+                                        this.child(render_signature_badge(
+                                            "commit-signature",
+                                            &signature,
+                                        ))
+                                    },
+                                ))
                                 .child(
                                     h_flex()
                                         .gap_1p5()
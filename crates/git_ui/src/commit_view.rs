@@ -4,6 +4,7 @@ use collections::HashMap;
 use editor::display_map::{BlockPlacement, BlockProperties, BlockStyle};
 use editor::{Addon, Editor, EditorEvent, ExcerptRange, MultiBuffer, multibuffer_context_lines};
 use git::repository::{CommitDetails, CommitDiff, RepoPath, is_binary_content};
+use git::signing::CommitSignature;
 use git::status::{FileStatus, StatusCode, TrackedStatus};
 use git::{
     BuildCommitPermalinkParams, GitHostingProviderRegistry, GitRemote, ParsedGitRemote,
@@ -39,7 +40,7 @@ use workspace::{
     searchable::SearchableItemHandle,
 };
 
-use crate::commit_tooltip::CommitAvatar;
+use crate::commit_tooltip::{CommitAvatar, render_signature_badge};
 use crate::git_panel::GitPanel;
 
 actions!(git, [ApplyCurrentStash, PopCurrentStash, DropCurrentStash,]);
@@ -61,6 +62,7 @@ pub fn init(cx: &mut App) {
 
 pub struct CommitView {
     commit: CommitDetails,
+    signature: Option<CommitSignature>,
     editor: Entity<Editor>,
     stash: Option<usize>,
     multibuffer: Entity<MultiBuffer>,
@@ -68,12 +70,12 @@ pub struct CommitView {
     remote: Option<GitRemote>,
 }
 
-struct GitBlob {
-    path: RepoPath,
-    worktree_id: WorktreeId,
-    is_deleted: bool,
-    is_binary: bool,
-    display_name: String,
+pub(crate) struct GitBlob {
+    pub(crate) path: RepoPath,
+    pub(crate) worktree_id: WorktreeId,
+    pub(crate) is_deleted: bool,
+    pub(crate) is_binary: bool,
+    pub(crate) display_name: String,
 }
 
 struct CommitDiffAddon {
@@ -389,8 +391,23 @@ impl CommitView {
             })
         });
 
+        let signature = repository.update(cx, |repository, _| {
+            repository.commit_signature(commit.sha.to_string())
+        });
+        cx.spawn(async move |this, cx| {
+            if let Ok(Ok(signature)) = signature.await {
+                this.update(cx, |this, cx| {
+                    this.signature = signature;
+                    cx.notify();
+                })
+                .ok();
+            }
+        })
+        .detach();
+
         Self {
             commit,
+            signature: None,
             editor,
             multibuffer,
             stash,
@@ -488,7 +505,16 @@ impl CommitView {
                                     Label::new(author_email)
                                         .color(Color::Muted)
                                         .size(LabelSize::Small),
-                                ),
+                                )
+                                .when_some(self.signature.as_ref(), |this, signature| {
+                                    this.child(
+                                        Label::new("•")
+                                            .size(LabelSize::Small)
+                                            .color(Color::Muted)
+                                            .alpha(0.5),
+                                    )
+                                    .child(render_signature_badge("commit-signature", signature))
+                                }),
                         ),
                     ),
             )
@@ -720,7 +746,7 @@ impl language::File for GitBlob {
     }
 }
 
-async fn build_buffer(
+pub(crate) async fn build_buffer(
     mut text: String,
     blob: Arc<dyn File>,
     language_registry: &Arc<language::LanguageRegistry>,
@@ -753,7 +779,7 @@ async fn build_buffer(
     Ok(buffer)
 }
 
-async fn build_buffer_diff(
+pub(crate) async fn build_buffer_diff(
     mut old_text: Option<String>,
     buffer: &Entity<Buffer>,
     language_registry: &Arc<LanguageRegistry>,
@@ -1,16 +1,19 @@
 use anyhow::Result;
 
+use editor::Editor;
 use git::repository::{FileHistory, FileHistoryEntry, RepoPath};
 use git::{GitHostingProviderRegistry, GitRemote, parse_git_remote_url};
 use gpui::{
     AnyElement, AnyEntity, App, Context, Entity, EventEmitter, FocusHandle, Focusable, IntoElement,
     Render, ScrollStrategy, Task, UniformListScrollHandle, WeakEntity, Window, uniform_list,
 };
+use language::Point;
 use project::{
     Project, ProjectPath,
     git_store::{GitStore, Repository},
 };
 use std::any::{Any, TypeId};
+use std::ops::Range;
 use std::sync::Arc;
 
 use time::OffsetDateTime;
@@ -28,6 +31,8 @@ const PAGE_SIZE: usize = 50;
 
 pub struct FileHistoryView {
     history: FileHistory,
+    /// Set when showing the history of a range of lines rather than the whole file.
+    traced_lines: Option<TracedLines>,
     repository: WeakEntity<Repository>,
     git_store: WeakEntity<GitStore>,
     workspace: WeakEntity<Workspace>,
@@ -39,6 +44,13 @@ pub struct FileHistoryView {
     has_more: bool,
 }
 
+struct TracedLines {
+    rows: Range<u32>,
+    /// The path of the file and the hunks that changed the traced lines, for each
+    /// entry of the history.
+    changes: Vec<(RepoPath, SharedString)>,
+}
+
 impl FileHistoryView {
     pub fn open(
         path: RepoPath,
@@ -51,16 +63,69 @@ impl FileHistoryView {
         let file_history_task = git_store
             .update(cx, |git_store, cx| {
                 repo.upgrade().map(|repo| {
-                    git_store.file_history_paginated(&repo, path.clone(), 0, Some(PAGE_SIZE), cx)
+                    let task =
+                        git_store.file_history_paginated(&repo, path, 0, Some(PAGE_SIZE), cx);
+                    cx.spawn(async move |_, _| anyhow::Ok((task.await?, None)))
                 })
             })
             .ok()
             .flatten();
+        Self::open_history(file_history_task, git_store, repo, workspace, window, cx);
+    }
 
+    /// Opens the history of the given rows of a file, which are zero-based,
+    /// end-exclusive and relative to the file's contents at `HEAD`.
+    pub fn open_line_range(
+        path: RepoPath,
+        rows: Range<u32>,
+        git_store: WeakEntity<GitStore>,
+        repo: WeakEntity<Repository>,
+        workspace: WeakEntity<Workspace>,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        let line_history_task = git_store
+            .update(cx, |git_store, cx| {
+                repo.upgrade().map(|repo| {
+                    let task = git_store.line_history(&repo, path, rows, cx);
+                    cx.spawn(async move |_, _| {
+                        let line_history = task.await?;
+                        let (entries, changes) = line_history
+                            .entries
+                            .into_iter()
+                            .map(|entry| (entry.commit, (entry.path, entry.diff)))
+                            .unzip();
+                        let history = FileHistory {
+                            entries,
+                            path: line_history.path,
+                        };
+                        let traced_lines = TracedLines {
+                            rows: line_history.rows,
+                            changes,
+                        };
+                        anyhow::Ok((history, Some(traced_lines)))
+                    })
+                })
+            })
+            .ok()
+            .flatten();
+        Self::open_history(line_history_task, git_store, repo, workspace, window, cx);
+    }
+
+    fn open_history(
+        history_task: Option<Task<Result<(FileHistory, Option<TracedLines>)>>>,
+        git_store: WeakEntity<GitStore>,
+        repo: WeakEntity<Repository>,
+        workspace: WeakEntity<Workspace>,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
         window
             .spawn(cx, async move |cx| {
-                let file_history = file_history_task?.await.log_err()?;
+                let (file_history, traced_lines) = history_task?.await.log_err()?;
                 let repo = repo.upgrade()?;
+                let path = file_history.path.clone();
+                let rows = traced_lines.as_ref().map(|traced| traced.rows.clone());
 
                 workspace
                     .update_in(cx, |workspace, window, cx| {
@@ -68,6 +133,7 @@ impl FileHistoryView {
                         let view = cx.new(|cx| {
                             FileHistoryView::new(
                                 file_history,
+                                traced_lines,
                                 git_store.clone(),
                                 repo.clone(),
                                 workspace.weak_handle(),
@@ -81,7 +147,12 @@ impl FileHistoryView {
                         pane.update(cx, |pane, cx| {
                             let ix = pane.items().position(|item| {
                                 let view = item.downcast::<FileHistoryView>();
-                                view.is_some_and(|v| v.read(cx).history.path == path)
+                                view.is_some_and(|view| {
+                                    let view = view.read(cx);
+                                    view.history.path == path
+                                        && view.traced_lines.as_ref().map(|t| &t.rows)
+                                            == rows.as_ref()
+                                })
                             });
                             if let Some(ix) = ix {
                                 pane.activate_item(ix, true, true, window, cx);
@@ -97,6 +168,7 @@ impl FileHistoryView {
 
     fn new(
         history: FileHistory,
+        traced_lines: Option<TracedLines>,
         git_store: WeakEntity<GitStore>,
         repository: Entity<Repository>,
         workspace: WeakEntity<Workspace>,
@@ -106,7 +178,13 @@ impl FileHistoryView {
     ) -> Self {
         let focus_handle = cx.focus_handle();
         let scroll_handle = UniformListScrollHandle::new();
-        let has_more = history.entries.len() >= PAGE_SIZE;
+        // Line histories are loaded in full, as `git log -L` can't skip commits
+        // without tracing the range through them.
+        let has_more = traced_lines.is_none() && history.entries.len() >= PAGE_SIZE;
+        let selected_entry = traced_lines
+            .as_ref()
+            .filter(|_| !history.entries.is_empty())
+            .map(|_| 0);
 
         let snapshot = repository.read(cx).snapshot();
         let remote_url = snapshot
@@ -125,11 +203,12 @@ impl FileHistoryView {
 
         Self {
             history,
+            traced_lines,
             git_store,
             repository: repository.downgrade(),
             workspace,
             remote,
-            selected_entry: None,
+            selected_entry,
             scroll_handle,
             focus_handle,
             loading_more: false,
@@ -249,12 +328,19 @@ impl FileHistoryView {
     }
 
     fn open_commit_view(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let Some(entry) = self
-            .selected_entry
-            .and_then(|ix| self.history.entries.get(ix))
-        else {
+        let Some(ix) = self.selected_entry else {
+            return;
+        };
+        let Some(entry) = self.history.entries.get(ix) else {
             return;
         };
+        // When tracing lines across renames, show the file under the name it had
+        // in that commit.
+        let path = self
+            .traced_lines
+            .as_ref()
+            .and_then(|traced| traced.changes.get(ix))
+            .map_or_else(|| self.history.path.clone(), |(path, _)| path.clone());
 
         if let Some(repo) = self.repository.upgrade() {
             let sha_str = entry.sha.to_string();
@@ -263,13 +349,69 @@ impl FileHistoryView {
                 repo.downgrade(),
                 self.workspace.clone(),
                 None,
-                Some(self.history.path.clone()),
+                Some(path),
                 window,
                 cx,
             );
         }
     }
 
+    fn title(&self) -> String {
+        let path = self.history.path.as_unix_str();
+        match &self.traced_lines {
+            Some(traced) => format!("{path}:{}", format_rows(&traced.rows)),
+            None => path.to_string(),
+        }
+    }
+
+    fn render_selected_hunks(&self, cx: &App) -> Option<AnyElement> {
+        let ix = self.selected_entry?;
+        let (path, diff) = self.traced_lines.as_ref()?.changes.get(ix)?;
+        let colors = cx.theme().colors();
+
+        let lines = diff
+            .lines()
+            .skip_while(|line| !line.starts_with("@@"))
+            .map(|line| {
+                let color = if line.starts_with("@@") {
+                    Color::Accent
+                } else if line.starts_with('+') {
+                    Color::Created
+                } else if line.starts_with('-') {
+                    Color::Deleted
+                } else {
+                    Color::Default
+                };
+                Label::new(line.to_string())
+                    .size(LabelSize::Small)
+                    .color(color)
+                    .buffer_font(cx)
+            });
+
+        Some(
+            v_flex()
+                .max_h_1_2()
+                .border_t_1()
+                .border_color(colors.border_variant)
+                .child(
+                    h_flex().px_3().py_1().child(
+                        Label::new(path.as_unix_str().to_string())
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+                )
+                .child(
+                    v_flex()
+                        .id("line-history-hunks")
+                        .px_3()
+                        .pb_2()
+                        .overflow_y_scroll()
+                        .children(lines),
+                )
+                .into_any_element(),
+        )
+    }
+
     fn render_commit_avatar(
         &self,
         sha: &SharedString,
@@ -410,11 +552,7 @@ impl Render for FileHistoryView {
                     .justify_between()
                     .border_b_1()
                     .border_color(cx.theme().colors().border_variant)
-                    .child(
-                        Label::new(self.history.path.as_unix_str().to_string())
-                            .color(Color::Muted)
-                            .buffer_font(cx),
-                    )
+                    .child(Label::new(self.title()).color(Color::Muted).buffer_font(cx))
                     .child(
                         h_flex()
                             .gap_1p5()
@@ -473,6 +611,7 @@ impl Render for FileHistoryView {
                     })
                     .vertical_scrollbar_for(&self.scroll_handle, window, cx),
             )
+            .children(self.render_selected_hunks(cx))
     }
 }
 
@@ -490,11 +629,14 @@ impl Item for FileHistoryView {
             .file_name()
             .map(|name| name.to_string())
             .unwrap_or_else(|| "File".to_string());
-        format!("History: {}", file_name).into()
+        match &self.traced_lines {
+            Some(traced) => format!("History: {file_name}:{}", format_rows(&traced.rows)).into(),
+            None => format!("History: {}", file_name).into(),
+        }
     }
 
     fn tab_tooltip_text(&self, _cx: &App) -> Option<SharedString> {
-        Some(format!("Git history for {}", self.history.path.as_unix_str()).into())
+        Some(format!("Git history for {}", self.title()).into())
     }
 
     fn tab_icon(&self, _window: &Window, _cx: &App) -> Option<Icon> {
@@ -611,3 +753,40 @@ impl Item for FileHistoryView {
         }
     }
 }
+
+/// Formats zero-based, end-exclusive rows as one-based line numbers.
+fn format_rows(rows: &Range<u32>) -> String {
+    if rows.end <= rows.start + 1 {
+        format!("{}", rows.start + 1)
+    } else {
+        format!("{}-{}", rows.start + 1, rows.end)
+    }
+}
+
+/// Returns the rows covered by the editor's newest selection, mapped through the
+/// buffer's diff so that they refer to the file's contents at `HEAD`.
+pub(crate) fn selected_committed_rows(editor: &Entity<Editor>, cx: &mut App) -> Option<Range<u32>> {
+    let display_snapshot = editor.update(cx, |editor, cx| editor.display_snapshot(cx));
+    let editor = editor.read(cx);
+    let selection = editor.selections.newest::<Point>(&display_snapshot);
+    let multi_buffer = editor.buffer().read(cx);
+    let buffer_snapshot = multi_buffer.as_singleton()?.read(cx).snapshot();
+
+    let (start, end) = match multi_buffer.diff_for(buffer_snapshot.remote_id()) {
+        Some(diff) => {
+            let diff = diff.read(cx).snapshot(cx);
+            (
+                diff.buffer_point_to_base_text_point(selection.start, &buffer_snapshot),
+                diff.buffer_point_to_base_text_point(selection.end, &buffer_snapshot),
+            )
+        }
+        None => (selection.start, selection.end),
+    };
+    // A selection ending at the start of a line doesn't include that line.
+    let end_row = if end.column == 0 && end.row > start.row {
+        end.row
+    } else {
+        end.row + 1
+    };
+    Some(start.row..end_row)
+}
@@ -30,10 +30,12 @@ use git::repository::{
 };
 use git::stash::GitStash;
 use git::status::{DiffStat, StageStatus};
+use git::submodule::{Submodule, SubmoduleOperation};
 use git::{Amend, Signoff, ToggleStaged, repository::RepoPath, status::FileStatus};
 use git::{
-    ExpandCommitEditor, GitHostingProviderRegistry, RestoreTrackedFiles, StageAll, StashAll,
-    StashApply, StashPop, TrashUntrackedFiles, UnstageAll,
+    ExpandCommitEditor, GitHostingProviderRegistry, InitSubmodules, RestoreTrackedFiles, StageAll,
+    StashAll, StashApply, StashPop, SyncSubmodules, TrashUntrackedFiles, UnstageAll,
+    UpdateSubmodules,
 };
 use gpui::{
     Action, AsyncApp, AsyncWindowContext, Bounds, ClickEvent, Corner, DismissEvent, Empty, Entity,
@@ -160,6 +162,7 @@ struct GitMenuState {
     has_new_changes: bool,
     sort_by_path: bool,
     has_stash_items: bool,
+    has_submodules: bool,
     tree_view: bool,
 }
 
@@ -190,6 +193,12 @@ fn git_panel_context_menu(
             )
             .action_disabled_when(!state.has_stash_items, "Stash Pop", StashPop.boxed_clone())
             .action("View Stash", zed_actions::git::ViewStash.boxed_clone())
+            .when(state.has_submodules, |this| {
+                this.separator()
+                    .action("Initialize Submodules", InitSubmodules.boxed_clone())
+                    .action("Update Submodules", UpdateSubmodules.boxed_clone())
+                    .action("Sync Submodules", SyncSubmodules.boxed_clone())
+            })
             .separator()
             .action("Open Diff", project_diff::Diff.boxed_clone())
             .separator()
@@ -653,6 +662,8 @@ pub struct GitPanel {
     local_committer_task: Option<Task<()>>,
     bulk_staging: Option<BulkStaging>,
     stash_entries: GitStash,
+    submodules: HashMap<RepoPath, Submodule>,
+    refresh_submodules_task: Task<()>,
 
     _settings_subscription: Subscription,
 }
@@ -840,6 +851,8 @@ impl GitPanel {
                 entry_count: 0,
                 bulk_staging: None,
                 stash_entries: Default::default(),
+                submodules: HashMap::default(),
+                refresh_submodules_task: Task::ready(()),
                 _settings_subscription,
             };
 
@@ -2155,6 +2168,7 @@ impl GitPanel {
                 CommitOptions {
                     amend: false,
                     signoff: self.signoff_enabled,
+                    ..Default::default()
                 },
                 window,
                 cx,
@@ -2195,6 +2209,7 @@ impl GitPanel {
                         CommitOptions {
                             amend: true,
                             signoff: self.signoff_enabled,
+                            ..Default::default()
                         },
                         window,
                         cx,
@@ -2293,13 +2308,20 @@ impl GitPanel {
 
     pub(crate) fn commit_changes(
         &mut self,
-        options: CommitOptions,
+        mut options: CommitOptions,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
         let Some(active_repository) = self.active_repository.clone() else {
             return;
         };
+        if options.signing_key.is_none() {
+            options.signing_key = GitPanelSettings::get_global(cx)
+                .signing_key
+                .clone()
+                .map(SharedString::from);
+        }
+        let amend = options.amend;
         let error_spawn = |message, window: &mut Window, cx: &mut App| {
             let prompt = window.prompt(PromptLevel::Warning, message, None, &["Ok"], cx);
             cx.spawn(async move |_| {
@@ -2347,7 +2369,7 @@ impl GitPanel {
                 .map(|status_entry| status_entry.repo_path.clone())
                 .collect::<Vec<_>>();
 
-            if changed_files.is_empty() && !options.amend {
+            if changed_files.is_empty() && !amend {
                 error_spawn("No changes to commit", window, cx);
                 return;
             }
@@ -2369,7 +2391,7 @@ impl GitPanel {
 
                 match result {
                     Ok(()) => {
-                        if options.amend {
+                        if amend {
                             this.set_amend_pending(false, cx);
                         } else {
                             this.commit_editor
@@ -3454,12 +3476,98 @@ impl GitPanel {
                 git_panel
                     .update_in(cx, |git_panel, window, cx| {
                         git_panel.update_visible_entries(window, cx);
+                        git_panel.refresh_submodules(cx);
                     })
                     .ok();
             }
         });
     }
 
+    fn refresh_submodules(&mut self, cx: &mut Context<Self>) {
+        let Some(repo) = self.active_repository.clone() else {
+            self.submodules.clear();
+            return;
+        };
+        let submodules = repo.update(cx, |repo, _| repo.submodules());
+        self.refresh_submodules_task = cx.spawn(async move |this, cx| {
+            let Some(submodules) = submodules
+                .await
+                .log_err()
+                .and_then(|result| result.log_err())
+            else {
+                return;
+            };
+            this.update(cx, |this, cx| {
+                this.submodules = submodules
+                    .into_iter()
+                    .map(|submodule| (submodule.path.clone(), submodule))
+                    .collect();
+                cx.notify();
+            })
+            .ok();
+        });
+    }
+
+    fn selected_submodule(&self) -> Option<&Submodule> {
+        let entry = self.entries.get(self.selected_entry?)?.status_entry()?;
+        self.submodules.get(&entry.repo_path)
+    }
+
+    /// Runs a submodule operation for the selected submodule, or for all of them when
+    /// the selection isn't a submodule.
+    pub(crate) fn submodule_operation(
+        &mut self,
+        operation: SubmoduleOperation,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if operation.is_remote() && !self.can_push_and_pull(cx) {
+            return;
+        }
+        let Some(repo) = self.active_repository.clone() else {
+            return;
+        };
+        let paths = self
+            .selected_submodule()
+            .map(|submodule| vec![submodule.path.clone()])
+            .unwrap_or_default();
+        let askpass =
+            self.askpass_delegate(format!("git submodule {}", operation.name()), window, cx);
+
+        cx.spawn(async move |this, cx| {
+            let result = repo
+                .update(cx, |repo, cx| {
+                    repo.submodule_operation(operation, paths, askpass, cx)
+                })
+                .await?;
+            this.update(cx, |this, cx| match result {
+                Ok(_) => this.refresh_submodules(cx),
+                Err(e) => {
+                    log::error!("Error while running git submodule: {:?}", e);
+                    this.show_error_toast(format!("submodule {}", operation.name()), e, cx)
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn init_submodules(&mut self, _: &InitSubmodules, window: &mut Window, cx: &mut Context<Self>) {
+        self.submodule_operation(SubmoduleOperation::Init, window, cx);
+    }
+
+    fn update_submodules(
+        &mut self,
+        _: &UpdateSubmodules,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.submodule_operation(SubmoduleOperation::Update, window, cx);
+    }
+
+    fn sync_submodules(&mut self, _: &SyncSubmodules, window: &mut Window, cx: &mut Context<Self>) {
+        self.submodule_operation(SubmoduleOperation::Sync, window, cx);
+    }
+
     fn reopen_commit_buffer(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         let Some(active_repo) = self.active_repository.as_ref() else {
             return;
@@ -3957,6 +4065,7 @@ impl GitPanel {
         let has_unstaged_changes = self.has_unstaged_changes();
         let has_new_changes = self.new_count > 0;
         let has_stash_items = self.stash_entries.entries.len() > 0;
+        let has_submodules = !self.submodules.is_empty();
 
         PopoverMenu::new(id.into())
             .trigger(
@@ -3974,6 +4083,7 @@ impl GitPanel {
                         has_new_changes,
                         sort_by_path: GitPanelSettings::get_global(cx).sort_by_path,
                         has_stash_items,
+                        has_submodules,
                         tree_view: GitPanelSettings::get_global(cx).tree_view,
                     },
                     window,
@@ -4454,7 +4564,11 @@ impl GitPanel {
                         git_panel
                             .update(cx, |git_panel, cx| {
                                 git_panel.commit_changes(
-                                    CommitOptions { amend, signoff },
+                                    CommitOptions {
+                                        amend,
+                                        signoff,
+                                        ..Default::default()
+                                    },
                                     window,
                                     cx,
                                 );
@@ -4963,6 +5077,7 @@ impl GitPanel {
         } else {
             "Discard Changes"
         };
+        let is_submodule = self.submodules.contains_key(&entry.repo_path);
         let context_menu = ContextMenu::build(window, cx, |context_menu, _, _| {
             let is_created = entry.status.is_created();
             context_menu
@@ -4979,6 +5094,11 @@ impl GitPanel {
                 .action("Open File", menu::SecondaryConfirm.boxed_clone())
                 .separator()
                 .action_disabled_when(is_created, "View File History", Box::new(git::FileHistory))
+                .when(is_submodule, |this| {
+                    this.separator()
+                        .action("Update Submodule", UpdateSubmodules.boxed_clone())
+                        .action("Sync Submodule", SyncSubmodules.boxed_clone())
+                })
         });
         self.selected_entry = Some(ix);
         self.set_context_menu(context_menu, position, window, cx);
@@ -4999,6 +5119,7 @@ impl GitPanel {
                 has_new_changes: self.new_count > 0,
                 sort_by_path: GitPanelSettings::get_global(cx).sort_by_path,
                 has_stash_items: self.stash_entries.entries.len() > 0,
+                has_submodules: !self.submodules.is_empty(),
                 tree_view: GitPanelSettings::get_global(cx).tree_view,
             },
             window,
@@ -5170,6 +5291,11 @@ impl GitPanel {
                 }
             });
 
+        let submodule_status = self
+            .submodules
+            .get(&entry.repo_path)
+            .map(|submodule| submodule.status);
+
         let id_for_diff_stat = id.clone();
 
         h_flex()
@@ -5188,6 +5314,15 @@ impl GitPanel {
             .hover(|s| s.bg(hover_bg))
             .active(|s| s.bg(active_bg))
             .child(name_row)
+            .when_some(submodule_status, |el, status| {
+                el.child(Label::new(status.label()).size(LabelSize::Small).color(
+                    if status.conflicted {
+                        Color::VersionControlConflict
+                    } else {
+                        Color::Muted
+                    },
+                ))
+            })
             .when(GitPanelSettings::get_global(cx).diff_stats, |el| {
                 el.when_some(entry.diff_stat, move |this, stat| {
                     let id = format!("diff-stat-{}", id_for_diff_stat);
@@ -5680,6 +5815,9 @@ impl Render for GitPanel {
             .on_action(cx.listener(Self::open_diff))
             .on_action(cx.listener(Self::open_file))
             .on_action(cx.listener(Self::file_history))
+            .on_action(cx.listener(Self::init_submodules))
+            .on_action(cx.listener(Self::update_submodules))
+            .on_action(cx.listener(Self::sync_submodules))
             .on_action(cx.listener(Self::focus_changes_list))
             .on_action(cx.listener(Self::focus_editor))
             .on_action(cx.listener(Self::expand_commit_editor))
@@ -5908,11 +6046,13 @@ impl GitPanelMessageTooltip {
                         remote_url.as_deref(),
                         provider_registry,
                     )),
+                    signature: None,
                 };
 
                 this.update(cx, |this: &mut GitPanelMessageTooltip, cx| {
                     this.commit_tooltip = Some(cx.new(move |cx| {
                         CommitTooltip::new(commit_details, repository, workspace, cx)
+                            .load_signature(cx)
                     }));
                     cx.notify();
                 })
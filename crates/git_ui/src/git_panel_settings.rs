@@ -30,6 +30,7 @@ pub struct GitPanelSettings {
     pub diff_stats: bool,
     pub show_count_badge: bool,
     pub starts_open: bool,
+    pub signing_key: Option<String>,
 }
 
 #[derive(Default)]
@@ -76,6 +77,7 @@ impl Settings for GitPanelSettings {
             diff_stats: git_panel.diff_stats.unwrap(),
             show_count_badge: git_panel.show_count_badge.unwrap(),
             starts_open: git_panel.starts_open.unwrap(),
+            signing_key: git_panel.signing_key,
         }
     }
 }
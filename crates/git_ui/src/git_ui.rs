@@ -12,8 +12,9 @@ mod blame_ui;
 pub mod clone;
 
 use git::{
-    repository::{Branch, Upstream, UpstreamTracking, UpstreamTrackingStatus},
+    repository::{Branch, RepoPath, Upstream, UpstreamTracking, UpstreamTrackingStatus},
     status::{FileStatus, StatusCode, UnmergedStatus, UnmergedStatusCode},
+    submodule::SubmoduleOperation,
 };
 use gpui::{
     App, Context, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, SharedString, Window,
@@ -29,6 +30,7 @@ use crate::{git_panel::GitPanel, text_diff_view::TextDiffView};
 
 mod askpass_modal;
 pub mod branch_picker;
+pub mod branch_review;
 mod commit_modal;
 pub mod commit_tooltip;
 pub mod commit_view;
@@ -41,6 +43,7 @@ pub mod git_picker;
 pub mod multi_diff_view;
 pub mod picker_prompt;
 pub mod project_diff;
+pub mod reflog_picker;
 pub(crate) mod remote_output;
 pub mod repository_selector;
 pub mod stash_picker;
@@ -62,6 +65,8 @@ pub fn init(cx: &mut App) {
         git_panel::register(workspace);
         repository_selector::register(workspace);
         git_picker::register(workspace);
+        reflog_picker::register(workspace);
+        branch_review::register(workspace);
         conflict_view::register_conflict_notification(workspace, cx);
 
         let project = workspace.project().read(cx);
@@ -94,6 +99,30 @@ pub fn init(cx: &mut App) {
                     panel.fetch(false, window, cx);
                 });
             });
+            workspace.register_action(|workspace, _: &git::InitSubmodules, window, cx| {
+                let Some(panel) = workspace.panel::<git_panel::GitPanel>(cx) else {
+                    return;
+                };
+                panel.update(cx, |panel, cx| {
+                    panel.submodule_operation(SubmoduleOperation::Init, window, cx);
+                });
+            });
+            workspace.register_action(|workspace, _: &git::UpdateSubmodules, window, cx| {
+                let Some(panel) = workspace.panel::<git_panel::GitPanel>(cx) else {
+                    return;
+                };
+                panel.update(cx, |panel, cx| {
+                    panel.submodule_operation(SubmoduleOperation::Update, window, cx);
+                });
+            });
+            workspace.register_action(|workspace, _: &git::SyncSubmodules, window, cx| {
+                let Some(panel) = workspace.panel::<git_panel::GitPanel>(cx) else {
+                    return;
+                };
+                panel.update(cx, |panel, cx| {
+                    panel.submodule_operation(SubmoduleOperation::Sync, window, cx);
+                });
+            });
             workspace.register_action(|workspace, _: &git::Push, window, cx| {
                 let Some(panel) = workspace.panel::<git_panel::GitPanel>(cx) else {
                     return;
@@ -214,33 +243,30 @@ pub fn init(cx: &mut App) {
             },
         );
         workspace.register_action(|workspace, _: &git::FileHistory, window, cx| {
-            let Some(active_item) = workspace.active_item(cx) else {
+            let Some((_, repo, repo_path)) = active_file_repository(workspace, cx) else {
                 return;
             };
-            let Some(editor) = active_item.downcast::<Editor>() else {
-                return;
-            };
-            let Some(buffer) = editor.read(cx).buffer().read(cx).as_singleton() else {
-                return;
-            };
-            let Some(file) = buffer.read(cx).file() else {
+            let git_store = workspace.project().read(cx).git_store().clone();
+            file_history_view::FileHistoryView::open(
+                repo_path,
+                git_store.downgrade(),
+                repo.downgrade(),
+                workspace.weak_handle(),
+                window,
+                cx,
+            );
+        });
+        workspace.register_action(|workspace, _: &git::LineHistory, window, cx| {
+            let Some((editor, repo, repo_path)) = active_file_repository(workspace, cx) else {
                 return;
             };
-            let worktree_id = file.worktree_id(cx);
-            let project_path = ProjectPath {
-                worktree_id,
-                path: file.path().clone(),
-            };
-            let project = workspace.project();
-            let git_store = project.read(cx).git_store();
-            let Some((repo, repo_path)) = git_store
-                .read(cx)
-                .repository_and_path_for_project_path(&project_path, cx)
-            else {
+            let Some(rows) = file_history_view::selected_committed_rows(&editor, cx) else {
                 return;
             };
-            file_history_view::FileHistoryView::open(
+            let git_store = workspace.project().read(cx).git_store().clone();
+            file_history_view::FileHistoryView::open_line_range(
                 repo_path,
+                rows,
                 git_store.downgrade(),
                 repo.downgrade(),
                 workspace.weak_handle(),
@@ -252,6 +278,27 @@ pub fn init(cx: &mut App) {
     .detach();
 }
 
+/// Returns the active editor along with the repository and path of the file it edits.
+fn active_file_repository(
+    workspace: &Workspace,
+    cx: &App,
+) -> Option<(Entity<Editor>, Entity<Repository>, RepoPath)> {
+    let editor = workspace.active_item(cx)?.downcast::<Editor>()?;
+    let buffer = editor.read(cx).buffer().read(cx).as_singleton()?;
+    let file = buffer.read(cx).file()?;
+    let project_path = ProjectPath {
+        worktree_id: file.worktree_id(cx),
+        path: file.path().clone(),
+    };
+    let (repo, repo_path) = workspace
+        .project()
+        .read(cx)
+        .git_store()
+        .read(cx)
+        .repository_and_path_for_project_path(&project_path, cx)?;
+    Some((editor, repo, repo_path))
+}
+
 fn open_modified_files(
     workspace: &mut Workspace,
     window: &mut Window,
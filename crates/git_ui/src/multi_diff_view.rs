@@ -1,13 +1,15 @@
-use anyhow::Result;
+use anyhow::{Context as _, Result};
 use buffer_diff::BufferDiff;
 use editor::{Editor, EditorEvent, MultiBuffer, multibuffer_context_lines};
+use git::repository::{CommitFile, is_binary_content};
 use gpui::{
     AnyElement, App, AppContext as _, AsyncApp, Context, Entity, EventEmitter, FocusHandle,
-    Focusable, Font, IntoElement, Render, SharedString, Task, Window,
+    Focusable, Font, InteractiveElement as _, IntoElement, ParentElement as _, Render,
+    SharedString, Styled as _, Task, Window, div,
 };
 use language::{Buffer, Capability, HighlightedText, OffsetRangeExt};
 use multi_buffer::PathKey;
-use project::Project;
+use project::{Project, git_store::Repository};
 use std::{
     any::{Any, TypeId},
     path::{Path, PathBuf},
@@ -22,9 +24,14 @@ use workspace::{
     searchable::SearchableItemHandle,
 };
 
+use crate::branch_review::BranchReview;
+use crate::commit_view::{GitBlob, build_buffer, build_buffer_diff as build_text_diff};
+
 pub struct MultiDiffView {
     editor: Entity<Editor>,
     file_count: usize,
+    title: Option<SharedString>,
+    review: Option<Entity<BranchReview>>,
 }
 
 struct Entry {
@@ -215,6 +222,132 @@ impl MultiDiffView {
         })
     }
 
+    /// Opens a read-only view of file contents that don't live in the worktree,
+    /// such as both sides of a diff between two branches.
+    pub fn open_file_diffs(
+        title: SharedString,
+        files: Vec<CommitFile>,
+        repository: Entity<Repository>,
+        workspace: &Workspace,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<Entity<Self>>> {
+        let project = workspace.project().clone();
+        let workspace = workspace.weak_handle();
+        let language_registry = project.read(cx).languages().clone();
+        let context_lines = multibuffer_context_lines(cx);
+        let first_worktree_id = project
+            .read(cx)
+            .worktrees(cx)
+            .next()
+            .map(|worktree| worktree.read(cx).id());
+
+        window.spawn(cx, async move |cx| {
+            let multibuffer = cx.new(|cx| {
+                let mut multibuffer = MultiBuffer::new(Capability::ReadOnly);
+                multibuffer.set_all_diff_hunks_expanded(cx);
+                multibuffer
+            });
+            let file_count = files.len();
+
+            for (ix, file) in files.into_iter().enumerate() {
+                let is_deleted = file.new_text.is_none();
+                let new_text = file.new_text.unwrap_or_default();
+                let is_binary = file.is_binary
+                    || is_binary_content(new_text.as_bytes())
+                    || file
+                        .old_text
+                        .as_ref()
+                        .is_some_and(|text| is_binary_content(text.as_bytes()));
+                let (new_text, old_text) = if is_binary {
+                    ("(binary file not shown)".to_string(), None)
+                } else {
+                    (new_text, file.old_text)
+                };
+                let worktree_id = repository
+                    .update(cx, |repository, cx| {
+                        repository
+                            .repo_path_to_project_path(&file.path, cx)
+                            .map(|path| path.worktree_id)
+                            .or(first_worktree_id)
+                    })
+                    .context("project has no worktrees")?;
+                let display_name = file
+                    .path
+                    .file_name()
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| file.path.display(PathStyle::local()).to_string());
+                let blob = Arc::new(GitBlob {
+                    path: file.path.clone(),
+                    worktree_id,
+                    is_deleted,
+                    is_binary,
+                    display_name,
+                }) as Arc<dyn language::File>;
+
+                let buffer = build_buffer(new_text, blob, &language_registry, cx).await?;
+                let diff = if is_binary {
+                    None
+                } else {
+                    Some(build_text_diff(old_text, &buffer, &language_registry, cx).await?)
+                };
+
+                multibuffer.update(cx, |multibuffer, cx| {
+                    let snapshot = buffer.read(cx).snapshot();
+                    let mut ranges = diff
+                        .as_ref()
+                        .map(|diff| {
+                            diff.read(cx)
+                                .snapshot(cx)
+                                .hunks(&snapshot)
+                                .map(|hunk| hunk.buffer_range.to_point(&snapshot))
+                                .collect::<Vec<_>>()
+                        })
+                        .unwrap_or_default();
+                    if ranges.is_empty() {
+                        ranges.push(language::Point::zero()..snapshot.max_point());
+                    }
+                    multibuffer.set_excerpts_for_path(
+                        PathKey::with_sort_prefix(ix as u64, file.path.as_ref().clone()),
+                        buffer.clone(),
+                        ranges,
+                        context_lines,
+                        cx,
+                    );
+                    if let Some(diff) = diff {
+                        multibuffer.add_diff(diff, cx);
+                    }
+                });
+            }
+
+            workspace.update_in(cx, |workspace, window, cx| {
+                let diff_view = cx.new(|cx| {
+                    let mut view =
+                        Self::new(multibuffer.clone(), project.clone(), file_count, window, cx);
+                    view.title = Some(title);
+                    view
+                });
+                workspace.active_pane().update(cx, |pane, cx| {
+                    pane.add_item(Box::new(diff_view.clone()), true, true, None, window, cx);
+                });
+                diff_view
+            })
+        })
+    }
+
+    pub fn editor(&self) -> &Entity<Editor> {
+        &self.editor
+    }
+
+    pub fn review(&self) -> Option<&Entity<BranchReview>> {
+        self.review.as_ref()
+    }
+
+    pub(crate) fn set_review(&mut self, review: Entity<BranchReview>, cx: &mut Context<Self>) {
+        self.review = Some(review);
+        cx.notify();
+    }
+
     fn new(
         multibuffer: Entity<MultiBuffer>,
         project: Entity<Project>,
@@ -235,7 +368,12 @@ impl MultiDiffView {
             editor
         });
 
-        Self { editor, file_count }
+        Self {
+            editor,
+            file_count,
+            title: None,
+            review: None,
+        }
     }
 
     fn title(&self) -> SharedString {
@@ -244,7 +382,10 @@ impl MultiDiffView {
         } else {
             format!("{} files", self.file_count)
         };
-        format!("Diff ({suffix})").into()
+        match &self.title {
+            Some(title) => format!("{title} ({suffix})").into(),
+            None => format!("Diff ({suffix})").into(),
+        }
     }
 }
 
@@ -371,6 +512,28 @@ impl Item for MultiDiffView {
 
 impl Render for MultiDiffView {
     fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
-        self.editor.clone()
+        let Some(review) = self.review.clone() else {
+            return self.editor.clone().into_any_element();
+        };
+        div()
+            .key_context("BranchReview")
+            .size_full()
+            .on_action({
+                let review = review.clone();
+                move |_: &crate::branch_review::AddComment, window, cx| {
+                    BranchReview::deploy_comment_modal(&review, window, cx);
+                }
+            })
+            .on_action({
+                let review = review.clone();
+                move |_: &crate::branch_review::ToggleThreadResolved, _, cx| {
+                    review.update(cx, |review, cx| review.toggle_resolved_at_cursor(cx));
+                }
+            })
+            .on_action(move |_: &crate::branch_review::PostComments, window, cx| {
+                BranchReview::post_comments_and_notify(&review, window, cx);
+            })
+            .child(self.editor.clone())
+            .into_any_element()
     }
 }
@@ -2647,6 +2647,80 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_submodule_expanded_into_its_changes(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/project"),
+            json!({
+                ".git": {},
+                "README.md": "readme\n",
+                "sub": {
+                    ".git": {},
+                    "lib.rs": "new\n",
+                },
+            }),
+        )
+        .await;
+        fs.set_head_and_index_for_repo(
+            path!("/project/.git").as_ref(),
+            &[("README.md", "readme\n".into())],
+        );
+        fs.set_submodules_for_repo(
+            path!("/project/.git").as_ref(),
+            vec![git::submodule::Submodule {
+                path: RepoPath::new("sub").unwrap(),
+                recorded_sha: Some("recorded".into()),
+                checked_out_sha: Some("checked-out".into()),
+                status: git::submodule::SubmoduleStatus {
+                    initialized: true,
+                    commit_changed: true,
+                    ..Default::default()
+                },
+            }],
+        );
+        fs.set_head_and_index_for_repo(
+            path!("/project/sub/.git").as_ref(),
+            &[("lib.rs", "old\n".into())],
+        );
+        // The fake repository diffs its HEAD against the merge base contents,
+        // which here stand in for the commit recorded by the superproject.
+        fs.set_merge_base_content_for_repo(
+            path!("/project/sub/.git").as_ref(),
+            &[("lib.rs", "older\n".into())],
+        );
+
+        let project = Project::test(fs.clone(), [path!("/project").as_ref()], cx).await;
+        let (multi_workspace, cx) =
+            cx.add_window_view(|window, cx| MultiWorkspace::test_new(project.clone(), window, cx));
+        let workspace = multi_workspace.read_with(cx, |mw, _| mw.workspace().clone());
+        let diff = cx.new_window_entity(|window, cx| {
+            ProjectDiff::new(project.clone(), workspace, window, cx)
+        });
+        cx.run_until_parked();
+        // Submodule diffs are computed once repository events settle.
+        cx.executor()
+            .advance_clock(std::time::Duration::from_secs(1));
+        cx.run_until_parked();
+
+        let editor = diff.read_with(cx, |diff, cx| diff.editor.read(cx).rhs_editor().clone());
+        assert_state_with_diff(
+            &editor,
+            cx,
+            &"
+                - ˇolder
+                + new
+            "
+            .unindent(),
+        );
+        assert_eq!(
+            diff.read_with(cx, |diff, cx| diff.excerpt_paths(cx)),
+            vec![rel_path("sub/lib.rs").into_arc()]
+        );
+    }
+
     #[gpui::test]
     async fn test_update_on_uncommit(cx: &mut TestAppContext) {
         init_test(cx);
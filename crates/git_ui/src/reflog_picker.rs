@@ -0,0 +1,737 @@
+use anyhow::anyhow;
+use fuzzy::StringMatchCandidate;
+use git::repository::{ReflogEntry, ResetMode};
+use gpui::{
+    Action, AnyElement, App, Context, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable,
+    InteractiveElement, IntoElement, ParentElement, Render, SharedString, Styled, Subscription,
+    Task, WeakEntity, Window, actions, rems,
+};
+use menu::{Cancel, Confirm};
+use picker::{Picker, PickerDelegate};
+use project::git_store::Repository;
+use std::sync::Arc;
+use time::{OffsetDateTime, UtcOffset};
+use ui::{
+    Headline, HeadlineSize, HighlightedLabel, KeyBinding, ListItem, ListItemSpacing, Tooltip,
+    prelude::*,
+};
+use util::ResultExt;
+use workspace::notifications::DetachAndPromptErr;
+use workspace::{ModalView, Workspace};
+
+use crate::commit_view::CommitView;
+
+actions!(
+    reflog_picker,
+    [
+        /// Create a new branch pointing at the selected reflog entry.
+        CreateBranchFromEntry,
+        /// Show the commit of the selected reflog entry.
+        ShowReflogEntry,
+    ]
+);
+
+pub fn register(workspace: &mut Workspace) {
+    workspace.register_action(open);
+}
+
+pub fn open(
+    workspace: &mut Workspace,
+    action: &zed_actions::git::ViewReflog,
+    window: &mut Window,
+    cx: &mut Context<Workspace>,
+) {
+    let repository = crate::resolve_active_repository(workspace, cx);
+    let ref_name = action
+        .ref_name
+        .clone()
+        .filter(|ref_name| !ref_name.is_empty())
+        .unwrap_or_else(|| "HEAD".to_string());
+    let weak_workspace = workspace.weak_handle();
+    workspace.toggle_modal(window, cx, |window, cx| {
+        ReflogList::new(repository, ref_name, weak_workspace, rems(34.), window, cx)
+    })
+}
+
+pub struct ReflogList {
+    width: Rems,
+    pub picker: Entity<Picker<ReflogListDelegate>>,
+    picker_focus_handle: FocusHandle,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl ReflogList {
+    fn new(
+        repository: Option<Entity<Repository>>,
+        ref_name: String,
+        workspace: WeakEntity<Workspace>,
+        width: Rems,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let reflog_request = repository
+            .clone()
+            .map(|repository| repository.update(cx, |repo, _| repo.reflog(ref_name.clone())));
+
+        cx.spawn_in(window, async move |this, cx| {
+            let entries = match reflog_request {
+                Some(request) => request.await??,
+                None => Vec::new(),
+            };
+
+            this.update_in(cx, |this, window, cx| {
+                this.picker.update(cx, |picker, cx| {
+                    picker.delegate.all_entries = Some(entries);
+                    picker.refresh(window, cx);
+                })
+            })?;
+
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+
+        let delegate = ReflogListDelegate::new(repository, ref_name, workspace, cx);
+        let picker = cx.new(|cx| Picker::uniform_list(delegate, window, cx).show_scrollbar(true));
+        let picker_focus_handle = picker.focus_handle(cx);
+        picker.update(cx, |picker, _| {
+            picker.delegate.focus_handle = picker_focus_handle.clone();
+        });
+        let _subscriptions = vec![cx.subscribe(&picker, |_, _, _, cx| {
+            cx.emit(DismissEvent);
+        })];
+
+        Self {
+            width,
+            picker,
+            picker_focus_handle,
+            _subscriptions,
+        }
+    }
+
+    fn handle_show_entry(
+        &mut self,
+        _: &ShowReflogEntry,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.picker.update(cx, |picker, cx| {
+            picker
+                .delegate
+                .show_entry_at(picker.delegate.selected_index(), window, cx);
+        });
+        cx.emit(DismissEvent);
+    }
+
+    fn handle_create_branch(
+        &mut self,
+        _: &CreateBranchFromEntry,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.picker.update(cx, |picker, cx| {
+            picker
+                .delegate
+                .create_branch_at(picker.delegate.selected_index(), window, cx);
+        });
+    }
+}
+
+impl ModalView for ReflogList {}
+impl EventEmitter<DismissEvent> for ReflogList {}
+impl Focusable for ReflogList {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.picker_focus_handle.clone()
+    }
+}
+
+impl Render for ReflogList {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("ReflogList")
+            .w(self.width)
+            .on_action(cx.listener(Self::handle_show_entry))
+            .on_action(cx.listener(Self::handle_create_branch))
+            .child(self.picker.clone())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ReflogEntryMatch {
+    entry: ReflogEntry,
+    positions: Vec<usize>,
+    formatted_timestamp: String,
+}
+
+pub struct ReflogListDelegate {
+    matches: Vec<ReflogEntryMatch>,
+    all_entries: Option<Vec<ReflogEntry>>,
+    repo: Option<Entity<Repository>>,
+    ref_name: String,
+    workspace: WeakEntity<Workspace>,
+    selected_index: usize,
+    focus_handle: FocusHandle,
+    timezone: UtcOffset,
+}
+
+impl ReflogListDelegate {
+    fn new(
+        repo: Option<Entity<Repository>>,
+        ref_name: String,
+        workspace: WeakEntity<Workspace>,
+        cx: &mut Context<ReflogList>,
+    ) -> Self {
+        Self {
+            matches: Vec::new(),
+            all_entries: None,
+            repo,
+            ref_name,
+            workspace,
+            selected_index: 0,
+            focus_handle: cx.focus_handle(),
+            timezone: UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
+        }
+    }
+
+    fn format_entry(entry: &ReflogEntry) -> String {
+        format!("{}: {}", entry.selector, entry.message)
+    }
+
+    fn format_timestamp(timestamp: i64, timezone: UtcOffset) -> String {
+        let timestamp =
+            OffsetDateTime::from_unix_timestamp(timestamp).unwrap_or(OffsetDateTime::now_utc());
+        time_format::format_localized_timestamp(
+            timestamp,
+            OffsetDateTime::now_utc(),
+            timezone,
+            time_format::TimestampFormat::EnhancedAbsolute,
+        )
+    }
+
+    fn show_entry_at(&self, ix: usize, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let Some(entry_match) = self.matches.get(ix) else {
+            return;
+        };
+        let Some(repo) = self.repo.clone() else {
+            return;
+        };
+        CommitView::open(
+            entry_match.entry.sha.to_string(),
+            repo.downgrade(),
+            self.workspace.clone(),
+            None,
+            None,
+            window,
+            cx,
+        );
+    }
+
+    /// Moves the branch this reflog belongs to back to the selected entry.
+    ///
+    /// The checked out branch is reset with `--mixed` so that no work in the
+    /// working tree is lost; any other branch is simply repointed.
+    fn restore_branch_at(&self, ix: usize, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let Some(entry_match) = self.matches.get(ix) else {
+            return;
+        };
+        let Some(repo) = self.repo.clone() else {
+            return;
+        };
+        let sha = entry_match.entry.sha.to_string();
+        let current_branch = repo
+            .read(cx)
+            .branch
+            .as_ref()
+            .map(|branch| branch.name().to_string());
+        let ref_name = self.ref_name.clone();
+
+        cx.spawn(async move |_, cx| {
+            let is_current_branch =
+                ref_name == "HEAD" || current_branch.as_ref() == Some(&ref_name);
+            if is_current_branch {
+                anyhow::ensure!(
+                    current_branch.is_some(),
+                    "Cannot restore a branch while HEAD is detached"
+                );
+                repo.update(cx, |repo, cx| repo.reset(sha, ResetMode::Mixed, cx))
+                    .await??;
+            } else {
+                repo.update(cx, |repo, _| repo.set_branch_target(ref_name, sha))
+                    .await??;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_prompt_err("Failed to restore branch", window, cx, |e, _, _| {
+            Some(e.to_string())
+        });
+        cx.emit(DismissEvent);
+    }
+
+    fn create_branch_at(&self, ix: usize, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let Some(entry_match) = self.matches.get(ix) else {
+            return;
+        };
+        let Some(repo) = self.repo.clone() else {
+            return;
+        };
+        let entry = entry_match.entry.clone();
+        self.workspace
+            .update(cx, |workspace, cx| {
+                workspace.toggle_modal(window, cx, |window, cx| {
+                    CreateBranchFromEntryModal::new(entry, repo, window, cx)
+                });
+            })
+            .log_err();
+    }
+}
+
+impl PickerDelegate for ReflogListDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _window: &mut Window, _cx: &mut App) -> Arc<str> {
+        format!("Search the {} reflog…", self.ref_name).into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(
+        &mut self,
+        ix: usize,
+        _window: &mut Window,
+        _: &mut Context<Picker<Self>>,
+    ) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Task<()> {
+        let Some(all_entries) = self.all_entries.clone() else {
+            return Task::ready(());
+        };
+        let timezone = self.timezone;
+
+        cx.spawn_in(window, async move |picker, cx| {
+            let matches: Vec<ReflogEntryMatch> = if query.is_empty() {
+                all_entries
+                    .into_iter()
+                    .map(|entry| ReflogEntryMatch {
+                        formatted_timestamp: Self::format_timestamp(entry.timestamp, timezone),
+                        entry,
+                        positions: Vec::new(),
+                    })
+                    .collect()
+            } else {
+                let candidates = all_entries
+                    .iter()
+                    .enumerate()
+                    .map(|(ix, entry)| StringMatchCandidate::new(ix, &Self::format_entry(entry)))
+                    .collect::<Vec<_>>();
+                fuzzy::match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    true,
+                    10000,
+                    &Default::default(),
+                    cx.background_executor().clone(),
+                )
+                .await
+                .into_iter()
+                .map(|candidate| {
+                    let entry = all_entries[candidate.candidate_id].clone();
+                    ReflogEntryMatch {
+                        formatted_timestamp: Self::format_timestamp(entry.timestamp, timezone),
+                        entry,
+                        positions: candidate.positions,
+                    }
+                })
+                .collect()
+            };
+
+            picker
+                .update(cx, |picker, _| {
+                    let delegate = &mut picker.delegate;
+                    delegate.matches = matches;
+                    if delegate.matches.is_empty() {
+                        delegate.selected_index = 0;
+                    } else {
+                        delegate.selected_index =
+                            core::cmp::min(delegate.selected_index, delegate.matches.len() - 1);
+                    }
+                })
+                .log_err();
+        })
+    }
+
+    fn confirm(&mut self, secondary: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        if secondary {
+            self.restore_branch_at(self.selected_index, window, cx);
+        } else {
+            self.show_entry_at(self.selected_index, window, cx);
+            cx.emit(DismissEvent);
+        }
+    }
+
+    fn dismissed(&mut self, _: &mut Window, cx: &mut Context<Picker<Self>>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let entry_match = &self.matches[ix];
+        let entry = &entry_match.entry;
+
+        let label = HighlightedLabel::new(Self::format_entry(entry), entry_match.positions.clone())
+            .truncate()
+            .into_any_element();
+
+        let commit_info = h_flex()
+            .gap_1p5()
+            .w_full()
+            .child(
+                Label::new(entry.short_sha().to_string())
+                    .color(Color::Muted)
+                    .size(LabelSize::Small),
+            )
+            .child(
+                Label::new(entry.subject.clone())
+                    .truncate()
+                    .color(Color::Muted)
+                    .size(LabelSize::Small),
+            )
+            .child(
+                Label::new("•")
+                    .alpha(0.5)
+                    .color(Color::Muted)
+                    .size(LabelSize::Small),
+            )
+            .child(
+                Label::new(entry_match.formatted_timestamp.clone())
+                    .color(Color::Muted)
+                    .size(LabelSize::Small),
+            );
+
+        let view_button = {
+            let focus_handle = self.focus_handle.clone();
+            IconButton::new(("view-reflog-entry", ix), IconName::Eye)
+                .icon_size(IconSize::Small)
+                .tooltip(move |_, cx| {
+                    Tooltip::for_action_in("View Commit", &ShowReflogEntry, &focus_handle, cx)
+                })
+                .on_click(cx.listener(move |this, _, window, cx| {
+                    this.delegate.show_entry_at(ix, window, cx);
+                    cx.emit(DismissEvent);
+                }))
+        };
+
+        let branch_button = {
+            let focus_handle = self.focus_handle.clone();
+            IconButton::new(("branch-from-reflog-entry", ix), IconName::GitBranch)
+                .icon_size(IconSize::Small)
+                .tooltip(move |_, cx| {
+                    Tooltip::for_action_in(
+                        "Create Branch From Entry",
+                        &CreateBranchFromEntry,
+                        &focus_handle,
+                        cx,
+                    )
+                })
+                .on_click(cx.listener(move |this, _, window, cx| {
+                    this.delegate.create_branch_at(ix, window, cx);
+                }))
+        };
+
+        Some(
+            ListItem::new(format!("reflog-{ix}"))
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .toggle_state(selected)
+                .child(
+                    h_flex()
+                        .w_full()
+                        .gap_2p5()
+                        .child(
+                            Icon::new(IconName::HistoryRerun)
+                                .size(IconSize::Small)
+                                .color(Color::Muted),
+                        )
+                        .child(div().w_full().child(label).child(commit_info)),
+                )
+                .end_slot(h_flex().gap_0p5().child(view_button).child(branch_button))
+                .show_end_slot_on_hover(),
+        )
+    }
+
+    fn no_matches_text(&self, _window: &mut Window, _cx: &mut App) -> Option<SharedString> {
+        Some("No reflog entries found".into())
+    }
+
+    fn render_footer(&self, _: &mut Window, cx: &mut Context<Picker<Self>>) -> Option<AnyElement> {
+        if self.matches.is_empty() {
+            return None;
+        }
+
+        let focus_handle = self.focus_handle.clone();
+
+        Some(
+            h_flex()
+                .w_full()
+                .p_1p5()
+                .gap_0p5()
+                .justify_end()
+                .flex_wrap()
+                .border_t_1()
+                .border_color(cx.theme().colors().border_variant)
+                .child(
+                    Button::new("create-branch", "Create Branch")
+                        .key_binding(
+                            KeyBinding::for_action_in(&CreateBranchFromEntry, &focus_handle, cx)
+                                .map(|kb| kb.size(rems_from_px(12.))),
+                        )
+                        .on_click(|_, window, cx| {
+                            window.dispatch_action(CreateBranchFromEntry.boxed_clone(), cx)
+                        }),
+                )
+                .child(
+                    Button::new("restore-branch", "Restore Branch Here")
+                        .key_binding(
+                            KeyBinding::for_action_in(&menu::SecondaryConfirm, &focus_handle, cx)
+                                .map(|kb| kb.size(rems_from_px(12.))),
+                        )
+                        .on_click(|_, window, cx| {
+                            window.dispatch_action(menu::SecondaryConfirm.boxed_clone(), cx)
+                        }),
+                )
+                .child(
+                    Button::new("view-commit", "View")
+                        .key_binding(
+                            KeyBinding::for_action_in(&menu::Confirm, &focus_handle, cx)
+                                .map(|kb| kb.size(rems_from_px(12.))),
+                        )
+                        .on_click(|_, window, cx| {
+                            window.dispatch_action(menu::Confirm.boxed_clone(), cx)
+                        }),
+                )
+                .into_any(),
+        )
+    }
+}
+
+struct CreateBranchFromEntryModal {
+    entry: ReflogEntry,
+    editor: Entity<editor::Editor>,
+    repo: Entity<Repository>,
+}
+
+impl CreateBranchFromEntryModal {
+    fn new(
+        entry: ReflogEntry,
+        repo: Entity<Repository>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let editor = cx.new(|cx| {
+            let mut editor = editor::Editor::single_line(window, cx);
+            editor.set_placeholder_text("Branch name", window, cx);
+            editor
+        });
+        Self {
+            entry,
+            editor,
+            repo,
+        }
+    }
+
+    fn cancel(&mut self, _: &Cancel, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let name = self.editor.read(cx).text(cx);
+        if name.trim().is_empty() {
+            cx.emit(DismissEvent);
+            return;
+        }
+
+        let repo = self.repo.clone();
+        let sha = self.entry.sha.to_string();
+        cx.spawn(async move |_, cx| {
+            match repo
+                .update(cx, |repo, _| {
+                    repo.create_branch(name.trim().to_string(), Some(sha))
+                })
+                .await
+            {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(error)) => Err(error),
+                Err(_) => Err(anyhow!("Operation was canceled")),
+            }
+        })
+        .detach_and_prompt_err("Failed to create branch", window, cx, |_, _, _| None);
+        cx.emit(DismissEvent);
+    }
+}
+
+impl EventEmitter<DismissEvent> for CreateBranchFromEntryModal {}
+impl ModalView for CreateBranchFromEntryModal {}
+impl Focusable for CreateBranchFromEntryModal {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.editor.focus_handle(cx)
+    }
+}
+
+impl Render for CreateBranchFromEntryModal {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("CreateBranchFromEntryModal")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .elevation_2(cx)
+            .w(rems(34.))
+            .child(
+                h_flex()
+                    .px_3()
+                    .pt_2()
+                    .pb_1()
+                    .w_full()
+                    .gap_1p5()
+                    .child(Icon::new(IconName::GitBranch).size(IconSize::XSmall))
+                    .child(
+                        Headline::new(format!(
+                            "Create Branch at {} ({})",
+                            self.entry.selector,
+                            self.entry.short_sha()
+                        ))
+                        .size(HeadlineSize::XSmall),
+                    ),
+            )
+            .child(div().px_3().pb_3().w_full().child(self.editor.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{TestAppContext, VisualTestContext};
+    use project::{FakeFs, Project};
+    use serde_json::json;
+    use settings::SettingsStore;
+    use std::path::Path;
+    use workspace::MultiWorkspace;
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+
+            theme_settings::init(theme::LoadThemes::JustBase, cx);
+            editor::init(cx);
+        })
+    }
+
+    fn reflog_entry(ix: usize, sha: &str, message: &str) -> ReflogEntry {
+        ReflogEntry {
+            selector: format!("feature@{{{ix}}}").into(),
+            sha: sha.to_string().into(),
+            message: message.to_string().into(),
+            subject: "Some commit".into(),
+            timestamp: 1000 - ix as i64,
+        }
+    }
+
+    #[gpui::test]
+    async fn test_restore_branch_from_reflog(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            Path::new("/project"),
+            json!({
+                ".git": {},
+                "file.txt": "content",
+            }),
+        )
+        .await;
+        let dot_git = Path::new("/project/.git");
+        fs.insert_branches(dot_git, &["main", "feature"]);
+        fs.set_branch_name(dot_git, Some("main"));
+        fs.set_reflog_for_repo(
+            dot_git,
+            "feature",
+            vec![
+                reflog_entry(
+                    0,
+                    "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb",
+                    "reset: moving to HEAD~1",
+                ),
+                reflog_entry(
+                    1,
+                    "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+                    "commit: Lost work",
+                ),
+            ],
+        );
+
+        let project = Project::test(fs.clone(), [Path::new("/project")], cx).await;
+        cx.run_until_parked();
+        let repository = project.read_with(cx, |project, cx| project.active_repository(cx));
+        let multi_workspace =
+            cx.add_window(|window, cx| MultiWorkspace::test_new(project, window, cx));
+        let cx = &mut VisualTestContext::from_window(*multi_workspace, cx);
+        let workspace = multi_workspace
+            .update(cx, |workspace, _, _| workspace.workspace().clone())
+            .unwrap();
+
+        let reflog_list = workspace.update_in(cx, |workspace, window, cx| {
+            let weak_workspace = workspace.weak_handle();
+            workspace.toggle_modal(window, cx, move |window, cx| {
+                ReflogList::new(
+                    repository,
+                    "feature".into(),
+                    weak_workspace,
+                    rems(34.),
+                    window,
+                    cx,
+                )
+            });
+            workspace.active_modal::<ReflogList>(cx).unwrap()
+        });
+        cx.run_until_parked();
+
+        reflog_list.update_in(cx, |reflog_list, window, cx| {
+            reflog_list.picker.update(cx, |picker, cx| {
+                assert_eq!(picker.delegate.matches.len(), 2);
+                picker.delegate.set_selected_index(1, window, cx);
+                picker.delegate.confirm(true, window, cx);
+            });
+        });
+        cx.run_until_parked();
+
+        workspace.update(cx, |workspace, cx| {
+            assert!(workspace.active_modal::<ReflogList>(cx).is_none());
+        });
+        let feature_target = fs
+            .with_git_state(dot_git, false, |state| {
+                state.refs.get("refs/heads/feature").cloned()
+            })
+            .unwrap();
+        assert_eq!(
+            feature_target.as_deref(),
+            Some("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa")
+        );
+    }
+}
@@ -1,4 +1,5 @@
-//! TextDiffView currently provides a UI for displaying differences between the clipboard and selected text.
+//! TextDiffView currently provides a UI for displaying differences between the clipboard (or
+//! another text, like a command's output) and selected text.
 
 use anyhow::Result;
 use buffer_diff::BufferDiff;
@@ -104,6 +105,10 @@ impl TextDiffView {
 
         let source_buffer_snapshot = source_buffer.read(cx).snapshot();
         let mut clipboard_text = diff_data.clipboard_text.clone();
+        let text_label = diff_data
+            .text_label
+            .clone()
+            .unwrap_or_else(|| "Clipboard".into());
 
         if !clipboard_text.ends_with("\n") {
             clipboard_text.push_str("\n");
@@ -127,6 +132,7 @@ impl TextDiffView {
                 let diff_view = cx.new(|cx| {
                     TextDiffView::new(
                         clipboard_buffer,
+                        text_label,
                         source_editor,
                         source_buffer,
                         expanded_selection_range,
@@ -152,6 +158,7 @@ impl TextDiffView {
 
     pub fn new(
         clipboard_buffer: Entity<Buffer>,
+        text_label: SharedString,
         source_editor: Entity<Editor>,
         source_buffer: Entity<Buffer>,
         source_range: Range<Point>,
@@ -227,8 +234,8 @@ impl TextDiffView {
 
         Self {
             diff_editor,
-            title: format!("Clipboard ↔ {selection_location_title}").into(),
-            path: Some(format!("Clipboard ↔ {selection_location_path}").into()),
+            title: format!("{text_label} ↔ {selection_location_title}").into(),
+            path: Some(format!("{text_label} ↔ {selection_location_path}").into()),
             buffer_changes_tx,
             _recalculate_diff_task: cx.spawn(async move |_, cx| {
                 while buffer_changes_rx.recv().await.is_ok() {
@@ -748,6 +755,7 @@ mod tests {
                     &DiffClipboardWithSelectionData {
                         clipboard_text: "REPLACED".to_string(),
                         editor,
+                        text_label: None,
                     },
                     workspace,
                     window,
@@ -837,6 +845,7 @@ mod tests {
                     &DiffClipboardWithSelectionData {
                         clipboard_text: "REPLACED".to_string(),
                         editor,
+                        text_label: None,
                     },
                     workspace,
                     window,
@@ -918,6 +927,7 @@ mod tests {
                     &DiffClipboardWithSelectionData {
                         clipboard_text: clipboard_text.to_string(),
                         editor,
+                        text_label: None,
                     },
                     workspace,
                     window,
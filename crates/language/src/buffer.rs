@@ -3126,6 +3126,38 @@ impl Buffer {
         }
     }
 
+    /// Restores an undo history persisted from an earlier buffer with the same text,
+    /// without making the buffer dirty. Returns whether the history was applied.
+    ///
+    /// The text ends up unchanged, so the replayed operations are neither broadcast
+    /// nor reported as edits. This must only be used on buffers that aren't shared.
+    pub fn restore_undo_history(&mut self, history: &UndoHistory, cx: &mut Context<Self>) -> bool {
+        if self.is_dirty() || self.text.restore_undo_history(history).is_none() {
+            return false;
+        }
+        self.saved_version = self.version.clone();
+        self.has_unsaved_edits
+            .set((self.saved_version.clone(), false));
+        self.was_changed();
+        self.reparse(cx, true);
+        cx.notify();
+        true
+    }
+
+    /// Moves the buffer to the given state of its undo tree, which may be on a
+    /// branch that can no longer be reached by undoing and redoing.
+    pub fn go_to_undo_state(&mut self, id: UndoStateId, cx: &mut Context<Self>) -> bool {
+        let was_dirty = self.is_dirty();
+        let old_version = self.version.clone();
+        if let Some(operation) = self.text.go_to_undo_state(id) {
+            self.send_operation(Operation::Buffer(operation), true, cx);
+            self.did_edit(&old_version, was_dirty, true, cx);
+            true
+        } else {
+            false
+        }
+    }
+
     /// Manually undoes a specific transaction in the buffer's undo history.
     pub fn undo_transaction(
         &mut self,
@@ -4175,6 +4207,95 @@ impl BufferSnapshot {
         result
     }
 
+    /// Find the syntax node that structural edits, like moving or duplicating a
+    /// node, apply to at the given range.
+    ///
+    /// For a non-empty range, this is the largest named node spanning exactly the
+    /// same bytes as the smallest named node containing the range. For an empty
+    /// range, this is the largest named node starting where the smallest named
+    /// node at the range starts, so that a cursor at the start of a statement or
+    /// an argument targets the whole statement or argument.
+    ///
+    /// Returns `None` if the only such node is the root of a syntax layer.
+    pub fn syntax_node_for_structural_edit<'a, T: ToOffset>(
+        &'a self,
+        range: Range<T>,
+    ) -> Option<tree_sitter::Node<'a>> {
+        let range = range.start.to_offset(self)..range.end.to_offset(self);
+        let mut result: Option<tree_sitter::Node<'a>> = None;
+
+        for layer in self
+            .syntax
+            .layers_for_range(range.clone(), &self.text, true)
+        {
+            let Some(mut node) = layer
+                .node()
+                .named_descendant_for_byte_range(range.start, range.end)
+            else {
+                continue;
+            };
+            while let Some(parent) = node.parent()
+                && parent.parent().is_some()
+                && parent.start_byte() == node.start_byte()
+                && (range.is_empty() || parent.end_byte() == node.end_byte())
+            {
+                node = parent;
+            }
+            if node.parent().is_none() {
+                continue;
+            }
+
+            if let Some(previous_result) = &result
+                && previous_result.byte_range().len() <= node.byte_range().len()
+            {
+                continue;
+            }
+            result = Some(node);
+        }
+
+        result
+    }
+
+    /// Find the smallest syntax node delimited by brackets that encloses the given
+    /// range between its brackets, e.g. an argument list or a block.
+    pub fn syntax_enclosing_bracketed_node<'a, T: ToOffset>(
+        &'a self,
+        range: Range<T>,
+    ) -> Option<tree_sitter::Node<'a>> {
+        let range = range.start.to_offset(self)..range.end.to_offset(self);
+        let mut result: Option<tree_sitter::Node<'a>> = None;
+
+        for layer in self
+            .syntax
+            .layers_for_range(range.clone(), &self.text, true)
+        {
+            let mut node = layer
+                .node()
+                .descendant_for_byte_range(range.start, range.end);
+            while let Some(current) = node {
+                if let Some((open, close)) = bracket_children(current)
+                    && open.end_byte() <= range.start
+                    && range.end <= close.start_byte()
+                {
+                    break;
+                }
+                node = current.parent();
+            }
+            let Some(node) = node else {
+                continue;
+            };
+
+            if let Some(previous_result) = &result
+                && previous_result.byte_range().len() <= node.byte_range().len()
+            {
+                continue;
+            }
+            result = Some(node);
+        }
+
+        result
+    }
+
     /// Returns the root syntax node within the given row
     pub fn syntax_root_ancestor(&self, position: Anchor) -> Option<tree_sitter::Node<'_>> {
         let start_offset = position.to_offset(self);
@@ -6054,3 +6175,24 @@ pub fn trailing_whitespace_ranges(rope: &Rope) -> Vec<Range<usize>> {
 
     ranges
 }
+
+/// Returns the opening and closing brackets of a syntax node whose first and last
+/// children are a matching pair of bracket tokens, like an argument list or a block.
+pub fn bracket_children(
+    node: tree_sitter::Node<'_>,
+) -> Option<(tree_sitter::Node<'_>, tree_sitter::Node<'_>)> {
+    const BRACKET_PAIRS: [(&str, &str); 3] = [("(", ")"), ("[", "]"), ("{", "}")];
+
+    let child_count = node.child_count();
+    if child_count < 2 {
+        return None;
+    }
+    let open = node.child(0)?;
+    let close = node.child(child_count as u32 - 1)?;
+    if open.is_named() || close.is_named() {
+        return None;
+    }
+    BRACKET_PAIRS
+        .contains(&(open.kind(), close.kind()))
+        .then_some((open, close))
+}
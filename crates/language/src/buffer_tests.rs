@@ -499,6 +499,43 @@ fn test_edit_events(cx: &mut gpui::App) {
     );
 }
 
+#[gpui::test]
+fn test_restore_undo_history(cx: &mut gpui::App) {
+    let buffer = cx.new(|cx| Buffer::local("one 2 three", cx));
+    let events = Arc::new(Mutex::new(Vec::new()));
+    buffer.update(cx, |_, cx| {
+        let events = events.clone();
+        cx.subscribe(&buffer, move |_, _, event, _| {
+            events.lock().push(event.clone())
+        })
+        .detach();
+    });
+
+    let history = UndoHistory {
+        undo_stack: vec![vec![HistoryEdit {
+            range: 4..7,
+            old_text: "two".into(),
+            new_text: "2".into(),
+        }]],
+        redo_stack: Vec::new(),
+    };
+    buffer.update(cx, |buffer, cx| {
+        assert!(buffer.restore_undo_history(&history, cx));
+        assert_eq!(buffer.text(), "one 2 three");
+        assert!(!buffer.is_dirty());
+    });
+    // The text is unchanged, so nothing is broadcast or reported as an edit.
+    assert_eq!(mem::take(&mut *events.lock()), Vec::new());
+
+    buffer.update(cx, |buffer, cx| {
+        buffer.undo(cx);
+        assert_eq!(buffer.text(), "one two three");
+        assert!(buffer.is_dirty());
+        // A history only applies to a buffer without one.
+        assert!(!buffer.restore_undo_history(&history, cx));
+    });
+}
+
 #[gpui::test]
 async fn test_apply_diff(cx: &mut TestAppContext) {
     let (text, offsets) = marked_text_offsets(
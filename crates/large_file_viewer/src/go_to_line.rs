@@ -0,0 +1,105 @@
+use editor::Editor;
+use gpui::{App, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, WeakEntity};
+use ui::prelude::*;
+use util::paths::FILE_ROW_COLUMN_DELIMITER;
+use workspace::ModalView;
+
+use crate::LargeFileView;
+
+/// Jumps to a line of a [`LargeFileView`], mirroring the editor's go-to-line modal.
+pub struct GoToLine {
+    line_editor: Entity<Editor>,
+    view: WeakEntity<LargeFileView>,
+    line_count: usize,
+}
+
+impl ModalView for GoToLine {}
+
+impl EventEmitter<DismissEvent> for GoToLine {}
+
+impl Focusable for GoToLine {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.line_editor.focus_handle(cx)
+    }
+}
+
+impl GoToLine {
+    pub fn new(
+        view: WeakEntity<LargeFileView>,
+        current_row: usize,
+        line_count: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let line_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text(&(current_row + 1).to_string(), window, cx);
+            editor
+        });
+        cx.subscribe(&line_editor, |_, _, event, cx| {
+            if let editor::EditorEvent::Blurred = event {
+                cx.emit(DismissEvent);
+            }
+        })
+        .detach();
+
+        Self {
+            line_editor,
+            view,
+            line_count,
+        }
+    }
+
+    fn line_from_query(&self, cx: &App) -> Option<usize> {
+        let query = self.line_editor.read(cx).text(cx);
+        let line = query.split(FILE_ROW_COLUMN_DELIMITER).next()?.trim();
+        line.parse::<usize>().ok()
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, _: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(line) = self.line_from_query(cx) {
+            self.view
+                .update(cx, |view, cx| {
+                    view.go_to_row(line.saturating_sub(1), cx);
+                    window.focus(&view.focus_handle(cx), cx);
+                })
+                .ok();
+        }
+        cx.emit(DismissEvent);
+    }
+}
+
+impl Render for GoToLine {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let help_text = match self.line_from_query(cx) {
+            Some(line) => format!("Go to line {line}"),
+            None => format!("Enter a line number between 1 and {}", self.line_count),
+        };
+
+        v_flex()
+            .w(rems(24.))
+            .elevation_2(cx)
+            .key_context("GoToLine")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .child(
+                div()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .px_2()
+                    .py_1()
+                    .child(self.line_editor.clone()),
+            )
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .gap_1()
+                    .child(Label::new(help_text).color(Color::Muted)),
+            )
+    }
+}
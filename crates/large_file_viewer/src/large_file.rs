@@ -0,0 +1,279 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Result;
+use gpui::{App, AppContext as _, Context, Entity, Subscription, Task};
+use project::{Project, ProjectEntryId, ProjectItem, ProjectPath, Worktree, WorktreeSettings};
+use regex::Regex;
+use settings::Settings as _;
+use util::ResultExt as _;
+use worktree::FileRangeReader;
+
+use crate::line_index::LineIndex;
+use crate::search::{LineMatch, search_lines};
+
+/// How many bytes are indexed between two updates of the line count.
+const INDEX_BATCH_LEN: u64 = 64 * 1024 * 1024;
+
+/// A file above the `large_file_threshold_mb` setting, which is read a page at a
+/// time, from disk or from the remote server, instead of being loaded into a buffer.
+///
+/// The file is expected to only grow by having lines appended, like a log. When it
+/// shrinks, it is indexed again from the start.
+pub struct LargeFile {
+    project_path: ProjectPath,
+    entry_id: Option<ProjectEntryId>,
+    abs_path: PathBuf,
+    reader: FileRangeReader,
+    index: Arc<LineIndex>,
+    /// Incremented whenever the file shrinks and previously read lines become stale.
+    generation: usize,
+    indexing: bool,
+    _index_task: Task<()>,
+    _worktree_subscription: Subscription,
+}
+
+impl LargeFile {
+    fn new(project_path: ProjectPath, worktree: Entity<Worktree>, cx: &mut Context<Self>) -> Self {
+        let worktree_snapshot = worktree.read(cx);
+        let entry_id = worktree_snapshot
+            .entry_for_path(&project_path.path)
+            .map(|entry| entry.id);
+        let abs_path = worktree_snapshot.absolutize(&project_path.path);
+        let reader = worktree_snapshot.file_range_reader(&project_path.path);
+        let worktree_subscription = cx.subscribe(&worktree, |this, worktree, event, cx| {
+            if let worktree::Event::UpdatedEntries(changes) = event
+                && changes
+                    .iter()
+                    .any(|(path, _, _)| *path == this.project_path.path)
+            {
+                let len = worktree
+                    .read(cx)
+                    .entry_for_path(&this.project_path.path)
+                    .map(|entry| entry.size);
+                this.file_changed(len, cx);
+            }
+        });
+
+        let mut this = Self {
+            project_path,
+            entry_id,
+            abs_path,
+            reader,
+            index: Arc::default(),
+            generation: 0,
+            indexing: false,
+            _index_task: Task::ready(()),
+            _worktree_subscription: worktree_subscription,
+        };
+        this.index_remaining(cx);
+        this
+    }
+
+    pub fn abs_path(&self) -> &Path {
+        &self.abs_path
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.index.line_count()
+    }
+
+    pub fn indexed_len(&self) -> u64 {
+        self.index.indexed_len()
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Whether the file is still being scanned for line starts, in which case more
+    /// lines may follow the ones counted so far.
+    pub fn is_indexing(&self) -> bool {
+        self.indexing
+    }
+
+    fn file_changed(&mut self, len: Option<u64>, cx: &mut Context<Self>) {
+        let Some(len) = len else {
+            return;
+        };
+        if len < self.index.indexed_len() {
+            self.index = Arc::default();
+            self.generation += 1;
+            self.indexing = false;
+            self._index_task = Task::ready(());
+            cx.notify();
+        }
+        // While indexing, the file is read until its end, including any appended lines.
+        if len > self.index.indexed_len() && !self.indexing {
+            self.index_remaining(cx);
+        }
+    }
+
+    /// Indexes the part of the file past what has been indexed so far, a batch at a time.
+    fn index_remaining(&mut self, cx: &mut Context<Self>) {
+        let reader = self.reader.clone();
+        self.indexing = true;
+        self._index_task = cx.spawn(async move |this, cx| {
+            let result = async {
+                loop {
+                    let mut continuation =
+                        this.read_with(cx, |this, _| this.index.continuation())?;
+                    let reader = reader.clone();
+                    let (continuation, done) = cx
+                        .background_spawn(async move {
+                            let done = continuation
+                                .read_from(INDEX_BATCH_LEN, |range| reader.read(range))
+                                .await?;
+                            anyhow::Ok((continuation, done))
+                        })
+                        .await?;
+                    this.update(cx, |this, cx| {
+                        Arc::make_mut(&mut this.index).append(continuation);
+                        cx.notify();
+                    })?;
+                    if done {
+                        return anyhow::Ok(());
+                    }
+                }
+            }
+            .await;
+            this.update(cx, |this, cx| {
+                this.indexing = false;
+                cx.notify();
+            })
+            .ok();
+            result.log_err();
+        });
+    }
+
+    /// Reads the given rows of the file in the background.
+    pub fn read_lines(&self, rows: Range<usize>, cx: &App) -> Task<Result<Vec<String>>> {
+        let index = self.index.clone();
+        let reader = self.reader.clone();
+        cx.background_spawn(async move { index.read_lines(rows, |range| reader.read(range)).await })
+    }
+
+    /// Searches the whole file in the background, including any lines that haven't
+    /// been indexed yet.
+    pub fn search(&self, regex: Regex, cx: &App) -> Task<Result<Vec<LineMatch>>> {
+        let reader = self.reader.clone();
+        cx.background_spawn(async move { search_lines(|range| reader.read(range), &regex).await })
+    }
+}
+
+/// Returns the worktree containing the given path if it's a file that should be
+/// opened in large file mode.
+fn large_file_worktree(
+    project: &Entity<Project>,
+    path: &ProjectPath,
+    cx: &App,
+) -> Option<Entity<Worktree>> {
+    let worktree = project.read(cx).worktree_for_id(path.worktree_id, cx)?;
+    let entry = worktree.read(cx).entry_for_path(&path.path)?;
+    let threshold = WorktreeSettings::get(Some(path.into()), cx).large_file_threshold;
+    (entry.is_file() && entry.size >= threshold).then_some(worktree)
+}
+
+impl ProjectItem for LargeFile {
+    fn try_open(
+        project: &Entity<Project>,
+        path: &ProjectPath,
+        cx: &mut App,
+    ) -> Option<Task<Result<Entity<Self>>>> {
+        let worktree = large_file_worktree(project, path, cx)?;
+        let path = path.clone();
+        Some(Task::ready(Ok(
+            cx.new(|cx| LargeFile::new(path, worktree, cx))
+        )))
+    }
+
+    fn entry_id(&self, _: &App) -> Option<ProjectEntryId> {
+        self.entry_id
+    }
+
+    fn project_path(&self, _: &App) -> Option<ProjectPath> {
+        Some(self.project_path.clone())
+    }
+
+    fn is_dirty(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+    use serde_json::json;
+    use settings::SettingsStore;
+    use util::{path, rel_path::rel_path};
+
+    #[gpui::test]
+    async fn test_large_file(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let store = SettingsStore::test(cx);
+            cx.set_global(store);
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.project.worktree.large_file_threshold_mb = Some(0);
+                });
+            });
+        });
+        let text = (0..1000)
+            .map(|row| format!("line {row}\n"))
+            .collect::<String>();
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(path!("/root"), json!({ "log.txt": text }))
+            .await;
+        let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+        let worktree_id = project.read_with(cx, |project, cx| {
+            project.worktrees(cx).next().unwrap().read(cx).id()
+        });
+        let path = ProjectPath {
+            worktree_id,
+            path: rel_path("log.txt").into(),
+        };
+
+        let large_file = cx
+            .update(|cx| LargeFile::try_open(&project, &path, cx))
+            .unwrap()
+            .await
+            .unwrap();
+        cx.run_until_parked();
+        large_file.read_with(cx, |large_file, _| {
+            assert_eq!(large_file.line_count(), 1001);
+            assert!(!large_file.is_indexing());
+        });
+
+        let lines = large_file
+            .read_with(cx, |large_file, cx| large_file.read_lines(500..502, cx))
+            .await
+            .unwrap();
+        assert_eq!(lines, ["line 500", "line 501"]);
+        let matches = large_file
+            .read_with(cx, |large_file, cx| {
+                large_file.search(Regex::new(r"line 99\d").unwrap(), cx)
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            matches.iter().map(|found| found.row).collect::<Vec<_>>(),
+            (990..1000).collect::<Vec<_>>()
+        );
+
+        // Lines appended to the file are indexed too.
+        fs.insert_file(path!("/root/log.txt"), format!("{text}line 1000\n").into())
+            .await;
+        cx.run_until_parked();
+        large_file.read_with(cx, |large_file, _| {
+            assert_eq!(large_file.line_count(), 1002);
+        });
+        let lines = large_file
+            .read_with(cx, |large_file, cx| large_file.read_lines(1000..1002, cx))
+            .await
+            .unwrap();
+        assert_eq!(lines, ["line 1000", ""]);
+    }
+}
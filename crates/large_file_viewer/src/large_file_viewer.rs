@@ -0,0 +1,596 @@
+mod go_to_line;
+mod large_file;
+mod line_index;
+mod search;
+
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use editor::{EditorSettings, actions::ToggleGoToLine, items::entry_git_aware_label_color};
+use file_icons::FileIcons;
+use gpui::{
+    AnyElement, App, Context, Entity, EventEmitter, FocusHandle, Focusable, HighlightStyle,
+    IntoElement, Render, ScrollStrategy, SharedString, StyledText, Subscription, Task,
+    UniformListScrollHandle, WeakEntity, Window, uniform_list,
+};
+use project::{Project, search::SearchQuery};
+use settings::Settings as _;
+use theme_settings::ThemeSettings;
+use ui::{WithScrollbar, prelude::*};
+use util::ResultExt as _;
+use workspace::{
+    ItemSettings, Pane, ToolbarItemLocation, Workspace,
+    invalid_item_view::InvalidItemView,
+    item::{HighlightedText, Item, ItemEvent, ProjectItem, TabContentParams},
+    searchable::{Direction, SearchEvent, SearchOptions, SearchToken, SearchableItem},
+};
+
+use crate::go_to_line::GoToLine;
+use crate::search::{LineMatch, regex_for_query};
+
+pub use crate::large_file::LargeFile;
+
+/// How many rows are read on either side of the visible ones, so that scrolling
+/// a little doesn't require reading the file again.
+const ROWS_AROUND_VISIBLE: usize = 256;
+
+pub fn init(cx: &mut App) {
+    workspace::register_project_item::<LargeFileView>(cx);
+}
+
+/// A read-only view of a [`LargeFile`], which only keeps the lines around the
+/// visible ones in memory.
+pub struct LargeFileView {
+    large_file: Entity<LargeFile>,
+    project: Entity<Project>,
+    workspace: Option<WeakEntity<Workspace>>,
+    focus_handle: FocusHandle,
+    scroll_handle: UniformListScrollHandle,
+    lines: LineCache,
+    loading_rows: Option<Range<usize>>,
+    selected_row: Option<usize>,
+    matches: Vec<LineMatch>,
+    active_match: Option<usize>,
+    _load_lines: Task<()>,
+    _large_file_subscription: Subscription,
+}
+
+#[derive(Default)]
+struct LineCache {
+    rows: Range<usize>,
+    lines: Vec<SharedString>,
+    generation: usize,
+    line_count: usize,
+}
+
+impl LineCache {
+    fn line(&self, row: usize) -> Option<&SharedString> {
+        self.lines.get(row.checked_sub(self.rows.start)?)
+    }
+}
+
+impl LargeFileView {
+    pub fn new(
+        large_file: Entity<LargeFile>,
+        project: Entity<Project>,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let large_file_subscription = cx.observe(&large_file, |this, large_file, cx| {
+            let large_file = large_file.read(cx);
+            if this.lines.generation != large_file.generation() {
+                this.lines = LineCache::default();
+                this.selected_row = None;
+                cx.emit(SearchEvent::MatchesInvalidated);
+            } else if this.lines.line_count != large_file.line_count()
+                && this.lines.rows.end >= this.lines.line_count
+            {
+                // The last line may have been extended by appended text.
+                this.lines = LineCache::default();
+            }
+            cx.notify();
+        });
+
+        Self {
+            large_file,
+            project,
+            workspace: None,
+            focus_handle: cx.focus_handle(),
+            scroll_handle: UniformListScrollHandle::new(),
+            lines: LineCache::default(),
+            loading_rows: None,
+            selected_row: None,
+            matches: Vec::new(),
+            active_match: None,
+            _load_lines: Task::ready(()),
+            _large_file_subscription: large_file_subscription,
+        }
+    }
+
+    fn load_rows(&mut self, rows: Range<usize>, cx: &mut Context<Self>) {
+        let is_covered = |range: &Range<usize>| range.start <= rows.start && rows.end <= range.end;
+        if is_covered(&self.lines.rows) || self.loading_rows.as_ref().is_some_and(is_covered) {
+            return;
+        }
+
+        let large_file = self.large_file.read(cx);
+        let generation = large_file.generation();
+        let line_count = large_file.line_count();
+        let rows = rows.start.saturating_sub(ROWS_AROUND_VISIBLE)
+            ..(rows.end + ROWS_AROUND_VISIBLE).min(line_count);
+        let read_lines = large_file.read_lines(rows.clone(), cx);
+        self.loading_rows = Some(rows.clone());
+        self._load_lines = cx.spawn(async move |this, cx| {
+            let lines = read_lines.await;
+            this.update(cx, |this, cx| {
+                this.loading_rows = None;
+                if let Some(lines) = lines.log_err() {
+                    this.lines = LineCache {
+                        rows,
+                        lines: lines.into_iter().map(SharedString::from).collect(),
+                        generation,
+                        line_count,
+                    };
+                    cx.notify();
+                }
+            })
+            .ok();
+        });
+    }
+
+    fn go_to_row(&mut self, row: usize, cx: &mut Context<Self>) {
+        let row = row.min(self.large_file.read(cx).line_count().saturating_sub(1));
+        self.selected_row = Some(row);
+        self.scroll_handle
+            .scroll_to_item(row, ScrollStrategy::Center);
+        cx.notify();
+    }
+
+    fn select_next(&mut self, _: &menu::SelectNext, _: &mut Window, cx: &mut Context<Self>) {
+        let row = self.selected_row.map_or(0, |row| row + 1);
+        self.go_to_row(row, cx);
+    }
+
+    fn select_previous(
+        &mut self,
+        _: &menu::SelectPrevious,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let row = self.selected_row.map_or(0, |row| row.saturating_sub(1));
+        self.go_to_row(row, cx);
+    }
+
+    fn select_first(&mut self, _: &menu::SelectFirst, _: &mut Window, cx: &mut Context<Self>) {
+        self.go_to_row(0, cx);
+    }
+
+    fn select_last(&mut self, _: &menu::SelectLast, _: &mut Window, cx: &mut Context<Self>) {
+        self.go_to_row(usize::MAX, cx);
+    }
+
+    fn toggle_go_to_line(
+        &mut self,
+        _: &ToggleGoToLine,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(workspace) = self
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.upgrade())
+        else {
+            return;
+        };
+        let view = cx.weak_entity();
+        let current_row = self.selected_row.unwrap_or(0);
+        let line_count = self.large_file.read(cx).line_count();
+        workspace.update(cx, |workspace, cx| {
+            workspace.toggle_modal(window, cx, |window, cx| {
+                GoToLine::new(view, current_row, line_count, window, cx)
+            });
+        });
+    }
+
+    fn render_line(&self, row: usize, cx: &Context<Self>) -> AnyElement {
+        let colors = cx.theme().colors();
+        let text = self.lines.line(row).cloned().unwrap_or_default();
+        let first_match = self.matches.partition_point(|found| found.row < row);
+        let highlights = self.matches[first_match..]
+            .iter()
+            .enumerate()
+            .take_while(|(_, found)| found.row == row)
+            .filter(|(_, found)| found.range.end <= text.len())
+            .map(|(ix, found)| {
+                let background_color = if Some(first_match + ix) == self.active_match {
+                    colors.search_active_match_background
+                } else {
+                    colors.search_match_background
+                };
+                (
+                    found.range.clone(),
+                    HighlightStyle {
+                        background_color: Some(background_color),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+
+        h_flex()
+            .id(("line", row))
+            .w_full()
+            .gap_4()
+            .when(Some(row) == self.selected_row, |this| {
+                this.bg(colors.editor_active_line_background)
+            })
+            .child(
+                div()
+                    .flex_none()
+                    .w(self.gutter_width(cx))
+                    .text_color(if Some(row) == self.selected_row {
+                        colors.editor_active_line_number
+                    } else {
+                        colors.editor_line_number
+                    })
+                    .child((row + 1).to_string()),
+            )
+            .child(
+                div()
+                    .min_w_0()
+                    .overflow_hidden()
+                    .whitespace_nowrap()
+                    .child(StyledText::new(text).with_highlights(highlights)),
+            )
+            .on_click(cx.listener(move |this, _, _, cx| {
+                this.selected_row = Some(row);
+                cx.notify();
+            }))
+            .into_any_element()
+    }
+
+    fn gutter_width(&self, cx: &App) -> Rems {
+        let digits = self.large_file.read(cx).line_count().to_string().len();
+        rems(0.6 * digits as f32 + 0.5)
+    }
+
+    fn render_status(&self, cx: &App) -> impl IntoElement {
+        let large_file = self.large_file.read(cx);
+        let size = format!("{} MB", large_file.indexed_len() / (1024 * 1024));
+        let lines = if large_file.is_indexing() {
+            format!("{}+ lines, indexing…", large_file.line_count())
+        } else {
+            format!("{} lines", large_file.line_count())
+        };
+        h_flex()
+            .px_3()
+            .py_1()
+            .gap_2()
+            .justify_between()
+            .border_b_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(
+                Label::new(
+                    "Large file mode: read-only, without syntax highlighting or language servers",
+                )
+                .size(LabelSize::Small)
+                .color(Color::Muted),
+            )
+            .child(
+                Label::new(format!("{lines} · {size}"))
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            )
+    }
+}
+
+impl EventEmitter<ItemEvent> for LargeFileView {}
+impl EventEmitter<SearchEvent> for LargeFileView {}
+
+impl Focusable for LargeFileView {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for LargeFileView {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let line_count = self.large_file.read(cx).line_count();
+        let settings = ThemeSettings::get_global(cx);
+        let view = cx.weak_entity();
+
+        v_flex()
+            .id("large-file-view")
+            .key_context("LargeFileViewer")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::select_next))
+            .on_action(cx.listener(Self::select_previous))
+            .on_action(cx.listener(Self::select_first))
+            .on_action(cx.listener(Self::select_last))
+            .on_action(cx.listener(Self::toggle_go_to_line))
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(self.render_status(cx))
+            .child(
+                div()
+                    .flex_1()
+                    .size_full()
+                    .font(settings.buffer_font.clone())
+                    .text_size(settings.buffer_font_size(cx))
+                    .text_color(cx.theme().colors().editor_foreground)
+                    .child(
+                        uniform_list("large-file-lines", line_count, move |range, _, cx| {
+                            let Some(view) = view.upgrade() else {
+                                return Vec::new();
+                            };
+                            view.update(cx, |this, cx| {
+                                this.load_rows(range.clone(), cx);
+                                range.map(|row| this.render_line(row, cx)).collect()
+                            })
+                        })
+                        .size_full()
+                        .pl_2()
+                        .track_scroll(&self.scroll_handle),
+                    )
+                    .vertical_scrollbar_for(&self.scroll_handle, window, cx),
+            )
+    }
+}
+
+impl Item for LargeFileView {
+    type Event = ItemEvent;
+
+    fn to_item_events(event: &Self::Event, f: &mut dyn FnMut(ItemEvent)) {
+        f(*event)
+    }
+
+    fn for_each_project_item(
+        &self,
+        cx: &App,
+        f: &mut dyn FnMut(gpui::EntityId, &dyn project::ProjectItem),
+    ) {
+        f(self.large_file.entity_id(), self.large_file.read(cx))
+    }
+
+    fn tab_tooltip_text(&self, cx: &App) -> Option<SharedString> {
+        Some(
+            self.large_file
+                .read(cx)
+                .abs_path()
+                .to_string_lossy()
+                .into_owned()
+                .into(),
+        )
+    }
+
+    fn tab_content(&self, params: TabContentParams, _window: &Window, cx: &App) -> AnyElement {
+        let project_path = project::ProjectItem::project_path(self.large_file.read(cx), cx);
+        let label_color = match project_path {
+            Some(project_path) if ItemSettings::get_global(cx).git_status => {
+                let project = self.project.read(cx);
+                let git_status = project
+                    .project_path_git_status(&project_path, cx)
+                    .map(|status| status.summary())
+                    .unwrap_or_default();
+                project
+                    .entry_for_path(&project_path, cx)
+                    .map(|entry| {
+                        entry_git_aware_label_color(git_status, entry.is_ignored, params.selected)
+                    })
+                    .unwrap_or_else(|| params.text_color())
+            }
+            _ => params.text_color(),
+        };
+        Label::new(self.tab_content_text(params.detail.unwrap_or_default(), cx))
+            .single_line()
+            .color(label_color)
+            .when(params.preview, |this| this.italic())
+            .into_any_element()
+    }
+
+    fn tab_content_text(&self, _: usize, cx: &App) -> SharedString {
+        self.large_file
+            .read(cx)
+            .abs_path()
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+            .into()
+    }
+
+    fn tab_icon(&self, _: &Window, cx: &App) -> Option<Icon> {
+        let path = self.large_file.read(cx).abs_path();
+        ItemSettings::get_global(cx)
+            .file_icons
+            .then(|| FileIcons::get_icon(path, cx))
+            .flatten()
+            .map(Icon::from_path)
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("Large File Viewer Opened")
+    }
+
+    fn breadcrumb_location(&self, cx: &App) -> ToolbarItemLocation {
+        if EditorSettings::get_global(cx).toolbar.breadcrumbs {
+            ToolbarItemLocation::PrimaryLeft
+        } else {
+            ToolbarItemLocation::Hidden
+        }
+    }
+
+    fn breadcrumbs(&self, cx: &App) -> Option<(Vec<HighlightedText>, Option<gpui::Font>)> {
+        let large_file = self.large_file.read(cx);
+        let project_path = project::ProjectItem::project_path(large_file, cx)?;
+        let text = project_path
+            .path
+            .display(self.project.read(cx).path_style(cx))
+            .to_string();
+        Some((
+            vec![HighlightedText {
+                text: text.into(),
+                highlights: vec![],
+            }],
+            Some(ThemeSettings::get_global(cx).buffer_font.clone()),
+        ))
+    }
+
+    fn as_searchable(
+        &self,
+        handle: &Entity<Self>,
+        _: &App,
+    ) -> Option<Box<dyn workspace::searchable::SearchableItemHandle>> {
+        Some(Box::new(handle.clone()))
+    }
+
+    fn added_to_workspace(
+        &mut self,
+        workspace: &mut Workspace,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) {
+        self.workspace = Some(workspace.weak_handle());
+    }
+
+    fn buffer_kind(&self, _: &App) -> workspace::item::ItemBufferKind {
+        workspace::item::ItemBufferKind::Singleton
+    }
+}
+
+impl SearchableItem for LargeFileView {
+    type Match = LineMatch;
+
+    fn supported_options(&self) -> SearchOptions {
+        SearchOptions {
+            case: true,
+            word: true,
+            regex: true,
+            replacement: false,
+            selection: false,
+            find_in_results: false,
+        }
+    }
+
+    fn clear_matches(&mut self, _window: &mut Window, cx: &mut Context<Self>) {
+        self.matches.clear();
+        self.active_match = None;
+        cx.notify();
+    }
+
+    fn update_matches(
+        &mut self,
+        matches: &[Self::Match],
+        active_match_index: Option<usize>,
+        _token: SearchToken,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.matches = matches.to_vec();
+        self.active_match = active_match_index;
+        cx.notify();
+    }
+
+    fn query_suggestion(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> String {
+        String::new()
+    }
+
+    fn activate_match(
+        &mut self,
+        index: usize,
+        matches: &[Self::Match],
+        _token: SearchToken,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(found) = matches.get(index) {
+            self.active_match = Some(index);
+            self.go_to_row(found.row, cx);
+        }
+    }
+
+    fn select_matches(
+        &mut self,
+        _matches: &[Self::Match],
+        _token: SearchToken,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) {
+        // Lines can't be selected in large file mode.
+    }
+
+    fn replace(
+        &mut self,
+        _: &Self::Match,
+        _: &SearchQuery,
+        _token: SearchToken,
+        _window: &mut Window,
+        _: &mut Context<Self>,
+    ) {
+        // Large files are read-only.
+    }
+
+    fn find_matches(
+        &mut self,
+        query: Arc<SearchQuery>,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<Vec<Self::Match>> {
+        let Some(regex) = regex_for_query(&query).log_err() else {
+            return Task::ready(Vec::new());
+        };
+        let search = self.large_file.read(cx).search(regex, cx);
+        cx.background_spawn(async move { search.await.log_err().unwrap_or_default() })
+    }
+
+    fn active_match_index(
+        &mut self,
+        direction: Direction,
+        matches: &[Self::Match],
+        _token: SearchToken,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) -> Option<usize> {
+        if matches.is_empty() {
+            return None;
+        }
+        let row = self.selected_row.unwrap_or(0);
+        let ix = match direction {
+            Direction::Next => matches.partition_point(|found| found.row < row),
+            Direction::Prev => matches
+                .partition_point(|found| found.row <= row)
+                .saturating_sub(1),
+        };
+        Some(ix.min(matches.len() - 1))
+    }
+}
+
+impl ProjectItem for LargeFileView {
+    type Item = LargeFile;
+
+    fn for_project_item(
+        project: Entity<Project>,
+        _: Option<&Pane>,
+        item: Entity<Self::Item>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new(item, project, window, cx)
+    }
+
+    fn for_broken_project_item(
+        abs_path: &Path,
+        is_local: bool,
+        e: &anyhow::Error,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<InvalidItemView>
+    where
+        Self: Sized,
+    {
+        Some(InvalidItemView::new(abs_path, is_local, e, window, cx))
+    }
+}
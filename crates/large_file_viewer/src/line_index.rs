@@ -0,0 +1,305 @@
+use std::ops::Range;
+
+use anyhow::Result;
+
+/// The number of lines between two consecutive checkpoints of a [`LineIndex`].
+const LINES_PER_CHECKPOINT: usize = 128;
+
+/// How many bytes are read from the file at once.
+pub const READ_CHUNK_LEN: u64 = worktree::MAX_FILE_RANGE_LEN;
+
+/// Lines longer than this many bytes are truncated when read or searched.
+pub const MAX_LINE_LEN: usize = 16 * 1024;
+
+/// A sparse index of the line starts in a file that is too large to load, recording
+/// the offset of every [`LINES_PER_CHECKPOINT`]th line. Any line can then be read by
+/// seeking to the preceding checkpoint and skipping a bounded number of lines.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineIndex {
+    checkpoints: Vec<u64>,
+    line_count: usize,
+    indexed_len: u64,
+}
+
+impl Default for LineIndex {
+    fn default() -> Self {
+        Self {
+            checkpoints: vec![0],
+            line_count: 1,
+            indexed_len: 0,
+        }
+    }
+}
+
+impl LineIndex {
+    /// The number of lines in the indexed part of the file. Like in a buffer, a
+    /// trailing newline starts an additional empty line.
+    pub fn line_count(&self) -> usize {
+        self.line_count
+    }
+
+    /// The number of bytes of the file that have been indexed.
+    pub fn indexed_len(&self) -> u64 {
+        self.indexed_len
+    }
+
+    /// Returns an empty index that continues where this one ends, so that more of the
+    /// file can be indexed in the background and merged back with [`Self::append`].
+    pub fn continuation(&self) -> Self {
+        Self {
+            checkpoints: Vec::new(),
+            line_count: self.line_count,
+            indexed_len: self.indexed_len,
+        }
+    }
+
+    pub fn append(&mut self, continuation: Self) {
+        debug_assert!(continuation.indexed_len >= self.indexed_len);
+        self.checkpoints.extend(continuation.checkpoints);
+        self.line_count = continuation.line_count;
+        self.indexed_len = continuation.indexed_len;
+    }
+
+    /// Indexes the given bytes, which follow the ones indexed so far.
+    pub fn push(&mut self, bytes: &[u8]) {
+        for (ix, _) in bytes.iter().enumerate().filter(|(_, byte)| **byte == b'\n') {
+            if self.line_count % LINES_PER_CHECKPOINT == 0 {
+                self.checkpoints.push(self.indexed_len + ix as u64 + 1);
+            }
+            self.line_count += 1;
+        }
+        self.indexed_len += bytes.len() as u64;
+    }
+
+    /// Indexes up to `max_len` bytes past [`Self::indexed_len`], read from the file
+    /// with `read`. Returns whether the end of the file was reached.
+    pub async fn read_from<F>(
+        &mut self,
+        max_len: u64,
+        mut read: impl FnMut(Range<u64>) -> F,
+    ) -> Result<bool>
+    where
+        F: Future<Output = Result<Vec<u8>>>,
+    {
+        let end = self.indexed_len.saturating_add(max_len);
+        while self.indexed_len < end {
+            let range = self.indexed_len..end.min(self.indexed_len + READ_CHUNK_LEN);
+            let requested_len = range.end - range.start;
+            let bytes = read(range).await?;
+            self.push(&bytes);
+            if (bytes.len() as u64) < requested_len {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Reads the given rows of the file with `read`, clamped to the indexed lines.
+    pub async fn read_lines<F>(
+        &self,
+        rows: Range<usize>,
+        read: impl FnMut(Range<u64>) -> F,
+    ) -> Result<Vec<String>>
+    where
+        F: Future<Output = Result<Vec<u8>>>,
+    {
+        let end = rows.end.min(self.line_count);
+        let start = rows.start.min(end);
+        let checkpoint_ix = (start / LINES_PER_CHECKPOINT).min(self.checkpoints.len() - 1);
+        let mut lines = LineReader::new(self.checkpoints[checkpoint_ix], read);
+        for _ in checkpoint_ix * LINES_PER_CHECKPOINT..start {
+            lines.next_line().await?;
+        }
+        let mut result = Vec::with_capacity(end - start);
+        for _ in start..end {
+            match lines.next_line().await? {
+                Some(line) => result.push(line.to_string()),
+                None => break,
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Reads the lines of a file from an offset on, a chunk at a time, truncating lines
+/// longer than [`MAX_LINE_LEN`] and replacing invalid UTF-8.
+pub struct LineReader<R> {
+    read: R,
+    /// The offset in the file of the first byte past `bytes`.
+    offset: u64,
+    /// The bytes read from the file that haven't been returned as lines yet.
+    bytes: Vec<u8>,
+    /// Where the next line starts within `bytes`.
+    line_start: usize,
+    /// Where the next line ends within `bytes`, once all of it has been read.
+    line_end: Option<usize>,
+    /// Whether bytes of the next line past [`MAX_LINE_LEN`] were dropped.
+    truncated: bool,
+    end_of_file: bool,
+    done: bool,
+    line: String,
+}
+
+impl<R, F> LineReader<R>
+where
+    R: FnMut(Range<u64>) -> F,
+    F: Future<Output = Result<Vec<u8>>>,
+{
+    /// Returns a reader of the lines starting at `offset`, which reads the bytes of
+    /// the file within a range with `read`, getting fewer of them past its end.
+    pub fn new(offset: u64, read: R) -> Self {
+        Self {
+            read,
+            offset,
+            bytes: Vec::new(),
+            line_start: 0,
+            line_end: None,
+            truncated: false,
+            end_of_file: false,
+            done: false,
+            line: String::new(),
+        }
+    }
+
+    /// Returns the next line without its line ending, or `None` past the last line.
+    pub async fn next_line(&mut self) -> Result<Option<&str>> {
+        while !self.find_line_end() {
+            let range = self.offset..self.offset + READ_CHUNK_LEN;
+            let bytes = (self.read)(range).await?;
+            self.offset += bytes.len() as u64;
+            self.end_of_file = (bytes.len() as u64) < READ_CHUNK_LEN;
+            self.bytes.drain(..self.line_start);
+            self.line_start = 0;
+            self.bytes.extend_from_slice(&bytes);
+        }
+        let Some(line_end) = self.line_end.take() else {
+            return Ok(None);
+        };
+
+        let line = &self.bytes[self.line_start..line_end];
+        let truncated = self.truncated || line.len() > MAX_LINE_LEN;
+        let mut line = &line[..line.len().min(MAX_LINE_LEN)];
+        if let [rest @ .., b'\r'] = line {
+            line = rest;
+        }
+        self.line.clear();
+        self.line.push_str(&String::from_utf8_lossy(line));
+        if truncated {
+            self.line.push('…');
+        }
+        self.truncated = false;
+        if line_end == self.bytes.len() {
+            self.done = true;
+        } else {
+            self.line_start = line_end + 1;
+        }
+        Ok(Some(&self.line))
+    }
+
+    /// Looks for the end of the next line in the bytes read so far, returning
+    /// whether it was found or there are no more lines.
+    fn find_line_end(&mut self) -> bool {
+        if self.done || self.line_end.is_some() {
+            return true;
+        }
+        let rest = &self.bytes[self.line_start..];
+        if let Some(newline) = rest.iter().position(|byte| *byte == b'\n') {
+            self.line_end = Some(self.line_start + newline);
+        } else if self.end_of_file {
+            self.line_end = Some(self.bytes.len());
+        } else {
+            // Only the start of a long line is kept while reading the rest of it.
+            if rest.len() > MAX_LINE_LEN {
+                self.bytes.truncate(self.line_start + MAX_LINE_LEN);
+                self.truncated = true;
+            }
+            return false;
+        }
+        true
+    }
+}
+
+/// Returns a function reading ranges of the given bytes, as of a file.
+#[cfg(test)]
+pub fn read_bytes(
+    bytes: &[u8],
+) -> impl FnMut(Range<u64>) -> std::future::Ready<Result<Vec<u8>>> + '_ {
+    |range| {
+        let len = bytes.len() as u64;
+        let start = range.start.min(len) as usize;
+        let end = range.end.min(len) as usize;
+        std::future::ready(Ok(bytes[start..end].to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(bytes: &[u8]) -> LineIndex {
+        let mut index = LineIndex::default();
+        assert!(smol::block_on(index.read_from(u64::MAX, read_bytes(bytes))).unwrap());
+        index
+    }
+
+    fn read_lines(index: &LineIndex, bytes: &[u8], rows: Range<usize>) -> Vec<String> {
+        smol::block_on(index.read_lines(rows, read_bytes(bytes))).unwrap()
+    }
+
+    #[test]
+    fn test_line_index() {
+        let text = (0..1000)
+            .map(|row| format!("line {row}\r\n"))
+            .collect::<String>();
+        let index = build(text.as_bytes());
+        assert_eq!(index.line_count(), 1001);
+        assert_eq!(index.indexed_len(), text.len() as u64);
+
+        let file = text.as_bytes();
+        assert_eq!(read_lines(&index, file, 0..2), ["line 0", "line 1"]);
+        assert_eq!(
+            read_lines(&index, file, 127..130),
+            ["line 127", "line 128", "line 129"]
+        );
+        assert_eq!(
+            read_lines(&index, file, 998..2000),
+            ["line 998", "line 999", ""]
+        );
+
+        // Indexing in pieces yields the same index as indexing at once.
+        let mut incremental = LineIndex::default();
+        for piece in text.as_bytes().chunks(7) {
+            let mut continuation = incremental.continuation();
+            continuation.push(piece);
+            incremental.append(continuation);
+        }
+        assert_eq!(incremental, index);
+
+        let mut batched = LineIndex::default();
+        while !smol::block_on(batched.read_from(1000, read_bytes(file))).unwrap() {}
+        assert_eq!(batched, index);
+    }
+
+    #[test]
+    fn test_long_and_invalid_lines() {
+        let mut bytes = vec![b'a'; MAX_LINE_LEN * 3];
+        bytes.extend_from_slice(b"\nvalid\n\xff\xfe\nlast");
+        let index = build(&bytes);
+        assert_eq!(index.line_count(), 4);
+
+        let lines = read_lines(&index, &bytes, 0..4);
+        assert_eq!(lines[0].len(), MAX_LINE_LEN + '…'.len_utf8());
+        assert!(lines[0].ends_with('…'));
+        assert_eq!(lines[1], "valid");
+        assert_eq!(lines[2], "\u{fffd}\u{fffd}");
+        assert_eq!(lines[3], "last");
+
+        // Lines spanning several chunks are truncated too.
+        let mut bytes = vec![b'b'; READ_CHUNK_LEN as usize * 2 + 1];
+        bytes.extend_from_slice(b"\nnext\n");
+        let index = build(&bytes);
+        let lines = read_lines(&index, &bytes, 0..3);
+        assert_eq!(lines[0], format!("{}…", "b".repeat(MAX_LINE_LEN)));
+        assert_eq!(lines[1..], ["next", ""]);
+    }
+}
@@ -0,0 +1,97 @@
+use std::ops::Range;
+
+use anyhow::Result;
+use project::search::SearchQuery;
+use regex::{Regex, RegexBuilder};
+
+use crate::line_index::LineReader;
+
+/// Searching stops after this many matches, to keep the memory used by the results
+/// bounded regardless of the file's size.
+pub const MAX_MATCHES: usize = 10_000;
+
+/// A match within a line, as returned by [`LineReader::next_line`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineMatch {
+    pub row: usize,
+    /// The byte range of the match within the line.
+    pub range: Range<usize>,
+}
+
+pub fn regex_for_query(query: &SearchQuery) -> Result<Regex> {
+    let mut pattern = if query.is_regex() {
+        query.as_str().to_string()
+    } else {
+        regex::escape(query.as_str())
+    };
+    if query.whole_word() {
+        pattern = format!(r"\b(?:{pattern})\b");
+    }
+    Ok(RegexBuilder::new(&pattern)
+        .case_insensitive(!query.case_sensitive())
+        .build()?)
+}
+
+/// Finds the matches of `regex` within each line of the file read with `read`,
+/// yielding to the executor periodically so that the search stops when its task
+/// is dropped.
+pub async fn search_lines<F>(
+    read: impl FnMut(Range<u64>) -> F,
+    regex: &Regex,
+) -> Result<Vec<LineMatch>>
+where
+    F: Future<Output = Result<Vec<u8>>>,
+{
+    let mut lines = LineReader::new(0, read);
+    let mut matches = Vec::new();
+    let mut row = 0;
+    while let Some(line) = lines.next_line().await? {
+        for found in regex.find_iter(line) {
+            matches.push(LineMatch {
+                row,
+                range: found.range(),
+            });
+            if matches.len() == MAX_MATCHES {
+                return Ok(matches);
+            }
+        }
+        row += 1;
+        if row % 4096 == 0 {
+            smol::future::yield_now().await;
+        }
+    }
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_index::read_bytes;
+
+    #[test]
+    fn test_search_lines() {
+        let text = "error: one\ninfo\nERROR: two error\n";
+        let regex = RegexBuilder::new("error")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+        let matches = smol::block_on(search_lines(read_bytes(text.as_bytes()), &regex)).unwrap();
+        assert_eq!(
+            matches,
+            [
+                LineMatch {
+                    row: 0,
+                    range: 0..5
+                },
+                LineMatch {
+                    row: 2,
+                    range: 0..5
+                },
+                LineMatch {
+                    row: 2,
+                    range: 11..16
+                },
+            ]
+        );
+    }
+}
@@ -381,6 +381,14 @@ pub fn debug_adapters_dir() -> &'static PathBuf {
     DEBUG_ADAPTERS_DIR.get_or_init(|| data_dir().join("debug_adapters"))
 }
 
+/// Returns the path to the debug recordings directory.
+///
+/// This is where the stops of debug sessions are recorded to, to be replayed later.
+pub fn debug_recordings_dir() -> &'static PathBuf {
+    static DEBUG_RECORDINGS_DIR: OnceLock<PathBuf> = OnceLock::new();
+    DEBUG_RECORDINGS_DIR.get_or_init(|| data_dir().join("debug_recordings"))
+}
+
 /// Returns the path to the external agents directory
 ///
 /// This is where agent servers are downloaded to
@@ -413,6 +421,12 @@ pub fn devcontainer_dir() -> &'static PathBuf {
     DEVCONTAINER_DIR.get_or_init(|| data_dir().join("devcontainer"))
 }
 
+/// Returns the path to the directory where the terminal's shell integration scripts are written.
+pub fn shell_integration_dir() -> &'static PathBuf {
+    static SHELL_INTEGRATION_DIR: OnceLock<PathBuf> = OnceLock::new();
+    SHELL_INTEGRATION_DIR.get_or_init(|| data_dir().join("shell_integration"))
+}
+
 /// Returns the relative path to a `.zed` folder within a project.
 pub fn local_settings_folder_name() -> &'static str {
     ".zed"
@@ -467,6 +481,14 @@ pub fn local_vscode_launch_file_relative_path() -> &'static RelPath {
     *CACHED
 }
 
+/// Returns the relative path to a `breakpoints.json` file within a project.
+/// .zed/breakpoints.json
+pub fn local_breakpoints_file_relative_path() -> &'static RelPath {
+    static CACHED: LazyLock<&'static RelPath> =
+        LazyLock::new(|| RelPath::unix(".zed/breakpoints.json").unwrap());
+    *CACHED
+}
+
 pub fn user_ssh_config_file() -> PathBuf {
     home_dir().join(".ssh/config")
 }
@@ -11,10 +11,10 @@ use gpui::{
     App, AppContext as _, AsyncApp, Context, Entity, EventEmitter, Subscription, Task, WeakEntity,
 };
 use language::{
-    Buffer, BufferEvent, Capability, DiskState, File as _, Language, Operation,
+    Anchor, Buffer, BufferEvent, Capability, DiskState, File as _, Language, Operation,
     proto::{
-        deserialize_line_ending, deserialize_version, serialize_line_ending, serialize_version,
-        split_operations,
+        deserialize_anchor, deserialize_line_ending, deserialize_version, serialize_anchor,
+        serialize_line_ending, serialize_version, split_operations,
     },
 };
 use rpc::{
@@ -23,7 +23,7 @@ use rpc::{
 };
 
 use settings::Settings;
-use std::{io, sync::Arc, time::Instant};
+use std::{io, ops::Range, sync::Arc, time::Instant};
 use text::{BufferId, ReplicaId};
 use util::{ResultExt as _, TryFutureExt, debug_panic, maybe, rel_path::RelPath};
 use worktree::{File, PathChange, ProjectEntryId, Worktree, WorktreeId, WorktreeSettings};
@@ -106,6 +106,54 @@ impl PartialEq for ProjectTransaction {
     }
 }
 
+/// Edits to several buffers that haven't been applied yet, such as the ones of
+/// a rename that is previewed before being applied.
+#[derive(Default, Debug, Clone)]
+pub struct ProjectEdits(pub Vec<(Entity<Buffer>, Vec<(Range<Anchor>, Arc<str>)>)>);
+
+impl ProjectEdits {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Adds edits to a buffer, after the ones already added for it.
+    pub fn push(&mut self, buffer: Entity<Buffer>, edits: Vec<(Range<Anchor>, Arc<str>)>) {
+        if edits.is_empty() {
+            return;
+        }
+        match self
+            .0
+            .iter_mut()
+            .find(|(existing_buffer, _)| *existing_buffer == buffer)
+        {
+            Some((_, existing_edits)) => existing_edits.extend(edits),
+            None => self.0.push((buffer, edits)),
+        }
+    }
+
+    /// Applies the edits of each buffer as a single transaction, which is
+    /// pushed to the buffer's history.
+    pub fn apply(self, cx: &mut App) -> ProjectTransaction {
+        let mut project_transaction = ProjectTransaction::default();
+        for (buffer, edits) in self.0 {
+            let transaction = buffer.update(cx, |buffer, cx| {
+                buffer.finalize_last_transaction();
+                buffer.start_transaction();
+                for (range, text) in edits {
+                    buffer.edit([(range, text)], None, cx);
+                }
+                let transaction_id = buffer.end_transaction(cx)?;
+                buffer.finalize_last_transaction();
+                buffer.get_transaction(transaction_id).cloned()
+            });
+            if let Some(transaction) = transaction {
+                project_transaction.0.insert(buffer, transaction);
+            }
+        }
+        project_transaction
+    }
+}
+
 impl EventEmitter<BufferStoreEvent> for BufferStore {}
 
 impl RemoteBufferStore {
@@ -296,6 +344,49 @@ impl RemoteBufferStore {
         })
     }
 
+    pub fn deserialize_project_edits(
+        &self,
+        message: Vec<proto::BufferEdits>,
+        cx: &mut Context<BufferStore>,
+    ) -> Task<Result<ProjectEdits>> {
+        cx.spawn(async move |this, cx| {
+            let mut project_edits = ProjectEdits::default();
+            for buffer_edits in message {
+                let buffer_id = BufferId::new(buffer_edits.buffer_id)?;
+                let buffer = this
+                    .update(cx, |this, cx| this.wait_for_remote_buffer(buffer_id, cx))?
+                    .await?;
+                let edits = buffer_edits
+                    .edits
+                    .into_iter()
+                    .map(|edit| {
+                        let start = edit
+                            .start
+                            .and_then(deserialize_anchor)
+                            .context("invalid edit start")?;
+                        let end = edit
+                            .end
+                            .and_then(deserialize_anchor)
+                            .context("invalid edit end")?;
+                        anyhow::Ok((start..end, Arc::from(edit.new_text)))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                buffer
+                    .update(cx, |buffer, _| {
+                        buffer.wait_for_anchors(
+                            edits
+                                .iter()
+                                .flat_map(|(range, _)| [range.start, range.end])
+                                .collect::<Vec<_>>(),
+                        )
+                    })
+                    .await?;
+                project_edits.push(buffer, edits);
+            }
+            Ok(project_edits)
+        })
+    }
+
     fn open_buffer(
         &self,
         path: Arc<RelPath>,
@@ -1671,6 +1762,19 @@ impl BufferStore {
         }
     }
 
+    pub fn deserialize_project_edits(
+        &mut self,
+        message: Vec<proto::BufferEdits>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<ProjectEdits>> {
+        if let Some(this) = self.as_remote_mut() {
+            this.deserialize_project_edits(message, cx)
+        } else {
+            debug_panic!("not a remote buffer store");
+            Task::ready(Err(anyhow!("not a remote buffer store")))
+        }
+    }
+
     pub fn wait_for_remote_buffer(
         &mut self,
         id: BufferId,
@@ -1707,6 +1811,33 @@ impl BufferStore {
         serialized_transaction
     }
 
+    pub fn serialize_project_edits_for_peer(
+        &mut self,
+        project_edits: ProjectEdits,
+        peer_id: proto::PeerId,
+        cx: &mut Context<Self>,
+    ) -> Vec<proto::BufferEdits> {
+        project_edits
+            .0
+            .into_iter()
+            .map(|(buffer, edits)| {
+                self.create_buffer_for_peer(&buffer, peer_id, cx)
+                    .detach_and_log_err(cx);
+                proto::BufferEdits {
+                    buffer_id: buffer.read(cx).remote_id().into(),
+                    edits: edits
+                        .into_iter()
+                        .map(|(range, new_text)| proto::AnchoredEdit {
+                            start: Some(serialize_anchor(&range.start)),
+                            end: Some(serialize_anchor(&range.end)),
+                            new_text: new_text.to_string(),
+                        })
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+
     pub(crate) fn register_project_search_result_handle(
         &mut self,
     ) -> (u64, smol::channel::Receiver<BufferId>) {
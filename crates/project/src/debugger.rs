@@ -16,6 +16,7 @@ pub mod dap_command;
 pub mod dap_store;
 pub mod locators;
 pub mod memory;
+pub mod recording;
 pub mod session;
 
 #[cfg(any(feature = "test-support", test))]
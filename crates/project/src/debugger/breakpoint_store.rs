@@ -16,8 +16,10 @@ use rpc::{
     proto::{self},
 };
 use std::{hash::Hash, ops::Range, path::Path, sync::Arc, u32};
+use task::{BreakpointSet, SavedBreakpoint};
 use text::{Point, PointUtf16};
-use util::maybe;
+use util::{maybe, rel_path::RelPath};
+use worktree::WorktreeId;
 
 use crate::{ProjectPath, buffer_store::BufferStore, worktree_store::WorktreeStore};
 
@@ -881,6 +883,154 @@ impl BreakpointStore {
         }
     }
 
+    /// Returns the breakpoints within the worktree as a set named `name`.
+    pub fn breakpoint_set(&self, name: String, worktree_id: WorktreeId, cx: &App) -> BreakpointSet {
+        let mut breakpoints = Vec::new();
+        if let Some(worktree) = self
+            .worktree_store
+            .read(cx)
+            .worktree_for_id(worktree_id, cx)
+        {
+            let worktree = worktree.read(cx);
+            for (path, source_breakpoints) in self.all_source_breakpoints(cx) {
+                let Some(path) = path
+                    .strip_prefix(worktree.abs_path())
+                    .ok()
+                    .and_then(|path| RelPath::new(path, worktree.path_style()).ok())
+                else {
+                    continue;
+                };
+                breakpoints.extend(source_breakpoints.into_iter().map(|breakpoint| {
+                    SavedBreakpoint {
+                        path: path.as_unix_str().to_string(),
+                        line: breakpoint.row + 1,
+                        enabled: breakpoint.state.is_enabled(),
+                        condition: breakpoint.condition.map(|condition| condition.to_string()),
+                        hit_condition: breakpoint
+                            .hit_condition
+                            .map(|hit_condition| hit_condition.to_string()),
+                        log_message: breakpoint.message.map(|message| message.to_string()),
+                    }
+                }));
+            }
+        }
+        breakpoints.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+        BreakpointSet { name, breakpoints }
+    }
+
+    /// Adds the breakpoints of the set to the worktree, replacing the ones
+    /// already on their lines.
+    pub fn load_breakpoint_set(
+        &self,
+        worktree_id: WorktreeId,
+        set: &BreakpointSet,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let buffer_store = self.buffer_store.downgrade();
+        let breakpoints_by_path = set
+            .breakpoints
+            .iter()
+            .cloned()
+            .into_group_map_by(|breakpoint| breakpoint.path.clone());
+        cx.spawn(async move |this, cx| {
+            for (path, breakpoints) in breakpoints_by_path {
+                let path = RelPath::unix(&path)
+                    .with_context(|| format!("invalid breakpoint path {path:?}"))?
+                    .into_arc();
+                let buffer = buffer_store
+                    .update(cx, |buffer_store, cx| {
+                        buffer_store.open_buffer(ProjectPath { worktree_id, path }, cx)
+                    })?
+                    .await?;
+                this.update(cx, |this, cx| {
+                    let Some(abs_path) = Self::abs_path_from_buffer(&buffer, cx) else {
+                        return;
+                    };
+                    let snapshot = buffer.read(cx).snapshot();
+                    for saved_breakpoint in breakpoints {
+                        let row = saved_breakpoint.line.saturating_sub(1);
+                        if row > snapshot.max_point().row {
+                            log::error!("skipping a saved breakpoint that's out of range");
+                            continue;
+                        }
+                        let breakpoint = Breakpoint::from(&saved_breakpoint);
+                        if let Some((_, existing)) = this.breakpoint_at_row(&abs_path, row, cx) {
+                            if existing.bp == breakpoint {
+                                continue;
+                            }
+                            this.toggle_breakpoint(
+                                buffer.clone(),
+                                existing,
+                                BreakpointEditAction::Toggle,
+                                cx,
+                            );
+                        }
+                        this.toggle_breakpoint(
+                            buffer.clone(),
+                            BreakpointWithPosition {
+                                position: snapshot.anchor_after(Point::new(row, 0)),
+                                bp: breakpoint,
+                            },
+                            BreakpointEditAction::Toggle,
+                            cx,
+                        );
+                    }
+                })?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Returns whether every line of the set has a breakpoint in the worktree.
+    ///
+    /// Breakpoints are matched by their line only, so a loaded set stays
+    /// loaded after its breakpoints are edited.
+    pub fn is_breakpoint_set_loaded(
+        &self,
+        worktree_id: WorktreeId,
+        set: &BreakpointSet,
+        cx: &App,
+    ) -> bool {
+        !set.breakpoints.is_empty()
+            && set.breakpoints.iter().all(|saved_breakpoint| {
+                self.saved_breakpoint(worktree_id, saved_breakpoint, cx)
+                    .is_some()
+            })
+    }
+
+    /// Removes the breakpoints on the lines of the set from the worktree.
+    pub fn unload_breakpoint_set(
+        &mut self,
+        worktree_id: WorktreeId,
+        set: &BreakpointSet,
+        cx: &mut Context<Self>,
+    ) {
+        for saved_breakpoint in &set.breakpoints {
+            if let Some((buffer, breakpoint)) =
+                self.saved_breakpoint(worktree_id, saved_breakpoint, cx)
+            {
+                self.toggle_breakpoint(buffer, breakpoint, BreakpointEditAction::Toggle, cx);
+            }
+        }
+    }
+
+    /// Returns the breakpoint on the line of `saved_breakpoint`, whatever its
+    /// state, condition and log message are.
+    fn saved_breakpoint(
+        &self,
+        worktree_id: WorktreeId,
+        saved_breakpoint: &SavedBreakpoint,
+        cx: &App,
+    ) -> Option<(Entity<Buffer>, BreakpointWithPosition)> {
+        let worktree = self
+            .worktree_store
+            .read(cx)
+            .worktree_for_id(worktree_id, cx)?;
+        let path = RelPath::unix(&saved_breakpoint.path).ok()?;
+        let abs_path = worktree.read(cx).absolutize(path);
+        self.breakpoint_at_row(&abs_path, saved_breakpoint.line.saturating_sub(1), cx)
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     pub(crate) fn breakpoint_paths(&self) -> Vec<Arc<Path>> {
         self.breakpoints.keys().cloned().collect()
@@ -1032,6 +1182,21 @@ impl Breakpoint {
     }
 }
 
+impl From<&SavedBreakpoint> for Breakpoint {
+    fn from(breakpoint: &SavedBreakpoint) -> Self {
+        Self {
+            message: breakpoint.log_message.as_deref().map(Into::into),
+            hit_condition: breakpoint.hit_condition.as_deref().map(Into::into),
+            condition: breakpoint.condition.as_deref().map(Into::into),
+            state: if breakpoint.enabled {
+                BreakpointState::Enabled
+            } else {
+                BreakpointState::Disabled
+            },
+        }
+    }
+}
+
 /// Breakpoint for location within source code.
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct SourceBreakpoint {
@@ -0,0 +1,130 @@
+//! Recordings of the stops of a debug session, written to a file so that they
+//! can be stepped through after the session has ended.
+
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+
+use dap::{OutputEvent, StoppedEvent, VariableReference};
+use fs::Fs;
+use futures::{StreamExt as _, channel::mpsc};
+use gpui::{App, AppContext as _, Task};
+use serde::{Deserialize, Serialize};
+use util::ResultExt as _;
+
+use super::session::OutputToken;
+
+/// The stops of a debug session, in the order they happened.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionRecording {
+    pub label: Option<String>,
+    pub adapter: String,
+    pub stops: Vec<RecordedStop>,
+}
+
+/// The state of the debuggee at one stop, as far as it was inspected while
+/// the session was stopped there.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedStop {
+    /// The event the debug adapter reported the stop with, if it was recorded.
+    pub event: Option<StoppedEvent>,
+    pub threads: Vec<RecordedThread>,
+    /// The variables that were fetched, such as those of expanded scopes and
+    /// variables, by their reference.
+    pub variables: BTreeMap<VariableReference, Vec<dap::Variable>>,
+    /// The console output between the previous recorded stop and this one.
+    pub output: Vec<OutputEvent>,
+}
+
+impl RecordedStop {
+    pub fn variables(&self, variables_reference: VariableReference) -> &[dap::Variable] {
+        self.variables
+            .get(&variables_reference)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedThread {
+    pub thread: dap::Thread,
+    pub stack_frames: Vec<RecordedStackFrame>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedStackFrame {
+    pub frame: dap::StackFrame,
+    pub scopes: Vec<dap::Scope>,
+}
+
+pub(super) struct PendingStop {
+    pub(super) event: Option<StoppedEvent>,
+    pub(super) output: Vec<OutputEvent>,
+}
+
+/// Writes the stops of a session to a file as they're recorded.
+///
+/// The recording is owned by a background task, which serializes and writes
+/// it after each stop, so that long recordings don't block the main thread.
+pub(super) struct SessionRecorder {
+    pub(super) path: PathBuf,
+    stops_tx: mpsc::UnboundedSender<RecordedStop>,
+    /// The console output up to this token has been recorded.
+    pub(super) output_token: OutputToken,
+    /// The stop the session is at, which is recorded once it's left.
+    pub(super) pending_stop: Option<PendingStop>,
+    write_task: Task<()>,
+}
+
+impl SessionRecorder {
+    pub(super) fn new(
+        path: PathBuf,
+        fs: Arc<dyn Fs>,
+        label: Option<String>,
+        adapter: String,
+        cx: &App,
+    ) -> Self {
+        let (stops_tx, mut stops_rx) = mpsc::unbounded();
+        let mut recording = SessionRecording {
+            label,
+            adapter,
+            stops: Vec::new(),
+        };
+        let write_task = cx.background_spawn({
+            let path = path.clone();
+            async move {
+                if let Some(parent) = path.parent() {
+                    fs.create_dir(parent).await.log_err();
+                }
+                loop {
+                    if let Some(contents) = serde_json::to_string_pretty(&recording).log_err() {
+                        fs.atomic_write(path.clone(), contents).await.log_err();
+                    }
+                    let Some(stop) = stops_rx.next().await else {
+                        break;
+                    };
+                    recording.stops.push(stop);
+                    // Stops recorded during the previous write are written together.
+                    while let Ok(Some(stop)) = stops_rx.try_next() {
+                        recording.stops.push(stop);
+                    }
+                }
+            }
+        });
+        Self {
+            path,
+            stops_tx,
+            output_token: OutputToken(0),
+            pending_stop: None,
+            write_task,
+        }
+    }
+
+    pub(super) fn record(&self, stop: RecordedStop) {
+        self.stops_tx.unbounded_send(stop).ok();
+    }
+
+    /// Returns a task that completes once everything recorded has been written.
+    pub(super) fn finish(self) -> Task<()> {
+        drop(self.stops_tx);
+        self.write_task
+    }
+}
@@ -2,14 +2,18 @@ use super::breakpoint_store::{
     BreakpointStore, BreakpointStoreEvent, BreakpointUpdatedReason, SourceBreakpoint,
 };
 use super::dap_command::{
-    self, Attach, ConfigurationDone, ContinueCommand, DataBreakpointInfoCommand, DisconnectCommand,
-    EvaluateCommand, Initialize, Launch, LoadedSourcesCommand, LocalDapCommand, LocationsCommand,
-    ModulesCommand, NextCommand, PauseCommand, RestartCommand, RestartStackFrameCommand,
-    ScopesCommand, SetDataBreakpointsCommand, SetExceptionBreakpoints, SetVariableValueCommand,
-    StackTraceCommand, StepBackCommand, StepCommand, StepInCommand, StepOutCommand,
-    TerminateCommand, TerminateThreadsCommand, ThreadsCommand, VariablesCommand,
+    self, Attach, ConfigurationDone, ContinueCommand, DataBreakpointInfoCommand,
+    DisassembleCommand, DisconnectCommand, EvaluateCommand, Initialize, Launch,
+    LoadedSourcesCommand, LocalDapCommand, LocationsCommand, ModulesCommand, NextCommand,
+    PauseCommand, RestartCommand, RestartStackFrameCommand, ScopesCommand,
+    SetDataBreakpointsCommand, SetExceptionBreakpoints, SetInstructionBreakpointsCommand,
+    SetVariableValueCommand, StackTraceCommand, StepBackCommand, StepCommand, StepInCommand,
+    StepOutCommand, TerminateCommand, TerminateThreadsCommand, ThreadsCommand, VariablesCommand,
 };
 use super::dap_store::DapStore;
+use super::recording::{
+    PendingStop, RecordedStackFrame, RecordedStop, RecordedThread, SessionRecorder,
+};
 use crate::debugger::breakpoint_store::BreakpointSessionState;
 use crate::debugger::dap_command::{DataBreakpointContext, ReadMemory};
 use crate::debugger::memory::{self, Memory, MemoryIterator, MemoryPageBuilder, PageAddress};
@@ -31,6 +35,7 @@ use dap::{
     RunInTerminalRequestArguments, StackFramePresentationHint, StartDebuggingRequestArguments,
     StartDebuggingRequestArgumentsRequest, VariablePresentationHint, WriteMemoryArguments,
 };
+use fs::Fs;
 use futures::channel::mpsc::UnboundedSender;
 use futures::channel::{mpsc, oneshot};
 use futures::io::BufReader;
@@ -47,7 +52,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use smol::net::{TcpListener, TcpStream};
 use std::any::TypeId;
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::net::Ipv4Addr;
 use std::ops::RangeInclusive;
 use std::path::PathBuf;
@@ -662,6 +667,17 @@ impl ThreadStates {
             .insert(thread_id, ThreadStatus::Exited);
     }
 
+    fn stopped_thread(&self, preferred: Option<ThreadId>) -> Option<ThreadId> {
+        preferred
+            .filter(|thread_id| self.thread_state(*thread_id) == Some(ThreadStatus::Stopped))
+            .or_else(|| {
+                self.known_thread_states
+                    .iter()
+                    .find(|(_, status)| **status == ThreadStatus::Stopped)
+                    .map(|(thread_id, _)| *thread_id)
+            })
+    }
+
     fn any_stopped_thread(&self) -> bool {
         self.global_state
             .is_some_and(|state| state == ThreadStatus::Stopped)
@@ -709,6 +725,7 @@ pub struct Session {
     ignore_breakpoints: bool,
     exception_breakpoints: BTreeMap<String, (ExceptionBreakpointsFilter, IsEnabled)>,
     data_breakpoints: BTreeMap<String, DataBreakpointState>,
+    instruction_breakpoints: BTreeSet<String>,
     background_tasks: Vec<Task<()>>,
     restart_task: Option<Task<()>>,
     task_context: SharedTaskContext,
@@ -718,6 +735,7 @@ pub struct Session {
     node_runtime: Option<NodeRuntime>,
     http_client: Option<Arc<dyn HttpClient>>,
     companion_port: Option<u16>,
+    recorder: Option<SessionRecorder>,
 }
 
 trait CacheableCommand: Any + Send + Sync {
@@ -883,6 +901,7 @@ impl Session {
                 ignore_breakpoints: false,
                 breakpoint_store,
                 data_breakpoints: Default::default(),
+                instruction_breakpoints: Default::default(),
                 exception_breakpoints: Default::default(),
                 label,
                 adapter,
@@ -893,6 +912,7 @@ impl Session {
                 node_runtime,
                 http_client,
                 companion_port: None,
+                recorder: None,
             }
         })
     }
@@ -922,10 +942,10 @@ impl Session {
             let mut initialized_tx = Some(initialized_tx);
             while let Some(message) = message_rx.next().await {
                 if let Message::Event(event) = message {
-                    if let Events::Initialized(_) = *event {
-                        if let Some(tx) = initialized_tx.take() {
-                            tx.send(()).ok();
-                        }
+                    if let Events::Initialized(_) = *event
+                        && let Some(tx) = initialized_tx.take()
+                    {
+                        tx.send(()).ok();
                     } else {
                         let Ok(_) = this.update(cx, |session, cx| {
                             session.handle_dap_event(event, cx);
@@ -1461,8 +1481,103 @@ impl Session {
         self.selected_snapshot_index
     }
 
+    /// Starts writing each stop of the session to `path`, so that the stops
+    /// can be stepped through after the session has ended.
+    pub fn start_recording(&mut self, path: PathBuf, fs: Arc<dyn Fs>, cx: &App) {
+        self.recorder = Some(SessionRecorder::new(
+            path,
+            fs,
+            self.label.as_ref().map(ToString::to_string),
+            self.adapter.to_string(),
+            cx,
+        ));
+        if self.any_stopped_thread() {
+            self.begin_recorded_stop(None);
+        }
+    }
+
+    /// The file the session is being recorded to, if it's being recorded.
+    pub fn recording_path(&self) -> Option<&Path> {
+        self.recorder
+            .as_ref()
+            .map(|recorder| recorder.path.as_path())
+    }
+
+    /// Records the stop the session is at and stops recording. The returned
+    /// task completes once the recording has been written.
+    pub fn stop_recording(&mut self) -> Task<()> {
+        self.record_stop();
+        match self.recorder.take() {
+            Some(recorder) => recorder.finish(),
+            None => Task::ready(()),
+        }
+    }
+
+    fn begin_recorded_stop(&mut self, event: Option<StoppedEvent>) {
+        let Some(output_token) = self.recorder.as_ref().map(|recorder| recorder.output_token)
+        else {
+            return;
+        };
+        let (output, output_token) = self.output(output_token);
+        let output = output.cloned().collect();
+        if let Some(recorder) = &mut self.recorder {
+            recorder.output_token = output_token;
+            recorder.pending_stop = Some(PendingStop { event, output });
+        }
+    }
+
+    /// Records the state of the stop the session is at, once it's about to
+    /// be left, so that everything inspected during the stop is included.
+    fn record_stop(&mut self) {
+        let Some(PendingStop { event, output }) = self
+            .recorder
+            .as_mut()
+            .and_then(|recorder| recorder.pending_stop.take())
+        else {
+            return;
+        };
+
+        let snapshot = &self.active_snapshot;
+        let threads = snapshot
+            .threads
+            .values()
+            .map(|thread| RecordedThread {
+                thread: thread.dap.clone(),
+                stack_frames: thread
+                    .stack_frames
+                    .iter()
+                    .map(|stack_frame| RecordedStackFrame {
+                        frame: stack_frame.dap.clone(),
+                        // Scopes are stored with the stack frames of the snapshot.
+                        scopes: snapshot
+                            .stack_frames
+                            .get(&stack_frame.dap.id)
+                            .map(|stack_frame| stack_frame.scopes.clone())
+                            .unwrap_or_default(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        let stop = RecordedStop {
+            event,
+            threads,
+            variables: snapshot
+                .variables
+                .iter()
+                .map(|(reference, variables)| (*reference, variables.clone()))
+                .collect(),
+            output,
+        };
+
+        if let Some(recorder) = &self.recorder {
+            recorder.record(stop);
+        }
+    }
+
     fn handle_stopped_event(&mut self, event: StoppedEvent, cx: &mut Context<Self>) {
+        self.record_stop();
         self.push_to_history();
+        self.begin_recorded_stop(Some(event.clone()));
 
         self.state.stopped();
         // todo(debugger): Find a clean way to get around the clone
@@ -1520,10 +1635,12 @@ impl Session {
     pub(crate) fn handle_dap_event(&mut self, event: Box<Events>, cx: &mut Context<Self>) {
         match *event {
             Events::Initialized(_) => {
-                debug_assert!(
-                    false,
-                    "Initialized event should have been handled in LocalMode"
-                );
+                // The first `initialized` event is handled in LocalMode. Later ones come from
+                // adapters that re-initialize in place, e.g. on restart, and forget the
+                // instruction breakpoints, which aren't kept in the breakpoint store.
+                if !self.instruction_breakpoints.is_empty() {
+                    self.send_instruction_breakpoints(cx);
+                }
             }
             Events::Stopped(event) => self.handle_stopped_event(event, cx),
             Events::Continued(event) => {
@@ -1794,6 +1911,15 @@ impl Session {
         self.active_snapshot.thread_states.any_stopped_thread()
     }
 
+    /// Returns a thread that is known to be stopped, preferring `preferred`.
+    ///
+    /// When the adapter stopped all threads without saying which one caused it,
+    /// only `preferred` is known to be stopped, even though
+    /// [`Self::any_stopped_thread`] is true.
+    pub fn stopped_thread_id(&self, preferred: Option<ThreadId>) -> Option<ThreadId> {
+        self.active_snapshot.thread_states.stopped_thread(preferred)
+    }
+
     pub fn thread_status(&self, thread_id: ThreadId) -> ThreadStatus {
         self.active_snapshot.thread_states.thread_status(thread_id)
     }
@@ -1980,6 +2106,27 @@ impl Session {
         );
     }
 
+    /// Disassembles `instruction_count` instructions, starting `instruction_offset`
+    /// instructions away from the one at `memory_reference`. The offset may be
+    /// negative to disassemble the instructions before it.
+    pub fn disassemble(
+        &mut self,
+        memory_reference: String,
+        instruction_offset: i64,
+        instruction_count: u64,
+        cx: &mut Context<Self>,
+    ) -> Task<Option<Vec<dap::DisassembledInstruction>>> {
+        self.request(
+            DisassembleCommand {
+                memory_reference,
+                instruction_offset,
+                instruction_count,
+            },
+            |_, response, _| response.log_err(),
+            cx,
+        )
+    }
+
     pub fn ignore_breakpoints(&self) -> bool {
         self.ignore_breakpoints
     }
@@ -2086,6 +2233,41 @@ impl Session {
         self.send_data_breakpoints(cx);
     }
 
+    /// The instruction references, i.e. addresses, of the instruction breakpoints.
+    pub fn instruction_breakpoints(&self) -> &BTreeSet<String> {
+        &self.instruction_breakpoints
+    }
+
+    pub fn toggle_instruction_breakpoint(
+        &mut self,
+        instruction_reference: String,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.instruction_breakpoints.remove(&instruction_reference) {
+            self.instruction_breakpoints.insert(instruction_reference);
+        }
+        self.send_instruction_breakpoints(cx);
+        cx.notify();
+    }
+
+    fn send_instruction_breakpoints(&mut self, cx: &mut Context<Self>) {
+        if let Some(mode) = self.as_running() {
+            let breakpoints = self
+                .instruction_breakpoints
+                .iter()
+                .map(|instruction_reference| dap::InstructionBreakpoint {
+                    instruction_reference: instruction_reference.clone(),
+                    offset: None,
+                    condition: None,
+                    hit_condition: None,
+                    mode: None,
+                })
+                .collect();
+            let command = SetInstructionBreakpointsCommand { breakpoints };
+            mode.request(command).detach_and_log_err(cx);
+        }
+    }
+
     pub fn breakpoints_enabled(&self) -> bool {
         self.ignore_breakpoints
     }
@@ -2217,6 +2399,7 @@ impl Session {
         }
 
         self.is_session_terminated = true;
+        self.stop_recording().detach();
         self.active_snapshot.thread_states.exit_all_threads();
         cx.notify();
 
@@ -2812,6 +2995,58 @@ impl Session {
         })
     }
 
+    /// Requests the threads from the adapter, bypassing the cache that
+    /// [`Self::threads`] reads from.
+    pub fn request_threads(&self) -> Task<Result<Vec<dap::Thread>>> {
+        self.state.request_dap(ThreadsCommand)
+    }
+
+    /// Requests the stack frames of a thread from the adapter, bypassing the
+    /// cache that [`Self::stack_frames`] reads from.
+    pub fn request_stack_frames(&self, thread_id: ThreadId) -> Task<Result<Vec<dap::StackFrame>>> {
+        self.state.request_dap(StackTraceCommand {
+            thread_id: thread_id.0,
+            start_frame: None,
+            levels: None,
+        })
+    }
+
+    /// Requests the scopes of a stack frame from the adapter, bypassing the
+    /// cache that [`Self::scopes`] reads from.
+    pub fn request_scopes(&self, stack_frame_id: u64) -> Task<Result<Vec<dap::Scope>>> {
+        self.state.request_dap(ScopesCommand { stack_frame_id })
+    }
+
+    /// Requests the children of a variable or scope from the adapter, bypassing
+    /// the cache that [`Self::variables`] reads from.
+    pub fn request_variables(
+        &self,
+        variables_reference: VariableReference,
+    ) -> Task<Result<Vec<dap::Variable>>> {
+        self.state.request_dap(VariablesCommand {
+            variables_reference,
+            filter: None,
+            start: None,
+            count: None,
+            format: None,
+        })
+    }
+
+    /// Evaluates an expression in a stack frame and returns its result, without
+    /// echoing it to the console like [`Self::evaluate`] does.
+    pub fn request_evaluate(
+        &self,
+        expression: String,
+        frame_id: Option<u64>,
+    ) -> Task<Result<dap::EvaluateResponse>> {
+        self.state.request_dap(EvaluateCommand {
+            expression,
+            context: Some(EvaluateArgumentsContext::Repl),
+            frame_id,
+            source: None,
+        })
+    }
+
     pub fn location(
         &mut self,
         reference: u64,
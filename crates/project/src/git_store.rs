@@ -33,15 +33,17 @@ use git::{
     parse_git_remote_url,
     repository::{
         Branch, CommitDetails, CommitDiff, CommitFile, CommitOptions, DiffType, FetchOptions,
-        GitRepository, GitRepositoryCheckpoint, GraphCommitData, InitialGraphCommitData, LogOrder,
-        LogSource, PushOptions, Remote, RemoteCommandOutput, RepoPath, ResetMode, SearchCommitArgs,
-        UpstreamTrackingStatus, Worktree as GitWorktree,
+        FileHistoryEntry, GitRepository, GitRepositoryCheckpoint, GraphCommitData,
+        InitialGraphCommitData, LogOrder, LogSource, PushOptions, Remote, RemoteCommandOutput,
+        RepoPath, ResetMode, SearchCommitArgs, UpstreamTrackingStatus, Worktree as GitWorktree,
     },
+    signing::CommitSignature,
     stash::{GitStash, StashEntry},
     status::{
         self, DiffStat, DiffTreeType, FileStatus, GitSummary, StatusCode, TrackedStatus, TreeDiff,
         TreeDiffStatus, UnmergedStatus, UnmergedStatusCode,
     },
+    submodule::{Submodule, SubmoduleOperation, SubmoduleStatus},
 };
 use gpui::{
     App, AppContext, AsyncApp, Context, Entity, EventEmitter, SharedString, Subscription, Task,
@@ -546,6 +548,10 @@ impl GitStore {
         client.add_entity_request_handler(Self::handle_create_remote);
         client.add_entity_request_handler(Self::handle_remove_remote);
         client.add_entity_request_handler(Self::handle_delete_branch);
+        client.add_entity_request_handler(Self::handle_set_branch_target);
+        client.add_entity_request_handler(Self::handle_reflog);
+        client.add_entity_request_handler(Self::handle_get_submodules);
+        client.add_entity_request_handler(Self::handle_submodule_operation);
         client.add_entity_request_handler(Self::handle_git_init);
         client.add_entity_request_handler(Self::handle_push);
         client.add_entity_request_handler(Self::handle_pull);
@@ -566,6 +572,8 @@ impl GitStore {
         client.add_entity_request_handler(Self::handle_diff_checkpoints);
         client.add_entity_request_handler(Self::handle_load_commit_diff);
         client.add_entity_request_handler(Self::handle_file_history);
+        client.add_entity_request_handler(Self::handle_line_history);
+        client.add_entity_request_handler(Self::handle_commit_signature);
         client.add_entity_request_handler(Self::handle_checkout_files);
         client.add_entity_request_handler(Self::handle_open_commit_message_buffer);
         client.add_entity_request_handler(Self::handle_set_index_text);
@@ -1233,6 +1241,18 @@ impl GitStore {
         cx.spawn(|_: &mut AsyncApp| async move { rx.await? })
     }
 
+    pub fn line_history(
+        &self,
+        repo: &Entity<Repository>,
+        path: RepoPath,
+        rows: Range<u32>,
+        cx: &mut App,
+    ) -> Task<Result<git::repository::LineHistory>> {
+        let rx = repo.update(cx, |repo, _| repo.line_history(path, rows));
+
+        cx.spawn(|_: &mut AsyncApp| async move { rx.await? })
+    }
+
     pub fn get_permalink_to_line(
         &self,
         buffer: &Entity<Buffer>,
@@ -1803,6 +1823,21 @@ impl GitStore {
         &self.repositories
     }
 
+    /// Returns the repository checked out at the submodule `path` of `repository`,
+    /// if it has been discovered.
+    pub fn repository_for_submodule(
+        &self,
+        repository: &Entity<Repository>,
+        path: &RepoPath,
+        cx: &App,
+    ) -> Option<Entity<Repository>> {
+        let abs_path = repository.read(cx).snapshot.repo_path_to_abs_path(path);
+        self.repositories
+            .values()
+            .find(|repo| *repo.read(cx).snapshot.work_directory_abs_path == *abs_path)
+            .cloned()
+    }
+
     /// Returns the original (main) repository working directory for the given worktree.
     /// For normal checkouts this equals the worktree's own path; for linked
     /// worktrees it points back to the original repo.
@@ -2338,6 +2373,8 @@ impl GitStore {
                     CommitOptions {
                         amend: options.amend,
                         signoff: options.signoff,
+                        sign: options.sign,
+                        signing_key: options.signing_key.map(SharedString::from),
                     },
                     askpass,
                     cx,
@@ -2583,6 +2620,123 @@ impl GitStore {
         Ok(proto::Ack {})
     }
 
+    async fn handle_set_branch_target(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitSetBranchTarget>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::Ack> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+        let branch_name = envelope.payload.branch_name;
+        let commit = envelope.payload.commit;
+
+        repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.set_branch_target(branch_name, commit)
+            })
+            .await??;
+
+        Ok(proto::Ack {})
+    }
+
+    async fn handle_reflog(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitReflog>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::GitReflogResponse> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+        let ref_name = envelope.payload.ref_name;
+
+        let entries = repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.reflog(ref_name)
+            })
+            .await??;
+
+        Ok(proto::GitReflogResponse {
+            entries: entries
+                .into_iter()
+                .map(|entry| proto::ReflogEntry {
+                    selector: entry.selector.to_string(),
+                    sha: entry.sha.to_string(),
+                    message: entry.message.to_string(),
+                    subject: entry.subject.to_string(),
+                    timestamp: entry.timestamp,
+                })
+                .collect(),
+        })
+    }
+
+    async fn handle_get_submodules(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitGetSubmodules>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::GitGetSubmodulesResponse> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+
+        let submodules = repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.submodules()
+            })
+            .await??;
+
+        Ok(proto::GitGetSubmodulesResponse {
+            submodules: submodules
+                .into_iter()
+                .map(|submodule| proto::Submodule {
+                    path: submodule.path.to_proto(),
+                    recorded_sha: submodule.recorded_sha.map(|sha| sha.to_string()),
+                    checked_out_sha: submodule.checked_out_sha.map(|sha| sha.to_string()),
+                    initialized: submodule.status.initialized,
+                    commit_changed: submodule.status.commit_changed,
+                    has_modified_content: submodule.status.has_modified_content,
+                    has_untracked_content: submodule.status.has_untracked_content,
+                    conflicted: submodule.status.conflicted,
+                })
+                .collect(),
+        })
+    }
+
+    async fn handle_submodule_operation(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitSubmoduleOperation>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::RemoteMessageResponse> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+        let operation =
+            SubmoduleOperation::from_name(&envelope.payload.operation).with_context(|| {
+                format!("unknown submodule operation {}", envelope.payload.operation)
+            })?;
+        let paths = envelope
+            .payload
+            .paths
+            .iter()
+            .map(|path| RepoPath::from_proto(path))
+            .collect::<Result<Vec<_>>>()?;
+
+        let askpass = make_remote_delegate(
+            this,
+            envelope.payload.project_id,
+            repository_id,
+            envelope.payload.askpass_id,
+            &mut cx,
+        );
+
+        let remote_output = repository_handle
+            .update(&mut cx, |repository_handle, cx| {
+                repository_handle.submodule_operation(operation, paths, askpass, cx)
+            })
+            .await??;
+
+        Ok(proto::RemoteMessageResponse {
+            stdout: remote_output.stdout,
+            stderr: remote_output.stderr,
+        })
+    }
+
     async fn handle_remove_remote(
         this: Entity<Self>,
         envelope: TypedEnvelope<proto::GitRemoveRemote>,
@@ -2614,13 +2768,7 @@ impl GitStore {
                 repository_handle.show(envelope.payload.commit)
             })
             .await??;
-        Ok(proto::GitCommitDetails {
-            sha: commit.sha.into(),
-            message: commit.message.into(),
-            commit_timestamp: commit.commit_timestamp,
-            author_email: commit.author_email.into(),
-            author_name: commit.author_name.into(),
-        })
+        Ok(commit_details_to_proto(&commit))
     }
 
     async fn handle_create_checkpoint(
@@ -2719,7 +2867,12 @@ impl GitStore {
 
         let commit_diff = repository_handle
             .update(&mut cx, |repository_handle, _| {
-                repository_handle.load_commit_diff(envelope.payload.commit)
+                match envelope.payload.base_ref {
+                    Some(base_ref) => {
+                        repository_handle.load_branch_diff(base_ref, envelope.payload.commit)
+                    }
+                    None => repository_handle.load_commit_diff(envelope.payload.commit),
+                }
             })
             .await??;
         Ok(proto::LoadCommitDiffResponse {
@@ -2756,20 +2909,67 @@ impl GitStore {
         Ok(proto::GitFileHistoryResponse {
             entries: file_history
                 .entries
-                .into_iter()
-                .map(|entry| proto::FileHistoryEntry {
-                    sha: entry.sha.to_string(),
-                    subject: entry.subject.to_string(),
-                    message: entry.message.to_string(),
-                    commit_timestamp: entry.commit_timestamp,
-                    author_name: entry.author_name.to_string(),
-                    author_email: entry.author_email.to_string(),
-                })
+                .iter()
+                .map(file_history_entry_to_proto)
                 .collect(),
             path: file_history.path.to_proto(),
         })
     }
 
+    async fn handle_commit_signature(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitCommitSignature>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::GitCommitSignatureResponse> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+
+        let signature = repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.commit_signature(envelope.payload.commit)
+            })
+            .await??;
+        Ok(proto::GitCommitSignatureResponse {
+            signature: signature.map(|signature| proto::CommitSignature {
+                status: signature.status.code().to_string(),
+                signer: signature.signer.to_string(),
+                key: signature.key.to_string(),
+            }),
+        })
+    }
+
+    async fn handle_line_history(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitLineHistory>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::GitLineHistoryResponse> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+        let path = RepoPath::from_proto(&envelope.payload.path)?;
+        let rows = envelope.payload.start_row..envelope.payload.end_row;
+
+        let line_history = repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.line_history(path, rows)
+            })
+            .await??;
+
+        Ok(proto::GitLineHistoryResponse {
+            entries: line_history
+                .entries
+                .iter()
+                .map(|entry| proto::LineHistoryEntry {
+                    commit: Some(file_history_entry_to_proto(&entry.commit)),
+                    path: entry.path.to_proto(),
+                    diff: entry.diff.to_string(),
+                })
+                .collect(),
+            path: line_history.path.to_proto(),
+            start_row: line_history.rows.start,
+            end_row: line_history.rows.end,
+        })
+    }
+
     async fn handle_reset(
         this: Entity<Self>,
         envelope: TypedEnvelope<proto::GitReset>,
@@ -4581,25 +4781,71 @@ impl Repository {
                         })
                         .await?;
 
-                    Ok(CommitDetails {
-                        sha: resp.sha.into(),
-                        message: resp.message.into(),
-                        commit_timestamp: resp.commit_timestamp,
-                        author_email: resp.author_email.into(),
-                        author_name: resp.author_name.into(),
-                    })
+                    Ok(proto_to_commit_details(&resp))
                 }
             }
         })
     }
 
-    pub fn load_commit_diff(&mut self, commit: String) -> oneshot::Receiver<Result<CommitDiff>> {
+    /// Verifies the signature of `commit`, which is slow enough that it's only done
+    /// where the signature is shown.
+    pub fn commit_signature(
+        &mut self,
+        commit: String,
+    ) -> oneshot::Receiver<Result<Option<CommitSignature>>> {
         let id = self.id;
-        self.send_job(None, move |git_repo, cx| async move {
+        self.send_job(None, move |git_repo, _cx| async move {
             match git_repo {
                 RepositoryState::Local(LocalRepositoryState { backend, .. }) => {
-                    backend.load_commit(commit, cx).await
+                    backend.commit_signature(commit).await
                 }
+                RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                    let response = client
+                        .request(proto::GitCommitSignature {
+                            project_id: project_id.0,
+                            repository_id: id.to_proto(),
+                            commit,
+                        })
+                        .await?;
+
+                    Ok(response.signature.and_then(|signature| {
+                        CommitSignature::from_format_fields(
+                            &signature.status,
+                            &signature.signer,
+                            &signature.key,
+                        )
+                    }))
+                }
+            }
+        })
+    }
+
+    pub fn load_commit_diff(&mut self, commit: String) -> oneshot::Receiver<Result<CommitDiff>> {
+        self.load_diff(None, commit)
+    }
+
+    /// Loads the full contents of every file changed on `head_ref` since it
+    /// diverged from `base_ref`.
+    pub fn load_branch_diff(
+        &mut self,
+        base_ref: String,
+        head_ref: String,
+    ) -> oneshot::Receiver<Result<CommitDiff>> {
+        self.load_diff(Some(base_ref), head_ref)
+    }
+
+    fn load_diff(
+        &mut self,
+        base_ref: Option<String>,
+        commit: String,
+    ) -> oneshot::Receiver<Result<CommitDiff>> {
+        let id = self.id;
+        self.send_job(None, move |git_repo, cx| async move {
+            match git_repo {
+                RepositoryState::Local(LocalRepositoryState { backend, .. }) => match base_ref {
+                    Some(base_ref) => backend.load_branch_diff(base_ref, commit, cx).await,
+                    None => backend.load_commit(commit, cx).await,
+                },
                 RepositoryState::Remote(RemoteRepositoryState {
                     client, project_id, ..
                 }) => {
@@ -4608,6 +4854,7 @@ impl Repository {
                             project_id: project_id.0,
                             repository_id: id.to_proto(),
                             commit,
+                            base_ref,
                         })
                         .await?;
                     Ok(CommitDiff {
@@ -4661,15 +4908,8 @@ impl Repository {
                     Ok(git::repository::FileHistory {
                         entries: response
                             .entries
-                            .into_iter()
-                            .map(|entry| git::repository::FileHistoryEntry {
-                                sha: entry.sha.into(),
-                                subject: entry.subject.into(),
-                                message: entry.message.into(),
-                                commit_timestamp: entry.commit_timestamp,
-                                author_name: entry.author_name.into(),
-                                author_email: entry.author_email.into(),
-                            })
+                            .iter()
+                            .map(proto_to_file_history_entry)
                             .collect(),
                         path: RepoPath::from_proto(&response.path)?,
                     })
@@ -4678,6 +4918,49 @@ impl Repository {
         })
     }
 
+    pub fn line_history(
+        &mut self,
+        path: RepoPath,
+        rows: Range<u32>,
+    ) -> oneshot::Receiver<Result<git::repository::LineHistory>> {
+        let id = self.id;
+        self.send_job(None, move |git_repo, _cx| async move {
+            match git_repo {
+                RepositoryState::Local(LocalRepositoryState { backend, .. }) => {
+                    backend.line_history(path, rows).await
+                }
+                RepositoryState::Remote(RemoteRepositoryState { client, project_id }) => {
+                    let response = client
+                        .request(proto::GitLineHistory {
+                            project_id: project_id.0,
+                            repository_id: id.to_proto(),
+                            path: path.to_proto(),
+                            start_row: rows.start,
+                            end_row: rows.end,
+                        })
+                        .await?;
+                    Ok(git::repository::LineHistory {
+                        entries: response
+                            .entries
+                            .iter()
+                            .map(|entry| {
+                                Ok(git::repository::LineHistoryEntry {
+                                    commit: proto_to_file_history_entry(
+                                        entry.commit.as_ref().context("missing commit")?,
+                                    ),
+                                    path: RepoPath::from_proto(&entry.path)?,
+                                    diff: entry.diff.clone().into(),
+                                })
+                            })
+                            .collect::<Result<_>>()?,
+                        path: RepoPath::from_proto(&response.path)?,
+                        rows: response.start_row..response.end_row,
+                    })
+                }
+            }
+        })
+    }
+
     pub fn get_graph_data(
         &self,
         log_source: LogSource,
@@ -5484,6 +5767,8 @@ impl Repository {
                             options: Some(proto::commit::CommitOptions {
                                 amend: options.amend,
                                 signoff: options.signoff,
+                                sign: options.sign,
+                                signing_key: options.signing_key.map(String::from),
                             }),
                             askpass_id,
                         })
@@ -6265,6 +6550,160 @@ impl Repository {
         )
     }
 
+    pub fn set_branch_target(
+        &mut self,
+        branch_name: String,
+        commit: String,
+    ) -> oneshot::Receiver<Result<()>> {
+        let id = self.id;
+        self.send_job(
+            Some(format!("git branch --force {branch_name} {commit}").into()),
+            move |repo, _cx| async move {
+                match repo {
+                    RepositoryState::Local(LocalRepositoryState { backend, .. }) => {
+                        backend.set_branch_target(branch_name, commit).await
+                    }
+                    RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                        client
+                            .request(proto::GitSetBranchTarget {
+                                project_id: project_id.0,
+                                repository_id: id.to_proto(),
+                                branch_name,
+                                commit,
+                            })
+                            .await?;
+
+                        Ok(())
+                    }
+                }
+            },
+        )
+    }
+
+    pub fn reflog(
+        &mut self,
+        ref_name: String,
+    ) -> oneshot::Receiver<Result<Vec<git::repository::ReflogEntry>>> {
+        let id = self.id;
+        self.send_job(None, move |repo, _cx| async move {
+            match repo {
+                RepositoryState::Local(LocalRepositoryState { backend, .. }) => {
+                    backend.reflog(ref_name).await
+                }
+                RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                    let response = client
+                        .request(proto::GitReflog {
+                            project_id: project_id.0,
+                            repository_id: id.to_proto(),
+                            ref_name,
+                        })
+                        .await?;
+
+                    Ok(response
+                        .entries
+                        .into_iter()
+                        .map(|entry| git::repository::ReflogEntry {
+                            selector: entry.selector.into(),
+                            sha: entry.sha.into(),
+                            message: entry.message.into(),
+                            subject: entry.subject.into(),
+                            timestamp: entry.timestamp,
+                        })
+                        .collect())
+                }
+            }
+        })
+    }
+
+    pub fn submodules(&mut self) -> oneshot::Receiver<Result<Vec<Submodule>>> {
+        let id = self.id;
+        self.send_job(None, move |repo, _cx| async move {
+            match repo {
+                RepositoryState::Local(LocalRepositoryState { backend, .. }) => {
+                    backend.submodules().await
+                }
+                RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                    let response = client
+                        .request(proto::GitGetSubmodules {
+                            project_id: project_id.0,
+                            repository_id: id.to_proto(),
+                        })
+                        .await?;
+
+                    response
+                        .submodules
+                        .into_iter()
+                        .map(|submodule| {
+                            Ok(Submodule {
+                                path: RepoPath::from_proto(&submodule.path)?,
+                                recorded_sha: submodule.recorded_sha.map(Into::into),
+                                checked_out_sha: submodule.checked_out_sha.map(Into::into),
+                                status: SubmoduleStatus {
+                                    initialized: submodule.initialized,
+                                    commit_changed: submodule.commit_changed,
+                                    has_modified_content: submodule.has_modified_content,
+                                    has_untracked_content: submodule.has_untracked_content,
+                                    conflicted: submodule.conflicted,
+                                },
+                            })
+                        })
+                        .collect()
+                }
+            }
+        })
+    }
+
+    pub fn submodule_operation(
+        &mut self,
+        operation: SubmoduleOperation,
+        paths: Vec<RepoPath>,
+        askpass: AskPassDelegate,
+        _cx: &mut App,
+    ) -> oneshot::Receiver<Result<RemoteCommandOutput>> {
+        let askpass_delegates = self.askpass_delegates.clone();
+        let askpass_id = util::post_inc(&mut self.latest_askpass_id);
+        let id = self.id;
+
+        self.send_job(
+            Some(format!("git submodule {}", operation.name()).into()),
+            move |git_repo, cx| async move {
+                match git_repo {
+                    RepositoryState::Local(LocalRepositoryState {
+                        backend,
+                        environment,
+                        ..
+                    }) => {
+                        backend
+                            .submodule_operation(operation, paths, askpass, environment, cx)
+                            .await
+                    }
+                    RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                        askpass_delegates.lock().insert(askpass_id, askpass);
+                        let _defer = util::defer(|| {
+                            let askpass_delegate = askpass_delegates.lock().remove(&askpass_id);
+                            debug_assert!(askpass_delegate.is_some());
+                        });
+
+                        let response = client
+                            .request(proto::GitSubmoduleOperation {
+                                project_id: project_id.0,
+                                repository_id: id.to_proto(),
+                                askpass_id,
+                                operation: operation.name().to_string(),
+                                paths: paths.iter().map(|path| path.to_proto()).collect(),
+                            })
+                            .await?;
+
+                        Ok(RemoteCommandOutput {
+                            stdout: response.stdout,
+                            stderr: response.stderr,
+                        })
+                    }
+                }
+            },
+        )
+    }
+
     pub fn rename_branch(
         &mut self,
         branch: String,
@@ -7278,6 +7717,28 @@ fn proto_to_commit_details(proto: &proto::GitCommitDetails) -> CommitDetails {
     }
 }
 
+fn file_history_entry_to_proto(entry: &FileHistoryEntry) -> proto::FileHistoryEntry {
+    proto::FileHistoryEntry {
+        sha: entry.sha.to_string(),
+        subject: entry.subject.to_string(),
+        message: entry.message.to_string(),
+        commit_timestamp: entry.commit_timestamp,
+        author_name: entry.author_name.to_string(),
+        author_email: entry.author_email.to_string(),
+    }
+}
+
+fn proto_to_file_history_entry(proto: &proto::FileHistoryEntry) -> FileHistoryEntry {
+    FileHistoryEntry {
+        sha: proto.sha.clone().into(),
+        subject: proto.subject.clone().into(),
+        message: proto.message.clone().into(),
+        commit_timestamp: proto.commit_timestamp,
+        author_name: proto.author_name.clone().into(),
+        author_email: proto.author_email.clone().into(),
+    }
+}
+
 /// This snapshot computes the repository state on the foreground thread while
 /// running the git commands on the background thread. We update branch, head,
 /// remotes, and worktrees first so the UI can react sooner, then compute file
@@ -1,6 +1,8 @@
+use std::time::Duration;
+
 use anyhow::Result;
 use buffer_diff::BufferDiff;
-use collections::HashSet;
+use collections::{HashMap, HashSet};
 use futures::StreamExt;
 use git::{
     repository::RepoPath,
@@ -18,7 +20,7 @@ use ztracing::instrument;
 
 use crate::{
     Project,
-    git_store::{GitStoreEvent, Repository, RepositoryEvent},
+    git_store::{GitStoreEvent, Repository, RepositoryEvent, RepositoryId},
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -40,11 +42,45 @@ pub struct BranchDiff {
     base_commit: Option<SharedString>,
     head_commit: Option<SharedString>,
     tree_diff: Option<TreeDiff>,
+    submodule_diffs: HashMap<RepoPath, SubmoduleDiff>,
+    /// The submodules of the repository, as of the last time the submodule diffs were computed.
+    submodule_paths: Vec<RepoPath>,
+    /// The state the submodule diffs were computed from.
+    submodule_fingerprint: Option<SubmoduleFingerprint>,
     _subscription: Subscription,
     update_needed: postage::watch::Sender<()>,
     _task: Task<()>,
 }
 
+/// How long repository events have to settle before the submodule diffs are recomputed,
+/// since that runs git in every submodule.
+const SUBMODULE_RELOAD_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// The changes inside a submodule of the diffed repository.
+#[derive(PartialEq)]
+struct SubmoduleDiff {
+    repo: Entity<Repository>,
+    /// Changes between the commit recorded by the superproject and the submodule's `HEAD`,
+    /// or `None` when only the submodule's working tree has changed.
+    tree_diff: Option<TreeDiff>,
+}
+
+/// What the submodule diffs depend on, so that they're only recomputed when it changes.
+#[derive(PartialEq)]
+struct SubmoduleFingerprint {
+    /// The status and branch diff of `.gitmodules` and of each submodule in the superproject.
+    superproject_entries: Vec<(RepoPath, Option<FileStatus>, Option<TreeDiffStatus>)>,
+    submodule_repos: Vec<SubmoduleRepoState>,
+}
+
+/// The `HEAD` and statuses of a submodule's repository.
+#[derive(PartialEq)]
+struct SubmoduleRepoState {
+    id: RepositoryId,
+    head_sha: Option<SharedString>,
+    statuses: Vec<(RepoPath, FileStatus)>,
+}
+
 pub enum BranchDiffEvent {
     FileListChanged,
 }
@@ -72,10 +108,24 @@ impl BranchDiff {
                         event_repo_id,
                         RepositoryEvent::StatusesChanged | RepositoryEvent::BranchChanged,
                         _,
-                    ) => this
-                        .repo
-                        .as_ref()
-                        .is_some_and(|r| r.read(cx).snapshot().id == *event_repo_id),
+                    ) => {
+                        let is_submodule =
+                            this.submodule_fingerprint
+                                .as_ref()
+                                .is_some_and(|fingerprint| {
+                                    fingerprint
+                                        .submodule_repos
+                                        .iter()
+                                        .any(|repo| repo.id == *event_repo_id)
+                                });
+                        if is_submodule {
+                            // The file list changes once the submodule diffs are recomputed.
+                            *this.update_needed.borrow_mut() = ();
+                        }
+                        this.repo
+                            .as_ref()
+                            .is_some_and(|r| r.read(cx).snapshot().id == *event_repo_id)
+                    }
                     GitStoreEvent::ConflictsUpdated => this.repo.is_some(),
                     _ => false,
                 };
@@ -98,6 +148,9 @@ impl BranchDiff {
             repo,
             project,
             tree_diff: None,
+            submodule_diffs: HashMap::default(),
+            submodule_paths: Vec::new(),
+            submodule_fingerprint: None,
             base_commit: None,
             head_commit: None,
             _subscription: git_store_subscription,
@@ -113,6 +166,9 @@ impl BranchDiff {
     pub fn set_repo(&mut self, repo: Option<Entity<Repository>>, cx: &mut Context<Self>) {
         self.repo = repo;
         self.tree_diff = None;
+        self.submodule_diffs.clear();
+        self.submodule_paths.clear();
+        self.submodule_fingerprint = None;
         self.base_commit = None;
         self.head_commit = None;
         cx.emit(BranchDiffEvent::FileListChanged);
@@ -125,6 +181,9 @@ impl BranchDiff {
         cx: &mut AsyncWindowContext,
     ) {
         Self::reload_tree_diff(this.clone(), cx).await.log_err();
+        Self::reload_submodule_diffs(this.clone(), cx)
+            .await
+            .log_err();
         while recv.next().await.is_some() {
             let Ok(needs_update) = this.update(cx, |this, cx| {
                 let mut needs_update = false;
@@ -166,6 +225,9 @@ impl BranchDiff {
             if needs_update {
                 Self::reload_tree_diff(this.clone(), cx).await.log_err();
             }
+            Self::reload_submodule_diffs(this.clone(), cx)
+                .await
+                .log_err();
         }
     }
 
@@ -176,17 +238,21 @@ impl BranchDiff {
             .git_store()
             .read(cx)
             .repository_and_path_for_buffer_id(buffer_id, cx)?;
-        if self.repo() == Some(&repo) {
-            return self.merge_statuses(
-                repo.read(cx)
-                    .status_for_path(&path)
-                    .map(|status| status.status),
-                self.tree_diff
-                    .as_ref()
-                    .and_then(|diff| diff.entries.get(&path)),
-            );
-        }
-        None
+        let tree_diff = if self.repo() == Some(&repo) {
+            self.tree_diff.as_ref()
+        } else {
+            self.submodule_diffs
+                .values()
+                .find(|diff| diff.repo == repo)?
+                .tree_diff
+                .as_ref()
+        };
+        self.merge_statuses(
+            repo.read(cx)
+                .status_for_path(&path)
+                .map(|status| status.status),
+            tree_diff.and_then(|diff| diff.entries.get(&path)),
+        )
     }
 
     pub fn merge_statuses(
@@ -276,6 +342,157 @@ impl BranchDiff {
         })
     }
 
+    fn submodule_fingerprint(&self, cx: &App) -> Option<SubmoduleFingerprint> {
+        let repo = self.repo.as_ref()?;
+        let git_store = self.project.read(cx).git_store().read(cx);
+        let superproject_entries = RepoPath::new(".gitmodules")
+            .ok()
+            .into_iter()
+            .chain(self.submodule_paths.iter().cloned())
+            .map(|path| {
+                let status = repo
+                    .read(cx)
+                    .status_for_path(&path)
+                    .map(|entry| entry.status);
+                let branch_diff = self
+                    .tree_diff
+                    .as_ref()
+                    .and_then(|diff| diff.entries.get(&path))
+                    .cloned();
+                (path, status, branch_diff)
+            })
+            .collect();
+        let submodule_repos = self
+            .submodule_paths
+            .iter()
+            .filter_map(|path| {
+                let submodule_repo = git_store.repository_for_submodule(repo, path, cx)?;
+                let snapshot = &submodule_repo.read(cx).snapshot;
+                Some(SubmoduleRepoState {
+                    id: snapshot.id,
+                    head_sha: snapshot
+                        .head_commit
+                        .as_ref()
+                        .map(|commit| commit.sha.clone()),
+                    statuses: snapshot
+                        .statuses_by_path
+                        .iter()
+                        .map(|entry| (entry.repo_path.clone(), entry.status))
+                        .collect(),
+                })
+            })
+            .collect();
+        Some(SubmoduleFingerprint {
+            superproject_entries,
+            submodule_repos,
+        })
+    }
+
+    /// Computes the changes inside each submodule of the repository, so that a
+    /// changed submodule can be expanded into the files that changed within it.
+    ///
+    /// This does nothing unless the submodules or their repositories changed since the
+    /// last time, and waits for a burst of changes to settle first.
+    pub async fn reload_submodule_diffs(
+        this: WeakEntity<Self>,
+        cx: &mut AsyncWindowContext,
+    ) -> Result<()> {
+        let is_changed = this.read_with(cx, |this, cx| {
+            let fingerprint = this.submodule_fingerprint(cx);
+            fingerprint.is_some() && fingerprint != this.submodule_fingerprint
+        })?;
+        if !is_changed {
+            return Ok(());
+        }
+        cx.background_executor()
+            .timer(SUBMODULE_RELOAD_DEBOUNCE)
+            .await;
+
+        let submodules = this.update(cx, |this, cx| {
+            this.submodule_fingerprint = this.submodule_fingerprint(cx);
+            let repo = this.repo.as_ref()?;
+            Some(repo.update(cx, |repo, _| repo.submodules()))
+        })?;
+        let Some(submodules) = submodules else {
+            return Ok(());
+        };
+        let submodules = submodules.await??;
+        let submodule_paths = submodules
+            .iter()
+            .map(|submodule| submodule.path.clone())
+            .collect::<Vec<_>>();
+
+        let mut submodule_diffs = HashMap::default();
+        for submodule in submodules {
+            if !submodule.status.initialized {
+                continue;
+            }
+            let Some((repo, tree_diff)) = this.update(cx, |this, cx| {
+                let parent = this.repo.as_ref()?;
+                // When diffing against a merge base, show every commit the submodule moved
+                // since then, not just the ones that aren't recorded in `HEAD` yet.
+                let base = this
+                    .tree_diff
+                    .as_ref()
+                    .and_then(|diff| diff.entries.get(&submodule.path))
+                    .and_then(|status| match status {
+                        TreeDiffStatus::Modified { old } | TreeDiffStatus::Deleted { old } => {
+                            Some(SharedString::from(old.to_string()))
+                        }
+                        TreeDiffStatus::Added => None,
+                    })
+                    .or_else(|| {
+                        submodule
+                            .status
+                            .commit_changed
+                            .then(|| submodule.recorded_sha.clone())
+                            .flatten()
+                    });
+                if base.is_none() && !submodule.status.is_dirty() {
+                    return None;
+                }
+                let repo = this
+                    .project
+                    .read(cx)
+                    .git_store()
+                    .read(cx)
+                    .repository_for_submodule(parent, &submodule.path, cx)?;
+                let tree_diff = base.map(|base| {
+                    repo.update(cx, |repo, cx| {
+                        repo.diff_tree(
+                            DiffTreeType::Since {
+                                base,
+                                head: "HEAD".into(),
+                            },
+                            cx,
+                        )
+                    })
+                });
+                Some((repo, tree_diff))
+            })?
+            else {
+                continue;
+            };
+            let tree_diff = match tree_diff {
+                Some(task) => Some(task.await??),
+                None => None,
+            };
+            submodule_diffs.insert(submodule.path, SubmoduleDiff { repo, tree_diff });
+        }
+
+        this.update(cx, |this, cx| {
+            if this.submodule_paths != submodule_paths {
+                this.submodule_paths = submodule_paths;
+                this.submodule_fingerprint = this.submodule_fingerprint(cx);
+            }
+            if this.submodule_diffs != submodule_diffs {
+                this.submodule_diffs = submodule_diffs;
+                cx.emit(BranchDiffEvent::FileListChanged);
+                cx.notify();
+            }
+        })
+    }
+
     pub fn repo(&self) -> Option<&Entity<Repository>> {
         self.repo.as_ref()
     }
@@ -288,61 +505,89 @@ impl BranchDiff {
         };
 
         self.project.update(cx, |_project, cx| {
-            let mut seen = HashSet::default();
-
-            for item in repo.read(cx).cached_status() {
-                seen.insert(item.repo_path.clone());
-                let branch_diff = self
-                    .tree_diff
-                    .as_ref()
-                    .and_then(|t| t.entries.get(&item.repo_path))
-                    .cloned();
-                let Some(status) = self.merge_statuses(Some(item.status), branch_diff.as_ref())
-                else {
-                    continue;
-                };
-                if !status.has_changes() {
-                    continue;
-                }
+            self.collect_diff_buffers(&repo, self.tree_diff.as_ref(), None, &mut output, cx);
+            for (submodule_path, submodule_diff) in &self.submodule_diffs {
+                self.collect_diff_buffers(
+                    &submodule_diff.repo,
+                    submodule_diff.tree_diff.as_ref(),
+                    Some(submodule_path),
+                    &mut output,
+                    cx,
+                );
+            }
+        });
+        output
+    }
 
-                let Some(project_path) =
-                    repo.read(cx).repo_path_to_project_path(&item.repo_path, cx)
-                else {
-                    continue;
-                };
-                let task = Self::load_buffer(branch_diff, project_path, repo.clone(), cx);
+    /// Collects the changed files of `repo`. For a submodule, `submodule_path` is its
+    /// path in the superproject, which the returned repo paths are nested under.
+    fn collect_diff_buffers(
+        &self,
+        repo: &Entity<Repository>,
+        tree_diff: Option<&TreeDiff>,
+        submodule_path: Option<&RepoPath>,
+        output: &mut Vec<DiffBuffer>,
+        cx: &mut Context<Project>,
+    ) {
+        let mut seen = HashSet::default();
+        let output_path = |repo_path: &RepoPath| match submodule_path {
+            Some(submodule_path) => RepoPath::from_rel_path(&submodule_path.join(repo_path)),
+            None => repo_path.clone(),
+        };
+        // Expanded submodules replace the entry for the submodule itself.
+        let is_expanded_submodule = |repo_path: &RepoPath| {
+            submodule_path.is_none() && self.submodule_diffs.contains_key(repo_path)
+        };
 
-                output.push(DiffBuffer {
-                    repo_path: item.repo_path.clone(),
-                    load: task,
-                    file_status: item.status,
-                });
+        for item in repo.read(cx).cached_status() {
+            seen.insert(item.repo_path.clone());
+            if is_expanded_submodule(&item.repo_path) {
+                continue;
             }
-            let Some(tree_diff) = self.tree_diff.as_ref() else {
-                return;
+            let branch_diff = tree_diff
+                .and_then(|t| t.entries.get(&item.repo_path))
+                .cloned();
+            let Some(status) = self.merge_statuses(Some(item.status), branch_diff.as_ref()) else {
+                continue;
             };
+            if !status.has_changes() {
+                continue;
+            }
 
-            for (path, branch_diff) in tree_diff.entries.iter() {
-                if seen.contains(&path) {
-                    continue;
-                }
-
-                let Some(project_path) = repo.read(cx).repo_path_to_project_path(&path, cx) else {
-                    continue;
-                };
-                let task =
-                    Self::load_buffer(Some(branch_diff.clone()), project_path, repo.clone(), cx);
+            let Some(project_path) = repo.read(cx).repo_path_to_project_path(&item.repo_path, cx)
+            else {
+                continue;
+            };
+            let task = Self::load_buffer(branch_diff, project_path, repo.clone(), cx);
 
-                let file_status = diff_status_to_file_status(branch_diff);
+            output.push(DiffBuffer {
+                repo_path: output_path(&item.repo_path),
+                load: task,
+                file_status: item.status,
+            });
+        }
+        let Some(tree_diff) = tree_diff else {
+            return;
+        };
 
-                output.push(DiffBuffer {
-                    repo_path: path.clone(),
-                    load: task,
-                    file_status,
-                });
+        for (path, branch_diff) in tree_diff.entries.iter() {
+            if seen.contains(&path) || is_expanded_submodule(path) {
+                continue;
             }
-        });
-        output
+
+            let Some(project_path) = repo.read(cx).repo_path_to_project_path(&path, cx) else {
+                continue;
+            };
+            let task = Self::load_buffer(Some(branch_diff.clone()), project_path, repo.clone(), cx);
+
+            let file_status = diff_status_to_file_status(branch_diff);
+
+            output.push(DiffBuffer {
+                repo_path: output_path(path),
+                load: task,
+                file_status,
+            });
+        }
     }
 
     #[instrument(skip_all)]
@@ -5,7 +5,7 @@ use crate::{
     DocumentHighlight, DocumentSymbol, Hover, HoverBlock, HoverBlockKind, InlayHint,
     InlayHintLabel, InlayHintLabelPart, InlayHintLabelPartTooltip, InlayHintTooltip, Location,
     LocationLink, LspAction, LspPullDiagnostics, MarkupContent, PrepareRenameResponse,
-    ProjectTransaction, PulledDiagnostics, ResolveState,
+    ProjectEdits, ProjectTransaction, PulledDiagnostics, RenameEdits, ResolveState,
     lsp_store::{LocalLspStore, LspFoldingRange, LspStore},
 };
 use anyhow::{Context as _, Result};
@@ -185,6 +185,15 @@ pub(crate) struct PerformRename {
     pub push_to_history: bool,
 }
 
+/// Requests the edits of a rename without applying them, unless they also
+/// create, rename or delete files.
+#[derive(Debug)]
+pub(crate) struct GetRenameEdits {
+    pub position: PointUtf16,
+    pub new_name: String,
+    pub push_to_history: bool,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct GetDefinitions {
     pub position: PointUtf16,
@@ -646,6 +655,170 @@ impl LspCommand for PerformRename {
     }
 }
 
+#[async_trait(?Send)]
+impl LspCommand for GetRenameEdits {
+    type Response = RenameEdits;
+    type LspRequest = lsp::request::Rename;
+    type ProtoRequest = proto::GetRenameEdits;
+
+    fn display_name(&self) -> &str {
+        "Rename"
+    }
+
+    fn check_capabilities(&self, capabilities: AdapterServerCapabilities) -> bool {
+        capabilities
+            .server_capabilities
+            .rename_provider
+            .is_some_and(|capability| match capability {
+                OneOf::Left(enabled) => enabled,
+                OneOf::Right(_) => true,
+            })
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &App,
+    ) -> Result<lsp::RenameParams> {
+        Ok(lsp::RenameParams {
+            text_document_position: make_lsp_text_document_position(path, self.position)?,
+            new_name: self.new_name.clone(),
+            work_done_progress_params: Default::default(),
+        })
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<lsp::WorkspaceEdit>,
+        lsp_store: Entity<LspStore>,
+        buffer: Entity<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncApp,
+    ) -> Result<RenameEdits> {
+        let Some(edit) = message else {
+            return Ok(RenameEdits::Edits(ProjectEdits::default()));
+        };
+        let (_, lsp_server) = language_server_for_buffer(&lsp_store, &buffer, server_id, &mut cx)?;
+        if let Some(edits) = LocalLspStore::workspace_edit_to_project_edits(
+            lsp_store.clone(),
+            edit.clone(),
+            lsp_server.clone(),
+            &mut cx,
+        )
+        .await?
+        {
+            return Ok(RenameEdits::Edits(edits));
+        }
+        // Servers aren't required to return the same edit again, so the file
+        // operations are applied from this response.
+        LocalLspStore::deserialize_workspace_edit(
+            lsp_store,
+            edit,
+            self.push_to_history,
+            lsp_server,
+            &mut cx,
+        )
+        .await
+        .map(RenameEdits::Applied)
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetRenameEdits {
+        proto::GetRenameEdits {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: Some(language::proto::serialize_anchor(
+                &buffer.anchor_before(self.position),
+            )),
+            new_name: self.new_name.clone(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetRenameEdits,
+        _: Entity<LspStore>,
+        buffer: Entity<Buffer>,
+        mut cx: AsyncApp,
+    ) -> Result<Self> {
+        let position = message
+            .position
+            .and_then(deserialize_anchor)
+            .context("invalid position")?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })
+            .await?;
+        Ok(Self {
+            position: buffer.read_with(&cx, |buffer, _| position.to_point_utf16(buffer)),
+            new_name: message.new_name,
+            push_to_history: false,
+        })
+    }
+
+    fn response_to_proto(
+        response: RenameEdits,
+        lsp_store: &mut LspStore,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut App,
+    ) -> proto::GetRenameEditsResponse {
+        lsp_store
+            .buffer_store()
+            .update(cx, |buffer_store, cx| match response {
+                RenameEdits::Edits(edits) => proto::GetRenameEditsResponse {
+                    buffers: buffer_store.serialize_project_edits_for_peer(edits, peer_id, cx),
+                    transaction: None,
+                },
+                RenameEdits::Applied(transaction) => proto::GetRenameEditsResponse {
+                    buffers: Vec::new(),
+                    transaction: Some(buffer_store.serialize_project_transaction_for_peer(
+                        transaction,
+                        peer_id,
+                        cx,
+                    )),
+                },
+            })
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetRenameEditsResponse,
+        lsp_store: Entity<LspStore>,
+        _: Entity<Buffer>,
+        mut cx: AsyncApp,
+    ) -> Result<RenameEdits> {
+        if let Some(transaction) = message.transaction {
+            return lsp_store
+                .update(&mut cx, |lsp_store, cx| {
+                    lsp_store.buffer_store().update(cx, |buffer_store, cx| {
+                        buffer_store.deserialize_project_transaction(
+                            transaction,
+                            self.push_to_history,
+                            cx,
+                        )
+                    })
+                })
+                .await
+                .map(RenameEdits::Applied);
+        }
+        lsp_store
+            .update(&mut cx, |lsp_store, cx| {
+                lsp_store.buffer_store().update(cx, |buffer_store, cx| {
+                    buffer_store.deserialize_project_edits(message.buffers, cx)
+                })
+            })
+            .await
+            .map(RenameEdits::Edits)
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetRenameEdits) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
 #[async_trait(?Send)]
 impl LspCommand for GetDefinitions {
     type Response = Vec<LocationLink>;
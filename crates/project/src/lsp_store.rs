@@ -29,7 +29,7 @@ use self::inlay_hints::BufferInlayHints;
 use crate::{
     CodeAction, Completion, CompletionDisplayOptions, CompletionResponse, CompletionSource,
     CoreCompletion, Hover, InlayHint, InlayId, LocationLink, LspAction, LspPullDiagnostics,
-    ManifestProvidersStore, Project, ProjectItem, ProjectPath, ProjectTransaction,
+    ManifestProvidersStore, Project, ProjectEdits, ProjectItem, ProjectPath, ProjectTransaction,
     PulledDiagnostics, ResolveState, Symbol,
     buffer_store::{BufferStore, BufferStoreEvent},
     environment::ProjectEnvironment,
@@ -3290,28 +3290,8 @@ impl LocalLspStore {
     ) -> Result<ProjectTransaction> {
         let fs = this.read_with(cx, |this, _| this.as_local().unwrap().fs.clone());
 
-        let mut operations = Vec::new();
-        if let Some(document_changes) = edit.document_changes {
-            match document_changes {
-                lsp::DocumentChanges::Edits(edits) => {
-                    operations.extend(edits.into_iter().map(lsp::DocumentChangeOperation::Edit))
-                }
-                lsp::DocumentChanges::Operations(ops) => operations = ops,
-            }
-        } else if let Some(changes) = edit.changes {
-            operations.extend(changes.into_iter().map(|(uri, edits)| {
-                lsp::DocumentChangeOperation::Edit(lsp::TextDocumentEdit {
-                    text_document: lsp::OptionalVersionedTextDocumentIdentifier {
-                        uri,
-                        version: None,
-                    },
-                    edits: edits.into_iter().map(Edit::Plain).collect(),
-                })
-            }));
-        }
-
         let mut project_transaction = ProjectTransaction::default();
-        for operation in operations {
+        for operation in workspace_edit_operations(edit) {
             match operation {
                 lsp::DocumentChangeOperation::Op(lsp::ResourceOp::Create(op)) => {
                     let abs_path = op
@@ -3508,6 +3488,72 @@ impl LocalLspStore {
         Ok(project_transaction)
     }
 
+    /// Resolves the text edits of a workspace edit without applying them, or
+    /// returns `None` when it also creates, renames or deletes files.
+    pub(crate) async fn workspace_edit_to_project_edits(
+        this: Entity<LspStore>,
+        edit: lsp::WorkspaceEdit,
+        language_server: Arc<LanguageServer>,
+        cx: &mut AsyncApp,
+    ) -> Result<Option<ProjectEdits>> {
+        let operations = workspace_edit_operations(edit);
+        if operations
+            .iter()
+            .any(|operation| matches!(operation, lsp::DocumentChangeOperation::Op(_)))
+        {
+            return Ok(None);
+        }
+
+        let mut project_edits = ProjectEdits::default();
+        for operation in operations {
+            let lsp::DocumentChangeOperation::Edit(op) = operation else {
+                continue;
+            };
+            let buffer = this
+                .update(cx, |this, cx| {
+                    this.open_local_buffer_via_lsp(
+                        op.text_document.uri.clone(),
+                        language_server.server_id(),
+                        cx,
+                    )
+                })
+                .await?;
+
+            let mut lsp_edits = Vec::new();
+            for edit in op.edits {
+                let edit = match edit {
+                    Edit::Plain(edit) => edit,
+                    Edit::Annotated(edit) => edit.text_edit,
+                    Edit::Snippet(edit) => {
+                        let Ok(snippet) = Snippet::parse(&edit.snippet.value) else {
+                            continue;
+                        };
+                        TextEdit {
+                            range: edit.range,
+                            new_text: snippet.text,
+                        }
+                    }
+                };
+                if !lsp_edits.contains(&edit) {
+                    lsp_edits.push(edit);
+                }
+            }
+            let edits = this
+                .update(cx, |this, cx| {
+                    this.as_local_mut().unwrap().edits_from_lsp(
+                        &buffer,
+                        lsp_edits,
+                        language_server.server_id(),
+                        op.text_document.version,
+                        cx,
+                    )
+                })
+                .await?;
+            project_edits.push(buffer, edits);
+        }
+        Ok(Some(project_edits))
+    }
+
     async fn on_lsp_workspace_edit(
         this: WeakEntity<LspStore>,
         params: lsp::ApplyWorkspaceEditParams,
@@ -3894,6 +3940,34 @@ impl LocalLspStore {
     }
 }
 
+/// The operations of a workspace edit, in the order they must be applied.
+fn workspace_edit_operations(edit: lsp::WorkspaceEdit) -> Vec<lsp::DocumentChangeOperation> {
+    if let Some(document_changes) = edit.document_changes {
+        match document_changes {
+            lsp::DocumentChanges::Edits(edits) => edits
+                .into_iter()
+                .map(lsp::DocumentChangeOperation::Edit)
+                .collect(),
+            lsp::DocumentChanges::Operations(operations) => operations,
+        }
+    } else if let Some(changes) = edit.changes {
+        changes
+            .into_iter()
+            .map(|(uri, edits)| {
+                lsp::DocumentChangeOperation::Edit(lsp::TextDocumentEdit {
+                    text_document: lsp::OptionalVersionedTextDocumentIdentifier {
+                        uri,
+                        version: None,
+                    },
+                    edits: edits.into_iter().map(Edit::Plain).collect(),
+                })
+            })
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
 fn notify_server_capabilities_updated(server: &LanguageServer, cx: &mut Context<LspStore>) {
     if let Some(capabilities) = serde_json::to_string(&server.capabilities()).ok() {
         cx.emit(LspStoreEvent::LanguageServerUpdate {
@@ -4152,6 +4226,7 @@ impl LspStore {
         client.add_entity_request_handler(Self::handle_lsp_command::<GetDocumentSymbols>);
         client.add_entity_request_handler(Self::handle_lsp_command::<PrepareRename>);
         client.add_entity_request_handler(Self::handle_lsp_command::<PerformRename>);
+        client.add_entity_request_handler(Self::handle_lsp_command::<GetRenameEdits>);
         client.add_entity_request_handler(Self::handle_lsp_command::<LinkedEditingRange>);
 
         client.add_entity_request_handler(Self::handle_lsp_ext_cancel_flycheck);
@@ -9432,7 +9507,7 @@ impl LspStore {
         let new_abs_path =
             new_worktree.read_with(&cx, |worktree, _| worktree.absolutize(&new_path));
 
-        let _transaction = Self::will_rename_entry(
+        let (_transaction, edits) = Self::will_rename_entry(
             this.downgrade(),
             old_worktree_id,
             &old_abs_path,
@@ -9441,6 +9516,7 @@ impl LspStore {
             cx.clone(),
         )
         .await;
+        cx.update(|cx| edits.apply(cx));
         let response = WorktreeStore::handle_rename_project_entry(
             worktree_store,
             envelope.payload,
@@ -9927,7 +10003,7 @@ impl LspStore {
         new_path: &Path,
         is_dir: bool,
         cx: AsyncApp,
-    ) -> Task<ProjectTransaction> {
+    ) -> Task<(ProjectTransaction, ProjectEdits)> {
         let old_uri = lsp::Uri::from_file_path(old_path)
             .ok()
             .map(|uri| uri.to_string());
@@ -9972,9 +10048,23 @@ impl LspStore {
                                 .context("will rename files")
                                 .log_err()
                                 .flatten()?;
+                            let this = this.upgrade()?;
 
+                            // Plain text edits are returned without being applied, so that they can
+                            // be previewed. Edits that create, rename or delete files are applied.
+                            if let Some(edits) = LocalLspStore::workspace_edit_to_project_edits(
+                                this.clone(),
+                                edit.clone(),
+                                language_server.clone(),
+                                cx,
+                            )
+                            .await
+                            .log_err()?
+                            {
+                                return Some((ProjectTransaction::default(), edits));
+                            }
                             LocalLspStore::deserialize_workspace_edit(
-                                this.upgrade()?,
+                                this,
                                 edit,
                                 false,
                                 language_server.clone(),
@@ -9982,6 +10072,7 @@ impl LspStore {
                             )
                             .await
                             .ok()
+                            .map(|transaction| (transaction, ProjectEdits::default()))
                         }
                     });
                     tasks.push(apply_edit);
@@ -9991,16 +10082,20 @@ impl LspStore {
             .ok()
             .flatten();
             let mut merged_transaction = ProjectTransaction::default();
+            let mut merged_edits = ProjectEdits::default();
             for task in tasks {
                 // Await on tasks sequentially so that the order of application of edits is deterministic
                 // (at least with regards to the order of registration of language servers)
-                if let Some(transaction) = task.await {
+                if let Some((transaction, edits)) = task.await {
                     for (buffer, buffer_transaction) in transaction.0 {
                         merged_transaction.0.insert(buffer, buffer_transaction);
                     }
+                    for (buffer, buffer_edits) in edits.0 {
+                        merged_edits.push(buffer, buffer_edits);
+                    }
                 }
             }
-            merged_transaction
+            (merged_transaction, merged_edits)
         })
     }
 
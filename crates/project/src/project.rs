@@ -157,7 +157,7 @@ pub use task_inventory::{
     TaskSourceKind,
 };
 
-pub use buffer_store::ProjectTransaction;
+pub use buffer_store::{ProjectEdits, ProjectTransaction};
 pub use lsp_store::{
     DiagnosticSummary, InvalidationStrategy, LanguageServerLogType, LanguageServerProgress,
     LanguageServerPromptRequest, LanguageServerStatus, LanguageServerToQuery, LspStore,
@@ -244,6 +244,9 @@ pub struct Project {
     settings_observer: Entity<SettingsObserver>,
     toolchain_store: Option<Entity<ToolchainStore>>,
     agent_location: Option<AgentLocation>,
+    /// Decides whether the edits requested before an entry is renamed are
+    /// proposed instead of being applied.
+    propose_will_rename_edits: Option<Box<dyn Fn(&ProjectEdits, &App) -> bool>>,
     downloading_files: Arc<Mutex<HashMap<(WorktreeId, String), DownloadingFile>>>,
 }
 
@@ -398,6 +401,10 @@ pub enum Event {
     SnippetEdit(BufferId, Vec<(lsp::Range, Snippet)>),
     ExpandedAllForEntry(WorktreeId, ProjectEntryId),
     EntryRenamed(ProjectTransaction, ProjectPath, PathBuf),
+    /// Edits requested by language servers for a renamed entry, which were not
+    /// applied because [`Project::set_propose_will_rename_edits`] chose to
+    /// propose them.
+    WillRenameEditsProposed(ProjectEdits),
     WorkspaceEditApplied(ProjectTransaction),
     AgentLocationChanged,
     BufferEdited,
@@ -450,6 +457,16 @@ impl ProjectPath {
     }
 }
 
+/// The result of requesting the edits of a rename.
+#[derive(Debug)]
+pub enum RenameEdits {
+    /// The text edits of the rename, which haven't been applied.
+    Edits(ProjectEdits),
+    /// The rename also creates, renames or deletes files, so its edits can't be
+    /// applied separately and were applied as a whole.
+    Applied(ProjectTransaction),
+}
+
 #[derive(Debug, Default)]
 pub enum PrepareRenameResponse {
     Success(Range<Anchor>),
@@ -1336,6 +1353,7 @@ impl Project {
                 toolchain_store: Some(toolchain_store),
 
                 agent_location: None,
+                propose_will_rename_edits: None,
                 downloading_files: Default::default(),
             }
         })
@@ -1573,6 +1591,7 @@ impl Project {
 
                 toolchain_store: Some(toolchain_store),
                 agent_location: None,
+                propose_will_rename_edits: None,
                 downloading_files: Default::default(),
             };
 
@@ -1854,6 +1873,7 @@ impl Project {
                 remotely_created_models: Arc::new(Mutex::new(RemotelyCreatedModels::default())),
                 toolchain_store: None,
                 agent_location: None,
+                propose_will_rename_edits: None,
                 downloading_files: Default::default(),
             };
             project.set_role(role, cx);
@@ -2487,6 +2507,16 @@ impl Project {
         })
     }
 
+    /// Emits the edits that language servers request before an entry is
+    /// renamed as [`Event::WillRenameEditsProposed`], instead of applying them
+    /// before the rename, whenever `propose` returns true for them.
+    pub fn set_propose_will_rename_edits(
+        &mut self,
+        propose: impl Fn(&ProjectEdits, &App) -> bool + 'static,
+    ) {
+        self.propose_will_rename_edits = Some(Box::new(propose));
+    }
+
     /// Renames the project entry with given `entry_id`.
     ///
     /// `new_path` is a relative path to worktree root.
@@ -2523,7 +2553,7 @@ impl Project {
                 };
                 (root_path.join(old_path.as_std_path()), new_abs_path)
             };
-            let transaction = LspStore::will_rename_entry(
+            let (mut transaction, edits) = LspStore::will_rename_entry(
                 lsp_store.clone(),
                 worktree_id,
                 &old_abs_path,
@@ -2532,6 +2562,22 @@ impl Project {
                 cx.clone(),
             )
             .await;
+            let proposed_edits = project
+                .update(cx, |project, cx| {
+                    if project
+                        .propose_will_rename_edits
+                        .as_ref()
+                        .is_some_and(|propose| propose(&edits, cx))
+                    {
+                        Some(edits)
+                    } else {
+                        transaction.0.extend(edits.apply(cx).0);
+                        None
+                    }
+                })
+                .ok()
+                .flatten()
+                .filter(|edits| !edits.is_empty());
 
             let entry = worktree_store
                 .update(cx, |worktree_store, cx| {
@@ -2546,6 +2592,9 @@ impl Project {
                         new_path.clone(),
                         new_abs_path.clone(),
                     ));
+                    if let Some(edits) = proposed_edits {
+                        cx.emit(Event::WillRenameEditsProposed(edits));
+                    }
                 })
                 .ok();
 
@@ -4381,6 +4430,29 @@ impl Project {
         )
     }
 
+    /// Requests the edits of renaming the symbol at the given position without
+    /// applying them, unless the rename also creates, renames or deletes files.
+    pub fn rename_edits<T: ToPointUtf16>(
+        &mut self,
+        buffer: Entity<Buffer>,
+        position: T,
+        new_name: String,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<RenameEdits>> {
+        let push_to_history = true;
+        let position = position.to_point_utf16(buffer.read(cx));
+        self.request_lsp(
+            buffer,
+            LanguageServerToQuery::FirstCapable,
+            GetRenameEdits {
+                position,
+                new_name,
+                push_to_history,
+            },
+            cx,
+        )
+    }
+
     pub fn on_type_format<T: ToPointUtf16>(
         &mut self,
         buffer: Entity<Buffer>,
@@ -102,6 +102,7 @@ impl WorktreeStore {
         client.add_entity_request_handler(Self::handle_delete_project_entry);
         client.add_entity_request_handler(Self::handle_expand_project_entry);
         client.add_entity_request_handler(Self::handle_expand_all_for_project_entry);
+        client.add_entity_request_handler(Self::handle_read_file_range);
     }
 
     pub fn init_remote(client: &AnyProtoClient) {
@@ -1224,6 +1225,18 @@ impl WorktreeStore {
         Worktree::handle_expand_all_for_entry(worktree, envelope.payload, cx).await
     }
 
+    pub async fn handle_read_file_range(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::ReadFileRange>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::ReadFileRangeResponse> {
+        let worktree_id = WorktreeId::from_proto(envelope.payload.worktree_id);
+        let worktree = this
+            .update(&mut cx, |this, cx| this.worktree_for_id(worktree_id, cx))
+            .context("invalid request")?;
+        Worktree::handle_read_file_range(worktree, envelope.payload, cx).await
+    }
+
     pub async fn handle_allocate_worktree_id(
         _this: Entity<Self>,
         _envelope: TypedEnvelope<proto::AllocateWorktreeId>,
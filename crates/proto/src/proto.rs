@@ -192,10 +192,14 @@ messages!(
     (OpenServerSettings, Foreground),
     (PerformRename, Background),
     (PerformRenameResponse, Background),
+    (GetRenameEdits, Background),
+    (GetRenameEditsResponse, Background),
     (Ping, Foreground),
     (PrepareRename, Background),
     (PrepareRenameResponse, Background),
     (ProjectEntryResponse, Foreground),
+    (ReadFileRange, Background),
+    (ReadFileRangeResponse, Background),
     (RefreshInlayHints, Background),
     (RefreshSemanticTokens, Background),
     (RegisterBufferWithLanguageServers, Background),
@@ -355,6 +359,16 @@ messages!(
     (GitCreateWorktree, Background),
     (GitRemoveWorktree, Background),
     (GitRenameWorktree, Background),
+    (GitSetBranchTarget, Background),
+    (GitReflog, Background),
+    (GitReflogResponse, Background),
+    (GitGetSubmodules, Background),
+    (GitGetSubmodulesResponse, Background),
+    (GitSubmoduleOperation, Background),
+    (GitLineHistory, Background),
+    (GitLineHistoryResponse, Background),
+    (GitCommitSignature, Background),
+    (GitCommitSignatureResponse, Background),
     (ShareAgentThread, Foreground),
     (GetSharedAgentThread, Foreground),
     (GetSharedAgentThreadResponse, Foreground),
@@ -436,8 +450,10 @@ request_messages!(
     (OpenCommitMessageBuffer, OpenBufferResponse),
     (OpenNewBuffer, OpenBufferResponse),
     (PerformRename, PerformRenameResponse),
+    (GetRenameEdits, GetRenameEditsResponse),
     (Ping, Ack),
     (PrepareRename, PrepareRenameResponse),
+    (ReadFileRange, ReadFileRangeResponse),
     (RefreshInlayHints, Ack),
     (RefreshSemanticTokens, Ack),
     (RefreshCodeLens, Ack),
@@ -561,6 +577,12 @@ request_messages!(
     (GitCreateWorktree, Ack),
     (GitRemoveWorktree, Ack),
     (GitRenameWorktree, Ack),
+    (GitSetBranchTarget, Ack),
+    (GitReflog, GitReflogResponse),
+    (GitGetSubmodules, GitGetSubmodulesResponse),
+    (GitSubmoduleOperation, RemoteMessageResponse),
+    (GitLineHistory, GitLineHistoryResponse),
+    (GitCommitSignature, GitCommitSignatureResponse),
     (TrustWorktrees, Ack),
     (RestrictWorktrees, Ack),
     (FindSearchCandidatesChunk, Ack),
@@ -649,7 +671,9 @@ entity_messages!(
     OpenBufferForSymbol,
     OpenCommitMessageBuffer,
     PerformRename,
+    GetRenameEdits,
     PrepareRename,
+    ReadFileRange,
     RefreshInlayHints,
     RefreshSemanticTokens,
     RefreshCodeLens,
@@ -752,6 +776,12 @@ entity_messages!(
     GitCreateWorktree,
     GitRemoveWorktree,
     GitRenameWorktree,
+    GitSetBranchTarget,
+    GitReflog,
+    GitGetSubmodules,
+    GitSubmoduleOperation,
+    GitLineHistory,
+    GitCommitSignature,
     TrustWorktrees,
     RestrictWorktrees,
     FindSearchCandidatesChunk,
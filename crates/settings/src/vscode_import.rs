@@ -257,6 +257,9 @@ impl VsCodeSettings {
             diagnostics_max_severity: None,
             double_click_in_multibuffer: None,
             drag_and_drop_selection: None,
+            persistent_undo: None,
+            keyboard_macros: None,
+            clipboard_history: None,
             excerpt_context_lines: None,
             expand_excerpt_lines: None,
             fast_scroll_sensitivity: self.read_f32("editor.fastScrollSensitivity"),
@@ -284,6 +287,7 @@ impl VsCodeSettings {
                 "relative" => Some(RelativeLineNumbers::Enabled),
                 _ => None,
             }),
+            rename_preview: None,
             rounded_selection: self.read_bool("editor.roundedSelection"),
             scroll_beyond_last_line: None,
             scroll_sensitivity: self.read_f32("editor.mouseWheelScrollSensitivity"),
@@ -881,6 +885,9 @@ impl VsCodeSettings {
             scroll_multiplier: None,
             toolbar: None,
             show_count_badge: None,
+            shell_integration: self.read_bool("terminal.integrated.shellIntegration.enabled"),
+            restore_scrollback: self.read_bool("terminal.integrated.enablePersistentSessions"),
+            triggers: None,
             flexible: None,
         })
     }
@@ -1055,6 +1062,7 @@ impl VsCodeSettings {
                         .collect::<Vec<_>>()
                 })
                 .filter(|r| !r.is_empty()),
+            large_file_threshold_mb: None,
         }
     }
 }
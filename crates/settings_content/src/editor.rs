@@ -199,6 +199,23 @@ pub struct EditorSettingsContent {
     /// Drag and drop related settings
     pub drag_and_drop_selection: Option<DragAndDropSelectionContent>,
 
+    /// Undo history persistence related settings
+    pub persistent_undo: Option<PersistentUndoContent>,
+
+    /// Named keyboard macros, which can be played with the `editor::PlayMacro`
+    /// action, e.g. `["editor::PlayMacro", { "name": "my_macro" }]`.
+    ///
+    /// Default: {}
+    pub keyboard_macros: Option<HashMap<String, Vec<KeyboardMacroStep>>>,
+
+    /// Clipboard history related settings
+    pub clipboard_history: Option<ClipboardHistoryContent>,
+
+    /// When to preview the edits of a rename before applying them.
+    ///
+    /// Default: never
+    pub rename_preview: Option<RenamePreviewMode>,
+
     /// How to render LSP `textDocument/documentColor` colors in the editor.
     ///
     /// Default: [`DocumentColorsRenderMode::Inlay`]
@@ -518,6 +535,34 @@ pub enum SeedQuerySetting {
     Never,
 }
 
+/// When to preview the edits of a rename before applying them.
+///
+/// Default: never
+#[derive(
+    Default,
+    Copy,
+    Clone,
+    Debug,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    JsonSchema,
+    MergeFrom,
+    strum::VariantArray,
+    strum::VariantNames,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RenamePreviewMode {
+    /// Apply the edits right away.
+    #[default]
+    Never,
+    /// Preview the edits when they span more than one file.
+    MultipleFiles,
+    /// Always preview the edits.
+    Always,
+}
+
 /// What to do when multibuffer is double clicked in some of its excerpts (parts of singleton buffers).
 #[derive(
     Default,
@@ -868,6 +913,65 @@ pub struct DragAndDropSelectionContent {
     pub delay: Option<DelayMs>,
 }
 
+/// Whether to keep the undo history of files across restarts.
+#[with_fallible_options]
+#[derive(Clone, Default, Debug, Serialize, Deserialize, JsonSchema, MergeFrom, PartialEq, Eq)]
+pub struct PersistentUndoContent {
+    /// When true, the undo and redo history of a file is saved along with it, and
+    /// restored when the file is reopened unless it was changed in the meantime.
+    ///
+    /// Default: false
+    pub enabled: Option<bool>,
+
+    /// The maximum size in kilobytes of the history kept for each file. The oldest
+    /// changes are dropped first when the history exceeds it.
+    ///
+    /// Default: 1024
+    pub max_size_kb: Option<u64>,
+}
+
+/// The history of texts copied or cut in editors.
+#[with_fallible_options]
+#[derive(Clone, Default, Debug, Serialize, Deserialize, JsonSchema, MergeFrom, PartialEq, Eq)]
+pub struct ClipboardHistoryContent {
+    /// The maximum number of entries kept in the history. The oldest entries
+    /// are dropped first.
+    ///
+    /// Default: 50
+    pub max_entries: Option<usize>,
+
+    /// Whether to keep the history across restarts. Turning it off deletes the
+    /// stored history.
+    ///
+    /// Default: false
+    pub persist: Option<bool>,
+
+    /// Whether to leave out of the history copied texts that look like secrets,
+    /// such as private keys, access tokens or password assignments.
+    ///
+    /// Default: true
+    pub exclude_secrets: Option<bool>,
+}
+
+/// A step of a keyboard macro.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, MergeFrom, PartialEq)]
+#[serde(untagged)]
+pub enum KeyboardMacroStep {
+    /// Dispatches an action, e.g. `{ "action": "editor::MoveDown" }`.
+    Action {
+        /// The name of the action.
+        action: String,
+        /// The arguments of the action, if it takes any.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        args: Option<serde_json::Value>,
+    },
+    /// Inserts text as if it was typed, e.g. `{ "text": "hello" }`.
+    Text {
+        /// The text to insert.
+        text: String,
+    },
+}
+
 /// When to show the minimap in the editor.
 ///
 /// Default: never
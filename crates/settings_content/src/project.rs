@@ -139,6 +139,12 @@ pub struct WorktreeSettingsContent {
     /// external dependencies that should not be modified directly.
     /// Default: []
     pub read_only_files: Option<Vec<String>>,
+
+    /// Files larger than this many megabytes are opened in a read-only large file
+    /// viewer instead of an editor, without syntax highlighting or language servers.
+    ///
+    /// Default: 256
+    pub large_file_threshold_mb: Option<u64>,
 }
 
 #[with_fallible_options]
@@ -192,6 +192,9 @@ pub struct SettingsContent {
 
     pub title_bar: Option<TitleBarSettingsContent>,
 
+    /// Configuration for the undo tree panel.
+    pub undo_tree_panel: Option<PanelSettingsContent>,
+
     /// Whether or not to enable Vim mode.
     ///
     /// Default: false
@@ -600,6 +603,12 @@ pub struct GitPanelSettingsContent {
     ///
     /// Default: false
     pub starts_open: Option<bool>,
+
+    /// The key to sign commits with, overriding git's `user.signingkey`.
+    /// When set, commits made from the git panel are always signed.
+    ///
+    /// Default: null
+    pub signing_key: Option<String>,
 }
 
 #[derive(
@@ -179,6 +179,66 @@ pub struct TerminalSettingsContent {
     ///
     /// Default: false
     pub show_count_badge: Option<bool>,
+    /// Whether to load Zed's shell integration into bash, zsh and fish, so that the
+    /// terminal knows where each command's prompt and output are and whether it failed.
+    /// This sets `PROMPT_COMMAND`, `ZDOTDIR` or `XDG_DATA_DIRS` in the shell's environment.
+    /// Only available on macOS and Linux.
+    ///
+    /// Default: false
+    pub shell_integration: Option<bool>,
+    /// Whether to save the scrollback of terminals when Zed quits and show it
+    /// above the new shell prompt when the terminals are restored.
+    ///
+    /// Default: true
+    pub restore_scrollback: Option<bool>,
+    /// Regexes matched against each line of terminal output, to highlight the
+    /// matches, show a notification, open a path or run a task when they match.
+    ///
+    /// Default: []
+    pub triggers: Option<Vec<TerminalTrigger>>,
+}
+
+/// A regex matched against each line of terminal output, along with what to do when it matches.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize, JsonSchema, MergeFrom)]
+pub struct TerminalTrigger {
+    /// The regex to match against each line of output.
+    pub regex: String,
+    /// The theme color to highlight the matches with.
+    #[serde(default)]
+    pub highlight: Option<TerminalTriggerHighlight>,
+    /// The message of a notification to show when the regex matches. Capture
+    /// groups can be referred to as `$1` or `${name}`.
+    #[serde(default)]
+    pub notify: Option<String>,
+    /// Whether to open the path the regex matches, such as `src/main.rs:12`.
+    /// When the regex has a `path` capture group, only that part of the match is opened.
+    #[serde(default)]
+    pub open_path: bool,
+    /// The label of a task to run when the regex matches.
+    #[serde(default)]
+    pub run_task: Option<String>,
+}
+
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    MergeFrom,
+    PartialEq,
+    Eq,
+    strum::VariantArray,
+    strum::VariantNames,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminalTriggerHighlight {
+    Error,
+    Warning,
+    Success,
+    Info,
+    Hint,
 }
 
 /// Shell configuration to open the terminal with.
@@ -1822,6 +1822,131 @@ fn editor_page() -> SettingsPage {
         ]
     }
 
+    fn persistent_undo_section() -> [SettingsPageItem; 3] {
+        [
+            SettingsPageItem::SectionHeader("Persistent Undo"),
+            SettingsPageItem::SettingItem(SettingItem {
+                title: "Enabled",
+                description: "Keep the undo history of files across restarts.",
+                field: Box::new(SettingField {
+                    json_path: Some("persistent_undo.enabled"),
+                    pick: |settings_content| {
+                        settings_content
+                            .editor
+                            .persistent_undo
+                            .as_ref()
+                            .and_then(|persistent_undo| persistent_undo.enabled.as_ref())
+                    },
+                    write: |settings_content, value| {
+                        settings_content
+                            .editor
+                            .persistent_undo
+                            .get_or_insert_default()
+                            .enabled = value;
+                    },
+                }),
+                metadata: None,
+                files: USER,
+            }),
+            SettingsPageItem::SettingItem(SettingItem {
+                title: "Max Size",
+                description: "Maximum size in kilobytes of the undo history kept for each file.",
+                field: Box::new(SettingField {
+                    json_path: Some("persistent_undo.max_size_kb"),
+                    pick: |settings_content| {
+                        settings_content
+                            .editor
+                            .persistent_undo
+                            .as_ref()
+                            .and_then(|persistent_undo| persistent_undo.max_size_kb.as_ref())
+                    },
+                    write: |settings_content, value| {
+                        settings_content
+                            .editor
+                            .persistent_undo
+                            .get_or_insert_default()
+                            .max_size_kb = value;
+                    },
+                }),
+                metadata: None,
+                files: USER,
+            }),
+        ]
+    }
+
+    fn clipboard_history_section() -> [SettingsPageItem; 4] {
+        [
+            SettingsPageItem::SectionHeader("Clipboard History"),
+            SettingsPageItem::SettingItem(SettingItem {
+                title: "Max Entries",
+                description: "Maximum number of copied texts kept in the clipboard history.",
+                field: Box::new(SettingField {
+                    json_path: Some("clipboard_history.max_entries"),
+                    pick: |settings_content| {
+                        settings_content
+                            .editor
+                            .clipboard_history
+                            .as_ref()
+                            .and_then(|clipboard_history| clipboard_history.max_entries.as_ref())
+                    },
+                    write: |settings_content, value| {
+                        settings_content
+                            .editor
+                            .clipboard_history
+                            .get_or_insert_default()
+                            .max_entries = value;
+                    },
+                }),
+                metadata: None,
+                files: USER,
+            }),
+            SettingsPageItem::SettingItem(SettingItem {
+                title: "Persist",
+                description: "Keep the clipboard history across restarts. Turning it off deletes the stored history.",
+                field: Box::new(SettingField {
+                    json_path: Some("clipboard_history.persist"),
+                    pick: |settings_content| {
+                        settings_content
+                            .editor
+                            .clipboard_history
+                            .as_ref()
+                            .and_then(|clipboard_history| clipboard_history.persist.as_ref())
+                    },
+                    write: |settings_content, value| {
+                        settings_content
+                            .editor
+                            .clipboard_history
+                            .get_or_insert_default()
+                            .persist = value;
+                    },
+                }),
+                metadata: None,
+                files: USER,
+            }),
+            SettingsPageItem::SettingItem(SettingItem {
+                title: "Exclude Secrets",
+                description: "Leave copied texts that look like secrets out of the clipboard history.",
+                field: Box::new(SettingField {
+                    json_path: Some("clipboard_history.exclude_secrets"),
+                    pick: |settings_content| {
+                        settings_content.editor.clipboard_history.as_ref().and_then(
+                            |clipboard_history| clipboard_history.exclude_secrets.as_ref(),
+                        )
+                    },
+                    write: |settings_content, value| {
+                        settings_content
+                            .editor
+                            .clipboard_history
+                            .get_or_insert_default()
+                            .exclude_secrets = value;
+                    },
+                }),
+                metadata: None,
+                files: USER,
+            }),
+        ]
+    }
+
     fn gutter_section() -> [SettingsPageItem; 8] {
         [
             SettingsPageItem::SectionHeader("Gutter"),
@@ -2689,6 +2814,8 @@ fn editor_page() -> SettingsPage {
         signature_help_section(),
         hover_popover_section(),
         drag_and_drop_selection_section(),
+        persistent_undo_section(),
+        clipboard_history_section(),
         gutter_section(),
         scrollbar_section(),
         minimap_section(),
@@ -5299,7 +5426,7 @@ fn panels_page() -> SettingsPage {
         ]
     }
 
-    fn git_panel_section() -> [SettingsPageItem; 14] {
+    fn git_panel_section() -> [SettingsPageItem; 15] {
         [
             SettingsPageItem::SectionHeader("Git Panel"),
             SettingsPageItem::SettingItem(SettingItem {
@@ -5517,6 +5644,24 @@ fn panels_page() -> SettingsPage {
                 metadata: None,
                 files: USER,
             }),
+            SettingsPageItem::SettingItem(SettingItem {
+                title: "Signing Key",
+                description: "The key to sign commits with, overriding git's `user.signingkey`. When set, commits made from the Git panel are always signed.",
+                field: Box::new(SettingField {
+                    json_path: Some("git_panel.signing_key"),
+                    pick: |settings_content| {
+                        settings_content.git_panel.as_ref()?.signing_key.as_ref()
+                    },
+                    write: |settings_content, value| {
+                        settings_content
+                            .git_panel
+                            .get_or_insert_default()
+                            .signing_key = value;
+                    },
+                }),
+                metadata: None,
+                files: USER,
+            }),
             SettingsPageItem::SettingItem(SettingItem {
                 title: "Scroll Bar",
                 description: "How and when the scrollbar should be displayed.",
@@ -5803,6 +5948,70 @@ fn panels_page() -> SettingsPage {
         ]
     }
 
+    fn undo_tree_panel_section() -> [SettingsPageItem; 4] {
+        [
+            SettingsPageItem::SectionHeader("Undo Tree Panel"),
+            SettingsPageItem::SettingItem(SettingItem {
+                title: "Undo Tree Panel Button",
+                description: "Show the undo tree panel button in the status bar.",
+                field: Box::new(SettingField {
+                    json_path: Some("undo_tree_panel.button"),
+                    pick: |settings_content| {
+                        settings_content.undo_tree_panel.as_ref()?.button.as_ref()
+                    },
+                    write: |settings_content, value| {
+                        settings_content
+                            .undo_tree_panel
+                            .get_or_insert_default()
+                            .button = value;
+                    },
+                }),
+                metadata: None,
+                files: USER,
+            }),
+            SettingsPageItem::SettingItem(SettingItem {
+                title: "Undo Tree Panel Dock",
+                description: "Where to dock the undo tree panel.",
+                field: Box::new(SettingField {
+                    json_path: Some("undo_tree_panel.dock"),
+                    pick: |settings_content| {
+                        settings_content.undo_tree_panel.as_ref()?.dock.as_ref()
+                    },
+                    write: |settings_content, value| {
+                        settings_content
+                            .undo_tree_panel
+                            .get_or_insert_default()
+                            .dock = value;
+                    },
+                }),
+                metadata: None,
+                files: USER,
+            }),
+            SettingsPageItem::SettingItem(SettingItem {
+                title: "Undo Tree Panel Default Width",
+                description: "Default width of the undo tree panel in pixels.",
+                field: Box::new(SettingField {
+                    json_path: Some("undo_tree_panel.default_width"),
+                    pick: |settings_content| {
+                        settings_content
+                            .undo_tree_panel
+                            .as_ref()?
+                            .default_width
+                            .as_ref()
+                    },
+                    write: |settings_content, value| {
+                        settings_content
+                            .undo_tree_panel
+                            .get_or_insert_default()
+                            .default_width = value;
+                    },
+                }),
+                metadata: None,
+                files: USER,
+            }),
+        ]
+    }
+
     SettingsPage {
         title: "Panels",
         items: concat_sections![
@@ -5814,6 +6023,7 @@ fn panels_page() -> SettingsPage {
             debugger_panel_section(),
             notification_panel_section(),
             collaboration_panel_section(),
+            undo_tree_panel_section(),
             agent_panel_section(),
         ],
     }
@@ -6510,7 +6720,7 @@ fn terminal_page() -> SettingsPage {
         ]
     }
 
-    fn behavior_settings_section() -> [SettingsPageItem; 4] {
+    fn behavior_settings_section() -> [SettingsPageItem; 7] {
         [
             SettingsPageItem::SectionHeader("Behavior Settings"),
             SettingsPageItem::SettingItem(SettingItem {
@@ -6571,6 +6781,68 @@ fn terminal_page() -> SettingsPage {
                 metadata: None,
                 files: USER,
             }),
+            SettingsPageItem::SettingItem(SettingItem {
+                title: "Shell Integration",
+                description: "Whether to load Zed's shell integration into bash, zsh and fish to track commands and their exit codes. Only available on macOS and Linux.",
+                field: Box::new(SettingField {
+                    json_path: Some("terminal.shell_integration"),
+                    pick: |settings_content| {
+                        settings_content
+                            .terminal
+                            .as_ref()?
+                            .shell_integration
+                            .as_ref()
+                    },
+                    write: |settings_content, value| {
+                        settings_content
+                            .terminal
+                            .get_or_insert_default()
+                            .shell_integration = value;
+                    },
+                }),
+                metadata: None,
+                files: USER,
+            }),
+            SettingsPageItem::SettingItem(SettingItem {
+                title: "Restore Scrollback",
+                description: "Whether to save the scrollback of terminals on quit and show it again when they are restored.",
+                field: Box::new(SettingField {
+                    json_path: Some("terminal.restore_scrollback"),
+                    pick: |settings_content| {
+                        settings_content
+                            .terminal
+                            .as_ref()?
+                            .restore_scrollback
+                            .as_ref()
+                    },
+                    write: |settings_content, value| {
+                        settings_content
+                            .terminal
+                            .get_or_insert_default()
+                            .restore_scrollback = value;
+                    },
+                }),
+                metadata: None,
+                files: USER,
+            }),
+            SettingsPageItem::SettingItem(SettingItem {
+                title: "Triggers",
+                description: "Regexes matched against terminal output to highlight matches, show notifications, open paths or run tasks.",
+                field: Box::new(
+                    SettingField {
+                        json_path: Some("terminal.triggers"),
+                        pick: |settings_content| {
+                            settings_content.terminal.as_ref()?.triggers.as_ref()
+                        },
+                        write: |settings_content, value| {
+                            settings_content.terminal.get_or_insert_default().triggers = value;
+                        },
+                    }
+                    .unimplemented(),
+                ),
+                metadata: None,
+                files: USER,
+            }),
         ]
     }
 
@@ -8893,7 +9165,7 @@ fn language_settings_data() -> Box<[SettingsPageItem]> {
 /// LanguageSettings items that should be included in the "Languages & Tools" page
 /// not the "Editor" page
 fn non_editor_language_settings_data() -> Box<[SettingsPageItem]> {
-    fn lsp_section() -> [SettingsPageItem; 8] {
+    fn lsp_section() -> [SettingsPageItem; 9] {
         [
             SettingsPageItem::SectionHeader("LSP"),
             SettingsPageItem::SettingItem(SettingItem {
@@ -8975,6 +9247,19 @@ fn non_editor_language_settings_data() -> Box<[SettingsPageItem]> {
                 metadata: None,
                 files: USER,
             }),
+            SettingsPageItem::SettingItem(SettingItem {
+                title: "Rename Preview",
+                description: "When to preview the edits of a rename before applying them.",
+                field: Box::new(SettingField {
+                    json_path: Some("rename_preview"),
+                    pick: |settings_content| settings_content.editor.rename_preview.as_ref(),
+                    write: |settings_content, value| {
+                        settings_content.editor.rename_preview = value;
+                    },
+                }),
+                metadata: None,
+                files: USER,
+            }),
             SettingsPageItem::SettingItem(SettingItem {
                 title: "Semantic Tokens",
                 description: {
@@ -80,6 +80,12 @@ const TOOLS: &[ToolInfo] = &[
         description: "Discards unsaved changes by reloading from disk",
         regex_explanation: "Patterns are matched against the file path being restored.",
     },
+    ToolInfo {
+        id: "debugger",
+        name: "Debugger",
+        description: "Debug sessions, breakpoints, stepping and expression evaluation",
+        regex_explanation: "Patterns are matched against the operation followed by its argument, such as `start My Scenario`, `set_breakpoint src/main.rs:12` or `evaluate x + 1`.",
+    },
 ];
 
 pub(crate) struct ToolInfo {
@@ -311,6 +317,7 @@ fn get_tool_render_fn(
         "fetch" => render_fetch_tool_config,
         "web_search" => render_web_search_tool_config,
         "restore_file_from_disk" => render_restore_file_from_disk_tool_config,
+        "debugger" => render_debugger_tool_config,
         _ => render_terminal_tool_config, // fallback
     }
 }
@@ -1394,6 +1401,7 @@ tool_config_page_fn!(
     render_restore_file_from_disk_tool_config,
     "restore_file_from_disk"
 );
+tool_config_page_fn!(render_debugger_tool_config, "debugger");
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,141 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A breakpoint saved in a breakpoint set.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SavedBreakpoint {
+    /// Path of the file, relative to the worktree root and using `/` as a separator.
+    pub path: String,
+    /// The 1-based line of the breakpoint.
+    pub line: u32,
+    /// Whether the breakpoint is enabled.
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub enabled: bool,
+    /// An expression that has to be true for the breakpoint to stop.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    /// How many hits of the breakpoint are ignored before it stops.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hit_condition: Option<String>,
+    /// A message to log instead of stopping, making this a log breakpoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_message: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+/// A named group of breakpoints that are loaded and unloaded together.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct BreakpointSet {
+    pub name: String,
+    #[serde(default)]
+    pub breakpoints: Vec<SavedBreakpoint>,
+}
+
+/// The breakpoint sets defined in a JSON file, such as `.zed/breakpoints.json`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+pub struct BreakpointSetFile(pub Vec<BreakpointSet>);
+
+impl BreakpointSetFile {
+    /// Adds the set, replacing the one with the same name if there is one.
+    pub fn insert(&mut self, set: BreakpointSet) {
+        if let Some(existing) = self.0.iter_mut().find(|existing| existing.name == set.name) {
+            *existing = set;
+        } else {
+            self.0.push(set);
+        }
+    }
+
+    /// Renames the set named `name`, replacing the one already named
+    /// `new_name` if there is one.
+    pub fn rename(&mut self, name: &str, new_name: String) {
+        if name == new_name || !self.0.iter().any(|set| set.name == name) {
+            return;
+        }
+        self.0.retain(|set| set.name != new_name);
+        if let Some(set) = self.0.iter_mut().find(|set| set.name == name) {
+            set.name = new_name;
+        }
+    }
+
+    /// Removes the set named `name`.
+    pub fn remove(&mut self, name: &str) {
+        self.0.retain(|set| set.name != name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_breakpoint_set_file_round_trip() {
+        let raw = json!([
+            {
+                "name": "parser",
+                "breakpoints": [
+                    { "path": "src/main.rs", "line": 12 },
+                    {
+                        "path": "src/parser.rs",
+                        "line": 3,
+                        "enabled": false,
+                        "condition": "depth > 2",
+                        "hit_condition": ">= 5",
+                        "log_message": "depth is {depth}"
+                    }
+                ]
+            }
+        ]);
+        let mut file: BreakpointSetFile = serde_json::from_value(raw.clone()).unwrap();
+        assert_eq!(
+            file.0[0].breakpoints[0],
+            SavedBreakpoint {
+                path: "src/main.rs".into(),
+                line: 12,
+                enabled: true,
+                condition: None,
+                hit_condition: None,
+                log_message: None,
+            }
+        );
+        assert_eq!(serde_json::to_value(&file).unwrap(), raw);
+
+        file.insert(BreakpointSet {
+            name: "parser".into(),
+            breakpoints: Vec::new(),
+        });
+        file.insert(BreakpointSet {
+            name: "startup".into(),
+            breakpoints: Vec::new(),
+        });
+        let names = file
+            .0
+            .iter()
+            .map(|set| set.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["parser", "startup"]);
+        assert!(file.0[0].breakpoints.is_empty());
+
+        file.rename("parser", "startup".into());
+        file.rename("missing", "other".into());
+        let names = file
+            .0
+            .iter()
+            .map(|set| set.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["startup"]);
+
+        file.remove("startup");
+        assert!(file.0.is_empty());
+    }
+}
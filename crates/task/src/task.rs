@@ -1,10 +1,12 @@
 //! Baseline interface of Tasks in Zed: all tasks in Zed are intended to use those for implementing their own logic.
 
 mod adapter_schema;
+mod breakpoint_format;
 mod debug_format;
 mod serde_helpers;
 pub mod static_source;
 mod task_template;
+mod vscode_breakpoint_format;
 mod vscode_debug_format;
 mod vscode_format;
 
@@ -18,6 +20,7 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 pub use adapter_schema::{AdapterSchema, AdapterSchemas};
+pub use breakpoint_format::{BreakpointSet, BreakpointSetFile, SavedBreakpoint};
 pub use debug_format::{
     AttachRequest, BuildTaskDefinition, DebugRequest, DebugScenario, DebugTaskFile, LaunchRequest,
     Request, TcpArgumentsTemplate, ZedDebugConfig,
@@ -28,6 +31,7 @@ pub use task_template::{
 };
 pub use util::shell::{Shell, ShellKind};
 pub use util::shell_builder::ShellBuilder;
+pub use vscode_breakpoint_format::VsCodeBreakpointFile;
 pub use vscode_debug_format::VsCodeDebugTaskFile;
 pub use vscode_format::VsCodeTaskFile;
 pub use zed_actions::RevealTarget;
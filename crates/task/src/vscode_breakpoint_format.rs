@@ -0,0 +1,221 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::{BreakpointSet, SavedBreakpoint};
+
+/// The location of a VS Code breakpoint: either a path or file URI, or the
+/// serialized `Uri` object that VS Code stores.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+enum VsCodeUri {
+    String(String),
+    #[serde(rename_all = "camelCase")]
+    Object {
+        #[serde(default)]
+        fs_path: Option<String>,
+        #[serde(default)]
+        path: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct VsCodeBreakpoint {
+    uri: VsCodeUri,
+    line_number: u32,
+    #[serde(default = "default_true")]
+    enabled: bool,
+    #[serde(default)]
+    condition: Option<String>,
+    #[serde(default)]
+    hit_condition: Option<String>,
+    #[serde(default)]
+    log_message: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum VsCodeBreakpoints {
+    Array(Vec<VsCodeBreakpoint>),
+    Object { breakpoints: Vec<VsCodeBreakpoint> },
+}
+
+/// A file of source breakpoints in VS Code's format, either as a bare array or
+/// under a `breakpoints` key.
+///
+/// Each breakpoint has a `uri`, which is a path, a `file://` URI, a path
+/// starting with `${workspaceFolder}/`, or a VS Code `Uri` object, and a 1-based
+/// `lineNumber`. It may also have `enabled`, `condition`, `hitCondition` and
+/// `logMessage`:
+///
+/// ```json
+/// [{ "uri": "${workspaceFolder}/src/main.rs", "lineNumber": 12, "condition": "x > 1" }]
+/// ```
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(from = "VsCodeBreakpoints")]
+pub struct VsCodeBreakpointFile {
+    breakpoints: Vec<VsCodeBreakpoint>,
+}
+
+impl From<VsCodeBreakpoints> for VsCodeBreakpointFile {
+    fn from(breakpoints: VsCodeBreakpoints) -> Self {
+        let (VsCodeBreakpoints::Array(breakpoints) | VsCodeBreakpoints::Object { breakpoints }) =
+            breakpoints;
+        Self { breakpoints }
+    }
+}
+
+impl VsCodeBreakpointFile {
+    /// Converts the breakpoints into a set named `name`, with their paths
+    /// relative to `worktree_root`. Breakpoints outside of the worktree are
+    /// left out.
+    pub fn to_breakpoint_set(&self, name: String, worktree_root: &Path) -> BreakpointSet {
+        let breakpoints = self
+            .breakpoints
+            .iter()
+            .filter_map(|breakpoint| {
+                let Some(path) = relative_path(&breakpoint.uri, worktree_root) else {
+                    log::warn!(
+                        "Skipping VS Code breakpoint outside of {}: {:?}",
+                        worktree_root.display(),
+                        breakpoint.uri
+                    );
+                    return None;
+                };
+                Some(SavedBreakpoint {
+                    path,
+                    line: breakpoint.line_number,
+                    enabled: breakpoint.enabled,
+                    condition: breakpoint.condition.clone(),
+                    hit_condition: breakpoint.hit_condition.clone(),
+                    log_message: breakpoint.log_message.clone(),
+                })
+            })
+            .collect();
+        BreakpointSet { name, breakpoints }
+    }
+}
+
+fn relative_path(uri: &VsCodeUri, worktree_root: &Path) -> Option<String> {
+    let path = match uri {
+        VsCodeUri::String(uri) => match uri.strip_prefix("file://") {
+            Some(path) => urlencoding::decode(path).ok()?.into_owned(),
+            None => uri.clone(),
+        },
+        VsCodeUri::Object { fs_path, path } => fs_path.clone().or_else(|| path.clone())?,
+    };
+    let path = path.replace('\\', "/");
+    if let Some(relative) = path.strip_prefix("${workspaceFolder}/") {
+        return Some(relative.to_string());
+    }
+
+    let root = worktree_root.to_string_lossy().replace('\\', "/");
+    let root = root.trim_end_matches('/');
+    // Windows paths in URIs start with a slash, as in `/c:/project`.
+    let path = path.trim_start_matches('/');
+    let root = root.trim_start_matches('/');
+    let relative = if cfg!(windows) {
+        path.get(..root.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(root))
+            .map(|_| &path[root.len()..])
+    } else {
+        path.strip_prefix(root)
+    };
+    relative?.strip_prefix('/').map(ToString::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use util::path;
+
+    use crate::{BreakpointSet, SavedBreakpoint};
+
+    use super::VsCodeBreakpointFile;
+
+    #[test]
+    fn test_parsing_vscode_breakpoints() {
+        let raw = r#"
+            {
+                "breakpoints": [
+                    {
+                        "uri": "${workspaceFolder}/src/main.rs",
+                        "lineNumber": 12,
+                    },
+                    {
+                        "uri": "file:///project/src/my%20parser.rs",
+                        "lineNumber": 3,
+                        "enabled": false,
+                        "condition": "depth > 2",
+                        "hitCondition": "5",
+                        "logMessage": "depth is {depth}"
+                    },
+                    {
+                        "uri": {
+                            "$mid": 1,
+                            "fsPath": "/project/lib.rs",
+                            "path": "/project/lib.rs",
+                            "scheme": "file"
+                        },
+                        "lineNumber": 7
+                    },
+                    {
+                        "uri": "/elsewhere/main.rs",
+                        "lineNumber": 1
+                    }
+                ]
+            }
+        "#;
+        let file: VsCodeBreakpointFile = serde_json_lenient::from_str(raw).unwrap();
+        let set = file.to_breakpoint_set("VS Code".into(), path!("/project").as_ref());
+        let mut expected = BreakpointSet {
+            name: "VS Code".into(),
+            breakpoints: vec![
+                SavedBreakpoint {
+                    path: "src/main.rs".into(),
+                    line: 12,
+                    enabled: true,
+                    condition: None,
+                    hit_condition: None,
+                    log_message: None,
+                },
+                SavedBreakpoint {
+                    path: "src/my parser.rs".into(),
+                    line: 3,
+                    enabled: false,
+                    condition: Some("depth > 2".into()),
+                    hit_condition: Some("5".into()),
+                    log_message: Some("depth is {depth}".into()),
+                },
+                SavedBreakpoint {
+                    path: "lib.rs".into(),
+                    line: 7,
+                    enabled: true,
+                    condition: None,
+                    hit_condition: None,
+                    log_message: None,
+                },
+            ],
+        };
+        if cfg!(windows) {
+            // The absolute paths above aren't within `C:\project`.
+            expected.breakpoints.truncate(1);
+        }
+        assert_eq!(set, expected);
+
+        let bare: VsCodeBreakpointFile = serde_json_lenient::from_str(
+            r#"[{ "uri": "${workspaceFolder}/a.rs", "lineNumber": 1 }]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            bare.to_breakpoint_set("bare".into(), path!("/project").as_ref())
+                .breakpoints
+                .len(),
+            1
+        );
+    }
+}
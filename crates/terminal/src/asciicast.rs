@@ -0,0 +1,277 @@
+//! Recordings of terminal sessions in the asciicast v2 format, as documented
+//! at <https://docs.asciinema.org/manual/asciicast/v2/>.
+//!
+//! A recording is a JSON header on the first line, followed by one JSON array
+//! per line for each event: `[time, code, data]`, with the time in seconds
+//! since the start of the recording.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter, Write as _},
+    path::Path,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context as _, Result, bail};
+use serde::{Deserialize, Serialize};
+
+/// The first line of a recording.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AsciicastHeader {
+    pub version: u32,
+    /// The size of the terminal when the recording started.
+    pub width: usize,
+    pub height: usize,
+    /// When the recording started, as a Unix timestamp.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    /// The longest pause to keep when replaying, in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_time_limit: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AsciicastEventKind {
+    /// `o`: output written to the terminal.
+    Output(String),
+    /// `r`: the terminal was resized.
+    Resize { columns: usize, lines: usize },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AsciicastEvent {
+    /// Seconds since the start of the recording.
+    pub time: f64,
+    pub kind: AsciicastEventKind,
+}
+
+/// A terminal session recorded in the asciicast v2 format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Asciicast {
+    pub header: AsciicastHeader,
+    pub events: Vec<AsciicastEvent>,
+}
+
+impl Asciicast {
+    /// Parses a recording, skipping the events other than output and resizes.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let header = lines.next().context("the recording is empty")?;
+        let header: AsciicastHeader =
+            serde_json::from_str(header).context("invalid asciicast header")?;
+        if header.version != 2 {
+            bail!("unsupported asciicast version {}", header.version);
+        }
+
+        let mut events = Vec::new();
+        for (ix, line) in lines.enumerate() {
+            let (time, code, data): (f64, String, String) = serde_json::from_str(line)
+                .with_context(|| format!("invalid asciicast event on line {}", ix + 2))?;
+            let kind = match code.as_str() {
+                "o" => AsciicastEventKind::Output(data),
+                "r" => {
+                    let Some((columns, lines)) =
+                        data.split_once('x').and_then(|(columns, lines)| {
+                            Some((columns.parse().ok()?, lines.parse().ok()?))
+                        })
+                    else {
+                        bail!("invalid terminal size {data:?} on line {}", ix + 2);
+                    };
+                    AsciicastEventKind::Resize { columns, lines }
+                }
+                // Input and markers don't affect what's shown.
+                _ => continue,
+            };
+            events.push(AsciicastEvent { time, kind });
+        }
+        // Events are expected in order, but the times of hand-edited recordings may not be.
+        events.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(Self { header, events })
+    }
+
+    /// Returns how long the recording takes to replay, in seconds.
+    pub fn duration(&self) -> f64 {
+        self.events.last().map_or(0., |event| event.time)
+    }
+}
+
+/// Writes a recording to a file while a terminal session goes on.
+pub(crate) struct AsciicastRecorder {
+    writer: BufWriter<File>,
+    started_at: Instant,
+    /// The end of a UTF-8 character that was split across reads.
+    incomplete_char: Vec<u8>,
+    /// The first error that occurred while writing, reported when the recording is finished.
+    error: Option<io::Error>,
+}
+
+impl AsciicastRecorder {
+    pub fn new(path: &Path, columns: usize, lines: usize) -> Result<Self> {
+        let header = AsciicastHeader {
+            version: 2,
+            width: columns,
+            height: lines,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|duration| duration.as_secs()),
+            idle_time_limit: None,
+            title: None,
+            env: BTreeMap::from_iter([("TERM".to_string(), "xterm-256color".to_string())]),
+        };
+        let file = File::create(path)
+            .with_context(|| format!("failed to create recording at {path:?}"))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &header)?;
+        writer.write_all(b"\n")?;
+        Ok(Self {
+            writer,
+            started_at: Instant::now(),
+            incomplete_char: Vec::new(),
+            error: None,
+        })
+    }
+
+    /// Records output read from the PTY.
+    pub fn record_output(&mut self, bytes: &[u8]) {
+        let mut bytes = std::mem::take(&mut self.incomplete_char)
+            .into_iter()
+            .chain(bytes.iter().copied())
+            .collect::<Vec<_>>();
+        // Keep the start of a character that continues in the next read.
+        let valid_len = match std::str::from_utf8(&bytes) {
+            Ok(_) => bytes.len(),
+            Err(error) if error.error_len().is_none() => error.valid_up_to(),
+            Err(_) => bytes.len(),
+        };
+        self.incomplete_char = bytes.split_off(valid_len);
+        if !bytes.is_empty() {
+            self.write_event("o", &String::from_utf8_lossy(&bytes));
+        }
+    }
+
+    /// Records that the terminal was resized.
+    pub fn record_resize(&mut self, columns: usize, lines: usize) {
+        self.write_event("r", &format!("{columns}x{lines}"));
+    }
+
+    fn write_event(&mut self, code: &str, data: &str) {
+        if self.error.is_some() {
+            return;
+        }
+        let time = self.started_at.elapsed().as_secs_f64();
+        let result = serde_json::to_writer(&mut self.writer, &(time, code, data))
+            .map_err(io::Error::from)
+            .and_then(|()| self.writer.write_all(b"\n"));
+        if let Err(error) = result {
+            self.error = Some(error);
+        }
+    }
+
+    /// Writes out what's left of the recording.
+    pub fn finish(mut self) -> Result<()> {
+        if !self.incomplete_char.is_empty() {
+            let incomplete_char = std::mem::take(&mut self.incomplete_char);
+            self.write_event("o", &String::from_utf8_lossy(&incomplete_char));
+        }
+        if let Some(error) = self.error.take() {
+            return Err(error).context("failed to write recording");
+        }
+        self.writer.flush().context("failed to write recording")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.cast");
+        let mut recorder = AsciicastRecorder::new(&path, 80, 24).unwrap();
+        recorder.record_output(b"$ echo \xc3");
+        recorder.record_output(b"\xa9\r\n\x1b[1m\xc3\xa9\x1b[0m\r\n");
+        recorder.record_resize(100, 30);
+        recorder.finish().unwrap();
+
+        let recording = Asciicast::parse(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(recording.header.version, 2);
+        assert_eq!((recording.header.width, recording.header.height), (80, 24));
+        let kinds = recording
+            .events
+            .iter()
+            .map(|event| event.kind.clone())
+            .collect::<Vec<_>>();
+        // Characters split across reads are kept whole.
+        assert_eq!(
+            kinds,
+            vec![
+                AsciicastEventKind::Output("$ echo ".into()),
+                AsciicastEventKind::Output("é\r\n\x1b[1mé\x1b[0m\r\n".into()),
+                AsciicastEventKind::Resize {
+                    columns: 100,
+                    lines: 30
+                },
+            ]
+        );
+        assert!(recording.events.is_sorted_by(|a, b| a.time <= b.time));
+    }
+
+    #[test]
+    fn test_parsing_recordings() {
+        // Input and marker events are skipped.
+        let recording = Asciicast::parse(concat!(
+            r#"{"version": 2, "width": 20, "height": 5, "timestamp": 1718000000, "idle_time_limit": 2.0, "env": {"SHELL": "/bin/zsh", "TERM": "xterm-256color"}}"#,
+            "\n",
+            r#"[0.012, "o", "\u001b[?2004h% "]"#,
+            "\n",
+            r#"[0.8, "i", "l"]"#,
+            "\n",
+            r#"[0.81, "o", "l"]"#,
+            "\n",
+            r#"[1.5, "r", "30x6"]"#,
+            "\n",
+            r#"[2.25, "m", "chapter"]"#,
+            "\n",
+        ))
+        .unwrap();
+        assert_eq!(recording.header.idle_time_limit, Some(2.0));
+        assert_eq!(recording.header.env["SHELL"], "/bin/zsh");
+        assert_eq!(
+            recording.events,
+            vec![
+                AsciicastEvent {
+                    time: 0.012,
+                    kind: AsciicastEventKind::Output("\x1b[?2004h% ".into()),
+                },
+                AsciicastEvent {
+                    time: 0.81,
+                    kind: AsciicastEventKind::Output("l".into()),
+                },
+                AsciicastEvent {
+                    time: 1.5,
+                    kind: AsciicastEventKind::Resize {
+                        columns: 30,
+                        lines: 6
+                    },
+                },
+            ]
+        );
+        assert_eq!(recording.duration(), 1.5);
+
+        assert!(Asciicast::parse("").is_err());
+        assert!(Asciicast::parse(r#"{"version": 1, "width": 1, "height": 1}"#).is_err());
+        assert!(
+            Asciicast::parse(
+                "{\"version\": 2, \"width\": 1, \"height\": 1}\n[0.5, \"r\", \"big\"]"
+            )
+            .is_err()
+        );
+    }
+}
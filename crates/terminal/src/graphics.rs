@@ -0,0 +1,697 @@
+//! Inline images sent with the kitty graphics protocol (in APC sequences) or
+//! as Sixel images (in DCS sequences).
+//!
+//! alacritty ignores both kinds of sequences, so Zed scans the PTY output for
+//! them, like it does for shell integration marks. Each image is placed at
+//! the cursor once the output before it has been processed, and the cursor is
+//! then moved past it by feeding alacritty line feeds and cursor movements.
+
+mod kitty;
+mod sixel;
+
+use std::{collections::VecDeque, ops::RangeInclusive, sync::Arc};
+
+use alacritty_terminal::index::{Column, Line, Point as AlacPoint};
+use anyhow::{Result, anyhow};
+use gpui::{Pixels, RenderImage, Size, px, size};
+use image::RgbaImage;
+
+use crate::shell_integration::{GridOrigin, MarkPosition};
+
+/// Longest graphics sequence that is buffered. The kitty protocol sends
+/// images in small chunks, but Sixel images are sent in one sequence.
+const MAX_SEQUENCE_LEN: usize = 32 * 1024 * 1024;
+/// Largest width or height of an image, in pixels.
+const MAX_IMAGE_DIMENSION: u32 = 10_000;
+/// Largest size of a single decoded image, in bytes, enough for a 4K screenshot.
+const MAX_IMAGE_BYTES: usize = 32 * 1024 * 1024;
+/// Largest size of all the decoded images kept by a terminal, in bytes.
+/// The oldest images are discarded first once this is exceeded.
+const MAX_STORED_IMAGE_BYTES: usize = 64 * 1024 * 1024;
+/// Largest number of columns or rows an image is shown across.
+const MAX_IMAGE_CELLS: usize = 1_000;
+/// Number of image placements kept by a terminal.
+const MAX_PLACEMENTS: usize = 1_000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum ScanState {
+    #[default]
+    Ground,
+    Escape,
+    Apc,
+    Dcs,
+    StringEscape,
+}
+
+/// A complete graphics sequence found in terminal output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum GraphicsSequence {
+    /// The contents of a kitty graphics APC sequence, after its `G`.
+    Kitty(Vec<u8>),
+    /// The contents of a Sixel DCS sequence, including its parameters.
+    Sixel(Vec<u8>),
+}
+
+/// Finds graphics sequences in terminal output, one byte at a time so that
+/// sequences split across reads are still found.
+#[derive(Debug, Default)]
+pub(crate) struct GraphicsScanner {
+    state: ScanState,
+    /// Whether the sequence being read is an APC sequence, as opposed to DCS.
+    apc: bool,
+    data: Vec<u8>,
+    truncated: bool,
+}
+
+impl GraphicsScanner {
+    /// Processes the next byte of output, returning the graphics sequence it
+    /// completes, if any.
+    pub fn advance(&mut self, byte: u8) -> Option<GraphicsSequence> {
+        match (self.state, byte) {
+            (ScanState::Ground, 0x1b) => self.state = ScanState::Escape,
+            (ScanState::Ground, _) => {}
+            (ScanState::Escape, b'_' | b'P') => {
+                self.state = if byte == b'_' {
+                    ScanState::Apc
+                } else {
+                    ScanState::Dcs
+                };
+                self.apc = byte == b'_';
+                self.data.clear();
+                self.truncated = false;
+            }
+            (ScanState::Escape, 0x1b) => {}
+            (ScanState::Escape, _) => self.state = ScanState::Ground,
+            (ScanState::Apc | ScanState::Dcs, 0x1b) => self.state = ScanState::StringEscape,
+            (ScanState::Apc | ScanState::Dcs, 0x18 | 0x1a) => self.state = ScanState::Ground,
+            (ScanState::Apc | ScanState::Dcs, _) => {
+                // APC sequences that aren't graphics commands are skipped.
+                let skipped = self.apc && self.data.is_empty() && byte != b'G';
+                if skipped || self.truncated {
+                    self.truncated = true;
+                } else if self.data.len() < MAX_SEQUENCE_LEN {
+                    self.data.push(byte);
+                } else {
+                    self.truncated = true;
+                    self.data = Vec::new();
+                }
+            }
+            (ScanState::StringEscape, b'\\') => return self.finish(),
+            (ScanState::StringEscape, _) => {
+                // An escape that doesn't terminate the sequence starts a new one.
+                self.state = ScanState::Escape;
+                return self.advance(byte);
+            }
+        }
+        None
+    }
+
+    fn finish(&mut self) -> Option<GraphicsSequence> {
+        self.state = ScanState::Ground;
+        if self.truncated {
+            return None;
+        }
+        let data = std::mem::take(&mut self.data);
+        if self.apc {
+            return Some(GraphicsSequence::Kitty(data.get(1..)?.to_vec()));
+        }
+        // Sixel sequences are the DCS sequences whose parameters end with `q`.
+        let params_len = data
+            .iter()
+            .position(|byte| !(byte.is_ascii_digit() || *byte == b';'))?;
+        (data[params_len] == b'q').then_some(GraphicsSequence::Sixel(data))
+    }
+}
+
+/// How the cursor moves past an image once it's placed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CursorMovement {
+    /// The cursor stays where it is.
+    None,
+    /// The cursor moves to the image's last line, past its last column.
+    AfterImage,
+    /// The cursor moves to the start of the line below the image.
+    BelowImage,
+}
+
+/// A decoded image that is waiting to be placed at the cursor.
+pub(crate) struct PendingImage {
+    image: Arc<RenderImage>,
+    size: Size<Pixels>,
+    columns: usize,
+    rows: usize,
+    /// Whether the image is stretched across its cells, rather than shown at its own size.
+    stretch: bool,
+    cursor_movement: CursorMovement,
+}
+
+struct StoredImage {
+    image: Arc<RenderImage>,
+    size: Size<Pixels>,
+    /// The id the kitty graphics protocol refers to the image with, if any.
+    kitty_id: Option<u32>,
+    bytes: usize,
+}
+
+struct Placement {
+    image: Arc<RenderImage>,
+    size: Size<Pixels>,
+    position: MarkPosition,
+    columns: usize,
+    rows: usize,
+    stretch: bool,
+}
+
+/// An image shown within the visible part of the terminal.
+#[derive(Clone)]
+pub struct TerminalImage {
+    pub image: Arc<RenderImage>,
+    /// The cell at the image's top left corner.
+    pub point: AlacPoint,
+    /// The number of cells the image covers.
+    pub columns: usize,
+    pub rows: usize,
+    /// The size to show the image at, or `None` to stretch it across its cells.
+    pub size: Option<Size<Pixels>>,
+}
+
+/// The images of a terminal, and where they are placed.
+pub(crate) struct Graphics {
+    cell_size: Size<Pixels>,
+    images: VecDeque<StoredImage>,
+    stored_bytes: usize,
+    placements: VecDeque<Placement>,
+    /// A kitty image whose data is being sent in chunks.
+    pending_transmission: Option<kitty::Command>,
+}
+
+impl Graphics {
+    pub fn new(cell_size: Size<Pixels>) -> Self {
+        Self {
+            cell_size,
+            images: VecDeque::new(),
+            stored_bytes: 0,
+            placements: VecDeque::new(),
+            pending_transmission: None,
+        }
+    }
+
+    /// Sets the size of cells, which images are laid out with.
+    pub fn set_cell_size(&mut self, cell_size: Size<Pixels>) {
+        self.cell_size = cell_size;
+    }
+
+    /// Removes all placements, as when the screen is cleared.
+    pub fn clear(&mut self) {
+        self.placements.clear();
+        self.remove_unused_images();
+    }
+
+    /// Moves the placements down after lines were inserted above them.
+    pub fn shift_down(&mut self, lines: usize) {
+        for placement in &mut self.placements {
+            placement.position.line += lines;
+        }
+    }
+
+    /// Moves the placements with the given function, dropping those it returns `None` for.
+    pub fn retain_positions(&mut self, mut f: impl FnMut(MarkPosition) -> Option<MarkPosition>) {
+        let len = self.placements.len();
+        self.placements
+            .retain_mut(|placement| match f(placement.position) {
+                Some(position) => {
+                    placement.position = position;
+                    true
+                }
+                None => false,
+            });
+        if self.placements.len() != len {
+            self.remove_unused_images();
+        }
+    }
+
+    /// Handles a graphics sequence, returning the image to place at the
+    /// cursor, if any, and the response to send to the program.
+    pub fn process(
+        &mut self,
+        sequence: GraphicsSequence,
+    ) -> (Option<PendingImage>, Option<String>) {
+        match sequence {
+            GraphicsSequence::Kitty(data) => match kitty::Command::parse(&data) {
+                Some(command) => self.process_kitty(command),
+                None => (None, None),
+            },
+            GraphicsSequence::Sixel(data) => match sixel::decode(&data) {
+                Ok(image) => {
+                    let (image, size) = self.store(image, None);
+                    let image = self.pending_image(image, size, 0, 0, CursorMovement::BelowImage);
+                    (Some(image), None)
+                }
+                Err(error) => {
+                    log::debug!("failed to decode sixel image: {error:#}");
+                    (None, None)
+                }
+            },
+        }
+    }
+
+    fn process_kitty(
+        &mut self,
+        mut command: kitty::Command,
+    ) -> (Option<PendingImage>, Option<String>) {
+        // Chunks after the first only say whether more follow.
+        if let Some(mut transmission) = self.pending_transmission.take() {
+            transmission.payload.append(&mut command.payload);
+            if transmission.payload.len() > MAX_SEQUENCE_LEN {
+                let response = transmission.response(&Err(anyhow!("EFBIG:too much data")));
+                return (None, response);
+            }
+            transmission.more = command.more;
+            command = transmission;
+        }
+        if command.more {
+            self.pending_transmission = Some(command);
+            return (None, None);
+        }
+
+        let result = match command.action {
+            b'q' => command.decode_image().map(|_| None),
+            b't' | b'T' => command.decode_image().map(|image| {
+                let kitty_id = (command.image_id != 0).then_some(command.image_id);
+                let (image, size) = self.store(image, kitty_id);
+                (command.action == b'T').then(|| self.kitty_placement(&command, image, size))
+            }),
+            b'p' => self
+                .images
+                .iter()
+                .find(|stored| stored.kitty_id == Some(command.image_id))
+                .map(|stored| (stored.image.clone(), stored.size))
+                .ok_or_else(|| anyhow!("ENOENT:no image with id {}", command.image_id))
+                .map(|(image, size)| Some(self.kitty_placement(&command, image, size))),
+            b'd' => {
+                self.delete_kitty_images(&command);
+                return (None, None);
+            }
+            _ => Ok(None),
+        };
+        match result {
+            Ok(image) => (image, command.response(&Ok(()))),
+            Err(error) => (None, command.response(&Err(error))),
+        }
+    }
+
+    fn kitty_placement(
+        &self,
+        command: &kitty::Command,
+        image: Arc<RenderImage>,
+        size: Size<Pixels>,
+    ) -> PendingImage {
+        let cursor_movement = if command.keep_cursor {
+            CursorMovement::None
+        } else {
+            CursorMovement::AfterImage
+        };
+        self.pending_image(
+            image,
+            size,
+            command.columns as usize,
+            command.rows as usize,
+            cursor_movement,
+        )
+    }
+
+    /// Works out the cells an image covers, from the requested number of
+    /// columns and rows, if any, and the image's size otherwise.
+    fn pending_image(
+        &self,
+        image: Arc<RenderImage>,
+        size: Size<Pixels>,
+        columns: usize,
+        rows: usize,
+        cursor_movement: CursorMovement,
+    ) -> PendingImage {
+        let cells = |length: Pixels, cell_length: Pixels| {
+            ((length / cell_length.max(px(1.))).ceil() as usize).clamp(1, MAX_IMAGE_CELLS)
+        };
+        let aspect_ratio = size.width / size.height;
+        let cell_aspect_ratio = self.cell_size.width / self.cell_size.height.max(px(1.));
+        let stretch = columns != 0 || rows != 0;
+        let (cell_columns, cell_rows) = match (columns, rows) {
+            (0, 0) => (
+                cells(size.width, self.cell_size.width),
+                cells(size.height, self.cell_size.height),
+            ),
+            (0, rows) => (
+                ((rows as f32 * aspect_ratio / cell_aspect_ratio).ceil() as usize)
+                    .clamp(1, MAX_IMAGE_CELLS),
+                rows,
+            ),
+            (columns, 0) => (
+                columns,
+                ((columns as f32 * cell_aspect_ratio / aspect_ratio).ceil() as usize)
+                    .clamp(1, MAX_IMAGE_CELLS),
+            ),
+            (columns, rows) => (columns, rows),
+        };
+        PendingImage {
+            image,
+            size,
+            columns: cell_columns,
+            rows: cell_rows,
+            stretch,
+            cursor_movement,
+        }
+    }
+
+    /// Places an image at the given position, returning the output that
+    /// moves the cursor past it.
+    pub fn place(&mut self, image: PendingImage, position: MarkPosition) -> Vec<u8> {
+        let mut cursor_movement = Vec::new();
+        let line_feeds = image.rows.saturating_sub(1).min(MAX_IMAGE_CELLS);
+        match image.cursor_movement {
+            CursorMovement::None => {}
+            CursorMovement::AfterImage => {
+                cursor_movement.extend(std::iter::repeat_n(b'\n', line_feeds));
+                cursor_movement.extend(format!("\x1b[{}C", image.columns).into_bytes());
+            }
+            CursorMovement::BelowImage => {
+                cursor_movement.extend(std::iter::repeat_n(b'\n', line_feeds + 1));
+                cursor_movement.push(b'\r');
+            }
+        }
+
+        if self.placements.len() == MAX_PLACEMENTS {
+            self.placements.pop_front();
+            self.remove_unused_images();
+        }
+        self.placements.push_back(Placement {
+            image: image.image,
+            size: image.size,
+            position,
+            columns: image.columns,
+            rows: image.rows,
+            stretch: image.stretch,
+        });
+        cursor_movement
+    }
+
+    /// Returns the images within the given range of grid lines.
+    pub fn visible_images(
+        &self,
+        origin: GridOrigin,
+        visible_lines: RangeInclusive<Line>,
+    ) -> Vec<TerminalImage> {
+        self.placements
+            .iter()
+            .filter_map(|placement| {
+                let line = origin.grid_line(placement.position);
+                let last_line = Line(
+                    (line.0 as i64 + placement.rows.saturating_sub(1) as i64).min(i32::MAX as i64)
+                        as i32,
+                );
+                (last_line >= *visible_lines.start() && line <= *visible_lines.end()).then(|| {
+                    TerminalImage {
+                        image: placement.image.clone(),
+                        point: AlacPoint::new(line, Column(placement.position.column)),
+                        columns: placement.columns,
+                        rows: placement.rows,
+                        size: (!placement.stretch).then_some(placement.size),
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn store(
+        &mut self,
+        mut pixels: RgbaImage,
+        kitty_id: Option<u32>,
+    ) -> (Arc<RenderImage>, Size<Pixels>) {
+        let size = size(px(pixels.width() as f32), px(pixels.height() as f32));
+        let bytes = pixels.as_raw().len();
+        // gpui expects images in BGRA.
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        let image = Arc::new(RenderImage::new(vec![image::Frame::new(pixels)]));
+
+        if let Some(kitty_id) = kitty_id {
+            // Images sent again with the same id replace the previous one.
+            self.remove_images(|stored| stored.kitty_id == Some(kitty_id));
+        }
+        self.images.push_back(StoredImage {
+            image: image.clone(),
+            size,
+            kitty_id,
+            bytes,
+        });
+        self.stored_bytes += bytes;
+        while self.stored_bytes > MAX_STORED_IMAGE_BYTES && self.images.len() > 1 {
+            let oldest = Arc::as_ptr(&self.images[0].image);
+            self.remove_images(|stored| Arc::as_ptr(&stored.image) == oldest);
+        }
+        (image, size)
+    }
+
+    fn delete_kitty_images(&mut self, command: &kitty::Command) {
+        match command.delete {
+            b'a' => self.placements.clear(),
+            b'A' => {
+                self.placements.clear();
+                self.remove_images(|_| true);
+            }
+            b'i' | b'I' => {
+                let Some(image) = self
+                    .images
+                    .iter()
+                    .find(|stored| stored.kitty_id == Some(command.image_id))
+                    .map(|stored| stored.image.clone())
+                else {
+                    return;
+                };
+                self.placements
+                    .retain(|placement| !Arc::ptr_eq(&placement.image, &image));
+                if command.delete == b'I' {
+                    self.remove_images(|stored| Arc::ptr_eq(&stored.image, &image));
+                }
+            }
+            // Deleting by position or z-index isn't supported.
+            _ => {}
+        }
+        self.remove_unused_images();
+    }
+
+    /// Removes the images matching the predicate, along with their placements.
+    fn remove_images(&mut self, mut predicate: impl FnMut(&StoredImage) -> bool) {
+        let mut removed = Vec::new();
+        self.images.retain(|stored| {
+            let remove = predicate(stored);
+            if remove {
+                removed.push((stored.image.clone(), stored.bytes));
+            }
+            !remove
+        });
+        for (image, bytes) in removed {
+            self.placements
+                .retain(|placement| !Arc::ptr_eq(&placement.image, &image));
+            self.stored_bytes -= bytes;
+        }
+    }
+
+    /// Removes the images that can't be placed again, once they are no longer shown.
+    fn remove_unused_images(&mut self) {
+        let placements = &self.placements;
+        let mut removed_bytes = 0;
+        self.images.retain(|stored| {
+            let used = stored.kitty_id.is_some()
+                || placements
+                    .iter()
+                    .any(|placement| Arc::ptr_eq(&placement.image, &stored.image));
+            if !used {
+                removed_bytes += stored.bytes;
+            }
+            used
+        });
+        self.stored_bytes -= removed_bytes;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(output: &[u8]) -> Vec<GraphicsSequence> {
+        let mut scanner = GraphicsScanner::default();
+        output
+            .iter()
+            .filter_map(|byte| scanner.advance(*byte))
+            .collect()
+    }
+
+    fn place(graphics: &mut Graphics, output: &[u8], line: usize) -> Vec<u8> {
+        let [sequence] = scan(output).try_into().unwrap();
+        let (image, _) = graphics.process(sequence);
+        graphics.place(image.unwrap(), MarkPosition { line, column: 0 })
+    }
+
+    /// Processes all the graphics sequences in the output, returning the images
+    /// to place as BGRA pixels along with their size.
+    fn decode_output(graphics: &mut Graphics, output: &[u8]) -> Vec<((u32, u32), Vec<u8>)> {
+        scan(output)
+            .into_iter()
+            .filter_map(|sequence| graphics.process(sequence).0)
+            .map(|image| {
+                let size = image.image.size(0);
+                let pixels = image.image.as_bytes(0).unwrap().to_vec();
+                ((size.width.0 as u32, size.height.0 as u32), pixels)
+            })
+            .collect()
+    }
+
+    fn bgra_pixel(((width, _), pixels): &((u32, u32), Vec<u8>), x: u32, y: u32) -> [u8; 4] {
+        let ix = (y * width + x) as usize * 4;
+        let [b, g, r, a] = pixels[ix..ix + 4].try_into().unwrap();
+        [r, g, b, a]
+    }
+
+    fn visible_points(graphics: &Graphics) -> Vec<(i32, usize, usize, usize)> {
+        graphics
+            .visible_images(GridOrigin::default(), Line(0)..=Line(23))
+            .into_iter()
+            .map(|image| {
+                (
+                    image.point.line.0,
+                    image.point.column.0,
+                    image.columns,
+                    image.rows,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_scanning_sequences() {
+        let sequences = scan(
+            b"ls\x1b_Ga=T,f=32;/wAA\x1b\\\x1b_not graphics\x1b\\\x1bP1$r\x1b\\\x1bP0;1q#0~\x1b\\\x1b_Ga=q\x18",
+        );
+        assert_eq!(
+            sequences,
+            vec![
+                GraphicsSequence::Kitty(b"a=T,f=32;/wAA".to_vec()),
+                GraphicsSequence::Sixel(b"0;1q#0~".to_vec()),
+            ]
+        );
+
+        // Escape sequences other than the terminator end the sequence.
+        assert_eq!(
+            scan(b"\x1b_Ga=q\x1b[0m\x1b_Gi=1\x1b\\"),
+            vec![GraphicsSequence::Kitty(b"i=1".to_vec())]
+        );
+    }
+
+    #[test]
+    fn test_kitty_images() {
+        let mut graphics = Graphics::new(size(px(10.), px(20.)));
+
+        // A 2x1 image sent in two chunks, and shown at its own size.
+        let [first, last] =
+            scan(b"\x1b_Ga=T,f=32,s=2,v=1,i=5,m=1;/wAA/wAA\x1b\\\x1b_Gm=0;//8=\x1b\\")
+                .try_into()
+                .ok()
+                .unwrap();
+        assert!(matches!(graphics.process(first), (None, None)));
+        let (image, response) = graphics.process(last);
+        assert_eq!(response.as_deref(), Some("\x1b_Gi=5;OK\x1b\\"));
+        let cursor_movement = graphics.place(image.unwrap(), MarkPosition { line: 1, column: 2 });
+        assert_eq!(cursor_movement, b"\x1b[1C");
+        let images = graphics.visible_images(0, Line(0)..=Line(23));
+        assert_eq!(images[0].size, Some(size(px(2.), px(1.))));
+
+        // Placed again, stretched across three rows and as many columns as keep its aspect ratio.
+        let cursor_movement = place(&mut graphics, b"\x1b_Ga=p,i=5,r=3,q=2\x1b\\", 4);
+        assert_eq!(cursor_movement, b"\n\n\x1b[12C");
+        assert_eq!(visible_points(&graphics), vec![(1, 2, 1, 1), (4, 0, 12, 3)]);
+        assert_eq!(graphics.visible_images(0, Line(0)..=Line(23))[1].size, None);
+
+        // Placements scroll with the output, and are only returned while visible.
+        graphics.shift_down(2);
+        let visible_lines = |lines| {
+            graphics
+                .visible_images(4, lines)
+                .into_iter()
+                .map(|image| image.point.line.0)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(visible_lines(Line(-1)..=Line(2)), vec![-1, 2]);
+        assert_eq!(visible_lines(Line(3)..=Line(26)), vec![2]);
+
+        let [missing] = scan(b"\x1b_Ga=p,i=9\x1b\\").try_into().unwrap();
+        let (image, response) = graphics.process(missing);
+        assert!(image.is_none());
+        assert_eq!(
+            response.as_deref(),
+            Some("\x1b_Gi=9;ENOENT:no image with id 9\x1b\\")
+        );
+
+        // Images with ids are kept for later placements until they are deleted.
+        graphics.clear();
+        assert_eq!(graphics.images.len(), 1);
+        let [delete] = scan(b"\x1b_Ga=d,d=I,i=5\x1b\\").try_into().unwrap();
+        assert!(matches!(graphics.process(delete), (None, None)));
+        assert!(graphics.images.is_empty());
+        assert_eq!(graphics.stored_bytes, 0);
+    }
+
+    #[test]
+    fn test_kitten_icat_output() {
+        // A 64x48 PNG, as `kitten icat` sends it in 4096 byte chunks.
+        let mut graphics = Graphics::new(size(px(10.), px(20.)));
+        let [image] = decode_output(
+            &mut graphics,
+            include_bytes!("../test_data/kitten-icat.out"),
+        )
+        .try_into()
+        .unwrap();
+        assert_eq!(image.0, (64, 48));
+        assert_eq!(bgra_pixel(&image, 0, 0), [0, 0, 220, 255]);
+        assert_eq!(bgra_pixel(&image, 10, 20), [40, 100, 72, 255]);
+        assert_eq!(bgra_pixel(&image, 63, 47), [252, 235, 248, 255]);
+    }
+
+    #[test]
+    fn test_img2sixel_output() {
+        // A 64x48 image of 4x4 blocks in 16 colors, as `img2sixel` encodes it.
+        let mut graphics = Graphics::new(size(px(10.), px(20.)));
+        let [image] = decode_output(&mut graphics, include_bytes!("../test_data/img2sixel.out"))
+            .try_into()
+            .unwrap();
+        assert_eq!(image.0, (64, 48));
+        assert_eq!(bgra_pixel(&image, 0, 0), [0, 0, 255, 255]);
+        assert_eq!(bgra_pixel(&image, 20, 13), [84, 84, 178, 255]);
+        assert_eq!(bgra_pixel(&image, 63, 47), [252, 252, 25, 255]);
+    }
+
+    #[test]
+    fn test_sixel_images() {
+        let mut graphics = Graphics::new(size(px(10.), px(20.)));
+
+        // Sixel images leave the cursor at the start of the line below them.
+        let cursor_movement = place(&mut graphics, b"\x1bPq#0;2;100;0;0!12~-~\x1b\\", 0);
+        assert_eq!(cursor_movement, b"\n\r");
+        assert_eq!(visible_points(&graphics), vec![(0, 0, 2, 1)]);
+
+        // The oldest placements are dropped once there are too many, along
+        // with the images that aren't shown anymore.
+        for line in 1..=MAX_PLACEMENTS {
+            place(&mut graphics, b"\x1bPq#0~\x1b\\", line);
+        }
+        assert_eq!(graphics.placements.len(), MAX_PLACEMENTS);
+        assert_eq!(graphics.images.len(), MAX_PLACEMENTS);
+        assert_eq!(graphics.stored_bytes, MAX_PLACEMENTS * 6 * 4);
+        assert_eq!(graphics.placements[0].position.line, 1);
+
+        graphics.clear();
+        assert!(graphics.images.is_empty());
+        assert_eq!(graphics.stored_bytes, 0);
+    }
+}
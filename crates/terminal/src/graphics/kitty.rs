@@ -0,0 +1,289 @@
+//! The kitty graphics protocol, as documented at
+//! <https://sw.kovidgoyal.net/kitty/graphics-protocol/>.
+//!
+//! Images can only be transmitted directly within the escape sequences, as
+//! the files and shared memory the other transmission media refer to may not
+//! be on this machine.
+
+use std::io::{Cursor, Read as _};
+
+use anyhow::{Context as _, Result, anyhow, bail};
+use base64::{
+    Engine as _, alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+};
+use flate2::read::ZlibDecoder;
+use image::{DynamicImage, ImageDecoder as _, RgbaImage, codecs::png::PngDecoder};
+
+use super::{MAX_IMAGE_BYTES, MAX_IMAGE_CELLS, MAX_IMAGE_DIMENSION};
+
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// A graphics command, from the control data and payload of an APC sequence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Command {
+    /// `a`: what to do, transmitting the image by default.
+    pub action: u8,
+    /// `f`: the format of the image data, either 24 (RGB), 32 (RGBA) or 100 (PNG).
+    pub format: u32,
+    /// `t`: how the image data is transmitted.
+    pub medium: u8,
+    /// `o`: how the image data is compressed, if it is.
+    pub compression: Option<u8>,
+    /// `s` and `v`: the size of RGB and RGBA images, in pixels.
+    pub width: u32,
+    pub height: u32,
+    /// `i`: the id the client refers to the image with, or 0.
+    pub image_id: u32,
+    /// `m`: whether more chunks of image data follow.
+    pub more: bool,
+    /// `c` and `r`: the number of cells to show the image in, or 0 to
+    /// derive them from the image's size.
+    pub columns: u32,
+    pub rows: u32,
+    /// `C`: whether the cursor stays in place after showing the image.
+    pub keep_cursor: bool,
+    /// `q`: 1 to suppress successful responses, 2 to suppress all of them.
+    pub quiet: u32,
+    /// `d`: what to delete.
+    pub delete: u8,
+    /// The base64 encoded image data.
+    pub payload: Vec<u8>,
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Self {
+            action: b't',
+            format: 32,
+            medium: b'd',
+            compression: None,
+            width: 0,
+            height: 0,
+            image_id: 0,
+            more: false,
+            columns: 0,
+            rows: 0,
+            keep_cursor: false,
+            quiet: 0,
+            delete: b'a',
+            payload: Vec::new(),
+        }
+    }
+}
+
+impl Command {
+    /// Parses the contents of an APC sequence, following the `G` that marks it
+    /// as a graphics command.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let (control, payload) = match data.iter().position(|byte| *byte == b';') {
+            Some(ix) => (&data[..ix], &data[ix + 1..]),
+            None => (data, &[][..]),
+        };
+        let mut command = Self {
+            payload: payload.to_vec(),
+            ..Self::default()
+        };
+        for pair in control.split(|byte| *byte == b',') {
+            let Some((&[key], value)) = pair
+                .iter()
+                .position(|byte| *byte == b'=')
+                .map(|ix| (&pair[..ix], &pair[ix + 1..]))
+            else {
+                continue;
+            };
+            let number = || std::str::from_utf8(value).ok()?.parse::<u32>().ok();
+            let letter = || match value {
+                [letter] => Some(*letter),
+                _ => None,
+            };
+            match key {
+                b'a' => command.action = letter()?,
+                b'f' => command.format = number()?,
+                b't' => command.medium = letter()?,
+                b'o' => command.compression = Some(letter()?),
+                b's' => command.width = number()?,
+                b'v' => command.height = number()?,
+                b'i' => command.image_id = number()?,
+                b'm' => command.more = number()? == 1,
+                b'c' => command.columns = number()?.min(MAX_IMAGE_CELLS as u32),
+                b'r' => command.rows = number()?.min(MAX_IMAGE_CELLS as u32),
+                b'C' => command.keep_cursor = number()? == 1,
+                b'q' => command.quiet = number()?,
+                b'd' => command.delete = letter()?,
+                // Source rectangles, offsets within cells, z-indices and
+                // placement ids are not supported.
+                _ => {}
+            }
+        }
+        Some(command)
+    }
+
+    /// Returns the response to send to the client for this command, if any.
+    pub fn response(&self, result: &Result<()>) -> Option<String> {
+        // Clients that don't give an id for the image don't expect responses.
+        if self.image_id == 0 {
+            return None;
+        }
+        let message = match result {
+            Ok(()) if self.quiet == 0 => "OK".to_string(),
+            Err(error) if self.quiet < 2 => {
+                let error = format!("{error:#}");
+                // Errors start with their code, like "ENOENT:...".
+                if error.starts_with('E') && error.contains(':') {
+                    error
+                } else {
+                    format!("EINVAL:{error}")
+                }
+            }
+            _ => return None,
+        };
+        Some(format!("\x1b_Gi={};{message}\x1b\\", self.image_id))
+    }
+
+    /// Decodes the image transmitted with this command.
+    pub fn decode_image(&self) -> Result<RgbaImage> {
+        if self.medium != b'd' {
+            bail!("EINVAL:only direct transmission is supported");
+        }
+        let data = BASE64
+            .decode(&self.payload)
+            .context("EINVAL:invalid base64 data")?;
+        let data = match self.compression {
+            None => data,
+            Some(b'z') => {
+                let mut decompressed = Vec::new();
+                ZlibDecoder::new(data.as_slice())
+                    .take(MAX_IMAGE_BYTES as u64 + 1)
+                    .read_to_end(&mut decompressed)
+                    .context("EINVAL:invalid compressed data")?;
+                decompressed
+            }
+            Some(compression) => bail!(
+                "EINVAL:unsupported compression {:?}",
+                char::from(compression)
+            ),
+        };
+
+        let image = match self.format {
+            24 | 32 => {
+                let (width, height) = (self.width, self.height);
+                check_size(width, height)?;
+                let pixels = width as usize * height as usize;
+                let mut rgba = Vec::with_capacity(pixels * 4);
+                if self.format == 24 {
+                    let data = data.get(..pixels * 3).context("ENODATA:too little data")?;
+                    for pixel in data.chunks_exact(3) {
+                        rgba.extend_from_slice(pixel);
+                        rgba.push(u8::MAX);
+                    }
+                } else {
+                    let data = data.get(..pixels * 4).context("ENODATA:too little data")?;
+                    rgba.extend_from_slice(data);
+                }
+                RgbaImage::from_raw(width, height, rgba)
+                    .ok_or_else(|| anyhow!("EINVAL:invalid image size"))?
+            }
+            100 => {
+                // The size is checked before the image data is decoded.
+                let decoder =
+                    PngDecoder::new(Cursor::new(&data)).context("EBADPNG:invalid PNG data")?;
+                let (width, height) = decoder.dimensions();
+                check_size(width, height)?;
+                DynamicImage::from_decoder(decoder)
+                    .context("EBADPNG:invalid PNG data")?
+                    .into_rgba8()
+            }
+            format => bail!("EINVAL:unsupported format {format}"),
+        };
+        Ok(image)
+    }
+}
+
+fn check_size(width: u32, height: u32) -> Result<()> {
+    if width == 0 || height == 0 {
+        bail!("EINVAL:the image is empty");
+    }
+    if width > MAX_IMAGE_DIMENSION
+        || height > MAX_IMAGE_DIMENSION
+        || width as usize * height as usize * 4 > MAX_IMAGE_BYTES
+    {
+        bail!("EFBIG:the image is too large");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsing_commands() {
+        let command = Command::parse(b"a=T,f=24,s=2,v=3,i=7,C=1,m=1,X=4;AAAA").unwrap();
+        assert_eq!(
+            command,
+            Command {
+                action: b'T',
+                format: 24,
+                width: 2,
+                height: 3,
+                image_id: 7,
+                keep_cursor: true,
+                more: true,
+                payload: b"AAAA".to_vec(),
+                ..Command::default()
+            }
+        );
+        // Images are shown across a bounded number of cells.
+        let command = Command::parse(b"a=p,c=4294967295,r=100000").unwrap();
+        assert_eq!(
+            (command.columns, command.rows),
+            (MAX_IMAGE_CELLS as u32, MAX_IMAGE_CELLS as u32)
+        );
+        assert_eq!(Command::parse(b"a=Tx"), None);
+        assert_eq!(Command::parse(b"i=x"), None);
+    }
+
+    #[test]
+    fn test_decoding_images() {
+        let command = Command::parse(b"f=32,s=2,v=1;/wAA/wAA//8=").unwrap();
+        let image = command.decode_image().unwrap();
+        assert_eq!(image.dimensions(), (2, 1));
+        assert_eq!(image.as_raw(), &[255, 0, 0, 255, 0, 0, 255, 255]);
+
+        let command = Command::parse(b"f=24,s=2,v=2,o=z;eJxj+M/AAEMAGfID/Q==").unwrap();
+        let image = command.decode_image().unwrap();
+        assert_eq!(image.dimensions(), (2, 2));
+        assert_eq!(image.get_pixel(1, 1).0, [0, 255, 0, 255]);
+
+        let command = Command::parse(b"f=32,s=2,v=2;/wAA/wAA//8=").unwrap();
+        assert!(command.decode_image().is_err());
+        let command = Command::parse(b"f=32,s=20000,v=1;/wAA/wAA//8=").unwrap();
+        assert!(command.decode_image().is_err());
+        let command = Command::parse(b"t=f,s=1,v=1;L3RtcC9pbWFnZQ==").unwrap();
+        assert!(command.decode_image().is_err());
+    }
+
+    #[test]
+    fn test_responses() {
+        let command = Command::parse(b"a=q,i=31").unwrap();
+        assert_eq!(
+            command.response(&Ok(())).as_deref(),
+            Some("\x1b_Gi=31;OK\x1b\\")
+        );
+        assert_eq!(
+            command
+                .response(&Err(anyhow!("ENOENT:no such image")))
+                .as_deref(),
+            Some("\x1b_Gi=31;ENOENT:no such image\x1b\\")
+        );
+
+        let quiet = Command::parse(b"a=q,i=31,q=1").unwrap();
+        assert_eq!(quiet.response(&Ok(())), None);
+        let anonymous = Command::parse(b"a=q").unwrap();
+        assert_eq!(anonymous.response(&Ok(())), None);
+    }
+}
@@ -0,0 +1,242 @@
+//! Decoding of Sixel images, as described in the VT330/VT340 programmer
+//! reference manual.
+
+use anyhow::{Result, bail};
+use image::RgbaImage;
+
+use super::{MAX_IMAGE_BYTES, MAX_IMAGE_DIMENSION};
+
+/// The 16 colors VT340 terminals start with, as RGB percentages.
+const DEFAULT_PALETTE: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [20, 20, 80],
+    [80, 13, 13],
+    [20, 80, 20],
+    [80, 20, 80],
+    [20, 80, 80],
+    [80, 80, 20],
+    [53, 53, 53],
+    [26, 26, 26],
+    [33, 33, 60],
+    [60, 26, 26],
+    [33, 60, 33],
+    [60, 33, 60],
+    [33, 60, 60],
+    [60, 60, 33],
+    [80, 80, 80],
+];
+
+const PALETTE_SIZE: usize = 256;
+
+/// Decodes a Sixel image from the contents of its DCS sequence, starting
+/// with the parameters that precede `q`.
+pub(crate) fn decode(data: &[u8]) -> Result<RgbaImage> {
+    let Some(start) = data.iter().position(|byte| *byte == b'q') else {
+        bail!("missing sixel data");
+    };
+    let mut decoder = Decoder::new();
+    let mut bytes = data[start + 1..].iter().copied().peekable();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'"' => {
+                let params = read_params(&mut bytes);
+                // Pan;Pad;Ph;Pv: the aspect ratio, then the size of the image.
+                if let [_, _, width, height, ..] = params[..] {
+                    decoder.width = decoder.width.max(width.min(MAX_IMAGE_DIMENSION as usize));
+                    decoder.height = decoder.height.max(height.min(MAX_IMAGE_DIMENSION as usize));
+                }
+            }
+            b'#' => {
+                let params = read_params(&mut bytes);
+                decoder.select_color(&params);
+            }
+            b'!' => {
+                let params = read_params(&mut bytes);
+                let count = params.first().copied().unwrap_or(1).max(1);
+                if let Some(sixel) = bytes.next_if(|byte| (0x3f..=0x7e).contains(byte)) {
+                    decoder.draw(sixel - 0x3f, count);
+                }
+            }
+            b'$' => decoder.x = 0,
+            b'-' => {
+                decoder.x = 0;
+                decoder.y += 6;
+            }
+            0x3f..=0x7e => decoder.draw(byte - 0x3f, 1),
+            _ => {}
+        }
+    }
+    decoder.finish()
+}
+
+fn read_params(bytes: &mut std::iter::Peekable<impl Iterator<Item = u8>>) -> Vec<usize> {
+    let mut params = vec![0usize];
+    while let Some(byte) = bytes.next_if(|byte| byte.is_ascii_digit() || *byte == b';') {
+        let last = params.last_mut().expect("params are never empty");
+        if byte == b';' {
+            params.push(0);
+        } else {
+            *last = last
+                .saturating_mul(10)
+                .saturating_add((byte - b'0') as usize);
+        }
+    }
+    params
+}
+
+struct Decoder {
+    palette: Vec<[u8; 4]>,
+    color: [u8; 4],
+    /// Rows of pixels, each as long as its rightmost pixel that was drawn.
+    rows: Vec<Vec<[u8; 4]>>,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Decoder {
+    fn new() -> Self {
+        let mut palette = vec![[0, 0, 0, u8::MAX]; PALETTE_SIZE];
+        for (color, [r, g, b]) in palette.iter_mut().zip(DEFAULT_PALETTE) {
+            *color = [
+                percent(r as usize),
+                percent(g as usize),
+                percent(b as usize),
+                u8::MAX,
+            ];
+        }
+        Self {
+            color: palette[0],
+            palette,
+            rows: Vec::new(),
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+        }
+    }
+
+    /// Handles `#Pc`, which selects a color, and `#Pc;Pu;Px;Py;Pz`, which
+    /// defines one in either the HLS (`Pu` = 1) or RGB (`Pu` = 2) color space.
+    fn select_color(&mut self, params: &[usize]) {
+        let index = params[0] % PALETTE_SIZE;
+        if let [_, space, x, y, z, ..] = *params {
+            let color = match space {
+                1 => hls_to_rgb(x, y, z),
+                2 => [percent(x), percent(y), percent(z), u8::MAX],
+                _ => return,
+            };
+            self.palette[index] = color;
+        }
+        self.color = self.palette[index];
+    }
+
+    /// Draws a column of six pixels `count` times, with bit 0 at the top.
+    fn draw(&mut self, sixel: u8, count: usize) {
+        let max = MAX_IMAGE_DIMENSION as usize;
+        let end = self.x.saturating_add(count).min(max);
+        for bit in 0..6 {
+            let y = self.y + bit;
+            if sixel & (1 << bit) == 0 || y >= max || self.x >= end {
+                continue;
+            }
+            if self.rows.len() <= y {
+                self.rows.resize_with(y + 1, Vec::new);
+            }
+            let row = &mut self.rows[y];
+            if row.len() < end {
+                row.resize(end, [0; 4]);
+            }
+            row[self.x..end].fill(self.color);
+        }
+        self.x = end;
+    }
+
+    fn finish(self) -> Result<RgbaImage> {
+        let width = self
+            .rows
+            .iter()
+            .map(|row| row.len())
+            .max()
+            .unwrap_or(0)
+            .max(self.width);
+        let height = self.rows.len().max(self.height);
+        if width == 0 || height == 0 {
+            bail!("the image is empty");
+        }
+        if width * height * 4 > MAX_IMAGE_BYTES {
+            bail!("the image is too large");
+        }
+
+        let mut image = RgbaImage::new(width as u32, height as u32);
+        for (y, row) in self.rows.iter().enumerate() {
+            for (x, pixel) in row.iter().enumerate() {
+                image.put_pixel(x as u32, y as u32, image::Rgba(*pixel));
+            }
+        }
+        Ok(image)
+    }
+}
+
+fn percent(value: usize) -> u8 {
+    (value.min(100) * 255 / 100) as u8
+}
+
+fn hls_to_rgb(hue: usize, lightness: usize, saturation: usize) -> [u8; 4] {
+    let lightness = lightness.min(100) as f32 / 100.;
+    let saturation = saturation.min(100) as f32 / 100.;
+    // Sixel hues start at blue rather than red.
+    let hue = ((hue + 240) % 360) as f32 / 360.;
+    let q = if lightness < 0.5 {
+        lightness * (1. + saturation)
+    } else {
+        lightness + saturation - lightness * saturation
+    };
+    let p = 2. * lightness - q;
+    let channel = |t: f32| {
+        let t = t.rem_euclid(1.);
+        let value = if t < 1. / 6. {
+            p + (q - p) * 6. * t
+        } else if t < 1. / 2. {
+            q
+        } else if t < 2. / 3. {
+            p + (q - p) * (2. / 3. - t) * 6.
+        } else {
+            p
+        };
+        (value * 255.).round() as u8
+    };
+    [
+        channel(hue + 1. / 3.),
+        channel(hue),
+        channel(hue - 1. / 3.),
+        u8::MAX,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decoding_sixel() {
+        // A 4x12 image with two colors, whose bottom right quarter is left unset.
+        let image = decode(b"0;1;0q\"1;1;4;12#0;2;100;0;0#1;2;0;0;100#0!4~-#1~~").unwrap();
+        assert_eq!(image.dimensions(), (4, 12));
+        assert_eq!(image.get_pixel(3, 5).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(1, 6).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(3, 11).0, [0, 0, 0, 0]);
+
+        // Bits select the rows of each sixel, and `$` returns to the start of the band.
+        let image = decode(b"q#1;1;0;50;100@$#2;2;0;100;0!2A").unwrap();
+        assert_eq!(image.dimensions(), (2, 2));
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(0, 1).0, [0, 255, 0, 255]);
+        assert_eq!(image.get_pixel(1, 0).0, [0, 0, 0, 0]);
+        assert_eq!(image.get_pixel(1, 1).0, [0, 255, 0, 255]);
+
+        assert!(decode(b"q").is_err());
+        assert!(decode(b"#0~").is_err());
+    }
+}
@@ -0,0 +1,258 @@
+//! Saving a terminal's scrollback as text with SGR sequences, so that it can
+//! be replayed into a new terminal with its colors and attributes.
+
+use std::fmt::Write as _;
+
+use alacritty_terminal::{
+    Term,
+    grid::{Dimensions as _, GridCell as _},
+    index::{Column, Line, Point as AlacPoint},
+    term::{
+        TermMode,
+        cell::{Cell, Flags},
+    },
+    vte::ansi::{Color, NamedColor, Processor, StdSyncHandler},
+};
+
+use crate::ZedListener;
+
+/// Cell flags that are saved along with the text.
+const STYLE_FLAGS: Flags = Flags::BOLD
+    .union(Flags::DIM)
+    .union(Flags::ITALIC)
+    .union(Flags::ALL_UNDERLINES)
+    .union(Flags::INVERSE)
+    .union(Flags::HIDDEN)
+    .union(Flags::STRIKEOUT);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct CellStyle {
+    fg: Color,
+    bg: Color,
+    flags: Flags,
+}
+
+impl Default for CellStyle {
+    fn default() -> Self {
+        Self {
+            fg: Color::Named(NamedColor::Foreground),
+            bg: Color::Named(NamedColor::Background),
+            flags: Flags::empty(),
+        }
+    }
+}
+
+impl CellStyle {
+    fn of(cell: &Cell) -> Self {
+        Self {
+            fg: cell.fg,
+            bg: cell.bg,
+            flags: cell.flags & STYLE_FLAGS,
+        }
+    }
+
+    fn write_sgr(&self, output: &mut String) {
+        output.push_str("\x1b[0");
+        for (flag, code) in [
+            (Flags::BOLD, "1"),
+            (Flags::DIM, "2"),
+            (Flags::ITALIC, "3"),
+            (Flags::INVERSE, "7"),
+            (Flags::HIDDEN, "8"),
+            (Flags::STRIKEOUT, "9"),
+        ] {
+            if self.flags.contains(flag) {
+                output.push(';');
+                output.push_str(code);
+            }
+        }
+        if self.flags.contains(Flags::DOUBLE_UNDERLINE) {
+            output.push_str(";21");
+        } else if self.flags.intersects(Flags::ALL_UNDERLINES) {
+            output.push_str(";4");
+        }
+        write_color(output, self.fg, 30);
+        write_color(output, self.bg, 40);
+        output.push('m');
+    }
+}
+
+fn write_color(output: &mut String, color: Color, base: usize) {
+    match color {
+        Color::Named(named) => {
+            let index = named as usize;
+            if index < 8 {
+                write!(output, ";{}", base + index).ok();
+            } else if index < 16 {
+                write!(output, ";{}", base + 60 + index - 8).ok();
+            }
+            // The default and dim colors follow from the reset and the DIM flag.
+        }
+        Color::Indexed(index) => {
+            write!(output, ";{};5;{index}", base + 8).ok();
+        }
+        Color::Spec(rgb) => {
+            write!(output, ";{};2;{};{};{}", base + 8, rgb.r, rgb.g, rgb.b).ok();
+        }
+    }
+}
+
+/// Returns up to `max_lines` lines of the terminal, ending with the cursor's
+/// line, as text with SGR sequences for the colors and attributes of cells.
+pub(crate) fn serialize(term: &Term<ZedListener>, max_lines: usize) -> String {
+    let grid = term.grid();
+    let columns = grid.columns();
+    let last_line = grid.cursor.point.line.0;
+    let first_line = (last_line as i64 + 1 - max_lines as i64).max(grid.topmost_line().0 as i64);
+
+    let mut output = String::new();
+    let mut style = CellStyle::default();
+    for line in first_line as i32..=last_line {
+        let cells = &grid[Line(line)][..Column(columns)];
+        // Wrapped lines are left for the terminal to wrap again when replayed.
+        let wraps = cells
+            .last()
+            .is_some_and(|cell| cell.flags.contains(Flags::WRAPLINE));
+        let len = if wraps {
+            columns
+        } else {
+            cells
+                .iter()
+                .rposition(|cell| !cell.is_empty())
+                .map_or(0, |ix| ix + 1)
+        };
+
+        for cell in &cells[..len] {
+            if cell
+                .flags
+                .intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+            {
+                continue;
+            }
+            let cell_style = CellStyle::of(cell);
+            if cell_style != style {
+                cell_style.write_sgr(&mut output);
+                style = cell_style;
+            }
+            output.push(cell.c);
+            if let Some(zerowidth) = cell.zerowidth() {
+                output.extend(zerowidth);
+            }
+        }
+
+        if !wraps && line != last_line {
+            // Reset the style first, so that backgrounds don't extend into the next line.
+            if style != CellStyle::default() {
+                output.push_str("\x1b[0m");
+                style = CellStyle::default();
+            }
+            output.push_str("\r\n");
+        }
+    }
+    if style != CellStyle::default() {
+        output.push_str("\x1b[0m");
+    }
+    output
+}
+
+/// Writes saved scrollback into the terminal above what has been printed so
+/// far, returning how many lines that output was moved down by.
+pub(crate) fn restore(term: &mut Term<ZedListener>, scrollback: &str) -> usize {
+    if scrollback.is_empty() || term.mode().contains(TermMode::ALT_SCREEN) {
+        return 0;
+    }
+
+    let absolute_cursor_line =
+        |term: &Term<ZedListener>| term.history_size() + term.grid().cursor.point.line.0 as usize;
+    let cursor = term.grid().cursor.point;
+    let cursor_line = absolute_cursor_line(term);
+
+    // Take out what the shell has printed, to put it back below the scrollback.
+    let printed_rows = (0..=cursor.line.0)
+        .map(|line| term.grid()[Line(line)].clone())
+        .collect::<Vec<_>>();
+    term.grid_mut().reset_region(..=cursor.line);
+    term.grid_mut().cursor.point = AlacPoint::new(Line(0), Column(0));
+
+    let mut processor = Processor::<StdSyncHandler>::new();
+    processor.advance(&mut *term, scrollback.as_bytes());
+    processor.advance(&mut *term, b"\x1b[0m\r\n");
+    for (ix, row) in printed_rows.into_iter().enumerate() {
+        if ix > 0 {
+            processor.advance(&mut *term, b"\r\n");
+        }
+        let line = term.grid().cursor.point.line;
+        term.grid_mut()[line] = row;
+    }
+    term.grid_mut().cursor.point.column = cursor.column;
+
+    absolute_cursor_line(term).saturating_sub(cursor_line)
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::term::Config;
+    use futures::channel::mpsc::unbounded;
+
+    use super::*;
+    use crate::TerminalBounds;
+
+    fn new_term() -> Term<ZedListener> {
+        let (events_tx, _events_rx) = unbounded();
+        Term::new(
+            Config::default(),
+            &TerminalBounds::default(),
+            ZedListener::new(events_tx),
+        )
+    }
+
+    fn write(term: &mut Term<ZedListener>, output: &str) {
+        let mut processor = Processor::<StdSyncHandler>::new();
+        processor.advance(term, output.as_bytes());
+    }
+
+    fn line_text(term: &Term<ZedListener>, line: i32) -> String {
+        let columns = term.grid().columns();
+        term.grid()[Line(line)][..Column(columns)]
+            .iter()
+            .map(|cell| cell.c)
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn test_scrollback_round_trip() {
+        let mut term = new_term();
+        write(
+            &mut term,
+            "\x1b[1;31mred\x1b[0m plain\r\n\x1b[48;2;1;2;3mrgb\x1b[0m\r\n$ ",
+        );
+        let scrollback = serialize(&term, 100);
+        assert_eq!(
+            scrollback,
+            "\x1b[0;1;31mred\x1b[0m plain\r\n\x1b[0;48;2;1;2;3mrgb\x1b[0m\r\n$"
+        );
+        // Only the last lines are kept.
+        assert_eq!(serialize(&term, 1), "$");
+
+        // The new shell's prompt ends up below the restored output.
+        let mut restored = new_term();
+        write(&mut restored, "> ");
+        assert_eq!(restore(&mut restored, &scrollback), 3);
+        assert_eq!(line_text(&restored, 0), "red plain");
+        assert_eq!(line_text(&restored, 1), "rgb");
+        assert_eq!(line_text(&restored, 2), "$");
+        assert_eq!(line_text(&restored, 3), ">");
+        assert_eq!(
+            restored.grid().cursor.point,
+            AlacPoint::new(Line(3), Column(2))
+        );
+
+        let red = &restored.grid()[Line(0)][Column(0)];
+        assert_eq!(red.fg, Color::Named(NamedColor::Red));
+        assert!(red.flags.contains(Flags::BOLD));
+        let plain = &restored.grid()[Line(0)][Column(4)];
+        assert_eq!(plain.fg, Color::Named(NamedColor::Foreground));
+    }
+}
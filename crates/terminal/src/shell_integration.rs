@@ -0,0 +1,1182 @@
+//! Shell integration through semantic prompt sequences, either OSC 133 (used
+//! by FinalTerm, iTerm2 and kitty) or OSC 633 (used by VS Code).
+//!
+//! Shells mark where their prompt starts (`A`), where the command line starts
+//! (`B`), where the command's output starts (`C`) and where it ends along with
+//! the command's exit code (`D`). alacritty ignores these sequences, so Zed
+//! scans the PTY output for them and records where the terminal's cursor was
+//! once the output up to each mark had been processed.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use alacritty_terminal::{
+    Term,
+    grid::Dimensions as _,
+    index::{Column, Line, Point as AlacPoint},
+    term::{TermMode, cell::Flags},
+};
+use anyhow::{Context as _, Result};
+use collections::HashMap;
+
+use crate::ZedListener;
+
+const BASH_SCRIPT: &str = include_str!("shell_integration/zed.bash");
+const ZSH_SCRIPT: &str = include_str!("shell_integration/zed.zsh");
+const ZSHENV_SCRIPT: &str = include_str!("shell_integration/zshenv.zsh");
+const FISH_SCRIPT: &str = include_str!("shell_integration/zed.fish");
+
+/// Longest OSC sequence that is buffered while looking for marks. Longer ones,
+/// such as clipboard writes, can't be marks.
+const MAX_OSC_LEN: usize = 4096;
+/// Number of command blocks kept for each terminal.
+const MAX_COMMAND_BLOCKS: usize = 10_000;
+
+/// A semantic prompt mark emitted by the shell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ShellMark {
+    PromptStart,
+    CommandStart,
+    CommandExecuted,
+    CommandFinished { exit_code: Option<i32> },
+    CommandLine(String),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum ScanState {
+    #[default]
+    Ground,
+    Escape,
+    Osc,
+    OscEscape,
+}
+
+/// Finds semantic prompt marks in terminal output, one byte at a time so
+/// that sequences split across reads are still found.
+#[derive(Debug, Default)]
+pub(crate) struct MarkScanner {
+    state: ScanState,
+    params: Vec<u8>,
+    truncated: bool,
+}
+
+impl MarkScanner {
+    /// Processes the next byte of output, returning the mark whose sequence
+    /// it completes, if any.
+    pub fn advance(&mut self, byte: u8) -> Option<ShellMark> {
+        match (self.state, byte) {
+            (ScanState::Ground, 0x1b) => self.state = ScanState::Escape,
+            (ScanState::Ground, _) => {}
+            (ScanState::Escape, b']') => {
+                self.state = ScanState::Osc;
+                self.params.clear();
+                self.truncated = false;
+            }
+            (ScanState::Escape, 0x1b) => {}
+            (ScanState::Escape, _) => self.state = ScanState::Ground,
+            (ScanState::Osc, 0x07) => return self.finish(),
+            (ScanState::Osc, 0x1b) => self.state = ScanState::OscEscape,
+            (ScanState::Osc, 0x18 | 0x1a) => self.state = ScanState::Ground,
+            (ScanState::Osc, _) => {
+                if self.params.len() < MAX_OSC_LEN {
+                    self.params.push(byte);
+                } else {
+                    self.truncated = true;
+                }
+            }
+            (ScanState::OscEscape, b'\\') => return self.finish(),
+            (ScanState::OscEscape, _) => {
+                // An escape that doesn't terminate the sequence starts a new one.
+                self.state = ScanState::Escape;
+                return self.advance(byte);
+            }
+        }
+        None
+    }
+
+    fn finish(&mut self) -> Option<ShellMark> {
+        self.state = ScanState::Ground;
+        if self.truncated {
+            return None;
+        }
+        parse_mark(&self.params)
+    }
+}
+
+fn parse_mark(params: &[u8]) -> Option<ShellMark> {
+    let params = std::str::from_utf8(params).ok()?;
+    let mut params = params.split(';');
+    let code = params.next()?;
+    if code != "133" && code != "633" {
+        return None;
+    }
+    match params.next()? {
+        "A" => Some(ShellMark::PromptStart),
+        "B" => Some(ShellMark::CommandStart),
+        "C" => Some(ShellMark::CommandExecuted),
+        "D" => Some(ShellMark::CommandFinished {
+            exit_code: params.next().and_then(|code| code.parse().ok()),
+        }),
+        "E" if code == "633" => Some(ShellMark::CommandLine(unescape_command_line(
+            params.next().unwrap_or_default(),
+        ))),
+        _ => None,
+    }
+}
+
+/// Undoes the escaping of OSC 633 command lines, where backslashes are doubled
+/// and other characters may be written as `\xAB`.
+fn unescape_command_line(escaped: &str) -> String {
+    let mut bytes = Vec::with_capacity(escaped.len());
+    let mut rest = escaped.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        match rest {
+            [b'\\', tail @ ..] => {
+                bytes.push(b'\\');
+                rest = tail;
+            }
+            [b'x', high, low, tail @ ..] if high.is_ascii_hexdigit() && low.is_ascii_hexdigit() => {
+                let hex = [*high, *low];
+                let hex = std::str::from_utf8(&hex).unwrap_or_default();
+                bytes.push(u8::from_str_radix(hex, 16).unwrap_or_default());
+                rest = tail;
+            }
+            _ => bytes.push(b'\\'),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// A point in the terminal's output, counted from the first line the terminal
+/// ever output, so that it stays in place while output scrolls and old lines
+/// are dropped from a full scrollback.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct MarkPosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Where the terminal's grid is within all of the lines it has output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct GridOrigin {
+    history_size: usize,
+    /// The number of lines dropped from the top of the scrollback.
+    evicted_lines: usize,
+}
+
+impl GridOrigin {
+    /// The first line that is still in the scrollback.
+    pub fn first_line(&self) -> usize {
+        self.evicted_lines
+    }
+
+    pub fn position(&self, point: AlacPoint) -> MarkPosition {
+        MarkPosition {
+            line: (self.evicted_lines + self.history_size)
+                .saturating_add_signed(point.line.0 as isize),
+            column: point.column.0,
+        }
+    }
+
+    /// Returns the position of the terminal's cursor.
+    pub fn cursor(&self, term: &Term<ZedListener>) -> MarkPosition {
+        self.position(term.grid().cursor.point)
+    }
+
+    pub fn grid_line(&self, position: MarkPosition) -> Line {
+        let line = position.line as i64 - (self.evicted_lines + self.history_size) as i64;
+        Line(line.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+    }
+}
+
+/// Counts the lines dropped from the top of a full scrollback.
+///
+/// alacritty keeps the scrollback in a ring buffer of rows, which it rotates
+/// as lines are pushed into the scrollback. Following how far the newest row
+/// in the scrollback moved up tells how many lines were pushed, and those
+/// that didn't grow the scrollback were dropped from its top.
+#[derive(Default)]
+pub(crate) struct ScrollbackTracker {
+    evicted_lines: usize,
+    /// The scrollback's size as of the last update.
+    history_size: usize,
+    /// The address of the cells of the newest row in the scrollback as of
+    /// the last update, if there was any.
+    anchor: Option<usize>,
+}
+
+impl ScrollbackTracker {
+    pub fn origin(&self, term: &Term<ZedListener>) -> GridOrigin {
+        // The alternate screen has no scrollback, and is laid out as if it
+        // replaced the visible part of the normal screen.
+        let history_size = if term.mode().contains(TermMode::ALT_SCREEN) {
+            self.history_size
+        } else {
+            term.grid().history_size()
+        };
+        GridOrigin {
+            history_size,
+            evicted_lines: self.evicted_lines,
+        }
+    }
+
+    /// Accounts for the lines dropped since the last update, given the number
+    /// of line feeds output since then if it's known, returning the resulting
+    /// origin of the grid.
+    pub fn update(&mut self, term: &Term<ZedListener>, line_feeds: Option<usize>) -> GridOrigin {
+        // The normal screen doesn't scroll while the alternate screen is shown.
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            return self.origin(term);
+        }
+
+        let history_size = term.grid().history_size();
+        let grown_lines = history_size.saturating_sub(self.history_size);
+        if let Some(anchor) = self.anchor {
+            let depth = (1..=history_size)
+                .find(|depth| row_address(term, Line(-(*depth as i32))) == anchor);
+            match depth {
+                Some(depth) => {
+                    let mut pushed_lines = depth - 1;
+                    // After at least as many lines as there are rows, the row
+                    // may have come around again, which only the line feeds
+                    // can tell.
+                    if let Some(line_feeds) = line_feeds
+                        && line_feeds >= pushed_lines + term.grid().total_lines()
+                    {
+                        pushed_lines = pushed_lines.max(line_feeds);
+                    }
+                    self.evicted_lines += pushed_lines.saturating_sub(grown_lines);
+                }
+                // The row is gone when the scrollback is cleared.
+                None if history_size < self.history_size => {
+                    self.evicted_lines += self.history_size - history_size;
+                }
+                // Otherwise it was dropped along with all the lines above it.
+                None => {
+                    let pushed_lines = line_feeds.unwrap_or_default().max(history_size);
+                    self.evicted_lines += pushed_lines.saturating_sub(grown_lines);
+                }
+            }
+        }
+        self.reanchor(term)
+    }
+
+    /// Follows the newest row in the scrollback without counting lines as
+    /// dropped, as after the rows were moved by resizing the grid.
+    pub fn reanchor(&mut self, term: &Term<ZedListener>) -> GridOrigin {
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            return self.origin(term);
+        }
+        self.history_size = term.grid().history_size();
+        self.anchor = (self.history_size > 0).then(|| row_address(term, Line(-1)));
+        self.origin(term)
+    }
+}
+
+fn row_address(term: &Term<ZedListener>, line: Line) -> usize {
+    std::ptr::from_ref(&term.grid()[line][Column(0)]) as usize
+}
+
+/// Returns the number of line feeds in the output, each of which may scroll
+/// the terminal by a line.
+pub(crate) fn count_line_feeds(output: &[u8]) -> usize {
+    output
+        .iter()
+        .filter(|byte| matches!(byte, b'\n' | 0x0b | 0x0c))
+        .count()
+}
+
+/// Moves positions to where their text is after the terminal's lines are
+/// reflowed to a different number of columns.
+///
+/// Positions are kept as a logical line, which is a run of rows joined by soft
+/// wraps, counted from the cursor's logical line, and an offset within it.
+pub(crate) struct Reflow {
+    cursor_logical_line: usize,
+    /// The logical line of each row, from the top of the scrollback.
+    logical_lines: Vec<usize>,
+    columns: usize,
+}
+
+impl Reflow {
+    pub fn new(term: &Term<ZedListener>) -> Self {
+        let grid = term.grid();
+        let topmost_line = grid.topmost_line();
+        let last_column = grid.last_column();
+        let mut logical_lines = Vec::with_capacity(grid.total_lines());
+        let mut logical_line = 0;
+        for row in 0..grid.total_lines() {
+            logical_lines.push(logical_line);
+            let line = topmost_line + row as i32;
+            if !grid[line][last_column].flags.contains(Flags::WRAPLINE) {
+                logical_line += 1;
+            }
+        }
+        let cursor_row = (grid.cursor.point.line.0 - topmost_line.0) as usize;
+        Self {
+            cursor_logical_line: logical_lines.get(cursor_row).copied().unwrap_or_default(),
+            logical_lines,
+            columns: grid.columns(),
+        }
+    }
+
+    /// Moves a position, given the grid's origin before and after reflowing,
+    /// and what the grid was like before. Returns `None` if its line is gone.
+    pub fn reflow(
+        &self,
+        position: MarkPosition,
+        old_origin: GridOrigin,
+        new: &Self,
+        new_origin: GridOrigin,
+    ) -> Option<MarkPosition> {
+        let old_row = position.line.checked_sub(old_origin.first_line())?;
+        let logical_line = *self.logical_lines.get(old_row)?;
+        let first_row = self
+            .logical_lines
+            .partition_point(|line| *line < logical_line);
+        let offset = (old_row - first_row) * self.columns + position.column;
+
+        let new_logical_line =
+            (new.cursor_logical_line + logical_line).checked_sub(self.cursor_logical_line)?;
+        let new_first_row = new
+            .logical_lines
+            .partition_point(|line| *line < new_logical_line);
+        let new_last_row = new
+            .logical_lines
+            .partition_point(|line| *line <= new_logical_line);
+        if new_first_row == new_last_row {
+            return None;
+        }
+        let columns = new.columns.max(1);
+        let new_row = (new_first_row + offset / columns).min(new_last_row - 1);
+        let column = if new_row == new_first_row + offset / columns {
+            offset % columns
+        } else {
+            columns - 1
+        };
+        Some(MarkPosition {
+            line: new_origin.first_line() + new_row,
+            column,
+        })
+    }
+}
+
+/// A mark along with where and when it was emitted.
+#[derive(Clone, Debug)]
+pub(crate) struct ResolvedMark {
+    pub mark: ShellMark,
+    pub position: MarkPosition,
+    pub time: Instant,
+}
+
+/// A command run in the terminal, from its prompt to the end of its output.
+#[derive(Clone, Debug)]
+pub struct CommandBlock {
+    /// The command line, when reported by the shell.
+    pub command: Option<String>,
+    /// The command's exit code, when reported by the shell.
+    pub exit_code: Option<i32>,
+    /// When the command started running.
+    pub started_at: Option<Instant>,
+    /// How long the command ran for, once it has finished.
+    pub duration: Option<Duration>,
+    pub(crate) prompt_start: MarkPosition,
+    pub(crate) command_start: Option<MarkPosition>,
+    pub(crate) output_start: Option<MarkPosition>,
+    pub(crate) output_end: Option<MarkPosition>,
+}
+
+/// The state of a command, shown next to its prompt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandStatus {
+    Running,
+    Succeeded,
+    Failed,
+    /// The command finished without reporting an exit code.
+    Finished,
+}
+
+impl CommandBlock {
+    fn new(prompt_start: MarkPosition) -> Self {
+        Self {
+            command: None,
+            exit_code: None,
+            started_at: None,
+            duration: None,
+            prompt_start,
+            command_start: None,
+            output_start: None,
+            output_end: None,
+        }
+    }
+
+    /// Whether the command was run, as opposed to the prompt still waiting for input.
+    pub fn was_run(&self) -> bool {
+        self.output_start.is_some()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.output_end.is_some()
+    }
+
+    /// Returns the state of the command, or `None` if it hasn't been run.
+    pub fn status(&self) -> Option<CommandStatus> {
+        if !self.was_run() {
+            None
+        } else if !self.is_finished() {
+            Some(CommandStatus::Running)
+        } else {
+            match self.exit_code {
+                Some(0) => Some(CommandStatus::Succeeded),
+                Some(_) => Some(CommandStatus::Failed),
+                None => Some(CommandStatus::Finished),
+            }
+        }
+    }
+
+    fn finish(&mut self, position: MarkPosition, time: Instant, exit_code: Option<i32>) {
+        self.output_end = Some(position);
+        self.exit_code = exit_code;
+        self.duration = self
+            .started_at
+            .map(|started_at| time.saturating_duration_since(started_at));
+    }
+}
+
+/// A prompt marker within the visible part of the terminal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PromptMarker {
+    pub line: Line,
+    pub status: CommandStatus,
+}
+
+/// Tracks the command blocks of a terminal from the marks its shell emits.
+#[derive(Default)]
+pub(crate) struct ShellIntegration {
+    /// Scans the output written to display-only terminals.
+    pub scanner: MarkScanner,
+    blocks: Vec<CommandBlock>,
+}
+
+impl ShellIntegration {
+    pub fn blocks(&self) -> &[CommandBlock] {
+        &self.blocks
+    }
+
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+    }
+
+    /// Moves the blocks down after lines were inserted above them.
+    pub fn shift_down(&mut self, lines: usize) {
+        for block in &mut self.blocks {
+            let positions = [
+                Some(&mut block.prompt_start),
+                block.command_start.as_mut(),
+                block.output_start.as_mut(),
+                block.output_end.as_mut(),
+            ];
+            for position in positions.into_iter().flatten() {
+                position.line += lines;
+            }
+        }
+    }
+
+    /// Moves the blocks with the given function, dropping those whose prompt
+    /// it returns `None` for, and the other positions it returns `None` for.
+    pub fn retain_positions(&mut self, mut f: impl FnMut(MarkPosition) -> Option<MarkPosition>) {
+        self.blocks.retain_mut(|block| {
+            let Some(prompt_start) = f(block.prompt_start) else {
+                return false;
+            };
+            block.prompt_start = prompt_start;
+            for position in [
+                &mut block.command_start,
+                &mut block.output_start,
+                &mut block.output_end,
+            ] {
+                *position = position.and_then(&mut f);
+            }
+            true
+        });
+    }
+
+    pub fn apply(&mut self, resolved: ResolvedMark) {
+        let ResolvedMark {
+            mark,
+            position,
+            time,
+        } = resolved;
+        match mark {
+            ShellMark::PromptStart => {
+                if let Some(last) = self.blocks.last_mut() {
+                    if !last.was_run() && last.command_start.is_none() {
+                        // The shell redrew its prompt without running anything.
+                        last.prompt_start = position;
+                        return;
+                    }
+                    if last.was_run() && !last.is_finished() {
+                        last.finish(position, time, None);
+                    }
+                }
+                self.push(CommandBlock::new(position));
+            }
+            ShellMark::CommandStart => {
+                self.pending_block(position).command_start = Some(position);
+            }
+            ShellMark::CommandLine(command) => {
+                self.pending_block(position).command = Some(command);
+            }
+            ShellMark::CommandExecuted => {
+                let block = self.pending_block(position);
+                block.output_start = Some(position);
+                block.started_at = Some(time);
+            }
+            ShellMark::CommandFinished { exit_code } => {
+                if let Some(last) = self.blocks.last_mut()
+                    && last.was_run()
+                    && !last.is_finished()
+                {
+                    last.finish(position, time, exit_code);
+                }
+            }
+        }
+    }
+
+    /// Returns the block whose command hasn't started running yet, starting a
+    /// new one for shells that didn't mark their prompt.
+    fn pending_block(&mut self, position: MarkPosition) -> &mut CommandBlock {
+        if self.blocks.last().is_none_or(|last| last.was_run()) {
+            self.push(CommandBlock::new(position));
+        }
+        self.blocks.last_mut().expect("a block was just pushed")
+    }
+
+    fn push(&mut self, block: CommandBlock) {
+        if self.blocks.len() == MAX_COMMAND_BLOCKS {
+            self.blocks.remove(0);
+        }
+        self.blocks.push(block);
+    }
+
+    /// Returns markers for the prompts of commands that were run and are
+    /// within the given range of grid lines.
+    pub fn prompt_markers(
+        &self,
+        origin: GridOrigin,
+        visible_lines: std::ops::RangeInclusive<Line>,
+    ) -> Vec<PromptMarker> {
+        self.blocks
+            .iter()
+            .filter_map(|block| {
+                let line = origin.grid_line(block.prompt_start);
+                let status = block.status()?;
+                visible_lines
+                    .contains(&line)
+                    .then_some(PromptMarker { line, status })
+            })
+            .collect()
+    }
+}
+
+/// Loads Zed's shell integration into the given shell through its environment.
+/// Shells other than bash, zsh and fish are left as they are.
+pub(crate) fn install(program: &str, env: &mut HashMap<String, String>) -> Result<()> {
+    let Some(shell) = Path::new(program)
+        .file_name()
+        .and_then(|name| name.to_str())
+    else {
+        return Ok(());
+    };
+    let dir = paths::shell_integration_dir();
+    match shell {
+        "bash" => {
+            let script = write_script(&dir.join("bash"), "zed.bash", BASH_SCRIPT)?;
+            let bootstrap = format!("builtin source {}", shell_quote(&script));
+            let prompt_command = match inherited_env_var(env, "PROMPT_COMMAND") {
+                Some(prompt_command) => {
+                    env.insert("ZED_USER_PROMPT_COMMAND_EXPORTED".to_string(), "1".into());
+                    format!("{bootstrap}\n{prompt_command}")
+                }
+                None => bootstrap.clone(),
+            };
+            env.insert("ZED_SHELL_INTEGRATION_BOOTSTRAP".to_string(), bootstrap);
+            env.insert("PROMPT_COMMAND".to_string(), prompt_command);
+        }
+        "zsh" => {
+            let zsh_dir = dir.join("zsh");
+            write_script(&zsh_dir, ".zshenv", ZSHENV_SCRIPT)?;
+            write_script(&zsh_dir, "zed.zsh", ZSH_SCRIPT)?;
+            if let Some(zdotdir) = inherited_env_var(env, "ZDOTDIR") {
+                env.insert("ZED_USER_ZDOTDIR".to_string(), zdotdir);
+            }
+            env.insert(
+                "ZDOTDIR".to_string(),
+                zsh_dir.to_string_lossy().into_owned(),
+            );
+        }
+        "fish" => {
+            let fish_dir = dir.join("fish");
+            write_script(
+                &fish_dir.join("fish").join("vendor_conf.d"),
+                "zed.fish",
+                FISH_SCRIPT,
+            )?;
+            let data_dirs = match inherited_env_var(env, "XDG_DATA_DIRS") {
+                Some(data_dirs) => {
+                    env.insert("ZED_USER_XDG_DATA_DIRS".to_string(), data_dirs.clone());
+                    format!("{}:{data_dirs}", fish_dir.display())
+                }
+                // The default value from the XDG Base Directory specification.
+                None => format!("{}:/usr/local/share:/usr/share", fish_dir.display()),
+            };
+            env.insert("XDG_DATA_DIRS".to_string(), data_dirs);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn inherited_env_var(env: &HashMap<String, String>, name: &str) -> Option<String> {
+    env.get(name)
+        .cloned()
+        .or_else(|| std::env::var(name).ok())
+        .filter(|value| !value.is_empty())
+}
+
+fn write_script(dir: &Path, file_name: &str, contents: &str) -> Result<PathBuf> {
+    let path = dir.join(file_name);
+    if fs::read(&path).is_ok_and(|existing| existing == contents.as_bytes()) {
+        return Ok(path);
+    }
+    fs::create_dir_all(dir)
+        .with_context(|| format!("creating shell integration directory {dir:?}"))?;
+    fs::write(&path, contents)
+        .with_context(|| format!("writing shell integration script {path:?}"))?;
+    Ok(path)
+}
+
+fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', r"'\''"))
+}
+
+#[cfg(unix)]
+pub(crate) use pty::{HeldBackOutput, ShellIntegrationPty, Unresolved};
+
+#[cfg(unix)]
+mod pty {
+    use std::{
+        fs::File,
+        io::{self, Read, Write as _},
+        os::unix::net::UnixStream,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::Instant,
+    };
+
+    use alacritty_terminal::{
+        event::{Event as AlacTermEvent, EventListener as _, OnResize, WindowSize},
+        tty::{ChildEvent, EventedPty, EventedReadWrite, Pty},
+    };
+    use parking_lot::Mutex;
+    use polling::{Event as PollingEvent, PollMode, Poller};
+
+    use super::{MarkScanner, ShellMark, count_line_feeds};
+    use crate::{
+        ZedListener,
+        asciicast::AsciicastRecorder,
+        graphics::{Graphics, GraphicsScanner, PendingImage},
+        terminal_triggers::TriggerScanner,
+    };
+
+    /// The token alacritty's event loop registers the PTY's reads and writes with.
+    const PTY_READ_WRITE_TOKEN: usize = 0;
+
+    /// Something in the output whose position is only known once alacritty
+    /// has processed the output before it.
+    pub(crate) enum Unresolved {
+        Mark(ShellMark, Instant),
+        Image(PendingImage),
+    }
+
+    #[derive(Default)]
+    enum HoldState {
+        #[default]
+        Reading,
+        /// alacritty hasn't processed the output up to this yet.
+        Unprocessed(Unresolved),
+        /// alacritty has processed the output up to this, so the terminal can resolve it.
+        Processed(Unresolved),
+        Resolving,
+        /// The terminal has resolved it, and the given output is to be
+        /// handed over before the held back output.
+        Resolved(Vec<u8>),
+    }
+
+    /// The output the PTY reader holds back until the terminal has resolved
+    /// the mark or image before it, shared between the reader and the terminal.
+    pub(crate) struct HeldBackOutput {
+        state: Mutex<HoldState>,
+        /// The number of line feeds handed over to alacritty since the
+        /// terminal last took them.
+        line_feeds: AtomicUsize,
+        waker: UnixStream,
+    }
+
+    impl HeldBackOutput {
+        /// Called from alacritty's event loop once it has processed a batch
+        /// of output, before the terminal is notified of it.
+        pub fn output_processed(&self) {
+            let mut state = self.state.lock();
+            *state = match std::mem::take(&mut *state) {
+                HoldState::Unprocessed(unresolved) => HoldState::Processed(unresolved),
+                held_back => held_back,
+            };
+        }
+
+        /// Whether the output up to the held back mark or image has been processed.
+        pub fn is_processed(&self) -> bool {
+            matches!(*self.state.lock(), HoldState::Processed(_))
+        }
+
+        /// Returns what is to be resolved at the terminal's cursor, if the
+        /// output up to it has been processed.
+        pub fn take_processed(&self) -> Option<Unresolved> {
+            let mut state = self.state.lock();
+            match std::mem::take(&mut *state) {
+                HoldState::Processed(unresolved) => {
+                    *state = HoldState::Resolving;
+                    Some(unresolved)
+                }
+                held_back => {
+                    *state = held_back;
+                    None
+                }
+            }
+        }
+
+        /// Lets the reader hand over the held back output, after the given output.
+        pub fn resume(&self, inserted_output: Vec<u8>) {
+            *self.state.lock() = HoldState::Resolved(inserted_output);
+            (&self.waker).write_all(&[0]).ok();
+        }
+
+        pub fn take_line_feeds(&self) -> usize {
+            self.line_feeds.swap(0, Ordering::AcqRel)
+        }
+    }
+
+    /// Wraps alacritty's PTY so that its output goes through a [`MarkReader`].
+    pub(crate) struct ShellIntegrationPty {
+        pty: Pty,
+        reader: MarkReader,
+    }
+
+    impl ShellIntegrationPty {
+        pub fn new(
+            pty: Pty,
+            listener: ZedListener,
+            graphics: Arc<Mutex<Graphics>>,
+            recorder: Arc<Mutex<Option<AsciicastRecorder>>>,
+            trigger_scanner: Option<TriggerScanner>,
+        ) -> io::Result<(Self, Arc<HeldBackOutput>)> {
+            let file = pty.file().try_clone()?;
+            let (waker, wakeups) = UnixStream::pair()?;
+            waker.set_nonblocking(true)?;
+            wakeups.set_nonblocking(true)?;
+            let held_back = Arc::new(HeldBackOutput {
+                state: Mutex::default(),
+                line_feeds: AtomicUsize::new(0),
+                waker,
+            });
+            let reader = MarkReader {
+                file,
+                held_back: held_back.clone(),
+                wakeups,
+                listener,
+                scanner: MarkScanner::default(),
+                graphics_scanner: GraphicsScanner::default(),
+                pending: Vec::new(),
+                graphics,
+                recorder,
+                trigger_scanner,
+            };
+            Ok((Self { pty, reader }, held_back))
+        }
+    }
+
+    /// Reads the PTY's output and hands it over to alacritty up to the end of
+    /// each mark or image. Further output is held back until alacritty has
+    /// processed that part and the terminal has recorded the cursor position
+    /// for the mark, or placed the image there.
+    ///
+    /// The reader never locks the terminal itself, as alacritty's event loop
+    /// may be holding that lock while it reads.
+    pub(crate) struct MarkReader {
+        file: File,
+        held_back: Arc<HeldBackOutput>,
+        /// Becomes readable once the terminal resumes the reader, to wake up
+        /// alacritty's event loop.
+        wakeups: UnixStream,
+        /// Sends the responses to graphics commands back to the program.
+        listener: ZedListener,
+        scanner: MarkScanner,
+        graphics_scanner: GraphicsScanner,
+        /// Output that was read from the PTY but not handed over yet.
+        pending: Vec<u8>,
+        graphics: Arc<Mutex<Graphics>>,
+        /// Records the output as it is read, if the terminal is being recorded.
+        recorder: Arc<Mutex<Option<AsciicastRecorder>>>,
+        /// Matches the output against the triggers from the terminal settings.
+        trigger_scanner: Option<TriggerScanner>,
+    }
+
+    impl MarkReader {
+        /// Scans the next byte of output, returning what to resolve once the
+        /// output up to this byte has been processed.
+        fn scan(&mut self, byte: u8) -> Option<Unresolved> {
+            if let Some(mark) = self.scanner.advance(byte) {
+                return Some(Unresolved::Mark(mark, Instant::now()));
+            }
+            let sequence = self.graphics_scanner.advance(byte)?;
+            let (image, response) = self.graphics.lock().process(sequence);
+            if let Some(response) = response {
+                self.listener.send_event(AlacTermEvent::PtyWrite(response));
+            }
+            image.map(Unresolved::Image)
+        }
+    }
+
+    impl Read for MarkReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            {
+                let mut state = self.held_back.state.lock();
+                match std::mem::take(&mut *state) {
+                    HoldState::Reading => {}
+                    HoldState::Resolved(inserted_output) => {
+                        let mut wakeups = [0; 64];
+                        while (&self.wakeups)
+                            .read(&mut wakeups)
+                            .is_ok_and(|read| read > 0)
+                        {}
+                        // The cursor is moved past an image before the output that follows it.
+                        self.pending.splice(0..0, inserted_output);
+                    }
+                    held_back => {
+                        *state = held_back;
+                        return Err(io::ErrorKind::WouldBlock.into());
+                    }
+                }
+            }
+
+            let len = if self.pending.is_empty() {
+                let len = self.file.read(buf)?;
+                if let Some(recorder) = self.recorder.lock().as_mut() {
+                    recorder.record_output(&buf[..len]);
+                }
+                if let Some(trigger_scanner) = &mut self.trigger_scanner {
+                    trigger_scanner.scan(&buf[..len]);
+                }
+                len
+            } else {
+                let len = self.pending.len().min(buf.len());
+                buf[..len].copy_from_slice(&self.pending[..len]);
+                self.pending.drain(..len);
+                len
+            };
+
+            let mut handed_over = len;
+            for (ix, byte) in buf[..len].iter().enumerate() {
+                if let Some(unresolved) = self.scan(*byte) {
+                    handed_over = ix + 1;
+                    self.pending
+                        .splice(0..0, buf[handed_over..len].iter().copied());
+                    *self.held_back.state.lock() = HoldState::Unprocessed(unresolved);
+                    break;
+                }
+            }
+            self.held_back
+                .line_feeds
+                .fetch_add(count_line_feeds(&buf[..handed_over]), Ordering::AcqRel);
+            Ok(handed_over)
+        }
+    }
+
+    impl EventedReadWrite for ShellIntegrationPty {
+        type Reader = MarkReader;
+        type Writer = File;
+
+        unsafe fn register(
+            &mut self,
+            poll: &Arc<Poller>,
+            interest: PollingEvent,
+            mode: PollMode,
+        ) -> io::Result<()> {
+            unsafe {
+                self.pty.register(poll, interest, mode)?;
+                // Wakeups are reported as the PTY being readable, so that the
+                // held back output is read once a mark has been resolved.
+                poll.add_with_mode(
+                    &self.reader.wakeups,
+                    PollingEvent::readable(PTY_READ_WRITE_TOKEN),
+                    PollMode::Level,
+                )
+            }
+        }
+
+        fn reregister(
+            &mut self,
+            poll: &Arc<Poller>,
+            interest: PollingEvent,
+            mode: PollMode,
+        ) -> io::Result<()> {
+            self.pty.reregister(poll, interest, mode)
+        }
+
+        fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+            self.pty.deregister(poll)?;
+            poll.delete(&self.reader.wakeups)
+        }
+
+        fn reader(&mut self) -> &mut MarkReader {
+            &mut self.reader
+        }
+
+        fn writer(&mut self) -> &mut File {
+            self.pty.writer()
+        }
+    }
+
+    impl EventedPty for ShellIntegrationPty {
+        fn next_child_event(&mut self) -> Option<ChildEvent> {
+            self.pty.next_child_event()
+        }
+    }
+
+    impl OnResize for ShellIntegrationPty {
+        fn on_resize(&mut self, window_size: WindowSize) {
+            self.pty.on_resize(window_size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{
+        term::Config,
+        vte::ansi::{Processor, StdSyncHandler},
+    };
+    use futures::channel::mpsc::unbounded;
+    use gpui::{Bounds, Point, Size, px};
+
+    use super::*;
+    use crate::TerminalBounds;
+
+    fn bounds(columns: usize, lines: usize) -> TerminalBounds {
+        TerminalBounds::new(
+            px(10.),
+            px(10.),
+            Bounds {
+                origin: Point::default(),
+                size: Size {
+                    width: px(10. * columns as f32),
+                    height: px(10. * lines as f32),
+                },
+            },
+        )
+    }
+
+    fn new_term(columns: usize, lines: usize, scrolling_history: usize) -> Term<ZedListener> {
+        let (events_tx, _events_rx) = unbounded();
+        Term::new(
+            Config {
+                scrolling_history,
+                ..Config::default()
+            },
+            &bounds(columns, lines),
+            ZedListener::new(events_tx),
+        )
+    }
+
+    fn write(term: &mut Term<ZedListener>, output: &str) {
+        Processor::<StdSyncHandler>::new().advance(term, output.as_bytes());
+    }
+
+    fn char_at(term: &Term<ZedListener>, origin: GridOrigin, position: MarkPosition) -> char {
+        term.grid()[origin.grid_line(position)][Column(position.column)].c
+    }
+
+    fn scan(output: &[u8]) -> Vec<ShellMark> {
+        let mut scanner = MarkScanner::default();
+        output
+            .iter()
+            .filter_map(|byte| scanner.advance(*byte))
+            .collect()
+    }
+
+    fn resolved(mark: ShellMark, line: usize, column: usize) -> ResolvedMark {
+        ResolvedMark {
+            mark,
+            position: MarkPosition { line, column },
+            time: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_scanning_marks() {
+        assert_eq!(
+            scan(b"\x1b]133;A\x07$ \x1b]133;B\x1b\\ls\r\n\x1b]133;C\x07a b\r\n\x1b]133;D;1\x07"),
+            vec![
+                ShellMark::PromptStart,
+                ShellMark::CommandStart,
+                ShellMark::CommandExecuted,
+                ShellMark::CommandFinished { exit_code: Some(1) },
+            ]
+        );
+        assert_eq!(
+            scan(b"\x1b]633;E;echo a\\x3b b \\\\ c;nonce\x07\x1b]633;D\x07"),
+            vec![
+                ShellMark::CommandLine("echo a; b \\ c".to_string()),
+                ShellMark::CommandFinished { exit_code: None },
+            ]
+        );
+        // Other sequences, and sequences interrupted by another escape, are ignored.
+        assert_eq!(
+            scan(b"\x1b]2;title\x07\x1b]133;\x1b[0m\x1b]8;;https://zed.dev\x1b\\\x1b]133;X\x07"),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_command_blocks() {
+        let mut shell_integration = ShellIntegration::default();
+        for mark in [
+            resolved(ShellMark::PromptStart, 0, 0),
+            resolved(ShellMark::CommandStart, 0, 2),
+            resolved(ShellMark::CommandLine("false".into()), 0, 7),
+            resolved(ShellMark::CommandExecuted, 1, 0),
+            resolved(ShellMark::CommandFinished { exit_code: Some(1) }, 1, 0),
+            resolved(ShellMark::PromptStart, 1, 0),
+            // Redrawing the prompt doesn't start another block.
+            resolved(ShellMark::PromptStart, 2, 0),
+            resolved(ShellMark::CommandStart, 2, 2),
+            resolved(ShellMark::CommandExecuted, 3, 0),
+        ] {
+            shell_integration.apply(mark);
+        }
+
+        let blocks = shell_integration.blocks();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].command.as_deref(), Some("false"));
+        assert_eq!(blocks[0].status(), Some(CommandStatus::Failed));
+        assert!(blocks[0].duration.is_some());
+        assert_eq!(blocks[1].prompt_start, MarkPosition { line: 2, column: 0 });
+        assert_eq!(blocks[1].status(), Some(CommandStatus::Running));
+
+        // A prompt after a command that didn't report finishing ends it.
+        shell_integration.apply(resolved(ShellMark::PromptStart, 5, 0));
+        let blocks = shell_integration.blocks();
+        assert_eq!(blocks[1].status(), Some(CommandStatus::Finished));
+        assert_eq!(blocks[2].status(), None);
+
+        let origin = GridOrigin {
+            history_size: 3,
+            evicted_lines: 0,
+        };
+        assert_eq!(
+            shell_integration.prompt_markers(origin, Line(-2)..=Line(0)),
+            vec![PromptMarker {
+                line: Line(-1),
+                status: CommandStatus::Finished,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_tracking_lines_dropped_from_scrollback() {
+        // Three lines on screen and five in the scrollback.
+        let mut term = new_term(10, 3, 5);
+        let mut tracker = ScrollbackTracker::default();
+        write(&mut term, "0\r\n1\r\n2");
+        let origin = tracker.update(&term, Some(2));
+        let first = origin.position(AlacPoint::new(Line(0), Column(0)));
+        let third = origin.position(AlacPoint::new(Line(2), Column(0)));
+
+        write(&mut term, "\r\n3\r\n4\r\n5\r\n6\r\n7");
+        let origin = tracker.update(&term, Some(5));
+        assert_eq!(origin.first_line(), 0);
+        assert_eq!(char_at(&term, origin, first), '0');
+
+        // Once the scrollback is full, the oldest lines are dropped.
+        write(&mut term, "\r\n8\r\n9");
+        let origin = tracker.update(&term, Some(2));
+        assert_eq!(origin.first_line(), 2);
+        assert_eq!(char_at(&term, origin, third), '2');
+
+        // Dropping a whole scrollback's worth of lines is told apart by the line feeds.
+        write(&mut term, "\r\na\r\nb\r\nc\r\nd\r\ne\r\nf\r\ng\r\nh");
+        let origin = tracker.update(&term, Some(8));
+        assert_eq!(origin.first_line(), 10);
+        assert_eq!(
+            char_at(
+                &term,
+                origin,
+                MarkPosition {
+                    line: 10,
+                    column: 0
+                }
+            ),
+            'a'
+        );
+
+        // Clearing the scrollback drops its lines.
+        write(&mut term, "\x1b[3J");
+        let origin = tracker.update(&term, Some(0));
+        assert_eq!(origin.first_line(), 15);
+        assert_eq!(
+            char_at(
+                &term,
+                origin,
+                MarkPosition {
+                    line: 15,
+                    column: 0
+                }
+            ),
+            'f'
+        );
+    }
+
+    #[test]
+    fn test_reflowing_positions() {
+        let mut term = new_term(10, 3, 10);
+        let mut tracker = ScrollbackTracker::default();
+        write(&mut term, "0123456789abc\r\n$ ");
+        let origin = tracker.update(&term, Some(1));
+        let mut b = MarkPosition { line: 1, column: 1 };
+        assert_eq!(char_at(&term, origin, b), 'b');
+        let mut cursor = origin.cursor(&term);
+
+        for columns in [20, 4] {
+            let old = Reflow::new(&term);
+            let old_origin = tracker.update(&term, None);
+            term.resize(bounds(columns, 3));
+            let new_origin = tracker.reanchor(&term);
+            let new = Reflow::new(&term);
+
+            b = old.reflow(b, old_origin, &new, new_origin).unwrap();
+            assert_eq!(char_at(&term, new_origin, b), 'b');
+            cursor = old.reflow(cursor, old_origin, &new, new_origin).unwrap();
+            assert_eq!(cursor, new_origin.cursor(&term));
+        }
+    }
+}
@@ -0,0 +1,75 @@
+# Zed's shell integration for bash.
+#
+# Reports where prompts, command lines and command output start, and the exit
+# code of each command, with OSC 133 sequences. Command lines are reported with
+# OSC 633. Zed loads this file from PROMPT_COMMAND before the first prompt.
+
+if [[ "$-" != *i* || -n "${__zed_shell_integration_loaded-}" ]]; then
+    builtin return 0
+fi
+__zed_shell_integration_loaded=1
+
+# Remove the command that loaded this file, and keep PROMPT_COMMAND from
+# leaking into programs started from this shell unless it was exported to it.
+if [[ -z "${ZED_USER_PROMPT_COMMAND_EXPORTED-}" ]]; then
+    builtin export -n PROMPT_COMMAND
+fi
+if [[ -n "${ZED_SHELL_INTEGRATION_BOOTSTRAP-}" ]]; then
+    PROMPT_COMMAND="${PROMPT_COMMAND//"$ZED_SHELL_INTEGRATION_BOOTSTRAP"/}"
+fi
+builtin unset ZED_SHELL_INTEGRATION_BOOTSTRAP ZED_USER_PROMPT_COMMAND_EXPORTED
+
+__zed_command_running=
+__zed_prompt_shown=
+
+__zed_escape() {
+    local value="$1"
+    value="${value//\\/\\\\}"
+    value="${value//;/\\x3b}"
+    value="${value//$'\n'/\\x0a}"
+    builtin printf '%s' "$value"
+}
+
+__zed_precmd() {
+    local exit_code="$?"
+    if [[ -n "$__zed_command_running" ]]; then
+        builtin printf '\e]133;D;%s\a' "$exit_code"
+        __zed_command_running=
+    fi
+    builtin printf '\e]133;A\a'
+}
+
+# Runs after the rest of PROMPT_COMMAND, which may have rebuilt the prompt.
+__zed_prompt_end() {
+    if [[ "$PS1" != *'\e]133;B\a'* ]]; then
+        PS1="$PS1"'\[\e]133;B\a\]'
+    fi
+    __zed_prompt_shown=1
+}
+
+__zed_preexec() {
+    # The DEBUG trap also runs for completion functions and for every command
+    # in PROMPT_COMMAND, so only the first command after a prompt counts.
+    if [[ -z "$__zed_prompt_shown" || -n "${COMP_LINE-}" ]]; then
+        builtin return
+    fi
+    __zed_prompt_shown=
+    __zed_command_running=1
+
+    local command_line
+    command_line="$(HISTTIMEFORMAT= builtin history 1)"
+    if [[ "$command_line" =~ ^[[:space:]]*[0-9]+[*]?[[:space:]]+(.*)$ ]]; then
+        builtin printf '\e]633;E;%s\a' "$(__zed_escape "${BASH_REMATCH[1]}")"
+    fi
+    builtin printf '\e]133;C\a'
+}
+
+PROMPT_COMMAND="__zed_precmd"$'\n'"${PROMPT_COMMAND-}"$'\n'"__zed_prompt_end"
+
+# Commands are only reported as running when no other DEBUG trap is installed.
+if [[ -z "$(builtin trap -p DEBUG)" ]]; then
+    builtin trap '__zed_preexec' DEBUG
+fi
+
+__zed_precmd
+__zed_prompt_end
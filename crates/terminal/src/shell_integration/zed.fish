@@ -0,0 +1,50 @@
+# Zed's shell integration for fish.
+#
+# Reports where prompts, command lines and command output start, and the exit
+# code of each command, with OSC 133 sequences. Command lines are reported with
+# OSC 633. Zed adds the directory containing this file to XDG_DATA_DIRS, so
+# that fish loads it as vendor configuration.
+
+# Restore the user's XDG_DATA_DIRS, so that Zed's directory doesn't leak into
+# programs started from this shell.
+if set -q ZED_USER_XDG_DATA_DIRS
+    set -gx XDG_DATA_DIRS $ZED_USER_XDG_DATA_DIRS
+    set -e ZED_USER_XDG_DATA_DIRS
+else
+    set -e XDG_DATA_DIRS
+end
+
+status is-interactive; or exit
+set -q __zed_shell_integration_loaded; and exit
+set -g __zed_shell_integration_loaded 1
+
+function __zed_escape
+    string replace -a -- '\\' '\\\\' $argv[1] | string replace -a -- ';' '\\x3b' | string join '\\x0a'
+end
+
+function __zed_preexec --on-event fish_preexec
+    set -g __zed_command_running 1
+    printf '\e]633;E;%s\a' (__zed_escape $argv[1])
+    printf '\e]133;C\a'
+end
+
+function __zed_postexec --on-event fish_postexec
+    set -l exit_code $status
+    if set -q __zed_command_running
+        printf '\e]133;D;%s\a' $exit_code
+        set -e __zed_command_running
+    end
+end
+
+function __zed_prompt_start --on-event fish_prompt
+    printf '\e]133;A\a'
+    # Wrap the prompt the first time it is shown, once the user's configuration
+    # has defined it.
+    if not functions -q __zed_original_fish_prompt
+        functions -c fish_prompt __zed_original_fish_prompt
+        function fish_prompt
+            __zed_original_fish_prompt
+            printf '\e]133;B\a'
+        end
+    end
+end
@@ -2,14 +2,24 @@ pub mod mappings;
 
 pub use alacritty_terminal;
 
+mod asciicast;
+mod graphics;
 mod pty_info;
+mod scrollback;
+mod shell_integration;
 mod terminal_hyperlinks;
 pub mod terminal_settings;
+mod terminal_triggers;
+
+pub use asciicast::{Asciicast, AsciicastEvent, AsciicastEventKind, AsciicastHeader};
+pub use graphics::TerminalImage;
+pub use shell_integration::{CommandBlock, CommandStatus, PromptMarker};
+pub use terminal_triggers::TriggerAction;
 
 use alacritty_terminal::{
     Term,
-    event::{Event as AlacTermEvent, EventListener, Notify, WindowSize},
-    event_loop::{EventLoop, Msg, Notifier},
+    event::{Event as AlacTermEvent, EventListener, Notify, OnResize, WindowSize},
+    event_loop::{EventLoop, EventLoopSender, Msg, Notifier},
     grid::{Dimensions, Grid, Row, Scroll as AlacScroll},
     index::{Boundary, Column, Direction as AlacDirection, Line, Point as AlacPoint},
     selection::{Selection, SelectionRange, SelectionType},
@@ -23,6 +33,7 @@ use alacritty_terminal::{
     vi_mode::{ViModeCursor, ViMotion},
     vte::ansi::{
         ClearMode, CursorStyle as AlacCursorStyle, Handler, NamedPrivateMode, PrivateMode,
+        Processor, StdSyncHandler,
     },
 };
 use anyhow::{Context as _, Result, bail};
@@ -39,18 +50,30 @@ use mappings::mouse::{
     scroll_report,
 };
 
+use asciicast::AsciicastRecorder;
 use collections::{HashMap, VecDeque};
 use futures::StreamExt;
+use graphics::{Graphics, GraphicsScanner};
+use parking_lot::Mutex;
 use pty_info::{ProcessIdGetter, PtyProcessInfo};
 use serde::{Deserialize, Serialize};
-use settings::Settings;
+use settings::{Settings, TerminalTriggerHighlight};
+use shell_integration::{
+    GridOrigin, MarkPosition, Reflow, ResolvedMark, ScrollbackTracker, ShellIntegration,
+    count_line_feeds,
+};
+#[cfg(unix)]
+use shell_integration::{HeldBackOutput, ShellIntegrationPty, Unresolved};
 use smol::channel::{Receiver, Sender};
 use task::{HideStrategy, Shell, SpawnInTerminal};
 use terminal_hyperlinks::RegexSearches;
 use terminal_settings::{AlternateScroll, CursorShape, TerminalSettings};
+use terminal_triggers::TriggerHighlights;
+#[cfg(unix)]
+use terminal_triggers::TriggerScanner;
 use theme::{ActiveTheme, Theme};
 use urlencoding;
-use util::{paths::PathStyle, truncate_and_trailoff};
+use util::{ResultExt as _, paths::PathStyle, truncate_and_trailoff};
 
 #[cfg(unix)]
 use std::os::unix::process::ExitStatusExt;
@@ -59,7 +82,7 @@ use std::{
     cmp::{self, min},
     fmt::Display,
     ops::{Deref, RangeInclusive},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::ExitStatus,
     sync::Arc,
     time::{Duration, Instant},
@@ -103,10 +126,34 @@ actions!(
         ScrollToTop,
         /// Scrolls to the bottom of the terminal buffer.
         ScrollToBottom,
+        /// Scrolls to the prompt of the previous command.
+        ///
+        /// Requires shell integration, which is only available on macOS and Linux.
+        ScrollToPreviousPrompt,
+        /// Scrolls to the prompt of the next command.
+        ///
+        /// Requires shell integration, which is only available on macOS and Linux.
+        ScrollToNextPrompt,
+        /// Copies the output of the last finished command to the clipboard.
+        ///
+        /// Requires shell integration, which is only available on macOS and Linux.
+        CopyLastCommandOutput,
+        /// Adds the output of the command in view to the agent thread.
+        ///
+        /// Requires shell integration, which is only available on macOS and Linux.
+        SendCommandOutputToAgent,
         /// Toggles vi mode in the terminal.
         ToggleViMode,
         /// Selects all text in the terminal.
         SelectAll,
+        /// Starts recording the terminal session to an asciicast file.
+        ///
+        /// Only available on macOS and Linux.
+        StartRecording,
+        /// Stops recording the terminal session.
+        StopRecording,
+        /// Opens an asciicast recording of a terminal session to replay it.
+        OpenRecording,
     ]
 );
 
@@ -140,6 +187,8 @@ pub enum Event {
     SelectionsChanged,
     NewNavigationTarget(Option<MaybeNavigationTarget>),
     Open(MaybeNavigationTarget),
+    /// A trigger from the terminal settings matched the output.
+    Triggered(TriggerAction),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -182,11 +231,29 @@ enum InternalEvent {
 
 ///A translation struct for Alacritty to communicate with us from their event loop
 #[derive(Clone)]
-pub struct ZedListener(pub UnboundedSender<AlacTermEvent>);
+pub struct ZedListener {
+    events_tx: UnboundedSender<AlacTermEvent>,
+    /// Called when alacritty's event loop has processed a batch of PTY output.
+    on_wakeup: Option<Arc<dyn Fn() + Send + Sync>>,
+}
+
+impl ZedListener {
+    pub fn new(events_tx: UnboundedSender<AlacTermEvent>) -> Self {
+        Self {
+            events_tx,
+            on_wakeup: None,
+        }
+    }
+}
 
 impl EventListener for ZedListener {
     fn send_event(&self, event: AlacTermEvent) {
-        self.0.unbounded_send(event).ok();
+        if let AlacTermEvent::Wakeup = event
+            && let Some(on_wakeup) = &self.on_wakeup
+        {
+            on_wakeup();
+        }
+        self.events_tx.unbounded_send(event).ok();
     }
 }
 
@@ -234,6 +301,13 @@ impl TerminalBounds {
     pub fn line_height(&self) -> Pixels {
         self.line_height
     }
+
+    pub fn cell_size(&self) -> Size<Pixels> {
+        Size {
+            width: self.cell_width,
+            height: self.line_height,
+        }
+    }
 }
 
 impl Default for TerminalBounds {
@@ -371,7 +445,7 @@ impl TerminalBuilder {
         let mut term = Term::new(
             config.clone(),
             &TerminalBounds::default(),
-            ZedListener(events_tx),
+            ZedListener::new(events_tx),
         );
 
         if let AlternateScroll::Off = alternate_scroll {
@@ -421,6 +495,19 @@ impl TerminalBuilder {
             event_loop_task: Task::ready(Ok(())),
             background_executor: background_executor.clone(),
             path_style,
+            shell_integration: ShellIntegration::default(),
+            scrollback: ScrollbackTracker::default(),
+            #[cfg(unix)]
+            held_back_output: None,
+            graphics: Arc::new(Mutex::new(Graphics::new(
+                TerminalBounds::default().cell_size(),
+            ))),
+            graphics_scanner: GraphicsScanner::default(),
+            output_processor: Processor::new(),
+            recorder: Arc::default(),
+            fixed_grid_size: None,
+            trigger_highlights: TriggerHighlights::default(),
+            trigger_actions: Arc::default(),
             #[cfg(any(test, feature = "test-support"))]
             input_log: Vec::new(),
         };
@@ -450,6 +537,8 @@ impl TerminalBuilder {
     ) -> Task<Result<TerminalBuilder>> {
         let version = release_channel::AppVersion::global(cx);
         let background_executor = cx.background_executor().clone();
+        let shell_integration_enabled = TerminalSettings::get_global(cx).shell_integration;
+        let triggers = TerminalSettings::get_global(cx).triggers.clone();
         let fut = async move {
             // Remove SHLVL so the spawned shell initializes it to 1, matching
             // the behavior of standalone terminal emulators like iTerm2/Kitty/Alacritty.
@@ -526,6 +615,24 @@ impl TerminalBuilder {
             // supported remoting into windows.
             let shell_kind = shell.shell_kind(cfg!(windows));
 
+            // Shells running a command (`-c`) don't show prompts to integrate with.
+            let runs_command = shell_params
+                .as_ref()
+                .and_then(|params| params.args.as_ref())
+                .is_some_and(|args| args.iter().any(|arg| arg == "-c"));
+            if shell_integration_enabled && task.is_none() && !is_remote_terminal && !runs_command {
+                let program = shell_params
+                    .as_ref()
+                    .map(|params| params.program.clone())
+                    .or_else(|| env.get("SHELL").cloned())
+                    .or_else(|| std::env::var("SHELL").ok());
+                if let Some(program) = program {
+                    shell_integration::install(&program, &mut env)
+                        .context("failed to install shell integration")
+                        .log_err();
+                }
+            }
+
             let pty_options = {
                 let alac_shell = shell_params.as_ref().map(|params| {
                     alacritty_terminal::tty::Shell::new(
@@ -582,7 +689,7 @@ impl TerminalBuilder {
             let mut term = Term::new(
                 config.clone(),
                 &TerminalBounds::default(),
-                ZedListener(events_tx.clone()),
+                ZedListener::new(events_tx.clone()),
             );
 
             //Alacritty defaults to alternate scrolling being on, so we just need to turn it off.
@@ -593,19 +700,43 @@ impl TerminalBuilder {
             let term = Arc::new(FairMutex::new(term));
 
             let pty_info = PtyProcessInfo::new(&pty);
+            let graphics = Arc::new(Mutex::new(Graphics::new(
+                TerminalBounds::default().cell_size(),
+            )));
+            let recorder = Arc::<Mutex<Option<AsciicastRecorder>>>::default();
+            let trigger_actions = Arc::<Mutex<Vec<TriggerAction>>>::default();
 
             //And connect them together
-            let event_loop = EventLoop::new(
+            #[cfg(unix)]
+            let (pty_tx, held_back_output) = {
+                let (pty, held_back_output) = ShellIntegrationPty::new(
+                    pty,
+                    ZedListener::new(events_tx.clone()),
+                    graphics.clone(),
+                    recorder.clone(),
+                    TriggerScanner::new(&triggers, trigger_actions.clone()),
+                )
+                .context("failed to set up shell integration")?;
+                let listener = ZedListener {
+                    events_tx,
+                    on_wakeup: Some(Arc::new({
+                        let held_back_output = held_back_output.clone();
+                        move || held_back_output.output_processed()
+                    })),
+                };
+                let pty_tx =
+                    spawn_event_loop(term.clone(), listener, pty, pty_options.drain_on_exit)?;
+                (pty_tx, held_back_output)
+            };
+            #[cfg(not(unix))]
+            terminal_triggers::warn_unsupported_action_triggers(&triggers);
+            #[cfg(not(unix))]
+            let pty_tx = spawn_event_loop(
                 term.clone(),
-                ZedListener(events_tx),
+                ZedListener::new(events_tx),
                 pty,
                 pty_options.drain_on_exit,
-                false,
-            )
-            .context("failed to create event loop")?;
-
-            let pty_tx = event_loop.channel();
-            let _io_thread = event_loop.spawn(); // DANGER
+            )?;
 
             let no_task = task.is_none();
             let terminal = Terminal {
@@ -655,6 +786,17 @@ impl TerminalBuilder {
                 event_loop_task: Task::ready(Ok(())),
                 background_executor,
                 path_style,
+                shell_integration: ShellIntegration::default(),
+                scrollback: ScrollbackTracker::default(),
+                #[cfg(unix)]
+                held_back_output: Some(held_back_output),
+                graphics,
+                graphics_scanner: GraphicsScanner::default(),
+                output_processor: Processor::new(),
+                recorder,
+                fixed_grid_size: None,
+                trigger_highlights: TriggerHighlights::new(&triggers),
+                trigger_actions,
                 #[cfg(any(test, feature = "test-support"))]
                 input_log: Vec::new(),
             };
@@ -694,6 +836,8 @@ impl TerminalBuilder {
     }
 
     pub fn subscribe(mut self, cx: &Context<Terminal>) -> Terminal {
+        #[cfg(unix)]
+        let held_back_output = self.terminal.held_back_output.clone();
         //Event loop
         self.terminal.event_loop_task = cx.spawn(async move |terminal, cx| {
             while let Some(event) = self.events_rx.next().await {
@@ -721,6 +865,15 @@ impl TerminalBuilder {
                                 if let Some(event) = event {
                                     if matches!(event, AlacTermEvent::Wakeup) {
                                         wakeup = true;
+                                        // The PTY's output is held back until the
+                                        // mark or image before it is resolved.
+                                        #[cfg(unix)]
+                                        if held_back_output
+                                            .as_ref()
+                                            .is_some_and(|output| output.is_processed())
+                                        {
+                                            break;
+                                        }
                                     } else {
                                         events.push(event);
                                     }
@@ -776,6 +929,24 @@ impl TerminalBuilder {
     }
 }
 
+/// Spawns alacritty's event loop on its own thread, returning the channel used to talk to it.
+fn spawn_event_loop<T>(
+    term: Arc<FairMutex<Term<ZedListener>>>,
+    listener: ZedListener,
+    pty: T,
+    drain_on_exit: bool,
+) -> Result<EventLoopSender>
+where
+    T: tty::EventedPty + OnResize + Send + 'static,
+{
+    let event_loop = EventLoop::new(term, listener, pty, drain_on_exit, false)
+        .context("failed to create event loop")?;
+    let pty_tx = event_loop.channel();
+    let _io_thread = event_loop.spawn(); // DANGER
+
+    Ok(pty_tx)
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct IndexedCell {
     pub point: AlacPoint,
@@ -805,6 +976,12 @@ pub struct TerminalContent {
     pub last_hovered_word: Option<HoveredWord>,
     pub scrolled_to_top: bool,
     pub scrolled_to_bottom: bool,
+    /// Prompts of the commands run within the visible lines, from shell integration.
+    pub prompt_markers: Vec<PromptMarker>,
+    /// Images shown within the visible lines.
+    pub images: Vec<TerminalImage>,
+    /// Matches of the triggers from the terminal settings within the visible lines.
+    pub trigger_highlights: Vec<(RangeInclusive<AlacPoint>, TerminalTriggerHighlight)>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -831,6 +1008,9 @@ impl Default for TerminalContent {
             last_hovered_word: None,
             scrolled_to_top: false,
             scrolled_to_bottom: false,
+            prompt_markers: Vec::new(),
+            images: Vec::new(),
+            trigger_highlights: Vec::new(),
         }
     }
 }
@@ -882,6 +1062,27 @@ pub struct Terminal {
     event_loop_task: Task<Result<(), anyhow::Error>>,
     background_executor: BackgroundExecutor,
     path_style: PathStyle,
+    shell_integration: ShellIntegration,
+    /// Counts the lines dropped from the scrollback, which marks and images
+    /// are positioned relative to.
+    scrollback: ScrollbackTracker,
+    /// The PTY output held back until the mark or image before it is resolved.
+    #[cfg(unix)]
+    held_back_output: Option<Arc<HeldBackOutput>>,
+    graphics: Arc<Mutex<Graphics>>,
+    /// Finds images in output written with [`Terminal::write_output`].
+    graphics_scanner: GraphicsScanner,
+    /// Parses output written with [`Terminal::write_output`], keeping escape
+    /// sequences that are split across writes.
+    output_processor: Processor<StdSyncHandler>,
+    /// Records the session while [`Terminal::start_recording`] is in effect.
+    recorder: Arc<Mutex<Option<AsciicastRecorder>>>,
+    /// The number of columns and lines the grid is kept at, regardless of the
+    /// size the terminal is shown at.
+    fixed_grid_size: Option<(usize, usize)>,
+    trigger_highlights: TriggerHighlights,
+    /// The actions of the triggers that matched output read from the PTY.
+    trigger_actions: Arc<Mutex<Vec<TriggerAction>>>,
     #[cfg(any(test, feature = "test-support"))]
     input_log: Vec<Vec<u8>>,
 }
@@ -987,7 +1188,12 @@ impl Terminal {
                 //NOOP, Handled in render
             }
             AlacTermEvent::Wakeup => {
+                self.resolve_held_back_output();
                 cx.emit(Event::Wakeup);
+                let trigger_actions = std::mem::take(&mut *self.trigger_actions.lock());
+                for action in trigger_actions {
+                    cx.emit(Event::Triggered(action));
+                }
 
                 if let TerminalType::Pty { info, .. } = &self.terminal_type {
                     info.emit_title_changed_if_changed(cx);
@@ -1036,7 +1242,11 @@ impl Terminal {
                     pty_tx.0.send(Msg::Resize(new_bounds.into())).ok();
                 }
 
-                term.resize(new_bounds);
+                self.resize_term(term, new_bounds);
+                self.graphics.lock().set_cell_size(new_bounds.cell_size());
+                if let Some(recorder) = self.recorder.lock().as_mut() {
+                    recorder.record_resize(new_bounds.num_columns(), new_bounds.num_lines());
+                }
                 // If there are matches we need to emit a wake up event to
                 // invalidate the matches and recalculate their locations
                 // in the new terminal layout
@@ -1046,6 +1256,8 @@ impl Terminal {
             }
             InternalEvent::Clear => {
                 trace!("Clearing");
+                self.shell_integration.clear();
+                self.graphics.lock().clear();
                 // Clear back buffer
                 term.clear_screen(ClearMode::Saved);
 
@@ -1317,17 +1529,130 @@ impl Terminal {
             converted.push(byte);
             prev_byte = byte;
         }
+        self.process_output(&converted);
+        cx.emit(Event::Wakeup);
+    }
 
-        let mut processor = alacritty_terminal::vte::ansi::Processor::<
-            alacritty_terminal::vte::ansi::StdSyncHandler,
-        >::new();
-        {
-            let mut term = self.term.lock();
-            processor.advance(&mut *term, &converted);
-        }
+    /// Writes output as it was read from a PTY, such as from a recording,
+    /// without converting line feeds like [`Self::write_output`] does.
+    pub fn write_pty_output(&mut self, bytes: &[u8], cx: &mut Context<Self>) {
+        self.process_output(bytes);
         cx.emit(Event::Wakeup);
     }
 
+    fn process_output(&mut self, output: &[u8]) {
+        if let Some(recorder) = self.recorder.lock().as_mut() {
+            recorder.record_output(output);
+        }
+
+        let term = self.term.clone();
+        let mut term = term.lock();
+        // Process the output up to each shell integration mark and image
+        // separately, so that they are placed where the cursor was at that point.
+        let mut processed = 0;
+        for (ix, byte) in output.iter().enumerate() {
+            if let Some(mark) = self.shell_integration.scanner.advance(*byte) {
+                let position = self.process_output_up_to(&mut term, &output[processed..=ix]);
+                processed = ix + 1;
+                self.shell_integration.apply(ResolvedMark {
+                    mark,
+                    position,
+                    time: Instant::now(),
+                });
+            }
+            if let Some(sequence) = self.graphics_scanner.advance(*byte) {
+                // There's no program to respond to.
+                let (image, _) = self.graphics.lock().process(sequence);
+                if let Some(image) = image {
+                    let position = self.process_output_up_to(&mut term, &output[processed..=ix]);
+                    processed = ix + 1;
+                    let cursor_movement = self.graphics.lock().place(image, position);
+                    self.output_processor.advance(&mut *term, &cursor_movement);
+                }
+            }
+        }
+        self.process_output_up_to(&mut term, &output[processed..]);
+    }
+
+    /// Processes part of the output written to a display-only terminal,
+    /// returning the position of the cursor afterwards.
+    fn process_output_up_to(
+        &mut self,
+        term: &mut Term<ZedListener>,
+        output: &[u8],
+    ) -> MarkPosition {
+        self.output_processor.advance(term, output);
+        let origin = self.update_scrollback(term, Some(count_line_feeds(output)));
+        origin.cursor(term)
+    }
+
+    /// Accounts for the lines dropped from the scrollback since the last
+    /// call, dropping the command blocks and images that were on them.
+    fn update_scrollback(
+        &mut self,
+        term: &Term<ZedListener>,
+        line_feeds: Option<usize>,
+    ) -> GridOrigin {
+        let first_line = self.scrollback.origin(term).first_line();
+        let origin = self.scrollback.update(term, line_feeds);
+        if origin.first_line() != first_line {
+            let first_line = origin.first_line();
+            let retained =
+                |position: MarkPosition| (position.line >= first_line).then_some(position);
+            self.shell_integration.retain_positions(retained);
+            self.graphics.lock().retain_positions(retained);
+        }
+        origin
+    }
+
+    /// Records the position of the mark, or places the image, that the PTY's
+    /// output is held back at once alacritty has processed the output up to
+    /// it, and lets the rest of the output through.
+    fn resolve_held_back_output(&mut self) {
+        let term = self.term.clone();
+        let term = term.lock_unfair();
+        #[cfg(unix)]
+        if let Some(held_back_output) = self.held_back_output.clone() {
+            let line_feeds = held_back_output.take_line_feeds();
+            let origin = self.update_scrollback(&term, Some(line_feeds));
+            if let Some(unresolved) = held_back_output.take_processed() {
+                let position = origin.cursor(&term);
+                let inserted_output = match unresolved {
+                    Unresolved::Mark(mark, time) => {
+                        self.shell_integration.apply(ResolvedMark {
+                            mark,
+                            position,
+                            time,
+                        });
+                        Vec::new()
+                    }
+                    Unresolved::Image(image) => self.graphics.lock().place(image, position),
+                };
+                held_back_output.resume(inserted_output);
+            }
+            return;
+        }
+        // Line feeds are only counted as the PTY's output is read.
+        self.update_scrollback(&term, None);
+    }
+
+    /// Resizes the grid, moving the marks and images along with the text
+    /// they are on as its lines are reflowed.
+    fn resize_term(&mut self, term: &mut Term<ZedListener>, bounds: TerminalBounds) {
+        // The normal screen is reflowed while the alternate screen is shown
+        // too, but it can't be looked at then, so positions are kept as they are.
+        let reflow = (!term.mode().contains(TermMode::ALT_SCREEN))
+            .then(|| (Reflow::new(term), self.update_scrollback(term, None)));
+        term.resize(bounds);
+        let new_origin = self.scrollback.reanchor(term);
+        if let Some((old, old_origin)) = reflow {
+            let new = Reflow::new(term);
+            let reflow = |position| old.reflow(position, old_origin, &new, new_origin);
+            self.shell_integration.retain_positions(reflow);
+            self.graphics.lock().retain_positions(reflow);
+        }
+    }
+
     pub fn total_lines(&self) -> usize {
         self.term.lock_unfair().total_lines()
     }
@@ -1433,12 +1758,219 @@ impl Terminal {
         self.last_content.scrolled_to_top
     }
 
+    /// Returns the terminal's output up to the cursor, bounded by its scrollback
+    /// size, as text with escape sequences for colors and attributes. Returns
+    /// `None` while a full screen application is shown.
+    pub fn serialized_scrollback(&self) -> Option<String> {
+        let term = self.term.lock_unfair();
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            return None;
+        }
+        Some(scrollback::serialize(
+            &term,
+            self.term_config.scrolling_history,
+        ))
+    }
+
+    /// Shows output saved with [`Self::serialized_scrollback`] above what has
+    /// been printed to the terminal so far.
+    pub fn restore_scrollback(&mut self, scrollback: &str) {
+        let term = self.term.clone();
+        let mut term = term.lock();
+        self.update_scrollback(&term, None);
+        let moved_lines = scrollback::restore(&mut term, scrollback);
+        // Lines are inserted above the ones marks and images are on, rather than scrolled in.
+        self.scrollback.reanchor(&term);
+        self.shell_integration.shift_down(moved_lines);
+        self.graphics.lock().shift_down(moved_lines);
+    }
+
+    /// Starts recording the terminal's output and size changes to an asciicast file.
+    pub fn start_recording(&mut self, path: &Path) -> Result<()> {
+        // Output from a PTY is only seen on its way to alacritty on Unix.
+        #[cfg(not(unix))]
+        if let TerminalType::Pty { .. } = self.terminal_type {
+            bail!("recording terminals is not supported on this platform");
+        }
+        let bounds = self.last_content.terminal_bounds;
+        let recorder = AsciicastRecorder::new(path, bounds.num_columns(), bounds.num_lines())?;
+        let previous = self.recorder.lock().replace(recorder);
+        if let Some(previous) = previous {
+            previous.finish().log_err();
+        }
+        Ok(())
+    }
+
+    /// Stops recording the terminal, finishing the file it's recorded to.
+    pub fn stop_recording(&mut self) -> Result<()> {
+        let recorder = self.recorder.lock().take();
+        recorder
+            .context("the terminal is not being recorded")?
+            .finish()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.lock().is_some()
+    }
+
+    /// Keeps the grid at the given number of columns and lines, regardless of
+    /// the size the terminal is shown at, such as to replay a recording.
+    pub fn set_fixed_grid_size(&mut self, columns: usize, lines: usize) {
+        self.fixed_grid_size = Some((columns.max(1), lines.max(1)));
+        let bounds = self.with_fixed_grid_size(self.last_content.terminal_bounds);
+        self.last_content.terminal_bounds = bounds;
+        let term = self.term.clone();
+        self.resize_term(&mut term.lock(), bounds);
+    }
+
+    fn with_fixed_grid_size(&self, mut bounds: TerminalBounds) -> TerminalBounds {
+        if let Some((columns, lines)) = self.fixed_grid_size {
+            bounds.bounds.size = Size {
+                width: bounds.cell_width * columns as f32,
+                height: bounds.line_height * lines as f32,
+            };
+        }
+        bounds
+    }
+
+    /// Clears everything written to the terminal, including its scrollback,
+    /// and resets its modes.
+    pub fn reset(&mut self, cx: &mut Context<Self>) {
+        {
+            let mut term = self.term.lock();
+            self.output_processor = Processor::new();
+            // RIS resets the grid and the scrollback along with everything else.
+            self.output_processor.advance(&mut *term, b"\x1bc");
+        }
+        self.shell_integration.clear();
+        self.graphics.lock().clear();
+        self.graphics_scanner = GraphicsScanner::default();
+        cx.emit(Event::Wakeup);
+    }
+
+    /// Returns the commands run in this terminal, as reported by the shell integration.
+    pub fn command_blocks(&self) -> &[CommandBlock] {
+        self.shell_integration.blocks()
+    }
+
+    /// Scrolls up to the closest prompt above the top of the viewport.
+    pub fn scroll_to_previous_prompt(&mut self) {
+        let origin = self.scrollback.origin(&self.term.lock_unfair());
+        let top = Line(-(self.last_content.display_offset as i32));
+        let previous_prompt = self
+            .shell_integration
+            .blocks()
+            .iter()
+            .rev()
+            .map(|block| origin.grid_line(block.prompt_start))
+            .find(|line| *line < top);
+        if let Some(line) = previous_prompt {
+            self.scroll_prompt_to_top(line);
+        }
+    }
+
+    /// Scrolls down to the closest prompt below the top of the viewport, or
+    /// to the bottom if there are none.
+    pub fn scroll_to_next_prompt(&mut self) {
+        let origin = self.scrollback.origin(&self.term.lock_unfair());
+        let top = Line(-(self.last_content.display_offset as i32));
+        let next_prompt = self
+            .shell_integration
+            .blocks()
+            .iter()
+            .map(|block| origin.grid_line(block.prompt_start))
+            .find(|line| *line > top && line.0 <= 0);
+        match next_prompt {
+            Some(line) => self.scroll_prompt_to_top(line),
+            None => self.scroll_to_bottom(),
+        }
+    }
+
+    fn scroll_prompt_to_top(&mut self, line: Line) {
+        let display_offset = self.last_content.display_offset as i32;
+        self.events
+            .push_back(InternalEvent::Scroll(AlacScroll::Delta(
+                -line.0 - display_offset,
+            )));
+    }
+
+    /// Returns the command whose output is being looked at: the last one that
+    /// finished when scrolled to the bottom, or the one at the top of the viewport.
+    pub fn focused_command_block(&self) -> Option<&CommandBlock> {
+        let mut blocks = self
+            .shell_integration
+            .blocks()
+            .iter()
+            .rev()
+            .filter(|block| block.was_run());
+        let display_offset = self.last_content.display_offset;
+        if display_offset == 0 {
+            return blocks.find(|block| block.is_finished());
+        }
+        let origin = self.scrollback.origin(&self.term.lock_unfair());
+        let top = Line(-(display_offset as i32));
+        blocks.find(|block| origin.grid_line(block.prompt_start) <= top)
+    }
+
+    /// Returns the text of the given command's output, without trailing whitespace.
+    pub fn command_output(&self, block: &CommandBlock) -> Option<String> {
+        let range = self.command_output_range(block)?;
+        let term = self.term.lock_unfair();
+        let output = term.bounds_to_string(*range.start(), *range.end());
+        Some(output.trim_end().to_string())
+    }
+
+    /// Returns the output of the last command that finished.
+    pub fn last_command_output(&self) -> Option<String> {
+        let block = self
+            .shell_integration
+            .blocks()
+            .iter()
+            .rev()
+            .find(|block| block.is_finished())?;
+        self.command_output(block)
+    }
+
+    /// Selects the given command's output, returning whether it is still in the scrollback.
+    pub fn select_command_output(&mut self, block: &CommandBlock) -> bool {
+        let Some(range) = self.command_output_range(block) else {
+            return false;
+        };
+        let end = *range.end();
+        self.set_selection(Some((make_selection(&range), end)));
+        true
+    }
+
+    fn command_output_range(&self, block: &CommandBlock) -> Option<RangeInclusive<AlacPoint>> {
+        let output_start = block.output_start?;
+        let term = self.term.lock_unfair();
+        let origin = self.scrollback.origin(&term);
+        let start_line = origin.grid_line(output_start);
+        if start_line < term.topmost_line() {
+            return None;
+        }
+        let start = AlacPoint::new(start_line, Column(output_start.column));
+        // The output ends right before the mark that finished the command.
+        let end = match block.output_end {
+            Some(output_end) => {
+                let line = origin.grid_line(output_end);
+                match output_end.column.checked_sub(1) {
+                    Some(column) => AlacPoint::new(line, Column(column)),
+                    None => AlacPoint::new(line - 1, term.last_column()),
+                }
+            }
+            None => AlacPoint::new(term.bottommost_line(), term.last_column()),
+        };
+        (start <= end).then_some(start..=end)
+    }
+
     pub fn scrolled_to_bottom(&self) -> bool {
         self.last_content.scrolled_to_bottom
     }
 
     ///Resize the terminal and the PTY.
     pub fn set_size(&mut self, new_bounds: TerminalBounds) {
+        let new_bounds = self.with_fixed_grid_size(new_bounds);
         if self.last_content.terminal_bounds != new_bounds {
             self.events.push_back(InternalEvent::Resize(new_bounds))
         }
@@ -1627,6 +2159,24 @@ impl Terminal {
         }
 
         self.last_content = Self::make_content(&terminal, &self.last_content);
+
+        let display_offset = self.last_content.display_offset as i32;
+        let visible_lines =
+            Line(-display_offset)..=Line(terminal.screen_lines() as i32 - 1 - display_offset);
+        let origin = self.scrollback.origin(&terminal);
+        // Prompts are on the normal screen, which the alternate screen covers.
+        self.last_content.prompt_markers = if terminal.mode().contains(TermMode::ALT_SCREEN) {
+            Vec::new()
+        } else {
+            self.shell_integration
+                .prompt_markers(origin, visible_lines.clone())
+        };
+        self.last_content.images = self
+            .graphics
+            .lock()
+            .visible_images(origin, visible_lines.clone());
+        self.last_content.trigger_highlights =
+            self.trigger_highlights.find(&terminal, visible_lines);
     }
 
     fn make_content(term: &Term<ZedListener>, last_content: &TerminalContent) -> TerminalContent {
@@ -1659,6 +2209,9 @@ impl Terminal {
             last_hovered_word: last_content.last_hovered_word.clone(),
             scrolled_to_top: content.display_offset == term.history_size(),
             scrolled_to_bottom: content.display_offset == 0,
+            prompt_markers: Vec::new(),
+            images: Vec::new(),
+            trigger_highlights: Vec::new(),
         }
     }
 
@@ -2563,6 +3116,7 @@ mod tests {
     use alacritty_terminal::{
         index::{Column, Line, Point as AlacPoint},
         term::cell::Cell,
+        vte::ansi::{Color as AnsiColor, NamedColor},
     };
     use collections::HashMap;
     use gpui::{
@@ -3207,6 +3761,135 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_write_output_tracks_command_blocks(cx: &mut TestAppContext) {
+        let terminal = cx.new(|cx| {
+            TerminalBuilder::new_display_only(
+                CursorShape::default(),
+                AlternateScroll::On,
+                None,
+                0,
+                cx.background_executor(),
+                PathStyle::local(),
+            )
+            .unwrap()
+            .subscribe(cx)
+        });
+
+        terminal.update(cx, |terminal, cx| {
+            terminal.write_output(b"\x1b]133;A\x07$ \x1b]133;B\x07echo hi\n\x1b]133;C", cx);
+            // Marks split across writes are still found.
+            terminal.write_output(b"\x07hi\nthere\n\x1b]133;D;0\x07\x1b]133;A\x07$ ", cx);
+        });
+
+        terminal.update(cx, |terminal, _cx| {
+            let blocks = terminal.command_blocks();
+            assert_eq!(blocks.len(), 2);
+            assert_eq!(blocks[0].status(), Some(CommandStatus::Succeeded));
+            assert_eq!(blocks[1].status(), None);
+            assert_eq!(
+                terminal.command_output(&blocks[0]).as_deref(),
+                Some("hi\nthere")
+            );
+            assert_eq!(terminal.last_command_output().as_deref(), Some("hi\nthere"));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_write_output_places_images(cx: &mut TestAppContext) {
+        let terminal = cx.new(|cx| {
+            TerminalBuilder::new_display_only(
+                CursorShape::default(),
+                AlternateScroll::On,
+                None,
+                0,
+                cx.background_executor(),
+                PathStyle::local(),
+            )
+            .unwrap()
+            .subscribe(cx)
+        });
+
+        terminal.update(cx, |terminal, cx| {
+            terminal.write_output(b"$ \x1b_Ga=T,f=32,s=2,v=1,c=3,r=2;/wAA", cx);
+            terminal.write_output(b"/wAA//8=\x1b\\x", cx);
+        });
+
+        terminal.update(cx, |terminal, _cx| {
+            let term = terminal.term.lock();
+            let images = terminal.graphics.lock().visible_images(
+                terminal.scrollback.origin(&term),
+                Line(0)..=Line(term.screen_lines() as i32 - 1),
+            );
+            assert_eq!(images.len(), 1);
+            assert_eq!(images[0].point, AlacPoint::new(Line(0), Column(2)));
+            assert_eq!((images[0].columns, images[0].rows), (3, 2));
+            // Output continues past the image's last column, on its last line.
+            assert_eq!(term.grid()[Line(1)][Column(5)].c, 'x');
+        });
+    }
+
+    #[gpui::test]
+    async fn test_recording_and_replaying_output(cx: &mut TestAppContext) {
+        let new_terminal = |cx: &mut TestAppContext| {
+            cx.new(|cx| {
+                TerminalBuilder::new_display_only(
+                    CursorShape::default(),
+                    AlternateScroll::On,
+                    None,
+                    0,
+                    cx.background_executor(),
+                    PathStyle::local(),
+                )
+                .unwrap()
+                .subscribe(cx)
+            })
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.cast");
+
+        let terminal = new_terminal(cx);
+        terminal.update(cx, |terminal, cx| {
+            terminal.start_recording(&path).unwrap();
+            assert!(terminal.is_recording());
+            terminal.write_output(b"$ ls\n\x1b[3", cx);
+            terminal.write_output(b"1mred\x1b[0m", cx);
+            terminal.stop_recording().unwrap();
+            assert!(!terminal.is_recording());
+            terminal.write_output(b"\nnot recorded", cx);
+        });
+
+        let recording = Asciicast::parse(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let output = recording
+            .events
+            .iter()
+            .filter_map(|event| match &event.kind {
+                AsciicastEventKind::Output(output) => Some(output.as_str()),
+                AsciicastEventKind::Resize { .. } => None,
+            })
+            .collect::<String>();
+        assert_eq!(output, "$ ls\r\n\x1b[31mred\x1b[0m");
+
+        // Escape sequences split across events are still applied.
+        let replay = new_terminal(cx);
+        replay.update(cx, |terminal, cx| {
+            terminal.set_fixed_grid_size(recording.header.width, recording.header.height);
+            for event in &recording.events {
+                if let AsciicastEventKind::Output(output) = &event.kind {
+                    terminal.write_pty_output(output.as_bytes(), cx);
+                }
+            }
+            assert_eq!(terminal.last_n_non_empty_lines(2), vec!["$ ls", "red"]);
+            assert_eq!(
+                terminal.term.lock().grid()[Line(1)][Column(0)].fg,
+                AnsiColor::Named(NamedColor::Red)
+            );
+
+            terminal.reset(cx);
+            assert!(terminal.last_n_non_empty_lines(2).is_empty());
+        });
+    }
+
     #[gpui::test]
     async fn test_hyperlink_ctrl_click_same_position(cx: &mut TestAppContext) {
         let terminal = init_ctrl_click_hyperlink_test(cx, b"Visit https://zed.dev/ for more\r\n");
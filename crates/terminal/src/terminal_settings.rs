@@ -10,7 +10,7 @@ pub use settings::AlternateScroll;
 
 use settings::{
     IntoGpui, PathHyperlinkRegex, RegisterSetting, ShowScrollbar, TerminalBlink,
-    TerminalDockPosition, TerminalLineHeight, VenvSettings, WorkingDirectory,
+    TerminalDockPosition, TerminalLineHeight, TerminalTrigger, VenvSettings, WorkingDirectory,
     merge_from::MergeFrom,
 };
 use task::Shell;
@@ -52,6 +52,9 @@ pub struct TerminalSettings {
     pub path_hyperlink_regexes: Vec<String>,
     pub path_hyperlink_timeout_ms: u64,
     pub show_count_badge: bool,
+    pub shell_integration: bool,
+    pub restore_scrollback: bool,
+    pub triggers: Vec<TerminalTrigger>,
 }
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
@@ -133,6 +136,9 @@ impl settings::Settings for TerminalSettings {
                 .collect(),
             path_hyperlink_timeout_ms: project_content.path_hyperlink_timeout_ms.unwrap(),
             show_count_badge: user_content.show_count_badge.unwrap(),
+            shell_integration: user_content.shell_integration.unwrap(),
+            restore_scrollback: user_content.restore_scrollback.unwrap(),
+            triggers: user_content.triggers.unwrap(),
         }
     }
 }
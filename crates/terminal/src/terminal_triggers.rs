@@ -0,0 +1,336 @@
+//! Triggers from the `terminal.triggers` setting: regexes matched against each
+//! line of output, to highlight the matches, show notifications, open paths or
+//! run tasks.
+
+use std::{ops::RangeInclusive, sync::Arc};
+
+use alacritty_terminal::{
+    Term,
+    event::EventListener,
+    index::{Column, Direction as AlacDirection, Line, Point as AlacPoint},
+    term::search::{RegexIter, RegexSearch},
+    vte::{Parser, Perform},
+};
+use log::warn;
+use parking_lot::Mutex;
+use regex::Regex;
+use settings::{TerminalTrigger, TerminalTriggerHighlight};
+
+/// How much of a line of output triggers are matched against.
+const MAX_LINE_LEN: usize = 4096;
+
+/// Something to do because a trigger matched a line of output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TriggerAction {
+    /// Shows a notification with the message.
+    Notify(String),
+    /// Opens the path, which may have a line and column attached, as in `file.rs:1:23`.
+    OpenPath(String),
+    /// Runs the task with the label.
+    RunTask(String),
+}
+
+struct ActionTrigger {
+    regex: Regex,
+    notify: Option<String>,
+    open_path: bool,
+    run_task: Option<String>,
+}
+
+impl ActionTrigger {
+    fn actions(&self, line: &str) -> Option<impl Iterator<Item = TriggerAction>> {
+        let captures = self.regex.captures(line)?;
+        let notify = self.notify.as_ref().map(|template| {
+            let mut message = String::new();
+            captures.expand(template, &mut message);
+            TriggerAction::Notify(message)
+        });
+        let open_path = self.open_path.then(|| {
+            let path = captures.name("path").or_else(|| captures.get(0));
+            TriggerAction::OpenPath(path.map_or("", |path| path.as_str()).to_string())
+        });
+        let run_task = self.run_task.clone().map(TriggerAction::RunTask);
+        Some(notify.into_iter().chain(open_path).chain(run_task))
+    }
+}
+
+/// Matches the triggers that do something against the output read from the
+/// PTY, one line at a time, once escape sequences are stripped from it.
+// Output is only read through Zed on Unix.
+#[cfg_attr(not(unix), allow(dead_code))]
+pub(crate) struct TriggerScanner {
+    triggers: Vec<ActionTrigger>,
+    parser: Parser,
+    lines: OutputLines,
+    actions: Arc<Mutex<Vec<TriggerAction>>>,
+}
+
+#[cfg_attr(not(unix), allow(dead_code))]
+impl TriggerScanner {
+    /// Returns a scanner for the triggers that do something when they match,
+    /// if there are any. Their actions are added to `actions`.
+    pub fn new(
+        triggers: &[TerminalTrigger],
+        actions: Arc<Mutex<Vec<TriggerAction>>>,
+    ) -> Option<Self> {
+        let triggers = triggers
+            .iter()
+            .filter(|trigger| {
+                trigger.notify.is_some() || trigger.open_path || trigger.run_task.is_some()
+            })
+            .filter_map(|trigger| {
+                Some(ActionTrigger {
+                    regex: Regex::new(&trigger.regex)
+                        .inspect_err(|error| warn_invalid_trigger(&trigger.regex, error))
+                        .ok()?,
+                    notify: trigger.notify.clone(),
+                    open_path: trigger.open_path,
+                    run_task: trigger.run_task.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        if triggers.is_empty() {
+            return None;
+        }
+        Some(Self {
+            triggers,
+            parser: Parser::new(),
+            lines: OutputLines::default(),
+            actions,
+        })
+    }
+
+    pub fn scan(&mut self, output: &[u8]) {
+        self.parser.advance(&mut self.lines, output);
+        if self.lines.completed.is_empty() {
+            return;
+        }
+
+        // Each trigger fires at most once per read, so that a flood of
+        // matching lines doesn't run a task over and over.
+        let mut actions = Vec::new();
+        for trigger in &self.triggers {
+            if let Some(trigger_actions) = self
+                .lines
+                .completed
+                .iter()
+                .find_map(|line| trigger.actions(line))
+            {
+                actions.extend(trigger_actions);
+            }
+        }
+        self.lines.completed.clear();
+        if !actions.is_empty() {
+            self.actions.lock().extend(actions);
+        }
+    }
+}
+
+/// Collects the text printed on each line, ignoring escape sequences.
+#[derive(Default)]
+struct OutputLines {
+    current: String,
+    /// Whether the cursor went back to the start of the line, so that what's
+    /// printed next replaces the line, as progress bars do.
+    carriage_return: bool,
+    completed: Vec<String>,
+}
+
+impl Perform for OutputLines {
+    fn print(&mut self, c: char) {
+        if self.carriage_return {
+            self.carriage_return = false;
+            self.current.clear();
+        }
+        if self.current.len() < MAX_LINE_LEN {
+            self.current.push(c);
+        }
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            b'\n' => {
+                self.carriage_return = false;
+                if !self.current.is_empty() {
+                    self.completed.push(std::mem::take(&mut self.current));
+                }
+            }
+            b'\r' => self.carriage_return = true,
+            // Backspace
+            0x08 => {
+                self.current.pop();
+            }
+            b'\t' => self.print('\t'),
+            _ => {}
+        }
+    }
+}
+
+/// Finds the matches of the triggers that have a highlight color.
+#[derive(Default)]
+pub(crate) struct TriggerHighlights {
+    searches: Vec<(RegexSearch, TerminalTriggerHighlight)>,
+}
+
+impl TriggerHighlights {
+    pub fn new(triggers: &[TerminalTrigger]) -> Self {
+        let searches = triggers
+            .iter()
+            .filter_map(|trigger| {
+                let highlight = trigger.highlight?;
+                let search = RegexSearch::new(&trigger.regex)
+                    .inspect_err(|error| warn_invalid_trigger(&trigger.regex, error))
+                    .ok()?;
+                Some((search, highlight))
+            })
+            .collect();
+        Self { searches }
+    }
+
+    /// Returns the matches within the given lines.
+    pub fn find<T: EventListener>(
+        &mut self,
+        term: &Term<T>,
+        lines: RangeInclusive<Line>,
+    ) -> Vec<(RangeInclusive<AlacPoint>, TerminalTriggerHighlight)> {
+        let start = AlacPoint::new(*lines.start(), Column(0));
+        let end = AlacPoint::new(*lines.end(), term.last_column());
+        let mut highlights = Vec::new();
+        for (search, highlight) in &mut self.searches {
+            highlights.extend(
+                RegexIter::new(start, end, AlacDirection::Right, term, search)
+                    .map(|range| (range, *highlight)),
+            );
+        }
+        highlights
+    }
+}
+
+/// Warns, once per session, that the configured triggers that do more than
+/// highlight are ignored, since output isn't read through Zed on this platform.
+#[cfg(not(unix))]
+pub(crate) fn warn_unsupported_action_triggers(triggers: &[TerminalTrigger]) {
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    if triggers
+        .iter()
+        .any(|trigger| trigger.notify.is_some() || trigger.open_path || trigger.run_task.is_some())
+    {
+        WARNED.call_once(|| {
+            warn!(
+                "Ignoring the `notify`, `open_path` and `run_task` actions of `terminal.triggers`: \
+                 they are only supported on macOS and Linux"
+            );
+        });
+    }
+}
+
+fn warn_invalid_trigger(regex: &str, error: &dyn std::error::Error) {
+    warn!("Ignoring trigger specified in `terminal.triggers`:\n\n\t{regex}\n\nError: {error}");
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{
+        event::VoidListener,
+        term::{Config, test::TermSize},
+        vte::ansi::{Processor, StdSyncHandler},
+    };
+
+    use super::*;
+
+    fn trigger(regex: &str) -> TerminalTrigger {
+        TerminalTrigger {
+            regex: regex.to_string(),
+            ..TerminalTrigger::default()
+        }
+    }
+
+    #[test]
+    fn test_trigger_actions() {
+        let actions = Arc::<Mutex<Vec<TriggerAction>>>::default();
+        let triggers = [
+            TerminalTrigger {
+                notify: Some("${profile} build finished".to_string()),
+                run_task: Some("deploy".to_string()),
+                ..trigger(r"Finished `(?<profile>\w+)` profile")
+            },
+            TerminalTrigger {
+                open_path: true,
+                ..trigger(r"--> (?<path>\S+:\d+:\d+)")
+            },
+            TerminalTrigger {
+                highlight: Some(TerminalTriggerHighlight::Error),
+                ..trigger("error")
+            },
+        ];
+        let mut scanner = TriggerScanner::new(&triggers, actions.clone()).unwrap();
+
+        // Lines are matched once they're complete, without their escape sequences.
+        scanner.scan(b"\x1b[1;31merror\x1b[0m: mismatched types\r\n  --> src/ma");
+        assert_eq!(*actions.lock(), Vec::new());
+        scanner.scan(b"in.rs:12:5\r\n");
+        assert_eq!(
+            std::mem::take(&mut *actions.lock()),
+            vec![TriggerAction::OpenPath("src/main.rs:12:5".to_string())]
+        );
+
+        // Only what's left of a line overwritten after a carriage return is matched.
+        scanner.scan(b"Finished `stale` profile\r    Finished `dev` profile in 2s\r\n");
+        assert_eq!(
+            std::mem::take(&mut *actions.lock()),
+            vec![
+                TriggerAction::Notify("dev build finished".to_string()),
+                TriggerAction::RunTask("deploy".to_string()),
+            ]
+        );
+
+        // Each trigger fires once per read.
+        scanner.scan(b"Finished `dev` profile\r\nFinished `release` profile\r\n");
+        assert_eq!(
+            std::mem::take(&mut *actions.lock()),
+            vec![
+                TriggerAction::Notify("dev build finished".to_string()),
+                TriggerAction::RunTask("deploy".to_string()),
+            ]
+        );
+
+        // Triggers that only highlight or whose regex is invalid have nothing to scan for.
+        assert!(TriggerScanner::new(&triggers[2..], actions.clone()).is_none());
+        let invalid = TerminalTrigger {
+            notify: Some("unreachable".to_string()),
+            ..trigger("(unclosed")
+        };
+        assert!(TriggerScanner::new(&[invalid], actions).is_none());
+    }
+
+    #[test]
+    fn test_trigger_highlights() {
+        let mut term = Term::new(Config::default(), &TermSize::new(20, 4), VoidListener);
+        let mut processor = Processor::<StdSyncHandler>::new();
+        processor.advance(
+            &mut term,
+            b"ok\r\nwarning: unused\r\nerror: failed\r\nwarning",
+        );
+
+        let mut highlights = TriggerHighlights::new(&[
+            TerminalTrigger {
+                highlight: Some(TerminalTriggerHighlight::Warning),
+                ..trigger("warning")
+            },
+            TerminalTrigger {
+                highlight: Some(TerminalTriggerHighlight::Error),
+                ..trigger(r"error: \w+")
+            },
+            trigger("ok"),
+        ]);
+        let point = |line, column| AlacPoint::new(Line(line), Column(column));
+        assert_eq!(
+            highlights.find(&term, Line(0)..=Line(2)),
+            vec![
+                (point(1, 0)..=point(1, 6), TerminalTriggerHighlight::Warning),
+                (point(2, 0)..=point(2, 12), TerminalTriggerHighlight::Error),
+            ]
+        );
+        assert_eq!(highlights.find(&term, Line(3)..=Line(3)).len(), 1);
+    }
+}
@@ -4,16 +4,16 @@ use gpui::{
     Element, ElementId, Entity, FocusHandle, Font, FontFeatures, FontStyle, FontWeight,
     GlobalElementId, HighlightStyle, Hitbox, Hsla, InputHandler, InteractiveElement, Interactivity,
     IntoElement, LayoutId, Length, ModifiersChangedEvent, MouseButton, MouseMoveEvent, Pixels,
-    Point, StatefulInteractiveElement, StrikethroughStyle, Styled, TextRun, TextStyle,
+    Point, RenderImage, StatefulInteractiveElement, StrikethroughStyle, Styled, TextRun, TextStyle,
     UTF16Selection, UnderlineStyle, WeakEntity, WhiteSpace, Window, div, fill, point, px, relative,
     size,
 };
 use itertools::Itertools;
 use language::CursorShape;
-use settings::Settings;
+use settings::{Settings, TerminalTriggerHighlight};
 use std::time::Instant;
 use terminal::{
-    IndexedCell, Terminal, TerminalBounds, TerminalContent,
+    CommandStatus, IndexedCell, Terminal, TerminalBounds, TerminalContent,
     alacritty_terminal::{
         grid::Dimensions,
         index::Point as AlacPoint,
@@ -33,7 +33,7 @@ use util::ResultExt;
 use workspace::Workspace;
 
 use std::mem;
-use std::{fmt::Debug, ops::RangeInclusive, rc::Rc};
+use std::{fmt::Debug, ops::RangeInclusive, rc::Rc, sync::Arc};
 
 use crate::{BlockContext, BlockProperties, ContentMode, TerminalMode, TerminalView};
 
@@ -43,6 +43,10 @@ pub struct LayoutState {
     batched_text_runs: Vec<BatchedTextRun>,
     rects: Vec<LayoutRect>,
     relative_highlighted_ranges: Vec<(RangeInclusive<AlacPoint>, Hsla)>,
+    /// Display rows of the prompts of the commands that were run, with their status color.
+    prompt_markers: Vec<(i32, Hsla)>,
+    /// Images shown in the terminal, with their bounds relative to its origin.
+    images: Vec<(Bounds<Pixels>, Arc<RenderImage>)>,
     cursor: Option<CursorLayout>,
     ime_cursor_bounds: Option<Bounds<Pixels>>,
     background_color: Hsla,
@@ -1033,13 +1037,64 @@ impl Element for TerminalElement {
                     cursor_char,
                     selection,
                     cursor,
+                    prompt_markers,
+                    images,
+                    trigger_highlights,
                     ..
                 } = &self.terminal.read(cx).last_content;
                 let mode = *mode;
                 let display_offset = *display_offset;
 
+                let status_colors = theme.status();
+                let prompt_markers = prompt_markers
+                    .iter()
+                    .map(|marker| {
+                        let color = match marker.status {
+                            CommandStatus::Running => status_colors.info,
+                            CommandStatus::Succeeded => status_colors.success,
+                            CommandStatus::Failed => status_colors.error,
+                            CommandStatus::Finished => status_colors.hint,
+                        };
+                        (marker.line.0 + display_offset as i32, color)
+                    })
+                    .collect::<Vec<_>>();
+
+                let images = images
+                    .iter()
+                    .map(|image| {
+                        let row = image.point.line.0 + display_offset as i32;
+                        let origin = point(
+                            image.point.column.0 as f32 * dimensions.cell_width,
+                            row as f32 * dimensions.line_height,
+                        );
+                        let cells_size = size(
+                            image.columns as f32 * dimensions.cell_width,
+                            image.rows as f32 * dimensions.line_height,
+                        );
+                        // Images shown at their own size are scaled down if they no
+                        // longer fit their cells, as happens when the font gets smaller.
+                        let image_size = image.size.map_or(cells_size, |image_size| {
+                            let scale = (cells_size.width / image_size.width)
+                                .min(cells_size.height / image_size.height)
+                                .min(1.);
+                            size(image_size.width * scale, image_size.height * scale)
+                        });
+                        (Bounds::new(origin, image_size), image.image.clone())
+                    })
+                    .collect::<Vec<_>>();
+
                 // searches, highlights to a single range representations
                 let mut relative_highlighted_ranges = Vec::new();
+                for (range, highlight) in trigger_highlights {
+                    let color = match highlight {
+                        TerminalTriggerHighlight::Error => status_colors.error_background,
+                        TerminalTriggerHighlight::Warning => status_colors.warning_background,
+                        TerminalTriggerHighlight::Success => status_colors.success_background,
+                        TerminalTriggerHighlight::Info => status_colors.info_background,
+                        TerminalTriggerHighlight::Hint => status_colors.hint_background,
+                    };
+                    relative_highlighted_ranges.push((range.clone(), color));
+                }
                 for search_match in search_matches {
                     relative_highlighted_ranges.push((search_match, match_color))
                 }
@@ -1218,6 +1273,8 @@ impl Element for TerminalElement {
                     dimensions,
                     rects,
                     relative_highlighted_ranges,
+                    prompt_markers,
+                    images,
                     mode,
                     display_offset,
                     hyperlink_tooltip,
@@ -1305,6 +1362,19 @@ impl Element for TerminalElement {
                         rect.paint(origin, &layout.dimensions, window);
                     }
 
+                    // Command status markers sit in the gutter, next to their prompt.
+                    let line_height = layout.dimensions.line_height;
+                    for (row, color) in &layout.prompt_markers {
+                        let marker_bounds = Bounds::new(
+                            point(
+                                origin.x - layout.gutter * 0.75,
+                                origin.y + line_height * *row as f32,
+                            ),
+                            size(layout.gutter * 0.5, line_height),
+                        );
+                        window.paint_quad(fill(marker_bounds, *color));
+                    }
+
                     for (relative_highlighted_range, color) in &layout.relative_highlighted_ranges {
                         if let Some((start_y, highlighted_range_lines)) =
                             to_highlighted_range_lines(relative_highlighted_range, layout, origin)
@@ -1333,6 +1403,26 @@ impl Element for TerminalElement {
                     }
                     let text_paint_time = text_paint_start.elapsed();
 
+                    for (image_bounds, image) in &layout.images {
+                        window
+                            .paint_image(
+                                *image_bounds + origin,
+                                Default::default(),
+                                image.clone(),
+                                0,
+                                false,
+                            )
+                            .log_err();
+                    }
+                    let painted_images = layout
+                        .images
+                        .iter()
+                        .map(|(_, image)| image.clone())
+                        .collect();
+                    self.terminal_view.update(cx, |terminal_view, _| {
+                        terminal_view.set_painted_images(painted_images, window)
+                    });
+
                     if let Some(text_to_mark) = &marked_text_cloned
                         && !text_to_mark.is_empty()
                         && let Some(ime_bounds) = layout.ime_cursor_bounds
@@ -48,7 +48,12 @@ actions!(
         /// Toggles the terminal panel.
         Toggle,
         /// Toggles focus on the terminal panel.
-        ToggleFocus
+        ToggleFocus,
+        /// Adds the active terminal to the group of terminals that keyboard input
+        /// is broadcast to, or removes it if it's already part of it.
+        ToggleBroadcast,
+        /// Stops broadcasting keyboard input, removing all terminals from the broadcast group.
+        StopBroadcast
     ]
 );
 
@@ -74,6 +79,25 @@ pub fn init(cx: &mut App) {
     .detach();
 }
 
+/// Terminals whose keyboard input is mirrored to each other, to type the same
+/// commands on several hosts or containers at once.
+#[derive(Default)]
+pub struct BroadcastGroup {
+    members: Vec<WeakEntity<TerminalView>>,
+}
+
+impl BroadcastGroup {
+    pub fn members(&self) -> impl Iterator<Item = Entity<TerminalView>> + '_ {
+        self.members.iter().filter_map(|member| member.upgrade())
+    }
+
+    pub fn contains(&self, terminal_view: &Entity<TerminalView>) -> bool {
+        self.members
+            .iter()
+            .any(|member| member.entity_id() == terminal_view.entity_id())
+    }
+}
+
 pub struct TerminalPanel {
     pub(crate) active_pane: Entity<Pane>,
     pub(crate) center: PaneGroup,
@@ -84,6 +108,7 @@ pub struct TerminalPanel {
     deferred_tasks: HashMap<TaskId, Task<()>>,
     assistant_enabled: bool,
     assistant_tab_bar_button: Option<AnyView>,
+    broadcast_group: Entity<BroadcastGroup>,
     active: bool,
 }
 
@@ -102,6 +127,7 @@ impl TerminalPanel {
             deferred_tasks: HashMap::default(),
             assistant_enabled: false,
             assistant_tab_bar_button: None,
+            broadcast_group: cx.new(|_| BroadcastGroup::default()),
             active: false,
         };
         terminal_panel.apply_tab_bar_buttons(&terminal_panel.active_pane, cx);
@@ -135,15 +161,20 @@ impl TerminalPanel {
         cx: &mut Context<Self>,
     ) {
         let assistant_tab_bar_button = self.assistant_tab_bar_button.clone();
+        let broadcast_group = self.broadcast_group.clone();
         terminal_pane.update(cx, |pane, cx| {
             pane.set_render_tab_bar_buttons(cx, move |pane, window, cx| {
-                let split_context = pane
+                let active_terminal_view = pane
                     .active_item()
-                    .and_then(|item| item.downcast::<TerminalView>())
+                    .and_then(|item| item.downcast::<TerminalView>());
+                let split_context = active_terminal_view
+                    .as_ref()
                     .map(|terminal_view| terminal_view.read(cx).focus_handle.clone());
-                let has_focused_rename_editor = pane
-                    .active_item()
-                    .and_then(|item| item.downcast::<TerminalView>())
+                let broadcasting = active_terminal_view
+                    .as_ref()
+                    .map(|terminal_view| broadcast_group.read(cx).contains(terminal_view));
+                let has_focused_rename_editor = active_terminal_view
+                    .as_ref()
                     .is_some_and(|view| view.read(cx).rename_editor_is_focused(window, cx));
                 if !pane.has_focus(window, cx)
                     && !pane.context_menu_focused(window, cx)
@@ -183,6 +214,27 @@ impl TerminalPanel {
                             }),
                     )
                     .children(assistant_tab_bar_button.clone())
+                    .when_some(broadcasting, |this, broadcasting| {
+                        this.child(
+                            IconButton::new("toggle-broadcast", IconName::SignalHigh)
+                                .icon_size(IconSize::Small)
+                                .toggle_state(broadcasting)
+                                .on_click(|_, window, cx| {
+                                    window.dispatch_action(ToggleBroadcast.boxed_clone(), cx);
+                                })
+                                .tooltip(move |_window, cx| {
+                                    Tooltip::for_action(
+                                        if broadcasting {
+                                            "Stop Broadcasting Input"
+                                        } else {
+                                            "Broadcast Input"
+                                        },
+                                        &ToggleBroadcast,
+                                        cx,
+                                    )
+                                }),
+                        )
+                    })
                     .child(
                         PopoverMenu::new("terminal-pane-tab-bar-split")
                             .trigger_with_tooltip(
@@ -232,6 +284,69 @@ impl TerminalPanel {
         });
     }
 
+    pub fn broadcast_group(&self) -> &Entity<BroadcastGroup> {
+        &self.broadcast_group
+    }
+
+    /// Adds the terminal to the broadcast group, or removes it if it's already part of it.
+    pub fn toggle_broadcast_for(
+        &mut self,
+        terminal_view: &Entity<TerminalView>,
+        cx: &mut Context<Self>,
+    ) {
+        let broadcasting = !self.broadcast_group.read(cx).contains(terminal_view);
+        self.broadcast_group.update(cx, |broadcast_group, cx| {
+            broadcast_group.members.retain(|member| {
+                member.upgrade().is_some() && member.entity_id() != terminal_view.entity_id()
+            });
+            if broadcasting {
+                broadcast_group.members.push(terminal_view.downgrade());
+            }
+            cx.notify();
+        });
+        let broadcast_group = broadcasting.then(|| self.broadcast_group.clone());
+        terminal_view.update(cx, |terminal_view, cx| {
+            terminal_view.set_broadcast_group(broadcast_group, cx);
+        });
+        self.notify_panes(cx);
+    }
+
+    fn toggle_broadcast(&mut self, _: &ToggleBroadcast, _: &mut Window, cx: &mut Context<Self>) {
+        let Some(terminal_view) = self
+            .active_pane
+            .read(cx)
+            .active_item()
+            .and_then(|item| item.downcast::<TerminalView>())
+        else {
+            return;
+        };
+        self.toggle_broadcast_for(&terminal_view, cx);
+    }
+
+    fn stop_broadcast(&mut self, _: &StopBroadcast, _: &mut Window, cx: &mut Context<Self>) {
+        let members = self.broadcast_group.update(cx, |broadcast_group, cx| {
+            cx.notify();
+            broadcast_group
+                .members
+                .drain(..)
+                .filter_map(|member| member.upgrade())
+                .collect::<Vec<_>>()
+        });
+        for terminal_view in members {
+            terminal_view.update(cx, |terminal_view, cx| {
+                terminal_view.set_broadcast_group(None, cx);
+            });
+        }
+        self.notify_panes(cx);
+    }
+
+    /// Re-renders the tab bars, whose broadcast toggles reflect the group's members.
+    fn notify_panes(&self, cx: &mut Context<Self>) {
+        for pane in self.center.panes() {
+            pane.update(cx, |_, cx| cx.notify());
+        }
+    }
+
     fn serialization_key(workspace: &Workspace) -> Option<String> {
         workspace
             .database_id()
@@ -1526,6 +1641,8 @@ impl Render for TerminalPanel {
                         };
                     },
                 ))
+                .on_action(cx.listener(Self::toggle_broadcast))
+                .on_action(cx.listener(Self::stop_broadcast))
             })
             .unwrap_or_else(|| div())
     }
@@ -1726,7 +1843,7 @@ mod tests {
     use std::num::NonZero;
 
     use super::*;
-    use gpui::{TestAppContext, UpdateGlobal as _};
+    use gpui::{Keystroke, TestAppContext, UpdateGlobal as _};
     use pretty_assertions::assert_eq;
     use project::FakeFs;
     use settings::SettingsStore;
@@ -2348,6 +2465,110 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_broadcasting_input(cx: &mut TestAppContext) {
+        init_test(cx);
+        let (window_handle, terminal_panel) = init_workspace_with_panel(cx).await;
+
+        let terminal_views = window_handle
+            .update(cx, |multi_workspace, window, cx| {
+                let workspace = multi_workspace.workspace().clone();
+                let project = workspace.read(cx).project().downgrade();
+                (0..3)
+                    .map(|_| {
+                        let terminal = cx.new(|cx| {
+                            terminal::TerminalBuilder::new_display_only(
+                                terminal::terminal_settings::CursorShape::default(),
+                                terminal::terminal_settings::AlternateScroll::On,
+                                None,
+                                0,
+                                cx.background_executor(),
+                                util::paths::PathStyle::local(),
+                            )
+                            .unwrap()
+                            .subscribe(cx)
+                        });
+                        let terminal_view = cx.new(|cx| {
+                            TerminalView::new(
+                                terminal,
+                                workspace.downgrade(),
+                                None,
+                                project.clone(),
+                                window,
+                                cx,
+                            )
+                        });
+                        terminal_panel.update(cx, |terminal_panel, cx| {
+                            terminal_panel.active_pane.update(cx, |pane, cx| {
+                                pane.add_item(
+                                    Box::new(terminal_view.clone()),
+                                    true,
+                                    true,
+                                    None,
+                                    window,
+                                    cx,
+                                );
+                            });
+                        });
+                        terminal_view
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap();
+
+        let take_input = |terminal_view: &Entity<TerminalView>, cx: &mut TestAppContext| {
+            terminal_view.update(cx, |terminal_view, cx| {
+                terminal_view
+                    .terminal()
+                    .update(cx, |terminal, _| terminal.take_input_log().concat())
+            })
+        };
+        let type_input = |terminal_view: &Entity<TerminalView>, cx: &mut TestAppContext| {
+            terminal_view.update(cx, |terminal_view, cx| {
+                terminal_view.commit_text("ls", cx);
+                terminal_view.process_keystroke(&Keystroke::parse("enter").unwrap(), cx);
+            })
+        };
+
+        terminal_panel.update(cx, |terminal_panel, cx| {
+            terminal_panel.toggle_broadcast_for(&terminal_views[0], cx);
+            terminal_panel.toggle_broadcast_for(&terminal_views[1], cx);
+        });
+        type_input(&terminal_views[0], cx);
+        assert_eq!(take_input(&terminal_views[0], cx), b"ls\r");
+        assert_eq!(take_input(&terminal_views[1], cx), b"ls\r");
+        assert_eq!(take_input(&terminal_views[2], cx), b"");
+
+        // Input typed into terminals outside of the group isn't mirrored.
+        type_input(&terminal_views[2], cx);
+        assert_eq!(take_input(&terminal_views[0], cx), b"");
+        assert_eq!(take_input(&terminal_views[1], cx), b"");
+        assert_eq!(take_input(&terminal_views[2], cx), b"ls\r");
+
+        terminal_panel.update(cx, |terminal_panel, cx| {
+            terminal_panel.toggle_broadcast_for(&terminal_views[0], cx);
+        });
+        type_input(&terminal_views[1], cx);
+        assert_eq!(take_input(&terminal_views[0], cx), b"");
+        assert_eq!(take_input(&terminal_views[1], cx), b"ls\r");
+        assert!(terminal_views[0].read_with(cx, |terminal_view, _| {
+            terminal_view.broadcast_group().is_none()
+        }));
+
+        window_handle
+            .update(cx, |_, window, cx| {
+                terminal_panel.update(cx, |terminal_panel, cx| {
+                    terminal_panel.stop_broadcast(&StopBroadcast, window, cx);
+                });
+            })
+            .unwrap();
+        assert!(terminal_views.iter().all(|terminal_view| {
+            terminal_view.read_with(cx, |terminal_view, _| {
+                terminal_view.broadcast_group().is_none()
+            })
+        }));
+    }
+
     fn set_max_tabs(cx: &mut TestAppContext, value: Option<usize>) {
         cx.update_global(|store: &mut SettingsStore, cx| {
             store.update_user_settings(cx, |settings| {
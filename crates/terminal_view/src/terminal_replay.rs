@@ -0,0 +1,555 @@
+//! A read-only tab that replays a terminal session recorded in the asciicast
+//! format, through a display-only terminal.
+
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context as _, Result};
+use gpui::{
+    App, Entity, EventEmitter, FocusHandle, Focusable, PathPromptOptions, Render, Task, WeakEntity,
+    actions,
+};
+use project::Project;
+use terminal::{
+    Asciicast, AsciicastEventKind, OpenRecording, Terminal, TerminalBuilder,
+    terminal_settings::{AlternateScroll, CursorShape},
+};
+use ui::{ProgressBar, Tooltip, prelude::*};
+use util::paths::PathStyle;
+use workspace::{
+    Workspace,
+    item::{Item, ItemEvent},
+    notifications::DetachAndPromptErr as _,
+};
+
+use crate::TerminalView;
+
+actions!(
+    terminal_replay,
+    [
+        /// Plays or pauses the replay of a terminal recording.
+        TogglePlayback,
+        /// Replays the recording from the start.
+        RestartPlayback,
+        /// Skips ahead in the recording.
+        SeekForward,
+        /// Goes back in the recording.
+        SeekBackward,
+    ]
+);
+
+/// How far seeking moves the playback position, in seconds.
+const SEEK_STEP: f64 = 5.;
+
+pub fn init(cx: &mut App) {
+    cx.observe_new(|workspace: &mut Workspace, _window, _cx| {
+        workspace.register_action(TerminalReplay::open);
+    })
+    .detach();
+}
+
+/// When an event is replayed, and what it does.
+struct ReplayEvent {
+    time: f64,
+    kind: AsciicastEventKind,
+}
+
+pub struct TerminalReplay {
+    title: SharedString,
+    /// The size of the terminal when the recording started.
+    initial_size: (usize, usize),
+    events: Vec<ReplayEvent>,
+    terminal: Entity<Terminal>,
+    terminal_view: Entity<TerminalView>,
+    focus_handle: FocusHandle,
+    /// The number of events that have been replayed into the terminal.
+    replayed_events: usize,
+    /// The playback position, in seconds, as of when playback was last paused or resumed.
+    position: f64,
+    /// When playback was last resumed, if it's playing.
+    playing_since: Option<Instant>,
+    _playback: Task<()>,
+}
+
+impl TerminalReplay {
+    fn open(
+        workspace: &mut Workspace,
+        _: &OpenRecording,
+        window: &mut Window,
+        cx: &mut Context<Workspace>,
+    ) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Replay".into()),
+        });
+        let project = workspace.project().downgrade();
+        cx.spawn_in(window, async move |workspace, cx| {
+            let Ok(Ok(Some(mut paths))) = paths.await else {
+                return Ok(());
+            };
+            let Some(path) = paths.pop() else {
+                return Ok(());
+            };
+            let recording = cx
+                .background_spawn({
+                    let path = path.clone();
+                    async move { load_recording(&path) }
+                })
+                .await?;
+            workspace.update_in(cx, |workspace, window, cx| {
+                let title = file_name(&path);
+                let replay = cx.new(|cx| {
+                    Self::new(
+                        recording,
+                        title,
+                        workspace.weak_handle(),
+                        project,
+                        window,
+                        cx,
+                    )
+                });
+                workspace.add_item_to_active_pane(Box::new(replay), None, true, window, cx);
+            })
+        })
+        .detach_and_prompt_err(
+            "Failed to open terminal recording",
+            window,
+            cx,
+            |_, _, _| None,
+        );
+    }
+
+    pub fn new(
+        recording: Asciicast,
+        title: SharedString,
+        workspace: WeakEntity<Workspace>,
+        project: WeakEntity<Project>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        // Long pauses are shortened, if the recording asks for it.
+        let idle_time_limit = recording
+            .header
+            .idle_time_limit
+            .filter(|limit| *limit >= 0.)
+            .unwrap_or(f64::INFINITY);
+        let mut previous_time = 0.;
+        let mut time = 0.;
+        let events = recording
+            .events
+            .into_iter()
+            .map(|event| {
+                time += (event.time - previous_time).clamp(0., idle_time_limit);
+                previous_time = event.time;
+                ReplayEvent {
+                    time,
+                    kind: event.kind,
+                }
+            })
+            .collect();
+
+        let terminal = cx.new(|cx| {
+            TerminalBuilder::new_display_only(
+                CursorShape::default(),
+                AlternateScroll::On,
+                None,
+                0,
+                cx.background_executor(),
+                PathStyle::local(),
+            )
+            .expect("display-only terminals are always created")
+            .subscribe(cx)
+        });
+        let initial_size = (recording.header.width, recording.header.height);
+        terminal.update(cx, |terminal, _| {
+            terminal.set_fixed_grid_size(initial_size.0, initial_size.1)
+        });
+        let terminal_view =
+            cx.new(|cx| TerminalView::new(terminal.clone(), workspace, None, project, window, cx));
+
+        let mut this = Self {
+            title,
+            initial_size,
+            events,
+            terminal,
+            terminal_view,
+            focus_handle: cx.focus_handle(),
+            replayed_events: 0,
+            position: 0.,
+            playing_since: None,
+            _playback: Task::ready(()),
+        };
+        this.play(cx);
+        this
+    }
+
+    fn duration(&self) -> f64 {
+        self.events.last().map_or(0., |event| event.time)
+    }
+
+    /// Returns the playback position, in seconds.
+    fn position(&self) -> f64 {
+        match self.playing_since {
+            Some(playing_since) => {
+                (self.position + playing_since.elapsed().as_secs_f64()).min(self.duration())
+            }
+            None => self.position,
+        }
+    }
+
+    fn is_playing(&self) -> bool {
+        self.playing_since.is_some()
+    }
+
+    fn play(&mut self, cx: &mut Context<Self>) {
+        if self.position() >= self.duration() {
+            self.seek(0., cx);
+        }
+        self.resume_from(self.position(), cx);
+    }
+
+    /// Plays the recording from the given position, which it has been replayed up to.
+    fn resume_from(&mut self, position: f64, cx: &mut Context<Self>) {
+        self.position = position;
+        self.playing_since = Some(Instant::now());
+        self._playback = cx.spawn(async move |this, cx| {
+            loop {
+                let Ok(Some(delay)) = this.update(cx, |this, cx| this.replay_due_events(cx)) else {
+                    break;
+                };
+                cx.background_executor().timer(delay).await;
+            }
+        });
+        cx.notify();
+    }
+
+    fn pause(&mut self, cx: &mut Context<Self>) {
+        self.position = self.position();
+        self.playing_since = None;
+        self._playback = Task::ready(());
+        cx.notify();
+    }
+
+    /// Replays the events up to the playback position, returning how long
+    /// to wait for the next one, or `None` once the recording has ended.
+    fn replay_due_events(&mut self, cx: &mut Context<Self>) -> Option<Duration> {
+        let position = self.position();
+        loop {
+            self.replay_until(position, cx);
+            let Some(event) = self.events.get(self.replayed_events) else {
+                // The playback task stops on its own.
+                self.position = self.duration();
+                self.playing_since = None;
+                cx.notify();
+                return None;
+            };
+            match Duration::try_from_secs_f64(event.time - position) {
+                Ok(delay) => return Some(delay),
+                // Events at times too far off to wait for are skipped.
+                Err(_) => self.replayed_events += 1,
+            }
+        }
+    }
+
+    fn replay_until(&mut self, position: f64, cx: &mut Context<Self>) {
+        let events = self.events[self.replayed_events..]
+            .iter()
+            .take_while(|event| event.time <= position);
+        let mut replayed_events = 0;
+        self.terminal.update(cx, |terminal, cx| {
+            for event in events {
+                match &event.kind {
+                    AsciicastEventKind::Output(output) => {
+                        terminal.write_pty_output(output.as_bytes(), cx)
+                    }
+                    AsciicastEventKind::Resize { columns, lines } => {
+                        terminal.set_fixed_grid_size(*columns, *lines)
+                    }
+                }
+                replayed_events += 1;
+            }
+        });
+        self.replayed_events += replayed_events;
+        cx.notify();
+    }
+
+    fn seek(&mut self, position: f64, cx: &mut Context<Self>) {
+        let position = position.clamp(0., self.duration());
+        // Output can't be taken back, so going back replays it from the start.
+        if position < self.position() {
+            self.terminal.update(cx, |terminal, cx| {
+                terminal.reset(cx);
+                terminal.set_fixed_grid_size(self.initial_size.0, self.initial_size.1);
+            });
+            self.replayed_events = 0;
+        }
+        self.replay_until(position, cx);
+        if self.is_playing() {
+            // The next event may now be due at a different time.
+            self.resume_from(position, cx);
+        } else {
+            self.position = position;
+        }
+    }
+
+    fn toggle_playback(&mut self, _: &TogglePlayback, _: &mut Window, cx: &mut Context<Self>) {
+        if self.is_playing() {
+            self.pause(cx);
+        } else {
+            self.play(cx);
+        }
+    }
+
+    fn restart_playback(&mut self, _: &RestartPlayback, _: &mut Window, cx: &mut Context<Self>) {
+        self.seek(0., cx);
+        if !self.is_playing() {
+            self.play(cx);
+        }
+    }
+
+    fn seek_forward(&mut self, _: &SeekForward, _: &mut Window, cx: &mut Context<Self>) {
+        self.seek(self.position() + SEEK_STEP, cx);
+    }
+
+    fn seek_backward(&mut self, _: &SeekBackward, _: &mut Window, cx: &mut Context<Self>) {
+        self.seek(self.position() - SEEK_STEP, cx);
+    }
+}
+
+fn load_recording(path: &Path) -> Result<Asciicast> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Asciicast::parse(&text)
+}
+
+fn file_name(path: &Path) -> SharedString {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+        .into()
+}
+
+fn format_time(seconds: f64) -> String {
+    let seconds = seconds as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+impl EventEmitter<ItemEvent> for TerminalReplay {}
+
+impl Focusable for TerminalReplay {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Item for TerminalReplay {
+    type Event = ItemEvent;
+
+    fn tab_content_text(&self, _detail: usize, _cx: &App) -> SharedString {
+        self.title.clone()
+    }
+
+    fn tab_icon(&self, _window: &Window, _cx: &App) -> Option<Icon> {
+        Some(Icon::new(IconName::PlayOutlined))
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn to_item_events(event: &Self::Event, f: &mut dyn FnMut(ItemEvent)) {
+        f(*event)
+    }
+}
+
+impl Render for TerminalReplay {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let position = self.position();
+        let duration = self.duration();
+        let (play_icon, play_tooltip) = if self.is_playing() {
+            (IconName::DebugPause, "Pause")
+        } else {
+            (IconName::PlayFilled, "Play")
+        };
+        let focus_handle = self.focus_handle.clone();
+
+        v_flex()
+            .key_context("TerminalReplay")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::toggle_playback))
+            .on_action(cx.listener(Self::restart_playback))
+            .on_action(cx.listener(Self::seek_forward))
+            .on_action(cx.listener(Self::seek_backward))
+            .size_full()
+            .child(
+                h_flex()
+                    .gap_1()
+                    .p_1()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(
+                        IconButton::new("restart-playback", IconName::RotateCcw)
+                            .icon_size(IconSize::Small)
+                            .tooltip(Tooltip::for_action_title_in(
+                                "Restart",
+                                &RestartPlayback,
+                                &focus_handle,
+                            ))
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.restart_playback(&RestartPlayback, window, cx)
+                            })),
+                    )
+                    .child(
+                        IconButton::new("seek-backward", IconName::ChevronLeft)
+                            .icon_size(IconSize::Small)
+                            .tooltip(Tooltip::for_action_title_in(
+                                "Back 5 Seconds",
+                                &SeekBackward,
+                                &focus_handle,
+                            ))
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.seek_backward(&SeekBackward, window, cx)
+                            })),
+                    )
+                    .child(
+                        IconButton::new("toggle-playback", play_icon)
+                            .icon_size(IconSize::Small)
+                            .tooltip(Tooltip::for_action_title_in(
+                                play_tooltip,
+                                &TogglePlayback,
+                                &focus_handle,
+                            ))
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.toggle_playback(&TogglePlayback, window, cx)
+                            })),
+                    )
+                    .child(
+                        IconButton::new("seek-forward", IconName::ChevronRight)
+                            .icon_size(IconSize::Small)
+                            .tooltip(Tooltip::for_action_title_in(
+                                "Forward 5 Seconds",
+                                &SeekForward,
+                                &focus_handle,
+                            ))
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.seek_forward(&SeekForward, window, cx)
+                            })),
+                    )
+                    .child(
+                        Label::new(format!(
+                            "{} / {}",
+                            format_time(position),
+                            format_time(duration)
+                        ))
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                    )
+                    .child(div().flex_1().px_2().child(ProgressBar::new(
+                        "playback-progress",
+                        position as f32,
+                        duration.max(f64::EPSILON) as f32,
+                        cx,
+                    ))),
+            )
+            .child(div().flex_1().min_h_0().child(self.terminal_view.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{TestAppContext, VisualTestContext};
+    use terminal::{AsciicastEvent, AsciicastHeader};
+    use workspace::{AppState, MultiWorkspace};
+
+    fn output(time: f64, output: &str) -> AsciicastEvent {
+        AsciicastEvent {
+            time,
+            kind: AsciicastEventKind::Output(output.into()),
+        }
+    }
+
+    #[gpui::test]
+    async fn test_seeking_replays(cx: &mut TestAppContext) {
+        let params = cx.update(AppState::test);
+        cx.update(|cx| {
+            theme_settings::init(theme::LoadThemes::JustBase, cx);
+        });
+        let project = Project::test(params.fs.clone(), [], cx).await;
+        let window_handle =
+            cx.add_window(|window, cx| MultiWorkspace::test_new(project.clone(), window, cx));
+
+        let recording = Asciicast {
+            header: AsciicastHeader {
+                version: 2,
+                width: 20,
+                height: 4,
+                timestamp: None,
+                idle_time_limit: Some(2.),
+                title: None,
+                env: Default::default(),
+            },
+            events: vec![
+                output(0.5, "one\r\n"),
+                output(1., "two\r\n"),
+                output(30., "three\r\n"),
+                AsciicastEvent {
+                    time: 31.,
+                    kind: AsciicastEventKind::Resize {
+                        columns: 30,
+                        lines: 5,
+                    },
+                },
+            ],
+        };
+        let replay = window_handle
+            .update(cx, |multi_workspace, window, cx| {
+                let workspace = multi_workspace.workspace().downgrade();
+                cx.new(|cx| {
+                    TerminalReplay::new(
+                        recording,
+                        "session.cast".into(),
+                        workspace,
+                        project.downgrade(),
+                        window,
+                        cx,
+                    )
+                })
+            })
+            .unwrap();
+        let cx = &mut VisualTestContext::from_window(window_handle.into(), cx);
+
+        let lines = |cx: &mut VisualTestContext| {
+            replay.update(cx, |replay, cx| {
+                replay.terminal.read(cx).last_n_non_empty_lines(10)
+            })
+        };
+        let grid_size = |cx: &mut VisualTestContext| {
+            replay.update(cx, |replay, cx| {
+                let bounds = replay.terminal.read(cx).last_content.terminal_bounds;
+                (bounds.num_columns(), bounds.num_lines())
+            })
+        };
+
+        replay.update(cx, |replay, cx| {
+            replay.pause(cx);
+            // The pause before the third line is shortened to the idle time limit.
+            assert_eq!(replay.duration(), 4.);
+            replay.seek(1., cx);
+        });
+        assert_eq!(lines(cx), vec!["one", "two"]);
+
+        replay.update(cx, |replay, cx| replay.seek(4., cx));
+        assert_eq!(lines(cx), vec!["one", "two", "three"]);
+        assert_eq!(grid_size(cx), (30, 5));
+
+        // Seeking back replays the output from the start, at the recorded size.
+        replay.update(cx, |replay, cx| replay.seek(0.7, cx));
+        assert_eq!(lines(cx), vec!["one"]);
+        assert_eq!(grid_size(cx), (20, 4));
+    }
+}
@@ -2,6 +2,7 @@ mod persistence;
 pub mod terminal_element;
 pub mod terminal_panel;
 mod terminal_path_like_target;
+pub mod terminal_replay;
 pub mod terminal_scrollbar;
 
 use editor::{
@@ -9,10 +10,10 @@ use editor::{
     ui_scrollbar_settings_from_raw,
 };
 use gpui::{
-    Action, AnyElement, App, ClipboardEntry, DismissEvent, Entity, EventEmitter, ExternalPaths,
-    FocusHandle, Focusable, Font, KeyContext, KeyDownEvent, Keystroke, MouseButton, MouseDownEvent,
-    Pixels, Point, Render, ScrollWheelEvent, Styled, Subscription, Task, WeakEntity, actions,
-    anchored, deferred, div,
+    Action, AnyElement, App, ClipboardEntry, ClipboardItem, DismissEvent, Entity, EventEmitter,
+    ExternalPaths, FocusHandle, Focusable, Font, KeyContext, KeyDownEvent, Keystroke, MouseButton,
+    MouseDownEvent, Pixels, Point, Render, RenderImage, ScrollWheelEvent, Styled, Subscription,
+    Task, WeakEntity, actions, anchored, deferred, div,
 };
 use itertools::Itertools;
 use menu;
@@ -23,7 +24,7 @@ use serde::Deserialize;
 use settings::{Settings, SettingsStore, TerminalBlink, WorkingDirectory};
 use std::{
     any::Any,
-    cmp,
+    cmp, mem,
     ops::{Range, RangeInclusive},
     path::{Path, PathBuf},
     rc::Rc,
@@ -32,9 +33,11 @@ use std::{
 };
 use task::TaskId;
 use terminal::{
-    Clear, Copy, Event, HoveredWord, MaybeNavigationTarget, Paste, ScrollLineDown, ScrollLineUp,
-    ScrollPageDown, ScrollPageUp, ScrollToBottom, ScrollToTop, ShowCharacterPalette, TaskState,
-    TaskStatus, Terminal, TerminalBounds, ToggleViMode,
+    Clear, Copy, CopyLastCommandOutput, Event, HoveredWord, MaybeNavigationTarget, Paste,
+    PathLikeTarget, ScrollLineDown, ScrollLineUp, ScrollPageDown, ScrollPageUp, ScrollToBottom,
+    ScrollToNextPrompt, ScrollToPreviousPrompt, ScrollToTop, SendCommandOutputToAgent,
+    ShowCharacterPalette, StartRecording, StopRecording, TaskState, TaskStatus, Terminal,
+    TerminalBounds, ToggleViMode, TriggerAction,
     alacritty_terminal::{
         index::Point as AlacPoint,
         term::{TermMode, point_to_viewport, search::RegexSearch},
@@ -42,7 +45,7 @@ use terminal::{
     terminal_settings::{CursorShape, TerminalSettings},
 };
 use terminal_element::TerminalElement;
-use terminal_panel::TerminalPanel;
+use terminal_panel::{BroadcastGroup, TerminalPanel};
 use terminal_path_like_target::{hover_path_like_target, open_path_like_target};
 use terminal_scrollbar::TerminalScrollHandle;
 use ui::{
@@ -52,11 +55,12 @@ use ui::{
 };
 use util::ResultExt;
 use workspace::{
-    CloseActiveItem, DraggedSelection, DraggedTab, NewCenterTerminal, NewTerminal, Pane,
+    CloseActiveItem, DraggedSelection, DraggedTab, NewCenterTerminal, NewTerminal, Pane, Toast,
     ToolbarItemLocation, Workspace, WorkspaceId, delete_unloaded_items,
     item::{
         HighlightedText, Item, ItemEvent, SerializableItem, TabContentParams, TabTooltipContent,
     },
+    notifications::{DetachAndPromptErr, NotificationId},
     register_serializable_item,
     searchable::{
         Direction, SearchEvent, SearchOptions, SearchToken, SearchableItem, SearchableItemHandle,
@@ -99,6 +103,7 @@ pub struct RenameTerminal;
 
 pub fn init(cx: &mut App) {
     terminal_panel::init(cx);
+    terminal_replay::init(cx);
 
     register_serializable_item::<TerminalView>(cx);
 
@@ -145,6 +150,10 @@ pub struct TerminalView {
     self_handle: WeakEntity<Self>,
     rename_editor: Option<Entity<Editor>>,
     rename_editor_subscription: Option<Subscription>,
+    broadcast_group: Option<Entity<BroadcastGroup>>,
+    /// The images painted in the last frame, whose textures are dropped once
+    /// they are no longer shown.
+    painted_images: Vec<Arc<RenderImage>>,
     _subscriptions: Vec<Subscription>,
     _terminal_subscriptions: Vec<Subscription>,
 }
@@ -265,6 +274,12 @@ impl TerminalView {
             focus_out,
             cx.observe(&blink_manager, |_, _, cx| cx.notify()),
             cx.observe_global::<SettingsStore>(Self::settings_changed),
+            cx.on_app_quit(Self::save_scrollback),
+            cx.on_release_in(window, |terminal_view: &mut Self, window, _| {
+                for image in terminal_view.painted_images.drain(..) {
+                    window.drop_image(image).ok();
+                }
+            }),
         ];
 
         Self {
@@ -291,6 +306,8 @@ impl TerminalView {
             self_handle: cx.entity().downgrade(),
             rename_editor: None,
             rename_editor_subscription: None,
+            broadcast_group: None,
+            painted_images: Vec::new(),
             _subscriptions: subscriptions,
             _terminal_subscriptions: terminal_subscriptions,
         }
@@ -371,6 +388,60 @@ impl TerminalView {
             self.terminal.update(cx, |term, _| {
                 term.input(text.to_string().into_bytes());
             });
+            self.broadcast_input(cx, |term, _| term.input(text.to_string().into_bytes()));
+        }
+    }
+
+    pub fn broadcast_group(&self) -> Option<&Entity<BroadcastGroup>> {
+        self.broadcast_group.as_ref()
+    }
+
+    pub(crate) fn set_broadcast_group(
+        &mut self,
+        broadcast_group: Option<Entity<BroadcastGroup>>,
+        cx: &mut Context<Self>,
+    ) {
+        self.broadcast_group = broadcast_group;
+        cx.notify();
+    }
+
+    /// Mirrors input typed into this terminal to the other terminals of its broadcast group.
+    fn broadcast_input(
+        &self,
+        cx: &mut Context<Self>,
+        input: impl Fn(&mut Terminal, &mut Context<Terminal>),
+    ) {
+        let Some(broadcast_group) = &self.broadcast_group else {
+            return;
+        };
+        let entity_id = cx.entity_id();
+        let terminals = broadcast_group
+            .read(cx)
+            .members()
+            .filter(|member| member.entity_id() != entity_id)
+            .map(|member| member.read(cx).terminal.clone())
+            .collect::<Vec<_>>();
+        for terminal in terminals {
+            terminal.update(cx, |terminal, cx| input(terminal, cx));
+        }
+    }
+
+    /// Drops the textures of the images that were painted before but no
+    /// longer are, as after they were deleted or scrolled out of view.
+    pub(crate) fn set_painted_images(
+        &mut self,
+        painted_images: Vec<Arc<RenderImage>>,
+        window: &mut Window,
+    ) {
+        let previous = mem::replace(&mut self.painted_images, painted_images);
+        for image in previous {
+            if !self
+                .painted_images
+                .iter()
+                .any(|painted| Arc::ptr_eq(painted, &image))
+            {
+                window.drop_image(image).ok();
+            }
         }
     }
 
@@ -504,6 +575,12 @@ impl TerminalView {
             .selection_text
             .as_ref()
             .is_some_and(|text| !text.is_empty());
+        let has_command_output = self
+            .terminal
+            .read(cx)
+            .command_blocks()
+            .iter()
+            .any(|block| block.is_finished());
         let context_menu = ContextMenu::build(window, cx, |menu, _, _| {
             menu.context(self.focus_handle.clone())
                 .action("New Terminal", Box::new(NewTerminal::default()))
@@ -512,12 +589,21 @@ impl TerminalView {
                 .action("Paste", Box::new(Paste))
                 .action("Select All", Box::new(SelectAll))
                 .action("Clear", Box::new(Clear))
+                .when(has_command_output, |menu| {
+                    menu.action("Copy Last Command Output", Box::new(CopyLastCommandOutput))
+                })
                 .when(assistant_enabled, |menu| {
                     menu.separator()
                         .action("Inline Assist", Box::new(InlineAssist::default()))
                         .when(has_selection, |menu| {
                             menu.action("Add to Agent Thread", Box::new(AddSelectionToThread))
                         })
+                        .when(has_command_output, |menu| {
+                            menu.action(
+                                "Send Command Output to Agent",
+                                Box::new(SendCommandOutputToAgent),
+                            )
+                        })
                 })
                 .separator()
                 .action(
@@ -581,6 +667,35 @@ impl TerminalView {
         cx.notify();
     }
 
+    /// Saves the terminal's scrollback when Zed quits, to show it again once
+    /// the terminal is restored. Clears it instead if that's turned off.
+    fn save_scrollback(&mut self, cx: &mut Context<Self>) -> Task<()> {
+        let Some(workspace_id) = self.workspace_id else {
+            return Task::ready(());
+        };
+        let terminal = self.terminal.read(cx);
+        if terminal.task().is_some() || !matches!(self.mode, TerminalMode::Standalone) {
+            return Task::ready(());
+        }
+        let scrollback = if TerminalSettings::get_global(cx).restore_scrollback {
+            // Keep what was saved before while a full screen application is shown.
+            let Some(scrollback) = terminal.serialized_scrollback() else {
+                return Task::ready(());
+            };
+            Some(scrollback)
+        } else {
+            None
+        };
+
+        let item_id = cx.entity_id().as_u64();
+        let db = TerminalDb::global(cx);
+        cx.background_spawn(async move {
+            db.save_scrollback(item_id, workspace_id, scrollback)
+                .await
+                .log_err();
+        })
+    }
+
     fn show_character_palette(
         &mut self,
         _: &ShowCharacterPalette,
@@ -746,6 +861,137 @@ impl TerminalView {
         cx.notify();
     }
 
+    fn scroll_to_previous_prompt(
+        &mut self,
+        _: &ScrollToPreviousPrompt,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_previous_prompt());
+        cx.notify();
+    }
+
+    fn scroll_to_next_prompt(
+        &mut self,
+        _: &ScrollToNextPrompt,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_next_prompt());
+        cx.notify();
+    }
+
+    fn copy_last_command_output(
+        &mut self,
+        _: &CopyLastCommandOutput,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(output) = self.terminal.read(cx).last_command_output() {
+            cx.write_to_clipboard(ClipboardItem::new_string(output));
+        }
+    }
+
+    fn send_command_output_to_agent(
+        &mut self,
+        _: &SendCommandOutputToAgent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // The agent picks up the terminal's selection, so select the output first.
+        let selected = self.terminal.update(cx, |terminal, cx| {
+            let Some(block) = terminal.focused_command_block().cloned() else {
+                return false;
+            };
+            if !terminal.select_command_output(&block) {
+                return false;
+            }
+            terminal.sync(window, cx);
+            true
+        });
+        if selected {
+            window.dispatch_action(AddSelectionToThread.boxed_clone(), cx);
+            cx.notify();
+        }
+    }
+
+    fn start_recording(&mut self, _: &StartRecording, window: &mut Window, cx: &mut Context<Self>) {
+        let directory = self
+            .terminal
+            .read(cx)
+            .working_directory()
+            .or_else(dirs::home_dir)
+            .unwrap_or_default();
+        let path = cx.prompt_for_new_path(&directory, Some("terminal.cast"));
+        cx.spawn_in(window, async move |this, cx| {
+            let Ok(Ok(Some(path))) = path.await else {
+                return Ok(());
+            };
+            this.update(cx, |this, cx| {
+                this.terminal
+                    .update(cx, |terminal, _| terminal.start_recording(&path))?;
+                cx.notify();
+                anyhow::Ok(())
+            })?
+        })
+        .detach_and_prompt_err("Failed to start recording", window, cx, |_, _, _| None);
+    }
+
+    fn stop_recording(&mut self, _: &StopRecording, window: &mut Window, cx: &mut Context<Self>) {
+        let result = self
+            .terminal
+            .update(cx, |terminal, _| terminal.stop_recording());
+        cx.notify();
+        Task::ready(result).detach_and_prompt_err(
+            "Failed to save recording",
+            window,
+            cx,
+            |_, _, _| None,
+        );
+    }
+
+    /// Does what a trigger from the terminal settings asks for when it matches the output.
+    fn run_trigger_action(
+        &mut self,
+        action: &TriggerAction,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match action {
+            TriggerAction::Notify(message) => {
+                let toast = Toast::new(
+                    NotificationId::composite::<TriggerAction>((
+                        "terminal-trigger",
+                        cx.entity_id(),
+                    )),
+                    message.clone(),
+                );
+                self.workspace
+                    .update(cx, |workspace, cx| workspace.show_toast(toast, cx))
+                    .ok();
+            }
+            TriggerAction::OpenPath(path) => {
+                let path_like_target = PathLikeTarget {
+                    maybe_path: path.clone(),
+                    terminal_dir: self.terminal.read(cx).working_directory(),
+                };
+                let workspace = self.workspace.clone();
+                open_path_like_target(&workspace, self, &path_like_target, window, cx);
+            }
+            TriggerAction::RunTask(label) => {
+                window.dispatch_action(
+                    Box::new(zed_actions::Spawn::ByName {
+                        task_name: label.clone(),
+                        reveal_target: None,
+                    }),
+                    cx,
+                );
+            }
+        }
+    }
+
     fn toggle_vi_mode(&mut self, _: &ToggleViMode, _: &mut Window, cx: &mut Context<Self>) {
         self.terminal.update(cx, |term, _| term.toggle_vi_mode());
         cx.notify();
@@ -826,6 +1072,7 @@ impl TerminalView {
                 if let Some(text) = clipboard.text() {
                     self.terminal
                         .update(cx, |terminal, _cx| terminal.paste(&text));
+                    self.broadcast_input(cx, |terminal, _| terminal.paste(&text));
                 }
             }
         }
@@ -853,6 +1100,7 @@ impl TerminalView {
         self.terminal.update(cx, |term, _| {
             term.input(text.0.to_string().into_bytes());
         });
+        self.broadcast_input(cx, |term, _| term.input(text.0.to_string().into_bytes()));
     }
 
     fn send_keystroke(&mut self, text: &SendKeystroke, _: &mut Window, cx: &mut Context<Self>) {
@@ -1101,6 +1349,9 @@ fn subscribe_for_terminal_events(
                     window.invalidate_character_coordinates();
                     cx.emit(SearchEvent::ActiveMatchChanged)
                 }
+                Event::Triggered(action) => {
+                    terminal_view.run_trigger_action(action, window, cx);
+                }
             }
         },
     );
@@ -1146,8 +1397,14 @@ impl TerminalView {
             )
         });
 
-        if handled && vi_mode_enabled {
-            cx.notify();
+        if handled {
+            if vi_mode_enabled {
+                cx.notify();
+            } else {
+                self.broadcast_input(cx, |term, cx| {
+                    term.try_keystroke(keystroke, TerminalSettings::get_global(cx).option_as_meta);
+                });
+            }
         }
 
         handled
@@ -1230,6 +1487,15 @@ impl Render for TerminalView {
             .on_action(cx.listener(TerminalView::scroll_page_down))
             .on_action(cx.listener(TerminalView::scroll_to_top))
             .on_action(cx.listener(TerminalView::scroll_to_bottom))
+            .on_action(cx.listener(TerminalView::scroll_to_previous_prompt))
+            .on_action(cx.listener(TerminalView::scroll_to_next_prompt))
+            .on_action(cx.listener(TerminalView::copy_last_command_output))
+            .on_action(cx.listener(TerminalView::send_command_output_to_agent))
+            // Output is only read through Zed, and so can only be recorded, on Unix.
+            .when(cfg!(unix), |div| {
+                div.on_action(cx.listener(TerminalView::start_recording))
+                    .on_action(cx.listener(TerminalView::stop_recording))
+            })
             .on_action(cx.listener(TerminalView::toggle_vi_mode))
             .on_action(cx.listener(TerminalView::show_character_palette))
             .on_action(cx.listener(TerminalView::select_all))
@@ -1256,6 +1522,9 @@ impl Render for TerminalView {
                     .id("terminal-view-container")
                     .size_full()
                     .bg(cx.theme().colors().editor_background)
+                    .when(self.broadcast_group.is_some(), |div| {
+                        div.border_1().border_color(cx.theme().colors().text_accent)
+                    })
                     .child(TerminalElement::new(
                         terminal_handle,
                         terminal_view_handle,
@@ -1414,6 +1683,13 @@ impl Item for TerminalView {
                         )
                     }),
             )
+            .when(self.broadcast_group.is_some(), |this| {
+                this.child(
+                    Icon::new(IconName::SignalHigh)
+                        .size(IconSize::XSmall)
+                        .color(Color::Accent),
+                )
+            })
             .into_any()
     }
 
@@ -1760,7 +2036,7 @@ impl SerializableItem for TerminalView {
         cx: &mut App,
     ) -> Task<anyhow::Result<Entity<Self>>> {
         window.spawn(cx, async move |cx| {
-            let (cwd, custom_title) = cx
+            let (cwd, custom_title, scrollback) = cx
                 .update(|_window, cx| {
                     let db = TerminalDb::global(cx);
                     let from_db = db
@@ -1782,14 +2058,21 @@ impl SerializableItem for TerminalView {
                         .log_err()
                         .flatten()
                         .filter(|title| !title.trim().is_empty());
-                    (cwd, custom_title)
+                    let scrollback = TerminalSettings::get_global(cx)
+                        .restore_scrollback
+                        .then(|| db.get_scrollback(item_id, workspace_id).log_err().flatten())
+                        .flatten();
+                    (cwd, custom_title, scrollback)
                 })
                 .ok()
-                .unwrap_or((None, None));
+                .unwrap_or((None, None, None));
 
             let terminal = project
                 .update(cx, |project, cx| project.create_terminal_shell(cwd, cx))
                 .await?;
+            if let Some(scrollback) = scrollback {
+                terminal.update(cx, |terminal, _| terminal.restore_scrollback(&scrollback));
+            }
             cx.update(|window, cx| {
                 cx.new(|cx| {
                     let mut view = TerminalView::new(
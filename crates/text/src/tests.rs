@@ -648,6 +648,147 @@ fn test_history() {
     assert_eq!(buffer.text(), "X12cde6");
 }
 
+#[test]
+fn test_undo_history() {
+    let mut buffer = Buffer::new(ReplicaId::LOCAL, BufferId::new(1).unwrap(), "one two three");
+    buffer.set_group_interval(Duration::from_secs(0));
+    buffer.edit([(0..3, "1"), (8..13, "3")]);
+    buffer.edit([(2..5, "2")]);
+    buffer.edit([(0..0, "zero ")]);
+    buffer.edit([(buffer.len()..buffer.len(), " four")]);
+    buffer.undo();
+    assert_eq!(buffer.text(), "zero 1 2 3");
+
+    let history = buffer.undo_history().unwrap();
+    assert_eq!(history.undo_stack.len(), 3);
+    assert_eq!(history.redo_stack.len(), 1);
+    assert_eq!(
+        history.undo_stack[0],
+        [
+            HistoryEdit {
+                range: 0..3,
+                old_text: "one".into(),
+                new_text: "1".into(),
+            },
+            HistoryEdit {
+                range: 8..13,
+                old_text: "three".into(),
+                new_text: "3".into(),
+            },
+        ]
+    );
+
+    // The history applies to a buffer with the same text, even without its operations.
+    let mut restored = Buffer::new(ReplicaId::LOCAL, BufferId::new(2).unwrap(), "zero 1 2 3");
+    restored.restore_undo_history(&history).unwrap();
+    assert_eq!(restored.text(), "zero 1 2 3");
+    assert_eq!(restored.undo_history().unwrap(), history);
+
+    restored.redo();
+    assert_eq!(restored.text(), "zero 1 2 3 four");
+    restored.undo();
+    restored.undo();
+    assert_eq!(restored.text(), "1 2 3");
+    restored.undo();
+    assert_eq!(restored.text(), "1 two 3");
+    restored.undo();
+    assert_eq!(restored.text(), "one two three");
+    assert!(restored.undo().is_none());
+
+    // A history ending at a different text is rejected without editing the buffer.
+    let mut changed = Buffer::new(ReplicaId::LOCAL, BufferId::new(3).unwrap(), "zero 1 2 4");
+    assert!(changed.restore_undo_history(&history).is_none());
+    assert_eq!(changed.text(), "zero 1 2 4");
+    assert!(changed.peek_undo_stack().is_none());
+}
+
+#[test]
+fn test_undo_tree() {
+    let mut buffer = Buffer::new(ReplicaId::LOCAL, BufferId::new(1).unwrap(), "abc");
+    buffer.set_group_interval(Duration::from_secs(0));
+    buffer.edit([(3..3, "d")]);
+    buffer.edit([(4..4, "e")]);
+    assert_eq!(buffer.undo_tree().current(), 2);
+
+    // Editing after undoing starts a new branch, keeping the undone state.
+    buffer.undo();
+    assert_eq!(buffer.undo_tree().current(), 1);
+    buffer.edit([(4..4, "f")]);
+    assert_eq!(buffer.text(), "abcdf");
+    assert!(buffer.redo().is_none());
+    let tree = buffer.undo_tree();
+    assert_eq!(tree.current(), 3);
+    assert_eq!(tree.states().collect::<Vec<_>>(), [1, 2, 3]);
+    assert_eq!(tree.children(1), [2, 3]);
+    assert_eq!(tree.leaves(), [2, 3]);
+    assert_eq!(tree.depth(3), 2);
+
+    // Any state can be reached, and undo and redo then follow its branch.
+    buffer.go_to_undo_state(2).unwrap();
+    assert_eq!(buffer.text(), "abcde");
+    assert_eq!(buffer.undo_tree().current(), 2);
+    buffer.undo();
+    assert_eq!(buffer.text(), "abcd");
+    buffer.redo();
+    assert_eq!(buffer.text(), "abcde");
+
+    buffer.go_to_undo_state(0).unwrap();
+    assert_eq!(buffer.text(), "abc");
+    buffer.redo();
+    buffer.redo();
+    assert_eq!(buffer.text(), "abcdf");
+
+    // States can be visited in the order they were created, as with Vim's `g-` and `g+`.
+    assert_eq!(buffer.undo_tree().older(), Some(2));
+    buffer.go_to_undo_state(2).unwrap();
+    assert_eq!(buffer.undo_tree().older(), Some(1));
+    assert_eq!(buffer.undo_tree().newer(), Some(3));
+
+    assert_eq!(
+        buffer.undo_state_edits(3).unwrap(),
+        [(
+            0,
+            HistoryEdit {
+                range: 4..4,
+                old_text: String::new(),
+                new_text: "f".into(),
+            }
+        )]
+    );
+    assert_eq!(buffer.text(), "abcde");
+
+    // Forgotten transactions become part of the states that follow them.
+    let transaction_id = buffer.peek_undo_stack().unwrap().transaction_id();
+    buffer.forget_transaction(transaction_id);
+    assert_eq!(buffer.undo_tree().states().collect::<Vec<_>>(), [1, 3]);
+    assert_eq!(buffer.undo_tree().current(), 1);
+}
+
+#[test]
+fn test_undo_tree_pruning() {
+    let mut buffer = Buffer::new(ReplicaId::LOCAL, BufferId::new(1).unwrap(), "");
+    buffer.set_group_interval(Duration::from_secs(0));
+    // Each iteration abandons a state by editing after undoing it.
+    for _ in 0..undo_tree::MAX_STATES {
+        buffer.edit([(buffer.len()..buffer.len(), "a")]);
+        buffer.undo();
+        buffer.edit([(buffer.len()..buffer.len(), "b")]);
+    }
+
+    let tree = buffer.undo_tree();
+    // Pruning happens in batches, so the tree may exceed the limit slightly.
+    assert!(tree.len() <= undo_tree::MAX_STATES + undo_tree::MAX_STATES / 4);
+    assert!(!tree.contains(1));
+    assert!(tree.contains(2));
+    // The states that can be undone are kept.
+    assert_eq!(tree.depth(tree.current()), undo_tree::MAX_STATES);
+    for _ in 0..undo_tree::MAX_STATES {
+        buffer.undo().unwrap();
+    }
+    assert_eq!(buffer.text(), "");
+    assert_eq!(buffer.undo_tree().current(), 0);
+}
+
 #[test]
 fn test_finalize_last_transaction() {
     let now = Instant::now();
@@ -9,6 +9,7 @@ pub mod subscription;
 #[cfg(test)]
 mod tests;
 mod undo_map;
+mod undo_tree;
 
 pub use anchor::*;
 use anyhow::{Context as _, Result};
@@ -40,6 +41,7 @@ pub use subscription::*;
 pub use sum_tree::Bias;
 use sum_tree::{Dimensions, FilterCursor, SumTree, TreeMap, TreeSet};
 use undo_map::UndoMap;
+pub use undo_tree::{UndoStateId, UndoTree, UndoTreeNode};
 use util::debug_panic;
 
 #[cfg(any(test, feature = "test-support"))]
@@ -150,6 +152,63 @@ impl HistoryEntry {
     }
 }
 
+/// A buffer's undo and redo stacks described as plain text edits, independently
+/// of the operations that produced them, so that they can outlive the buffer.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UndoHistory {
+    /// The transactions that can be undone, oldest first.
+    pub undo_stack: Vec<Vec<HistoryEdit>>,
+    /// The transactions that can be redone, with the next one to redo last.
+    pub redo_stack: Vec<Vec<HistoryEdit>>,
+}
+
+impl UndoHistory {
+    pub fn is_empty(&self) -> bool {
+        self.undo_stack.is_empty() && self.redo_stack.is_empty()
+    }
+}
+
+/// A copy of a buffer and its history, from which an [`UndoHistory`] can be built
+/// without holding onto the buffer, e.g. on a background thread.
+pub struct UndoHistoryReplay(Buffer);
+
+impl UndoHistoryReplay {
+    pub fn build(self) -> UndoHistory {
+        let mut buffer = self.0.fork();
+        let mut undo_stack = Vec::new();
+        while !buffer.history.undo_stack.is_empty() {
+            let after = buffer.snapshot.clone();
+            buffer.undo();
+            undo_stack.push(history_edits(&buffer.snapshot, &after));
+        }
+        undo_stack.reverse();
+
+        let mut buffer = self.0;
+        let mut redo_stack = Vec::new();
+        while !buffer.history.redo_stack.is_empty() {
+            let before = buffer.snapshot.clone();
+            buffer.redo();
+            redo_stack.push(history_edits(&before, &buffer.snapshot));
+        }
+        redo_stack.reverse();
+
+        UndoHistory {
+            undo_stack,
+            redo_stack,
+        }
+    }
+}
+
+/// An edit performed by a transaction of an [`UndoHistory`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryEdit {
+    /// The replaced range, in the text preceding the transaction.
+    pub range: Range<usize>,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+#[derive(Clone)]
 struct History {
     base_text: Rope,
     operations: TreeMap<clock::Lamport, Operation>,
@@ -157,6 +216,7 @@ struct History {
     redo_stack: Vec<HistoryEntry>,
     transaction_depth: usize,
     group_interval: Duration,
+    undo_tree: UndoTree,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -228,6 +288,14 @@ impl History {
             } else {
                 Duration::from_millis(300)
             },
+            undo_tree: UndoTree::default(),
+        }
+    }
+
+    fn push_undo_tree_state(&mut self) {
+        if let Some(entry) = self.undo_stack.last() {
+            self.undo_tree.push(&entry.transaction);
+            self.undo_tree.prune(&self.redo_stack);
         }
     }
 
@@ -276,6 +344,7 @@ impl History {
                 None
             } else {
                 self.redo_stack.clear();
+                self.push_undo_tree_state();
                 let entry = self.undo_stack.last_mut().unwrap();
                 entry.last_edit_at = now;
                 Some(entry)
@@ -330,6 +399,11 @@ impl History {
             if let Some(entry) = entries_to_merge.last_mut() {
                 last_entry.last_edit_at = entry.last_edit_at;
             }
+
+            for entry in &*entries_to_merge {
+                self.undo_tree.remove(entry.transaction.id);
+            }
+            self.undo_tree.update(&last_entry.transaction);
         }
 
         self.undo_stack.truncate(new_len);
@@ -352,6 +426,7 @@ impl History {
             last_edit_at: now,
             suppress_grouping: false,
         });
+        self.push_undo_tree_state();
     }
 
     /// Differs from `push_transaction` in that it does not clear the redo
@@ -384,6 +459,7 @@ impl History {
             last_edit_at: now,
             suppress_grouping: false,
         });
+        self.push_undo_tree_state();
         id
     }
 
@@ -398,6 +474,7 @@ impl History {
     fn pop_undo(&mut self) -> Option<&HistoryEntry> {
         assert_eq!(self.transaction_depth, 0);
         if let Some(entry) = self.undo_stack.pop() {
+            self.undo_tree.undo(entry.transaction.id);
             self.redo_stack.push(entry);
             self.redo_stack.last()
         } else {
@@ -413,6 +490,7 @@ impl History {
             .iter()
             .rposition(|entry| entry.transaction.id == transaction_id)?;
         let entry = self.undo_stack.remove(entry_ix);
+        self.undo_tree.undo(entry.transaction.id);
         self.redo_stack.push(entry);
         self.redo_stack.last()
     }
@@ -426,15 +504,17 @@ impl History {
             .iter()
             .rposition(|entry| entry.transaction.id == transaction_id)
         {
-            self.redo_stack
-                .extend(self.undo_stack.drain(entry_ix..).rev());
+            for entry in self.undo_stack.drain(entry_ix..).rev() {
+                self.undo_tree.undo(entry.transaction.id);
+                self.redo_stack.push(entry);
+            }
         }
         &self.redo_stack[redo_stack_start_len..]
     }
 
     fn forget(&mut self, transaction_id: TransactionId) -> Option<Transaction> {
         assert_eq!(self.transaction_depth, 0);
-        if let Some(entry_ix) = self
+        let transaction = if let Some(entry_ix) = self
             .undo_stack
             .iter()
             .rposition(|entry| entry.transaction.id == transaction_id)
@@ -448,7 +528,9 @@ impl History {
             Some(self.redo_stack.remove(entry_ix).transaction)
         } else {
             None
-        }
+        };
+        self.undo_tree.remove(transaction_id);
+        transaction
     }
 
     fn transaction(&self, transaction_id: TransactionId) -> Option<&Transaction> {
@@ -482,12 +564,14 @@ impl History {
             && let Some(destination) = self.transaction_mut(destination)
         {
             destination.edit_ids.extend(transaction.edit_ids);
+            self.undo_tree.update(destination);
         }
     }
 
     fn pop_redo(&mut self) -> Option<&HistoryEntry> {
         assert_eq!(self.transaction_depth, 0);
         if let Some(entry) = self.redo_stack.pop() {
+            self.undo_tree.redo(entry.transaction.id);
             self.undo_stack.push(entry);
             self.undo_stack.last()
         } else {
@@ -504,13 +588,28 @@ impl History {
             .iter()
             .rposition(|entry| entry.transaction.id == transaction_id)
         {
-            self.undo_stack
-                .extend(self.redo_stack.drain(entry_ix..).rev());
+            for entry in self.redo_stack.drain(entry_ix..).rev() {
+                self.undo_tree.redo(entry.transaction.id);
+                self.undo_stack.push(entry);
+            }
         }
         &self.undo_stack[undo_stack_start_len..]
     }
 }
 
+/// Returns the edits that turn the text of `before` into the text of `after`, given
+/// that `after` is a later version of the same buffer.
+fn history_edits(before: &BufferSnapshot, after: &BufferSnapshot) -> Vec<HistoryEdit> {
+    after
+        .edits_since::<usize>(&before.version)
+        .map(|edit| HistoryEdit {
+            old_text: before.text_for_range(edit.old.clone()).collect(),
+            new_text: after.text_for_range(edit.new).collect(),
+            range: edit.old,
+        })
+        .collect()
+}
+
 struct Edits<'a, D: TextDimension, F: FnMut(&FragmentSummary) -> bool> {
     visible_cursor: rope::Cursor<'a>,
     deleted_cursor: rope::Cursor<'a>,
@@ -1613,6 +1712,185 @@ impl Buffer {
             .collect()
     }
 
+    /// Describes the undo and redo stacks as text edits, or returns `None` while a
+    /// transaction is in progress.
+    pub fn undo_history(&self) -> Option<UndoHistory> {
+        self.undo_history_replay().map(UndoHistoryReplay::build)
+    }
+
+    /// Captures the undo and redo stacks so that they can be described as text edits
+    /// away from the buffer, or returns `None` while a transaction is in progress.
+    pub fn undo_history_replay(&self) -> Option<UndoHistoryReplay> {
+        if self.history.transaction_depth > 0 {
+            return None;
+        }
+        Some(UndoHistoryReplay(self.fork()))
+    }
+
+    /// Replays the given history so that it can be undone and redone, given that the
+    /// buffer has no history of its own and its text is the one the history ends at.
+    ///
+    /// Returns the operations performed, or `None` if the history doesn't apply.
+    pub fn restore_undo_history(&mut self, history: &UndoHistory) -> Option<Vec<Operation>> {
+        if self.history.transaction_depth > 0
+            || !self.history.undo_stack.is_empty()
+            || !self.history.redo_stack.is_empty()
+        {
+            return None;
+        }
+
+        // Ensure the whole history applies before touching the buffer.
+        let mut buffer = self.fork();
+        for edits in history.undo_stack.iter().rev() {
+            buffer.revert_history_edits(edits)?;
+        }
+        for edits in history
+            .undo_stack
+            .iter()
+            .chain(history.redo_stack.iter().rev())
+        {
+            buffer.apply_history_edits(edits)?;
+        }
+
+        let mut operations = Vec::new();
+        // Rewind to the oldest text without recording a transaction.
+        self.start_transaction();
+        for edits in history.undo_stack.iter().rev() {
+            operations.extend(self.revert_history_edits(edits));
+        }
+        if let Some((transaction_id, _)) = self.end_transaction() {
+            self.forget_transaction(transaction_id);
+        }
+
+        for edits in history
+            .undo_stack
+            .iter()
+            .chain(history.redo_stack.iter().rev())
+        {
+            self.start_transaction();
+            operations.extend(self.apply_history_edits(edits));
+            self.end_transaction();
+            self.finalize_last_transaction();
+        }
+        for _ in &history.redo_stack {
+            operations.extend(self.undo().map(|(_, operation)| operation));
+        }
+        Some(operations)
+    }
+
+    pub fn undo_tree(&self) -> &UndoTree {
+        &self.history.undo_tree
+    }
+
+    /// Moves the buffer to the given state of its undo tree, undoing and redoing
+    /// transactions across branches as needed. Afterwards, the undo stack leads to
+    /// that state and the redo stack follows its most recent changes.
+    pub fn go_to_undo_state(&mut self, id: UndoStateId) -> Option<Operation> {
+        let tree = &mut self.history.undo_tree;
+        if self.history.transaction_depth > 0 || !tree.contains(id) || tree.current() == id {
+            return None;
+        }
+
+        let transactions = tree.move_to(id);
+        let tree = &self.history.undo_tree;
+        let now = Instant::now();
+        let entries = |states: Vec<UndoStateId>| {
+            states
+                .into_iter()
+                .filter_map(|state| tree.node(state))
+                .map(|node| HistoryEntry {
+                    transaction: node.transaction().clone(),
+                    first_edit_at: now,
+                    last_edit_at: now,
+                    suppress_grouping: true,
+                })
+                .collect::<Vec<_>>()
+        };
+        let undo_stack = entries(tree.path_to(id));
+        let mut redo_stack = entries(tree.redo_path_from(id));
+        redo_stack.reverse();
+        self.history.undo_stack = undo_stack;
+        self.history.redo_stack = redo_stack;
+
+        let mut counts = HashMap::default();
+        for transaction in transactions {
+            for edit_id in transaction.edit_ids {
+                counts.insert(edit_id, self.undo_map.undo_count(edit_id).saturating_add(1));
+            }
+        }
+        let operation = self.undo_operations(counts);
+        self.history.push(operation.clone());
+        Some(operation)
+    }
+
+    /// Returns the edits that lead to the given state from the one preceding it in
+    /// the undo tree, along with the row at which each of them starts.
+    pub fn undo_state_edits(&self, id: UndoStateId) -> Option<Vec<(u32, HistoryEdit)>> {
+        let parent = self.history.undo_tree.parent(id)?;
+        if self.history.transaction_depth > 0 || self.history.undo_tree.node(id).is_none() {
+            return None;
+        }
+        let mut buffer = self.fork();
+        buffer.go_to_undo_state(parent);
+        let before = buffer.snapshot.clone();
+        buffer.go_to_undo_state(id);
+        Some(
+            history_edits(&before, &buffer.snapshot)
+                .into_iter()
+                .map(|edit| (before.offset_to_point(edit.range.start).row, edit))
+                .collect(),
+        )
+    }
+
+    fn apply_history_edits(&mut self, edits: &[HistoryEdit]) -> Option<Operation> {
+        for edit in edits {
+            if !self.contains_text_at(edit.range.start, &edit.old_text) {
+                return None;
+            }
+        }
+        Some(
+            self.edit(
+                edits
+                    .iter()
+                    .map(|edit| (edit.range.clone(), edit.new_text.as_str())),
+            ),
+        )
+    }
+
+    fn revert_history_edits(&mut self, edits: &[HistoryEdit]) -> Option<Operation> {
+        let mut delta = 0_isize;
+        let mut reverted_edits = Vec::with_capacity(edits.len());
+        for edit in edits {
+            let start = edit.range.start.checked_add_signed(delta)?;
+            let range = start..start + edit.new_text.len();
+            if !self.contains_text_at(start, &edit.new_text) {
+                return None;
+            }
+            delta += edit.new_text.len() as isize - edit.range.len() as isize;
+            reverted_edits.push((range, edit.old_text.as_str()));
+        }
+        Some(self.edit(reverted_edits))
+    }
+
+    fn contains_text_at(&self, offset: usize, text: &str) -> bool {
+        offset + text.len() <= self.len() && self.contains_str_at(offset, text)
+    }
+
+    /// Returns a copy of the buffer that shares its history, for replaying that
+    /// history without affecting the buffer.
+    fn fork(&self) -> Self {
+        Self {
+            snapshot: self.snapshot.clone(),
+            history: self.history.clone(),
+            deferred_ops: OperationQueue::new(),
+            deferred_replicas: HashSet::default(),
+            lamport_clock: self.lamport_clock,
+            subscriptions: Default::default(),
+            edit_id_resolvers: Default::default(),
+            wait_for_version_txs: Default::default(),
+        }
+    }
+
     fn undo_or_redo(&mut self, transaction: Transaction) -> Operation {
         let mut counts = HashMap::default();
         for edit_id in transaction.edit_ids {
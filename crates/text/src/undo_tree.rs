@@ -0,0 +1,314 @@
+use crate::{HistoryEntry, Transaction, TransactionId};
+use collections::{BTreeMap, HashMap, HashSet};
+use std::time::SystemTime;
+
+/// Identifies a state of an [`UndoTree`]. States are numbered in the order they
+/// were created, like Vim's change numbers, and the original state is `0`.
+pub type UndoStateId = usize;
+
+/// The number of states above which states that can no longer be reached by
+/// undoing and redoing are discarded, oldest first.
+pub(crate) const MAX_STATES: usize = 1000;
+
+/// Tracks every state a buffer went through, including the ones on branches that
+/// can no longer be redone because the buffer was edited after undoing them.
+///
+/// The tree is kept up to date as transactions are pushed, undone and redone: the
+/// undo stack is the path from the original state to the current one, and the
+/// redo stack continues that path through the states that were undone last.
+#[derive(Clone, Debug)]
+pub struct UndoTree {
+    nodes: BTreeMap<UndoStateId, UndoTreeNode>,
+    /// The children of the original state, oldest first.
+    roots: Vec<UndoStateId>,
+    ids_by_transaction: HashMap<TransactionId, UndoStateId>,
+    /// The applied states, from the original state to the current one.
+    path: Vec<UndoStateId>,
+    next_id: UndoStateId,
+    prune_at: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct UndoTreeNode {
+    transaction: Transaction,
+    parent: UndoStateId,
+    /// The states following this one, oldest first.
+    children: Vec<UndoStateId>,
+    created_at: SystemTime,
+    applied: bool,
+}
+
+impl UndoTreeNode {
+    pub fn transaction(&self) -> &Transaction {
+        &self.transaction
+    }
+
+    pub fn created_at(&self) -> SystemTime {
+        self.created_at
+    }
+}
+
+impl Default for UndoTree {
+    fn default() -> Self {
+        Self {
+            nodes: BTreeMap::default(),
+            roots: Vec::new(),
+            ids_by_transaction: HashMap::default(),
+            path: Vec::new(),
+            next_id: 1,
+            prune_at: MAX_STATES,
+        }
+    }
+}
+
+impl UndoTree {
+    /// The state the buffer is in.
+    pub fn current(&self) -> UndoStateId {
+        self.path.last().copied().unwrap_or(0)
+    }
+
+    /// The states of the tree other than the original one, oldest first.
+    pub fn states(&self) -> impl Iterator<Item = UndoStateId> + '_ {
+        self.nodes.keys().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, id: UndoStateId) -> Option<&UndoTreeNode> {
+        self.nodes.get(&id)
+    }
+
+    pub fn contains(&self, id: UndoStateId) -> bool {
+        id == 0 || self.nodes.contains_key(&id)
+    }
+
+    /// The state preceding the given one, or `None` for the original state.
+    pub fn parent(&self, id: UndoStateId) -> Option<UndoStateId> {
+        Some(self.nodes.get(&id)?.parent)
+    }
+
+    /// The states following the given one, oldest first.
+    pub fn children(&self, id: UndoStateId) -> &[UndoStateId] {
+        if id == 0 {
+            &self.roots
+        } else {
+            self.nodes
+                .get(&id)
+                .map_or(&[], |node| node.children.as_slice())
+        }
+    }
+
+    /// The number of changes between the original state and the given one.
+    pub fn depth(&self, id: UndoStateId) -> usize {
+        let mut depth = 0;
+        let mut state = id;
+        while let Some(parent) = self.parent(state) {
+            depth += 1;
+            state = parent;
+        }
+        depth
+    }
+
+    /// The states without any children, which Vim's `:undolist` shows.
+    pub fn leaves(&self) -> Vec<UndoStateId> {
+        self.nodes
+            .iter()
+            .filter(|(_, node)| node.children.is_empty())
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// The state created right before the current one, as in Vim's `g-`.
+    pub fn older(&self) -> Option<UndoStateId> {
+        let current = self.current();
+        if current == 0 {
+            return None;
+        }
+        self.nodes
+            .range(..current)
+            .next_back()
+            .map(|(id, _)| *id)
+            .or(Some(0))
+    }
+
+    /// The state created right after the current one, as in Vim's `g+`.
+    pub fn newer(&self) -> Option<UndoStateId> {
+        self.nodes
+            .range(self.current() + 1..)
+            .next()
+            .map(|(id, _)| *id)
+    }
+
+    /// The states from the original one (excluded) to the given one (included).
+    pub(crate) fn path_to(&self, id: UndoStateId) -> Vec<UndoStateId> {
+        let mut path = Vec::new();
+        let mut state = id;
+        while let Some(parent) = self.parent(state) {
+            path.push(state);
+            state = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    /// The states that would be redone from the given one, following the most
+    /// recently created child at each step.
+    pub(crate) fn redo_path_from(&self, id: UndoStateId) -> Vec<UndoStateId> {
+        let mut path = Vec::new();
+        let mut state = id;
+        while let Some(child) = self.children(state).last().copied() {
+            path.push(child);
+            state = child;
+        }
+        path
+    }
+
+    /// Marks the given state as the one the buffer is in, returning the transactions
+    /// whose edits must be undone or redone to get there from the current state.
+    pub(crate) fn move_to(&mut self, id: UndoStateId) -> Vec<Transaction> {
+        let path = self.path_to(id);
+        let target = path.iter().copied().collect::<HashSet<_>>();
+        let mut transactions = Vec::new();
+        for (state, node) in &mut self.nodes {
+            let applied = target.contains(state);
+            if node.applied != applied {
+                node.applied = applied;
+                transactions.push(node.transaction.clone());
+            }
+        }
+        self.path = path;
+        transactions
+    }
+
+    /// Adds a state for a transaction pushed onto the undo stack, following the
+    /// current one.
+    pub(crate) fn push(&mut self, transaction: &Transaction) {
+        if self.ids_by_transaction.contains_key(&transaction.id) {
+            return;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let parent = self.current();
+        self.nodes.insert(
+            id,
+            UndoTreeNode {
+                transaction: transaction.clone(),
+                parent,
+                children: Vec::new(),
+                created_at: SystemTime::now(),
+                applied: true,
+            },
+        );
+        self.children_mut(parent).push(id);
+        self.ids_by_transaction.insert(transaction.id, id);
+        self.path.push(id);
+    }
+
+    /// Records that the given transaction was moved from the undo stack to the
+    /// redo stack.
+    pub(crate) fn undo(&mut self, transaction_id: TransactionId) {
+        let Some(id) = self.ids_by_transaction.get(&transaction_id).copied() else {
+            return;
+        };
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.applied = false;
+        }
+        if let Some(ix) = self.path.iter().rposition(|state| *state == id) {
+            self.path.remove(ix);
+        }
+    }
+
+    /// Records that the given transaction was moved from the redo stack to the
+    /// undo stack.
+    pub(crate) fn redo(&mut self, transaction_id: TransactionId) {
+        let Some(id) = self.ids_by_transaction.get(&transaction_id).copied() else {
+            return;
+        };
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.applied = true;
+            self.path.push(id);
+        }
+    }
+
+    /// Records that edits were merged into the given transaction.
+    pub(crate) fn update(&mut self, transaction: &Transaction) {
+        if let Some(node) = self
+            .ids_by_transaction
+            .get(&transaction.id)
+            .and_then(|id| self.nodes.get_mut(id))
+        {
+            node.transaction = transaction.clone();
+        }
+    }
+
+    /// Removes the state of a transaction that was removed from the history. When
+    /// it was applied, its edits become part of the states following it.
+    pub(crate) fn remove(&mut self, transaction_id: TransactionId) {
+        let Some(id) = self.ids_by_transaction.remove(&transaction_id) else {
+            return;
+        };
+        let Some(node) = self.nodes.remove(&id) else {
+            return;
+        };
+        for child in &node.children {
+            if let Some(child) = self.nodes.get_mut(child) {
+                child.parent = node.parent;
+            }
+        }
+        let siblings = self.children_mut(node.parent);
+        siblings.retain(|state| *state != id);
+        siblings.extend(node.children);
+        siblings.sort_unstable();
+        if let Some(ix) = self.path.iter().rposition(|state| *state == id) {
+            self.path.remove(ix);
+        }
+    }
+
+    /// Discards the oldest states that can no longer be reached by undoing and
+    /// redoing once the tree grows past [`MAX_STATES`].
+    pub(crate) fn prune(&mut self, redo_stack: &[HistoryEntry]) {
+        if self.nodes.len() <= self.prune_at {
+            return;
+        }
+        let redoable = redo_stack
+            .iter()
+            .map(|entry| entry.transaction.id)
+            .collect::<HashSet<_>>();
+        let mut excess = self.nodes.len().saturating_sub(MAX_STATES * 3 / 4);
+        let mut pruned = Vec::new();
+        for node in self.nodes.values() {
+            if excess == 0 {
+                break;
+            }
+            if !node.applied && node.children.is_empty() && !redoable.contains(&node.transaction.id)
+            {
+                pruned.push(node.transaction.id);
+                excess -= 1;
+            }
+        }
+        for transaction_id in pruned {
+            self.remove(transaction_id);
+        }
+        // States that can still be reached are kept, so only try again once the
+        // tree has grown significantly, to keep pushing transactions cheap.
+        self.prune_at = MAX_STATES.max(self.nodes.len() + MAX_STATES / 4);
+    }
+
+    fn children_mut(&mut self, id: UndoStateId) -> &mut Vec<UndoStateId> {
+        if id == 0 {
+            &mut self.roots
+        } else {
+            &mut self
+                .nodes
+                .get_mut(&id)
+                .expect("parent state must exist")
+                .children
+        }
+    }
+}
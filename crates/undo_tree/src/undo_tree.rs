@@ -0,0 +1,615 @@
+mod undo_tree_panel_settings;
+
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use anyhow::Result;
+use collections::HashMap;
+use editor::Editor;
+use fs::Fs;
+use gpui::{
+    Action, App, AsyncWindowContext, Context, Entity, EventEmitter, FocusHandle, Focusable,
+    ListHorizontalSizingBehavior, Pixels, ScrollStrategy, Subscription, Task,
+    UniformListScrollHandle, WeakEntity, Window, uniform_list,
+};
+use language::{Buffer, BufferEvent};
+use settings::Settings;
+use text::{UndoStateId, UndoTree};
+use time::{OffsetDateTime, UtcOffset};
+use ui::{ListItem, ListItemSpacing, prelude::*};
+use workspace::{
+    Workspace,
+    dock::{DockPosition, Panel, PanelEvent},
+};
+use zed_actions::undo_tree::{Toggle, ToggleFocus};
+
+pub use undo_tree_panel_settings::UndoTreePanelSettings;
+
+const UNDO_TREE_PANEL_KEY: &str = "UndoTreePanel";
+
+/// The maximum number of lines shown in the preview of a state's changes.
+const MAX_PREVIEW_LINES: usize = 200;
+
+pub fn init(cx: &mut App) {
+    cx.observe_new(|workspace: &mut Workspace, _, _| {
+        workspace.register_action(|workspace, _: &ToggleFocus, window, cx| {
+            workspace.toggle_panel_focus::<UndoTreePanel>(window, cx);
+        });
+        workspace.register_action(|workspace, _: &Toggle, window, cx| {
+            if !workspace.toggle_panel_focus::<UndoTreePanel>(window, cx) {
+                workspace.close_panel::<UndoTreePanel>(window, cx);
+            }
+        });
+    })
+    .detach();
+}
+
+/// Shows every state of the active editor's buffer, including the ones on
+/// abandoned branches, and lets the buffer be moved to any of them.
+pub struct UndoTreePanel {
+    fs: Arc<dyn Fs>,
+    focus_handle: FocusHandle,
+    active: bool,
+    active_editor: Option<ActiveEditor>,
+    current: UndoStateId,
+    rows: Vec<UndoTreeRow>,
+    selected_index: Option<usize>,
+    preview: Vec<PreviewLine>,
+    scroll_handle: UniformListScrollHandle,
+    timezone: UtcOffset,
+    _subscriptions: Vec<Subscription>,
+}
+
+struct ActiveEditor {
+    editor: WeakEntity<Editor>,
+    buffer: Entity<Buffer>,
+    _subscription: Subscription,
+}
+
+struct UndoTreeRow {
+    id: UndoStateId,
+    /// How many branches away from the most recent path this state is.
+    indent: usize,
+    /// The number of changes between the original state and this one.
+    depth: usize,
+    created_at: Option<SystemTime>,
+}
+
+enum PreviewLine {
+    Header(u32),
+    Deleted(SharedString),
+    Inserted(SharedString),
+}
+
+impl UndoTreePanel {
+    pub fn new(
+        workspace: &mut Workspace,
+        window: &mut Window,
+        cx: &mut Context<Workspace>,
+    ) -> Entity<Self> {
+        let fs = workspace.app_state().fs.clone();
+        let active_editor = workspace_active_editor(workspace, cx);
+        let workspace_handle = cx.entity();
+        cx.new(|cx| {
+            let subscriptions = vec![cx.subscribe_in(
+                &workspace_handle,
+                window,
+                |this, workspace, event, _, cx| {
+                    if let workspace::Event::ActiveItemChanged = event {
+                        let editor = workspace_active_editor(workspace.read(cx), cx);
+                        this.set_active_editor(editor, cx);
+                    }
+                },
+            )];
+            let mut this = Self {
+                fs,
+                focus_handle: cx.focus_handle(),
+                active: false,
+                active_editor: None,
+                current: 0,
+                rows: Vec::new(),
+                selected_index: None,
+                preview: Vec::new(),
+                scroll_handle: UniformListScrollHandle::new(),
+                timezone: UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
+                _subscriptions: subscriptions,
+            };
+            this.set_active_editor(active_editor, cx);
+            this
+        })
+    }
+
+    pub fn load(
+        workspace: WeakEntity<Workspace>,
+        cx: AsyncWindowContext,
+    ) -> Task<Result<Entity<Self>>> {
+        cx.spawn(async move |cx| {
+            workspace.update_in(cx, |workspace, window, cx| Self::new(workspace, window, cx))
+        })
+    }
+
+    fn set_active_editor(&mut self, editor: Option<Entity<Editor>>, cx: &mut Context<Self>) {
+        let buffer = editor
+            .as_ref()
+            .and_then(|editor| editor.read(cx).buffer().read(cx).as_singleton());
+        let (Some(editor), Some(buffer)) = (editor, buffer) else {
+            self.active_editor = None;
+            self.refresh(cx);
+            return;
+        };
+        if self.active_editor.as_ref().is_some_and(|active_editor| {
+            active_editor.buffer == buffer && active_editor.editor.entity_id() == editor.entity_id()
+        }) {
+            return;
+        }
+
+        let subscription = cx.subscribe(&buffer, |this, _, event: &BufferEvent, cx| {
+            if let BufferEvent::Edited { .. } = event {
+                this.refresh(cx);
+            }
+        });
+        self.active_editor = Some(ActiveEditor {
+            editor: editor.downgrade(),
+            buffer,
+            _subscription: subscription,
+        });
+        self.refresh(cx);
+    }
+
+    /// Rebuilds the rows from the active buffer's undo tree, selecting the state
+    /// the buffer is in. The tree is only read while the panel is open.
+    fn refresh(&mut self, cx: &mut Context<Self>) {
+        if !self.active {
+            return;
+        }
+        match &self.active_editor {
+            Some(active_editor) => {
+                let tree = active_editor.buffer.read(cx).undo_tree();
+                self.current = tree.current();
+                self.rows = tree_rows(tree);
+            }
+            None => {
+                self.current = 0;
+                self.rows.clear();
+            }
+        }
+        let current_index = self.rows.iter().position(|row| row.id == self.current);
+        self.select(current_index, cx);
+    }
+
+    fn select(&mut self, ix: Option<usize>, cx: &mut Context<Self>) {
+        self.selected_index = ix;
+        self.preview.clear();
+        if let Some(ix) = ix {
+            self.scroll_handle
+                .scroll_to_item(ix, ScrollStrategy::Center);
+        }
+        let edits = ix
+            .and_then(|ix| self.rows.get(ix))
+            .zip(self.active_editor.as_ref())
+            .and_then(|(row, active_editor)| active_editor.buffer.read(cx).undo_state_edits(row.id))
+            .unwrap_or_default();
+        for (row, edit) in edits {
+            if self.preview.len() >= MAX_PREVIEW_LINES {
+                break;
+            }
+            self.preview.push(PreviewLine::Header(row + 1));
+            self.preview.extend(
+                edit.old_text
+                    .lines()
+                    .map(|line| PreviewLine::Deleted(SharedString::from(line.to_string()))),
+            );
+            self.preview.extend(
+                edit.new_text
+                    .lines()
+                    .map(|line| PreviewLine::Inserted(SharedString::from(line.to_string()))),
+            );
+        }
+        self.preview.truncate(MAX_PREVIEW_LINES);
+        cx.notify();
+    }
+
+    fn select_next(&mut self, _: &menu::SelectNext, _: &mut Window, cx: &mut Context<Self>) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let ix = self
+            .selected_index
+            .map_or(0, |ix| (ix + 1).min(self.rows.len() - 1));
+        self.select(Some(ix), cx);
+    }
+
+    fn select_previous(
+        &mut self,
+        _: &menu::SelectPrevious,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let ix = self.selected_index.map_or(0, |ix| ix.saturating_sub(1));
+        self.select(Some(ix), cx);
+    }
+
+    fn select_first(&mut self, _: &menu::SelectFirst, _: &mut Window, cx: &mut Context<Self>) {
+        if !self.rows.is_empty() {
+            self.select(Some(0), cx);
+        }
+    }
+
+    fn select_last(&mut self, _: &menu::SelectLast, _: &mut Window, cx: &mut Context<Self>) {
+        if !self.rows.is_empty() {
+            self.select(Some(self.rows.len() - 1), cx);
+        }
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(ix) = self.selected_index {
+            self.go_to_row(ix, window, cx);
+        }
+    }
+
+    /// Moves the active buffer to the state shown at the given row.
+    fn go_to_row(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(row) = self.rows.get(ix) else {
+            return;
+        };
+        let Some(editor) = self
+            .active_editor
+            .as_ref()
+            .and_then(|active_editor| active_editor.editor.upgrade())
+        else {
+            return;
+        };
+        let id = row.id;
+        editor.update(cx, |editor, cx| editor.go_to_undo_state(id, window, cx));
+    }
+
+    fn render_row(&self, ix: usize, cx: &mut Context<Self>) -> AnyElement {
+        let row = &self.rows[ix];
+        let is_current = row.id == self.current;
+        let label = if row.id == 0 {
+            "Original".to_string()
+        } else {
+            format!("#{}", row.id)
+        };
+        let time = row
+            .created_at
+            .map(|created_at| {
+                time_format::format_localized_timestamp(
+                    OffsetDateTime::from(created_at),
+                    OffsetDateTime::now_utc(),
+                    self.timezone,
+                    time_format::TimestampFormat::Relative,
+                )
+            })
+            .unwrap_or_default();
+        let changes = match row.depth {
+            1 => "1 change".to_string(),
+            depth => format!("{depth} changes"),
+        };
+
+        ListItem::new(ix)
+            .inset(true)
+            .spacing(ListItemSpacing::Sparse)
+            .toggle_state(self.selected_index == Some(ix))
+            .on_click(cx.listener(move |this, _, window, cx| {
+                this.select(Some(ix), cx);
+                this.go_to_row(ix, window, cx);
+            }))
+            .child(
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .pl(rems(row.indent as f32))
+                    .child(
+                        Label::new(if is_current { "●" } else { "○" }).color(if is_current {
+                            Color::Accent
+                        } else {
+                            Color::Muted
+                        }),
+                    )
+                    .child(Label::new(label))
+                    .child(
+                        Label::new(changes)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .child(div().flex_1())
+                    .child(Label::new(time).size(LabelSize::Small).color(Color::Muted)),
+            )
+            .into_any_element()
+    }
+
+    fn render_preview(&self, cx: &App) -> impl IntoElement {
+        let content = if self.preview.is_empty() {
+            v_flex().child(
+                Label::new("No changes")
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            )
+        } else {
+            v_flex().children(self.preview.iter().map(|line| {
+                match line {
+                    PreviewLine::Header(row) => Label::new(format!("@@ line {row}"))
+                        .buffer_font(cx)
+                        .size(LabelSize::Small)
+                        .color(Color::Muted)
+                        .into_any_element(),
+                    PreviewLine::Deleted(text) => Label::new(format!("-{text}"))
+                        .buffer_font(cx)
+                        .size(LabelSize::Small)
+                        .color(Color::Deleted)
+                        .into_any_element(),
+                    PreviewLine::Inserted(text) => Label::new(format!("+{text}"))
+                        .buffer_font(cx)
+                        .size(LabelSize::Small)
+                        .color(Color::Created)
+                        .into_any_element(),
+                }
+            }))
+        };
+        v_flex()
+            .max_h(rems(20.))
+            .p_2()
+            .overflow_hidden()
+            .border_t_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(content)
+    }
+}
+
+fn workspace_active_editor(workspace: &Workspace, cx: &App) -> Option<Entity<Editor>> {
+    workspace
+        .active_item(cx)?
+        .act_as::<Editor>(cx)
+        .filter(|editor| editor.read(cx).mode().is_full())
+}
+
+/// Lists the states of the tree newest first. The newest child of a state
+/// continues its branch, while older children are indented one level further.
+fn tree_rows(tree: &UndoTree) -> Vec<UndoTreeRow> {
+    let mut indents = HashMap::default();
+    let mut depths = HashMap::default();
+    indents.insert(0, 0);
+    depths.insert(0, 0);
+    let mut rows = vec![UndoTreeRow {
+        id: 0,
+        indent: 0,
+        depth: 0,
+        created_at: None,
+    }];
+    for id in tree.states() {
+        let parent = tree.parent(id).unwrap_or_default();
+        let parent_indent = indents.get(&parent).copied().unwrap_or_default();
+        let indent = if tree.children(parent).last() == Some(&id) {
+            parent_indent
+        } else {
+            parent_indent + 1
+        };
+        let depth = depths.get(&parent).copied().unwrap_or_default() + 1;
+        indents.insert(id, indent);
+        depths.insert(id, depth);
+        rows.push(UndoTreeRow {
+            id,
+            indent,
+            depth,
+            created_at: tree.node(id).map(|node| node.created_at()),
+        });
+    }
+    rows.reverse();
+    rows
+}
+
+impl Focusable for UndoTreePanel {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl EventEmitter<PanelEvent> for UndoTreePanel {}
+
+impl Render for UndoTreePanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .id("undo-tree-panel")
+            .key_context("UndoTreePanel")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .overflow_hidden()
+            .on_action(cx.listener(Self::select_next))
+            .on_action(cx.listener(Self::select_previous))
+            .on_action(cx.listener(Self::select_first))
+            .on_action(cx.listener(Self::select_last))
+            .on_action(cx.listener(Self::confirm))
+            .map(|this| {
+                if self.active_editor.is_none() {
+                    this.child(
+                        v_flex().size_full().justify_center().items_center().child(
+                            Label::new("Open a file to see its undo tree").color(Color::Muted),
+                        ),
+                    )
+                } else {
+                    this.child(
+                        uniform_list(
+                            "undo-tree-rows",
+                            self.rows.len(),
+                            cx.processor(|this, range: Range<usize>, _, cx| {
+                                range.map(|ix| this.render_row(ix, cx)).collect()
+                            }),
+                        )
+                        .with_horizontal_sizing_behavior(
+                            ListHorizontalSizingBehavior::Unconstrained,
+                        )
+                        .track_scroll(&self.scroll_handle)
+                        .flex_1(),
+                    )
+                    .child(self.render_preview(cx))
+                }
+            })
+    }
+}
+
+impl Panel for UndoTreePanel {
+    fn persistent_name() -> &'static str {
+        "Undo Tree Panel"
+    }
+
+    fn panel_key() -> &'static str {
+        UNDO_TREE_PANEL_KEY
+    }
+
+    fn position(&self, _: &Window, cx: &App) -> DockPosition {
+        UndoTreePanelSettings::get_global(cx).dock
+    }
+
+    fn position_is_valid(&self, position: DockPosition) -> bool {
+        matches!(position, DockPosition::Left | DockPosition::Right)
+    }
+
+    fn set_position(&mut self, position: DockPosition, _: &mut Window, cx: &mut Context<Self>) {
+        settings::update_settings_file(self.fs.clone(), cx, move |settings, _| {
+            settings.undo_tree_panel.get_or_insert_default().dock = Some(position.into())
+        });
+    }
+
+    fn default_size(&self, _: &Window, cx: &App) -> Pixels {
+        UndoTreePanelSettings::get_global(cx).default_width
+    }
+
+    fn set_active(&mut self, active: bool, _: &mut Window, cx: &mut Context<Self>) {
+        self.active = active;
+        self.refresh(cx);
+    }
+
+    fn icon(&self, _: &Window, cx: &App) -> Option<IconName> {
+        UndoTreePanelSettings::get_global(cx)
+            .button
+            .then_some(IconName::HistoryRerun)
+    }
+
+    fn icon_tooltip(&self, _window: &Window, _: &App) -> Option<&'static str> {
+        Some("Undo Tree Panel")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+
+    fn activation_priority(&self) -> u32 {
+        8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{TestAppContext, VisualTestContext};
+    use project::{FakeFs, Project};
+    use serde_json::json;
+    use std::time::Duration;
+    use util::{path, rel_path::rel_path};
+    use workspace::{AppState, MultiWorkspace};
+
+    #[gpui::test]
+    async fn test_undo_tree_panel(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(path!("/dir"), json!({ "a.txt": "abc" }))
+            .await;
+        let project = Project::test(fs, [path!("/dir").as_ref()], cx).await;
+        let (multi_workspace, cx) =
+            cx.add_window_view(|window, cx| MultiWorkspace::test_new(project.clone(), window, cx));
+        let workspace = cx.read(|cx| multi_workspace.read(cx).workspace().clone());
+        let worktree_id = workspace.update(cx, |workspace, cx| {
+            workspace.project().update(cx, |project, cx| {
+                project.worktrees(cx).next().unwrap().read(cx).id()
+            })
+        });
+        let editor = workspace
+            .update_in(cx, |workspace, window, cx| {
+                workspace.open_path((worktree_id, rel_path("a.txt")), None, true, window, cx)
+            })
+            .await
+            .unwrap()
+            .downcast::<Editor>()
+            .unwrap();
+        let buffer = editor.read_with(cx, |editor, cx| {
+            editor.buffer().read(cx).as_singleton().unwrap()
+        });
+        let panel = workspace.update_in(cx, |workspace, window, cx| {
+            let panel = UndoTreePanel::new(workspace, window, cx);
+            workspace.add_panel(panel.clone(), window, cx);
+            workspace.toggle_panel_focus::<UndoTreePanel>(window, cx);
+            panel
+        });
+        cx.run_until_parked();
+        assert_eq!(rows(&panel, cx), [(0, 0, 0)]);
+
+        // Editing after undoing starts a new branch, shown indented under the
+        // state it was abandoned from.
+        buffer.update(cx, |buffer, cx| {
+            buffer.set_group_interval(Duration::ZERO);
+            buffer.edit([(3..3, "d")], None, cx);
+            buffer.edit([(4..4, "e")], None, cx);
+            buffer.undo(cx);
+            buffer.edit([(4..4, "f")], None, cx);
+        });
+        cx.run_until_parked();
+        assert_eq!(
+            rows(&panel, cx),
+            [(3, 0, 2), (2, 1, 2), (1, 0, 1), (0, 0, 0)]
+        );
+        panel.read_with(cx, |panel, _| {
+            assert_eq!(panel.current, 3);
+            assert_eq!(panel.selected_index, Some(0));
+        });
+
+        // Confirming a row moves the buffer to its state, even on another branch.
+        panel.update_in(cx, |panel, window, cx| {
+            panel.select_next(&menu::SelectNext, window, cx);
+            panel.confirm(&menu::Confirm, window, cx);
+        });
+        cx.run_until_parked();
+        assert_eq!(buffer.read_with(cx, |buffer, _| buffer.text()), "abcde");
+        panel.read_with(cx, |panel, _| {
+            assert_eq!(panel.current, 2);
+            assert_eq!(panel.selected_index, Some(1));
+        });
+
+        panel.update_in(cx, |panel, window, cx| {
+            panel.select_last(&menu::SelectLast, window, cx);
+            panel.confirm(&menu::Confirm, window, cx);
+        });
+        cx.run_until_parked();
+        assert_eq!(buffer.read_with(cx, |buffer, _| buffer.text()), "abc");
+        // Moving between states doesn't create new ones.
+        assert_eq!(
+            rows(&panel, cx),
+            [(3, 0, 2), (2, 1, 2), (1, 0, 1), (0, 0, 0)]
+        );
+    }
+
+    fn rows(
+        panel: &Entity<UndoTreePanel>,
+        cx: &mut VisualTestContext,
+    ) -> Vec<(UndoStateId, usize, usize)> {
+        panel.read_with(cx, |panel, _| {
+            panel
+                .rows
+                .iter()
+                .map(|row| (row.id, row.indent, row.depth))
+                .collect()
+        })
+    }
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            AppState::test(cx);
+            editor::init(cx);
+            crate::init(cx);
+        });
+    }
+}
@@ -96,6 +96,10 @@ actions!(
         Redo,
         /// Undoes all changes to the most recently changed line.
         UndoLastLine,
+        /// Goes to the text state created right before the current one, as in Vim's `g-`.
+        GoToOlderTextState,
+        /// Goes to the text state created right after the current one, as in Vim's `g+`.
+        GoToNewerTextState,
         /// Go to tab page (with count support).
         GoToTab,
         /// Go to previous tab page (with count support).
@@ -254,6 +258,24 @@ pub(crate) fn register(editor: &mut Editor, cx: &mut Context<Vim>) {
             }
         });
     });
+    Vim::action(editor, cx, |vim, _: &GoToOlderTextState, window, cx| {
+        let times = Vim::take_count(cx);
+        Vim::take_forced_motion(cx);
+        vim.update_editor(cx, |_, editor, cx| {
+            for _ in 0..times.unwrap_or(1) {
+                editor.go_to_older_undo_state(&editor::actions::GoToOlderUndoState, window, cx);
+            }
+        });
+    });
+    Vim::action(editor, cx, |vim, _: &GoToNewerTextState, window, cx| {
+        let times = Vim::take_count(cx);
+        Vim::take_forced_motion(cx);
+        vim.update_editor(cx, |_, editor, cx| {
+            for _ in 0..times.unwrap_or(1) {
+                editor.go_to_newer_undo_state(&editor::actions::GoToNewerUndoState, window, cx);
+            }
+        });
+    });
     Vim::action(editor, cx, |vim, _: &UndoLastLine, window, cx| {
         Vim::take_forced_motion(cx);
         vim.update_editor(cx, |vim, editor, cx| {
@@ -614,6 +636,13 @@ impl Vim {
 
     fn insert_after(&mut self, _: &InsertAfter, window: &mut Window, cx: &mut Context<Self>) {
         self.start_recording(cx);
+        if self.mode == Mode::VisualBlock {
+            // Appending to a block pads the lines ending before it, so that the
+            // text is appended in the same column on every line.
+            self.update_editor(cx, |_, editor, cx| {
+                editor.pad_block_selection(true, window, cx);
+            });
+        }
         self.switch_mode(Mode::Insert, false, window, cx);
         self.update_editor(cx, |_, editor, cx| {
             editor.change_selections(Default::default(), window, cx, |s| {
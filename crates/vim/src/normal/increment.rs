@@ -1,4 +1,4 @@
-use editor::{Editor, MultiBufferSnapshot, ToOffset, ToPoint};
+use editor::{Editor, MultiBufferSnapshot, ToOffset, ToPoint, format_number_like};
 use gpui::{Action, Context, Window};
 use language::{Bias, Point};
 use schemars::JsonSchema;
@@ -165,24 +165,7 @@ fn increment_hex_string(num: &str, delta: i64) -> String {
     } else {
         u64::MAX
     };
-    if should_use_lowercase(num) {
-        format!("{:0width$x}", result, width = num.len())
-    } else {
-        format!("{:0width$X}", result, width = num.len())
-    }
-}
-
-fn should_use_lowercase(num: &str) -> bool {
-    let mut use_uppercase = false;
-    for ch in num.chars() {
-        if ch.is_ascii_lowercase() {
-            return true;
-        }
-        if ch.is_ascii_uppercase() {
-            use_uppercase = true;
-        }
-    }
-    !use_uppercase
+    format_number_like(result, 16, num)
 }
 
 fn increment_binary_string(num: &str, delta: i64) -> String {
@@ -191,7 +174,7 @@ fn increment_binary_string(num: &str, delta: i64) -> String {
     } else {
         u64::MAX
     };
-    format!("{:0width$b}", result, width = num.len())
+    format_number_like(result, 2, num)
 }
 
 fn find_target(
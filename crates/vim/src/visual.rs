@@ -421,7 +421,33 @@ impl Vim {
                     .row();
             }
 
+            // Record the rectangle, using the columns of the row the block was
+            // started from, so that it extends past the end of shorter lines.
+            // Blocks reaching the end of every line (`$`) have no such columns.
+            let block_columns = match goal {
+                SelectionGoal::HorizontalRange { start, end }
+                    if start.is_finite() && end.is_finite() =>
+                {
+                    selections.first().map(|selection| {
+                        let start = selection.start.to_display_point(map).column();
+                        let end = selection.end.to_display_point(map).column();
+                        if selection.reversed {
+                            (end, start)
+                        } else {
+                            (start, end)
+                        }
+                    })
+                }
+                _ => None,
+            };
+
             s.select(selections);
+            if let Some((tail_column, head_column)) = block_columns {
+                s.set_block_selection(
+                    DisplayPoint::new(tail.row(), tail_column),
+                    DisplayPoint::new(head.row(), head_column),
+                );
+            }
         })
     }
 
@@ -1067,6 +1093,27 @@ mod test {
         );
     }
 
+    #[gpui::test]
+    async fn test_visual_block_append_pads_short_lines(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {
+            "The ˇquick brown
+            fox
+            the lazy dog
+            "
+        })
+        .await;
+        cx.simulate_shared_keystrokes("ctrl-v l j j shift-a - escape")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {
+            "The quˇ-ick brown
+            fox   -
+            the la-zy dog
+            "
+        });
+    }
+
     #[gpui::test]
     async fn test_enter_visual_line_mode(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
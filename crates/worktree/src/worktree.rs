@@ -78,6 +78,10 @@ use crate::ignore::IgnoreKind;
 
 pub const FS_WATCH_LATENCY: Duration = Duration::from_millis(100);
 
+/// The most bytes of a file a [`FileRangeReader`] reads at once, which for remote
+/// worktrees are sent in a single message.
+pub const MAX_FILE_RANGE_LEN: u64 = 1024 * 1024;
+
 /// A set of local or remote files that are being opened as part of a project.
 /// Responsible for tracking related FS (for local)/collab (for remote) events and corresponding updates.
 /// Stores git repositories data and the diagnostics for the file(s).
@@ -125,6 +129,52 @@ impl fmt::Debug for LoadedBinaryFile {
     }
 }
 
+/// Reads parts of a file in a worktree without loading all of it, from the file
+/// system for local worktrees, or from the host for remote ones.
+#[derive(Clone)]
+pub enum FileRangeReader {
+    Local {
+        fs: Arc<dyn Fs>,
+        abs_path: PathBuf,
+    },
+    Remote {
+        client: AnyProtoClient,
+        project_id: u64,
+        worktree_id: WorktreeId,
+        path: Arc<RelPath>,
+    },
+}
+
+impl FileRangeReader {
+    /// Reads the bytes within the range, up to [`MAX_FILE_RANGE_LEN`] of them,
+    /// or fewer if the file ends before it.
+    pub async fn read(&self, range: Range<u64>) -> Result<Vec<u8>> {
+        let end = range
+            .end
+            .min(range.start.saturating_add(MAX_FILE_RANGE_LEN));
+        match self {
+            Self::Local { fs, abs_path } => fs.load_range(abs_path, range.start..end).await,
+            Self::Remote {
+                client,
+                project_id,
+                worktree_id,
+                path,
+            } => {
+                let response = client
+                    .request(proto::ReadFileRange {
+                        project_id: *project_id,
+                        worktree_id: worktree_id.to_proto(),
+                        path: path.to_proto(),
+                        start: range.start,
+                        end,
+                    })
+                    .await?;
+                Ok(response.data)
+            }
+        }
+    }
+}
+
 pub struct LocalWorktree {
     snapshot: LocalSnapshot,
     scan_requests_tx: channel::Sender<ScanRequest>,
@@ -774,6 +824,21 @@ impl Worktree {
         }
     }
 
+    pub fn file_range_reader(&self, path: &RelPath) -> FileRangeReader {
+        match self {
+            Worktree::Local(this) => FileRangeReader::Local {
+                fs: this.fs.clone(),
+                abs_path: this.absolutize(path),
+            },
+            Worktree::Remote(this) => FileRangeReader::Remote {
+                client: this.client.clone(),
+                project_id: this.project_id,
+                worktree_id: this.id(),
+                path: path.into(),
+            },
+        }
+    }
+
     pub fn write_file(
         &self,
         path: Arc<RelPath>,
@@ -1014,6 +1079,18 @@ impl Worktree {
         })
     }
 
+    pub async fn handle_read_file_range(
+        this: Entity<Self>,
+        request: proto::ReadFileRange,
+        cx: AsyncApp,
+    ) -> Result<proto::ReadFileRangeResponse> {
+        let path = RelPath::from_proto(&request.path)?;
+        let reader = this.read_with(&cx, |this, _| this.file_range_reader(&path));
+        Ok(proto::ReadFileRangeResponse {
+            data: reader.read(request.start..request.end).await?,
+        })
+    }
+
     pub async fn handle_expand_all_for_entry(
         this: Entity<Self>,
         request: proto::ExpandAllForProjectEntry,
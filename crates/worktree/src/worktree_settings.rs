@@ -21,6 +21,9 @@ pub struct WorktreeSettings {
     pub private_files: PathMatcher,
     pub hidden_files: PathMatcher,
     pub read_only_files: PathMatcher,
+    /// The size in bytes above which files are opened in large file mode rather
+    /// than loaded into a buffer.
+    pub large_file_threshold: u64,
 }
 
 impl WorktreeSettings {
@@ -64,6 +67,10 @@ impl Settings for WorktreeSettings {
         let private_files = worktree.private_files.unwrap().0;
         let hidden_files = worktree.hidden_files.unwrap();
         let read_only_files = worktree.read_only_files.unwrap_or_default();
+        let large_file_threshold = worktree
+            .large_file_threshold_mb
+            .unwrap()
+            .saturating_mul(1024 * 1024);
         let parsed_file_scan_inclusions: Vec<String> = file_scan_inclusions
             .iter()
             .flat_map(|glob| {
@@ -97,6 +104,7 @@ impl Settings for WorktreeSettings {
             read_only_files: path_matchers(read_only_files, "read_only_files")
                 .log_err()
                 .unwrap_or_default(),
+            large_file_threshold,
         }
     }
 }
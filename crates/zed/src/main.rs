@@ -701,6 +701,7 @@ fn main() {
         load_embedded_fonts(cx);
 
         editor::init(cx);
+        large_file_viewer::init(cx);
         image_viewer::init(cx);
         repl::notebook::init(cx);
         diagnostics::init(cx);
@@ -713,6 +714,8 @@ fn main() {
         file_finder::init(cx);
         tab_switcher::init(cx);
         outline::init(cx);
+        undo_tree::init(cx);
+        clipboard_history::init(cx);
         project_symbols::init(cx);
         project_panel::init(cx);
         outline_panel::init(cx);
@@ -250,7 +250,9 @@ pub mod workspace {
 }
 
 pub mod git {
-    use gpui::actions;
+    use gpui::{Action, actions};
+    use schemars::JsonSchema;
+    use serde::Deserialize;
 
     actions!(
         git,
@@ -276,6 +278,29 @@ pub mod git {
             CreatePullRequest
         ]
     );
+
+    /// Opens the reflog of a git ref to find and recover lost commits.
+    #[derive(PartialEq, Clone, Default, Debug, Deserialize, JsonSchema, Action)]
+    #[action(namespace = git)]
+    #[serde(deny_unknown_fields)]
+    pub struct ViewReflog {
+        /// The ref whose reflog is shown, e.g. a branch name. Defaults to `HEAD`.
+        #[serde(default)]
+        pub ref_name: Option<String>,
+    }
+
+    /// Reviews the changes a branch introduces relative to the branch it will be merged into.
+    #[derive(PartialEq, Clone, Default, Debug, Deserialize, JsonSchema, Action)]
+    #[action(namespace = git)]
+    #[serde(deny_unknown_fields)]
+    pub struct ReviewBranch {
+        /// The branch being merged into. Defaults to the repository's default branch.
+        #[serde(default)]
+        pub base: Option<String>,
+        /// The branch under review. Defaults to `HEAD`.
+        #[serde(default)]
+        pub head: Option<String>,
+    }
 }
 
 pub mod toast {
@@ -668,6 +693,36 @@ actions!(
     ]
 );
 
+pub mod clipboard_history {
+    use gpui::actions;
+
+    actions!(
+        clipboard_history,
+        [
+            /// Toggles the clipboard history, listing the texts previously copied or
+            /// cut in editors to paste them again.
+            Toggle,
+            /// Clears the clipboard history.
+            ClearHistory
+        ]
+    );
+}
+
+pub mod undo_tree {
+    use gpui::actions;
+
+    actions!(
+        undo_tree,
+        [
+            /// Toggles the undo tree panel, listing every state of the active
+            /// editor's buffer including the ones on abandoned branches.
+            Toggle,
+            /// Toggles focus on the undo tree panel.
+            ToggleFocus
+        ]
+    );
+}
+
 pub mod debug_panel {
     use gpui::actions;
     actions!(